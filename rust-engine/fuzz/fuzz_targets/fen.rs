@@ -0,0 +1,32 @@
+// FEN parsing: `from_fen` and `from_fen_strict` on arbitrary text, then every
+// legal move made and unmade on whatever the lenient parser accepts.
+//
+// Usage: cargo +nightly fuzz run fen
+
+#![no_main]
+
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|fen: &str| {
+    if let Ok(pos) = Position::from_fen_strict(fen) {
+        assert_eq!(pos.validate(), Ok(()), "{}", fen);
+    }
+    let Ok(mut pos) = Position::from_fen(fen) else { return };
+
+    // The FEN written back reads as the same position
+    let written = pos.to_fen();
+    let reread = Position::from_fen(&written).expect("to_fen output parses");
+    assert_eq!(reread.to_fen(), written);
+    assert_eq!(reread.hash(), pos.hash());
+
+    let _ = pos.game_status();
+    for m in generate_legal_moves(&pos).iter() {
+        let _ = m.to_san(&pos);
+        let undo = pos.make_move(*m).expect("legal move");
+        let _ = pos.game_status();
+        pos.unmake_move(*m, &undo);
+    }
+    assert_eq!(pos.to_fen(), written);
+});
@@ -0,0 +1,27 @@
+// PGN parsing: arbitrary text split into games and parsed, each game written
+// back out, and loaded into a GameState (FEN and Variant tags, SAN moves,
+// comments, NAGs and variations).
+//
+// Usage: cargo +nightly fuzz run pgn
+
+#![no_main]
+
+use chess_engine::pgn::parse_pgn_games;
+use chess_engine::GameState;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|pgn: &str| {
+    for game in parse_pgn_games(pgn).into_iter().flatten() {
+        let _ = game.to_pgn();
+        let _ = (game.start_fen(), game.variant());
+    }
+
+    let mut gs = GameState::new();
+    if gs.load_pgn(pgn).is_ok() {
+        let _ = gs.pgn();
+        let _ = gs.status();
+    } else {
+        // A game that fails to load leaves the board alone
+        assert_eq!(gs.fen(), GameState::new().fen());
+    }
+});
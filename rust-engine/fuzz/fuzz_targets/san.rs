@@ -0,0 +1,33 @@
+// SAN parsing: arbitrary text read as a SAN move in positions with castling,
+// en passant, promotions and disambiguation. A parsed move must be legal and
+// written back as SAN that parses to the same move.
+//
+// Usage: cargo +nightly fuzz run san
+
+#![no_main]
+
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use chess_engine::san::parse_san;
+use chess_engine::GameState;
+use libfuzzer_sys::fuzz_target;
+
+const FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+    "7k/1P6/8/8/8/8/R6R/4K1N1 w - - 0 1",
+];
+
+fuzz_target!(|san: &str| {
+    for fen in FENS {
+        let pos = Position::from_fen(fen).unwrap();
+        if let Ok(m) = parse_san(&pos, san) {
+            assert!(generate_legal_moves(&pos).iter().any(|&l| l == m), "{:?} parsed to an illegal move", san);
+            assert_eq!(parse_san(&pos, &m.to_san(&pos)), Ok(m));
+        }
+
+        let mut gs = GameState::from_fen(fen).unwrap();
+        let _ = gs.try_move_san(san);
+    }
+});
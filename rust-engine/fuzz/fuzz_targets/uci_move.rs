@@ -0,0 +1,35 @@
+// UCI move parsing: arbitrary text played as a UCI move, through the free
+// `make_move_uci` and `GameState::try_move_uci`, in positions with castling,
+// en passant and promotions available.
+//
+// Usage: cargo +nightly fuzz run uci_move
+
+#![no_main]
+
+use chess_engine::position::Position;
+use chess_engine::GameState;
+use libfuzzer_sys::fuzz_target;
+
+const FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+];
+
+fuzz_target!(|uci: &str| {
+    for fen in FENS {
+        let mut pos = Position::from_fen(fen).unwrap();
+        if !chess_engine::make_move_uci(&mut pos, uci) {
+            assert_eq!(pos.to_fen(), fen, "rejected move {:?} changed the position", uci);
+        }
+
+        let mut gs = GameState::from_fen(fen).unwrap();
+        if gs.try_move_uci(uci).is_ok() {
+            // Both entry points agree on the move
+            assert_eq!(gs.fen(), pos.to_fen(), "{:?}", uci);
+            assert!(!gs.undo().is_empty());
+        }
+        assert_eq!(gs.fen(), fen);
+    }
+});
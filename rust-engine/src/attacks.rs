@@ -129,6 +129,152 @@ pub static BLACK_PAWN_ATTACKS: [Bitboard; 64] = {
     attacks
 };
 
+// =============================================================================
+// PAWN STRUCTURE MASKS
+// =============================================================================
+
+/// Files adjacent to each square's file (own file excluded) - indexed by square
+pub static ADJACENT_FILE_MASKS: [Bitboard; 64] = {
+    let mut masks = [Bitboard::EMPTY; 64];
+    let mut sq = 0u8;
+
+    while sq < 64 {
+        let file = sq % 8;
+        let mut mask = 0u64;
+        if file > 0 { mask |= 0x0101_0101_0101_0101u64 << (file - 1); }
+        if file < 7 { mask |= 0x0101_0101_0101_0101u64 << (file + 1); }
+        masks[sq as usize] = Bitboard(mask);
+        sq += 1;
+    }
+
+    masks
+};
+
+/// Passed pawn masks - indexed by [color][square] (0 = white, 1 = black).
+/// All squares ahead of the pawn on its own and adjacent files: a pawn is
+/// passed when no enemy pawn stands on its mask.
+pub static PASSED_PAWN_MASKS: [[Bitboard; 64]; 2] = {
+    let mut masks = [[Bitboard::EMPTY; 64]; 2];
+    let mut sq = 0u8;
+
+    while sq < 64 {
+        let file = sq % 8;
+        let rank = sq / 8;
+        let mut files = 0x0101_0101_0101_0101u64 << file;
+        if file > 0 { files |= 0x0101_0101_0101_0101u64 << (file - 1); }
+        if file < 7 { files |= 0x0101_0101_0101_0101u64 << (file + 1); }
+
+        // Ranks strictly in front: above for white, below for black
+        let ahead_white = if rank < 7 { !0u64 << ((rank + 1) * 8) } else { 0 };
+        let ahead_black = if rank > 0 { !0u64 >> ((8 - rank) * 8) } else { 0 };
+
+        masks[0][sq as usize] = Bitboard(files & ahead_white);
+        masks[1][sq as usize] = Bitboard(files & ahead_black);
+        sq += 1;
+    }
+
+    masks
+};
+
+/// Pawn attack spans - indexed by [color][square] (0 = white, 1 = black).
+/// Every square a pawn on this square could ever attack while advancing:
+/// the adjacent files, strictly ahead. Conversely, an enemy pawn can only ever
+/// attack `sq` if it stands on our attack span from `sq`.
+pub static PAWN_ATTACK_SPANS: [[Bitboard; 64]; 2] = {
+    let mut masks = [[Bitboard::EMPTY; 64]; 2];
+    let mut sq = 0u8;
+
+    while sq < 64 {
+        let file = sq % 8;
+        let rank = sq / 8;
+        let mut files = 0u64;
+        if file > 0 { files |= 0x0101_0101_0101_0101u64 << (file - 1); }
+        if file < 7 { files |= 0x0101_0101_0101_0101u64 << (file + 1); }
+
+        let ahead_white = if rank < 7 { !0u64 << ((rank + 1) * 8) } else { 0 };
+        let ahead_black = if rank > 0 { !0u64 >> ((8 - rank) * 8) } else { 0 };
+
+        masks[0][sq as usize] = Bitboard(files & ahead_white);
+        masks[1][sq as usize] = Bitboard(files & ahead_black);
+        sq += 1;
+    }
+
+    masks
+};
+
+// =============================================================================
+// BETWEEN / LINE TABLES - Indexed by [square][square]
+// =============================================================================
+
+/// The eight ray directions as (file, rank) steps; each is followed by its opposite
+const DIRECTIONS: [(i8, i8); 8] = [(0, 1), (0, -1), (1, 0), (-1, 0), (1, 1), (-1, -1), (1, -1), (-1, 1)];
+
+/// Squares from `sq` (exclusive) to the board edge in one direction
+const fn ray(sq: u8, dir: usize) -> u64 {
+    let (df, dr) = DIRECTIONS[dir];
+    let mut file = (sq % 8) as i8 + df;
+    let mut rank = (sq / 8) as i8 + dr;
+    let mut bits = 0u64;
+    while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+        bits |= 1u64 << (rank * 8 + file);
+        file += df;
+        rank += dr;
+    }
+    bits
+}
+
+/// Squares strictly between two squares on a shared rank, file or diagonal;
+/// empty when they aren't aligned
+pub static BETWEEN: [[Bitboard; 64]; 64] = {
+    let mut table = [[Bitboard::EMPTY; 64]; 64];
+    let mut a = 0u8;
+
+    while a < 64 {
+        let mut dir = 0;
+        while dir < 8 {
+            let (df, dr) = DIRECTIONS[dir];
+            let mut file = (a % 8) as i8 + df;
+            let mut rank = (a / 8) as i8 + dr;
+            let mut passed = 0u64;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                let b = (rank * 8 + file) as usize;
+                table[a as usize][b] = Bitboard(passed);
+                passed |= 1u64 << b;
+                file += df;
+                rank += dr;
+            }
+            dir += 1;
+        }
+        a += 1;
+    }
+
+    table
+};
+
+/// The whole rank, file or diagonal through two aligned squares, edge to edge
+/// and including both; empty when they aren't aligned
+pub static LINE: [[Bitboard; 64]; 64] = {
+    let mut table = [[Bitboard::EMPTY; 64]; 64];
+    let mut a = 0u8;
+
+    while a < 64 {
+        let mut dir = 0;
+        while dir < 8 {
+            let forward = ray(a, dir);
+            let line = Bitboard(forward | ray(a, dir ^ 1) | (1u64 << a));
+            let mut rest = forward;
+            while rest != 0 {
+                table[a as usize][rest.trailing_zeros() as usize] = line;
+                rest &= rest - 1;
+            }
+            dir += 1;
+        }
+        a += 1;
+    }
+
+    table
+};
+
 // =============================================================================
 // HELPER FUNCTIONS
 // =============================================================================
@@ -146,12 +292,14 @@ pub fn king_attacks(sq: Square) -> Bitboard {
 }
 
 /// Get pawn attacks for white from a square
+#[allow(dead_code)]
 #[inline]
 pub fn white_pawn_attacks(sq: Square) -> Bitboard {
     WHITE_PAWN_ATTACKS[sq.index()]
 }
 
 /// Get pawn attacks for black from a square
+#[allow(dead_code)]
 #[inline]
 pub fn black_pawn_attacks(sq: Square) -> Bitboard {
     BLACK_PAWN_ATTACKS[sq.index()]
@@ -167,6 +315,38 @@ pub fn pawn_attacks(sq: Square, is_white: bool) -> Bitboard {
     }
 }
 
+/// Files adjacent to the square's file
+#[inline]
+pub fn adjacent_files(sq: Square) -> Bitboard {
+    ADJACENT_FILE_MASKS[sq.index()]
+}
+
+/// Passed pawn mask (front span on own + adjacent files) for either color
+#[inline]
+pub fn passed_pawn_mask(sq: Square, is_white: bool) -> Bitboard {
+    PASSED_PAWN_MASKS[if is_white { 0 } else { 1 }][sq.index()]
+}
+
+/// Pawn attack span (adjacent files, strictly ahead) for either color
+#[inline]
+pub fn pawn_attack_span(sq: Square, is_white: bool) -> Bitboard {
+    PAWN_ATTACK_SPANS[if is_white { 0 } else { 1 }][sq.index()]
+}
+
+/// Squares strictly between two squares sharing a rank, file or diagonal
+/// (empty if they aren't aligned or are adjacent)
+#[inline]
+pub fn between(a: Square, b: Square) -> Bitboard {
+    BETWEEN[a.index()][b.index()]
+}
+
+/// Full line through two squares sharing a rank, file or diagonal, including
+/// both (empty if they aren't aligned)
+#[inline]
+pub fn line(a: Square, b: Square) -> Bitboard {
+    LINE[a.index()][b.index()]
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -245,4 +425,99 @@ mod tests {
         assert_eq!(attacks.count(), 1);
         assert!(attacks.has(Square::from_file_rank(1, 4))); // b5
     }
+
+    #[test]
+    fn test_adjacent_files() {
+        assert_eq!(adjacent_files(Square::from_file_rank(0, 3)), Bitboard::FILE_B);
+        assert_eq!(adjacent_files(Square::from_file_rank(4, 0)), Bitboard::FILE_D | Bitboard::FILE_F);
+        assert_eq!(adjacent_files(Square::from_file_rank(7, 7)), Bitboard::FILE_G);
+    }
+
+    #[test]
+    fn test_passed_pawn_masks() {
+        // White pawn on e4: d5-f8 block (3 files x 4 ranks)
+        let e4 = Square::from_file_rank(4, 3);
+        let white = passed_pawn_mask(e4, true);
+        assert_eq!(white.count(), 12);
+        assert!(white.has(Square::from_file_rank(3, 4))); // d5
+        assert!(white.has(Square::from_file_rank(5, 7))); // f8
+        assert!(!white.has(Square::from_file_rank(4, 3))); // not e4 itself
+        assert!(!white.has(Square::from_file_rank(3, 3))); // not d4 (same rank)
+
+        // Black pawn on e4: d3-f1 block (3 files x 3 ranks)
+        let black = passed_pawn_mask(e4, false);
+        assert_eq!(black.count(), 9);
+        assert!(black.has(Square::from_file_rank(5, 0))); // f1
+        assert!(!black.has(Square::from_file_rank(4, 4))); // not e5
+
+        // Edge file and last rank
+        assert_eq!(passed_pawn_mask(Square::from_file_rank(0, 1), true).count(), 12);
+        assert!(passed_pawn_mask(Square::from_file_rank(3, 7), true).is_empty());
+    }
+
+    #[test]
+    fn test_pawn_attack_spans() {
+        // White on e4: d5-d8 and f5-f8
+        let e4 = Square::from_file_rank(4, 3);
+        let span = pawn_attack_span(e4, true);
+        assert_eq!(span.count(), 8);
+        assert!(span.has(Square::from_file_rank(3, 4))); // d5
+        assert!(span.has(Square::from_file_rank(5, 7))); // f8
+        assert!(!span.has(Square::from_file_rank(4, 5))); // not e6 (own file)
+        assert_eq!(span, passed_pawn_mask(e4, true) & adjacent_files(e4));
+
+        // Black on e4: d3-d1 and f3-f1
+        assert_eq!(pawn_attack_span(e4, false).count(), 6);
+        assert_eq!(pawn_attack_span(Square::from_file_rank(0, 6), false).count(), 6); // a7: b6-b1
+    }
+
+    #[test]
+    fn test_between() {
+        let sq = |s| Square::from_algebraic(s).unwrap();
+        assert_eq!(between(sq("a1"), sq("h8")).count(), 6);
+        assert!(between(sq("a1"), sq("h8")).has(sq("d4")));
+        assert_eq!(between(sq("e1"), sq("e8")), between(sq("e8"), sq("e1")));
+        assert_eq!(between(sq("e1"), sq("e8")).count(), 6);
+        assert_eq!(between(sq("b2"), sq("f2")).count(), 3);
+        // Adjacent, not aligned, or the same square
+        assert!(between(sq("e4"), sq("e5")).is_empty());
+        assert!(between(sq("a1"), sq("b3")).is_empty());
+        assert!(between(sq("d4"), sq("d4")).is_empty());
+    }
+
+    #[test]
+    fn test_between_matches_sliders() {
+        use crate::magic::{bishop_attacks, rook_attacks};
+        for a in 0..64 {
+            for b in 0..64 {
+                let (a, b) = (Square::new(a), Square::new(b));
+                let (a_bb, b_bb) = (Bitboard::from_square(a), Bitboard::from_square(b));
+                let expected = if rook_attacks(a, Bitboard::EMPTY).has(b) {
+                    rook_attacks(a, b_bb) & rook_attacks(b, a_bb)
+                } else if bishop_attacks(a, Bitboard::EMPTY).has(b) {
+                    bishop_attacks(a, b_bb) & bishop_attacks(b, a_bb)
+                } else {
+                    Bitboard::EMPTY
+                };
+                assert_eq!(between(a, b), expected, "{} {}", a.to_algebraic(), b.to_algebraic());
+            }
+        }
+    }
+
+    #[test]
+    fn test_line() {
+        let sq = |s| Square::from_algebraic(s).unwrap();
+        // The long diagonal, from any two of its squares
+        let diagonal = line(sq("c3"), sq("e5"));
+        assert_eq!(diagonal.count(), 8);
+        assert!(diagonal.has(sq("a1")) && diagonal.has(sq("h8")));
+        assert_eq!(line(sq("h8"), sq("b2")), diagonal);
+        // Ranks and files
+        assert_eq!(line(sq("a4"), sq("c4")), Bitboard::RANK_4);
+        assert_eq!(line(sq("d2"), sq("d7")).count(), 8);
+        // Contains what's between the squares
+        assert_eq!(line(sq("b1"), sq("g6")) & between(sq("b1"), sq("g6")), between(sq("b1"), sq("g6")));
+        assert!(line(sq("a1"), sq("b3")).is_empty());
+        assert!(line(sq("d4"), sq("d4")).is_empty());
+    }
 }
@@ -0,0 +1,113 @@
+// Bench
+// Searches a fixed set of positions to a fixed depth, each with a fresh
+// table, and totals the nodes. The total is a signature of the search: it
+// changes exactly when a commit changes what the engine searches, and stays
+// put for pure speedups, which show in nodes per second instead.
+
+use crate::position::Position;
+use crate::search::{search_with_control, SearchControl, SearchOptions};
+use crate::tt::TranspositionTable;
+
+/// Depth of `uci bench` without an argument
+pub const DEFAULT_BENCH_DEPTH: u8 = 8;
+/// Table size for each bench search, as a power of two entries
+const BENCH_TT_POWER: u8 = 20;
+
+/// Openings, middlegames with tactics, endgames, promotions and castling
+/// oddities
+pub const BENCH_POSITIONS: [&str; 16] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+    "r1bq1rk1/pp2ppbp/2np1np1/8/3NP3/2N1BP2/PPPQ2PP/R3KB1R w KQ - 3 9",
+    "2r3k1/pp3ppp/4p3/3pP3/1q1P4/1P1Q1N2/P4PPP/2R3K1 b - - 0 22",
+    "r1b2rk1/2q1b1pp/p2ppn2/1p6/3QP3/1BN1B3/PPP3PP/R4RK1 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "8/5pk1/6p1/8/3R4/6P1/5PK1/1r6 b - - 5 45",
+    "4k3/1P6/8/8/8/8/6p1/4K3 w - - 0 1",
+    "8/8/8/3k4/8/8/3KQ3/8 w - - 0 1",
+    "r2q1rk1/ppp2ppp/2n1bn2/2bpp3/4P3/2PP1NP1/PP1N1PBP/R1BQ1RK1 w - - 0 8",
+];
+
+/// Result of one bench position
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchEntry {
+    pub fen: &'static str,
+    pub nodes: u64,
+    pub time_ms: f64,
+}
+
+/// Result of a bench run
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub depth: u8,
+    pub entries: Vec<BenchEntry>,
+}
+
+impl BenchResult {
+    /// Nodes over all positions: the signature
+    pub fn nodes(&self) -> u64 {
+        self.entries.iter().map(|e| e.nodes).sum()
+    }
+
+    pub fn time_ms(&self) -> f64 {
+        self.entries.iter().map(|e| e.time_ms).sum()
+    }
+
+    pub fn nps(&self) -> u64 {
+        (self.nodes() as f64 * 1000.0 / self.time_ms().max(1.0)) as u64
+    }
+}
+
+/// Search every bench position to `depth`, single-threaded with a fresh
+/// table each, calling `on_entry` after each one
+pub fn run_bench(depth: u8, mut on_entry: impl FnMut(usize, &BenchEntry)) -> BenchResult {
+    let options = SearchOptions::default();
+    let mut entries = Vec::with_capacity(BENCH_POSITIONS.len());
+    for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+        let mut pos = Position::from_fen(fen).expect("bench FEN");
+        let mut tt = TranspositionTable::new(BENCH_TT_POWER);
+        let control = SearchControl::new(f64::INFINITY);
+        let (_, _, stats) = search_with_control(&mut pos, depth, &mut tt, &control, &options);
+        let entry = BenchEntry { fen, nodes: stats.nodes, time_ms: stats.time_ms };
+        on_entry(i, &entry);
+        entries.push(entry);
+    }
+    BenchResult { depth, entries }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_are_legal() {
+        for fen in BENCH_POSITIONS {
+            let pos = Position::from_fen_strict(fen).unwrap_or_else(|e| panic!("{}: {}", fen, e));
+            assert_eq!(pos.game_status(), "playing", "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_bench_is_deterministic() {
+        let mut seen = 0;
+        let first = run_bench(1, |i, entry| {
+            assert_eq!(i, seen);
+            assert!(entry.nodes > 0);
+            seen += 1;
+        });
+        assert_eq!(seen, BENCH_POSITIONS.len());
+        let second = run_bench(1, |_, _| {});
+        assert_eq!(first.nodes(), second.nodes());
+        assert!(run_bench(2, |_, _| {}).nodes() > first.nodes());
+    }
+}
@@ -0,0 +1,370 @@
+// =============================================================================
+// PGN Annotator
+// =============================================================================
+// Batch game review: replays every game of a PGN file, searches each position
+// and writes the games back with an evaluation after every move, the best
+// move where the played one lost ground, and ?! / ? / ?? on inaccuracies,
+// mistakes and blunders. A summary per game (counts and average centipawn
+// loss for each side) goes to stderr.
+//
+// Usage:
+//   cargo run --release --bin annotate -- --input games.pgn --output reviewed.pgn
+//   cargo run --release --bin annotate -- -i games.pgn --movetime 500 --blunder 250
+// =============================================================================
+
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::pgn::{parse_pgn_games, PgnGame};
+use chess_engine::position::Position;
+use chess_engine::san::parse_san;
+use chess_engine::search::{search_with_control, SearchControl, SearchOptions};
+use chess_engine::tt::TranspositionTable;
+use chess_engine::types::{Color, Move};
+use chess_engine::variant::Variant;
+
+use clap::Parser;
+use rayon::prelude::*;
+
+/// Scores are clamped to this before losses are taken, so a won position
+/// that is merely less won is not a blunder
+const LOSS_CLAMP_CP: i32 = 1000;
+/// NAGs of move assessments (!, ?, !!, ??, !?, ?!); a move keeps its own
+const ASSESSMENT_NAGS: std::ops::RangeInclusive<u8> = 1..=6;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "annotate", about = "Annotate PGN games with engine evaluations and blunder markers")]
+struct Args {
+    /// PGN file to read
+    #[arg(short, long)]
+    input: String,
+
+    /// Annotated PGN to write (stdout when absent)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Search depth per position
+    #[arg(short, long, default_value_t = 10)]
+    depth: u8,
+
+    /// Search time per position in ms, instead of a fixed depth
+    #[arg(long)]
+    movetime: Option<f64>,
+
+    /// Centipawns lost for an inaccuracy (?!)
+    #[arg(long, default_value_t = 50)]
+    inaccuracy: i32,
+
+    /// Centipawns lost for a mistake (?)
+    #[arg(long, default_value_t = 100)]
+    mistake: i32,
+
+    /// Centipawns lost for a blunder (??)
+    #[arg(long, default_value_t = 200)]
+    blunder: i32,
+
+    /// Transposition table size per game, as a power of two entries
+    #[arg(long, default_value_t = 20)]
+    hash_power: u8,
+}
+
+// =============================================================================
+// REVIEW
+// =============================================================================
+
+/// How the positions are searched and moves judged
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    /// 0 with a movetime
+    depth: u8,
+    movetime: f64,
+    /// Centipawn losses for ?!, ? and ??
+    thresholds: [i32; 3],
+    hash_power: u8,
+}
+
+impl Settings {
+    fn from_args(args: &Args) -> Self {
+        let (depth, movetime) = match args.movetime {
+            Some(ms) => (0, ms.max(1.0)),
+            None => (args.depth.max(1), f64::INFINITY),
+        };
+        Settings { depth, movetime, thresholds: [args.inaccuracy, args.mistake, args.blunder], hash_power: args.hash_power }
+    }
+
+    /// NAG and name of a move that lost `loss` centipawns, if it is marked
+    fn assessment(&self, loss: i32) -> Option<(u8, &'static str)> {
+        let [inaccuracy, mistake, blunder] = self.thresholds;
+        if loss >= blunder {
+            Some((4, "Blunder"))
+        } else if loss >= mistake {
+            Some((2, "Mistake"))
+        } else if loss >= inaccuracy {
+            Some((6, "Inaccuracy"))
+        } else {
+            None
+        }
+    }
+}
+
+/// One side's marked moves and centipawn loss
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SideReview {
+    inaccuracies: u32,
+    mistakes: u32,
+    blunders: u32,
+    moves: u32,
+    total_loss: i64,
+}
+
+impl SideReview {
+    /// Average centipawn loss
+    fn acpl(&self) -> i64 {
+        if self.moves == 0 { 0 } else { self.total_loss / self.moves as i64 }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} inaccuracies, {} mistakes, {} blunders, ACPL {}",
+            self.inaccuracies,
+            self.mistakes,
+            self.blunders,
+            self.acpl()
+        )
+    }
+}
+
+/// Score of a position with no game left to play, for the side to move
+fn terminal_score(pos: &Position) -> Option<i32> {
+    match pos.game_status().as_str() {
+        "playing" => None,
+        "stalemate" | "draw" => Some(0),
+        // Checkmated, or the opponent met a variant's win condition
+        _ => Some(-MATE_SCORE),
+    }
+}
+
+/// "[%eval 0.35]" or "[%eval #-2]" for a score from white's point of view
+fn eval_comment(white_score: i32) -> String {
+    let plies = MATE_SCORE - white_score.abs();
+    if plies < 256 {
+        let moves = (plies + 1) / 2;
+        format!("[%eval #{}]", if white_score > 0 { moves } else { -moves })
+    } else {
+        format!("[%eval {:.2}]", white_score as f64 / 100.0)
+    }
+}
+
+/// The game's moves from its starting position (FEN and Variant tags)
+fn replay(game: &PgnGame) -> Result<(Position, Vec<Move>), String> {
+    let mut pos = match game.start_fen() {
+        Some(fen) => Position::from_fen(fen).map_err(|e| format!("Invalid FEN tag: {}", e))?,
+        None => Position::starting_position(),
+    };
+    if let Some(name) = game.variant() {
+        pos.set_variant(Variant::from_name(name).ok_or_else(|| format!("Unsupported variant: {}", name))?);
+    }
+    let start = pos.clone();
+    let mut moves = Vec::new();
+    for (ply, san) in game.moves.iter().enumerate() {
+        let m = parse_san(&pos, san).map_err(|e| format!("{} at ply {}: {}", e, ply + 1, san))?;
+        pos.make_move(m);
+        moves.push(m);
+    }
+    Ok((start, moves))
+}
+
+/// The game with evaluations, best moves and assessment NAGs added, and the
+/// review of each side (white first)
+fn annotate(game: &PgnGame, settings: &Settings) -> Result<(PgnGame, [SideReview; 2]), String> {
+    let (start, moves) = replay(game)?;
+    let mut tt = TranspositionTable::new(settings.hash_power);
+    let options = SearchOptions::default();
+
+    // Score and best move of every position, for the side to move in it
+    let mut pos = start;
+    let mut searched = Vec::with_capacity(moves.len() + 1);
+    for i in 0..=moves.len() {
+        let result = match terminal_score(&pos) {
+            Some(score) => (None, score),
+            None => {
+                let control = SearchControl::new(settings.movetime);
+                let (best, score, _) = search_with_control(&mut pos.clone(), settings.depth, &mut tt, &control, &options);
+                (best, score)
+            }
+        };
+        searched.push((pos.clone(), result));
+        if let Some(&m) = moves.get(i) {
+            pos.make_move(m);
+        }
+    }
+
+    let mut annotated = game.clone();
+    if annotated.header("Annotator").is_none() {
+        let by = if settings.depth > 0 {
+            format!("depth {}", settings.depth)
+        } else {
+            format!("{} ms", settings.movetime)
+        };
+        annotated.headers.push(("Annotator".to_string(), format!("Promotion Variant Chess ({})", by)));
+    }
+    let mut review = [SideReview::default(); 2];
+    for (i, &played) in moves.iter().enumerate() {
+        let (before, (best, best_score)) = &searched[i];
+        let (after, (_, reply_score)) = &searched[i + 1];
+        let mover = before.side_to_move();
+        let played_score = -reply_score;
+        let clamp = |score: i32| score.clamp(-LOSS_CLAMP_CP, LOSS_CLAMP_CP);
+        let loss = (clamp(*best_score) - clamp(played_score)).max(0);
+
+        let side = &mut review[if mover == Color::White { 0 } else { 1 }];
+        side.moves += 1;
+        side.total_loss += loss as i64;
+
+        let ply = i + 1;
+        let mut notes = Vec::new();
+        if after.is_checkmate() {
+            notes.push("Checkmate".to_string());
+        } else {
+            let white_score = if mover == Color::White { played_score } else { -played_score };
+            notes.push(eval_comment(white_score));
+        }
+        let assessment = settings.assessment(loss).filter(|_| *best != Some(played));
+        if let (Some((nag, name)), Some(best)) = (assessment, best) {
+            match nag {
+                4 => side.blunders += 1,
+                2 => side.mistakes += 1,
+                _ => side.inaccuracies += 1,
+            }
+            notes.push(format!("{}. {} was best.", name, best.to_san(before)));
+            if !annotated.nags_at(ply).iter().any(|n| ASSESSMENT_NAGS.contains(n)) {
+                annotated.nags.push((ply, nag));
+            }
+        }
+        let text = notes.join(" ");
+        match annotated.comments.iter_mut().find(|(p, _)| *p == ply) {
+            Some((_, comment)) => *comment = format!("{} {}", comment, text),
+            None => annotated.comments.push((ply, text)),
+        }
+    }
+    Ok((annotated, review))
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn main() {
+    let args = Args::parse();
+    let settings = Settings::from_args(&args);
+    let text = match std::fs::read_to_string(&args.input) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", args.input, e);
+            std::process::exit(1);
+        }
+    };
+    let games = parse_pgn_games(&text);
+    if games.is_empty() {
+        eprintln!("No games in {}", args.input);
+        std::process::exit(1);
+    }
+
+    let results: Vec<_> = games
+        .par_iter()
+        .map(|game| game.as_ref().map_err(|e| e.clone()).and_then(|game| annotate(game, &settings).map(|r| (game, r))))
+        .collect();
+
+    let mut out = String::new();
+    for (n, result) in results.iter().enumerate() {
+        match result {
+            Ok((game, (annotated, [white, black]))) => {
+                let name = |tag| game.header(tag).unwrap_or("?");
+                eprintln!("Game {}: {} - {} {}", n + 1, name("White"), name("Black"), game.result);
+                eprintln!("  White: {}", white.summary());
+                eprintln!("  Black: {}", black.summary());
+                out.push_str(&annotated.to_pgn());
+            }
+            Err(e) => {
+                // Written back unchanged
+                eprintln!("Game {}: skipped ({})", n + 1, e);
+                if let Ok(game) = &games[n] {
+                    out.push_str(&game.to_pgn());
+                }
+            }
+        }
+        out.push('\n');
+    }
+
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, out) {
+                eprintln!("Cannot write {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", out),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_engine::pgn::parse_pgn;
+
+    fn settings(depth: u8) -> Settings {
+        Settings { depth, movetime: f64::INFINITY, thresholds: [50, 100, 200], hash_power: 16 }
+    }
+
+    #[test]
+    fn test_assessment_and_eval_comment() {
+        let settings = settings(1);
+        assert_eq!(settings.assessment(49), None);
+        assert_eq!(settings.assessment(50), Some((6, "Inaccuracy")));
+        assert_eq!(settings.assessment(150), Some((2, "Mistake")));
+        assert_eq!(settings.assessment(900), Some((4, "Blunder")));
+
+        assert_eq!(eval_comment(35), "[%eval 0.35]");
+        assert_eq!(eval_comment(-120), "[%eval -1.20]");
+        assert_eq!(eval_comment(MATE_SCORE - 3), "[%eval #2]");
+        assert_eq!(eval_comment(-MATE_SCORE + 1), "[%eval #-1]");
+
+        let side = SideReview { moves: 4, total_loss: 130, ..SideReview::default() };
+        assert_eq!(side.acpl(), 32);
+        assert_eq!(SideReview::default().acpl(), 0);
+    }
+
+    #[test]
+    fn test_annotate_marks_blunder() {
+        // 3...Nf6?? allows mate; 4.Qxf7# ends the game
+        let game = parse_pgn("[White \"A\"]\n[Black \"B\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 {Threat} Nf6 4. Qxf7# 1-0").unwrap();
+        let (annotated, [white, black]) = annotate(&game, &settings(3)).unwrap();
+
+        assert_eq!(annotated.nags_at(6), vec![4]);
+        assert!(annotated.comment(6).unwrap().contains("Blunder."), "{:?}", annotated.comment(6));
+        assert!(annotated.comment(6).unwrap().starts_with("[%eval #1]"), "{:?}", annotated.comment(6));
+        assert_eq!(annotated.comment(7), Some("Checkmate"));
+        assert!(annotated.comment(5).unwrap().starts_with("Threat [%eval"), "{:?}", annotated.comment(5));
+        assert!(annotated.header("Annotator").unwrap().contains("depth 3"));
+        assert_eq!((black.blunders, black.moves), (1, 3));
+        assert_eq!((white.blunders, white.moves), (0, 4));
+        assert!(black.acpl() > white.acpl());
+
+        let pgn = annotated.to_pgn();
+        assert!(pgn.contains("Nf6 $4 {[%eval #1] Blunder."), "{}", pgn);
+    }
+
+    #[test]
+    fn test_replay_errors() {
+        let game = parse_pgn("1. e4 e5 2. Ke3 *").unwrap();
+        assert_eq!(annotate(&game, &settings(1)).unwrap_err(), "Illegal move at ply 3: Ke3");
+        let game = parse_pgn("[Variant \"Atomic\"]\n\n1. e4 *").unwrap();
+        assert_eq!(replay(&game).err().unwrap(), "Unsupported variant: Atomic");
+    }
+}
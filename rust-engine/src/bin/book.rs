@@ -0,0 +1,171 @@
+// =============================================================================
+// Opening Book Builder
+// =============================================================================
+// Builds an opening book from PGN collections and/or the tournament's SQLite
+// database. Entries are laid out as in Polyglot books but keyed by the
+// engine's Zobrist hashes (see src/book.rs), so the book is for this engine.
+//
+// Usage:
+//   cargo run --release --bin book -- --pgn games.pgn --output book.bin
+//   cargo run --release --bin book -- --db tournament_results.db --min-games 5 --weighting count
+// =============================================================================
+
+use chess_engine::book::{write_book, BookBuilder, Weighting};
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::pgn::parse_pgn_games;
+use chess_engine::position::Position;
+use chess_engine::san::parse_san;
+use chess_engine::types::Move;
+
+use clap::Parser;
+use rusqlite::Connection;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "book", about = "Build an opening book from PGN files or a tournament database")]
+struct Args {
+    /// PGN file to read (may be given several times)
+    #[arg(long)]
+    pgn: Vec<String>,
+
+    /// Tournament database to read openings and results from
+    #[arg(long)]
+    db: Option<String>,
+
+    /// Output book path
+    #[arg(short, long, default_value = "book.bin")]
+    output: String,
+
+    /// Plies of each game to keep
+    #[arg(long, default_value_t = 16)]
+    max_ply: usize,
+
+    /// Games a move must appear in to be kept
+    #[arg(long, default_value_t = 3)]
+    min_games: u32,
+
+    /// Move weights: "score" (points scored with the move) or "count" (games)
+    #[arg(long, default_value = "score")]
+    weighting: String,
+}
+
+// =============================================================================
+// INPUT
+// =============================================================================
+
+/// Add the games of a PGN file. Returns (games used, games skipped).
+fn add_pgn(builder: &mut BookBuilder, path: &str) -> std::io::Result<(usize, usize)> {
+    let text = std::fs::read_to_string(path)?;
+    let (mut used, mut skipped) = (0, 0);
+    for game in parse_pgn_games(&text) {
+        let Ok(game) = game else {
+            skipped += 1;
+            continue;
+        };
+        let start = match game.start_fen().map(Position::from_fen) {
+            Some(Ok(pos)) => pos,
+            Some(Err(_)) => {
+                skipped += 1;
+                continue;
+            }
+            None => Position::starting_position(),
+        };
+        let mut pos = start.clone();
+        let mut moves = Vec::new();
+        // Keep the moves up to the first one that doesn't parse
+        for san in &game.moves {
+            match parse_san(&pos, san) {
+                Ok(m) if pos.make_move(m).is_some() => moves.push(m),
+                _ => break,
+            }
+        }
+        if builder.add_game(&start, &moves, &game.result) {
+            used += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+    Ok((used, skipped))
+}
+
+/// Add the openings played in a tournament database. Returns (games used, games skipped).
+fn add_db(builder: &mut BookBuilder, path: &str) -> rusqlite::Result<(usize, usize)> {
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare("SELECT opening_uci, result FROM games WHERE opening_uci IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    let start = Position::starting_position();
+    let (mut used, mut skipped) = (0, 0);
+    for row in rows {
+        let (opening, result) = row?;
+        let mut pos = start.clone();
+        let mut moves: Vec<Move> = Vec::new();
+        for uci in opening.split_whitespace() {
+            let Some(m) = generate_legal_moves(&pos).iter().copied().find(|m| m.to_uci() == uci) else { break };
+            pos.make_move(m);
+            moves.push(m);
+        }
+        if builder.add_game(&start, &moves, &result) {
+            used += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+    Ok((used, skipped))
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn main() {
+    let args = Args::parse();
+    let weighting = match args.weighting.as_str() {
+        "score" => Weighting::Score,
+        "count" => Weighting::Count,
+        other => {
+            eprintln!("Unknown weighting: {} (expected score or count)", other);
+            std::process::exit(2);
+        }
+    };
+    if args.pgn.is_empty() && args.db.is_none() {
+        eprintln!("Nothing to read: give --pgn and/or --db");
+        std::process::exit(2);
+    }
+
+    let mut builder = BookBuilder::new(args.max_ply);
+    for path in &args.pgn {
+        match add_pgn(&mut builder, path) {
+            Ok((used, skipped)) => println!("{}: {} games ({} skipped)", path, used, skipped),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = &args.db {
+        match add_db(&mut builder, path) {
+            Ok((used, skipped)) => println!("{}: {} games ({} skipped)", path, used, skipped),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let entries = builder.build(args.min_games, weighting);
+    let positions = entries.windows(2).filter(|w| w[0].key != w[1].key).count() + usize::from(!entries.is_empty());
+    if let Err(e) = std::fs::write(&args.output, write_book(&entries)) {
+        eprintln!("{}: {}", args.output, e);
+        std::process::exit(1);
+    }
+    println!(
+        "Wrote {}: {} moves in {} positions from {} games",
+        args.output,
+        entries.len(),
+        positions,
+        builder.games()
+    );
+}
@@ -0,0 +1,240 @@
+// =============================================================================
+// Training Data Generator
+// =============================================================================
+// Plays self-play games in parallel and writes their quiet positions with the
+// search score and the game result (see `chess_engine::datagen`), as input
+// for evaluation tuning. Text output has a "<fen> | <score> | <result>" line
+// per position; --binary writes 32-byte records instead.
+//
+// Usage:
+//   cargo run --release --bin datagen -- --games 10000 -o data.txt
+//   cargo run --release --bin datagen -- --games 1000000 --nodes 8000 --binary -o data.bin
+// =============================================================================
+
+use chess_engine::datagen::{selfplay_game, DatagenOptions, TrainingRecord};
+
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+/// Games played between writes to the output
+const BATCH_GAMES: u64 = 256;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "datagen", about = "Generate scored self-play positions for tuning")]
+struct Args {
+    /// Output file
+    #[arg(short, long, default_value = "datagen.txt")]
+    output: String,
+
+    /// Write 32-byte binary records instead of text lines
+    #[arg(long)]
+    binary: bool,
+
+    /// Number of games
+    #[arg(short, long, default_value_t = 1000)]
+    games: u64,
+
+    /// Nodes per move
+    #[arg(long, default_value_t = 5000)]
+    nodes: u64,
+
+    /// Depth per move (0 = only the node limit)
+    #[arg(long, default_value_t = 0)]
+    depth: u8,
+
+    /// Random moves at the start of each game
+    #[arg(long, default_value_t = 8)]
+    random_plies: u32,
+
+    /// Plies after which a game is scored a draw
+    #[arg(long, default_value_t = 400)]
+    max_plies: usize,
+
+    /// Score, in centipawns, past which the losing side resigns...
+    #[arg(long, default_value_t = 1000)]
+    resign_cp: i32,
+
+    /// ...after this many plies in a row
+    #[arg(long, default_value_t = 8)]
+    resign_plies: u32,
+
+    /// Transposition table size per game, as a power of two entries
+    #[arg(long, default_value_t = 16)]
+    hash_power: u8,
+
+    /// Number of threads (0 = all cores)
+    #[arg(short, long, default_value_t = 0)]
+    threads: usize,
+
+    /// Random seed for reproducibility
+    #[arg(short, long, default_value_t = 1)]
+    seed: u64,
+}
+
+impl Args {
+    fn options(&self) -> DatagenOptions {
+        DatagenOptions {
+            random_plies: self.random_plies,
+            nodes: self.nodes,
+            depth: self.depth,
+            max_plies: self.max_plies,
+            resign_cp: self.resign_cp,
+            resign_plies: self.resign_plies,
+            hash_power: self.hash_power,
+        }
+    }
+}
+
+// =============================================================================
+// OUTPUT
+// =============================================================================
+
+/// Records written, and those skipped as too big for a binary record
+#[derive(Debug, Default, PartialEq)]
+struct Written {
+    records: u64,
+    skipped: u64,
+}
+
+/// Write `records` as text lines or binary records
+fn write_records(out: &mut impl Write, records: &[TrainingRecord], binary: bool) -> std::io::Result<Written> {
+    let mut written = Written::default();
+    for record in records {
+        if binary {
+            match record.to_bytes() {
+                Ok(bytes) => out.write_all(&bytes)?,
+                Err(_) => {
+                    written.skipped += 1;
+                    continue;
+                }
+            }
+        } else {
+            writeln!(out, "{}", record.to_text())?;
+        }
+        written.records += 1;
+    }
+    Ok(written)
+}
+
+/// Seed of game `game`: different games, and different runs per seed
+fn game_seed(seed: u64, game: u64) -> u64 {
+    seed ^ game.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn main() {
+    let args = Args::parse();
+    if args.threads > 0 {
+        rayon::ThreadPoolBuilder::new().num_threads(args.threads).build_global().ok();
+    }
+    let options = args.options();
+    let mut out = match File::create(&args.output) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => {
+            eprintln!("Cannot create {}: {}", args.output, e);
+            std::process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let pb = ProgressBar::new(args.games);
+    pb.set_style(
+        ProgressStyle::with_template("[{bar:40.cyan/blue}] {pos}/{len} games ({per_sec}, ETA {eta}) {msg}")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+    let mut total = Written::default();
+    // Games by White's half-points: losses, draws, wins
+    let mut results = [0u64; 3];
+    let mut game = 0;
+    while game < args.games {
+        let batch = BATCH_GAMES.min(args.games - game);
+        let games: Vec<Vec<TrainingRecord>> = (game..game + batch)
+            .into_par_iter()
+            .map(|g| {
+                let records = selfplay_game(game_seed(args.seed, g), &options);
+                pb.inc(1);
+                records
+            })
+            .collect();
+        for records in &games {
+            if let Some(record) = records.first() {
+                results[record.result as usize] += 1;
+            }
+            match write_records(&mut out, records, args.binary) {
+                Ok(written) => {
+                    total.records += written.records;
+                    total.skipped += written.skipped;
+                }
+                Err(e) => {
+                    eprintln!("Cannot write {}: {}", args.output, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        pb.set_message(format!("{} positions", total.records));
+        game += batch;
+    }
+    if let Err(e) = out.flush() {
+        eprintln!("Cannot write {}: {}", args.output, e);
+        std::process::exit(1);
+    }
+    pb.finish();
+
+    let secs = start.elapsed().as_secs_f64();
+    let rate = total.records as f64 / secs.max(1e-9);
+    println!("{} positions from {} games in {:.1}s ({:.0} positions/s)", total.records, args.games, secs, rate);
+    println!("White wins {}, draws {}, black wins {}", results[2], results[1], results[0]);
+    if total.skipped > 0 {
+        println!("{} positions with more than 32 pieces skipped", total.skipped);
+    }
+    println!("Written to {}", args.output);
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_engine::datagen::RECORD_BYTES;
+
+    #[test]
+    fn test_write_records() {
+        let args = Args::parse_from(["datagen", "--nodes", "300", "--max-plies", "30"]);
+        let records = selfplay_game(game_seed(args.seed, 0), &args.options());
+        assert!(!records.is_empty());
+
+        let mut text = Vec::new();
+        let written = write_records(&mut text, &records, false).unwrap();
+        assert_eq!(written, Written { records: records.len() as u64, skipped: 0 });
+        let lines: Vec<TrainingRecord> =
+            String::from_utf8(text).unwrap().lines().map(|l| TrainingRecord::parse_text(l).unwrap()).collect();
+        assert_eq!(lines, records);
+
+        let mut binary = Vec::new();
+        write_records(&mut binary, &records, true).unwrap();
+        assert_eq!(binary.len(), records.len() * RECORD_BYTES);
+        let first = TrainingRecord::from_bytes(binary[..RECORD_BYTES].try_into().unwrap()).unwrap();
+        assert_eq!(first, records[0]);
+
+        // Only real positions fit in 32 pieces
+        let fen = "kkkkkkkk/pppppppp/pppppppp/8/8/PPPPPPPP/PPPPPPPP/KKKKKKKK w - - 0 1";
+        let crowded = TrainingRecord { fen: fen.to_string(), score: 0, result: 1 };
+        assert_eq!(write_records(&mut Vec::new(), &[crowded], true).unwrap(), Written { records: 0, skipped: 1 });
+        assert_ne!(game_seed(1, 0), game_seed(2, 0));
+        assert_ne!(game_seed(1, 0), game_seed(1, 1));
+    }
+}
@@ -0,0 +1,326 @@
+// =============================================================================
+// EPD Test Suite Runner
+// =============================================================================
+// Runs the engine on EPD test suites (WAC, Bratko-Kopec, STS, ...) with a
+// time or depth limit per position. A position is solved when the move found
+// is one of its "bm" moves and none of its "am" moves; STS-style suites that
+// give points per move in a c0 comment ("Nf3=10, e4=5") are also scored in
+// points. Prints a line per position, then the pass rate of each suite.
+//
+// Usage:
+//   cargo run --release --bin epd -- wac.epd --movetime 1000
+//   cargo run --release --bin epd -- bk.epd sts1.epd --depth 10 --jobs 4
+// =============================================================================
+
+use chess_engine::epd::{parse_epd_file, EpdRecord};
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::position::Position;
+use chess_engine::san::parse_san;
+use chess_engine::search::{search_with_control, SearchControl, SearchOptions};
+use chess_engine::tt::TranspositionTable;
+use chess_engine::types::Move;
+
+use clap::Parser;
+use rayon::prelude::*;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "epd", about = "Run EPD test suites and report the pass rate")]
+struct Args {
+    /// EPD files to run
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Search time per position in ms
+    #[arg(short, long, default_value_t = 1000.0)]
+    movetime: f64,
+
+    /// Search depth per position, instead of a time limit
+    #[arg(short, long)]
+    depth: Option<u8>,
+
+    /// Positions searched at once (more than one skews timed runs)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Transposition table size per position, as a power of two entries
+    #[arg(long, default_value_t = 20)]
+    hash_power: u8,
+
+    /// Print only the failed positions and the summary
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+// =============================================================================
+// SCORING
+// =============================================================================
+
+/// What a suite position asks of the engine
+#[derive(Debug, PartialEq)]
+struct Expectation {
+    best: Vec<Move>,
+    avoid: Vec<Move>,
+    /// Points per move from a c0 "Move=points, ..." comment
+    points: Vec<(Move, u32)>,
+}
+
+impl Expectation {
+    /// Moves of the record's bm, am and c0 operations, as legal moves of `pos`
+    fn from_record(record: &EpdRecord, pos: &Position) -> Result<Self, String> {
+        let moves = |sans: &[String]| -> Result<Vec<Move>, String> {
+            sans.iter().map(|san| parse_san(pos, san).map_err(|e| format!("{}: {}", e, san))).collect()
+        };
+        let best = moves(record.best_moves())?;
+        let avoid = moves(record.avoid_moves())?;
+        let mut points = Vec::new();
+        let comment = record.operation("c0").and_then(|ops| ops.first()).map(String::as_str).unwrap_or_default();
+        for entry in comment.split(',') {
+            // Other c0 comments (sources, descriptions) don't score moves
+            let Some((san, value)) = entry.trim().split_once('=') else { continue };
+            let (Ok(m), Ok(value)) = (parse_san(pos, san), value.trim().parse::<u32>()) else { continue };
+            points.push((m, value));
+        }
+        if best.is_empty() && avoid.is_empty() && points.is_empty() {
+            return Err("No bm, am or scored c0 operation".to_string());
+        }
+        Ok(Expectation { best, avoid, points })
+    }
+
+    fn solved_by(&self, m: Move) -> bool {
+        (self.best.is_empty() || self.best.contains(&m)) && !self.avoid.contains(&m)
+    }
+
+    /// Points for `m` and the most the position gives, when it gives points
+    fn points_for(&self, m: Move) -> Option<(u32, u32)> {
+        let max = self.points.iter().map(|&(_, p)| p).max()?;
+        let got = self.points.iter().find(|&&(pm, _)| pm == m).map_or(0, |&(_, p)| p);
+        Some((got, max))
+    }
+}
+
+/// Result of one position
+#[derive(Debug)]
+struct Outcome {
+    id: String,
+    /// Move found, in SAN
+    found: String,
+    /// The bm/am operations, as in the file
+    expected: String,
+    solved: bool,
+    points: Option<(u32, u32)>,
+    depth: u8,
+    score: i32,
+}
+
+/// Score as "+1.25" or "#3" / "#-2"
+fn format_score(score: i32) -> String {
+    let plies = MATE_SCORE - score.abs();
+    if plies < 256 {
+        let moves = (plies + 1) / 2;
+        format!("#{}", if score > 0 { moves } else { -moves })
+    } else {
+        format!("{:+.2}", score as f64 / 100.0)
+    }
+}
+
+/// Search a suite position and judge the move found
+fn run_position(record: &EpdRecord, index: usize, args: &Args) -> Result<Outcome, String> {
+    let pos = Position::from_fen(&record.fen()).map_err(|e| e.to_string())?;
+    let expectation = Expectation::from_record(record, &pos)?;
+    let mut tt = TranspositionTable::new(args.hash_power);
+    let (depth, max_ms) = match args.depth {
+        Some(depth) => (depth.max(1), f64::INFINITY),
+        None => (0, args.movetime.max(1.0)),
+    };
+    let control = SearchControl::new(max_ms);
+    let (best, score, stats) = search_with_control(&mut pos.clone(), depth, &mut tt, &control, &SearchOptions::default());
+    let best = best.ok_or("No legal move")?;
+
+    let mut expected = Vec::new();
+    if !record.best_moves().is_empty() {
+        expected.push(format!("bm {}", record.best_moves().join(" ")));
+    }
+    if !record.avoid_moves().is_empty() {
+        expected.push(format!("am {}", record.avoid_moves().join(" ")));
+    }
+    Ok(Outcome {
+        id: record.id().map_or_else(|| format!("#{}", index + 1), str::to_string),
+        found: best.to_san(&pos),
+        expected: expected.join(" "),
+        solved: expectation.solved_by(best),
+        points: expectation.points_for(best),
+        depth: stats.depth,
+        score,
+    })
+}
+
+/// Totals of a suite
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+    solved: usize,
+    positions: usize,
+    errors: usize,
+    points: u32,
+    max_points: u32,
+}
+
+impl Summary {
+    fn add(&mut self, outcome: &Result<Outcome, String>) {
+        match outcome {
+            Ok(outcome) => {
+                self.positions += 1;
+                self.solved += outcome.solved as usize;
+                if let Some((got, max)) = outcome.points {
+                    self.points += got;
+                    self.max_points += max;
+                }
+            }
+            Err(_) => self.errors += 1,
+        }
+    }
+
+    fn line(&self, name: &str) -> String {
+        let rate = 100.0 * self.solved as f64 / self.positions.max(1) as f64;
+        let mut line = format!("{}: {}/{} solved ({:.1}%)", name, self.solved, self.positions, rate);
+        if self.max_points > 0 {
+            line.push_str(&format!(", {}/{} points", self.points, self.max_points));
+        }
+        if self.errors > 0 {
+            line.push_str(&format!(", {} positions skipped", self.errors));
+        }
+        line
+    }
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn main() {
+    let args = Args::parse();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.jobs.max(1)).build().expect("thread pool");
+    let mut total = Summary::default();
+    let mut summaries = Vec::new();
+
+    for path in &args.files {
+        let records = match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|t| parse_epd_file(&t)) {
+            Ok(records) => records,
+            Err(e) => {
+                eprintln!("Cannot load {}: {}", path, e);
+                std::process::exit(1);
+            }
+        };
+        println!("{} ({} positions)", path, records.len());
+        let outcomes: Vec<_> =
+            pool.install(|| records.par_iter().enumerate().map(|(i, r)| run_position(r, i, &args)).collect());
+
+        let mut summary = Summary::default();
+        for (i, outcome) in outcomes.iter().enumerate() {
+            summary.add(outcome);
+            total.add(outcome);
+            match outcome {
+                Ok(o) if args.quiet && o.solved => {}
+                Ok(o) => {
+                    let points = o.points.map(|(got, max)| format!(" {}/{}", got, max)).unwrap_or_default();
+                    println!(
+                        "  {:<24} {:<4} {:<8} {:<20} depth {:>2} {}{}",
+                        o.id,
+                        if o.solved { "ok" } else { "FAIL" },
+                        o.found,
+                        o.expected,
+                        o.depth,
+                        format_score(o.score),
+                        points
+                    );
+                }
+                Err(e) => println!("  #{:<23} skipped: {}", i + 1, e),
+            }
+        }
+        summaries.push(summary.line(path));
+    }
+
+    println!();
+    for line in &summaries {
+        println!("{}", line);
+    }
+    if args.files.len() > 1 {
+        println!("{}", total.line("Total"));
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_engine::epd::parse_epd;
+
+    fn args(depth: u8) -> Args {
+        Args { files: Vec::new(), movetime: 1000.0, depth: Some(depth), jobs: 1, hash_power: 16, quiet: false }
+    }
+
+    #[test]
+    fn test_expectation() {
+        let record = parse_epd(r#"6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; am Ra7; c0 "Ra8=10, Kf2=1, no score";"#).unwrap();
+        let pos = Position::from_fen(&record.fen()).unwrap();
+        let expectation = Expectation::from_record(&record, &pos).unwrap();
+        let mv = |san| parse_san(&pos, san).unwrap();
+        assert!(expectation.solved_by(mv("Ra8")));
+        assert!(!expectation.solved_by(mv("Ra7")) && !expectation.solved_by(mv("Kf1")));
+        assert_eq!(expectation.points_for(mv("Ra8")), Some((10, 10)));
+        assert_eq!(expectation.points_for(mv("Kf1")), Some((0, 10)));
+
+        // am only: anything else solves it
+        let record = parse_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - am Ra7;").unwrap();
+        let expectation = Expectation::from_record(&record, &pos).unwrap();
+        assert!(expectation.solved_by(mv("Kf1")) && expectation.points_for(mv("Kf1")).is_none());
+
+        let record = parse_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Qh5;").unwrap();
+        assert_eq!(Expectation::from_record(&record, &pos).unwrap_err(), "Illegal move: Qh5");
+        let record = parse_epd(r#"6k1/5ppp/8/8/8/8/8/R5K1 w - - id "x";"#).unwrap();
+        assert!(Expectation::from_record(&record, &pos).is_err());
+    }
+
+    #[test]
+    fn test_run_position() {
+        let record = parse_epd(r#"6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "mate.1";"#).unwrap();
+        let outcome = run_position(&record, 0, &args(3)).unwrap();
+        assert_eq!((outcome.id.as_str(), outcome.found.as_str(), outcome.solved), ("mate.1", "Ra8#", true));
+        assert_eq!(format_score(outcome.score), "#1");
+        assert_eq!(outcome.expected, "bm Ra8#");
+
+        // The engine won't play the losing move it is told to avoid
+        let record = parse_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - am Ra7;").unwrap();
+        let outcome = run_position(&record, 4, &args(3)).unwrap();
+        assert_eq!((outcome.id.as_str(), outcome.solved), ("#5", true));
+    }
+
+    #[test]
+    fn test_summary() {
+        let outcome = |solved, points| {
+            Ok(Outcome {
+                id: String::new(),
+                found: String::new(),
+                expected: String::new(),
+                solved,
+                points,
+                depth: 1,
+                score: 0,
+            })
+        };
+        let mut summary = Summary::default();
+        summary.add(&outcome(true, Some((10, 10))));
+        summary.add(&outcome(false, Some((3, 10))));
+        summary.add(&outcome(true, None));
+        summary.add(&Err("No legal move".to_string()));
+        assert_eq!(summary.line("sts"), "sts: 2/3 solved (66.7%), 13/20 points, 1 positions skipped");
+        assert_eq!(format_score(-125), "-1.25");
+        assert_eq!(format_score(40), "+0.40");
+    }
+}
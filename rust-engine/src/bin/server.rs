@@ -0,0 +1,620 @@
+// =============================================================================
+// Analysis Server
+// =============================================================================
+// Serves the engine to backend services over HTTP, or over stdin/stdout as
+// JSON-RPC lines, without going through WASM. Requests are handled
+// concurrently by a pool of workers whose searches share one transposition
+// table.
+//
+// Usage:
+//   cargo run --release --bin server -- --port 8080 --hash 256
+//   curl -d '{"fen":"...","depth":12}' localhost:8080/analyse
+//   echo '{"jsonrpc":"2.0","id":1,"method":"bestmove","params":{"movetime":500}}' | server --stdio
+//
+// Methods (HTTP: POST /<method> with the params as the body, or POST /rpc
+// with a JSON-RPC 2.0 request; GET /health):
+//   analyse      {fen?, moves?, variant?, depth?, movetime?, multipv?}
+//                → {depth, nodes, nps, timeMs, lines: [{move, san, score, mate?, pv}]}
+//   bestmove     {fen?, moves?, variant?, depth?, movetime?}
+//                → {move, ponder, score, mate?, depth, nodes, timeMs}
+//   legal-moves  {fen?, moves?, variant?} → {fen, moves, san, status}
+// Scores are centipawns for the side to move; mate is in moves, negative
+// when the side to move is mated. Depth 0 is the same as no depth. A position
+// the game is over in is not searched: depth 0, no lines and no move.
+// =============================================================================
+
+use chess_engine::driver::EngineOptions;
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use chess_engine::search::{search_multipv_with_control, PvLine, SearchControl, SearchOptions, SearchStats};
+use chess_engine::tt::{SharedTable, TranspositionTable};
+use chess_engine::variant::Variant;
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// Search time when a request gives neither depth nor movetime, in ms
+const DEFAULT_MOVETIME_MS: f64 = 1000.0;
+/// Most lines an "analyse" request may ask for
+const MAX_MULTIPV: usize = 32;
+/// Largest request body accepted, in bytes
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "server", about = "Chess analysis server (HTTP or JSON-RPC lines on stdin)")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+
+    /// Read JSON-RPC requests from stdin (one per line) instead of serving HTTP
+    #[arg(long)]
+    stdio: bool,
+
+    /// Transposition table size in MB, shared by all requests
+    #[arg(long, default_value_t = 64)]
+    hash: usize,
+
+    /// Requests handled at once (0 = one per CPU)
+    #[arg(short, long, default_value_t = 0)]
+    workers: usize,
+
+    /// Longest search a request may ask for, in ms
+    #[arg(long, default_value_t = 10_000.0)]
+    max_movetime: f64,
+}
+
+// =============================================================================
+// REQUESTS
+// =============================================================================
+
+/// Parameters of every method; each uses the ones it needs
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    /// Starting position; the standard one when absent
+    fen: Option<String>,
+    /// UCI moves played from it
+    moves: Vec<String>,
+    variant: Option<String>,
+    depth: Option<u8>,
+    movetime: Option<f64>,
+    multipv: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+enum RequestError {
+    UnknownMethod(String),
+    InvalidParams(String),
+}
+
+impl RequestError {
+    /// JSON-RPC error code
+    fn code(&self) -> i32 {
+        match self {
+            RequestError::UnknownMethod(_) => -32601,
+            RequestError::InvalidParams(_) => -32602,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            RequestError::UnknownMethod(method) => format!("Unknown method: {}", method),
+            RequestError::InvalidParams(message) => message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Line {
+    #[serde(rename = "move")]
+    mv: String,
+    san: String,
+    score: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mate: Option<i32>,
+    pv: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Analysis {
+    depth: u8,
+    nodes: u64,
+    nps: u64,
+    time_ms: f64,
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BestMove {
+    #[serde(rename = "move")]
+    mv: Option<String>,
+    ponder: Option<String>,
+    score: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mate: Option<i32>,
+    depth: u8,
+    nodes: u64,
+    time_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct LegalMoves {
+    fen: String,
+    moves: Vec<String>,
+    san: Vec<String>,
+    status: String,
+}
+
+/// Moves to mate for a mate score, negative when mated
+fn mate_in(score: i32) -> Option<i32> {
+    let plies = MATE_SCORE - score.abs();
+    (plies < 256).then(|| if score > 0 { (plies + 1) / 2 } else { -(plies + 1) / 2 })
+}
+
+// =============================================================================
+// ENGINE
+// =============================================================================
+
+struct Server {
+    table: Arc<SharedTable>,
+    max_movetime: f64,
+}
+
+impl Server {
+    fn new(hash_mb: usize, max_movetime: f64) -> Self {
+        let options = EngineOptions { hash_mb, ..EngineOptions::default() };
+        Server { table: SharedTable::new(options.hash_size_power()), max_movetime }
+    }
+
+    /// Run `method` with its JSON parameters
+    fn dispatch(&self, method: &str, params: Value) -> Result<Value, RequestError> {
+        let params: Params = match params {
+            Value::Null => Params::default(),
+            params => serde_json::from_value(params).map_err(|e| RequestError::InvalidParams(e.to_string()))?,
+        };
+        let result = match method {
+            "analyse" | "analyze" => serde_json::to_value(self.analyse(&params)?),
+            "bestmove" => serde_json::to_value(self.bestmove(&params)?),
+            "legal-moves" | "legal_moves" => serde_json::to_value(legal_moves(&params)?),
+            _ => return Err(RequestError::UnknownMethod(method.to_string())),
+        };
+        Ok(result.expect("results serialize"))
+    }
+
+    /// Answer one JSON-RPC 2.0 request
+    fn rpc(&self, request: &str) -> Value {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(e) => return rpc_error(Value::Null, -32700, &format!("Parse error: {}", e)),
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return rpc_error(id, -32600, "Invalid request: no method");
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        match self.dispatch(method, params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(e) => rpc_error(id, e.code(), &e.message()),
+        }
+    }
+
+    /// The best `n` lines of the requested position
+    fn search(&self, params: &Params, n: usize) -> Result<(Position, Vec<PvLine>, SearchStats), RequestError> {
+        let pos = setup(params)?;
+        if let Some(score) = final_score(&pos) {
+            return Ok((pos, Vec::new(), SearchStats { score, ..SearchStats::default() }));
+        }
+        let (depth, movetime) = search_limits(params);
+        let control = SearchControl::new(movetime.min(self.max_movetime));
+        let mut tt = TranspositionTable::attach(Arc::clone(&self.table));
+        let (lines, stats) =
+            search_multipv_with_control(&mut pos.clone(), n, depth, &mut tt, &control, &SearchOptions::default());
+        Ok((pos, lines, stats))
+    }
+
+    fn analyse(&self, params: &Params) -> Result<Analysis, RequestError> {
+        let n = params.multipv.unwrap_or(1).clamp(1, MAX_MULTIPV);
+        let (pos, lines, stats) = self.search(params, n)?;
+        let lines = lines
+            .iter()
+            .map(|line| Line {
+                mv: line.mv.to_uci(),
+                san: line.mv.to_san(&pos),
+                score: line.score,
+                mate: mate_in(line.score),
+                pv: line.pv.iter().map(|m| m.to_uci()).collect(),
+            })
+            .collect();
+        Ok(Analysis { depth: stats.depth, nodes: stats.nodes, nps: stats.nps, time_ms: stats.time_ms, lines })
+    }
+
+    fn bestmove(&self, params: &Params) -> Result<BestMove, RequestError> {
+        let (_, lines, stats) = self.search(params, 1)?;
+        let best = lines.first();
+        let score = best.map_or(stats.score, |line| line.score);
+        Ok(BestMove {
+            mv: best.map(|line| line.mv.to_uci()),
+            ponder: best.and_then(|line| line.pv.get(1)).map(|m| m.to_uci()),
+            score,
+            mate: best.and_then(|_| mate_in(score)),
+            depth: stats.depth,
+            nodes: stats.nodes,
+            time_ms: stats.time_ms,
+        })
+    }
+}
+
+/// The position of a request: its FEN (or the standard start) under its
+/// variant, with its moves played
+fn setup(params: &Params) -> Result<Position, RequestError> {
+    let invalid = RequestError::InvalidParams;
+    let mut pos = match &params.fen {
+        Some(fen) => Position::from_fen_strict(fen).map_err(|e| invalid(format!("Invalid FEN: {}", e)))?,
+        None => Position::starting_position(),
+    };
+    if let Some(name) = &params.variant {
+        pos.set_variant(Variant::from_name(name).ok_or_else(|| invalid(format!("Unknown variant: {}", name)))?);
+    }
+    for uci in &params.moves {
+        let m = generate_legal_moves(&pos).iter().copied().find(|m| m.to_uci() == *uci);
+        let m = m.ok_or_else(|| invalid(format!("Illegal move: {}", uci)))?;
+        pos.make_move(m);
+    }
+    Ok(pos)
+}
+
+/// Depth (0 = unlimited) and movetime in ms of a request. A depth of 0
+/// counts as none, so it gets the default movetime rather than no limit.
+fn search_limits(params: &Params) -> (u8, f64) {
+    let depth = params.depth.filter(|&d| d > 0);
+    let movetime = match (params.movetime, depth) {
+        (Some(ms), _) => ms.max(1.0),
+        (None, Some(_)) => f64::INFINITY,
+        (None, None) => DEFAULT_MOVETIME_MS,
+    };
+    (depth.unwrap_or(0), movetime)
+}
+
+/// Score for the side to move if the game is over: won or lost under the
+/// variant's rules, checkmate or stalemate
+fn final_score(pos: &Position) -> Option<i32> {
+    if let Some((winner, _)) = pos.variant().outcome(pos) {
+        return Some(if winner == pos.side_to_move() { MATE_SCORE } else { -MATE_SCORE });
+    }
+    if !generate_legal_moves(pos).is_empty() {
+        return None;
+    }
+    Some(if pos.is_in_check(pos.side_to_move()) { -MATE_SCORE } else { 0 })
+}
+
+fn legal_moves(params: &Params) -> Result<LegalMoves, RequestError> {
+    let pos = setup(params)?;
+    let moves = generate_legal_moves(&pos);
+    Ok(LegalMoves {
+        fen: pos.to_fen(),
+        moves: moves.iter().map(|m| m.to_uci()).collect(),
+        san: moves.iter().map(|m| m.to_san(&pos)).collect(),
+        status: pos.game_status(),
+    })
+}
+
+fn rpc_error(id: Value, code: i32, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+// =============================================================================
+// HTTP
+// =============================================================================
+
+/// The parts of an HTTP request the server looks at
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+/// Read one request: request line, headers (only Content-Length is used), body
+fn read_request(reader: &mut impl BufRead) -> Result<Request, String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("Malformed request line".to_string());
+    };
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let method = method.to_string();
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| "Bad Content-Length".to_string())?;
+            }
+        }
+    }
+    if length > MAX_BODY_BYTES {
+        return Err("Request body too large".to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "Body is not UTF-8".to_string())?;
+    Ok(Request { method, path, body })
+}
+
+/// Status code and JSON body answering `request`
+fn respond(server: &Server, request: &Request) -> (u16, Value) {
+    let error = |status, message: &str| (status, json!({ "error": message }));
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => (200, json!({"status": "ok"})),
+        ("POST", "/rpc") => (200, server.rpc(&request.body)),
+        ("POST", path) => {
+            let params = if request.body.trim().is_empty() {
+                Value::Null
+            } else {
+                match serde_json::from_str(&request.body) {
+                    Ok(params) => params,
+                    Err(e) => return error(400, &format!("Invalid JSON: {}", e)),
+                }
+            };
+            match server.dispatch(path.trim_start_matches('/'), params) {
+                Ok(result) => (200, result),
+                Err(e @ RequestError::UnknownMethod(_)) => error(404, &e.message()),
+                Err(e) => error(400, &e.message()),
+            }
+        }
+        (_, "/health" | "/rpc" | "/analyse" | "/analyze" | "/bestmove" | "/legal-moves") => {
+            error(405, "Method not allowed")
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    }
+}
+
+/// Answer the one request of a connection, then close it
+fn handle_connection(server: &Server, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (status, body) = match read_request(&mut reader) {
+        Ok(request) => respond(server, &request),
+        Err(message) => (400, json!({ "error": message })),
+    };
+    let body = body.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        status_text(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn main() {
+    let args = Args::parse();
+    let server = Arc::new(Server::new(args.hash, args.max_movetime));
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.workers).build().expect("worker pool");
+
+    if args.stdio {
+        // Answers go out as they finish, matched to requests by id
+        pool.in_place_scope(|scope| {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let server = &server;
+                scope.spawn(move |_| println!("{}", server.rpc(&line)));
+            }
+        });
+        return;
+    }
+
+    let listener = match TcpListener::bind((args.host.as_str(), args.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Cannot listen on {}:{}: {}", args.host, args.port, e);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on http://{}", listener.local_addr().expect("bound address"));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let server = Arc::clone(&server);
+        pool.spawn(move || {
+            if let Err(e) = handle_connection(&server, stream) {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+    fn server() -> Server {
+        Server::new(1, 5000.0)
+    }
+
+    #[test]
+    fn test_methods() {
+        let server = server();
+        let result = server.dispatch("analyse", json!({"fen": MATE_IN_ONE, "depth": 3, "multipv": 2})).unwrap();
+        let lines = result["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["move"], "a1a8");
+        assert_eq!(lines[0]["san"], "Ra8#");
+        assert_eq!(lines[0]["mate"], 1);
+        assert_eq!(result["depth"], 3);
+
+        let result = server.dispatch("bestmove", json!({"moves": ["e2e4"], "depth": 2})).unwrap();
+        assert!(result["move"].is_string() && result["ponder"].is_string(), "{}", result);
+
+        let result = server.dispatch("legal-moves", json!({"moves": ["f2f3", "e7e5", "g2g4", "d8h4"]})).unwrap();
+        assert_eq!(result["status"], "checkmate");
+        assert_eq!(result["moves"], json!([]));
+        let result = server.dispatch("legal-moves", Value::Null).unwrap();
+        assert_eq!(result["san"].as_array().unwrap().len(), 20);
+
+        // No legal move: nothing to play
+        let result = server.dispatch("bestmove", json!({"moves": ["f2f3", "e7e5", "g2g4", "d8h4"], "depth": 2}));
+        assert_eq!(result.unwrap()["move"], Value::Null);
+    }
+
+    #[test]
+    fn test_finished_games() {
+        let server = server();
+        let mated = json!({"moves": ["f2f3", "e7e5", "g2g4", "d8h4"], "depth": 2});
+        let result = server.dispatch("bestmove", mated.clone()).unwrap();
+        assert_eq!((&result["move"], &result["depth"], &result["nodes"]), (&Value::Null, &json!(0), &json!(0)));
+        assert_eq!(result["score"], -MATE_SCORE);
+        let result = server.dispatch("analyse", mated).unwrap();
+        assert_eq!((&result["lines"], &result["depth"]), (&json!([]), &json!(0)));
+
+        let stalemate = json!({"fen": "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"});
+        let result = server.dispatch("bestmove", stalemate).unwrap();
+        assert_eq!((&result["move"], &result["depth"], &result["score"]), (&Value::Null, &json!(0), &json!(0)));
+
+        // Won on the third check before any checkmate
+        let third_check = json!({"fen": "4k3/8/8/8/8/8/8/4K2R b K - 0 1 +3+0", "variant": "three-check"});
+        let result = server.dispatch("bestmove", third_check).unwrap();
+        assert_eq!((&result["move"], &result["depth"]), (&Value::Null, &json!(0)));
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let limits = |params: Value| search_limits(&serde_json::from_value(params).unwrap());
+        assert_eq!(limits(json!({})), (0, DEFAULT_MOVETIME_MS));
+        assert_eq!(limits(json!({"depth": 0})), (0, DEFAULT_MOVETIME_MS));
+        assert_eq!(limits(json!({"depth": 6})), (6, f64::INFINITY));
+        assert_eq!(limits(json!({"depth": 0, "movetime": 250.0})), (0, 250.0));
+        assert_eq!(limits(json!({"depth": 6, "movetime": 0.0})), (6, 1.0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = server();
+        let err = server.dispatch("analyse", json!({"moves": ["e2e5"]})).unwrap_err();
+        assert_eq!(err, RequestError::InvalidParams("Illegal move: e2e5".to_string()));
+        let err = server.dispatch("analyse", json!({"variant": "atomic"})).unwrap_err();
+        assert_eq!(err.message(), "Unknown variant: atomic");
+        assert!(server.dispatch("bestmove", json!({"fen": "8/8 w"})).unwrap_err().message().starts_with("Invalid FEN"));
+        assert!(matches!(server.dispatch("bestmove", json!({"dpeth": 3})), Err(RequestError::InvalidParams(_))));
+        assert_eq!(server.dispatch("perft", Value::Null).unwrap_err().code(), -32601);
+    }
+
+    #[test]
+    fn test_rpc() {
+        let server = server();
+        let reply = server.rpc(r#"{"jsonrpc":"2.0","id":7,"method":"legal-moves","params":{"variant":"three-check"}}"#);
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["result"]["moves"].as_array().unwrap().len(), 20);
+
+        let reply = server.rpc(r#"{"jsonrpc":"2.0","id":"a","method":"nope"}"#);
+        assert_eq!((reply["id"].clone(), reply["error"]["code"].clone()), (json!("a"), json!(-32601)));
+        assert_eq!(server.rpc("{not json")["error"]["code"], -32700);
+        assert_eq!(server.rpc(r#"{"id":1}"#)["error"]["code"], -32600);
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /analyse?x=1 HTTP/1.1\r\nHost: x\r\ncontent-length: 11\r\n\r\n{\"depth\":1}";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(
+            request,
+            Request { method: "POST".to_string(), path: "/analyse".to_string(), body: "{\"depth\":1}".to_string() }
+        );
+        assert!(read_request(&mut "\r\n".as_bytes()).is_err());
+        assert!(read_request(&mut "POST / HTTP/1.1\r\nContent-Length: 9999999\r\n\r\n".as_bytes()).is_err());
+
+        let server = server();
+        let get = |method: &str, path: &str, body: &str| {
+            respond(&server, &Request { method: method.to_string(), path: path.to_string(), body: body.to_string() })
+        };
+        assert_eq!(get("GET", "/health", "").0, 200);
+        assert_eq!(get("GET", "/bestmove", "").0, 405);
+        assert_eq!(get("GET", "/nowhere", "").0, 404);
+        assert_eq!(get("POST", "/perft", "").0, 404);
+        assert_eq!(get("POST", "/analyse", "{").0, 400);
+        assert_eq!(get("POST", "/legal-moves", "").1["moves"].as_array().unwrap().len(), 20);
+    }
+
+    #[test]
+    fn test_concurrent_connections() {
+        let server = Arc::new(server());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accept = {
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || {
+                std::thread::scope(|scope| {
+                    for stream in listener.incoming().take(3) {
+                        let (server, stream) = (&server, stream.unwrap());
+                        scope.spawn(move || handle_connection(server, stream).unwrap());
+                    }
+                })
+            })
+        };
+        let clients: Vec<_> = (0..3)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let mut stream = TcpStream::connect(addr).unwrap();
+                    let body = r#"{"depth":3}"#;
+                    write!(stream, "POST /bestmove HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response
+                })
+            })
+            .collect();
+        for client in clients {
+            let response = client.join().unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+            let body: Value = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+            assert_eq!(body["depth"], 3);
+        }
+        accept.join().unwrap();
+    }
+}
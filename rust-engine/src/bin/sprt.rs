@@ -0,0 +1,484 @@
+// =============================================================================
+// SPRT Match Runner
+// =============================================================================
+// Plays two engine configurations against each other to decide whether a
+// change gains strength. Every opening is played twice, once with each
+// configuration as white, and the pair's score feeds a sequential probability
+// ratio test (see `chess_engine::elo`): the match stops as soon as the test
+// accepts H0 (no gain of elo0) or H1 (a gain of elo1), or after --max-pairs.
+//
+// A configuration is a comma-separated key=value list: personality, contempt,
+// depth, nodes, movetime. Keys left out take the command-line defaults.
+//
+// Usage:
+//   cargo run --release --bin sprt -- --test personality=aggressive --base personality=balanced
+//   cargo run --release --bin sprt -- --test contempt=20 --base contempt=0 --nodes 50000 --jobs 8
+//   cargo run --release --bin sprt -- --test depth=7 --base depth=6 --openings book.epd --elo1 10
+// =============================================================================
+
+use chess_engine::elo::{Pentanomial, Sprt, SprtVerdict};
+use chess_engine::epd::parse_epd;
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::personality::Personality;
+use chess_engine::position::Position;
+use chess_engine::search::{search_with_control, search_with_options, SearchControl, SearchOptions};
+use chess_engine::tt::TranspositionTable;
+use chess_engine::types::Color;
+
+use clap::Parser;
+use rand::prelude::*;
+use rayon::prelude::*;
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "sprt", about = "Decide between two engine configurations with an SPRT")]
+struct Args {
+    /// Configuration under test, e.g. "personality=aggressive,contempt=20"
+    #[arg(long, default_value = "")]
+    test: String,
+
+    /// Configuration it is measured against
+    #[arg(long, default_value = "")]
+    base: String,
+
+    /// Elo gain of H0 (the change doesn't help)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    elo0: f64,
+
+    /// Elo gain of H1 (the change helps)
+    #[arg(long, default_value_t = 5.0, allow_negative_numbers = true)]
+    elo1: f64,
+
+    /// False positive rate
+    #[arg(long, default_value_t = 0.05)]
+    alpha: f64,
+
+    /// False negative rate
+    #[arg(long, default_value_t = 0.05)]
+    beta: f64,
+
+    /// Stop without a verdict after this many game pairs
+    #[arg(long, default_value_t = 20000)]
+    max_pairs: u64,
+
+    /// Nodes per move, for configurations without a limit of their own
+    #[arg(long, default_value_t = 20000)]
+    nodes: u64,
+
+    /// Depth per move, for configurations without a limit of their own
+    #[arg(long)]
+    depth: Option<u8>,
+
+    /// Time per move in ms, for configurations without a limit of their own
+    #[arg(long)]
+    movetime: Option<f64>,
+
+    /// FEN or EPD openings, one per line (default: random openings)
+    #[arg(long)]
+    openings: Option<String>,
+
+    /// Random plies from the start position in a generated opening
+    #[arg(long, default_value_t = 8)]
+    random_plies: u32,
+
+    /// Largest imbalance of a generated opening, in centipawns
+    #[arg(long, default_value_t = 150)]
+    max_opening_cp: i32,
+
+    /// Moves per game before it is scored a draw
+    #[arg(long, default_value_t = 150)]
+    max_moves: u32,
+
+    /// Game pairs played at once
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Transposition table size per side and game, as a power of two entries
+    #[arg(long, default_value_t = 18)]
+    hash_power: u8,
+
+    /// Seed of the generated openings
+    #[arg(short, long, default_value_t = 1)]
+    seed: u64,
+}
+
+// =============================================================================
+// CONFIGURATIONS
+// =============================================================================
+
+/// One side of the match: a playing style and how long it searches per move
+#[derive(Clone, Debug, PartialEq)]
+struct Config {
+    personality: Personality,
+    /// Overrides the personality's contempt
+    contempt: Option<i32>,
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    movetime: Option<f64>,
+}
+
+impl Config {
+    /// Parse a "key=value,..." list; search limits missing from it come from
+    /// `defaults`
+    fn parse(spec: &str, defaults: &Config) -> Result<Config, String> {
+        let mut config = Config { personality: defaults.personality, contempt: defaults.contempt, ..Config::unlimited() };
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, value) = entry.split_once('=').ok_or_else(|| format!("Expected key=value: {}", entry))?;
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+            let invalid = || format!("Invalid value for {}: {}", key, value);
+            match key.as_str() {
+                "personality" => config.personality = Personality::from_name(value).ok_or_else(invalid)?,
+                "contempt" => config.contempt = Some(value.parse().map_err(|_| invalid())?),
+                "depth" => config.depth = Some(value.parse().ok().filter(|&d| d > 0).ok_or_else(invalid)?),
+                "nodes" => config.nodes = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?),
+                "movetime" => config.movetime = Some(value.parse().ok().filter(|&ms| ms > 0.0).ok_or_else(invalid)?),
+                _ => return Err(format!("Unknown key: {}", key)),
+            }
+        }
+        if config.depth.is_none() && config.nodes.is_none() && config.movetime.is_none() {
+            (config.depth, config.nodes, config.movetime) = (defaults.depth, defaults.nodes, defaults.movetime);
+        }
+        Ok(config)
+    }
+
+    fn unlimited() -> Config {
+        Config { personality: Personality::Balanced, contempt: None, depth: None, nodes: None, movetime: None }
+    }
+
+    fn search_options(&self) -> SearchOptions<'static> {
+        let options = self.personality.search_options();
+        SearchOptions { contempt: self.contempt.unwrap_or(options.contempt), ..options }
+    }
+
+    /// Short description, as printed in the header
+    fn describe(&self) -> String {
+        let mut parts = vec![self.personality.name().to_string()];
+        if let Some(contempt) = self.contempt {
+            parts.push(format!("contempt {}", contempt));
+        }
+        if let Some(depth) = self.depth {
+            parts.push(format!("depth {}", depth));
+        }
+        if let Some(nodes) = self.nodes {
+            parts.push(format!("{} nodes", nodes));
+        }
+        if let Some(ms) = self.movetime {
+            parts.push(format!("{} ms", ms));
+        }
+        parts.join(", ")
+    }
+}
+
+// =============================================================================
+// OPENINGS
+// =============================================================================
+
+/// Openings of a file: FEN or EPD lines, blank lines and # comments skipped
+fn load_openings(text: &str) -> Result<Vec<Position>, String> {
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fen = match Position::from_fen_strict(line) {
+            Ok(_) => line.to_string(),
+            Err(_) => parse_epd(line).map_err(|e| format!("line {}: {}", i, e))?.fen(),
+        };
+        let pos = Position::from_fen_strict(&fen).map_err(|e| format!("line {}: {}", i, e))?;
+        if generate_legal_moves(&pos).is_empty() {
+            return Err(format!("line {}: game already over", i));
+        }
+        openings.push(pos);
+    }
+    if openings.is_empty() {
+        return Err("No openings".to_string());
+    }
+    Ok(openings)
+}
+
+/// `plies` random moves from the start position, retried until a short
+/// search finds the result within `max_cp` of equal
+fn random_opening(seed: u64, plies: u32, max_cp: i32) -> Position {
+    let mut rng = StdRng::seed_from_u64(seed);
+    loop {
+        let mut pos = Position::starting_position();
+        let complete = (0..plies).all(|_| {
+            let Some(m) = generate_legal_moves(&pos).iter().copied().choose(&mut rng) else { return false };
+            pos.make_move(m);
+            true
+        });
+        if !complete || generate_legal_moves(&pos).is_empty() {
+            continue;
+        }
+        let (_, score, _) = search_with_options(&mut pos.clone(), 4, &SearchOptions::default());
+        if score.abs() <= max_cp {
+            return pos;
+        }
+    }
+}
+
+// =============================================================================
+// GAMES
+// =============================================================================
+
+/// How a game ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct GameOutcome {
+    /// None for a draw
+    winner: Option<Color>,
+    termination: &'static str,
+    plies: u32,
+}
+
+/// The result of `pos` reached after the positions hashed in `history`, if
+/// the game is over
+fn game_over(pos: &Position, history: &[u64]) -> Option<(Option<Color>, &'static str)> {
+    if let Some((winner, status)) = pos.variant().outcome(pos) {
+        return Some((Some(winner), status));
+    }
+    if pos.is_checkmate() {
+        return Some((Some(pos.side_to_move().flip()), "checkmate"));
+    }
+    if pos.is_stalemate() {
+        return Some((None, "stalemate"));
+    }
+    if pos.is_insufficient_material() || pos.is_draw() {
+        let status = if pos.is_fifty_move_draw() { "fifty-move" } else { "insufficient-material" };
+        return Some((None, status));
+    }
+    if history.iter().filter(|&&h| h == pos.hash()).count() >= 3 {
+        return Some((None, "threefold"));
+    }
+    None
+}
+
+/// Play `opening` out between `white` and `black`, each with its own table
+fn play_game(opening: &Position, white: &Config, black: &Config, args: &Args) -> GameOutcome {
+    let mut pos = opening.clone();
+    let mut history = vec![pos.hash()];
+    let mut tables = [TranspositionTable::new(args.hash_power), TranspositionTable::new(args.hash_power)];
+    let mut plies = 0;
+    loop {
+        if let Some((winner, termination)) = game_over(&pos, &history) {
+            return GameOutcome { winner, termination, plies };
+        }
+        if plies >= args.max_moves * 2 {
+            return GameOutcome { winner: None, termination: "max-moves", plies };
+        }
+        let side = pos.side_to_move();
+        let config = if side == Color::White { white } else { black };
+        let control = SearchControl::new(config.movetime.unwrap_or(f64::INFINITY));
+        if let Some(nodes) = config.nodes {
+            control.set_node_limit(nodes);
+        }
+        let tt = &mut tables[side as usize];
+        let depth = config.depth.unwrap_or(0);
+        let (best, _, _) = search_with_control(&mut pos.clone(), depth, tt, &control, &config.search_options());
+        let Some(best) = best else {
+            return GameOutcome { winner: None, termination: "no-move", plies };
+        };
+        pos.make_move(best);
+        history.push(pos.hash());
+        plies += 1;
+    }
+}
+
+/// Half-points `side` scored in a game
+fn half_points(outcome: &GameOutcome, side: Color) -> usize {
+    match outcome.winner {
+        Some(winner) if winner == side => 2,
+        Some(_) => 0,
+        None => 1,
+    }
+}
+
+/// Play both colors of `opening`: the test configuration's half-points over
+/// the pair, and the two games
+fn play_pair(opening: &Position, test: &Config, base: &Config, args: &Args) -> (usize, [GameOutcome; 2]) {
+    let first = play_game(opening, test, base, args);
+    let second = play_game(opening, base, test, args);
+    (half_points(&first, Color::White) + half_points(&second, Color::Black), [first, second])
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+/// Wins, draws and losses of the test configuration
+#[derive(Debug, Default, PartialEq)]
+struct Tally {
+    wins: u64,
+    draws: u64,
+    losses: u64,
+}
+
+impl Tally {
+    fn add(&mut self, half_points: usize) {
+        match half_points {
+            2 => self.wins += 1,
+            1 => self.draws += 1,
+            _ => self.losses += 1,
+        }
+    }
+}
+
+/// One progress line: games, W/D/L, Elo and the LLR against its bounds
+fn status_line(pairs: &Pentanomial, tally: &Tally, sprt: &Sprt) -> String {
+    let (elo, margin) = pairs.elo();
+    let (lower, upper) = sprt.bounds();
+    format!(
+        "Games {:>6}  W {} D {} L {}  Elo {:+.1} ± {:.1}  LLR {:+.2} ({:.2}, {:.2})  Pairs {:?}",
+        pairs.pairs() * 2,
+        tally.wins,
+        tally.draws,
+        tally.losses,
+        elo,
+        margin,
+        sprt.llr(pairs),
+        lower,
+        upper,
+        pairs.counts
+    )
+}
+
+fn main() {
+    let args = Args::parse();
+    let defaults = Config {
+        nodes: (args.depth.is_none() && args.movetime.is_none()).then_some(args.nodes),
+        depth: args.depth,
+        movetime: args.movetime,
+        ..Config::unlimited()
+    };
+    let (test, base) = match (Config::parse(&args.test, &defaults), Config::parse(&args.base, &defaults)) {
+        (Ok(test), Ok(base)) => (test, base),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let openings = match &args.openings {
+        Some(path) => match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|t| load_openings(&t)) {
+            Ok(openings) => Some(openings),
+            Err(e) => {
+                eprintln!("Cannot load {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let sprt = Sprt { elo0: args.elo0, elo1: args.elo1, alpha: args.alpha, beta: args.beta };
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.jobs.max(1)).build().expect("thread pool");
+
+    println!("Test: {}", test.describe());
+    println!("Base: {}", base.describe());
+    println!("SPRT: elo0 {} elo1 {} alpha {} beta {}", sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta);
+
+    let mut pairs = Pentanomial::default();
+    let mut tally = Tally::default();
+    let mut verdict = SprtVerdict::Continue;
+    while verdict == SprtVerdict::Continue && pairs.pairs() < args.max_pairs {
+        let start = pairs.pairs();
+        let batch = (args.jobs.max(1) as u64).min(args.max_pairs - start);
+        let results: Vec<_> = pool.install(|| {
+            (start..start + batch)
+                .into_par_iter()
+                .map(|i| {
+                    let opening = match &openings {
+                        Some(openings) => openings[i as usize % openings.len()].clone(),
+                        None => random_opening(args.seed.wrapping_add(i), args.random_plies, args.max_opening_cp),
+                    };
+                    play_pair(&opening, &test, &base, &args)
+                })
+                .collect()
+        });
+        for (score, games) in results {
+            pairs.add(score);
+            tally.add(half_points(&games[0], Color::White));
+            tally.add(half_points(&games[1], Color::Black));
+        }
+        verdict = sprt.verdict(&pairs);
+        println!("{}", status_line(&pairs, &tally, &sprt));
+    }
+
+    println!();
+    match verdict {
+        SprtVerdict::AcceptH1 => println!("H1 accepted: the test configuration gains at least {} Elo", sprt.elo1),
+        SprtVerdict::AcceptH0 => println!("H0 accepted: the test configuration gains no more than {} Elo", sprt.elo0),
+        SprtVerdict::Continue => println!("No verdict after {} pairs", pairs.pairs()),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> Args {
+        Args::parse_from(["sprt", "--hash-power", "12", "--max-moves", "20"])
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let defaults = Config { nodes: Some(1000), ..Config::unlimited() };
+        let config = Config::parse("personality=Aggressive, contempt=-10", &defaults).unwrap();
+        assert_eq!(config.personality, Personality::Aggressive);
+        assert_eq!(config.search_options().contempt, -10);
+        assert_eq!((config.depth, config.nodes), (None, Some(1000)));
+
+        // A limit of its own replaces the default ones
+        let config = Config::parse("depth=3", &defaults).unwrap();
+        assert_eq!((config.depth, config.nodes), (Some(3), None));
+        assert_eq!(config.describe(), "balanced, depth 3");
+        assert_eq!(Config::parse("", &defaults).unwrap(), defaults);
+
+        assert_eq!(Config::parse("depth=0", &defaults).unwrap_err(), "Invalid value for depth: 0");
+        assert_eq!(Config::parse("style=sharp", &defaults).unwrap_err(), "Unknown key: style");
+        assert_eq!(Config::parse("depth", &defaults).unwrap_err(), "Expected key=value: depth");
+    }
+
+    #[test]
+    fn test_openings() {
+        let text = "# openings\nrnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\n\n\
+                    rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - id \"sicilian\";\n";
+        let openings = load_openings(text).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[1].side_to_move(), Color::White);
+        assert!(load_openings("# nothing\n").is_err());
+        assert!(load_openings("7k/5QQ1/8/8/8/8/8/K7 b - -").map(|o| o.len()).unwrap_err().contains("game already over"));
+
+        let opening = random_opening(7, 6, 150);
+        assert_eq!(opening.hash(), random_opening(7, 6, 150).hash());
+        assert_eq!(opening.fullmove_number(), 4);
+    }
+
+    #[test]
+    fn test_games() {
+        let args = args();
+        let quick = Config { depth: Some(2), ..Config::unlimited() };
+        let mated = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let outcome = play_game(&mated, &quick, &quick, &args);
+        assert_eq!(outcome, GameOutcome { winner: Some(Color::White), termination: "checkmate", plies: 1 });
+        assert_eq!((half_points(&outcome, Color::White), half_points(&outcome, Color::Black)), (2, 0));
+
+        // Each side wins the mate in one with white: an even pair
+        let (score, games) = play_pair(&mated, &quick, &Config { depth: Some(3), ..quick.clone() }, &args);
+        assert_eq!(score, 2);
+        assert_eq!(games.map(|g| g.winner), [Some(Color::White); 2]);
+
+        let bare = Position::from_fen("8/8/4k3/8/8/4K3/8/8 w - - 0 1").unwrap();
+        assert_eq!(play_game(&bare, &quick, &quick, &args).termination, "insufficient-material");
+        let start = play_game(&Position::starting_position(), &quick, &quick, &args);
+        assert!(start.plies <= 40, "{:?}", start);
+
+        let pairs = Pentanomial { counts: [0, 1, 2, 1, 0] };
+        let tally = Tally { wins: 2, draws: 4, losses: 2 };
+        let line = status_line(&pairs, &tally, &Sprt::default());
+        assert!(line.starts_with("Games      8  W 2 D 4 L 2  Elo +0.0"), "{}", line);
+    }
+}
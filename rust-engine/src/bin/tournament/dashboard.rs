@@ -0,0 +1,258 @@
+// =============================================================================
+// Tournament Dashboard
+// =============================================================================
+// Progress of a running tournament served over HTTP (--dashboard).
+// =============================================================================
+
+use indicatif::ProgressBar;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::{head_to_head, leaderboard, mean_interval, AiPersona, Args, Group, RatingSystem};
+
+// =============================================================================
+// DASHBOARD
+// =============================================================================
+
+/// Players in the dashboard's standings
+const DASHBOARD_LEADERS: usize = 20;
+
+/// The page at `/`, showing `/status` every few seconds
+const DASHBOARD_HTML: &str = r##"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Tournament</title>
+<style>
+body { font: 14px monospace; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+td, th { padding: 2px 10px; text-align: right; }
+th { border-bottom: 1px solid #888; }
+td:nth-child(2) { text-align: left; }
+</style>
+</head>
+<body>
+<h2 id="title">Tournament</h2>
+<p id="progress"></p>
+<table id="ab"></table>
+<table id="standings"></table>
+<script>
+const row = (cells, tag = "td") => "<tr>" + cells.map(c => `<${tag}>${c}</${tag}>`).join("") + "</tr>";
+const fixed = (x, digits) => x == null ? "-" : x.toFixed(digits);
+async function refresh() {
+  const s = await (await fetch("status")).json();
+  document.getElementById("title").textContent =
+    `${s.format}: round ${s.completed_rounds}/${s.rounds}` + (s.finished ? " (finished)" : "");
+  const r = s.current_round;
+  const round = r ? ` | round ${r.round}: ${r.played}/${r.games}` : "";
+  document.getElementById("progress").textContent =
+    `${s.games_played} games, ${fixed(s.games_per_sec, 1)} games/s, ${fixed(s.elapsed_s, 0)}s` + round;
+  const ab = s.ab, h = ab.head_to_head;
+  document.getElementById("ab").innerHTML = row(["", "Control (A)", "Rewards (B)"], "th") +
+    row(["Avg ELO", `${fixed(ab.control.mean_elo, 1)} ± ${fixed(ab.control.margin, 1)}`,
+         `${fixed(ab.rewards.mean_elo, 1)} ± ${fixed(ab.rewards.margin, 1)}`]) +
+    row(["Win rate", fixed(ab.control.win_rate * 100, 1) + "%", fixed(ab.rewards.win_rate * 100, 1) + "%"]) +
+    row(["B vs A", `${h.wins}/${h.draws}/${h.losses}`,
+         `Elo ${fixed(h.elo, 1)} ± ${fixed(h.margin, 1)}, LOS ${fixed(h.los * 100, 1)}%`]);
+  document.getElementById("standings").innerHTML = row(["#", "Name", "Rating", "Pts", "W / D / L"], "th") +
+    s.standings.map(p => row([p.rank, `${p.name} [${p.group == "rewards" ? "B" : "A"}]`,
+      fixed(p.rating, 0) + (p.deviation == null ? "" : ` ±${fixed(p.deviation, 0)}`), fixed(p.points, 1),
+      `${p.wins}/${p.draws}/${p.losses}`])).join("");
+}
+refresh();
+setInterval(refresh, 2000);
+</script>
+</body>
+</html>
+"##;
+
+/// Live state of the tournament for the dashboard
+pub(crate) struct Dashboard {
+    started: Instant,
+    /// Standings and A/B metrics as of the last finished round
+    standings: Mutex<Value>,
+    /// Games of the finished rounds
+    games_played: AtomicU64,
+    /// The round being played, and its progress
+    round: Mutex<Option<(u32, ProgressBar)>>,
+}
+
+impl Dashboard {
+    pub(crate) fn new() -> Dashboard {
+        Dashboard {
+            started: Instant::now(),
+            standings: Mutex::new(Value::Null),
+            games_played: AtomicU64::new(0),
+            round: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn start_round(&self, round: u32, pb: &ProgressBar) {
+        *self.round.lock().unwrap() = Some((round, pb.clone()));
+    }
+
+    /// Take in a finished round (or the standings before the first)
+    pub(crate) fn finish_round(&self, standings: Value, games: u64) {
+        *self.standings.lock().unwrap() = standings;
+        self.games_played.fetch_add(games, Ordering::Relaxed);
+        *self.round.lock().unwrap() = None;
+    }
+
+    fn status(&self) -> Value {
+        let mut status = self.standings.lock().unwrap().clone();
+        let mut played = self.games_played.load(Ordering::Relaxed);
+        let current = self.round.lock().unwrap().as_ref().map(|(round, pb)| {
+            played += pb.position();
+            json!({ "round": round, "played": pb.position(), "games": pb.length() })
+        });
+        let elapsed = self.started.elapsed().as_secs_f64();
+        status["elapsed_s"] = json!(elapsed);
+        status["games_played"] = json!(played);
+        status["games_per_sec"] = json!(played as f64 / elapsed.max(1e-3));
+        status["current_round"] = current.unwrap_or(Value::Null);
+        status
+    }
+}
+
+/// Standings and A/B metrics after `completed` of `rounds` rounds
+pub(crate) fn tournament_status(args: &Args, players: &[AiPersona], completed: u32, rounds: u32) -> Value {
+    let glicko = args.rating_system == RatingSystem::Glicko2;
+    let standings: Vec<Value> = leaderboard(players)
+        .iter()
+        .take(DASHBOARD_LEADERS)
+        .enumerate()
+        .map(|(i, p)| {
+            json!({
+                "rank": i + 1, "id": p.id, "name": p.name, "group": p.group.name(),
+                "rating": p.elo, "deviation": glicko.then_some(p.rating_deviation), "points": p.points,
+                "wins": p.wins, "draws": p.draws, "losses": p.losses,
+                "depth": p.search_depth, "personality": p.personality.name(), "strategy": p.strategy.name(),
+            })
+        })
+        .collect();
+    let group = |group: Group| {
+        let members: Vec<&AiPersona> = players.iter().filter(|p| p.group == group).collect();
+        let (mean_elo, margin) = mean_interval(&members.iter().map(|p| p.elo).collect::<Vec<_>>());
+        let (wins, games) =
+            members.iter().fold((0u32, 0u32), |(w, g), p| (w + p.wins, g + p.wins + p.losses + p.draws));
+        json!({
+            "players": members.len(), "mean_elo": mean_elo, "margin": margin,
+            "win_rate": if games > 0 { wins as f64 / games as f64 } else { 0.0 },
+        })
+    };
+    let games = head_to_head(players, Group::Rewards);
+    let (elo, margin) = games.elo();
+    json!({
+        "format": args.format.name(),
+        "variant": args.variant.name(),
+        "rating_system": args.rating_system.name(),
+        "players": players.len(),
+        "rounds": rounds,
+        "completed_rounds": completed,
+        "finished": completed == rounds,
+        "standings": standings,
+        "ab": {
+            "control": group(Group::Control),
+            "rewards": group(Group::Rewards),
+            "head_to_head": {
+                "wins": games.wins, "draws": games.draws, "losses": games.losses,
+                "score": games.score(), "elo": elo, "margin": margin, "los": games.los(),
+            },
+        },
+    })
+}
+
+/// Serve the dashboard on `addr`: the page at `/`, JSON at `/status`
+pub(crate) fn serve_dashboard(addr: &str, dashboard: Arc<Dashboard>) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client going away mid-request is its own business
+            let _ = answer_dashboard_request(&dashboard, stream);
+        }
+    });
+    Ok(local)
+}
+
+fn answer_dashboard_request(dashboard: &Dashboard, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers tell us nothing we need
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let (status, content_type, body) = match (method, path.split('?').next().unwrap_or_default()) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", DASHBOARD_HTML.to_string()),
+        ("GET", "/status") => ("200 OK", "application/json", dashboard.status().to_string()),
+        ("GET", _) => ("404 Not Found", "application/json", json!({ "error": "Not found" }).to_string()),
+        _ => ("405 Method Not Allowed", "application/json", json!({ "error": "Method not allowed" }).to_string()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{apply_round, generate_personas, GameResult};
+    use crate::tests::game;
+    use clap::Parser;
+
+    #[test]
+    fn test_dashboard() {
+        let args = Args::parse_from(["tournament", "--players", "6", "--rating-system", "glicko2"]);
+        let mut players = generate_personas(6, 4);
+        let records = [game(1, 0, 1, GameResult::WhiteWins), game(1, 2, 3, GameResult::Draw)];
+        apply_round(&mut players, &records, args.rating_system);
+        let dashboard = Arc::new(Dashboard::new());
+        dashboard.finish_round(tournament_status(&args, &players, 1, 3), 2);
+        let pb = ProgressBar::hidden();
+        pb.set_length(3);
+        pb.inc(1);
+        dashboard.start_round(2, &pb);
+
+        let addr = serve_dashboard("127.0.0.1:0", dashboard).unwrap();
+        let get = |request: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "{}\r\nHost: test\r\n\r\n", request).unwrap();
+            let mut response = String::new();
+            std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+            response
+        };
+        let response = get("GET /status HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let status: Value = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!((status["completed_rounds"].as_u64(), status["finished"].as_bool()), (Some(1), Some(false)));
+        assert_eq!(status["games_played"], 3);
+        assert_eq!(status["current_round"], json!({ "round": 2, "played": 1, "games": 3 }));
+        assert_eq!(status["standings"][0]["id"], 0);
+        assert!(status["standings"][0]["deviation"].is_f64());
+        let group_size = |group: &str| status["ab"][group]["players"].as_u64().unwrap();
+        assert_eq!(group_size("control") + group_size("rewards"), 6);
+        assert!(get("GET / HTTP/1.1").contains("<title>Tournament</title>"));
+        assert!(get("GET /nope HTTP/1.1").starts_with("HTTP/1.1 404"));
+        assert!(get("POST /status HTTP/1.1").starts_with("HTTP/1.1 405"));
+    }
+}
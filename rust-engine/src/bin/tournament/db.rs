@@ -0,0 +1,922 @@
+// =============================================================================
+// Tournament Database
+// =============================================================================
+// The SQLite results database: schema, saving rounds and their checkpoints on
+// a writer thread, the settings and standings a resumed run rebuilds, and the
+// stored games read back for PGN export.
+// =============================================================================
+
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::personality::Personality;
+use chess_engine::pgn::PgnGame;
+use chess_engine::position::Position;
+use chess_engine::variant::Variant;
+
+use clap::ValueEnum;
+use rusqlite::{params, Connection};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+use crate::formats::Bracket;
+use crate::{
+    apply_round, find_move_by_uci, parse_variant, AiPersona, Args, Format, GameRecord, GameResult, Group, OpeningStyle,
+    RatingSystem, SearchBudget, Strategy, TimeControl,
+};
+
+// =============================================================================
+// DATABASE
+// =============================================================================
+
+pub(crate) fn init_database(path: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+
+    conn.execute_batch(
+        "
+        PRAGMA journal_mode = WAL;
+        PRAGMA synchronous = NORMAL;
+        PRAGMA cache_size = -64000;
+
+        CREATE TABLE IF NOT EXISTS players (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            initial_elo REAL NOT NULL,
+            final_elo REAL NOT NULL,
+            search_depth INTEGER NOT NULL,
+            aggression REAL NOT NULL,
+            personality TEXT NOT NULL DEFAULT 'balanced',
+            opening_style TEXT NOT NULL,
+            blunder_rate REAL NOT NULL,
+            test_group TEXT NOT NULL,
+            wins INTEGER NOT NULL DEFAULT 0,
+            losses INTEGER NOT NULL DEFAULT 0,
+            draws INTEGER NOT NULL DEFAULT 0,
+            total_moves_played INTEGER NOT NULL DEFAULT 0,
+            avg_game_length REAL NOT NULL DEFAULT 0,
+            games_as_white INTEGER NOT NULL DEFAULT 0,
+            games_as_black INTEGER NOT NULL DEFAULT 0,
+            blunders_made INTEGER NOT NULL DEFAULT 0,
+            points REAL NOT NULL DEFAULT 0,
+            search_budget TEXT NOT NULL DEFAULT 'depth',
+            time_used_ms INTEGER NOT NULL DEFAULT 0,
+            time_forfeits INTEGER NOT NULL DEFAULT 0,
+            rating_deviation REAL,
+            volatility REAL,
+            avg_cp_loss REAL NOT NULL DEFAULT 0,
+            strategy TEXT NOT NULL DEFAULT 'noisy'
+        );
+
+        CREATE TABLE IF NOT EXISTS games (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            round INTEGER NOT NULL,
+            white_id INTEGER NOT NULL,
+            black_id INTEGER NOT NULL,
+            result TEXT NOT NULL,
+            moves INTEGER NOT NULL,
+            opening_uci TEXT,
+            moves_uci TEXT,
+            termination TEXT NOT NULL,
+            white_blunders INTEGER NOT NULL DEFAULT 0,
+            black_blunders INTEGER NOT NULL DEFAULT 0,
+            white_cp_loss INTEGER NOT NULL DEFAULT 0,
+            black_cp_loss INTEGER NOT NULL DEFAULT 0,
+            white_time_ms INTEGER NOT NULL DEFAULT 0,
+            black_time_ms INTEGER NOT NULL DEFAULT 0,
+            start_fen TEXT,
+            FOREIGN KEY (white_id) REFERENCES players(id),
+            FOREIGN KEY (black_id) REFERENCES players(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS rounds (
+            round_num INTEGER PRIMARY KEY,
+            total_games INTEGER NOT NULL,
+            bye_id INTEGER,
+            avg_elo_change REAL NOT NULL,
+            duration_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS checkpoint (
+            round INTEGER NOT NULL,
+            game INTEGER NOT NULL,
+            record TEXT NOT NULL,
+            PRIMARY KEY (round, game)
+        );
+
+        CREATE TABLE IF NOT EXISTS tournament_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_games_round ON games(round);
+        CREATE INDEX IF NOT EXISTS idx_games_white ON games(white_id);
+        CREATE INDEX IF NOT EXISTS idx_games_black ON games(black_id);
+        CREATE INDEX IF NOT EXISTS idx_players_group ON players(test_group);
+        CREATE INDEX IF NOT EXISTS idx_players_elo ON players(final_elo);
+    ",
+    )?;
+
+    // Columns databases from earlier versions lack
+    let added = [
+        ("games", "moves_uci", "TEXT"),
+        ("games", "white_time_ms", "INTEGER NOT NULL DEFAULT 0"),
+        ("games", "black_time_ms", "INTEGER NOT NULL DEFAULT 0"),
+        ("games", "start_fen", "TEXT"),
+        ("players", "search_budget", "TEXT NOT NULL DEFAULT 'depth'"),
+        ("players", "time_used_ms", "INTEGER NOT NULL DEFAULT 0"),
+        ("players", "time_forfeits", "INTEGER NOT NULL DEFAULT 0"),
+        ("players", "rating_deviation", "REAL"),
+        ("players", "volatility", "REAL"),
+        ("players", "avg_cp_loss", "REAL NOT NULL DEFAULT 0"),
+        ("players", "strategy", "TEXT NOT NULL DEFAULT 'noisy'"),
+        ("games", "white_cp_loss", "INTEGER NOT NULL DEFAULT 0"),
+        ("games", "black_cp_loss", "INTEGER NOT NULL DEFAULT 0"),
+    ];
+    for (table, column, definition) in added {
+        if conn.prepare(&format!("SELECT {} FROM {} LIMIT 0", column, table)).is_err() {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
+    }
+
+    Ok(conn)
+}
+
+pub(crate) fn save_players(conn: &Connection, players: &[AiPersona]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR REPLACE INTO players
+        (id, name, initial_elo, final_elo, search_depth, aggression, personality,
+         opening_style, blunder_rate, test_group, wins, losses, draws,
+         total_moves_played, avg_game_length, games_as_white, games_as_black,
+         blunders_made, points, search_budget, time_used_ms, time_forfeits,
+         rating_deviation, volatility, avg_cp_loss, strategy)
+        VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18,?19,?20,?21,?22,?23,?24,?25,?26)",
+    )?;
+
+    for p in players {
+        let games_total = p.wins + p.losses + p.draws;
+        let avg_len = if games_total > 0 {
+            p.total_game_length as f64 / games_total as f64
+        } else {
+            0.0
+        };
+        stmt.execute(params![
+            p.id, p.name, p.initial_elo, p.elo, p.search_depth, p.aggression, p.personality.name(),
+            p.opening_style.name(), p.blunder_rate, p.group.name(),
+            p.wins, p.losses, p.draws, p.total_moves_played, avg_len,
+            p.games_as_white, p.games_as_black, p.blunders_made, p.points,
+            p.search_budget.name(), p.time_used_ms, p.time_forfeits,
+            p.rating_deviation, p.volatility, p.avg_cp_loss(), p.strategy.name(),
+        ])?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn save_games_batch(conn: &Connection, games: &[GameRecord]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO games (round, white_id, black_id, result, moves, opening_uci,
+                          moves_uci, termination, white_blunders, black_blunders,
+                          white_time_ms, black_time_ms, start_fen, white_cp_loss, black_cp_loss)
+        VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15)",
+    )?;
+
+    for g in games {
+        stmt.execute(params![
+            g.round, g.white_id, g.black_id, g.result.as_str(), g.moves, g.opening_uci(),
+            g.moves_uci.join(" "), g.termination, g.white_blunders, g.black_blunders,
+            g.white_time_ms, g.black_time_ms, g.start_fen, g.white_cp_loss, g.black_cp_loss,
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// A finished round
+pub(crate) struct RoundSummary {
+    pub(crate) round: u32,
+    pub(crate) games: Vec<GameRecord>,
+    pub(crate) bye: Option<u32>,
+    /// Mean rating change per player and game
+    pub(crate) avg_elo_change: f64,
+    pub(crate) duration_ms: u64,
+}
+
+/// Save a round's games, the players' standings after it and the round
+/// itself in one transaction, so an interrupted run only loses the round it
+/// was playing
+fn save_round(conn: &mut Connection, summary: &RoundSummary, players: &[AiPersona]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    save_games_batch(&tx, &summary.games)?;
+    save_players(&tx, players)?;
+    tx.prepare_cached(
+        "INSERT OR REPLACE INTO rounds (round_num, total_games, bye_id, avg_elo_change, duration_ms)
+        VALUES (?1,?2,?3,?4,?5)",
+    )?
+    .execute(params![summary.round, summary.games.len(), summary.bye, summary.avg_elo_change, summary.duration_ms])?;
+    // The round's games are all saved now
+    tx.prepare_cached("DELETE FROM checkpoint WHERE round <= ?1")?.execute([summary.round])?;
+    tx.commit()
+}
+
+/// Add finished games of the round being played, by their index in the
+/// round, to the checkpoint
+fn save_checkpoint(conn: &mut Connection, round: u32, games: &[(usize, GameRecord)]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached("INSERT OR REPLACE INTO checkpoint (round, game, record) VALUES (?1,?2,?3)")?;
+        for (index, record) in games {
+            let json = serde_json::to_string(record).expect("Game records serialize");
+            stmt.execute(params![round, *index as i64, json])?;
+        }
+    }
+    tx.commit()
+}
+
+/// The checkpointed games of a round, by their index in it
+pub(crate) fn load_checkpoint(conn: &Connection, round: u32) -> rusqlite::Result<HashMap<usize, GameRecord>> {
+    let mut stmt = conn.prepare("SELECT game, record FROM checkpoint WHERE round = ?1")?;
+    let rows = stmt.query_map([round], |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, String>(1)?)))?;
+    let mut games = HashMap::new();
+    for row in rows {
+        let (index, json) = row?;
+        // A record that doesn't parse is just played again
+        if let Ok(record) = serde_json::from_str(&json) {
+            games.insert(index, record);
+        }
+    }
+    Ok(games)
+}
+
+/// Results of a round's games (those from `offset` on in it) that a resumed
+/// run's checkpoint holds, taken out of it; None for games still to play
+pub(crate) fn checkpointed_results(
+    games: &[(u32, u32, Option<usize>)],
+    round: u32,
+    offset: usize,
+    checkpoint: &mut HashMap<usize, GameRecord>,
+) -> Vec<Option<GameRecord>> {
+    games
+        .iter()
+        .enumerate()
+        .map(|(i, &(white, black, _))| {
+            // Pairings are rebuilt the same way, but a game that changed is played
+            checkpoint.remove(&(offset + i)).filter(|r| (r.round, r.white_id, r.black_id) == (round, white, black))
+        })
+        .collect()
+}
+
+/// What the database writer is asked to save
+enum WriterMessage {
+    Round(RoundWrite),
+    /// Finished games of the round being played, by index in the round
+    Checkpoint(u32, Vec<(usize, GameRecord)>),
+}
+
+/// A finished round on its way to the database
+pub(crate) struct RoundWrite {
+    pub(crate) summary: RoundSummary,
+    /// Standings after the round
+    pub(crate) players: Vec<AiPersona>,
+    /// The round's games as PGN, for --pgn-out
+    pub(crate) pgn: Option<String>,
+}
+
+/// Rounds handed to the database writer and not saved yet before `save`
+/// waits: one being written and one queued, so the simulation runs at most
+/// a round ahead
+const MAX_UNSAVED_ROUNDS: usize = 2;
+
+/// Saves finished rounds on a thread of its own, so the next round plays
+/// while the last one is written
+pub(crate) struct DbWriter {
+    /// Unbounded, so a checkpoint never holds up the workers adding to it
+    messages: Sender<WriterMessage>,
+    /// One message per round saved
+    saved: Receiver<()>,
+    unsaved_rounds: Cell<usize>,
+    thread: JoinHandle<Connection>,
+}
+
+impl DbWriter {
+    pub(crate) fn spawn(mut conn: Connection, mut pgn_out: Option<BufWriter<File>>) -> DbWriter {
+        let (messages, queue) = mpsc::channel::<WriterMessage>();
+        let (saved_tx, saved) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            for message in queue {
+                let write = match message {
+                    WriterMessage::Round(write) => write,
+                    WriterMessage::Checkpoint(round, games) => {
+                        // A lost checkpoint only means more games to replay
+                        if let Err(e) = save_checkpoint(&mut conn, round, &games) {
+                            eprintln!("Failed to checkpoint round {}: {}", round, e);
+                        }
+                        continue;
+                    }
+                };
+                if let Err(e) = save_round(&mut conn, &write.summary, &write.players) {
+                    eprintln!("Failed to save round {}: {}", write.summary.round, e);
+                    std::process::exit(1);
+                }
+                // Only saved rounds are written, so a resumed run doesn't repeat games
+                if let (Some(out), Some(pgn)) = (&mut pgn_out, &write.pgn) {
+                    if let Err(e) = out.write_all(pgn.as_bytes()).and_then(|_| out.flush()) {
+                        eprintln!("Failed to write PGN: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                let _ = saved_tx.send(());
+            }
+            conn
+        });
+        DbWriter { messages, saved, unsaved_rounds: Cell::new(0), thread }
+    }
+
+    pub(crate) fn save(&self, write: RoundWrite) {
+        // Sending and waiting only fail once the writer has given up on an error
+        if self.unsaved_rounds.get() >= MAX_UNSAVED_ROUNDS {
+            self.saved.recv().expect("Database writer stopped");
+            self.unsaved_rounds.set(self.unsaved_rounds.get() - 1);
+        }
+        self.messages.send(WriterMessage::Round(write)).expect("Database writer stopped");
+        self.unsaved_rounds.set(self.unsaved_rounds.get() + 1);
+    }
+
+    /// The connection back, once every queued round is saved
+    pub(crate) fn finish(self) -> Connection {
+        drop(self.messages);
+        self.thread.join().expect("Database writer panicked")
+    }
+}
+
+/// Finished games of the round being played, handed to the database writer
+/// for its checkpoint every `every` games (never when 0). Only game records
+/// are kept: ratings, points and Swiss or knockout pairing state change
+/// between rounds, and a resumed run rebuilds them from the saved rounds.
+pub(crate) struct Checkpoint<'a> {
+    messages: &'a Sender<WriterMessage>,
+    round: u32,
+    every: usize,
+    unsaved: Mutex<Vec<(usize, GameRecord)>>,
+}
+
+impl<'a> Checkpoint<'a> {
+    pub(crate) fn new(writer: &'a DbWriter, round: u32, every: usize) -> Self {
+        Checkpoint { messages: &writer.messages, round, every, unsaved: Mutex::new(Vec::new()) }
+    }
+
+    /// Note the game at `index` of the round as finished
+    pub(crate) fn add(&self, index: usize, record: &GameRecord) {
+        if self.every == 0 {
+            return;
+        }
+        let full = {
+            let mut unsaved = self.unsaved.lock().unwrap();
+            unsaved.push((index, record.clone()));
+            (unsaved.len() >= self.every).then(|| std::mem::take(&mut *unsaved))
+        };
+        if let Some(games) = full {
+            let message = WriterMessage::Checkpoint(self.round, games);
+            self.messages.send(message).expect("Database writer stopped");
+        }
+    }
+}
+
+/// Games by how they ended, most common first
+pub(crate) fn termination_counts(conn: &Connection) -> rusqlite::Result<Vec<(String, u64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT termination, COUNT(*) FROM games GROUP BY termination ORDER BY COUNT(*) DESC, termination",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// Games won by white, drawn and won by black
+pub(crate) fn color_results(conn: &Connection) -> rusqlite::Result<[u64; 3]> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(result = '1-0'), 0), COALESCE(SUM(result = '1/2-1/2'), 0), COALESCE(SUM(result = '0-1'), 0)
+        FROM games",
+        [],
+        |row| Ok([row.get(0)?, row.get(1)?, row.get(2)?]),
+    )
+}
+
+// =============================================================================
+// RESUMING
+// =============================================================================
+
+/// Record the settings a resumed run needs in tournament_meta
+pub(crate) fn save_settings(conn: &Connection, args: &Args, rounds: u32) -> rusqlite::Result<()> {
+    let settings = [
+        ("players", args.players.to_string()),
+        ("rounds", rounds.to_string()),
+        ("format", args.format.name().to_string()),
+        ("rating_system", args.rating_system.name().to_string()),
+        ("seed", args.seed.to_string()),
+        ("max_moves", args.max_moves.to_string()),
+        ("variant", args.variant.name().to_string()),
+        ("time_control", args.time_control.map_or(String::new(), |tc| tc.describe())),
+        ("openings", args.openings.clone().unwrap_or_default()),
+        ("book_plies", args.book_plies.to_string()),
+        ("resign_score", args.resign_score.map_or(String::new(), |cp| cp.to_string())),
+        ("resign_moves", args.resign_moves.to_string()),
+        ("draw_score", args.draw_score.map_or(String::new(), |cp| cp.to_string())),
+        ("draw_moves", args.draw_moves.to_string()),
+        ("draw_after", args.draw_after.to_string()),
+    ];
+    for (key, value) in settings {
+        conn.execute("INSERT OR REPLACE INTO tournament_meta (key, value) VALUES (?1, ?2)", params![key, value])?;
+    }
+    Ok(())
+}
+
+/// Take the settings of the tournament in `conn` over into `args`
+pub(crate) fn load_settings(conn: &Connection, args: &mut Args) -> Result<(), String> {
+    let get = |key: &str| -> Result<String, String> {
+        conn.query_row("SELECT value FROM tournament_meta WHERE key = ?1", [key], |row| row.get(0))
+            .map_err(|_| format!("no {} setting: not a tournament database, or from an older version", key))
+    };
+    let number = |key: &str| -> Result<u64, String> {
+        let value = get(key)?;
+        value.parse().map_err(|_| format!("invalid {} setting: {}", key, value))
+    };
+    let format = get("format")?;
+    args.format = Format::from_str(&format, true).map_err(|_| format!("unknown format: {}", format))?;
+    // Missing before Glicko-2, when every tournament was rated by Elo
+    let system = get("rating_system").unwrap_or_else(|_| "elo".to_string());
+    args.rating_system =
+        RatingSystem::from_str(&system, true).map_err(|_| format!("unknown rating system: {}", system))?;
+    args.players = number("players")? as u32;
+    args.rounds = number("rounds")? as u32;
+    args.seed = number("seed")?;
+    args.max_moves = number("max_moves")? as u32;
+    // Missing before variants, when every game was standard chess
+    args.variant = parse_variant(&get("variant").unwrap_or_else(|_| "standard".to_string()))?;
+    // Empty, or missing before clocks, for fixed-depth games
+    let time_control = get("time_control").unwrap_or_default();
+    args.time_control = match time_control.as_str() {
+        "" => None,
+        spec => Some(TimeControl::parse(spec)?),
+    };
+    // Likewise missing before opening suites
+    let openings = get("openings").unwrap_or_default();
+    args.openings = (!openings.is_empty()).then_some(openings);
+    if args.openings.is_some() {
+        args.book_plies = number("book_plies")? as u32;
+    }
+    // And before adjudication, which was off
+    let score = |key: &str| -> Result<Option<i32>, String> {
+        match get(key).unwrap_or_default().as_str() {
+            "" => Ok(None),
+            value => value.parse().map(Some).map_err(|_| format!("invalid {} setting: {}", key, value)),
+        }
+    };
+    args.resign_score = score("resign_score")?;
+    args.draw_score = score("draw_score")?;
+    if args.resign_score.is_some() {
+        args.resign_moves = number("resign_moves")? as u32;
+    }
+    if args.draw_score.is_some() {
+        args.draw_moves = number("draw_moves")? as u32;
+        args.draw_after = number("draw_after")? as u32;
+    }
+    Ok(())
+}
+
+/// The players of a results database as they started, before any game:
+/// their results and pairing history are rebuilt by `restore_rounds`
+pub(crate) fn load_players(conn: &Connection) -> Result<Vec<AiPersona>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, initial_elo, search_depth, aggression, personality, opening_style,
+                    blunder_rate, test_group, search_budget, strategy
+            FROM players ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                AiPersona {
+                    search_depth: row.get(3)?,
+                    aggression: row.get(4)?,
+                    blunder_rate: row.get(7)?,
+                    ..AiPersona::new(row.get(0)?, row.get(1)?, row.get(2)?, Group::Control)
+                },
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut players = Vec::new();
+    for row in rows {
+        let (mut player, personality, opening_style, group, budget, strategy) = row.map_err(|e| e.to_string())?;
+        if player.id as usize != players.len() {
+            return Err(format!("player ids are not 0..n: found #{} at {}", player.id, players.len()));
+        }
+        player.personality =
+            Personality::from_name(&personality).ok_or_else(|| format!("unknown personality: {}", personality))?;
+        player.opening_style = OpeningStyle::from_name(&opening_style)
+            .ok_or_else(|| format!("unknown opening style: {}", opening_style))?;
+        player.group = Group::from_name(&group).ok_or_else(|| format!("unknown group: {}", group))?;
+        player.search_budget =
+            SearchBudget::from_name(&budget).ok_or_else(|| format!("unknown search budget: {}", budget))?;
+        player.strategy =
+            Strategy::from_name(&strategy).ok_or_else(|| format!("unknown strategy: {}", strategy))?;
+        players.push(player);
+    }
+    Ok(players)
+}
+
+/// Give `id` a round off: a point in a Swiss, nothing in a round-robin,
+/// where everyone sits out once
+pub(crate) fn apply_bye(players: &mut [AiPersona], id: u32, format: Format) {
+    if format == Format::Swiss {
+        players[id as usize].points += 1.0;
+    }
+    players[id as usize].byes += 1;
+}
+
+/// Replay the byes and games of the rounds completed so far into `players`
+/// (fresh from `load_players`) and the knockout bracket, in the order they
+/// were first applied, and delete the games of a round cut short. Returns
+/// the number of rounds completed.
+pub(crate) fn restore_rounds(
+    conn: &Connection,
+    players: &mut [AiPersona],
+    format: Format,
+    rating_system: RatingSystem,
+    mut bracket: Option<&mut Bracket>,
+) -> rusqlite::Result<u32> {
+    let completed: u32 = conn.query_row("SELECT COALESCE(MAX(round_num), 0) FROM rounds", [], |row| row.get(0))?;
+    conn.execute("DELETE FROM games WHERE round > ?1", [completed])?;
+
+    let mut byes = conn.prepare("SELECT bye_id FROM rounds WHERE round_num = ?1")?;
+    let mut games = conn.prepare(
+        "SELECT white_id, black_id, result, moves, termination, white_blunders, black_blunders,
+                white_time_ms, black_time_ms, start_fen, white_cp_loss, black_cp_loss
+        FROM games WHERE round = ?1 ORDER BY id",
+    )?;
+    for round in 1..=completed {
+        let bye: Option<u32> = byes.query_row([round], |row| row.get(0))?;
+        if let Some(id) = bye {
+            apply_bye(players, id, format);
+        }
+        let records = games
+            .query_map([round], |row| {
+                let result: String = row.get(2)?;
+                Ok(GameRecord {
+                    round,
+                    white_id: row.get(0)?,
+                    black_id: row.get(1)?,
+                    result: GameResult::from_pgn(&result).unwrap_or(GameResult::Draw),
+                    moves: row.get(3)?,
+                    moves_uci: Vec::new(),
+                    termination: row.get(4)?,
+                    white_blunders: row.get(5)?,
+                    black_blunders: row.get(6)?,
+                    white_time_ms: row.get(7)?,
+                    black_time_ms: row.get(8)?,
+                    start_fen: row.get(9)?,
+                    white_cp_loss: row.get(10)?,
+                    black_cp_loss: row.get(11)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<GameRecord>>>()?;
+        apply_round(players, &records, rating_system);
+        if let Some(bracket) = bracket.as_deref_mut() {
+            bracket.advance(round, &records);
+        }
+    }
+    Ok(completed)
+}
+
+// =============================================================================
+// PGN EXPORT
+// =============================================================================
+
+/// A game as stored in the database, with its players' names
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StoredGame {
+    pub(crate) id: i64,
+    pub(crate) round: u32,
+    pub(crate) white: String,
+    pub(crate) black: String,
+    pub(crate) result: String,
+    pub(crate) termination: String,
+    pub(crate) moves_uci: Vec<String>,
+    pub(crate) start_fen: Option<String>,
+    pub(crate) variant: Variant,
+}
+
+impl StoredGame {
+    pub(crate) fn from_record(record: &GameRecord, players: &[AiPersona], variant: Variant) -> Self {
+        StoredGame {
+            id: 0,
+            round: record.round,
+            white: players[record.white_id as usize].name.clone(),
+            black: players[record.black_id as usize].name.clone(),
+            result: record.result.as_str().to_string(),
+            termination: record.termination.clone(),
+            moves_uci: record.moves_uci.clone(),
+            start_fen: record.start_fen.clone(),
+            variant,
+        }
+    }
+
+    /// The position the game started from, under its variant's rules
+    pub(crate) fn start_position(&self) -> Result<Position, String> {
+        let mut pos = match &self.start_fen {
+            Some(fen) => Position::from_fen(fen).map_err(|e| format!("Invalid start FEN {}: {}", fen, e))?,
+            None => Position::starting_position(),
+        };
+        pos.set_variant(self.variant);
+        Ok(pos)
+    }
+
+    /// The game as PGN, its UCI moves replayed from the starting position
+    /// into SAN (up to the first one that doesn't fit)
+    pub(crate) fn to_pgn(&self) -> String {
+        let start = self.start_fen.as_deref().and_then(|fen| Position::from_fen(fen).ok());
+        let mut pos = start.clone().unwrap_or_else(Position::starting_position);
+        pos.set_variant(self.variant);
+        let mut game = PgnGame { result: self.result.clone(), ..PgnGame::default() };
+        for uci in &self.moves_uci {
+            let Some(m) = find_move_by_uci(&generate_legal_moves(&pos), uci) else { break };
+            game.moves.push(m.to_san(&pos));
+            pos.make_move(m);
+        }
+        let round = self.round.to_string();
+        for (name, value) in [
+            ("Event", "AI Tournament"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", &round),
+            ("White", &self.white),
+            ("Black", &self.black),
+            ("Result", &self.result),
+            ("Termination", &self.termination),
+        ] {
+            game.headers.push((name.to_string(), value.to_string()));
+        }
+        if self.variant != Variant::Standard {
+            game.headers.push(("Variant".to_string(), self.variant.name().to_string()));
+        }
+        if let Some(start) = start {
+            game.headers.push(("SetUp".to_string(), "1".to_string()));
+            game.headers.push(("FEN".to_string(), start.to_fen()));
+        }
+        game.to_pgn()
+    }
+}
+
+/// The variant the games of a results database were played under
+pub(crate) fn stored_variant(conn: &Connection) -> Variant {
+    // Missing before variants, when every game was standard chess
+    conn.query_row("SELECT value FROM tournament_meta WHERE key = 'variant'", [], |row| row.get::<_, String>(0))
+        .ok()
+        .and_then(|name| Variant::from_name(&name))
+        .unwrap_or_default()
+}
+
+/// Games of a results database, optionally only those of one round and of
+/// one player (or the one game of an id), in the order they were played
+pub(crate) fn load_games(
+    conn: &Connection,
+    round: Option<u32>,
+    player: Option<u32>,
+    game: Option<i64>,
+) -> rusqlite::Result<Vec<StoredGame>> {
+    // Read-only databases from before openings lack the column
+    let start_fen = if conn.prepare("SELECT start_fen FROM games LIMIT 0").is_ok() { "g.start_fen" } else { "NULL" };
+    let variant = stored_variant(conn);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT g.id, g.round, g.white_id, g.black_id, pw.name, pb.name, g.result, g.termination,
+                COALESCE(g.moves_uci, ''), {}
+        FROM games g
+        LEFT JOIN players pw ON pw.id = g.white_id
+        LEFT JOIN players pb ON pb.id = g.black_id
+        WHERE (?1 IS NULL OR g.round = ?1) AND (?2 IS NULL OR g.white_id = ?2 OR g.black_id = ?2)
+            AND (?3 IS NULL OR g.id = ?3)
+        ORDER BY g.id",
+        start_fen,
+    ))?;
+    let rows = stmt.query_map(params![round, player, game], |row| {
+        // Name players missing from the table by id
+        let name = |index: usize, id: u32| -> rusqlite::Result<String> {
+            Ok(row.get::<_, Option<String>>(index)?.unwrap_or_else(|| format!("Player #{}", id)))
+        };
+        let moves: String = row.get(8)?;
+        Ok(StoredGame {
+            id: row.get(0)?,
+            round: row.get(1)?,
+            white: name(4, row.get(2)?)?,
+            black: name(5, row.get(3)?)?,
+            result: row.get(6)?,
+            termination: row.get(7)?,
+            moves_uci: moves.split_whitespace().map(str::to_string).collect(),
+            start_fen: row.get(9)?,
+            variant,
+        })
+    })?;
+    rows.collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{round_robin_pair, swiss_pair, Pairings};
+    use crate::{generate_personas, play_game, StrategyMix};
+    use crate::tests::{game, rules};
+    use clap::Parser;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_resume_restores_standings() {
+        let args = Args::parse_from([
+            "tournament", "--format", "round-robin", "--players", "5", "--seed", "9", "--rating-system", "glicko2",
+            "--variant", "three-check",
+        ]);
+        let rounds = args.format.rounds(args.players, args.rounds);
+        let mut players = generate_personas(args.players, args.seed);
+        StrategyMix::parse("search,material").unwrap().assign(&mut players, args.seed);
+        let mut conn = init_database(":memory:").unwrap();
+        save_settings(&conn, &args, rounds).unwrap();
+        save_players(&conn, &players).unwrap();
+
+        // Two rounds complete, the third cut short after a game
+        let outcomes = [GameResult::WhiteWins, GameResult::Draw, GameResult::BlackWins];
+        for round in 1..=2 {
+            let Pairings { pairs, bye } = round_robin_pair(args.players, round);
+            apply_bye(&mut players, bye.unwrap(), args.format);
+            let games: Vec<GameRecord> =
+                pairs.iter().enumerate().map(|(i, &(w, b))| game(round, w, b, outcomes[(i + round as usize) % 3])).collect();
+            apply_round(&mut players, &games, args.rating_system);
+            let summary = RoundSummary { round, games, bye, avg_elo_change: 0.0, duration_ms: 0 };
+            save_round(&mut conn, &summary, &players).unwrap();
+        }
+        let (w, b) = round_robin_pair(args.players, 3).pairs[0];
+        save_games_batch(&conn, &[game(3, w, b, GameResult::WhiteWins)]).unwrap();
+
+        let mut resumed = Args::parse_from(["tournament", "--resume", "x.db"]);
+        load_settings(&conn, &mut resumed).unwrap();
+        assert_eq!((resumed.format, resumed.players, resumed.rounds), (Format::RoundRobin, 5, 5));
+        assert_eq!((resumed.seed, resumed.max_moves, resumed.rating_system), (9, args.max_moves, RatingSystem::Glicko2));
+        assert_eq!(resumed.variant, Variant::ThreeCheck);
+
+        let mut restored = load_players(&conn).unwrap();
+        assert_eq!(restore_rounds(&conn, &mut restored, resumed.format, resumed.rating_system, None).unwrap(), 2);
+        for (p, r) in players.iter().zip(&restored) {
+            assert_eq!((&p.name, p.initial_elo, p.personality, p.group), (&r.name, r.initial_elo, r.personality, r.group));
+            assert_eq!((p.elo, p.points, p.byes, p.wins, p.draws), (r.elo, r.points, r.byes, r.wins, r.draws));
+            assert_eq!((p.rating_deviation, p.volatility), (r.rating_deviation, r.volatility));
+            assert_eq!(p.strategy, r.strategy);
+            assert_eq!((&p.opponents, &p.colors, &p.scores), (&r.opponents, &r.colors, &r.scores));
+        }
+        assert!(load_games(&conn, Some(3), None, None).unwrap().is_empty(), "The unfinished round's game is dropped");
+        assert!(load_settings(&init_database(":memory:").unwrap(), &mut resumed).is_err());
+    }
+
+    #[test]
+    fn test_db_writer() {
+        let players = generate_personas(4, 2);
+        let conn = init_database(":memory:").unwrap();
+        save_players(&conn, &players).unwrap();
+        let pgn_path = std::env::temp_dir().join(format!("db_writer_{}.pgn", std::process::id()));
+        let writer = DbWriter::spawn(conn, Some(BufWriter::new(File::create(&pgn_path).unwrap())));
+        for round in 1..=3 {
+            let games = vec![game(round, 0, 1, GameResult::Draw), game(round, 3, 2, GameResult::WhiteWins)];
+            let pgn = Some(format!("[Round \"{}\"]\n", round));
+            let summary = RoundSummary { round, games, bye: None, avg_elo_change: 0.0, duration_ms: 0 };
+            writer.save(RoundWrite { summary, players: players.clone(), pgn });
+        }
+
+        // Finishing waits for the queued rounds
+        let conn = writer.finish();
+        let rounds: u32 = conn.query_row("SELECT COUNT(*) FROM rounds", [], |row| row.get(0)).unwrap();
+        assert_eq!((rounds, load_games(&conn, None, None, None).unwrap().len()), (3, 6));
+        let pgn = std::fs::read_to_string(&pgn_path).unwrap();
+        std::fs::remove_file(&pgn_path).unwrap();
+        assert_eq!(pgn, "[Round \"1\"]\n[Round \"2\"]\n[Round \"3\"]\n");
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let players = generate_personas(4, 2);
+        let conn = init_database(":memory:").unwrap();
+        save_players(&conn, &players).unwrap();
+        let games = [(0, 1, None), (2, 3, None), (1, 2, None), (3, 0, None)];
+        let records: Vec<GameRecord> =
+            games.iter().map(|&(white, black, _)| game(2, white, black, GameResult::WhiteWins)).collect();
+
+        // Every second game sends the two since to the writer
+        let writer = DbWriter::spawn(conn, None);
+        let checkpoint = Checkpoint::new(&writer, 2, 2);
+        for i in [3, 0, 2] {
+            checkpoint.add(i, &records[i]);
+        }
+        let conn = writer.finish();
+        let mut saved = load_checkpoint(&conn, 2).unwrap();
+        assert_eq!(saved.keys().copied().collect::<HashSet<_>>(), HashSet::from([0, 3]));
+        assert!(load_checkpoint(&conn, 1).unwrap().is_empty());
+
+        // A resumed round takes the games that still match its pairings
+        saved.get_mut(&3).unwrap().white_id = 2;
+        let restored = checkpointed_results(&games, 2, 0, &mut saved);
+        assert_eq!(restored, vec![Some(records[0].clone()), None, None, None]);
+        assert!(saved.is_empty());
+
+        // Saving the round clears its checkpoint
+        let mut conn = conn;
+        let summary = RoundSummary { round: 2, games: records, bye: None, avg_elo_change: 0.0, duration_ms: 0 };
+        save_round(&mut conn, &summary, &players).unwrap();
+        assert!(load_checkpoint(&conn, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_resume_pairings() {
+        // Swiss and knockout pairings depend on the standings, which the
+        // checkpoint doesn't hold: a resumed run rebuilds them from the saved
+        // rounds and pairs the interrupted round the same way again
+        for format in [Format::Swiss, Format::Knockout] {
+            let mut players = generate_personas(8, 4);
+            let mut bracket = (format == Format::Knockout).then(|| Bracket::new(&players));
+            let pairings = |players: &[AiPersona], bracket: Option<&Bracket>, round| match bracket {
+                Some(bracket) => bracket.pair(round),
+                None => swiss_pair(players, round),
+            };
+            let mut conn = init_database(":memory:").unwrap();
+            save_players(&conn, &players).unwrap();
+            let play = |pairs: &[(u32, u32)], round| -> Vec<GameRecord> {
+                let results = [GameResult::WhiteWins, GameResult::BlackWins, GameResult::WhiteWins];
+                pairs.iter().enumerate().map(|(i, &(w, b))| game(round, w, b, results[i % 3])).collect()
+            };
+            let pairs = pairings(&players, bracket.as_ref(), 1).pairs;
+            let results = play(&pairs, 1);
+            apply_round(&mut players, &results, RatingSystem::Elo);
+            if let Some(bracket) = &mut bracket {
+                bracket.advance(1, &results);
+            }
+            let summary = RoundSummary { round: 1, games: results, bye: None, avg_elo_change: 0.0, duration_ms: 0 };
+            save_round(&mut conn, &summary, &players).unwrap();
+
+            // Round 2 stops after its first game reached the checkpoint
+            let pairs = pairings(&players, bracket.as_ref(), 2).pairs;
+            let records = play(&pairs, 2);
+            let writer = DbWriter::spawn(conn, None);
+            Checkpoint::new(&writer, 2, 1).add(0, &records[0]);
+            let conn = writer.finish();
+
+            let mut restored = load_players(&conn).unwrap();
+            let mut rebuilt = (format == Format::Knockout).then(|| Bracket::new(&restored));
+            assert_eq!(restore_rounds(&conn, &mut restored, format, RatingSystem::Elo, rebuilt.as_mut()).unwrap(), 1);
+            for (p, r) in players.iter().zip(&restored) {
+                assert_eq!((p.elo, p.points, &p.opponents, &p.colors), (r.elo, r.points, &r.opponents, &r.colors));
+            }
+            assert_eq!(pairings(&restored, rebuilt.as_ref(), 2).pairs, pairs, "{:?}", format);
+            let games: Vec<(u32, u32, Option<usize>)> = pairs.iter().map(|&(w, b)| (w, b, None)).collect();
+            let resumed = checkpointed_results(&games, 2, 0, &mut load_checkpoint(&conn, 2).unwrap());
+            assert_eq!(resumed[0].as_ref(), Some(&records[0]));
+            assert!(resumed[1..].iter().all(Option::is_none));
+        }
+    }
+
+    #[test]
+    fn test_games_export_as_pgn() {
+        let personas = generate_personas(4, 42);
+        let records = [
+            play_game(&personas[0], &personas[1], 1, &rules(30), None, 1),
+            play_game(&personas[2], &personas[3], 2, &rules(30), None, 2),
+        ];
+        let conn = init_database(":memory:").unwrap();
+        save_players(&conn, &personas).unwrap();
+        save_games_batch(&conn, &records).unwrap();
+
+        let stored = load_games(&conn, None, None, None).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].moves_uci, records[0].moves_uci);
+        let stored = load_games(&conn, Some(2), Some(3), None).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].black, personas[3].name);
+        assert!(load_games(&conn, Some(2), Some(0), None).unwrap().is_empty());
+
+        // The PGN replays to the same moves and result as the record
+        let mut expected = StoredGame::from_record(&records[1], &personas, Variant::Standard);
+        expected.id = stored[0].id;
+        assert_eq!(stored[0], expected);
+        let game = chess_engine::pgn::parse_pgn(&stored[0].to_pgn()).unwrap();
+        assert_eq!(game.moves.len(), records[1].moves as usize);
+        assert_eq!(game.result, records[1].result.as_str());
+        assert_eq!(game.header("Round"), Some("2"));
+        assert_eq!(game.header("Termination"), Some(records[1].termination.as_str()));
+    }
+}
@@ -0,0 +1,399 @@
+// =============================================================================
+// Distributed Play
+// =============================================================================
+// Coordinator (--serve) and workers (--worker) playing a round's games together.
+// =============================================================================
+
+use chess_engine::personality::Personality;
+use chess_engine::position::Position;
+
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::{play_game, AiPersona, GameRecord, GameRules, Group, OpeningStyle, SearchBudget, Strategy};
+
+// =============================================================================
+// DISTRIBUTED PLAY
+// =============================================================================
+// The coordinator (--serve) pairs and rates as usual; each round's games go
+// into a queue that its own threads and any connected workers (--worker) play
+// from. Messages are JSON lines over TCP: a worker says hello with its thread
+// count and gets the game rules, then takes batches of games and streams back
+// one result per game. Games of a worker that disconnects are played again.
+
+/// Games handed to a worker at a time, per worker thread
+const WORKER_BATCH_PER_THREAD: usize = 4;
+
+/// What a worker needs of a player to play its games
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PlayerSpec {
+    id: u32,
+    search_depth: u8,
+    personality: String,
+    opening_style: String,
+    blunder_rate: f64,
+    search_budget: String,
+    strategy: String,
+}
+
+impl PlayerSpec {
+    pub(crate) fn of(p: &AiPersona) -> PlayerSpec {
+        PlayerSpec {
+            id: p.id,
+            search_depth: p.search_depth,
+            personality: p.personality.name().to_string(),
+            opening_style: p.opening_style.name().to_string(),
+            blunder_rate: p.blunder_rate,
+            search_budget: p.search_budget.name().to_string(),
+            strategy: p.strategy.name().to_string(),
+        }
+    }
+
+    fn persona(&self) -> Result<AiPersona, String> {
+        let unknown = |what: &str, name: &str| format!("unknown {}: {}", what, name);
+        Ok(AiPersona {
+            search_depth: self.search_depth,
+            personality: Personality::from_name(&self.personality)
+                .ok_or_else(|| unknown("personality", &self.personality))?,
+            opening_style: OpeningStyle::from_name(&self.opening_style)
+                .ok_or_else(|| unknown("opening style", &self.opening_style))?,
+            blunder_rate: self.blunder_rate,
+            search_budget: SearchBudget::from_name(&self.search_budget)
+                .ok_or_else(|| unknown("search budget", &self.search_budget))?,
+            strategy: Strategy::from_name(&self.strategy).ok_or_else(|| unknown("strategy", &self.strategy))?,
+            ..AiPersona::new(self.id, String::new(), 0.0, Group::Control)
+        })
+    }
+}
+
+/// A game to play, wherever it is played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct GameJob {
+    pub(crate) white: PlayerSpec,
+    pub(crate) black: PlayerSpec,
+    pub(crate) round: u32,
+    /// Suite opening the game starts from, as FEN
+    pub(crate) opening: Option<String>,
+    pub(crate) seed: u64,
+}
+
+impl GameJob {
+    fn play(&self, rules: &GameRules) -> Result<GameRecord, String> {
+        let opening = self.opening.as_deref().map(Position::from_fen).transpose().map_err(|e| e.to_string())?;
+        let (white, black) = (self.white.persona()?, self.black.persona()?);
+        Ok(play_game(&white, &black, self.round, rules, opening.as_ref(), self.seed))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WorkerMessage {
+    Hello { threads: usize },
+    Result { index: usize, record: GameRecord },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CoordinatorMessage {
+    Setup { rules: GameRules },
+    Games { games: Vec<(usize, GameJob)> },
+}
+
+fn send<T: Serialize>(out: &mut impl Write, message: &T) -> Result<(), String> {
+    serde_json::to_writer(&mut *out, message).map_err(|e| e.to_string())?;
+    out.write_all(b"\n").and_then(|_| out.flush()).map_err(|e| e.to_string())
+}
+
+/// The next message, or None once the other side has closed the connection
+fn receive<T: DeserializeOwned>(input: &mut impl BufRead) -> Result<Option<T>, String> {
+    let mut line = String::new();
+    match input.read_line(&mut line).map_err(|e| e.to_string())? {
+        0 => Ok(None),
+        _ => serde_json::from_str(&line).map(Some).map_err(|e| format!("bad message: {}", e)),
+    }
+}
+
+/// Called with each game's index and result as they come in
+type OnResult<'a> = dyn Fn(usize, &GameRecord) + Sync + 'a;
+
+#[derive(Debug, Default)]
+struct RoundState {
+    jobs: Vec<GameJob>,
+    /// Games nobody is playing yet
+    pending: VecDeque<usize>,
+    results: Vec<Option<GameRecord>>,
+    done: usize,
+    /// Games done since the round's results were last looked at
+    finished: Vec<usize>,
+}
+
+/// The games of the round being played, shared by the coordinator's threads
+/// and the connections to its workers
+#[derive(Debug, Default)]
+pub(crate) struct RoundQueue {
+    state: Mutex<RoundState>,
+    changed: Condvar,
+}
+
+impl RoundQueue {
+    fn start(&self, jobs: Vec<GameJob>) {
+        let games = jobs.len();
+        *self.state.lock().unwrap() = RoundState {
+            jobs,
+            pending: (0..games).collect(),
+            results: vec![None; games],
+            done: 0,
+            finished: Vec::new(),
+        };
+        self.changed.notify_all();
+    }
+
+    /// Up to `max` games to play, waiting for a round with some if `wait`
+    fn take(&self, max: usize, wait: bool) -> Vec<(usize, GameJob)> {
+        let mut state = self.state.lock().unwrap();
+        while wait && state.pending.is_empty() {
+            state = self.changed.wait(state).unwrap();
+        }
+        let count = max.min(state.pending.len());
+        let taken: Vec<usize> = state.pending.drain(..count).collect();
+        taken.into_iter().map(|i| (i, state.jobs[i].clone())).collect()
+    }
+
+    /// Record a game's result; the games done so far
+    fn finish(&self, index: usize, record: GameRecord) -> usize {
+        let mut state = self.state.lock().unwrap();
+        // Late results of a finished round are dropped
+        let current = state.jobs.get(index).is_some_and(|job| job.round == record.round);
+        if let Some(slot @ None) = state.results.get_mut(index).filter(|_| current) {
+            *slot = Some(record);
+            state.done += 1;
+            state.finished.push(index);
+        }
+        self.changed.notify_all();
+        state.done
+    }
+
+    /// Results that came in since the last call, with their index
+    fn newly_finished(&self) -> Vec<(usize, GameRecord)> {
+        let mut state = self.state.lock().unwrap();
+        let finished = std::mem::take(&mut state.finished);
+        finished.into_iter().filter_map(|i| state.results.get(i).cloned().flatten().map(|r| (i, r))).collect()
+    }
+
+    /// Put games a worker dropped back in the queue
+    fn requeue(&self, indices: impl IntoIterator<Item = usize>) {
+        let mut state = self.state.lock().unwrap();
+        for index in indices {
+            if state.results.get(index).is_some_and(Option::is_none) {
+                state.pending.push_back(index);
+            }
+        }
+        self.changed.notify_all();
+    }
+
+    /// Play waiting games on this machine's threads until there are none,
+    /// passing every result that comes in to `on_result`
+    fn play_local(&self, rules: &GameRules, pb: &ProgressBar, on_result: &OnResult<'_>) {
+        rayon::broadcast(|_| {
+            while let Some((index, job)) = self.take(1, false).pop() {
+                let record = job.play(rules).expect("The coordinator's own games are valid");
+                pb.set_position(self.finish(index, record) as u64);
+                self.newly_finished().iter().for_each(|(i, record)| on_result(*i, record));
+            }
+        });
+    }
+
+    /// The round's results once every game is done, or None as soon as
+    /// games wait to be played
+    fn wait(&self, pb: &ProgressBar, on_result: &OnResult<'_>) -> Option<Vec<GameRecord>> {
+        loop {
+            let state = self.state.lock().unwrap();
+            let settled = state.done == state.jobs.len() || !state.pending.is_empty();
+            if settled {
+                drop(state);
+            } else {
+                pb.set_position(state.done as u64);
+                drop(self.changed.wait_timeout(state, Duration::from_millis(200)).unwrap());
+            }
+            // Results are passed on with the lock released
+            self.newly_finished().iter().for_each(|(i, record)| on_result(*i, record));
+            if settled {
+                break;
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        if !state.pending.is_empty() {
+            return None;
+        }
+        let results = std::mem::take(&mut state.results);
+        Some(results.into_iter().map(|record| record.expect("Every game is done")).collect())
+    }
+
+    /// Play a round with the workers' help, results in the order of `jobs`
+    pub(crate) fn play_round(
+        &self,
+        jobs: Vec<GameJob>,
+        rules: &GameRules,
+        pb: &ProgressBar,
+        on_result: &OnResult<'_>,
+    ) -> Vec<GameRecord> {
+        self.start(jobs);
+        loop {
+            self.play_local(rules, pb, on_result);
+            if let Some(results) = self.wait(pb, on_result) {
+                return results;
+            }
+        }
+    }
+}
+
+/// Accept workers on `addr`, each served on a thread of its own
+pub(crate) fn serve(addr: &str, queue: Arc<RoundQueue>, rules: GameRules) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map_or_else(|_| "?".to_string(), |a| a.to_string());
+                if let Err(e) = serve_worker(&queue, stream, &rules) {
+                    eprintln!("   Worker {} left: {}", peer, e);
+                }
+            });
+        }
+    });
+    Ok(local)
+}
+
+/// Hand one worker batches of games for as long as it stays connected
+fn serve_worker(queue: &RoundQueue, stream: TcpStream, rules: &GameRules) -> Result<(), String> {
+    let mut input = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut output = BufWriter::new(stream);
+    let threads = match receive(&mut input)? {
+        Some(WorkerMessage::Hello { threads }) => threads.max(1),
+        _ => return Err("expected hello".into()),
+    };
+    send(&mut output, &CoordinatorMessage::Setup { rules: *rules })?;
+    loop {
+        let games = queue.take(threads * WORKER_BATCH_PER_THREAD, true);
+        let mut unfinished: HashSet<usize> = games.iter().map(|&(index, _)| index).collect();
+        let exchange = || -> Result<(), String> {
+            send(&mut output, &CoordinatorMessage::Games { games })?;
+            while !unfinished.is_empty() {
+                match receive(&mut input)? {
+                    Some(WorkerMessage::Result { index, record }) if unfinished.remove(&index) => {
+                        queue.finish(index, record);
+                    }
+                    Some(message) => return Err(format!("unexpected {:?}", message)),
+                    None => return Err("disconnected".into()),
+                }
+            }
+            Ok(())
+        };
+        if let Err(e) = exchange() {
+            queue.requeue(unfinished);
+            return Err(e);
+        }
+    }
+}
+
+/// Play games for the coordinator at `addr` until it closes the connection;
+/// the number of games played
+pub(crate) fn run_worker(addr: &str) -> Result<u64, String> {
+    let stream = TcpStream::connect(addr).map_err(|e| format!("cannot connect to {}: {}", addr, e))?;
+    let mut input = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let output = Mutex::new(BufWriter::new(stream));
+    send(&mut *output.lock().unwrap(), &WorkerMessage::Hello { threads: rayon::current_num_threads() })?;
+    let rules = match receive(&mut input)? {
+        Some(CoordinatorMessage::Setup { rules }) => rules,
+        _ => return Err("expected the game rules".into()),
+    };
+    let mut played = 0;
+    loop {
+        let games = match receive(&mut input)? {
+            Some(CoordinatorMessage::Games { games }) => games,
+            Some(message) => return Err(format!("unexpected {:?}", message)),
+            None => return Ok(played),
+        };
+        played += games.len() as u64;
+        games.into_par_iter().try_for_each(|(index, job)| {
+            let record = job.play(&rules)?;
+            send(&mut *output.lock().unwrap(), &WorkerMessage::Result { index, record })
+        })?;
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_personas;
+    use crate::tests::rules;
+
+    #[test]
+    fn test_distributed_round() {
+        let mut personas = generate_personas(4, 8);
+        personas.iter_mut().for_each(|p| p.search_depth = 1);
+        let rules = rules(12);
+        let jobs: Vec<GameJob> = [(0, 1), (2, 3), (1, 2)]
+            .into_iter()
+            .map(|(w, b)| GameJob {
+                white: PlayerSpec::of(&personas[w]),
+                black: PlayerSpec::of(&personas[b]),
+                round: 1,
+                opening: None,
+                seed: (w * 7 + b) as u64,
+            })
+            .collect();
+        // Thinking times vary from run to run
+        let untimed = |records: Vec<GameRecord>| -> Vec<GameRecord> {
+            records.into_iter().map(|r| GameRecord { white_time_ms: 0, black_time_ms: 0, ..r }).collect()
+        };
+        let local = untimed(jobs.iter().map(|job| job.play(&rules).unwrap()).collect());
+        assert_eq!(local[0], untimed(vec![play_game(&personas[0], &personas[1], 1, &rules, None, 1)])[0]);
+
+        // A worker leaving mid-batch hands its games back
+        let queue = Arc::new(RoundQueue::default());
+        let addr = serve("127.0.0.1:0", Arc::clone(&queue), rules).unwrap();
+        queue.start(jobs.clone());
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut input = BufReader::new(stream.try_clone().unwrap());
+        send(&mut &stream, &WorkerMessage::Hello { threads: 1 }).unwrap();
+        assert!(matches!(receive(&mut input), Ok(Some(CoordinatorMessage::Setup { .. }))));
+        let Ok(Some(CoordinatorMessage::Games { games })) = receive(&mut input) else { panic!("no games") };
+        assert_eq!(games.len(), 3);
+        drop((input, stream));
+        while queue.state.lock().unwrap().pending.len() < 3 {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // A real worker plays them as they would be played here
+        thread::spawn(move || run_worker(&addr.to_string()));
+        let pb = ProgressBar::hidden();
+        let reported = Mutex::new(Vec::new());
+        let report = |index: usize, record: &GameRecord| reported.lock().unwrap().push((index, record.clone()));
+        let results = loop {
+            match queue.wait(&pb, &report) {
+                Some(results) => break results,
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert_eq!(untimed(results.clone()), local);
+        // Every result is passed on once as it comes in
+        let mut reported = std::mem::take(&mut *reported.lock().unwrap());
+        reported.sort_by_key(|(index, _)| *index);
+        assert_eq!(reported.into_iter().map(|(_, record)| record).collect::<Vec<_>>(), results);
+        assert_eq!(untimed(queue.play_round(jobs, &rules, &pb, &|_, _| {})), local);
+    }
+}
@@ -0,0 +1,590 @@
+// =============================================================================
+// Tournament Formats
+// =============================================================================
+// Pairings of each format: Swiss by score group, round-robin by the circle
+// method, the gauntlet of player #0, and the seeded knockout bracket.
+// =============================================================================
+
+use chess_engine::types::Color;
+
+use std::collections::HashMap;
+
+use crate::{AiPersona, GameRecord, GameResult};
+
+// =============================================================================
+// SWISS PAIRING
+// =============================================================================
+
+/// Pairings of a round: (white, black) ids, and the player sitting out
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Pairings {
+    pub(crate) pairs: Vec<(u32, u32)>,
+    pub(crate) bye: Option<u32>,
+}
+
+/// How much a player wants white next: +3 / -3 when white / black is due
+/// (two more of the other color, or the other color twice in a row), +2 / -2
+/// when one behind, +1 / -1 to alternate, 0 before any game
+fn color_preference(player: &AiPersona) -> i32 {
+    let balance = player.colors.iter().map(|&c| if c == Color::White { 1 } else { -1 }).sum::<i32>();
+    let last = player.colors.last().copied();
+    let twice = player.colors.len() >= 2 && player.colors[player.colors.len() - 2] == last.unwrap();
+    match (balance, last) {
+        (b, _) if b <= -2 => 3,
+        (b, _) if b >= 2 => -3,
+        (_, Some(Color::Black)) if twice => 3,
+        (_, Some(Color::White)) if twice => -3,
+        (-1, _) => 2,
+        (1, _) => -2,
+        (_, Some(Color::Black)) => 1,
+        (_, Some(Color::White)) => -1,
+        _ => 0,
+    }
+}
+
+/// Whether `a` and `b` may meet: not a rematch, and not both due the same color
+fn can_pair(a: &AiPersona, b: &AiPersona) -> bool {
+    let (pa, pb) = (color_preference(a), color_preference(b));
+    let same_due = pa.abs() == 3 && pa == pb;
+    !a.opponents.contains(&b.id) && !same_due
+}
+
+/// Colors for `a` (the higher ranked) and `b`: white to the stronger wish,
+/// the higher ranked player's wish on a tie, alternating by round otherwise
+fn assign_colors(a: &AiPersona, b: &AiPersona, round: u32) -> (u32, u32) {
+    let (pa, pb) = (color_preference(a), color_preference(b));
+    let a_white = match pa.cmp(&pb) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal if pa != 0 => pa > 0,
+        std::cmp::Ordering::Equal => round % 2 == 1,
+    };
+    if a_white { (a.id, b.id) } else { (b.id, a.id) }
+}
+
+/// Pair a score group (ranked, `carried` floaters from above first) by
+/// folding: the top half meets the bottom half in order, each player taking
+/// the nearest allowed opponent from its fold partner on. Returns the pairs
+/// as (higher, lower ranked) indices and the players left to float down, in
+/// ranking order (`rank` of each index).
+fn pair_score_group(players: &[AiPersona], rank: &[usize], mut pool: Vec<usize>) -> (Vec<(usize, usize)>, Vec<usize>) {
+    let mut floaters = Vec::new();
+    if pool.len() % 2 == 1 {
+        floaters.push(pool.pop().unwrap());
+    }
+    let half = pool.len() / 2;
+    let (top, bottom) = pool.split_at(half);
+    let mut taken = vec![false; bottom.len()];
+    let mut pairs = Vec::with_capacity(half);
+    for (i, &a) in top.iter().enumerate() {
+        // Fold partner first, then further down, then back up
+        let candidate = (i..bottom.len())
+            .chain((0..i).rev())
+            .find(|&j| !taken[j] && can_pair(&players[a], &players[bottom[j]]));
+        match candidate {
+            Some(j) => {
+                taken[j] = true;
+                pairs.push((a, bottom[j]));
+            }
+            None => floaters.push(a),
+        }
+    }
+    floaters.extend(bottom.iter().zip(&taken).filter(|(_, &t)| !t).map(|(&b, _)| b));
+    floaters.sort_unstable_by_key(|&i| rank[i]);
+    (pairs, floaters)
+}
+
+/// Swiss pairings: score groups from the top, folded, with no rematches and
+/// colors kept balanced; whoever can't be paired in a group floats down to
+/// the next. With an odd field the lowest ranked player yet without a bye
+/// sits out for a point. The last floaters are paired among themselves,
+/// rematches allowed only if nothing else is left.
+pub(crate) fn swiss_pair(players: &[AiPersona], round: u32) -> Pairings {
+    let mut ranked: Vec<usize> = (0..players.len()).collect();
+    ranked.sort_by(|&a, &b| {
+        players[b]
+            .points
+            .partial_cmp(&players[a].points)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| {
+                players[b]
+                    .elo
+                    .partial_cmp(&players[a].elo)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| a.cmp(&b))
+    });
+    // Rank of each player index, to keep floaters in ranking order
+    let mut rank = vec![0; players.len()];
+    for (r, &i) in ranked.iter().enumerate() {
+        rank[i] = r;
+    }
+
+    let mut bye = None;
+    if ranked.len() % 2 == 1 {
+        let pos = ranked.iter().rposition(|&i| players[i].byes == 0).unwrap_or(ranked.len() - 1);
+        bye = Some(players[ranked.remove(pos)].id);
+    }
+
+    let mut pairs = Vec::with_capacity(ranked.len() / 2);
+    let mut floaters: Vec<usize> = Vec::new();
+    let mut start = 0;
+    while start < ranked.len() {
+        let points = players[ranked[start]].points;
+        let end = ranked[start..].iter().position(|&i| players[i].points != points).map_or(ranked.len(), |n| start + n);
+        // Floaters come first: they outrank the whole group
+        let pool = floaters.drain(..).chain(ranked[start..end].iter().copied()).collect();
+        let (group_pairs, left) = pair_score_group(players, &rank, pool);
+        pairs.extend(group_pairs);
+        floaters = left;
+        start = end;
+    }
+
+    // Whoever is still unpaired meets the nearest allowed player, or swaps
+    // into a pair made already (from the bottom up): a and b take c and d
+    let can = |x: usize, y: usize| can_pair(&players[x], &players[y]);
+    let by_rank = |x: usize, y: usize| if rank[x] < rank[y] { (x, y) } else { (y, x) };
+    let mut left = floaters;
+    while left.len() >= 2 {
+        let a = left.remove(0);
+        if let Some(j) = left.iter().position(|&b| can(a, b)) {
+            pairs.push((a, left.remove(j)));
+            continue;
+        }
+        let swap = (0..left.len()).find_map(|j| {
+            let b = left[j];
+            pairs.iter().rposition(|&(c, d)| (can(a, c) && can(b, d)) || (can(a, d) && can(b, c))).map(|k| (j, k))
+        });
+        match swap {
+            Some((j, k)) => {
+                let b = left.remove(j);
+                let (c, d) = pairs[k];
+                let (c, d) = if can(a, c) && can(b, d) { (c, d) } else { (d, c) };
+                pairs[k] = by_rank(a, c);
+                pairs.push(by_rank(b, d));
+            }
+            // A rematch only when nothing else is left
+            None => pairs.push((a, left.remove(0))),
+        }
+    }
+
+    Pairings {
+        pairs: pairs.into_iter().map(|(a, b)| assign_colors(&players[a], &players[b], round)).collect(),
+        bye,
+    }
+}
+
+// =============================================================================
+// ROUND-ROBIN AND GAUNTLET PAIRING
+// =============================================================================
+
+/// Rounds of a round-robin: one more for an odd field, where everyone sits
+/// out once
+pub(crate) fn round_robin_rounds(players: u32) -> u32 {
+    if players.is_multiple_of(2) { players.saturating_sub(1) } else { players }
+}
+
+/// Round `round` (from 1) of a round-robin, by the circle method: the last
+/// seat (the bye, in an odd field) stays put while the others rotate. Colors
+/// alternate, so nobody has either color three times in a row or more than
+/// once more than the other.
+pub(crate) fn round_robin_pair(players: u32, round: u32) -> Pairings {
+    let seats = players + players % 2;
+    let mut pairings = Pairings::default();
+    if seats < 2 {
+        return pairings;
+    }
+    let r = (round - 1) % (seats - 1);
+    for i in 0..seats / 2 {
+        let (a, b, a_white) = if i == 0 {
+            (r, seats - 1, r.is_multiple_of(2))
+        } else {
+            ((r + i) % (seats - 1), (r + seats - 1 - i) % (seats - 1), i.is_multiple_of(2))
+        };
+        if b == players {
+            pairings.bye = Some(a);
+        } else {
+            pairings.pairs.push(if a_white { (a, b) } else { (b, a) });
+        }
+    }
+    pairings
+}
+
+/// Gauntlet round: the candidate (player #0) meets everyone else, with white
+/// in odd rounds, so every two rounds make a color-reversed pair of games
+/// against each opponent
+pub(crate) fn gauntlet_pair(players: u32, round: u32) -> Pairings {
+    let pairs = (1..players).map(|id| if round % 2 == 1 { (0, id) } else { (id, 0) }).collect();
+    Pairings { pairs, bye: None }
+}
+
+// =============================================================================
+// KNOCKOUT BRACKET
+// =============================================================================
+
+/// Seeds (from 0) of a `size`-slot bracket in bracket order, so that the
+/// top seeds can only meet in the late rounds: 0, 7, 3, 4, 1, 6, 2, 5 for 8
+fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let slots = order.len() * 2;
+        order = order.iter().flat_map(|&seed| [seed, slots - 1 - seed]).collect();
+    }
+    order
+}
+
+/// A decided knockout match
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KnockoutMatch {
+    pub(crate) round: u32,
+    /// Higher and lower seeded player ids
+    pub(crate) high: u32,
+    pub(crate) low: u32,
+    pub(crate) winner: u32,
+    /// Games played: 2 when the first was drawn and replayed
+    pub(crate) games: u32,
+    /// Both games drawn: the higher seed went through
+    pub(crate) by_seed: bool,
+}
+
+/// Single-elimination bracket, seeded by rating; when the field isn't a
+/// power of two the top seeds get byes into the second round
+pub(crate) struct Bracket {
+    /// Players still in, in bracket order (None for a bye)
+    slots: Vec<Option<u32>>,
+    /// Seed of each player id, from 0
+    pub(crate) seeds: Vec<u32>,
+    pub(crate) matches: Vec<KnockoutMatch>,
+}
+
+impl Bracket {
+    pub(crate) fn new(players: &[AiPersona]) -> Bracket {
+        let mut ranked: Vec<u32> = (0..players.len() as u32).collect();
+        ranked.sort_by(|&a, &b| {
+            players[b as usize]
+                .elo
+                .partial_cmp(&players[a as usize].elo)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.cmp(&b))
+        });
+        let mut seeds = vec![0; players.len()];
+        for (seed, &id) in ranked.iter().enumerate() {
+            seeds[id as usize] = seed as u32;
+        }
+        let size = players.len().max(1).next_power_of_two();
+        let slots = bracket_order(size).into_iter().map(|seed| ranked.get(seed).copied()).collect();
+        Bracket { slots, seeds, matches: Vec::new() }
+    }
+
+    /// The winner, once only one player is left
+    pub(crate) fn champion(&self) -> Option<u32> {
+        if self.slots.len() == 1 { self.slots[0] } else { None }
+    }
+
+    /// (higher, lower) seeded of two players
+    fn by_seed(&self, a: u32, b: u32) -> (u32, u32) {
+        if self.seeds[a as usize] < self.seeds[b as usize] { (a, b) } else { (b, a) }
+    }
+
+    /// The round's games: the higher seed has white in odd rounds
+    pub(crate) fn pair(&self, round: u32) -> Pairings {
+        let pairs = self
+            .slots
+            .chunks(2)
+            .filter_map(|slots| match slots {
+                [Some(a), Some(b)] => {
+                    let (high, low) = self.by_seed(*a, *b);
+                    Some(if round % 2 == 1 { (high, low) } else { (low, high) })
+                }
+                _ => None,
+            })
+            .collect();
+        Pairings { pairs, bye: None }
+    }
+
+    /// Move on the winners of the round's matches, each decided by the
+    /// points the two players scored against each other over the round (a
+    /// drawn game and its replay, or an opening's twin games), and by seed
+    /// if they scored the same
+    pub(crate) fn advance(&mut self, round: u32, results: &[GameRecord]) {
+        let mut half_points: HashMap<u32, u32> = HashMap::new();
+        let mut games: HashMap<u32, u32> = HashMap::new();
+        for record in results {
+            let (white, black) = match record.result {
+                GameResult::WhiteWins => (2, 0),
+                GameResult::BlackWins => (0, 2),
+                GameResult::Draw => (1, 1),
+            };
+            *half_points.entry(record.white_id).or_default() += white;
+            *half_points.entry(record.black_id).or_default() += black;
+            for id in [record.white_id, record.black_id] {
+                *games.entry(id).or_default() += 1;
+            }
+        }
+        let mut next = Vec::with_capacity(self.slots.len() / 2);
+        for i in (0..self.slots.len()).step_by(2) {
+            let (a, b) = (self.slots[i], self.slots.get(i + 1).copied().flatten());
+            let (a, b) = match (a, b) {
+                (Some(a), Some(b)) => (a, b),
+                (player, None) | (None, player) => {
+                    next.push(player);
+                    continue;
+                }
+            };
+            let (high, low) = self.by_seed(a, b);
+            let points = |id: u32| half_points.get(&id).copied().unwrap_or(0);
+            let winner = match points(high).cmp(&points(low)) {
+                std::cmp::Ordering::Greater => Some(high),
+                std::cmp::Ordering::Less => Some(low),
+                std::cmp::Ordering::Equal => None,
+            };
+            self.matches.push(KnockoutMatch {
+                round,
+                high,
+                low,
+                winner: winner.unwrap_or(high),
+                games: games.get(&high).copied().unwrap_or(0),
+                by_seed: winner.is_none(),
+            });
+            next.push(Some(winner.unwrap_or(high)));
+        }
+        self.slots = next;
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apply_candidate, apply_result, gauntlet_pairs, generate_personas, knockout_round_name, round_robin_standings,
+        Format, Strategy,
+    };
+    use crate::tests::game;
+    use chess_engine::personality::Personality;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_swiss_pairing() {
+        let personas = generate_personas(10, 42);
+        let round = swiss_pair(&personas, 1);
+        assert_eq!(round.pairs.len(), 5);
+        assert_eq!(round.bye, None);
+    }
+
+    /// Play `rounds` Swiss rounds with random results, checking each round's
+    /// pairings; returns the players
+    fn simulate_swiss(count: u32, rounds: u32) -> Vec<AiPersona> {
+        let mut players = generate_personas(count, 7);
+        let mut rng = StdRng::seed_from_u64(11);
+        for round in 1..=rounds {
+            let Pairings { pairs, bye } = swiss_pair(&players, round);
+            let mut seen: Vec<u32> = pairs.iter().flat_map(|&(w, b)| [w, b]).chain(bye).collect();
+            seen.sort_unstable();
+            assert_eq!(seen, (0..count).collect::<Vec<_>>(), "Round {} doesn't seat everyone once", round);
+            if let Some(id) = bye {
+                players[id as usize].points += 1.0;
+                players[id as usize].byes += 1;
+            }
+            for (white_id, black_id) in pairs {
+                let result = [GameResult::WhiteWins, GameResult::BlackWins, GameResult::Draw][rng.gen_range(0..3)];
+                apply_result(&mut players, &game(round, white_id, black_id, result));
+            }
+        }
+        players
+    }
+
+    #[test]
+    fn test_swiss_no_rematches_and_balanced_colors() {
+        let players = simulate_swiss(16, 7);
+        for p in &players {
+            let mut opponents = p.opponents.clone();
+            opponents.sort_unstable();
+            opponents.dedup();
+            assert_eq!(opponents.len(), 7, "{} had a rematch: {:?}", p.name, p.opponents);
+            let whites = p.colors.iter().filter(|&&c| c == Color::White).count() as i32;
+            assert!((2 * whites - 7).abs() <= 3, "{} played {} whites of 7", p.name, whites);
+            assert!(!p.colors.windows(3).any(|w| w[0] == w[1] && w[1] == w[2]), "{}: {:?}", p.name, p.colors);
+        }
+    }
+
+    #[test]
+    fn test_swiss_byes() {
+        let players = simulate_swiss(9, 5);
+        assert_eq!(players.iter().map(|p| p.byes).sum::<u32>(), 5);
+        assert!(players.iter().all(|p| p.byes <= 1), "Someone sat out twice");
+
+        // The lowest ranked player sits out
+        let players = generate_personas(5, 3);
+        let lowest = players.iter().min_by(|a, b| a.elo.partial_cmp(&b.elo).unwrap()).unwrap().id;
+        assert_eq!(swiss_pair(&players, 1).bye, Some(lowest));
+    }
+
+    #[test]
+    fn test_swiss_folding_and_colors() {
+        // Round one: the top half meets the bottom half in ranking order
+        let players = generate_personas(8, 5);
+        let mut ranked: Vec<u32> = players.iter().map(|p| p.id).collect();
+        ranked.sort_by(|&a, &b| players[b as usize].elo.partial_cmp(&players[a as usize].elo).unwrap());
+        let round = swiss_pair(&players, 1);
+        for (i, &(white, black)) in round.pairs.iter().enumerate() {
+            assert_eq!((white, black), (ranked[i], ranked[i + 4]));
+        }
+
+        // A player due white gets it; two due the same color don't meet
+        let mut a = players[0].clone();
+        let mut b = players[1].clone();
+        a.colors = vec![Color::Black, Color::Black];
+        b.colors = vec![Color::White];
+        assert_eq!((color_preference(&a), color_preference(&b)), (3, -2));
+        assert_eq!(assign_colors(&b, &a, 3), (a.id, b.id));
+        b.colors = vec![Color::Black, Color::White, Color::Black, Color::Black];
+        assert!(!can_pair(&a, &b));
+        b.colors.clear();
+        assert!(can_pair(&a, &b));
+        a.opponents.push(b.id);
+        assert!(!can_pair(&a, &b));
+    }
+
+    #[test]
+    fn test_round_robin_pairing() {
+        for count in [2, 5, 8, 9] {
+            let rounds = Format::RoundRobin.rounds(count, 99);
+            assert_eq!(rounds, if count % 2 == 0 { count - 1 } else { count });
+            let mut players = generate_personas(count, 1);
+            let mut met = std::collections::HashSet::new();
+            for round in 1..=rounds {
+                let Pairings { pairs, bye } = round_robin_pair(count, round);
+                assert_eq!(bye.is_some(), count % 2 == 1);
+                if let Some(id) = bye {
+                    players[id as usize].byes += 1;
+                }
+                for (white_id, black_id) in pairs {
+                    assert!(met.insert((white_id.min(black_id), white_id.max(black_id))), "Rematch in round {}", round);
+                    apply_result(&mut players, &game(round, white_id, black_id, GameResult::Draw));
+                }
+            }
+            assert_eq!(met.len() as u32, count * (count - 1) / 2);
+            for p in &players {
+                let whites = p.colors.iter().filter(|&&c| c == Color::White).count() as i32;
+                assert!((2 * whites - p.colors.len() as i32).abs() <= 1, "{}: {:?}", p.name, p.colors);
+                assert!(!p.colors.windows(3).any(|w| w[0] == w[1] && w[1] == w[2]), "{}: {:?}", p.name, p.colors);
+                assert_eq!(p.byes, count % 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_robin_tie_break() {
+        // 0 beats 1, 1 beats 2, 2 beats 0, all beat 3 but 2, who draws: 0
+        // and 1 are level, 0 ahead on beating the better opponent
+        let mut players = generate_personas(4, 1);
+        for (white_id, black_id, result) in [
+            (0, 1, GameResult::WhiteWins),
+            (1, 2, GameResult::WhiteWins),
+            (2, 0, GameResult::WhiteWins),
+            (3, 0, GameResult::BlackWins),
+            (3, 1, GameResult::BlackWins),
+            (2, 3, GameResult::Draw),
+        ] {
+            apply_result(&mut players, &game(1, white_id, black_id, result));
+        }
+        assert_eq!(players[0].points, players[1].points);
+        let standings = round_robin_standings(&players);
+        assert_eq!(standings[0].0.id, 0);
+        assert_eq!((standings[0].1, standings[1].1), (2.0 + 0.5, 1.5 + 0.5));
+        assert_eq!(standings[1].0.id, 1);
+    }
+
+    #[test]
+    fn test_gauntlet() {
+        let mut players = generate_personas(4, 1);
+        apply_candidate(&mut players[0], "depth=4, personality=aggressive, blunder=0, strategy=search").unwrap();
+        assert_eq!((players[0].search_depth, players[0].personality), (4, Personality::Aggressive));
+        assert_eq!(players[0].strategy, Strategy::Search);
+        assert_eq!(players[0].blunder_rate, 0.0);
+        assert!(apply_candidate(&mut players[0], "depth=9").is_err());
+        assert!(apply_candidate(&mut players[0], "speed=1").is_err());
+
+        // Colors swap every round, so two rounds make a pair against everyone
+        assert_eq!(gauntlet_pair(4, 1).pairs, vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(gauntlet_pair(4, 2).pairs, vec![(1, 0), (2, 0), (3, 0)]);
+        for round in 1..=3 {
+            for (white_id, black_id) in gauntlet_pair(4, round).pairs {
+                let result = if white_id == 0 { GameResult::WhiteWins } else { GameResult::Draw };
+                apply_result(&mut players, &game(round, white_id, black_id, result));
+            }
+        }
+        // Round three's games have no partner yet
+        assert_eq!(gauntlet_pairs(&players[0]).counts, [0, 0, 0, 3, 0]);
+    }
+
+    #[test]
+    fn test_knockout_bracket() {
+        assert_eq!(bracket_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+        assert_eq!(Format::Knockout.rounds(6, 99), 3);
+
+        // Six players: the top two seeds skip the first round
+        let players = generate_personas(6, 1);
+        let mut bracket = Bracket::new(&players);
+        let seed = |s: u32| bracket.seeds.iter().position(|&x| x == s).unwrap() as u32;
+        let (first, second) = (seed(0), seed(1));
+        let round = bracket.pair(1);
+        assert_eq!(round.pairs.len(), 2);
+        assert!(round.pairs.iter().all(|&(w, b)| ![w, b].contains(&first) && ![w, b].contains(&second)));
+
+        // The higher seed loses one match and wins the other on seed
+        let (a, b) = round.pairs[0];
+        let (c, d) = round.pairs[1];
+        let results = [
+            game(1, a, b, GameResult::BlackWins),
+            game(1, c, d, GameResult::Draw),
+            game(1, d, c, GameResult::Draw),
+        ];
+        bracket.advance(1, &results);
+        assert_eq!(bracket.slots.len(), 4);
+        assert_eq!((bracket.matches[0].winner, bracket.matches[0].games, bracket.matches[0].by_seed), (b, 1, false));
+        assert_eq!((bracket.matches[1].winner, bracket.matches[1].games, bracket.matches[1].by_seed), (c, 2, true));
+        assert!(bracket.slots.contains(&Some(first)) && bracket.slots.contains(&Some(second)));
+
+        // Higher seeds win from here on
+        for round in 2..=3 {
+            let results: Vec<GameRecord> = bracket
+                .pair(round)
+                .pairs
+                .iter()
+                .map(|&(w, b)| {
+                    let result = if bracket.seeds[w as usize] < bracket.seeds[b as usize] {
+                        GameResult::WhiteWins
+                    } else {
+                        GameResult::BlackWins
+                    };
+                    game(round, w, b, result)
+                })
+                .collect();
+            assert!(results.iter().all(|r| bracket.by_seed(r.white_id, r.black_id).0 == r.white_id) == (round % 2 == 1));
+            bracket.advance(round, &results);
+        }
+        assert_eq!(bracket.champion(), Some(first));
+        assert_eq!(bracket.matches.len(), 5);
+        assert_eq!(knockout_round_name(3, 3), "Final");
+        assert_eq!(knockout_round_name(1, 5), "Round 1");
+    }
+
+    #[test]
+    fn test_knockout_twin_games() {
+        let players = generate_personas(2, 1);
+        let mut bracket = Bracket::new(&players);
+        let (high, low) = bracket.by_seed(0, 1);
+        // A win each goes to the higher seed, a win and a draw to its winner
+        bracket.advance(1, &[game(1, high, low, GameResult::BlackWins), game(1, low, high, GameResult::BlackWins)]);
+        assert_eq!((bracket.champion(), bracket.matches[0].by_seed), (Some(high), true));
+
+        let mut bracket = Bracket::new(&players);
+        bracket.advance(1, &[game(1, high, low, GameResult::Draw), game(1, low, high, GameResult::WhiteWins)]);
+        assert_eq!((bracket.champion(), bracket.matches[0].games), (Some(low), 2));
+    }
+}
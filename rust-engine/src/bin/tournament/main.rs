@@ -0,0 +1,2868 @@
+// =============================================================================
+// 1-Million AI Tournament Runner
+// =============================================================================
+// Runs a chess tournament with configurable AI personas: Swiss system by
+// default, or a round-robin, a gauntlet (player #0 against the field) or a
+// seeded knockout (--format).
+// Each AI has unique personality traits (depth, playing style, opening preference).
+// Supports A/B testing: half get reward bonuses, half don't.
+// Every game's moves are stored, so games can be written as PGN while the
+// tournament runs (--pgn-out) or exported from the database afterwards (replay).
+// With an opening suite (--openings) every pairing plays both colors of an opening.
+// Games can be spread over several machines (--serve and --worker), and a long
+// run watched in a browser (--dashboard).
+// Pairings live in formats.rs, the results database in db.rs, distributed play
+// in distributed.rs, the dashboard in dashboard.rs and the replay and report
+// subcommands in report.rs.
+//
+// Usage:
+//   cargo run --release --bin tournament -- --players 1000 --rounds 10
+//   cargo run --release --bin tournament -- --players 1000000 --rounds 20
+//   cargo run --release --bin tournament -- --players 100 --pgn-out games.pgn
+//   cargo run --release --bin tournament -- --players 1000 --time-control 10+0.1
+//   cargo run --release --bin tournament -- --players 1000 --openings openings.epd
+//   cargo run --release --bin tournament -- --players 1000 --resign-score 600 --draw-score 15
+//   cargo run --release --bin tournament -- --format gauntlet --players 200 --candidate depth=3
+//   cargo run --release --bin tournament -- --players 1000 --strategies noisy=8,material=1,random=1
+//   cargo run --release --bin tournament -- --players 1000 --variant three-check
+//   cargo run --release --bin tournament -- --resume tournament_results.db
+//   cargo run --release --bin tournament -- --players 1000000 --serve 0.0.0.0:7878
+//   cargo run --release --bin tournament -- --worker coordinator-host:7878
+//   cargo run --release --bin tournament -- --players 100000 --dashboard 127.0.0.1:8090
+//   cargo run --release --bin tournament -- replay --db tournament_results.db --round 3 -o round3.pgn
+//   cargo run --release --bin tournament -- replay --db tournament_results.db --game 1234
+//   cargo run --release --bin tournament -- report --db tournament_results.db --csv report/
+// =============================================================================
+
+use chess_engine::book::{probe, read_book, BookEntry};
+use chess_engine::elo::{GameCounts, Pentanomial, Z_95};
+use chess_engine::epd::parse_epd;
+use chess_engine::eval::piece_value;
+use chess_engine::movegen::{generate_legal_moves, MoveList};
+use chess_engine::personality::Personality;
+use chess_engine::pgn::parse_pgn_games;
+use chess_engine::position::Position;
+use chess_engine::san::parse_san;
+use chess_engine::search::{
+    search_multipv, search_multipv_with_control, search_with_control, search_with_tt, PvLine, SearchControl,
+};
+use chess_engine::timeman::TimeLimits;
+use chess_engine::tt::TranspositionTable;
+use chess_engine::types::{Color, Move, PieceType};
+use chess_engine::variant::Variant;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rayon::prelude::*;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::Arc;
+use std::time::Instant;
+
+mod dashboard;
+mod db;
+mod distributed;
+mod formats;
+mod report;
+
+use crate::dashboard::{serve_dashboard, tournament_status, Dashboard};
+use crate::db::{
+    apply_bye, checkpointed_results, color_results, init_database, load_checkpoint, load_players, load_settings,
+    restore_rounds, save_players, save_settings, termination_counts, Checkpoint, DbWriter, RoundSummary, RoundWrite,
+    StoredGame,
+};
+use crate::distributed::{run_worker, serve, GameJob, PlayerSpec, RoundQueue};
+use crate::formats::{gauntlet_pair, round_robin_pair, round_robin_rounds, swiss_pair, Bracket, Pairings};
+use crate::report::{replay, replay_game, report};
+
+// =============================================================================
+// CLI ARGUMENTS
+// =============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "tournament", about = "1M AI Chess Tournament Runner")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Number of AI players
+    #[arg(short, long, default_value_t = 1000)]
+    players: u32,
+
+    /// Tournament format
+    #[arg(long, value_enum, default_value_t = Format::Swiss)]
+    format: Format,
+
+    /// Rating system
+    #[arg(long, value_enum, default_value_t = RatingSystem::Elo)]
+    rating_system: RatingSystem,
+
+    /// Number of Swiss rounds, or of passes through the field in a gauntlet
+    /// (round-robin and knockout play as many rounds as they need)
+    #[arg(short, long, default_value_t = 10)]
+    rounds: u32,
+
+    /// Gauntlet candidate settings for player #0, as key=value pairs: depth
+    /// (1-4), personality, blunder (0-1), budget (depth or clock), strategy,
+    /// e.g. "depth=3,blunder=0.02"
+    #[arg(long)]
+    candidate: Option<String>,
+
+    /// Output database path
+    #[arg(short, long, default_value = "tournament_results.db")]
+    output: String,
+
+    /// Random seed for reproducibility
+    #[arg(short, long, default_value_t = 42)]
+    seed: u64,
+
+    /// Max moves per game before declaring draw
+    #[arg(long, default_value_t = 200)]
+    max_moves: u32,
+
+    /// Rules every game is played under: standard, early-promotion,
+    /// capture-limited or three-check
+    #[arg(long, default_value = "standard", value_parser = parse_variant)]
+    variant: Variant,
+
+    /// Play on a clock, "<seconds>+<increment>" (e.g. 10+0.1): players with
+    /// the clock search budget think as long as it allows, and a flag loses.
+    /// Without one every player searches to its fixed depth.
+    #[arg(long, value_parser = TimeControl::parse)]
+    time_control: Option<TimeControl>,
+
+    /// Resign a game once both players' searches put one side this many
+    /// centipawns ahead for --resign-moves moves each (off by default)
+    #[arg(long, value_name = "CP")]
+    resign_score: Option<i32>,
+
+    #[arg(long, default_value_t = 3)]
+    resign_moves: u32,
+
+    /// Draw a game once both players' searches stay within this many
+    /// centipawns of equal for --draw-moves moves each, from move
+    /// --draw-after on (off by default)
+    #[arg(long, value_name = "CP")]
+    draw_score: Option<i32>,
+
+    #[arg(long, default_value_t = 8)]
+    draw_moves: u32,
+
+    #[arg(long, default_value_t = 40)]
+    draw_after: u32,
+
+    /// Number of threads (0 = all cores)
+    #[arg(short, long, default_value_t = 0)]
+    threads: usize,
+
+    /// Give every player this playing style instead of a random one
+    /// (balanced, aggressive, positional, materialist, gambiteer)
+    #[arg(long)]
+    personality: Option<String>,
+
+    /// Share out move strategies among the players, as name=weight pairs:
+    /// search (always the best move), noisy (eval noise by blunder rate,
+    /// the default), material (grabs material) or random, e.g.
+    /// "noisy=8,material=1,random=1"
+    #[arg(long, value_name = "MIX", value_parser = StrategyMix::parse)]
+    strategies: Option<StrategyMix>,
+
+    /// Also write every game to this PGN file, round by round
+    #[arg(long)]
+    pgn_out: Option<String>,
+
+    /// Opening suite: every pairing plays each opening twice, colors
+    /// reversed. EPD/FEN lines, a PGN file (the position after each game's
+    /// moves) or a book from the book tool (.bin, random lines of
+    /// --book-plies moves). Polyglot books key positions differently and are
+    /// rejected.
+    #[arg(long, value_name = "FILE")]
+    openings: Option<String>,
+
+    /// Length of the lines drawn from a --openings book
+    #[arg(long, default_value_t = 8)]
+    book_plies: u32,
+
+    /// Carry on the interrupted tournament in this database from its first
+    /// unfinished round, with the settings it was started with
+    #[arg(long, value_name = "DB")]
+    resume: Option<String>,
+
+    /// Save the finished games of the round being played every this many
+    /// games, so a resumed run only replays those since (0: only whole rounds)
+    #[arg(long, default_value_t = 1000, value_name = "GAMES")]
+    checkpoint_every: usize,
+
+    /// Coordinate a distributed tournament: accept workers on this address
+    /// (e.g. 0.0.0.0:7878), who play games alongside the local threads
+    #[arg(long, value_name = "ADDR", conflicts_with = "worker")]
+    serve: Option<String>,
+
+    /// Play games for the coordinator at this address instead of running a
+    /// tournament (the coordinator sets the rules; --threads is this machine's)
+    #[arg(long, value_name = "ADDR")]
+    worker: Option<String>,
+
+    /// Serve live progress on this address (e.g. 127.0.0.1:8090): a page at
+    /// / and standings, games/s, round progress and A/B metrics as JSON at
+    /// /status
+    #[arg(long, value_name = "ADDR")]
+    dashboard: Option<String>,
+
+    /// Syzygy tablebase directories, separated by ':' (';' on Windows)
+    #[cfg(feature = "syzygy")]
+    #[arg(long)]
+    syzygy_path: Option<String>,
+
+    /// Minimum remaining depth for probing positions with the maximum tablebase piece count
+    #[cfg(feature = "syzygy")]
+    #[arg(long, default_value_t = chess_engine::syzygy::DEFAULT_PROBE_DEPTH)]
+    syzygy_probe_depth: u8,
+}
+
+/// How ratings follow results
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum RatingSystem {
+    /// Elo, updated after every game with a fixed K-factor
+    Elo,
+    /// Glicko-2, updated every round, with a deviation and volatility per player
+    Glicko2,
+}
+
+impl RatingSystem {
+    fn name(&self) -> &str {
+        match self {
+            RatingSystem::Elo => "elo",
+            RatingSystem::Glicko2 => "glicko2",
+        }
+    }
+}
+
+impl Args {
+    fn game_rules(&self) -> GameRules {
+        let adjudication = Adjudication {
+            resign_cp: self.resign_score,
+            resign_moves: self.resign_moves,
+            draw_cp: self.draw_score,
+            draw_moves: self.draw_moves,
+            draw_after: self.draw_after,
+        };
+        GameRules { max_moves: self.max_moves, variant: self.variant, time_control: self.time_control, adjudication }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    /// Players with similar scores meet each round
+    Swiss,
+    /// Everyone meets everyone once
+    RoundRobin,
+    /// Player #0 meets the rest of the field every round
+    Gauntlet,
+    /// Single elimination, seeded by rating
+    Knockout,
+}
+
+impl Format {
+    fn name(&self) -> &str {
+        match self {
+            Format::Swiss => "Swiss",
+            Format::RoundRobin => "Round-robin",
+            Format::Gauntlet => "Gauntlet",
+            Format::Knockout => "Knockout",
+        }
+    }
+
+    /// Rounds to play with `players` players and `--rounds rounds`
+    fn rounds(&self, players: u32, rounds: u32) -> u32 {
+        match self {
+            Format::Swiss | Format::Gauntlet => rounds,
+            Format::RoundRobin => round_robin_rounds(players),
+            Format::Knockout => players.max(1).next_power_of_two().trailing_zeros(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Export games stored in a results database as PGN
+    Replay {
+        /// Results database
+        #[arg(long, default_value = "tournament_results.db")]
+        db: String,
+
+        /// Only games of this round
+        #[arg(long)]
+        round: Option<u32>,
+
+        /// Only games of this player id
+        #[arg(long)]
+        player: Option<u32>,
+
+        /// Step through this one game: the board after every move with its
+        /// evaluation (PGN only goes to --output)
+        #[arg(long, conflicts_with_all = ["round", "player"])]
+        game: Option<i64>,
+
+        /// Search depth of the evaluations shown with --game
+        #[arg(long, default_value_t = 8)]
+        depth: u8,
+
+        /// PGN file to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Print statistics tables from a results database
+    Report {
+        /// Results database
+        #[arg(long, default_value = "tournament_results.db")]
+        db: String,
+
+        /// Top finishers in the head-to-head matrix
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Also write each table as a CSV file into this directory
+        #[arg(long)]
+        csv: Option<String>,
+
+        /// Also write the whole report as JSON to this file
+        #[arg(long)]
+        json: Option<String>,
+    },
+}
+
+// =============================================================================
+// AI PERSONA
+// =============================================================================
+
+/// Opening preference — which first move the AI favors
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OpeningStyle {
+    KingPawn,  // 1. e4  (tactical, open games)
+    QueenPawn, // 1. d4  (positional, closed games)
+    English,   // 1. c4  (flexible, hypermodern)
+    Nf3,       // 1. Nf3 (transpositional)
+    Random,    // Pick any legal first move
+}
+
+impl OpeningStyle {
+    fn preferred_first_move(&self) -> &str {
+        match self {
+            OpeningStyle::KingPawn => "e2e4",
+            OpeningStyle::QueenPawn => "d2d4",
+            OpeningStyle::English => "c2c4",
+            OpeningStyle::Nf3 => "g1f3",
+            OpeningStyle::Random => "",
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            OpeningStyle::KingPawn => "King's Pawn",
+            OpeningStyle::QueenPawn => "Queen's Pawn",
+            OpeningStyle::English => "English",
+            OpeningStyle::Nf3 => "Reti",
+            OpeningStyle::Random => "Random",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<OpeningStyle> {
+        [
+            OpeningStyle::KingPawn,
+            OpeningStyle::QueenPawn,
+            OpeningStyle::English,
+            OpeningStyle::Nf3,
+            OpeningStyle::Random,
+        ]
+        .into_iter()
+        .find(|style| style.name() == name)
+    }
+}
+
+/// What limits a persona's search under a time control: its fixed depth,
+/// or its clock (iterative deepening until the time manager stops it)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchBudget {
+    Depth,
+    Clock,
+}
+
+impl SearchBudget {
+    fn name(&self) -> &str {
+        match self {
+            SearchBudget::Depth => "depth",
+            SearchBudget::Clock => "clock",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<SearchBudget> {
+        [SearchBudget::Depth, SearchBudget::Clock].into_iter().find(|b| b.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// How a persona chooses its moves, once out of its book
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Strategy {
+    /// Always the best move found
+    Search,
+    /// The best move by scores blurred with noise scaled by the blunder rate
+    Noisy,
+    /// Whatever wins the most material on the spot
+    Material,
+    /// Any legal move
+    Random,
+}
+
+impl Strategy {
+    const ALL: [Strategy; 4] = [Strategy::Search, Strategy::Noisy, Strategy::Material, Strategy::Random];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Strategy::Search => "search",
+            Strategy::Noisy => "noisy",
+            Strategy::Material => "material",
+            Strategy::Random => "random",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Strategy> {
+        Strategy::ALL.into_iter().find(|s| s.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn moves(&self) -> &'static dyn MoveStrategy {
+        match self {
+            Strategy::Search => &PureSearch,
+            Strategy::Noisy => &NoisySearch,
+            Strategy::Material => &MaterialGrabber,
+            Strategy::Random => &RandomMove,
+        }
+    }
+}
+
+/// Shares of the field playing each strategy, e.g. "noisy=8,random=2"
+#[derive(Debug, Clone, PartialEq)]
+struct StrategyMix(Vec<(Strategy, u32)>);
+
+impl StrategyMix {
+    fn parse(spec: &str) -> Result<StrategyMix, String> {
+        let mut mix = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, weight) = entry.split_once('=').unwrap_or((entry, "1"));
+            let strategy = Strategy::from_name(name).ok_or_else(|| format!("unknown strategy: {}", name.trim()))?;
+            let weight = weight.trim().parse().map_err(|_| format!("invalid weight: {}", entry))?;
+            mix.push((strategy, weight));
+        }
+        if mix.iter().all(|&(_, weight)| weight == 0) {
+            return Err(format!("no strategy with a weight: {}", spec));
+        }
+        Ok(StrategyMix(mix))
+    }
+
+    fn describe(&self) -> String {
+        self.0.iter().map(|(strategy, weight)| format!("{}={}", strategy.name(), weight)).collect::<Vec<_>>().join(",")
+    }
+
+    /// Give each player a strategy drawn by weight
+    fn assign(&self, players: &mut [AiPersona], seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let weights = WeightedIndex::new(self.0.iter().map(|&(_, weight)| weight)).expect("mix has a weight");
+        for player in players {
+            player.strategy = self.0[weights.sample(&mut rng)].0;
+        }
+    }
+}
+
+/// AI personality traits
+#[derive(Debug, Clone)]
+struct AiPersona {
+    id: u32,
+    name: String,
+    elo: f64,
+    /// Rating before the first round
+    initial_elo: f64,
+    /// Glicko-2 uncertainty of `elo`, under that rating system
+    rating_deviation: f64,
+    volatility: f64,
+    search_depth: u8,
+    aggression: f64,
+    personality: Personality,
+    opening_style: OpeningStyle,
+    /// Lack of skill: scales the noise on the persona's move scores
+    blunder_rate: f64,
+    search_budget: SearchBudget,
+    strategy: Strategy,
+    group: Group,
+
+    // Accumulated stats
+    wins: u32,
+    losses: u32,
+    draws: u32,
+    total_moves_played: u64,
+    total_game_length: u64,
+    games_as_white: u32,
+    games_as_black: u32,
+    blunders_made: u32,
+    /// Centipawns lost, over the moves made
+    cp_loss: u64,
+    moves_made: u64,
+    points: f64,
+    /// Thinking time over all games
+    time_used_ms: u64,
+    time_forfeits: u32,
+
+    // Pairing history
+    /// Ids of the players met so far
+    opponents: Vec<u32>,
+    /// Colors played so far, in order
+    colors: Vec<Color>,
+    /// Points scored in each game, in order
+    scores: Vec<f64>,
+    byes: u32,
+}
+
+impl AiPersona {
+    /// A balanced depth-1 player with no games played yet
+    fn new(id: u32, name: String, elo: f64, group: Group) -> AiPersona {
+        AiPersona {
+            id,
+            name,
+            elo,
+            initial_elo: elo,
+            rating_deviation: GLICKO_DEVIATION,
+            volatility: GLICKO_VOLATILITY,
+            search_depth: 1,
+            aggression: 0.5,
+            personality: Personality::Balanced,
+            opening_style: OpeningStyle::Random,
+            blunder_rate: 0.0,
+            search_budget: SearchBudget::Depth,
+            strategy: Strategy::Noisy,
+            group,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            total_moves_played: 0,
+            total_game_length: 0,
+            games_as_white: 0,
+            games_as_black: 0,
+            blunders_made: 0,
+            cp_loss: 0,
+            moves_made: 0,
+            points: 0.0,
+            time_used_ms: 0,
+            time_forfeits: 0,
+            opponents: Vec::new(),
+            colors: Vec::new(),
+            scores: Vec::new(),
+            byes: 0,
+        }
+    }
+
+    /// Average centipawns lost per move made
+    fn avg_cp_loss(&self) -> f64 {
+        if self.moves_made == 0 { 0.0 } else { self.cp_loss as f64 / self.moves_made as f64 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Group {
+    Control,
+    Rewards,
+}
+
+impl Group {
+    /// Name in the test_group column
+    fn name(&self) -> &str {
+        match self {
+            Group::Control => "control",
+            Group::Rewards => "rewards",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Group> {
+        [Group::Control, Group::Rewards].into_iter().find(|group| group.name() == name)
+    }
+}
+
+// =============================================================================
+// NAME GENERATOR
+// =============================================================================
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Alice", "Atlas", "Aurora", "Axel", "Blaze", "Boris", "Cass", "Chess",
+    "Clara", "Cosmo", "Cyrus", "Dante", "Echo", "Elara", "Felix", "Freya", "Garry", "Gemma",
+    "Hank", "Hugo", "Iris", "Ivan", "Jade", "Juno", "Kai", "Knox", "Luna", "Leo", "Magnus",
+    "Mira", "Neo", "Nina", "Odin", "Orion", "Petra", "Quinn", "Rex", "Rook", "Sage", "Sable",
+    "Tal", "Titan", "Uma", "Vex", "Wren", "Xena", "Yuki", "Zara", "Ash", "Bishop", "Cleo",
+    "Drake", "Eve", "Fang", "Grit", "Hawk", "Ion", "Jinx", "Koda", "Lynx", "Mars", "Nyx",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Alpha", "Bitboard", "Castle", "Deepblue", "Endgame", "Fischer", "Gambit", "Hydra",
+    "Inverse", "Junior", "Kasparov", "Lambda", "Magnus", "Node", "Omega", "Pawn", "Quasar",
+    "Rybka", "Stockfish", "Turbo", "Ultra", "Vector", "Warp", "Xray", "Yankee", "Zenith",
+    "Blitz", "Capture", "Diagonal", "Engine", "Fork", "Granite", "Hash", "Iron", "Joker",
+    "Knight", "Laser", "Matrix", "Neutron", "Oxide", "Prism", "Quantum", "Razor", "Silicon",
+    "Thunder", "Uranium", "Viper", "Wolfram", "Xerxes", "Yggdrasil", "Zephyr", "Anvil",
+    "Bronze", "Chrome", "Delta", "Ember", "Flux", "Grid", "Hex", "Ivory", "Jet", "Krypton",
+    "Lux", "Mako", "Neon",
+];
+
+fn generate_name(rng: &mut impl Rng, id: u32) -> String {
+    let first = FIRST_NAMES[rng.gen_range(0..FIRST_NAMES.len())];
+    let last = LAST_NAMES[rng.gen_range(0..LAST_NAMES.len())];
+    format!("{} {} #{}", first, last, id)
+}
+
+// =============================================================================
+// PERSONA GENERATOR
+// =============================================================================
+
+fn generate_personas(count: u32, seed: u64) -> Vec<AiPersona> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let opening_styles = [
+        OpeningStyle::KingPawn,
+        OpeningStyle::QueenPawn,
+        OpeningStyle::English,
+        OpeningStyle::Nf3,
+        OpeningStyle::Random,
+    ];
+    let opening_weights = WeightedIndex::new([35, 30, 15, 10, 10]).unwrap();
+    // Same order as Personality::ALL: balanced, aggressive, positional, materialist, gambiteer
+    let personality_weights = WeightedIndex::new([40, 15, 20, 15, 10]).unwrap();
+
+    (0..count)
+        .map(|id| {
+            let search_depth = match rng.gen_range(0..100u32) {
+                0..=30 => 1,
+                31..=65 => 2,
+                66..=90 => 3,
+                _ => 4,
+            };
+
+            let aggression = rng.gen_range(0.1..=1.0_f64);
+            let blunder_rate = match search_depth {
+                1 => rng.gen_range(0.10..=0.30),
+                2 => rng.gen_range(0.05..=0.15),
+                3 => rng.gen_range(0.01..=0.08),
+                _ => rng.gen_range(0.00..=0.03),
+            };
+
+            let group = if id < count / 2 {
+                Group::Control
+            } else {
+                Group::Rewards
+            };
+
+            let base_elo = match search_depth {
+                1 => 600.0,
+                2 => 1000.0,
+                3 => 1400.0,
+                _ => 1800.0,
+            };
+            let elo = base_elo + rng.gen_range(-200.0..200.0);
+
+            let name = generate_name(&mut rng, id);
+            AiPersona {
+                search_depth,
+                aggression,
+                personality: Personality::ALL[personality_weights.sample(&mut rng)],
+                opening_style: opening_styles[opening_weights.sample(&mut rng)],
+                blunder_rate,
+                // Half search to their depth whatever the clock, half by the clock
+                search_budget: if rng.gen_bool(0.5) { SearchBudget::Clock } else { SearchBudget::Depth },
+                ..AiPersona::new(id, name, elo, group)
+            }
+        })
+        .collect()
+}
+
+/// Apply gauntlet candidate settings ("depth=3,personality=aggressive,
+/// blunder=0.02,budget=clock,strategy=search") to `persona`
+fn apply_candidate(persona: &mut AiPersona, spec: &str) -> Result<(), String> {
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (key, value) = entry.split_once('=').ok_or_else(|| format!("Expected key=value: {}", entry))?;
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        let invalid = || format!("Invalid value for {}: {}", key, value);
+        match key.as_str() {
+            "depth" => persona.search_depth = value.parse().ok().filter(|d| (1..=4).contains(d)).ok_or_else(invalid)?,
+            "personality" => persona.personality = Personality::from_name(value).ok_or_else(invalid)?,
+            "blunder" => {
+                persona.blunder_rate = value.parse().ok().filter(|b| (0.0..=1.0).contains(b)).ok_or_else(invalid)?
+            }
+            "budget" => persona.search_budget = SearchBudget::from_name(value).ok_or_else(invalid)?,
+            "strategy" => persona.strategy = Strategy::from_name(value).ok_or_else(invalid)?,
+            _ => return Err(format!("Unknown key: {}", key)),
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// ELO CALCULATION
+// =============================================================================
+
+const K_FACTOR: f64 = 32.0;
+
+fn elo_expected(player_elo: f64, opponent_elo: f64) -> f64 {
+    1.0 / (1.0 + 10.0_f64.powf((opponent_elo - player_elo) / 400.0))
+}
+
+fn elo_update(player_elo: f64, opponent_elo: f64, score: f64) -> f64 {
+    let expected = elo_expected(player_elo, opponent_elo);
+    player_elo + K_FACTOR * (score - expected)
+}
+
+// =============================================================================
+// GLICKO-2
+// =============================================================================
+
+/// Rating deviation and volatility of a player before any game
+const GLICKO_DEVIATION: f64 = 350.0;
+const GLICKO_VOLATILITY: f64 = 0.06;
+/// System constant: how much volatility may change in a rating period
+const GLICKO_TAU: f64 = 0.5;
+/// Ratio of the Glicko (Elo-like) scale to Glicko-2's internal one
+const GLICKO_SCALE: f64 = 173.7178;
+
+/// A Glicko-2 rating on the Glicko scale: rating and deviation in Elo-like
+/// points
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Glicko {
+    rating: f64,
+    deviation: f64,
+    volatility: f64,
+}
+
+/// Weight of a result against an opponent as uncertain as `phi`
+fn glicko_weight(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (std::f64::consts::PI * std::f64::consts::PI)).sqrt()
+}
+
+/// Rating after a rating period whose games scored `results` against
+/// opponents rated as at its start (Glickman's algorithm). A player without
+/// games only grows less certain, up to the deviation of a new player.
+fn glicko2_update(player: Glicko, results: &[(Glicko, f64)]) -> Glicko {
+    let phi = player.deviation / GLICKO_SCALE;
+    if results.is_empty() {
+        let deviation = (phi * phi + player.volatility * player.volatility).sqrt() * GLICKO_SCALE;
+        return Glicko { deviation: deviation.min(GLICKO_DEVIATION), ..player };
+    }
+    let mu = (player.rating - 1500.0) / GLICKO_SCALE;
+    let (mut information, mut improvement) = (0.0, 0.0);
+    for (opponent, score) in results {
+        let weight = glicko_weight(opponent.deviation / GLICKO_SCALE);
+        let expected = 1.0 / (1.0 + (-weight * (mu - (opponent.rating - 1500.0) / GLICKO_SCALE)).exp());
+        information += weight * weight * expected * (1.0 - expected);
+        improvement += weight * (score - expected);
+    }
+    let v = 1.0 / information;
+    let delta = v * improvement;
+
+    // New volatility: the root of f, by the Illinois algorithm
+    let a = (player.volatility * player.volatility).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let d = phi * phi + v + ex;
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * d * d) - (x - a) / (GLICKO_TAU * GLICKO_TAU)
+    };
+    let mut lo = a;
+    let mut hi = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * GLICKO_TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * GLICKO_TAU
+    };
+    let (mut f_lo, mut f_hi) = (f(lo), f(hi));
+    while (hi - lo).abs() > 1e-6 {
+        let c = lo + (lo - hi) * f_lo / (f_hi - f_lo);
+        let f_c = f(c);
+        if f_c * f_hi <= 0.0 {
+            (lo, f_lo) = (hi, f_hi);
+        } else {
+            f_lo /= 2.0;
+        }
+        (hi, f_hi) = (c, f_c);
+    }
+    let volatility = (lo / 2.0).exp();
+
+    let phi_star = (phi * phi + volatility * volatility).sqrt();
+    let phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let mu = mu + phi * phi * improvement;
+    Glicko { rating: mu * GLICKO_SCALE + 1500.0, deviation: phi * GLICKO_SCALE, volatility }
+}
+
+/// Rate a round's games as one rating period: `start` holds every player's
+/// rating at its start (ratings move game by game as Elo in `apply_result`)
+fn rate_glicko2_round(players: &mut [AiPersona], start: &[f64], records: &[GameRecord]) {
+    let glicko = |p: &AiPersona| Glicko {
+        rating: start[p.id as usize],
+        deviation: p.rating_deviation,
+        volatility: p.volatility,
+    };
+    let mut results: HashMap<u32, Vec<(Glicko, f64)>> = HashMap::new();
+    for record in records {
+        let (white, black) = (&players[record.white_id as usize], &players[record.black_id as usize]);
+        let white_score = match record.result {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+        };
+        results.entry(white.id).or_default().push((glicko(black), white_score));
+        results.entry(black.id).or_default().push((glicko(white), 1.0 - white_score));
+    }
+    let rated: Vec<Glicko> = players
+        .iter()
+        .map(|p| glicko2_update(glicko(p), results.get(&p.id).map_or(&[][..], |r| r.as_slice())))
+        .collect();
+    for (player, rating) in players.iter_mut().zip(rated) {
+        player.elo = rating.rating;
+        player.rating_deviation = rating.deviation;
+        player.volatility = rating.volatility;
+    }
+}
+
+// =============================================================================
+// GAME RESULT
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// PGN result
+    fn as_str(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    fn from_pgn(result: &str) -> Option<GameResult> {
+        [GameResult::WhiteWins, GameResult::BlackWins, GameResult::Draw].into_iter().find(|r| r.as_str() == result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct GameRecord {
+    round: u32,
+    white_id: u32,
+    black_id: u32,
+    result: GameResult,
+    moves: u32,
+    /// Every move of the game
+    moves_uci: Vec<String>,
+    termination: String,
+    /// Moves losing BLUNDER_CP or more, and centipawns lost over the game,
+    /// per side
+    white_blunders: u32,
+    black_blunders: u32,
+    white_cp_loss: u64,
+    black_cp_loss: u64,
+    /// Thinking time of each side over the game
+    white_time_ms: u64,
+    black_time_ms: u64,
+    /// Starting position, when the game began from an opening of the suite
+    start_fen: Option<String>,
+}
+
+impl GameRecord {
+    /// The first four moves, as stored in the opening_uci column (none for
+    /// a game from a suite opening, which didn't start from the start position)
+    fn opening_uci(&self) -> Option<String> {
+        let moves = self.moves_uci.iter().take(4).cloned().collect::<Vec<_>>().join(" ");
+        self.start_fen.is_none().then_some(moves)
+    }
+
+    fn black_started(&self) -> bool {
+        self.start_fen.as_deref().and_then(|fen| fen.split_whitespace().nth(1)) == Some("b")
+    }
+
+    /// The side to move when the game ended, who lost a time forfeit
+    fn last_to_move(&self) -> Color {
+        if self.black_started() == self.moves.is_multiple_of(2) { Color::Black } else { Color::White }
+    }
+
+    /// Moves made by White and by Black
+    fn moves_by_side(&self) -> [u32; 2] {
+        let (first, second) = (self.moves.div_ceil(2), self.moves / 2);
+        if self.black_started() { [second, first] } else { [first, second] }
+    }
+}
+
+// =============================================================================
+// MOVE STRATEGIES
+// =============================================================================
+
+/// Standard deviation of the eval noise, in centipawns, per unit of a
+/// persona's blunder rate (its lack of skill)
+const NOISE_CP: f64 = 1000.0;
+/// Best root moves a persona with eval noise chooses among
+const NOISY_LINES: usize = 5;
+/// Most centipawns a single move counts as losing (mate scores aside)
+const MAX_CP_LOSS: i32 = 1000;
+/// Centipawn loss that makes a move a blunder
+const BLUNDER_CP: u32 = 300;
+
+/// A persona's move, and what its search made of it
+#[derive(Debug, Clone, Copy)]
+struct Choice {
+    mv: Move,
+    /// Score of the best move found, for the side to move; None for a move
+    /// played without a search
+    score: Option<i32>,
+    /// Centipawns given away against the best move found
+    cp_loss: u32,
+}
+
+impl Choice {
+    /// A move played without a search
+    fn unsearched(mv: Move) -> Choice {
+        Choice { mv, score: None, cp_loss: 0 }
+    }
+}
+
+/// Everything a strategy may look at to choose a move
+struct MoveContext<'a> {
+    pos: &'a mut Position,
+    legal_moves: &'a MoveList,
+    persona: &'a AiPersona,
+    /// The persona's first move of a game from the standard position
+    own_opening: bool,
+    /// The persona's table, kept from move to move
+    tt: &'a mut TranspositionTable,
+    /// The persona's share of its clock, when it searches by the clock
+    /// rather than to its depth
+    clock: Option<TimeLimits>,
+}
+
+impl MoveContext<'_> {
+    /// The best move of a search in the persona's style
+    fn search(&mut self) -> Option<Choice> {
+        let options = self.persona.personality.search_options();
+        let (best_move, score, _stats) = match self.clock {
+            Some(limits) => search_with_control(self.pos, 0, self.tt, &SearchControl::with_limits(limits), &options),
+            None => {
+                self.tt.new_search();
+                search_with_tt(self.pos, self.persona.search_depth, self.tt, &options)
+            }
+        };
+        best_move.map(|mv| Choice { mv, score: Some(score), cp_loss: 0 })
+    }
+
+    /// The `n` best moves of a search in the persona's style, best first
+    fn search_lines(&mut self, n: usize) -> Vec<PvLine> {
+        let options = self.persona.personality.search_options();
+        match self.clock {
+            Some(limits) => {
+                let control = SearchControl::with_limits(limits);
+                search_multipv_with_control(self.pos, n, 0, self.tt, &control, &options).0
+            }
+            None => search_multipv(self.pos, n, self.persona.search_depth, self.tt, &options),
+        }
+    }
+}
+
+/// A way of choosing moves. A strategy may pass (None), leaving the move to
+/// the next one: personas play book moves first, then their own strategy,
+/// then a plain search.
+trait MoveStrategy: Sync {
+    fn choose(&self, ctx: &mut MoveContext, rng: &mut StdRng) -> Option<Choice>;
+}
+
+/// The persona's favorite first move
+struct BookMove;
+
+impl MoveStrategy for BookMove {
+    fn choose(&self, ctx: &mut MoveContext, _rng: &mut StdRng) -> Option<Choice> {
+        let pref = ctx.persona.opening_style.preferred_first_move();
+        if !ctx.own_opening || pref.is_empty() {
+            return None;
+        }
+        find_move_by_uci(ctx.legal_moves, pref).map(Choice::unsearched)
+    }
+}
+
+/// The best move found, whatever the persona's blunder rate
+struct PureSearch;
+
+impl MoveStrategy for PureSearch {
+    fn choose(&self, ctx: &mut MoveContext, _rng: &mut StdRng) -> Option<Choice> {
+        ctx.search()
+    }
+}
+
+/// The best of the top moves by scores blurred with Gaussian noise of
+/// NOISE_CP per unit of the persona's blunder rate, so weaker personas
+/// misjudge close moves often and throw away clear ones rarely
+struct NoisySearch;
+
+impl MoveStrategy for NoisySearch {
+    fn choose(&self, ctx: &mut MoveContext, rng: &mut StdRng) -> Option<Choice> {
+        let noise = NOISE_CP * ctx.persona.blunder_rate;
+        if noise <= 0.0 || ctx.legal_moves.len() == 1 {
+            return ctx.search();
+        }
+        let lines = ctx.search_lines(NOISY_LINES);
+        let best = lines.first()?;
+        let noisy: Vec<f64> = lines.iter().map(|line| line.score as f64 + noise * gaussian(rng)).collect();
+        let pick = (0..lines.len()).max_by(|&a, &b| noisy[a].total_cmp(&noisy[b])).unwrap_or(0);
+        let cp_loss = (best.score - lines[pick].score).clamp(0, MAX_CP_LOSS) as u32;
+        Some(Choice { mv: lines[pick].mv, score: Some(best.score), cp_loss })
+    }
+}
+
+/// The capture (or promotion) winning the most material on the spot, without
+/// looking at the reply; a plain search when nothing can be won
+struct MaterialGrabber;
+
+impl MaterialGrabber {
+    /// Material the move wins before any recapture
+    fn gain(pos: &Position, mv: Move) -> i32 {
+        let captured = if mv.is_en_passant() {
+            Some(PieceType::Pawn)
+        } else {
+            pos.piece_on(mv.to()).filter(|&(color, _)| color != pos.side_to_move()).map(|(_, piece)| piece)
+        };
+        let promoted = mv.promotion_piece().map_or(0, |piece| piece_value(piece) - piece_value(PieceType::Pawn));
+        captured.map_or(0, piece_value) + promoted
+    }
+}
+
+impl MoveStrategy for MaterialGrabber {
+    fn choose(&self, ctx: &mut MoveContext, _rng: &mut StdRng) -> Option<Choice> {
+        let (mv, gain) = ctx.legal_moves.iter().map(|&mv| (mv, Self::gain(ctx.pos, mv))).max_by_key(|&(_, gain)| gain)?;
+        if gain > 0 { Some(Choice::unsearched(mv)) } else { ctx.search() }
+    }
+}
+
+/// Any legal move
+struct RandomMove;
+
+impl MoveStrategy for RandomMove {
+    fn choose(&self, ctx: &mut MoveContext, rng: &mut StdRng) -> Option<Choice> {
+        let n = ctx.legal_moves.len();
+        (n > 0).then(|| Choice::unsearched(ctx.legal_moves.get(rng.gen_range(0..n))))
+    }
+}
+
+fn find_move_by_uci(moves: &MoveList, uci: &str) -> Option<Move> {
+    for m in moves.iter() {
+        if m.to_uci() == uci {
+            return Some(*m);
+        }
+    }
+    None
+}
+
+/// The persona's move: its book move, else its strategy's, else the best
+/// move of a plain search
+fn choose_move(ctx: &mut MoveContext, rng: &mut StdRng) -> Choice {
+    let strategies: [&dyn MoveStrategy; 3] = [&BookMove, ctx.persona.strategy.moves(), &PureSearch];
+    strategies
+        .into_iter()
+        .find_map(|strategy| strategy.choose(ctx, rng))
+        .unwrap_or_else(|| Choice::unsearched(ctx.legal_moves.get(0)))
+}
+
+// =============================================================================
+// GAME RUNNER
+// =============================================================================
+
+/// Termination of a game lost (or drawn) on time
+const TIME_FORFEIT: &str = "time-forfeit";
+/// Transposition table size of each side in a game, as a power of two entries:
+/// the size a fixed-depth search gets from the library
+const SEARCH_TT_POWER: u8 = 18;
+
+thread_local! {
+    /// Each worker thread's tables, one per side, cleared at the start of
+    /// every game and kept from move to move within it
+    static SEARCH_TTS: RefCell<[TranspositionTable; 2]> =
+        RefCell::new([(); 2].map(|_| TranspositionTable::new(SEARCH_TT_POWER)));
+}
+
+/// Chess clock for both players: base time and increment per move
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct TimeControl {
+    base_ms: f64,
+    inc_ms: f64,
+}
+
+impl TimeControl {
+    /// Parse "<base>+<increment>" in seconds, e.g. "10+0.1", or just "<base>"
+    fn parse(spec: &str) -> Result<TimeControl, String> {
+        let (base, inc) = spec.trim().split_once('+').unwrap_or((spec.trim(), "0"));
+        let seconds = |s: &str| s.trim().parse::<f64>().ok().filter(|t| t.is_finite() && *t >= 0.0);
+        match (seconds(base), seconds(inc)) {
+            (Some(base), Some(inc)) if base > 0.0 => Ok(TimeControl { base_ms: base * 1000.0, inc_ms: inc * 1000.0 }),
+            _ => Err(format!("expected <seconds>+<increment>, e.g. 10+0.1: {}", spec)),
+        }
+    }
+
+    /// As parsed by `parse`
+    fn describe(&self) -> String {
+        format!("{}+{}", self.base_ms / 1000.0, self.inc_ms / 1000.0)
+    }
+}
+
+/// The variant of a name, or an error listing the variants there are
+fn parse_variant(name: &str) -> Result<Variant, String> {
+    Variant::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Variant::ALL.iter().map(|v| v.name()).collect();
+        format!("unsupported variant {} (expected one of: {})", name, names.join(", "))
+    })
+}
+
+/// Variants by name, for the rules sent to workers
+mod variant_name {
+    use super::{parse_variant, Variant};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(variant: &Variant, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(variant.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        parse_variant(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// How every game of the tournament is played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct GameRules {
+    /// Moves after which a game is scored a draw
+    max_moves: u32,
+    #[serde(with = "variant_name")]
+    variant: Variant,
+    time_control: Option<TimeControl>,
+    adjudication: Adjudication,
+}
+
+/// Rules ending games early on the players' search scores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct Adjudication {
+    /// Score, in centipawns, past which the losing side resigns...
+    resign_cp: Option<i32>,
+    /// ...once both players' searches have agreed for this many moves each
+    resign_moves: u32,
+    /// Score window around equality for a draw...
+    draw_cp: Option<i32>,
+    /// ...held for this many moves each...
+    draw_moves: u32,
+    /// ...from this move number on
+    draw_after: u32,
+}
+
+/// Progress of a game toward adjudication, fed each move's score
+#[derive(Debug, Default)]
+struct Adjudicator {
+    rules: Adjudication,
+    /// Plies in a row the score has put one side resign_cp ahead: positive
+    /// for White, negative for Black
+    lopsided: i32,
+    /// Plies in a row the score has stayed within draw_cp
+    level: u32,
+}
+
+impl Adjudicator {
+    fn new(rules: Adjudication) -> Adjudicator {
+        Adjudicator { rules, ..Adjudicator::default() }
+    }
+
+    /// Count a move made at `move_number`, whose search scored `white_score`
+    /// from White's view (None for a move played without a search, which
+    /// breaks any run). Returns the result once the game is adjudicated.
+    fn update(&mut self, white_score: Option<i32>, move_number: u32) -> Option<GameResult> {
+        let Some(score) = white_score else {
+            (self.lopsided, self.level) = (0, 0);
+            return None;
+        };
+        if let Some(resign_cp) = self.rules.resign_cp {
+            self.lopsided = match score {
+                s if s >= resign_cp => self.lopsided.max(0) + 1,
+                s if s <= -resign_cp => self.lopsided.min(0) - 1,
+                _ => 0,
+            };
+            if self.lopsided.unsigned_abs() >= 2 * self.rules.resign_moves.max(1) {
+                return Some(if self.lopsided > 0 { GameResult::WhiteWins } else { GameResult::BlackWins });
+            }
+        }
+        if let Some(draw_cp) = self.rules.draw_cp {
+            let level = move_number >= self.rules.draw_after && score.abs() <= draw_cp;
+            self.level = if level { self.level + 1 } else { 0 };
+            if self.level >= 2 * self.rules.draw_moves.max(1) {
+                return Some(GameResult::Draw);
+            }
+        }
+        None
+    }
+}
+
+/// Times the current position has occurred in the game, `history` holding
+/// the hash of every position so far (only those since the last capture or
+/// pawn move can match)
+fn repetitions(pos: &Position, history: &[u64]) -> usize {
+    let hash = pos.hash();
+    history.iter().rev().take(pos.halfmove_clock() as usize + 1).filter(|&&h| h == hash).count()
+}
+
+fn play_game(
+    white: &AiPersona,
+    black: &AiPersona,
+    round: u32,
+    rules: &GameRules,
+    opening: Option<&Position>,
+    game_seed: u64,
+) -> GameRecord {
+    SEARCH_TTS.with_borrow_mut(|tts| {
+        // Entries of the last game were searched in other players' styles
+        tts.iter_mut().for_each(TranspositionTable::clear);
+        play_game_with(white, black, round, rules, opening, game_seed, tts)
+    })
+}
+
+/// `play_game`, each side searching with its table in `tts`
+fn play_game_with(
+    white: &AiPersona,
+    black: &AiPersona,
+    round: u32,
+    rules: &GameRules,
+    opening: Option<&Position>,
+    game_seed: u64,
+    tts: &mut [TranspositionTable; 2],
+) -> GameRecord {
+    let GameRules { max_moves, variant, time_control, adjudication } = *rules;
+    let mut rng = StdRng::seed_from_u64(game_seed);
+    let mut pos = opening.cloned().unwrap_or_else(Position::starting_position);
+    pos.set_variant(variant);
+    let mut history = vec![pos.hash()];
+    let mut adjudicator = Adjudicator::new(adjudication);
+    let mut adjudicated = None;
+    let mut move_count = 0u32;
+    let mut moves_uci: Vec<String> = Vec::new();
+    // Blunders and centipawns lost, per side
+    let mut blunders = [0u32; 2];
+    let mut cp_loss = [0u64; 2];
+    // Thinking time so far and, under a time control, time left, per side
+    let mut time_used = [0.0f64; 2];
+    let mut clocks = time_control.map(|tc| [tc.base_ms; 2]);
+    let by_clock = [white, black].map(|p| time_control.is_some() && p.search_budget == SearchBudget::Clock);
+
+    let (result, termination) = loop {
+        // A variant's own win condition comes before mate and draws
+        if let Some((winner, status)) = variant.outcome(&pos) {
+            let result = if winner == Color::White { GameResult::WhiteWins } else { GameResult::BlackWins };
+            break (result, status);
+        }
+
+        if pos.is_checkmate() {
+            let result = match pos.side_to_move() {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            };
+            break (result, "checkmate");
+        }
+
+        if pos.is_draw() {
+            let term = if pos.is_stalemate() {
+                "stalemate"
+            } else if pos.is_fifty_move_draw() {
+                "50-move"
+            } else if pos.is_insufficient_material() {
+                "insufficient"
+            } else {
+                "dead-position"
+            };
+            break (GameResult::Draw, term);
+        }
+
+        if repetitions(&pos, &history) >= 3 {
+            break (GameResult::Draw, "threefold");
+        }
+
+        if let Some(result) = adjudicated {
+            break (result, if result == GameResult::Draw { "adjudicated-draw" } else { "resigned" });
+        }
+
+        if move_count >= max_moves * 2 {
+            break (GameResult::Draw, "max-moves");
+        }
+
+        let side = pos.side_to_move() as usize;
+        let current = if side == 0 { white } else { black };
+
+        let legal_moves = generate_legal_moves(&pos);
+        if legal_moves.is_empty() {
+            break (GameResult::Draw, "unknown");
+        }
+
+        let clock = match (clocks, time_control) {
+            (Some(clocks), Some(tc)) if by_clock[side] => Some(TimeLimits::for_clock(clocks[side], tc.inc_ms, None)),
+            _ => None,
+        };
+        let timer = Instant::now();
+        // Personas open with their favorite first move, unless given an opening
+        let own_opening = opening.is_none() && move_count < 2;
+        let tt = &mut tts[side];
+        let mut ctx =
+            MoveContext { pos: &mut pos, legal_moves: &legal_moves, persona: current, own_opening, tt, clock };
+        let choice = choose_move(&mut ctx, &mut rng);
+        let elapsed = timer.elapsed().as_secs_f64() * 1000.0;
+        time_used[side] += elapsed;
+        if let (Some(clocks), Some(tc)) = (clocks.as_mut(), time_control) {
+            clocks[side] -= elapsed;
+            if clocks[side] <= 0.0 {
+                // A clock run down to zero has flagged: a loss, unless the
+                // opponent couldn't mate anyway
+                let opponent = if side == 0 { Color::Black } else { Color::White };
+                let result = match opponent {
+                    _ if !pos.has_mating_material(opponent) => GameResult::Draw,
+                    Color::White => GameResult::WhiteWins,
+                    Color::Black => GameResult::BlackWins,
+                };
+                break (result, TIME_FORFEIT);
+            }
+            clocks[side] += tc.inc_ms;
+        }
+
+        cp_loss[side] += choice.cp_loss as u64;
+        if choice.cp_loss >= BLUNDER_CP {
+            blunders[side] += 1;
+        }
+
+        moves_uci.push(choice.mv.to_uci());
+        // Scores are the side to move's; the verdict waits for the rules above
+        let white_score = choice.score.map(|s| if side == 0 { s } else { -s });
+        adjudicated = adjudicator.update(white_score, pos.fullmove_number() as u32);
+        pos.make_move(choice.mv);
+        history.push(pos.hash());
+        move_count += 1;
+    };
+
+    GameRecord {
+        round,
+        white_id: white.id,
+        black_id: black.id,
+        result,
+        moves: move_count,
+        moves_uci,
+        termination: termination.into(),
+        white_blunders: blunders[0],
+        black_blunders: blunders[1],
+        white_cp_loss: cp_loss[0],
+        black_cp_loss: cp_loss[1],
+        white_time_ms: time_used[0].round() as u64,
+        black_time_ms: time_used[1].round() as u64,
+        start_fen: opening.map(|pos| pos.to_fen()),
+    }
+}
+
+// =============================================================================
+// OPENINGS
+// =============================================================================
+
+/// Most distinct positions drawn from an opening book
+const MAX_BOOK_OPENINGS: usize = 1000;
+
+/// The opening suite in `path`, by extension: a book written by the book
+/// tool (.bin), a PGN file (.pgn) or FEN/EPD lines
+fn load_openings(path: &str, book_plies: u32, seed: u64) -> Result<Vec<Position>, String> {
+    let lower = path.to_ascii_lowercase();
+    let openings = if lower.ends_with(".bin") {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let openings = book_openings(&read_book(&bytes)?, book_plies, seed);
+        if openings.is_empty() {
+            return Err("Book has no moves from the start position".to_string());
+        }
+        openings
+    } else {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        if lower.ends_with(".pgn") { pgn_openings(&text)? } else { epd_openings(&text)? }
+    };
+    if openings.is_empty() {
+        return Err("No openings".to_string());
+    }
+    Ok(openings)
+}
+
+/// Positions of FEN or EPD lines, skipping blank lines and '#' comments
+fn epd_openings(text: &str) -> Result<Vec<Position>, String> {
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fen = match Position::from_fen_strict(line) {
+            Ok(_) => line.to_string(),
+            Err(_) => parse_epd(line).map_err(|e| format!("line {}: {}", i, e))?.fen(),
+        };
+        let pos = Position::from_fen_strict(&fen).map_err(|e| format!("line {}: {}", i, e))?;
+        if generate_legal_moves(&pos).is_empty() {
+            return Err(format!("line {}: game already over", i));
+        }
+        openings.push(pos);
+    }
+    Ok(openings)
+}
+
+/// The position after each PGN game's moves (up to the first one that
+/// doesn't parse), from its FEN tag or the start position
+fn pgn_openings(text: &str) -> Result<Vec<Position>, String> {
+    let mut openings = Vec::new();
+    for (i, game) in parse_pgn_games(text).into_iter().enumerate().map(|(i, g)| (i + 1, g)) {
+        let game = game.map_err(|e| format!("game {}: {}", i, e))?;
+        let mut pos = match game.start_fen() {
+            Some(fen) => Position::from_fen(fen).map_err(|e| format!("game {}: {}", i, e))?,
+            None => Position::starting_position(),
+        };
+        for san in &game.moves {
+            match parse_san(&pos, san) {
+                Ok(m) if pos.make_move(m).is_some() => {}
+                _ => break,
+            }
+        }
+        if generate_legal_moves(&pos).is_empty() {
+            return Err(format!("game {}: game already over", i));
+        }
+        openings.push(pos);
+    }
+    Ok(openings)
+}
+
+/// Distinct positions at the end of random book lines of up to `plies`
+/// moves, each move drawn by its weight (a line ends early where the book
+/// does). Lines are drawn until MAX_BOOK_OPENINGS positions are found, or
+/// until as many lines again bring nothing new.
+fn book_openings(book: &[BookEntry], plies: u32, seed: u64) -> Vec<Position> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut seen = HashSet::new();
+    let mut openings = Vec::new();
+    let mut misses = 0;
+    while openings.len() < MAX_BOOK_OPENINGS && misses < MAX_BOOK_OPENINGS {
+        let mut pos = Position::starting_position();
+        let mut played = 0;
+        while played < plies {
+            let moves = probe(book, &pos);
+            let Ok(weights) = WeightedIndex::new(moves.iter().map(|&(_, w)| w)) else { break };
+            pos.make_move(moves[weights.sample(&mut rng)].0);
+            played += 1;
+        }
+        if played > 0 && !generate_legal_moves(&pos).is_empty() && seen.insert(pos.hash()) {
+            openings.push(pos);
+        } else {
+            misses += 1;
+        }
+    }
+    openings
+}
+
+/// The round's games with an opening suite: every pairing plays twice from
+/// the same opening, colors reversed, the openings taken in turn from
+/// `first` (the pairings played before). Entries are (white, black, opening).
+fn twin_games(pairs: &[(u32, u32)], first: usize, openings: usize) -> Vec<(u32, u32, Option<usize>)> {
+    pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &(white, black))| {
+            let opening = Some((first + i) % openings.max(1));
+            [(white, black, opening), (black, white, opening)]
+        })
+        .collect()
+}
+
+// =============================================================================
+// STANDINGS
+// =============================================================================
+
+/// Count a finished game in both players' results, ratings and pairing history
+fn apply_result(players: &mut [AiPersona], record: &GameRecord) {
+    let w = record.white_id as usize;
+    let b = record.black_id as usize;
+
+    players[w].games_as_white += 1;
+    players[w].total_moves_played += record.moves as u64;
+    players[w].total_game_length += record.moves as u64;
+    players[w].blunders_made += record.white_blunders;
+    let [white_moves, black_moves] = record.moves_by_side();
+    players[w].cp_loss += record.white_cp_loss;
+    players[w].moves_made += white_moves as u64;
+    players[b].cp_loss += record.black_cp_loss;
+    players[b].moves_made += black_moves as u64;
+    players[w].time_used_ms += record.white_time_ms;
+
+    players[b].games_as_black += 1;
+    players[b].total_moves_played += record.moves as u64;
+    players[b].total_game_length += record.moves as u64;
+    players[b].blunders_made += record.black_blunders;
+    players[b].time_used_ms += record.black_time_ms;
+    if record.termination == TIME_FORFEIT {
+        // The flag fell before the move was made
+        let flagged = if record.last_to_move() == Color::White { w } else { b };
+        players[flagged].time_forfeits += 1;
+    }
+
+    players[w].opponents.push(record.black_id);
+    players[w].colors.push(Color::White);
+    players[b].opponents.push(record.white_id);
+    players[b].colors.push(Color::Black);
+    let white_score = match record.result {
+        GameResult::WhiteWins => 1.0,
+        GameResult::BlackWins => 0.0,
+        GameResult::Draw => 0.5,
+    };
+    players[w].scores.push(white_score);
+    players[b].scores.push(1.0 - white_score);
+
+    match record.result {
+        GameResult::WhiteWins => {
+            let nw = elo_update(players[w].elo, players[b].elo, 1.0);
+            let nb = elo_update(players[b].elo, players[w].elo, 0.0);
+            players[w].elo = nw;
+            players[w].wins += 1;
+            players[w].points += 1.0;
+            players[b].elo = nb;
+            players[b].losses += 1;
+        }
+        GameResult::BlackWins => {
+            let nw = elo_update(players[w].elo, players[b].elo, 0.0);
+            let nb = elo_update(players[b].elo, players[w].elo, 1.0);
+            players[w].elo = nw;
+            players[w].losses += 1;
+            players[b].elo = nb;
+            players[b].wins += 1;
+            players[b].points += 1.0;
+        }
+        GameResult::Draw => {
+            let nw = elo_update(players[w].elo, players[b].elo, 0.5);
+            let nb = elo_update(players[b].elo, players[w].elo, 0.5);
+            players[w].elo = nw;
+            players[b].elo = nb;
+            players[w].draws += 1;
+            players[b].draws += 1;
+            players[w].points += 0.5;
+            players[b].points += 0.5;
+        }
+    }
+}
+
+/// Apply a round's games to the standings and ratings (under Glicko-2 the
+/// round is one rating period). Returns the players' total rating change.
+fn apply_round(players: &mut [AiPersona], records: &[GameRecord], system: RatingSystem) -> f64 {
+    let start: Vec<f64> = players.iter().map(|p| p.elo).collect();
+    for record in records {
+        apply_result(players, record);
+    }
+    if system == RatingSystem::Glicko2 {
+        rate_glicko2_round(players, &start, records);
+    }
+    players.iter().zip(&start).map(|(p, elo)| (p.elo - elo).abs()).sum()
+}
+
+/// Sonneborn-Berger tie-break: the final points of each opponent, times
+/// the points scored against them
+fn sonneborn_berger(players: &[AiPersona], player: &AiPersona) -> f64 {
+    player.opponents.iter().zip(&player.scores).map(|(&id, &score)| score * players[id as usize].points).sum()
+}
+
+/// Players by points, then rating
+fn leaderboard(players: &[AiPersona]) -> Vec<&AiPersona> {
+    let mut sorted: Vec<&AiPersona> = players.iter().collect();
+    sorted.sort_by(|a, b| {
+        b.points.partial_cmp(&a.points).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.elo.partial_cmp(&a.elo).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    sorted
+}
+
+/// Round-robin standings: by points, then Sonneborn-Berger, then rating
+fn round_robin_standings(players: &[AiPersona]) -> Vec<(&AiPersona, f64)> {
+    let mut standings: Vec<(&AiPersona, f64)> = players.iter().map(|p| (p, sonneborn_berger(players, p))).collect();
+    standings.sort_by(|(a, sa), (b, sb)| {
+        b.points.partial_cmp(&a.points).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| sb.partial_cmp(sa).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| b.elo.partial_cmp(&a.elo).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    standings
+}
+
+/// The gauntlet candidate's games against each opponent, in color-reversed
+/// pairs (an unpaired last game is left out)
+fn gauntlet_pairs(candidate: &AiPersona) -> Pentanomial {
+    let mut by_opponent: HashMap<u32, Vec<f64>> = HashMap::new();
+    for (&id, &score) in candidate.opponents.iter().zip(&candidate.scores) {
+        by_opponent.entry(id).or_default().push(score);
+    }
+    let mut pairs = Pentanomial::default();
+    for scores in by_opponent.values() {
+        for pair in scores.chunks_exact(2) {
+            pairs.add(((pair[0] + pair[1]) * 2.0).round() as usize);
+        }
+    }
+    pairs
+}
+
+/// Results of the players of `group` in their games against the other group
+fn head_to_head(players: &[AiPersona], group: Group) -> GameCounts {
+    let mut games = GameCounts::default();
+    for player in players.iter().filter(|p| p.group == group) {
+        for (&id, &score) in player.opponents.iter().zip(&player.scores) {
+            if players[id as usize].group != group {
+                games.add(score);
+            }
+        }
+    }
+    games
+}
+
+/// Mean of `values` and the half-width of its 95% confidence interval
+fn mean_interval(values: &[f64]) -> (f64, f64) {
+    let n = values.len().max(1) as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, Z_95 * (variance / n).sqrt())
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+/// Open the Syzygy tablebases given on the command line, if any
+#[cfg_attr(not(feature = "syzygy"), allow(unused_variables))]
+fn init_tablebases(args: &Args) {
+    #[cfg(feature = "syzygy")]
+    if let Some(path) = &args.syzygy_path {
+        chess_engine::syzygy::set_probe_depth(args.syzygy_probe_depth);
+        match chess_engine::syzygy::init(path) {
+            Ok(pieces) => println!("   Syzygy:      {:>10}", format!("{}-piece", pieces)),
+            Err(e) => eprintln!("   Syzygy:      {}", e),
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let done = match &args.command {
+        Some(Command::Replay { db, game: Some(id), depth, output, .. }) => {
+            replay_game(db, *id, *depth, output.as_deref())
+        }
+        Some(Command::Replay { db, round, player, game: None, output, .. }) => {
+            replay(db, *round, *player, output.as_deref())
+        }
+        Some(Command::Report { db, top, csv, json }) => report(db, *top, csv.as_deref(), json.as_deref()),
+        None if args.worker.is_some() => work(&args),
+        None => run_tournament(args),
+    };
+    if let Err(e) = done {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Size the global thread pool from --threads (0 = all cores)
+fn init_threads(args: &Args) {
+    if args.threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global()
+            .unwrap();
+    }
+}
+
+/// `tournament --worker`: play a coordinator's games until its tournament ends
+fn work(args: &Args) -> Result<(), String> {
+    let addr = args.worker.as_deref().unwrap_or_default();
+    init_threads(args);
+    init_tablebases(args);
+    println!("Playing games for {} on {} threads", addr, rayon::current_num_threads());
+    let games = run_worker(addr).map_err(|e| format!("Worker stopped: {}", e))?;
+    println!("Coordinator finished after {} games from this worker", format_number(games as u32));
+    Ok(())
+}
+
+/// Run the tournament, or carry on with the one given to --resume
+fn run_tournament(mut args: Args) -> Result<(), String> {
+    init_threads(&args);
+    // A resumed tournament carries on with the settings it was started with
+    let resumed = match args.resume.clone() {
+        Some(db) => {
+            let conn = init_database(&db)
+                .map_err(|e| e.to_string())
+                .and_then(|conn| load_settings(&conn, &mut args).map(|_| conn))
+                .map_err(|e| format!("Cannot resume {}: {}", db, e))?;
+            args.output = db;
+            Some(conn)
+        }
+        None => None,
+    };
+    let forced_personality = args.personality.as_deref().map(|name| {
+        Personality::from_name(name).unwrap_or_else(|| {
+            eprintln!("Unknown personality: {}", name);
+            std::process::exit(2);
+        })
+    });
+    let openings = match &args.openings {
+        Some(path) => load_openings(path, args.book_plies, args.seed)
+            .map_err(|e| format!("Cannot load openings {}: {}", path, e))?,
+        None => Vec::new(),
+    };
+
+    let rounds = args.format.rounds(args.players, args.rounds);
+    print_configuration(&args, rounds, openings.len(), forced_personality);
+    init_tablebases(&args);
+    let rules = args.game_rules();
+    let queue = match args.serve.as_deref() {
+        Some(addr) => {
+            let queue = Arc::new(RoundQueue::default());
+            let local = serve(addr, Arc::clone(&queue), rules)
+                .map_err(|e| format!("Cannot serve on {}: {}", addr, e))?;
+            println!("   Serving:     {} (workers: --worker {})", local, local);
+            Some(queue)
+        }
+        None => None,
+    };
+    let dashboard = match args.dashboard.as_deref() {
+        Some(addr) => {
+            let dashboard = Arc::new(Dashboard::new());
+            let local = serve_dashboard(addr, Arc::clone(&dashboard))
+                .map_err(|e| format!("Cannot serve the dashboard on {}: {}", addr, e))?;
+            println!("   Dashboard:   http://{}/", local);
+            Some(dashboard)
+        }
+        None => None,
+    };
+    println!();
+
+    // Phase 1: Generate personas (or load those of the resumed tournament)
+    let mut players = setup_players(&args, resumed.as_ref(), forced_personality)?;
+
+    // Phase 2: Initialize database, or rebuild the standings of the resumed tournament
+    let mut bracket = (args.format == Format::Knockout).then(|| Bracket::new(&players));
+    let resuming = resumed.is_some();
+    let (conn, completed) = open_database(&args, resumed, &mut players, bracket.as_mut(), rounds);
+
+    // Suite openings are taken in turn, a resumed run carrying on with the next
+    let played: usize =
+        conn.query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0)).expect("Failed to count games");
+
+    // Games of the round a crash cut short, saved in its checkpoint
+    let checkpointed = if resuming {
+        load_checkpoint(&conn, completed + 1).expect("Failed to read the checkpoint")
+    } else {
+        HashMap::new()
+    };
+    if !checkpointed.is_empty() {
+        let games = format_number(checkpointed.len() as u32);
+        println!("   {} games of round {} restored from its checkpoint", games, completed + 1);
+        println!();
+    }
+
+    let pgn_out = args
+        .pgn_out
+        .as_ref()
+        .map(|path| {
+            let file = File::options().create(true).write(true).append(resuming).truncate(!resuming).open(path);
+            file.map(BufWriter::new).map_err(|e| format!("Cannot create {}: {}", path, e))
+        })
+        .transpose()?;
+
+    let writer = DbWriter::spawn(conn, pgn_out);
+    let mut tournament = Tournament {
+        args: &args,
+        rules,
+        rounds,
+        openings,
+        queue,
+        dashboard,
+        players,
+        bracket,
+        checkpointed,
+        next_opening: played / 2,
+    };
+    if let Some(dashboard) = &tournament.dashboard {
+        dashboard.finish_round(tournament_status(&args, &tournament.players, completed, rounds), 0);
+    }
+
+    // Phase 3: Run tournament rounds
+    let multi = MultiProgress::new();
+    let total_timer = Instant::now();
+    let mut games_played = 0u64;
+    let mut total_moves = 0u64;
+    for round in completed + 1..=rounds {
+        let (games, moves) = tournament.play_round(round, &writer, &multi);
+        games_played += games;
+        total_moves += moves;
+    }
+
+    let conn = writer.finish();
+    print_totals(games_played, total_moves, total_timer.elapsed().as_secs_f64());
+    tournament.print_results(&conn);
+    Ok(())
+}
+
+/// The settings the tournament runs with
+fn print_configuration(args: &Args, rounds: u32, openings: usize, forced_personality: Option<Personality>) {
+    println!("======================================================");
+    println!("  1-MILLION AI CHESS TOURNAMENT");
+    println!("  {} | Parallel Execution | A/B Testing", args.format.name());
+    println!("======================================================");
+    println!();
+
+    println!("Configuration:");
+    println!("   Format:      {:>10}", args.format.name());
+    println!("   Ratings:     {:>10}", args.rating_system.name());
+    println!("   Players:     {:>10}", format_number(args.players));
+    println!("   Rounds:      {:>10}", rounds);
+    println!("   Threads:     {:>10}", rayon::current_num_threads());
+    println!("   Max moves:   {:>10}", args.max_moves);
+    println!("   Variant:     {:>10}", args.variant.name());
+    match args.time_control {
+        Some(tc) => println!("   Time control:{:>10}", tc.describe()),
+        None => println!("   Time control:{:>10}", "none"),
+    }
+    println!("   Seed:        {:>10}", args.seed);
+    if let Some(cp) = args.resign_score {
+        println!("   Resign:      {:>10}", format!("{}cp x{}", cp, args.resign_moves));
+    }
+    if let Some(cp) = args.draw_score {
+        println!("   Draw:        {:>10} from move {}", format!("{}cp x{}", cp, args.draw_moves), args.draw_after);
+    }
+    if let Some(path) = &args.openings {
+        println!("   Openings:    {:>10} from {} (twin games)", format_number(openings as u32), path);
+    }
+    if let Some(p) = forced_personality {
+        println!("   Personality: {:>10}", p.name());
+    }
+    if let Some(mix) = &args.strategies {
+        println!("   Strategies:  {:>10}", mix.describe());
+    }
+    println!("   Output:      {}", args.output);
+    if let Some(path) = &args.pgn_out {
+        println!("   PGN:         {}", path);
+    }
+}
+
+/// Phase 1: the players of the resumed tournament, or a new field with the
+/// personality, strategies and candidate the arguments ask for
+fn setup_players(
+    args: &Args,
+    resumed: Option<&Connection>,
+    forced_personality: Option<Personality>,
+) -> Result<Vec<AiPersona>, String> {
+    let timer = Instant::now();
+    let players = if let Some(conn) = resumed {
+        print!("Loading {} AI personas... ", format_number(args.players));
+        let players = load_players(conn).map_err(|e| format!("Cannot resume {}: {}", args.output, e))?;
+        if players.len() != args.players as usize {
+            let stored = players.len();
+            return Err(format!("Cannot resume {}: {} players stored, {} expected", args.output, stored, args.players));
+        }
+        players
+    } else {
+        print!("Generating {} AI personas... ", format_number(args.players));
+        let mut players = generate_personas(args.players, args.seed);
+        if let Some(p) = forced_personality {
+            players.iter_mut().for_each(|player| player.personality = p);
+        }
+        if let Some(mix) = &args.strategies {
+            mix.assign(&mut players, args.seed);
+        }
+        if let (Some(spec), Some(candidate)) = (&args.candidate, players.first_mut()) {
+            if let Err(e) = apply_candidate(candidate, spec) {
+                eprintln!("Invalid --candidate: {}", e);
+                std::process::exit(2);
+            }
+        }
+        players
+    };
+    println!("done ({:.1}ms)", timer.elapsed().as_secs_f64() * 1000.0);
+
+    let control_count = players.iter().filter(|p| p.group == Group::Control).count();
+    let rewards_count = players.iter().filter(|p| p.group == Group::Rewards).count();
+    println!("   Group A (Control):  {}", format_number(control_count as u32));
+    println!("   Group B (Rewards):  {}", format_number(rewards_count as u32));
+    println!();
+    Ok(players)
+}
+
+/// Phase 2: a fresh database holding the settings and players, or the resumed
+/// one with the standings rebuilt from its rounds; returns the rounds completed
+fn open_database(
+    args: &Args,
+    resumed: Option<Connection>,
+    players: &mut [AiPersona],
+    bracket: Option<&mut Bracket>,
+    rounds: u32,
+) -> (Connection, u32) {
+    match resumed {
+        Some(conn) => {
+            let completed = restore_rounds(&conn, players, args.format, args.rating_system, bracket)
+                .expect("Failed to restore completed rounds");
+            println!("Resuming after round {}/{}", completed, rounds);
+            println!();
+            (conn, completed)
+        }
+        None => {
+            let mut conn = init_database(&args.output).expect("Failed to create database");
+            // A fresh start replaces any tournament already in the file. Games
+            // reference their players, which are saved again after every round.
+            let tx = conn.transaction().expect("Failed to create database");
+            tx.execute_batch("DELETE FROM games; DELETE FROM rounds; DELETE FROM players; DELETE FROM checkpoint;")
+                .expect("Failed to clear database");
+            save_settings(&tx, args, rounds).expect("Failed to save settings");
+            save_players(&tx, players).expect("Failed to save players");
+            tx.commit().expect("Failed to save players");
+            (conn, 0)
+        }
+    }
+}
+
+/// A tournament under way: its settings, standings and where games are played
+struct Tournament<'a> {
+    args: &'a Args,
+    rules: GameRules,
+    rounds: u32,
+    openings: Vec<Position>,
+    /// Round games shared with workers (--serve)
+    queue: Option<Arc<RoundQueue>>,
+    dashboard: Option<Arc<Dashboard>>,
+    players: Vec<AiPersona>,
+    bracket: Option<Bracket>,
+    /// Games of the round a crash cut short, saved in its checkpoint
+    checkpointed: HashMap<usize, GameRecord>,
+    /// The suite opening the next pairing starts from
+    next_opening: usize,
+}
+
+impl Tournament<'_> {
+    /// Pair, play, rate and save a round; returns its games and moves
+    fn play_round(&mut self, round: u32, writer: &DbWriter, multi: &MultiProgress) -> (u64, u64) {
+        let args = self.args;
+        let round_timer = Instant::now();
+        let Pairings { pairs, bye } = match args.format {
+            Format::Swiss => swiss_pair(&self.players, round),
+            Format::RoundRobin => round_robin_pair(args.players, round),
+            Format::Gauntlet => gauntlet_pair(args.players, round),
+            Format::Knockout => self.bracket.as_ref().map(|b| b.pair(round)).unwrap_or_default(),
+        };
+        if let Some(id) = bye {
+            apply_bye(&mut self.players, id, args.format);
+        }
+        let games = if self.openings.is_empty() {
+            pairs.iter().map(|&(white, black)| (white, black, None)).collect()
+        } else {
+            twin_games(&pairs, self.next_opening, self.openings.len())
+        };
+        self.next_opening += pairs.len();
+
+        let pb = multi.add(ProgressBar::new(games.len() as u64));
+        pb.set_style(
+            ProgressStyle::with_template(
+                "   Round {msg} [{bar:40.cyan/blue}] {pos}/{len} ({per_sec} g/s, ETA {eta})",
+            )
+            .unwrap()
+            .progress_chars("##-"),
+        );
+        pb.set_message(format!("{}/{}", round, self.rounds));
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.start_round(round, &pb);
+        }
+
+        let game_seed = |white_id: u32, black_id: u32| {
+            args.seed.wrapping_mul(round as u64).wrapping_add(white_id as u64 * 1_000_000 + black_id as u64)
+        };
+        let checkpoint = Checkpoint::new(writer, round, args.checkpoint_every);
+        let (players, openings, rules) = (&self.players, &self.openings, &self.rules);
+        let checkpointed = &mut self.checkpointed;
+        // Plays the games from `offset` on in the round, but those checkpointed
+        let mut play = |games: &[(u32, u32, Option<usize>)], offset: usize| -> Vec<GameRecord> {
+            let mut results = checkpointed_results(games, round, offset, checkpointed);
+            let todo: Vec<usize> = (0..games.len()).filter(|&i| results[i].is_none()).collect();
+            pb.set_length(pb.length().unwrap_or(0) - (games.len() - todo.len()) as u64);
+            let played: Vec<GameRecord> = if let Some(queue) = &self.queue {
+                let jobs = todo
+                    .iter()
+                    .map(|&i| {
+                        let (white_id, black_id, opening) = games[i];
+                        GameJob {
+                            white: PlayerSpec::of(&players[white_id as usize]),
+                            black: PlayerSpec::of(&players[black_id as usize]),
+                            round,
+                            opening: opening.map(|i| openings[i].to_fen()),
+                            seed: game_seed(white_id, black_id),
+                        }
+                    })
+                    .collect();
+                queue.play_round(jobs, rules, &pb, &|job, record| checkpoint.add(offset + todo[job], record))
+            } else {
+                todo.par_iter()
+                    .map(|&i| {
+                        let (white_id, black_id, opening) = games[i];
+                        let white = &players[white_id as usize];
+                        let black = &players[black_id as usize];
+                        let opening = opening.map(|i| &openings[i]);
+                        let record = play_game(white, black, round, rules, opening, game_seed(white_id, black_id));
+                        checkpoint.add(offset + i, &record);
+                        pb.inc(1);
+                        record
+                    })
+                    .collect()
+            };
+            for (i, record) in todo.into_iter().zip(played) {
+                results[i] = Some(record);
+            }
+            results.into_iter().map(|record| record.expect("Every game is played")).collect()
+        };
+        let mut results = play(&games, 0);
+        if self.bracket.is_some() && openings.is_empty() {
+            // A drawn knockout game is replayed once with colors reversed (twin
+            // games already give both players each color)
+            let replays: Vec<(u32, u32, Option<usize>)> = results
+                .iter()
+                .filter(|r| r.result == GameResult::Draw)
+                .map(|r| (r.black_id, r.white_id, None))
+                .collect();
+            pb.inc_length(replays.len() as u64);
+            results.extend(play(&replays, games.len()));
+        }
+        let num_games = results.len();
+        let moves = results.iter().map(|r| r.moves as u64).sum::<u64>();
+
+        pb.finish();
+
+        let elo_change = apply_round(&mut self.players, &results, args.rating_system);
+        if let Some(bracket) = &mut self.bracket {
+            bracket.advance(round, &results);
+        }
+
+        let pgn = args.pgn_out.is_some().then(|| {
+            results
+                .iter()
+                .map(|record| StoredGame::from_record(record, &self.players, args.variant).to_pgn() + "\n")
+                .collect()
+        });
+        let summary = RoundSummary {
+            round,
+            games: results,
+            bye,
+            avg_elo_change: elo_change / (2 * num_games).max(1) as f64,
+            duration_ms: round_timer.elapsed().as_millis() as u64,
+        };
+        writer.save(RoundWrite { summary, players: self.players.clone(), pgn });
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.finish_round(tournament_status(args, &self.players, round, self.rounds), num_games as u64);
+        }
+
+        let round_secs = round_timer.elapsed().as_secs_f64();
+        let gps = num_games as f64 / round_secs;
+        println!(
+            "   Round {}/{}: {} games in {:.1}s ({:.0} games/s)",
+            round, self.rounds, format_number(num_games as u32), round_secs, gps,
+        );
+        (num_games as u64, moves)
+    }
+
+    /// Final standings, how games ended, and the A/B comparison
+    fn print_results(&self, conn: &Connection) {
+        let (args, players) = (self.args, &self.players);
+        match args.format {
+            Format::Swiss => print_leaderboard(players, args.rating_system),
+            Format::RoundRobin => print_round_robin_standings(players, args.rating_system),
+            Format::Gauntlet => print_gauntlet(players),
+            Format::Knockout => {
+                if let Some(bracket) = &self.bracket {
+                    print_bracket(players, bracket, self.rounds);
+                }
+            }
+        }
+
+        // Playing styles, and move strategies when the field mixes them
+        let styles =
+            Personality::ALL.map(|style| (style.name(), players.iter().filter(|p| p.personality == style).collect()));
+        print_styles("PERSONALITIES", "Style", &styles);
+        if players.iter().any(|p| p.strategy != players[0].strategy) {
+            let strategies = Strategy::ALL
+                .map(|strategy| (strategy.name(), players.iter().filter(|p| p.strategy == strategy).collect()));
+            print_styles("STRATEGIES", "Strategy", &strategies);
+        }
+
+        // How games ended, resumed rounds included
+        match termination_counts(conn) {
+            Ok(counts) => print_terminations(&counts),
+            Err(e) => eprintln!("Cannot count game endings: {}", e),
+        }
+        // First-move advantage, which variant rules can shift
+        match color_results(conn) {
+            Ok(results) => print_color_balance(args.variant, results),
+            Err(e) => eprintln!("Cannot count results by color: {}", e),
+        }
+
+        // Fixed depth against the clock
+        if args.time_control.is_some() {
+            print_search_budgets(players);
+        }
+
+        print_ab_test(players);
+        println!("  Results saved to: {}", args.output);
+        println!();
+    }
+}
+
+/// Games, moves and speed over the whole run
+fn print_totals(games: u64, moves: u64, secs: f64) {
+    println!();
+    println!("======================================================");
+    println!("  TOURNAMENT COMPLETE");
+    println!("======================================================");
+    println!();
+    println!("   Total games:     {:>12}", format_number(games as u32));
+    println!("   Total moves:     {:>12}", format_number(moves as u32));
+    println!("   Total time:      {:>10.1}s", secs);
+    println!("   Avg games/sec:   {:>10.0}", games as f64 / secs);
+    println!("   Avg moves/game:  {:>10.1}", moves as f64 / games.max(1) as f64);
+    println!();
+}
+
+/// Control (A) against Rewards (B): group averages, then their games
+/// against each other
+fn print_ab_test(players: &[AiPersona]) {
+    let control: Vec<&AiPersona> = players.iter().filter(|p| p.group == Group::Control).collect();
+    let rewards: Vec<&AiPersona> = players.iter().filter(|p| p.group == Group::Rewards).collect();
+
+    let avg = |group: &[&AiPersona], f: fn(&&AiPersona) -> f64| -> f64 {
+        if group.is_empty() { return 0.0; }
+        group.iter().map(f).sum::<f64>() / group.len() as f64
+    };
+
+    let elos = |group: &[&AiPersona]| group.iter().map(|p| p.elo).collect::<Vec<f64>>();
+    let (ctrl_elo, ctrl_margin) = mean_interval(&elos(&control));
+    let (rwrd_elo, rwrd_margin) = mean_interval(&elos(&rewards));
+    let ctrl_wr = avg(&control, |p| {
+        let t = (p.wins + p.losses + p.draws) as f64;
+        if t > 0.0 { p.wins as f64 / t } else { 0.0 }
+    });
+    let rwrd_wr = avg(&rewards, |p| {
+        let t = (p.wins + p.losses + p.draws) as f64;
+        if t > 0.0 { p.wins as f64 / t } else { 0.0 }
+    });
+    let ctrl_len = avg(&control, |p| {
+        let t = (p.wins + p.losses + p.draws) as f64;
+        if t > 0.0 { p.total_game_length as f64 / t } else { 0.0 }
+    });
+    let rwrd_len = avg(&rewards, |p| {
+        let t = (p.wins + p.losses + p.draws) as f64;
+        if t > 0.0 { p.total_game_length as f64 / t } else { 0.0 }
+    });
+    let ctrl_bl = avg(&control, |p| p.blunders_made as f64);
+    let rwrd_bl = avg(&rewards, |p| p.blunders_made as f64);
+    let ctrl_cpl = avg(&control, |p| p.avg_cp_loss());
+    let rwrd_cpl = avg(&rewards, |p| p.avg_cp_loss());
+
+    println!("  A/B TEST RESULTS");
+    println!("  {:<22} {:>12} {:>12} {:>8}", "Metric", "Control(A)", "Rewards(B)", "Delta");
+    println!("  {}", "-".repeat(58));
+    println!("  {:<22} {:>12.1} {:>12.1} {:>+8.1}", "Avg ELO", ctrl_elo, rwrd_elo, rwrd_elo - ctrl_elo);
+    let delta_margin = ctrl_margin.hypot(rwrd_margin);
+    let margins = [ctrl_margin, rwrd_margin, delta_margin].map(|m| format!("± {:.1}", m));
+    println!("  {:<22} {:>12} {:>12} {:>8}", "  95% interval", margins[0], margins[1], margins[2]);
+    println!("  {:<22} {:>11.1}% {:>11.1}% {:>+7.1}%", "Win Rate", ctrl_wr * 100.0, rwrd_wr * 100.0, (rwrd_wr - ctrl_wr) * 100.0);
+    println!("  {:<22} {:>12.1} {:>12.1} {:>+8.1}", "Avg Game Length", ctrl_len, rwrd_len, rwrd_len - ctrl_len);
+    println!("  {:<22} {:>12.2} {:>12.2} {:>+8.2}", "Avg Blunders", ctrl_bl, rwrd_bl, rwrd_bl - ctrl_bl);
+    println!("  {:<22} {:>12.1} {:>12.1} {:>+8.1}", "Avg CP Loss", ctrl_cpl, rwrd_cpl, rwrd_cpl - ctrl_cpl);
+    println!();
+
+    // Ratings drift with pairings; the games between the groups are the test
+    let games = head_to_head(players, Group::Rewards);
+    if games.games() > 0 {
+        let (elo, margin) = games.elo();
+        let (nelo, nelo_margin) = games.normalized_elo();
+        println!("  REWARDS (B) VS CONTROL (A)");
+        println!("  {}", "-".repeat(58));
+        println!("  {:<22} {:>12}", "Games", format_number(games.games() as u32));
+        println!("  {:<22} {:>4}/{:>3}/{:>3}", "W / D / L", games.wins, games.draws, games.losses);
+        println!("  {:<22} {:>11.1}%", "Score", games.score() * 100.0);
+        println!("  {:<22} {:>+12.1} ± {:.1}", "Elo", elo, margin);
+        println!("  {:<22} {:>+12.1} ± {:.1}", "Normalized Elo", nelo, nelo_margin);
+        println!("  {:<22} {:>11.1}%", "LOS", games.los() * 100.0);
+        println!();
+    }
+}
+
+// =============================================================================
+// STANDINGS OUTPUT
+// =============================================================================
+
+/// Players, average rating and win rate of each named group of players
+fn print_styles(title: &str, label: &str, groups: &[(&str, Vec<&AiPersona>)]) {
+    println!("  {}", title);
+    println!("  {:<14} {:>10} {:>10} {:>10}", label, "Players", "Avg ELO", "Win Rate");
+    println!("  {}", "-".repeat(47));
+    for (name, group) in groups.iter().filter(|(_, group)| !group.is_empty()) {
+        let n = group.len() as f64;
+        let elo = group.iter().map(|p| p.elo).sum::<f64>() / n;
+        let (wins, games) = group.iter().fold((0u32, 0u32), |(w, g), p| (w + p.wins, g + p.wins + p.losses + p.draws));
+        let win_rate = if games > 0 { wins as f64 / games as f64 } else { 0.0 };
+        println!("  {:<14} {:>10} {:>10.1} {:>9.1}%", name, group.len(), elo, win_rate * 100.0);
+    }
+    println!();
+}
+
+/// A player's rating, with its deviation under Glicko-2
+fn rating_label(player: &AiPersona, system: RatingSystem) -> String {
+    match system {
+        RatingSystem::Elo => format!("{:.0}", player.elo),
+        RatingSystem::Glicko2 => format!("{:.0} ±{:.0}", player.elo, player.rating_deviation),
+    }
+}
+
+fn print_leaderboard(players: &[AiPersona], system: RatingSystem) {
+    println!("  TOP 10 LEADERBOARD");
+    println!("  {:<4} {:<30} {:>10} {:>6} {:>16}", "Rank", "Name", "ELO", "Pts", "W / D / L");
+    println!("  {}", "-".repeat(73));
+    for (i, p) in leaderboard(players).iter().take(10).enumerate() {
+        let tag = if p.group == Group::Rewards { "[B]" } else { "[A]" };
+        println!(
+            "  {:<4} {:<26} {} {:>10} {:>6.1} {:>4}/{:>4}/{:>4}",
+            i + 1, p.name, tag, rating_label(p, system), p.points, p.wins, p.draws, p.losses,
+        );
+    }
+    println!();
+}
+
+/// Full standings for fields up to 32, the top 10 otherwise
+fn print_round_robin_standings(players: &[AiPersona], system: RatingSystem) {
+    let shown = if players.len() <= 32 { players.len() } else { 10 };
+    println!("  STANDINGS");
+    println!("  {:<4} {:<30} {:>10} {:>6} {:>7} {:>16}", "Rank", "Name", "ELO", "Pts", "SB", "W / D / L");
+    println!("  {}", "-".repeat(81));
+    for (i, (p, sb)) in round_robin_standings(players).iter().take(shown).enumerate() {
+        let tag = if p.group == Group::Rewards { "[B]" } else { "[A]" };
+        println!(
+            "  {:<4} {:<26} {} {:>10} {:>6.1} {:>7.2} {:>4}/{:>4}/{:>4}",
+            i + 1, p.name, tag, rating_label(p, system), p.points, sb, p.wins, p.draws, p.losses,
+        );
+    }
+    println!();
+}
+
+/// The candidate's result against the field, overall and by opponent depth
+fn print_gauntlet(players: &[AiPersona]) {
+    let Some(candidate) = players.first() else {
+        return;
+    };
+    let games = candidate.scores.len();
+    let score = candidate.scores.iter().sum::<f64>() / games.max(1) as f64;
+    println!("  GAUNTLET: {}", candidate.name);
+    println!(
+        "  Depth {}, {}, blunder rate {:.1}%, {} strategy",
+        candidate.search_depth,
+        candidate.personality.name(),
+        candidate.blunder_rate * 100.0,
+        candidate.strategy.name(),
+    );
+    println!("  {}", "-".repeat(47));
+    println!("  {:<22} {:>12}", "Games", games);
+    println!("  {:<22} {:>4}/{:>3}/{:>3}", "W / D / L", candidate.wins, candidate.draws, candidate.losses);
+    println!("  {:<22} {:>11.1}%", "Score", score * 100.0);
+    let pairs = gauntlet_pairs(candidate);
+    if pairs.pairs() > 0 {
+        let (elo, margin) = pairs.elo();
+        println!("  {:<22} {:>+12.1} ± {:.1} ({} color-reversed pairs)", "Elo vs field", elo, margin, pairs.pairs());
+    }
+    println!();
+    println!("  {:<14} {:>10} {:>10}", "Opponent depth", "Games", "Score");
+    println!("  {}", "-".repeat(36));
+    for depth in 1..=4 {
+        let scores: Vec<f64> = candidate
+            .opponents
+            .iter()
+            .zip(&candidate.scores)
+            .filter(|(&id, _)| players[id as usize].search_depth == depth)
+            .map(|(_, &score)| score)
+            .collect();
+        if scores.is_empty() {
+            continue;
+        }
+        let score = scores.iter().sum::<f64>() / scores.len() as f64;
+        println!("  {:<14} {:>10} {:>9.1}%", depth, scores.len(), score * 100.0);
+    }
+    println!();
+}
+
+/// Games by termination, with their share of all games
+fn print_terminations(counts: &[(String, u64)]) {
+    let total = counts.iter().map(|(_, n)| n).sum::<u64>().max(1) as f64;
+    println!("  GAME ENDINGS");
+    println!("  {:<14} {:>10} {:>10}", "Termination", "Games", "Share");
+    println!("  {}", "-".repeat(36));
+    for (termination, games) in counts {
+        println!("  {:<14} {:>10} {:>9.1}%", termination, format_number(*games as u32), *games as f64 / total * 100.0);
+    }
+    println!();
+}
+
+/// Games won by each color and drawn, and white's score
+fn print_color_balance(variant: Variant, [white, draws, black]: [u64; 3]) {
+    let total = (white + draws + black).max(1) as f64;
+    println!("  RESULTS BY COLOR ({})", variant.name());
+    println!("  {:<14} {:>10} {:>10}", "Result", "Games", "Share");
+    println!("  {}", "-".repeat(36));
+    for (name, games) in [("White wins", white), ("Draws", draws), ("Black wins", black)] {
+        println!("  {:<14} {:>10} {:>9.1}%", name, format_number(games as u32), games as f64 / total * 100.0);
+    }
+    println!("  {:<14} {:>20.1}%", "White score", (white as f64 + draws as f64 / 2.0) / total * 100.0);
+    println!();
+}
+
+/// Depth and clock players compared: strength, time per move and flags
+fn print_search_budgets(players: &[AiPersona]) {
+    println!("  SEARCH BUDGET");
+    println!("  {:<8} {:>9} {:>9} {:>9} {:>12} {:>9}", "Budget", "Players", "Avg ELO", "Win Rate", "ms / move", "Forfeits");
+    println!("  {}", "-".repeat(61));
+    for budget in [SearchBudget::Depth, SearchBudget::Clock] {
+        let group: Vec<&AiPersona> = players.iter().filter(|p| p.search_budget == budget).collect();
+        if group.is_empty() {
+            continue;
+        }
+        let elo = group.iter().map(|p| p.elo).sum::<f64>() / group.len() as f64;
+        let (wins, games) = group.iter().fold((0u32, 0u32), |(w, g), p| (w + p.wins, g + p.wins + p.losses + p.draws));
+        let win_rate = if games > 0 { wins as f64 / games as f64 } else { 0.0 };
+        // Each player makes about half the moves of its games
+        let moves = group.iter().map(|p| p.total_moves_played).sum::<u64>() as f64 / 2.0;
+        let time = group.iter().map(|p| p.time_used_ms).sum::<u64>() as f64;
+        let forfeits = group.iter().map(|p| p.time_forfeits).sum::<u32>();
+        println!(
+            "  {:<8} {:>9} {:>9.1} {:>8.1}% {:>12.1} {:>9}",
+            budget.name(), group.len(), elo, win_rate * 100.0, time / moves.max(1.0), forfeits,
+        );
+    }
+    println!();
+}
+
+/// Name of knockout round `round` of `rounds`
+fn knockout_round_name(round: u32, rounds: u32) -> String {
+    match rounds - round {
+        0 => "Final".into(),
+        1 => "Semifinals".into(),
+        2 => "Quarterfinals".into(),
+        _ => format!("Round {}", round),
+    }
+}
+
+/// The last three rounds of the bracket (earlier games are in the database)
+fn print_bracket(players: &[AiPersona], bracket: &Bracket, rounds: u32) {
+    let seeded = |id: u32| format!("{} [{}]", players[id as usize].name, bracket.seeds[id as usize] + 1);
+    println!("  KNOCKOUT BRACKET");
+    let mut current = 0;
+    for m in bracket.matches.iter().filter(|m| m.round + 3 > rounds) {
+        if m.round != current {
+            current = m.round;
+            println!("  {}", knockout_round_name(m.round, rounds));
+        }
+        let loser = if m.winner == m.high { m.low } else { m.high };
+        let how = if m.by_seed { " (on seed)" } else if m.games > 1 { " (replay)" } else { "" };
+        println!("    {} def. {}{}", seeded(m.winner), seeded(loser), how);
+    }
+    if let Some(id) = bracket.champion() {
+        println!("  Champion: {}", seeded(id));
+    }
+    println!();
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+/// A standard normal sample (Box-Muller)
+fn gaussian(rng: &mut impl Rng) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
+
+fn format_number(n: u32) -> String {
+    let s = n.to_string();
+    let mut result = String::new();
+    for (i, c) in s.chars().rev().enumerate() {
+        if i > 0 && i % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result.chars().rev().collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        color_results, init_database, load_games, save_games_batch, save_players, save_settings, termination_counts,
+    };
+    use crate::report::game_walkthrough;
+    use chess_engine::book::{write_book, BookBuilder, Weighting, ENTRY_SIZE};
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(0), "0");
+        assert_eq!(format_number(999), "999");
+        assert_eq!(format_number(1000), "1,000");
+        assert_eq!(format_number(1000000), "1,000,000");
+    }
+
+    #[test]
+    fn test_elo_calculation() {
+        let e = elo_expected(1200.0, 1200.0);
+        assert!((e - 0.5).abs() < 0.001);
+        assert!(elo_update(1200.0, 1200.0, 1.0) > 1200.0);
+        assert!(elo_update(1200.0, 1200.0, 0.0) < 1200.0);
+    }
+
+    #[test]
+    fn test_generate_personas() {
+        let personas = generate_personas(100, 42);
+        assert_eq!(personas.len(), 100);
+        let control = personas.iter().filter(|p| p.group == Group::Control).count();
+        assert_eq!(control, 50);
+        for p in &personas {
+            assert!(!p.name.is_empty());
+            assert!(p.search_depth >= 1 && p.search_depth <= 4);
+        }
+        // Every style shows up in a field of 100
+        for style in Personality::ALL {
+            assert!(personas.iter().any(|p| p.personality == style), "No {} player", style.name());
+        }
+    }
+
+    /// Plain games of up to `max_moves` moves
+    pub(crate) fn rules(max_moves: u32) -> GameRules {
+        GameRules { max_moves, ..GameRules::default() }
+    }
+
+    /// A finished game between two players
+    pub(crate) fn game(round: u32, white_id: u32, black_id: u32, result: GameResult) -> GameRecord {
+        GameRecord {
+            round,
+            white_id,
+            black_id,
+            result,
+            moves: 40,
+            moves_uci: Vec::new(),
+            termination: "checkmate".into(),
+            white_blunders: 0,
+            black_blunders: 0,
+            white_cp_loss: 0,
+            black_cp_loss: 0,
+            white_time_ms: 1000,
+            black_time_ms: 2000,
+
+            start_fen: None,
+        }
+    }
+
+    #[test]
+    fn test_play_game_completes() {
+        let personas = generate_personas(2, 42);
+        let record = play_game(&personas[0], &personas[1], 1, &rules(50), None, 123);
+        assert!(record.moves > 0 || record.termination == "max-moves");
+        assert_eq!(record.moves_uci.len(), record.moves as usize);
+        assert_eq!(record.opening_uci(), Some(record.moves_uci[..4].join(" ")));
+        // Reused tables are no smaller than the ones searches made per move
+        let capacity = SEARCH_TTS.with_borrow(|tts| tts.each_ref().map(TranspositionTable::capacity));
+        assert_eq!(capacity, [TranspositionTable::default_wasm().capacity(); 2]);
+    }
+
+    #[test]
+    fn test_eval_noise() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples: Vec<f64> = (0..4000).map(|_| gaussian(&mut rng)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.1 && (var - 1.0).abs() < 0.1, "{} {}", mean, var);
+
+        // A noise-free persona plays its best move; a noisy one gives some away
+        let mut persona = AiPersona::new(0, "Noisy".into(), 1200.0, Group::Control);
+        let mut tt = TranspositionTable::new(SEARCH_TT_POWER);
+        persona.search_depth = 2;
+        let mut pos = Position::starting_position();
+        let legal_moves = generate_legal_moves(&pos);
+        let exact = choose_move(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng);
+        assert_eq!(exact.cp_loss, 0);
+        assert!(exact.score.is_some());
+        persona.blunder_rate = 0.3;
+        let losses: Vec<u32> = (0..20)
+            .map(|_| choose_move(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng).cp_loss)
+            .collect();
+        assert!(losses.iter().any(|&l| l > 0) && losses.iter().all(|&l| l <= MAX_CP_LOSS as u32));
+
+        // Games keep the loss per side, which players average over their moves
+        let mut personas = generate_personas(2, 42);
+        personas.iter_mut().for_each(|p| p.blunder_rate = 0.3);
+        let record = play_game(&personas[0], &personas[1], 1, &rules(20), None, 5);
+        assert!(record.white_cp_loss + record.black_cp_loss > 0);
+        assert_eq!(record.moves_by_side().iter().sum::<u32>(), record.moves);
+        apply_result(&mut personas, &record);
+        assert_eq!(personas[0].cp_loss, record.white_cp_loss);
+        assert_eq!(personas[1].moves_made, record.moves_by_side()[1] as u64);
+        assert!(personas[0].avg_cp_loss() > 0.0 || personas[1].avg_cp_loss() > 0.0);
+    }
+
+    /// A fixed-depth persona's view of `pos`, past its book move
+    fn context<'a>(
+        pos: &'a mut Position,
+        legal_moves: &'a MoveList,
+        persona: &'a AiPersona,
+        tt: &'a mut TranspositionTable,
+    ) -> MoveContext<'a> {
+        MoveContext { pos, legal_moves, persona, own_opening: false, tt, clock: None }
+    }
+
+    #[test]
+    fn test_move_strategies() {
+        let mix = StrategyMix::parse("noisy=8, random").unwrap();
+        assert_eq!(mix, StrategyMix(vec![(Strategy::Noisy, 8), (Strategy::Random, 1)]));
+        assert_eq!(StrategyMix::parse(&mix.describe()), Ok(mix));
+        for bad in ["bogus=1", "search=x", "noisy=0", ""] {
+            assert!(StrategyMix::parse(bad).is_err(), "{}", bad);
+        }
+        let mut players = generate_personas(20, 3);
+        assert!(players.iter().all(|p| p.strategy == Strategy::Noisy));
+        StrategyMix::parse("material").unwrap().assign(&mut players, 3);
+        assert!(players.iter().all(|p| p.strategy == Strategy::Material));
+
+        // The book comes first, whatever the strategy
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut persona = AiPersona::new(0, "Grabby".into(), 1200.0, Group::Control);
+        let mut tt = TranspositionTable::new(SEARCH_TT_POWER);
+        persona.opening_style = OpeningStyle::KingPawn;
+        persona.strategy = Strategy::Random;
+        let mut pos = Position::starting_position();
+        let legal_moves = generate_legal_moves(&pos);
+        let mut ctx = MoveContext { own_opening: true, ..context(&mut pos, &legal_moves, &persona, &mut tt) };
+        assert_eq!(choose_move(&mut ctx, &mut rng).mv.to_uci(), "e2e4");
+        assert!(BookMove.choose(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng).is_none());
+        let random = RandomMove.choose(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng).unwrap();
+        assert!(legal_moves.iter().any(|&m| m == random.mv) && random.score.is_none());
+
+        // The grabber takes the queen; a pure search ignores the blunder rate
+        let mut pos = Position::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let legal_moves = generate_legal_moves(&pos);
+        let grab = MaterialGrabber.choose(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng).unwrap();
+        assert_eq!(grab.mv.to_uci(), "d2d5");
+        persona.blunder_rate = 1.0;
+        let best = PureSearch.choose(&mut context(&mut pos, &legal_moves, &persona, &mut tt), &mut rng).unwrap();
+        assert_eq!((best.mv.to_uci().as_str(), best.cp_loss), ("d2d5", 0));
+        let mut quiet = Position::starting_position();
+        let legal_moves = generate_legal_moves(&quiet);
+        let mut ctx = context(&mut quiet, &legal_moves, &persona, &mut tt);
+        assert!(MaterialGrabber.choose(&mut ctx, &mut rng).unwrap().score.is_some());
+    }
+
+    #[test]
+    fn test_time_control() {
+        assert_eq!(TimeControl::parse("10+0.1"), Ok(TimeControl { base_ms: 10_000.0, inc_ms: 100.0 }));
+        assert_eq!(TimeControl::parse(" 5 "), Ok(TimeControl { base_ms: 5000.0, inc_ms: 0.0 }));
+        for bad in ["0+1", "ten", "5+-1", "+1"] {
+            assert!(TimeControl::parse(bad).is_err(), "{}", bad);
+        }
+        let tc = TimeControl::parse("60+0.5").unwrap();
+        assert_eq!(TimeControl::parse(&tc.describe()), Ok(tc));
+        assert_eq!(SearchBudget::from_name("Clock"), Some(SearchBudget::Clock));
+
+        // Clock players share out their time; a fixed depth ignores the clock,
+        // and loses on it
+        let mut personas = generate_personas(2, 42);
+        for p in &mut personas {
+            p.opening_style = OpeningStyle::Random;
+            p.blunder_rate = 0.0;
+        }
+        personas[0].search_budget = SearchBudget::Clock;
+        personas[1].search_budget = SearchBudget::Clock;
+        let clocked = GameRules { time_control: TimeControl::parse("0.5").ok(), ..rules(4) };
+        let record = play_game(&personas[0], &personas[1], 1, &clocked, None, 1);
+        assert_eq!((record.moves, record.termination.as_str()), (8, "max-moves"));
+        assert!(record.white_time_ms > 0 && record.white_time_ms < 500, "{}", record.white_time_ms);
+
+        personas[0].search_budget = SearchBudget::Depth;
+        personas[0].search_depth = 4;
+        // An empty clock flags on the first move however fast it is played
+        let flag = GameRules { time_control: Some(TimeControl { base_ms: 0.0, inc_ms: 0.0 }), ..rules(4) };
+        let record = play_game(&personas[0], &personas[1], 1, &flag, None, 1);
+        assert_eq!((record.moves, record.result, record.termination.as_str()), (0, GameResult::BlackWins, TIME_FORFEIT));
+        apply_result(&mut personas, &record);
+        assert_eq!((personas[0].time_forfeits, personas[1].time_forfeits), (1, 0));
+        assert_eq!(personas[0].time_used_ms, record.white_time_ms);
+    }
+
+    #[test]
+    fn test_group_head_to_head() {
+        let mut players = generate_personas(4, 1);
+        for (player, group) in players.iter_mut().zip([Group::Control, Group::Control, Group::Rewards, Group::Rewards]) {
+            player.group = group;
+        }
+        // Games within a group don't count
+        for record in [
+            game(1, 0, 2, GameResult::BlackWins),
+            game(1, 3, 1, GameResult::Draw),
+            game(2, 2, 3, GameResult::WhiteWins),
+            game(2, 1, 2, GameResult::BlackWins),
+            game(3, 0, 1, GameResult::WhiteWins),
+            game(3, 3, 0, GameResult::BlackWins),
+        ] {
+            apply_result(&mut players, &record);
+        }
+        assert_eq!(head_to_head(&players, Group::Rewards), GameCounts { wins: 2, draws: 1, losses: 1 });
+        assert_eq!(head_to_head(&players, Group::Control), GameCounts { wins: 1, draws: 1, losses: 2 });
+
+        let (mean, margin) = mean_interval(&[1000.0, 1100.0, 1200.0]);
+        assert_eq!(mean, 1100.0);
+        assert!((margin - Z_95 * 100.0 / 3f64.sqrt()).abs() < 1e-9);
+        assert_eq!(mean_interval(&[1500.0]), (1500.0, 0.0));
+        assert_eq!(mean_interval(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_glicko2() {
+        // The worked example of Glickman's paper
+        let rating = |rating, deviation| Glicko { rating, deviation, volatility: 0.06 };
+        let results = [(rating(1400.0, 30.0), 1.0), (rating(1550.0, 100.0), 0.0), (rating(1700.0, 300.0), 0.0)];
+        let rated = glicko2_update(rating(1500.0, 200.0), &results);
+        assert!((rated.rating - 1464.06).abs() < 0.01, "{:?}", rated);
+        assert!((rated.deviation - 151.52).abs() < 0.01, "{:?}", rated);
+        assert!((rated.volatility - 0.05999).abs() < 1e-5, "{:?}", rated);
+        // Idle players only grow less certain, up to a new player's deviation
+        let idle = glicko2_update(rating(1500.0, 200.0), &[]);
+        assert_eq!(idle.rating, 1500.0);
+        assert!(idle.deviation > 200.0 && idle.deviation < 201.0);
+        assert_eq!(glicko2_update(rating(1500.0, 350.0), &[]).deviation, GLICKO_DEVIATION);
+
+        // A round is one period, rated from its starting ratings
+        let mut players = generate_personas(3, 1);
+        for (player, elo) in players.iter_mut().zip([1500.0, 1500.0, 1800.0]) {
+            player.elo = elo;
+        }
+        let records = [game(1, 0, 1, GameResult::WhiteWins), game(1, 1, 0, GameResult::WhiteWins)];
+        let change = apply_round(&mut players, &records, RatingSystem::Glicko2);
+        assert!((players[0].elo - 1500.0).abs() < 1e-6 && (players[1].elo - 1500.0).abs() < 1e-6);
+        assert!(players[0].rating_deviation < GLICKO_DEVIATION);
+        assert_eq!(players[2].elo, 1800.0);
+        assert_eq!(players[2].rating_deviation, GLICKO_DEVIATION);
+        assert!(change < 1e-6);
+        // Elo moves game by game instead: the second game starts from the first's ratings
+        let mut players = generate_personas(2, 1);
+        players.iter_mut().for_each(|p| p.elo = 1500.0);
+        let change = apply_round(&mut players, &records, RatingSystem::Elo);
+        assert!(players[0].elo < 1500.0 && change > 0.0);
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut pos = Position::starting_position();
+        let mut history = vec![pos.hash()];
+        let mut counts = Vec::new();
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+            pos.make_move(find_move_by_uci(&generate_legal_moves(&pos), uci).unwrap());
+            history.push(pos.hash());
+            counts.push(repetitions(&pos, &history));
+        }
+        assert_eq!(counts, vec![1, 1, 1, 2, 2, 2, 2, 3]);
+        // A pawn move makes every earlier position unreachable
+        pos.make_move(find_move_by_uci(&generate_legal_moves(&pos), "e2e4").unwrap());
+        history.push(pos.hash());
+        assert_eq!(repetitions(&pos, &history), 1);
+
+        let conn = init_database(":memory:").unwrap();
+        let personas = generate_personas(2, 1);
+        save_players(&conn, &personas).unwrap();
+        let mut records = vec![game(1, 0, 1, GameResult::Draw), game(1, 1, 0, GameResult::Draw)];
+        records[0].termination = "threefold".into();
+        records[1].termination = "threefold".into();
+        records.push(game(1, 0, 1, GameResult::WhiteWins));
+        save_games_batch(&conn, &records).unwrap();
+        let counts = termination_counts(&conn).unwrap();
+        assert_eq!(counts, vec![("threefold".to_string(), 2), ("checkmate".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_variant_games() {
+        assert_eq!(parse_variant("Three-Check"), Ok(Variant::ThreeCheck));
+        assert!(parse_variant("atomic").unwrap_err().contains("early-promotion, capture-limited, three-check"));
+        let three_check = GameRules { variant: Variant::ThreeCheck, ..rules(20) };
+        let text = serde_json::to_string(&three_check).unwrap();
+        assert!(text.contains(r#""variant":"three-check""#));
+        assert_eq!(serde_json::from_str::<GameRules>(&text).unwrap(), three_check);
+
+        // White's third check wins on the spot
+        let mut personas = generate_personas(2, 1);
+        for p in &mut personas {
+            (p.strategy, p.search_depth) = (Strategy::Search, 2);
+        }
+        let opening = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0").unwrap();
+        let record = play_game(&personas[0], &personas[1], 1, &three_check, Some(&opening), 1);
+        let outcome = (record.result, record.termination.as_str(), record.moves);
+        assert_eq!(outcome, (GameResult::WhiteWins, "three_check", 1));
+        let standard = play_game(&personas[0], &personas[1], 1, &rules(20), Some(&opening), 1);
+        assert_ne!(standard.termination, "three_check");
+
+        // Stored games replay under the tournament's variant
+        let conn = init_database(":memory:").unwrap();
+        let args = Args::parse_from(["tournament", "--variant", "three-check"]);
+        save_settings(&conn, &args, 1).unwrap();
+        save_players(&conn, &personas).unwrap();
+        save_games_batch(&conn, &[record]).unwrap();
+        let stored = load_games(&conn, None, None, None).unwrap();
+        assert_eq!(stored[0].variant, Variant::ThreeCheck);
+        let pgn = stored[0].to_pgn();
+        assert!(pgn.contains("[Variant \"three-check\"]"));
+        assert_eq!(chess_engine::pgn::parse_pgn(&pgn).unwrap().moves.len(), 1);
+        assert!(game_walkthrough(&stored[0], 1).unwrap().contains("Eval three_check\n"));
+        assert_eq!(color_results(&conn).unwrap(), [1, 0, 0]);
+    }
+
+    #[test]
+    fn test_adjudication() {
+        let adjudication =
+            Adjudication { resign_cp: Some(500), resign_moves: 2, draw_cp: Some(10), draw_moves: 2, draw_after: 40 };
+        let mut adjudicator = Adjudicator::new(adjudication);
+        // Both sides must agree, move after move
+        assert_eq!(adjudicator.update(Some(600), 10), None);
+        assert_eq!(adjudicator.update(Some(400), 10), None);
+        for score in [600, 700, 900] {
+            assert_eq!(adjudicator.update(Some(score), 11), None);
+        }
+        assert_eq!(adjudicator.update(None, 12), None);
+        for score in [-600, -2000, -550] {
+            assert_eq!(adjudicator.update(Some(score), 12), None);
+        }
+        assert_eq!(adjudicator.update(Some(-700), 13), Some(GameResult::BlackWins));
+
+        // Level scores only count from the draw move on
+        let mut adjudicator = Adjudicator::new(adjudication);
+        for _ in 0..6 {
+            assert_eq!(adjudicator.update(Some(0), 39), None);
+        }
+        for score in [5, -10, 0] {
+            assert_eq!(adjudicator.update(Some(score), 40), None);
+        }
+        assert_eq!(adjudicator.update(Some(3), 41), Some(GameResult::Draw));
+        let mut off = Adjudicator::new(Adjudication::default());
+        assert!((0..100).all(|_| off.update(Some(5000), 50).is_none()));
+
+        // In a game, windows every score falls into end it after a move each
+        // (searched moves: a given opening skips the personas' first moves)
+        let mut personas = generate_personas(2, 5);
+        personas.iter_mut().for_each(|p| p.blunder_rate = 0.0);
+        let start = Position::starting_position();
+        let draw = Adjudication { draw_cp: Some(10_000), draw_moves: 1, draw_after: 1, ..Adjudication::default() };
+        let adjudicated = |adjudication| GameRules { adjudication, ..rules(50) };
+        let record = play_game(&personas[0], &personas[1], 1, &adjudicated(draw), Some(&start), 1);
+        assert_eq!((record.moves, record.result), (2, GameResult::Draw));
+        assert_eq!(record.termination, "adjudicated-draw");
+        let resign = Adjudication { resign_cp: Some(-10_000), resign_moves: 1, ..Adjudication::default() };
+        let record = play_game(&personas[0], &personas[1], 1, &adjudicated(resign), Some(&start), 1);
+        assert_eq!((record.moves, record.result, record.termination.as_str()), (2, GameResult::WhiteWins, "resigned"));
+    }
+
+    #[test]
+    fn test_opening_suites() {
+        let text = "# suite\nrnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\n\n\
+                    rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - id \"open\";\n";
+        let openings = epd_openings(text).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].side_to_move(), Color::Black);
+        let mate = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+        assert!(epd_openings(mate).is_err_and(|e| e.contains("line 1")));
+
+        let pgn = "[Event \"a\"]\n\n1. d4 d5 2. c4 *\n\n[Event \"b\"]\n\n1. e4 c5 2. Nf3 Zz9 3. d4 *\n";
+        let openings = pgn_openings(pgn).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].side_to_move(), Color::Black);
+        assert_eq!(openings[1].to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        // Book lines end where the book does, each distinct position once
+        let mut builder = BookBuilder::new(4);
+        let start = Position::starting_position();
+        for line in [["e2e4", "e7e5"], ["d2d4", "d7d5"]] {
+            let mut pos = start.clone();
+            let moves: Vec<Move> = line
+                .iter()
+                .map(|uci| {
+                    let m = find_move_by_uci(&generate_legal_moves(&pos), uci).unwrap();
+                    pos.make_move(m);
+                    m
+                })
+                .collect();
+            builder.add_game(&start, &moves, "1/2-1/2");
+        }
+        let book = builder.build(1, Weighting::Count);
+        let openings = book_openings(&book, 8, 7);
+        assert_eq!(openings.len(), 2);
+        assert!(openings.iter().all(|pos| pos.side_to_move() == Color::White));
+        assert!(book_openings(&[], 8, 7).is_empty());
+
+        // Polyglot books and books without the start position are turned away
+        let path = std::env::temp_dir().join(format!("openings_{}.bin", std::process::id()));
+        let bytes = write_book(&book);
+        std::fs::write(&path, &bytes[ENTRY_SIZE..]).unwrap();
+        let polyglot = load_openings(path.to_str().unwrap(), 8, 7).err().unwrap();
+        std::fs::write(&path, write_book(&[])).unwrap();
+        let empty = load_openings(path.to_str().unwrap(), 8, 7).err().unwrap();
+        std::fs::write(&path, &bytes).unwrap();
+        let loaded = load_openings(path.to_str().unwrap(), 8, 7);
+        std::fs::remove_file(&path).unwrap();
+        assert!(polyglot.contains("Polyglot"), "{}", polyglot);
+        assert_eq!(empty, "Book has no moves from the start position");
+        assert_eq!(loaded.unwrap().len(), 2);
+
+        // Each pairing plays both colors from one opening, the next pairing the next
+        let games = twin_games(&[(0, 1), (2, 3)], 5, 6);
+        assert_eq!(games, vec![(0, 1, Some(5)), (1, 0, Some(5)), (2, 3, Some(0)), (3, 2, Some(0))]);
+    }
+
+    #[test]
+    fn test_game_from_opening() {
+        let personas = generate_personas(2, 3);
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        let opening = Position::from_fen(fen).unwrap();
+        let record = play_game(&personas[0], &personas[1], 1, &rules(20), Some(&opening), 9);
+        assert_eq!(record.start_fen.as_deref(), Some(fen));
+        assert_eq!(record.opening_uci(), None);
+        let mut pos = opening.clone();
+        for uci in &record.moves_uci {
+            pos.make_move(find_move_by_uci(&generate_legal_moves(&pos), uci).unwrap());
+        }
+        assert_eq!(record.last_to_move(), pos.side_to_move());
+
+        let conn = init_database(":memory:").unwrap();
+        save_players(&conn, &personas).unwrap();
+        save_games_batch(&conn, std::slice::from_ref(&record)).unwrap();
+        let stored = load_games(&conn, None, None, None).unwrap();
+        assert_eq!(stored[0].start_fen.as_deref(), Some(fen));
+        let game = chess_engine::pgn::parse_pgn(&stored[0].to_pgn()).unwrap();
+        assert_eq!((game.header("SetUp"), game.start_fen()), (Some("1"), Some(fen)));
+        assert_eq!(game.moves.len(), record.moves as usize);
+    }
+}
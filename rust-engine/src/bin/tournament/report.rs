@@ -0,0 +1,591 @@
+// =============================================================================
+// Tournament Replay and Reports
+// =============================================================================
+// The `replay` and `report` subcommands, which read a finished (or running)
+// tournament's database: games as PGN or an annotated walkthrough, and
+// summary tables on openings, pairings, search depth and game endings.
+// =============================================================================
+
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use chess_engine::search::{search_with_tt, SearchOptions};
+use chess_engine::tt::TranspositionTable;
+use chess_engine::types::Color;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::db::{load_games, stored_variant, StoredGame};
+use crate::{find_move_by_uci, GameResult, SEARCH_TT_POWER};
+
+// =============================================================================
+// REPLAY
+// =============================================================================
+
+/// `tournament replay`: write the stored games as PGN
+pub(crate) fn replay(db: &str, round: Option<u32>, player: Option<u32>, output: Option<&str>) -> Result<(), String> {
+    let conn = Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Cannot open {}: {}", db, e))?;
+    let games = load_games(&conn, round, player, None).map_err(|e| format!("Cannot read {}: {}", db, e))?;
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut without_moves = 0;
+    for game in &games {
+        // Games stored before full move lists were kept can't be replayed
+        if game.moves_uci.is_empty() {
+            without_moves += 1;
+            continue;
+        }
+        writeln!(out, "{}", game.to_pgn()).map_err(|e| e.to_string())?;
+    }
+    out.flush().map_err(|e| e.to_string())?;
+    eprintln!("{} games exported", games.len() - without_moves);
+    if without_moves > 0 {
+        eprintln!("{} games without stored moves skipped", without_moves);
+    }
+    Ok(())
+}
+
+/// A score from white's point of view: "+0.35", or "#-2" for a mate
+fn eval_label(white_score: i32) -> String {
+    let plies = MATE_SCORE - white_score.abs();
+    if plies < 256 {
+        let moves = (plies + 1) / 2;
+        format!("#{}", if white_score > 0 { moves } else { -moves })
+    } else {
+        format!("{:+.2}", white_score as f64 / 100.0)
+    }
+}
+
+/// The board as eight ranks of FEN piece letters, white at the bottom
+fn board_diagram(pos: &Position) -> String {
+    let fen = pos.to_fen();
+    let placement = fen.split(' ').next().unwrap_or_default();
+    let mut diagram = String::new();
+    for (rank, row) in (1..=8).rev().zip(placement.split('/')) {
+        let squares: Vec<String> = row
+            .chars()
+            .flat_map(|c| match c.to_digit(10) {
+                Some(empty) => vec![".".to_string(); empty as usize],
+                None => vec![c.to_string()],
+            })
+            .collect();
+        diagram.push_str(&format!("  {}  {}\n", rank, squares.join(" ")));
+    }
+    diagram.push_str("     a b c d e f g h\n");
+    diagram
+}
+
+/// A stored game move by move: the board after each one, searched to `depth`
+/// for an evaluation, up to its stored result and termination
+pub(crate) fn game_walkthrough(game: &StoredGame, depth: u8) -> Result<String, String> {
+    let mut pos = game.start_position()?;
+    let mut tt = TranspositionTable::new(SEARCH_TT_POWER);
+    let options = SearchOptions::default();
+    let mut evaluate = |pos: &Position| -> String {
+        let status = pos.game_status();
+        if status != "playing" {
+            return status;
+        }
+        tt.new_search();
+        let (_, score, _) = search_with_tt(&mut pos.clone(), depth, &mut tt, &options);
+        eval_label(if pos.side_to_move() == Color::White { score } else { -score })
+    };
+
+    let mut text = format!(
+        "Game {}, round {}: {} (white) vs {} (black), {}\n\n{}  Eval {}\n\n",
+        game.id,
+        game.round,
+        game.white,
+        game.black,
+        game.variant.name(),
+        board_diagram(&pos),
+        evaluate(&pos),
+    );
+    for (ply, uci) in game.moves_uci.iter().enumerate() {
+        let m = find_move_by_uci(&generate_legal_moves(&pos), uci)
+            .ok_or_else(|| format!("Stored move {} at ply {} is not legal in {}", uci, ply + 1, pos.to_fen()))?;
+        let dots = if pos.side_to_move() == Color::White { "." } else { "..." };
+        let number = format!("{}{}", pos.fullmove_number(), dots);
+        let san = m.to_san(&pos);
+        pos.make_move(m);
+        text.push_str(&format!("{} {}  Eval {}\n{}\n", number, san, evaluate(&pos), board_diagram(&pos)));
+    }
+    text.push_str(&format!("Result {} ({})\n", game.result, game.termination));
+    Ok(text)
+}
+
+/// `tournament replay --game`: step through one stored game, and write it
+/// as PGN to `output` if given
+pub(crate) fn replay_game(db: &str, id: i64, depth: u8, output: Option<&str>) -> Result<(), String> {
+    let conn = Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Cannot open {}: {}", db, e))?;
+    let game = load_games(&conn, None, None, Some(id))
+        .map_err(|e| format!("Cannot read {}: {}", db, e))?
+        .pop()
+        .ok_or_else(|| format!("No game {} in {}", id, db))?;
+    if game.moves_uci.is_empty() {
+        return Err(format!("Game {} was stored without its moves", id));
+    }
+    print!("{}", game_walkthrough(&game, depth)?);
+    if let Some(path) = output {
+        std::fs::write(path, game.to_pgn() + "\n").map_err(|e| format!("Cannot write {}: {}", path, e))?;
+        eprintln!("Game {} written to {}", id, path);
+    }
+    Ok(())
+}
+
+// =============================================================================
+// REPORTS
+// =============================================================================
+
+/// Results of the players of one opening style
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct StyleStats {
+    style: String,
+    players: u32,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+/// Scores of the players of one search depth, as fractions of the points
+/// they could have won: their mean and quartiles
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DepthStats {
+    depth: u32,
+    players: u32,
+    mean: f64,
+    min: f64,
+    p25: f64,
+    median: f64,
+    p75: f64,
+    max: f64,
+}
+
+/// Games that ended one way, by result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct TerminationStats {
+    termination: String,
+    games: u64,
+    white_wins: u64,
+    draws: u64,
+    black_wins: u64,
+    avg_moves: f64,
+}
+
+/// Points a player scored in its games against one opponent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct Meeting {
+    points: f64,
+    games: u32,
+}
+
+/// The top finishers, best first, and each one's meetings with the others in
+/// the same order (`None` where they never met)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct HeadToHead {
+    players: Vec<String>,
+    meetings: Vec<Vec<Option<Meeting>>>,
+}
+
+/// Everything `tournament report` shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Report {
+    variant: String,
+    opening_styles: Vec<StyleStats>,
+    head_to_head: HeadToHead,
+    depths: Vec<DepthStats>,
+    terminations: Vec<TerminationStats>,
+}
+
+/// One table of a report, as text cells
+struct ReportTable {
+    /// File name of its CSV export
+    name: &'static str,
+    title: &'static str,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Report {
+    fn load(conn: &Connection, top: usize) -> rusqlite::Result<Report> {
+        Ok(Report {
+            variant: stored_variant(conn).name().to_string(),
+            opening_styles: style_stats(conn)?,
+            head_to_head: top_meetings(conn, top)?,
+            depths: depth_stats(conn)?,
+            terminations: termination_stats(conn)?,
+        })
+    }
+
+    fn tables(&self) -> Vec<ReportTable> {
+        let percent = |points: f64, games: u32| format!("{:.1}", points / games.max(1) as f64 * 100.0);
+        let styles = ReportTable {
+            name: "opening_styles",
+            title: "RESULTS BY OPENING STYLE",
+            header: ["Style", "Players", "Wins", "Draws", "Losses", "Score %"].map(String::from).to_vec(),
+            rows: self
+                .opening_styles
+                .iter()
+                .map(|s| {
+                    let games = s.wins + s.draws + s.losses;
+                    let points = s.wins as f64 + s.draws as f64 / 2.0;
+                    vec![
+                        s.style.clone(),
+                        s.players.to_string(),
+                        s.wins.to_string(),
+                        s.draws.to_string(),
+                        s.losses.to_string(),
+                        percent(points, games),
+                    ]
+                })
+                .collect(),
+        };
+
+        let mut header = vec!["#".to_string(), "Name".to_string()];
+        header.extend((1..=self.head_to_head.players.len()).map(|rank| rank.to_string()));
+        header.push("Score".to_string());
+        let head_to_head = ReportTable {
+            name: "head_to_head",
+            title: "HEAD TO HEAD (row player's points / games)",
+            header,
+            rows: self
+                .head_to_head
+                .players
+                .iter()
+                .zip(&self.head_to_head.meetings)
+                .enumerate()
+                .map(|(i, (name, meetings))| {
+                    let mut row = vec![(i + 1).to_string(), name.clone()];
+                    row.extend(meetings.iter().enumerate().map(|(j, meeting)| match meeting {
+                        _ if i == j => "x".to_string(),
+                        Some(m) => format!("{}/{}", m.points, m.games),
+                        None => "-".to_string(),
+                    }));
+                    let (points, games) =
+                        meetings.iter().flatten().fold((0.0, 0), |(p, g), m| (p + m.points, g + m.games));
+                    row.push(format!("{}/{}", points, games));
+                    row
+                })
+                .collect(),
+        };
+
+        let depths = ReportTable {
+            name: "depths",
+            title: "SCORE DISTRIBUTION BY SEARCH DEPTH (%)",
+            header: ["Depth", "Players", "Mean", "Min", "25%", "Median", "75%", "Max"].map(String::from).to_vec(),
+            rows: self
+                .depths
+                .iter()
+                .map(|d| {
+                    let mut row = vec![d.depth.to_string(), d.players.to_string()];
+                    row.extend([d.mean, d.min, d.p25, d.median, d.p75, d.max].map(|score| percent(score, 1)));
+                    row
+                })
+                .collect(),
+        };
+
+        let terminations = ReportTable {
+            name: "terminations",
+            title: "GAME ENDINGS BY RESULT",
+            header: ["Termination", "Games", "1-0", "1/2-1/2", "0-1", "Avg Moves"].map(String::from).to_vec(),
+            rows: self
+                .terminations
+                .iter()
+                .map(|t| {
+                    vec![
+                        t.termination.clone(),
+                        t.games.to_string(),
+                        t.white_wins.to_string(),
+                        t.draws.to_string(),
+                        t.black_wins.to_string(),
+                        format!("{:.1}", t.avg_moves),
+                    ]
+                })
+                .collect(),
+        };
+
+        vec![styles, head_to_head, depths, terminations]
+    }
+}
+
+impl ReportTable {
+    /// Columns padded to their widest cell, the first (and names) left-aligned
+    fn print(&self) {
+        let mut widths: Vec<usize> = self.header.iter().map(String::len).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let left = |column: usize| column == 0 || self.header[column] == "Name";
+        let line = |cells: &[String]| -> String {
+            let cells: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if left(i) {
+                        format!("{:<w$}", cell, w = widths[i])
+                    } else {
+                        format!("{:>w$}", cell, w = widths[i])
+                    }
+                })
+                .collect();
+            format!("  {}", cells.join("  "))
+        };
+        println!("  {}", self.title);
+        println!("{}", line(&self.header));
+        println!("  {}", "-".repeat(widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1)));
+        for row in &self.rows {
+            println!("{}", line(row));
+        }
+        println!();
+    }
+
+    fn to_csv(&self) -> String {
+        let field = |cell: &String| {
+            if cell.contains([',', '"', '\n']) { format!("\"{}\"", cell.replace('"', "\"\"")) } else { cell.clone() }
+        };
+        std::iter::once(&self.header)
+            .chain(&self.rows)
+            .map(|row| row.iter().map(field).collect::<Vec<_>>().join(",") + "\n")
+            .collect()
+    }
+}
+
+fn style_stats(conn: &Connection) -> rusqlite::Result<Vec<StyleStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT opening_style, COUNT(*), SUM(wins), SUM(draws), SUM(losses)
+        FROM players GROUP BY opening_style ORDER BY opening_style",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(StyleStats {
+            style: row.get(0)?,
+            players: row.get(1)?,
+            wins: row.get(2)?,
+            draws: row.get(3)?,
+            losses: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The `top` finishers by points, then rating, and the matrix of their
+/// games against each other
+fn top_meetings(conn: &Connection, top: usize) -> rusqlite::Result<HeadToHead> {
+    let mut stmt = conn.prepare("SELECT id, name FROM players ORDER BY points DESC, final_elo DESC, id LIMIT ?1")?;
+    let players: Vec<(u32, String)> =
+        stmt.query_map([top as i64], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<_>>()?;
+    let rank: HashMap<u32, usize> = players.iter().enumerate().map(|(i, (id, _))| (*id, i)).collect();
+    let mut matrix = vec![vec![None; players.len()]; players.len()];
+    if !players.is_empty() {
+        let ids = players.iter().map(|(id, _)| id.to_string()).collect::<Vec<_>>().join(",");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT white_id, black_id, result FROM games WHERE white_id IN ({0}) AND black_id IN ({0})",
+            ids,
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let (white, black) = (rank[&row.get::<_, u32>(0)?], rank[&row.get::<_, u32>(1)?]);
+            let white_points = match GameResult::from_pgn(&row.get::<_, String>(2)?) {
+                Some(GameResult::WhiteWins) => 1.0,
+                Some(GameResult::BlackWins) => 0.0,
+                _ => 0.5,
+            };
+            for (a, b, points) in [(white, black, white_points), (black, white, 1.0 - white_points)] {
+                let meeting = matrix[a][b].get_or_insert(Meeting { points: 0.0, games: 0 });
+                meeting.points += points;
+                meeting.games += 1;
+            }
+        }
+    }
+    Ok(HeadToHead { players: players.into_iter().map(|(_, name)| name).collect(), meetings: matrix })
+}
+
+fn depth_stats(conn: &Connection) -> rusqlite::Result<Vec<DepthStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT search_depth, points / (wins + draws + losses) FROM players
+        WHERE wins + draws + losses > 0 ORDER BY search_depth",
+    )?;
+    let mut by_depth: Vec<(u32, Vec<f64>)> = Vec::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let (depth, score): (u32, f64) = (row.get(0)?, row.get(1)?);
+        match by_depth.last_mut() {
+            Some((last, scores)) if *last == depth => scores.push(score),
+            _ => by_depth.push((depth, vec![score])),
+        }
+    }
+    Ok(by_depth
+        .into_iter()
+        .map(|(depth, mut scores)| {
+            scores.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            let quantile = |q: f64| scores[((scores.len() - 1) as f64 * q).round() as usize];
+            DepthStats {
+                depth,
+                players: scores.len() as u32,
+                mean: scores.iter().sum::<f64>() / scores.len() as f64,
+                min: quantile(0.0),
+                p25: quantile(0.25),
+                median: quantile(0.5),
+                p75: quantile(0.75),
+                max: quantile(1.0),
+            }
+        })
+        .collect())
+}
+
+/// Games by how they ended, most common first
+fn termination_stats(conn: &Connection) -> rusqlite::Result<Vec<TerminationStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT termination, COUNT(*), SUM(result = '1-0'), SUM(result = '1/2-1/2'), SUM(result = '0-1'), AVG(moves)
+        FROM games GROUP BY termination ORDER BY COUNT(*) DESC, termination",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TerminationStats {
+            termination: row.get(0)?,
+            games: row.get(1)?,
+            white_wins: row.get(2)?,
+            draws: row.get(3)?,
+            black_wins: row.get(4)?,
+            avg_moves: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// `tournament report`: print the statistics tables, and export them as CSV
+/// files and the whole report as JSON if asked
+pub(crate) fn report(db: &str, top: usize, csv: Option<&str>, json: Option<&str>) -> Result<(), String> {
+    let conn = Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Cannot open {}: {}", db, e))?;
+    let report = Report::load(&conn, top).map_err(|e| format!("Cannot read {}: {}", db, e))?;
+    let tables = report.tables();
+    println!();
+    println!("  Variant: {}", report.variant);
+    println!();
+    for table in &tables {
+        table.print();
+    }
+    if let Some(dir) = csv {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir, e))?;
+        for table in &tables {
+            let path = std::path::Path::new(dir).join(format!("{}.csv", table.name));
+            std::fs::write(&path, table.to_csv()).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        }
+        eprintln!("{} CSV tables written to {}", tables.len(), dir);
+    }
+    if let Some(path) = json {
+        let text = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        std::fs::write(path, text + "\n").map_err(|e| format!("Cannot write {}: {}", path, e))?;
+        eprintln!("Report written to {}", path);
+    }
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, save_games_batch, save_players};
+    use crate::{apply_result, generate_personas};
+    use crate::tests::game;
+
+    #[test]
+    fn test_replay_game() {
+        let conn = init_database(":memory:").unwrap();
+        let personas = generate_personas(2, 1);
+        save_players(&conn, &personas).unwrap();
+        let mut mate = game(1, 0, 1, GameResult::BlackWins);
+        mate.moves_uci = ["f2f3", "e7e5", "g2g4", "d8h4"].map(String::from).to_vec();
+        save_games_batch(&conn, &[game(1, 1, 0, GameResult::Draw), mate]).unwrap();
+
+        let stored = load_games(&conn, None, None, Some(2)).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].id, stored[0].white.as_str()), (2, personas[0].name.as_str()));
+        let text = game_walkthrough(&stored[0], 1).unwrap();
+        let title = format!("Game 2, round 1: {} (white) vs {} (black), standard", personas[0].name, personas[1].name);
+        assert!(text.starts_with(&title));
+        assert!(text.contains("  8  r n b q k b n r\n"));
+        assert!(text.contains("1. f3  Eval "));
+        assert!(text.contains("2... Qh4#  Eval checkmate\n"));
+        assert_eq!(text.matches("a b c d e f g h").count(), 5);
+        assert!(text.ends_with("Result 0-1 (checkmate)\n"));
+
+        let mut broken = stored[0].clone();
+        broken.moves_uci[2] = "e1e3".to_string();
+        assert!(game_walkthrough(&broken, 1).unwrap_err().contains("e1e3 at ply 3"));
+        assert_eq!((eval_label(35), eval_label(-120), eval_label(0)), ("+0.35".into(), "-1.20".into(), "+0.00".into()));
+        assert_eq!((eval_label(MATE_SCORE - 3), eval_label(-MATE_SCORE + 2)), ("#2".into(), "#-1".into()));
+    }
+
+    #[test]
+    fn test_report() {
+        let conn = init_database(":memory:").unwrap();
+        let mut personas = generate_personas(4, 1);
+        let mut records = vec![
+            game(1, 0, 1, GameResult::WhiteWins),
+            game(1, 2, 3, GameResult::Draw),
+            game(2, 1, 0, GameResult::BlackWins),
+            game(2, 3, 2, GameResult::BlackWins),
+            game(3, 0, 2, GameResult::Draw),
+            game(3, 1, 3, GameResult::WhiteWins),
+        ];
+        records[1].termination = "threefold".into();
+        records[4].termination = "threefold".into();
+        records[4].moves = 20;
+        for record in &records {
+            apply_result(&mut personas, record);
+        }
+        save_players(&conn, &personas).unwrap();
+        save_games_batch(&conn, &records).unwrap();
+
+        let report = Report::load(&conn, 3).unwrap();
+        let styles = &report.opening_styles;
+        assert_eq!(styles.iter().map(|s| s.players).sum::<u32>(), 4);
+        let total = |count: fn(&StyleStats) -> u32| styles.iter().map(count).sum::<u32>();
+        assert_eq!((total(|s| s.wins), total(|s| s.draws), total(|s| s.losses)), (4, 4, 4));
+
+        // Players 0, 2 and 1 finish first with 2.5, 2 and 1 points
+        let names = |ids: [usize; 3]| ids.map(|id| personas[id].name.clone()).to_vec();
+        assert_eq!(report.head_to_head.players, names([0, 2, 1]));
+        let meeting = |points, games| Some(Meeting { points, games });
+        assert_eq!(report.head_to_head.meetings[0], vec![None, meeting(0.5, 1), meeting(2.0, 2)]);
+        assert_eq!(report.head_to_head.meetings[2], vec![meeting(0.0, 2), None, None]);
+
+        assert_eq!(report.depths.iter().map(|d| d.players).sum::<u32>(), 4);
+        for d in &report.depths {
+            assert!(d.min <= d.p25 && d.p25 <= d.median && d.median <= d.p75 && d.p75 <= d.max);
+            assert!(d.min <= d.mean && d.mean <= d.max);
+        }
+
+        let endings: Vec<_> = report
+            .terminations
+            .iter()
+            .map(|t| (t.termination.as_str(), t.games, t.white_wins, t.draws, t.black_wins, t.avg_moves))
+            .collect();
+        assert_eq!(endings, vec![("checkmate", 4, 2, 0, 2, 40.0), ("threefold", 2, 0, 2, 0, 30.0)]);
+
+        let tables = report.tables();
+        let table_names: Vec<_> = tables.iter().map(|t| t.name).collect();
+        assert_eq!(table_names, ["opening_styles", "head_to_head", "depths", "terminations"]);
+        assert_eq!(tables[1].rows[0][2..], ["x", "0.5/1", "2/2", "2.5/3"]);
+        assert!(tables[0].to_csv().starts_with("Style,Players,Wins,Draws,Losses,Score %\n"));
+        let header = vec!["a,\"b\"".to_string()];
+        let quoted = ReportTable { name: "t", title: "T", header, rows: vec![vec!["c".to_string()]] };
+        assert_eq!(quoted.to_csv(), "\"a,\"\"b\"\"\"\nc\n");
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json["head_to_head"]["meetings"][0][0].is_null());
+        assert_eq!(json["head_to_head"]["meetings"][0][2]["points"], 2.0);
+    }
+}
@@ -0,0 +1,421 @@
+// =============================================================================
+// UCI Engine
+// =============================================================================
+// Speaks the Universal Chess Interface on stdin/stdout, so the engine can be
+// loaded into CuteChess, Arena, lichess-bot and other UCI front ends.
+//
+// Usage:
+//   cargo run --release --bin uci
+//   cutechess-cli -engine cmd=target/release/uci proto=uci -engine ... -each tc=40/60
+//
+// Supported: uci, isready, ucinewgame, position (startpos | fen ...) [moves ...],
+// go (wtime btime winc binc movestogo movetime depth nodes infinite ponder),
+// stop, ponderhit, quit, setoption, and "bench [depth]" (also as
+// `uci bench [depth]` on the command line, exiting afterwards). The search
+// runs on its own thread (see chess_engine::driver, shared with the xboard
+// binary) and reports an "info" line per completed depth, then "bestmove".
+//
+// Options: Hash (MB), Threads, MultiPV, Ponder, Skill Level (0-20) and
+// UCI_Variant.
+// =============================================================================
+
+use chess_engine::bench::{run_bench, DEFAULT_BENCH_DEPTH};
+use chess_engine::driver::{EngineOptions, SearchDriver, SearchEvent, SearchInfo, SearchLimits};
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use chess_engine::variant::Variant;
+
+use std::io::BufRead;
+use std::sync::Arc;
+
+/// Where the engine's lines go: stdout, or a buffer in tests
+type Output = Arc<dyn Fn(String) + Send + Sync>;
+
+// =============================================================================
+// COMMAND PARSING
+// =============================================================================
+
+/// Name and value of the words after "setoption": "name <words> [value <words>]"
+fn parse_setoption(args: &[&str]) -> Option<(String, String)> {
+    let (&"name", rest) = args.split_first()? else { return None };
+    let value_at = rest.iter().position(|&w| w == "value").unwrap_or(rest.len());
+    let name = rest[..value_at].join(" ");
+    let value = rest.get(value_at + 1..).unwrap_or_default().join(" ");
+    (!name.is_empty()).then_some((name, value))
+}
+
+/// Limits of a "go" command; unknown words and bad numbers are skipped
+fn parse_go(args: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut words = args.iter();
+    while let Some(&word) = words.next() {
+        let mut value = || words.next().and_then(|v| v.parse::<f64>().ok());
+        match word {
+            "wtime" => limits.wtime = value(),
+            "btime" => limits.btime = value(),
+            "winc" => limits.winc = value().unwrap_or(0.0),
+            "binc" => limits.binc = value().unwrap_or(0.0),
+            "movestogo" => limits.movestogo = value().map(|v| v as u32),
+            "movetime" => limits.movetime = value(),
+            "depth" => limits.depth = value().map(|v| v.clamp(1.0, 255.0) as u8),
+            "nodes" => limits.nodes = value().map(|v| v as u64),
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            _ => {}
+        }
+    }
+    limits
+}
+
+/// The position of a "position" command: "startpos" or "fen <6 fields>",
+/// then optionally "moves" and UCI moves, played under `variant`'s rules
+fn parse_position(args: &[&str], variant: Variant) -> Result<Position, String> {
+    let moves_at = args.iter().position(|&w| w == "moves").unwrap_or(args.len());
+    let mut pos = match args.first() {
+        Some(&"startpos") => Position::starting_position(),
+        Some(&"fen") => {
+            let fen = args[1..moves_at].join(" ");
+            Position::from_fen_strict(&fen).map_err(|e| format!("Invalid FEN: {}", e))?
+        }
+        _ => return Err("Expected startpos or fen".to_string()),
+    };
+    pos.set_variant(variant);
+    for uci in args.iter().skip(moves_at + 1) {
+        let m = generate_legal_moves(&pos).iter().copied().find(|m| m.to_uci() == *uci);
+        match m {
+            Some(m) => {
+                pos.make_move(m);
+            }
+            None => return Err(format!("Illegal move: {}", uci)),
+        }
+    }
+    Ok(pos)
+}
+
+// =============================================================================
+// OUTPUT
+// =============================================================================
+
+/// The "option" lines of the handshake
+fn option_declarations(options: &EngineOptions) -> Vec<String> {
+    let mut lines: Vec<String> = options
+        .spins()
+        .iter()
+        .map(|(name, value, min, max)| format!("option name {} type spin default {} min {} max {}", name, value, min, max))
+        .collect();
+    lines.push(format!("option name Ponder type check default {}", options.ponder));
+    let variants: Vec<String> = Variant::ALL.iter().map(|v| format!("var {}", v.name())).collect();
+    lines.push(format!("option name UCI_Variant type combo default {} {}", options.variant.name(), variants.join(" ")));
+    lines
+}
+
+/// "cp <centipawns>" or "mate <moves>", negative when the engine is mated
+fn uci_score(score: i32) -> String {
+    let plies = MATE_SCORE - score.abs();
+    if plies < 256 {
+        let moves = (plies + 1) / 2;
+        format!("mate {}", if score > 0 { moves } else { -moves })
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+/// An "info" line for a completed depth
+fn info_line(info: &SearchInfo) -> String {
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_uci()).collect();
+    let multipv = info.multipv.map(|k| format!(" multipv {}", k)).unwrap_or_default();
+    format!(
+        "info depth {} seldepth {}{} score {} nodes {} nps {} time {} pv {}",
+        info.depth,
+        info.seldepth,
+        multipv,
+        uci_score(info.score),
+        info.nodes,
+        info.nps,
+        info.time_ms as u64,
+        pv.join(" ")
+    )
+}
+
+/// The line a driver event is reported with
+fn event_line(event: &SearchEvent) -> String {
+    match event {
+        SearchEvent::Info(info) => info_line(info),
+        SearchEvent::BestMove { best: Some(best), ponder: Some(reply) } => {
+            format!("bestmove {} ponder {}", best.to_uci(), reply.to_uci())
+        }
+        SearchEvent::BestMove { best: Some(best), ponder: None } => format!("bestmove {}", best.to_uci()),
+        SearchEvent::BestMove { best: None, .. } => "bestmove 0000".to_string(),
+    }
+}
+
+/// Run the bench: a line per position, then the totals
+fn bench(args: &[&str], out: &Output) {
+    let depth = args.first().and_then(|d| d.parse::<u8>().ok()).filter(|&d| d > 0).unwrap_or(DEFAULT_BENCH_DEPTH);
+    let result = run_bench(depth, |i, entry| {
+        out(format!("info string position {} nodes {} time {} fen {}", i + 1, entry.nodes, entry.time_ms as u64, entry.fen));
+    });
+    out(format!("info string bench depth {}", result.depth));
+    out(format!("Total time (ms) : {}", result.time_ms() as u64));
+    out(format!("Nodes searched  : {}", result.nodes()));
+    out(format!("Nodes/second    : {}", result.nps()));
+}
+
+// =============================================================================
+// ENGINE
+// =============================================================================
+
+struct Engine {
+    pos: Position,
+    driver: SearchDriver,
+    out: Output,
+}
+
+impl Engine {
+    fn new(out: Output) -> Self {
+        let sink = Arc::clone(&out);
+        Engine {
+            pos: Position::starting_position(),
+            driver: SearchDriver::new(Arc::new(move |event| sink(event_line(&event)))),
+            out,
+        }
+    }
+
+    fn send(&self, line: impl Into<String>) {
+        (self.out)(line.into());
+    }
+
+    /// Handle one line from the GUI. Returns false on "quit".
+    fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else { return true };
+        match command {
+            "uci" => {
+                self.send("id name Promotion Variant Chess");
+                self.send("id author Promotion Variant Chess contributors");
+                for option in option_declarations(&EngineOptions::default()) {
+                    self.send(option);
+                }
+                self.send("uciok");
+            }
+            "isready" => self.send("readyok"),
+            "setoption" => match parse_setoption(args) {
+                Some((name, value)) => match self.driver.set_option(&name, &value) {
+                    Ok(()) => self.pos.set_variant(self.driver.options().variant),
+                    Err(e) => self.send(format!("info string {}", e)),
+                },
+                None => self.send("info string Expected setoption name <name> [value <value>]"),
+            },
+            "ucinewgame" => {
+                self.driver.new_game();
+                self.pos = Position::starting_position();
+                self.pos.set_variant(self.driver.options().variant);
+            }
+            "position" => {
+                self.driver.stop();
+                match parse_position(args, self.driver.options().variant) {
+                    Ok(pos) => self.pos = pos,
+                    Err(e) => self.send(format!("info string {}", e)),
+                }
+            }
+            "go" => self.driver.go(&self.pos, parse_go(args)),
+            "stop" => self.driver.stop(),
+            "ponderhit" => self.driver.ponderhit(),
+            "bench" => {
+                self.driver.stop();
+                bench(args, &self.out);
+            }
+            "quit" => {
+                self.driver.stop();
+                return false;
+            }
+            _ => self.send(format!("info string Unknown command: {}", command)),
+        }
+        true
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new(Arc::new(|line| println!("{}", line)));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let words: Vec<&str> = args.iter().map(String::as_str).collect();
+        engine.handle(&words.join(" "));
+        engine.driver.stop();
+        return;
+    }
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+    engine.driver.stop();
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn engine() -> (Engine, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        (Engine::new(Arc::new(move |line| sink.lock().unwrap().push(line))), lines)
+    }
+
+    /// Let the running search end by itself, then collect it
+    fn wait(engine: &mut Engine) {
+        let start = std::time::Instant::now();
+        while engine.driver.is_thinking() {
+            assert!(start.elapsed().as_secs() < 10, "Search didn't end");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        engine.driver.stop();
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let go = parse_go(&["wtime", "60000", "btime", "30000", "winc", "1000", "binc", "0", "movestogo", "12"]);
+        assert_eq!((go.wtime, go.btime, go.winc, go.movestogo), (Some(60000.0), Some(30000.0), 1000.0, Some(12)));
+        let go = parse_go(&["movetime", "250", "ponder"]);
+        assert_eq!((go.movetime, go.ponder), (Some(250.0), true));
+        let go = parse_go(&["depth", "5", "nodes", "1000", "wtime", "x", "infinite", "bogus"]);
+        assert_eq!((go.depth, go.nodes, go.wtime, go.infinite), (Some(5), Some(1000), None, true));
+    }
+
+    #[test]
+    fn test_parse_position() {
+        let pos = parse_position(&["startpos", "moves", "e2e4", "e7e5", "e1e2"], Variant::Standard).unwrap();
+        assert_eq!(pos.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 1 2");
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".split(' ').collect::<Vec<_>>();
+        let pos = parse_position(&[&["fen"], &fen[..], &["moves", "e1g1"]].concat(), Variant::Standard).unwrap();
+        assert_eq!(pos.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+
+        assert_eq!(parse_position(&["startpos", "moves", "e2e5"], Variant::Standard).err().unwrap(), "Illegal move: e2e5");
+        assert!(parse_position(&["fen", "8/8/8", "w"], Variant::Standard).err().unwrap().starts_with("Invalid FEN"));
+        assert!(parse_position(&[], Variant::Standard).is_err());
+    }
+
+    #[test]
+    fn test_uci_score() {
+        assert_eq!(uci_score(35), "cp 35");
+        assert_eq!(uci_score(-120), "cp -120");
+        assert_eq!(uci_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(uci_score(MATE_SCORE - 3), "mate 2");
+        assert_eq!(uci_score(-MATE_SCORE + 2), "mate -1");
+    }
+
+    #[test]
+    fn test_handshake_and_search() {
+        let (mut engine, lines) = engine();
+        assert!(engine.handle("uci"));
+        assert!(engine.handle("isready"));
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "readyok");
+        assert!(lines.lock().unwrap().contains(&"uciok".to_string()));
+
+        engine.handle("position startpos moves e2e4");
+        engine.handle("go depth 3");
+        wait(&mut engine);
+        let out = lines.lock().unwrap().clone();
+        let infos: Vec<&String> = out.iter().filter(|l| l.starts_with("info depth")).collect();
+        assert_eq!(infos.len(), 3, "{:?}", out);
+        assert!(infos[2].starts_with("info depth 3 "), "{}", infos[2]);
+        assert!(out.last().unwrap().starts_with("bestmove "), "{:?}", out);
+
+        // Mated side: no move to play
+        engine.handle("position startpos moves f2f3 e7e5 g2g4 d8h4");
+        engine.handle("go depth 2");
+        wait(&mut engine);
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "bestmove 0000");
+        assert!(!engine.handle("quit"));
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(
+            parse_setoption(&["name", "Skill", "Level", "value", "5"]),
+            Some(("Skill Level".to_string(), "5".to_string()))
+        );
+        assert_eq!(parse_setoption(&["name", "Clear", "Hash"]), Some(("Clear Hash".to_string(), String::new())));
+        assert_eq!(parse_setoption(&["value", "5"]), None);
+
+        let declarations = option_declarations(&EngineOptions::default());
+        assert_eq!(declarations.len(), 6);
+        assert!(declarations.contains(&"option name Skill Level type spin default 20 min 0 max 20".to_string()));
+        assert!(declarations.contains(&"option name Ponder type check default false".to_string()));
+        assert!(declarations.last().unwrap().ends_with("var capture-limited var three-check"));
+    }
+
+    #[test]
+    fn test_multipv_threads_and_skill() {
+        let (mut engine, lines) = engine();
+        engine.handle("setoption name MultiPV value 3");
+        engine.handle("setoption name Threads value 3");
+        engine.handle("go depth 2");
+        wait(&mut engine);
+        let out = lines.lock().unwrap().clone();
+        let depth2: Vec<&String> = out.iter().filter(|l| l.starts_with("info depth 2 ")).collect();
+        assert_eq!(depth2.len(), 3, "{:?}", out);
+        assert!(depth2[2].contains(" multipv 3 "), "{}", depth2[2]);
+
+        // Weakest level: one depth, still a legal move
+        engine.handle("setoption name MultiPV value 1");
+        engine.handle("setoption name Skill Level value 0");
+        lines.lock().unwrap().clear();
+        engine.handle("go depth 6");
+        wait(&mut engine);
+        let out = lines.lock().unwrap().clone();
+        assert_eq!(out.len(), 2, "{:?}", out);
+        assert!(out[0].starts_with("info depth 1 ") && !out[0].contains("multipv"), "{}", out[0]);
+        assert!(out[1].starts_with("bestmove "));
+
+        engine.handle("setoption name Ponder value maybe");
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "info string Invalid value for Ponder: maybe");
+    }
+
+    #[test]
+    fn test_variant_option() {
+        let (mut engine, _) = engine();
+        engine.handle("setoption name UCI_Variant value three-check");
+        engine.handle("position startpos moves e2e4");
+        assert_eq!(engine.pos.variant(), Variant::ThreeCheck);
+        engine.handle("ucinewgame");
+        assert_eq!(engine.pos.variant(), Variant::ThreeCheck);
+    }
+
+    #[test]
+    fn test_bench() {
+        let (mut engine, lines) = engine();
+        engine.handle("bench 2");
+        let out = lines.lock().unwrap().clone();
+        assert!(out[0].starts_with("info string position 1 nodes "), "{}", out[0]);
+        assert_eq!(out[out.len() - 4], "info string bench depth 2");
+        let nodes = out[out.len() - 2].clone();
+        assert!(nodes.starts_with("Nodes searched  : "), "{}", nodes);
+
+        lines.lock().unwrap().clear();
+        engine.handle("bench 2");
+        assert_eq!(lines.lock().unwrap()[out.len() - 2], nodes);
+    }
+
+    #[test]
+    fn test_infinite_waits_for_stop() {
+        let (mut engine, lines) = engine();
+        engine.handle("go infinite");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!lines.lock().unwrap().iter().any(|l| l.starts_with("bestmove")));
+        engine.handle("stop");
+        assert!(lines.lock().unwrap().last().unwrap().starts_with("bestmove "));
+
+        // A node limit ends the search by itself
+        engine.handle("go nodes 5000");
+        wait(&mut engine);
+        assert!(lines.lock().unwrap().last().unwrap().starts_with("bestmove "));
+    }
+}
@@ -0,0 +1,636 @@
+// =============================================================================
+// XBoard Engine
+// =============================================================================
+// Speaks the Chess Engine Communication Protocol (xboard/WinBoard, protocol
+// version 2) on stdin/stdout. It shares its search, options and threads with
+// the uci binary through chess_engine::driver; unlike UCI, the engine keeps
+// the game itself, plays its moves with "move" and announces results.
+//
+// Usage:
+//   cargo run --release --bin xboard
+//   xboard -fcp target/release/xboard
+//   cutechess-cli -engine cmd=target/release/xboard proto=xboard -engine ... -each tc=40/60
+//
+// Supported: xboard, protover, new, variant, force, go, playother, usermove, ?,
+// ping, setboard, undo, remove, level, st, sd, time, otim, post, nopost,
+// analyze, exit, memory, cores, option, result, quit. Pondering (hard) is
+// accepted and ignored.
+// =============================================================================
+
+use chess_engine::driver::{SearchDriver, SearchEvent, SearchInfo, SearchLimits};
+use chess_engine::eval::MATE_SCORE;
+use chess_engine::movegen::generate_legal_moves;
+use chess_engine::position::Position;
+use chess_engine::types::{Color, Move};
+use chess_engine::variant::Variant;
+
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+
+/// Where the engine's lines go: stdout, or a buffer in tests
+type Output = Arc<dyn Fn(String) + Send + Sync>;
+
+/// What the main loop waits for: a line from the GUI, or an event of the
+/// search started in the given generation
+enum Input {
+    Line(String),
+    Search(u64, SearchEvent),
+}
+
+/// Score xboard shows as a mate: 100000 + moves
+const XBOARD_MATE: i32 = 100_000;
+
+// =============================================================================
+// CLOCK
+// =============================================================================
+
+/// Time controls set by "level", "st" and "sd", and the clocks from "time"
+/// and "otim"
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Clock {
+    /// Moves per time control, 0 for the whole game
+    moves_per_session: u32,
+    base_ms: f64,
+    inc_ms: f64,
+    /// Fixed time per move
+    move_time_ms: Option<f64>,
+    depth: Option<u8>,
+    own_ms: Option<f64>,
+    opponent_ms: Option<f64>,
+}
+
+impl Default for Clock {
+    /// xboard's own default: 40 moves in 5 minutes
+    fn default() -> Self {
+        Clock {
+            moves_per_session: 40,
+            base_ms: 300_000.0,
+            inc_ms: 0.0,
+            move_time_ms: None,
+            depth: None,
+            own_ms: None,
+            opponent_ms: None,
+        }
+    }
+}
+
+impl Clock {
+    /// Limits for the engine playing `engine` in `pos`
+    fn limits(&self, pos: &Position, engine: Color) -> SearchLimits {
+        let mut limits = SearchLimits { depth: self.depth, ..SearchLimits::default() };
+        if let Some(ms) = self.move_time_ms {
+            limits.movetime = Some(ms);
+            return limits;
+        }
+        let own = self.own_ms.unwrap_or(self.base_ms);
+        let other = self.opponent_ms.unwrap_or(self.base_ms);
+        let (white, black) = if engine == Color::White { (own, other) } else { (other, own) };
+        limits.wtime = Some(white);
+        limits.btime = Some(black);
+        limits.winc = self.inc_ms;
+        limits.binc = self.inc_ms;
+        if self.moves_per_session > 0 {
+            let played = (pos.fullmove_number().max(1) as u32 - 1) % self.moves_per_session;
+            limits.movestogo = Some(self.moves_per_session - played);
+        }
+        limits
+    }
+}
+
+/// The words after "level": moves per session, base time ("minutes" or
+/// "minutes:seconds") and increment in seconds, as (moves, base ms, inc ms)
+fn parse_level(args: &[&str]) -> Option<(u32, f64, f64)> {
+    let [moves, base, inc] = args else { return None };
+    let base_ms = match base.split_once(':') {
+        Some((min, sec)) => (min.parse::<f64>().ok()? * 60.0 + sec.parse::<f64>().ok()?) * 1000.0,
+        None => base.parse::<f64>().ok()? * 60_000.0,
+    };
+    Some((moves.parse().ok()?, base_ms, inc.parse::<f64>().ok()? * 1000.0))
+}
+
+// =============================================================================
+// PROTOCOL
+// =============================================================================
+
+/// Name of a variant in xboard's "variant" command and "variants" feature
+fn xboard_variant(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "normal",
+        Variant::ThreeCheck => "3check",
+        other => other.name(),
+    }
+}
+
+fn parse_variant(name: &str) -> Option<Variant> {
+    Variant::ALL.into_iter().find(|&v| xboard_variant(v) == name).or_else(|| Variant::from_name(name))
+}
+
+/// The reply to "protover 2"
+fn feature_lines() -> Vec<String> {
+    let variants: Vec<&str> = Variant::ALL.into_iter().map(xboard_variant).collect();
+    vec![
+        "feature done=0".to_string(),
+        format!(
+            "feature myname=\"Promotion Variant Chess\" setboard=1 usermove=1 ping=1 sigint=0 sigterm=0 \
+             analyze=1 colors=0 memory=1 smp=1 variants=\"{}\"",
+            variants.join(",")
+        ),
+        "feature option=\"MultiPV -spin 1 1 64\"".to_string(),
+        "feature option=\"Skill Level -spin 20 0 20\"".to_string(),
+        "feature done=1".to_string(),
+    ]
+}
+
+/// Score in centipawns, or XBOARD_MATE + moves to mate (negative when mated)
+fn xboard_score(score: i32) -> i32 {
+    let plies = MATE_SCORE - score.abs();
+    if plies < 256 {
+        let moves = (plies + 1) / 2;
+        if score > 0 { XBOARD_MATE + moves } else { -(XBOARD_MATE + moves) }
+    } else {
+        score
+    }
+}
+
+/// A thinking line: "ply score time nodes pv", time in centiseconds
+fn thinking_line(info: &SearchInfo) -> String {
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_uci()).collect();
+    format!(
+        "{} {} {} {} {}",
+        info.depth,
+        xboard_score(info.score),
+        (info.time_ms / 10.0) as u64,
+        info.nodes,
+        pv.join(" ")
+    )
+}
+
+fn parse_move(pos: &Position, text: &str) -> Option<Move> {
+    generate_legal_moves(pos).iter().copied().find(|m| m.to_uci() == text)
+}
+
+// =============================================================================
+// ENGINE
+// =============================================================================
+
+struct Engine {
+    /// Positions since the start of the game (or the last "setboard"),
+    /// the current one last
+    game: Vec<Position>,
+    driver: SearchDriver,
+    /// Bumped when a search is abandoned, so its late events are dropped
+    generation: Arc<AtomicU64>,
+    /// Side the engine plays; None in force mode
+    side: Option<Color>,
+    clock: Clock,
+    post: bool,
+    analyzing: bool,
+    out: Output,
+}
+
+impl Engine {
+    /// An engine whose search events come back as `Input::Search` on `events`
+    fn new(out: Output, events: Sender<Input>) -> Self {
+        let generation = Arc::new(AtomicU64::new(0));
+        let current = Arc::clone(&generation);
+        let sink = move |event| {
+            let _ = events.send(Input::Search(current.load(Ordering::SeqCst), event));
+        };
+        Engine {
+            game: vec![Position::starting_position()],
+            driver: SearchDriver::new(Arc::new(sink)),
+            generation,
+            side: Some(Color::Black),
+            clock: Clock::default(),
+            post: false,
+            analyzing: false,
+            out,
+        }
+    }
+
+    fn send(&self, line: impl Into<String>) {
+        (self.out)(line.into());
+    }
+
+    fn pos(&self) -> &Position {
+        self.game.last().expect("game has a position")
+    }
+
+    /// Handle a line or a search event. Returns false on "quit".
+    fn handle(&mut self, input: Input) -> bool {
+        match input {
+            Input::Line(line) => return self.command(&line),
+            Input::Search(generation, event) if generation == self.generation.load(Ordering::SeqCst) => {
+                self.search_event(event)
+            }
+            Input::Search(..) => {}
+        }
+        true
+    }
+
+    fn command(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else { return true };
+        match command {
+            "xboard" | "accepted" | "rejected" | "hard" | "easy" | "random" | "computer" | "name" | "rating"
+            | "ics" | "." => {}
+            "protover" => {
+                for line in feature_lines() {
+                    self.send(line);
+                }
+            }
+            "new" => {
+                self.cancel();
+                self.driver.new_game();
+                if self.driver.options().variant != Variant::Standard {
+                    self.set_option("Variant", Variant::Standard.name());
+                }
+                self.reset(Position::starting_position());
+                self.side = Some(Color::Black);
+                self.clock = Clock { depth: None, own_ms: None, opponent_ms: None, ..self.clock };
+            }
+            "variant" => match args.first().and_then(|name| parse_variant(name)) {
+                Some(variant) => {
+                    self.cancel();
+                    self.set_option("Variant", variant.name());
+                    self.reset(Position::starting_position());
+                }
+                None => self.send(format!("Error (unsupported variant): {}", args.join(" "))),
+            },
+            "force" => {
+                self.cancel();
+                self.side = None;
+            }
+            "go" => {
+                self.cancel();
+                self.side = Some(self.pos().side_to_move());
+                self.think();
+            }
+            "playother" => {
+                self.cancel();
+                self.side = Some(self.pos().side_to_move().flip());
+            }
+            "usermove" => self.user_move(args.first().copied().unwrap_or_default()),
+            "?" => {
+                // The move arrives as a search event
+                if !self.analyzing {
+                    self.driver.stop();
+                }
+            }
+            "ping" => self.send(format!("pong {}", args.join(" "))),
+            "setboard" => {
+                self.cancel();
+                match Position::from_fen_strict(&args.join(" ")) {
+                    Ok(pos) => {
+                        self.reset(pos);
+                        self.restart_analysis();
+                    }
+                    Err(_) => self.send("tellusererror Illegal position"),
+                }
+            }
+            "undo" | "remove" => {
+                self.cancel();
+                let plies = if command == "undo" { 1 } else { 2 };
+                let keep = self.game.len().saturating_sub(plies).max(1);
+                self.game.truncate(keep);
+                self.restart_analysis();
+            }
+            "level" => match parse_level(args) {
+                Some((moves, base_ms, inc_ms)) => {
+                    self.clock =
+                        Clock { moves_per_session: moves, base_ms, inc_ms, move_time_ms: None, ..self.clock };
+                }
+                None => self.send(format!("Error (bad level): {}", args.join(" "))),
+            },
+            "st" => self.clock.move_time_ms = args.first().and_then(|s| s.parse::<f64>().ok()).map(|s| s * 1000.0),
+            "sd" => self.clock.depth = args.first().and_then(|d| d.parse::<u8>().ok()).filter(|&d| d > 0),
+            "time" => self.clock.own_ms = args.first().and_then(|t| t.parse::<f64>().ok()).map(|cs| cs * 10.0),
+            "otim" => self.clock.opponent_ms = args.first().and_then(|t| t.parse::<f64>().ok()).map(|cs| cs * 10.0),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "analyze" => {
+                self.cancel();
+                self.side = None;
+                self.analyzing = true;
+                self.restart_analysis();
+            }
+            "exit" => {
+                self.cancel();
+                self.analyzing = false;
+            }
+            "memory" => self.set_option("Hash", &args.join(" ")),
+            "cores" => self.set_option("Threads", &args.join(" ")),
+            "option" => match args.join(" ").split_once('=') {
+                Some((name, value)) => self.set_option(name, value),
+                None => self.send(format!("Error (bad option): {}", args.join(" "))),
+            },
+            "result" => {
+                self.cancel();
+                self.side = None;
+            }
+            "quit" => {
+                self.cancel();
+                return false;
+            }
+            _ => self.send(format!("Error (unknown command): {}", command)),
+        }
+        true
+    }
+
+    fn search_event(&mut self, event: SearchEvent) {
+        match event {
+            SearchEvent::Info(info) => {
+                if self.post || self.analyzing {
+                    self.send(thinking_line(&info));
+                }
+            }
+            // Analysis ends with "exit", not with a move
+            SearchEvent::BestMove { .. } if self.analyzing => {}
+            SearchEvent::BestMove { best: Some(best), .. } => {
+                self.play(best);
+                self.send(format!("move {}", best.to_uci()));
+                self.announce_result();
+            }
+            SearchEvent::BestMove { best: None, .. } => self.announce_result(),
+        }
+    }
+
+    fn user_move(&mut self, text: &str) {
+        let Some(m) = parse_move(self.pos(), text) else {
+            self.send(format!("Illegal move: {}", text));
+            return;
+        };
+        self.cancel();
+        self.play(m);
+        if self.analyzing {
+            self.restart_analysis();
+        } else if self.side == Some(self.pos().side_to_move()) {
+            self.think();
+        } else {
+            self.announce_result();
+        }
+    }
+
+    /// Abandon the running search, dropping the events it still has queued
+    fn cancel(&mut self) {
+        self.driver.stop();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) {
+        self.cancel();
+        match self.driver.set_option(name.trim(), value.trim()) {
+            Ok(()) => {
+                let variant = self.driver.options().variant;
+                for pos in &mut self.game {
+                    pos.set_variant(variant);
+                }
+            }
+            Err(e) => self.send(format!("Error (bad option): {}", e)),
+        }
+    }
+
+    /// Start a game from `pos` under the current variant
+    fn reset(&mut self, mut pos: Position) {
+        pos.set_variant(self.driver.options().variant);
+        self.game = vec![pos];
+    }
+
+    fn play(&mut self, m: Move) {
+        let mut pos = self.pos().clone();
+        pos.make_move(m);
+        self.game.push(pos);
+    }
+
+    /// Search for the engine's move, unless the game is over
+    fn think(&mut self) {
+        let Some(side) = self.side else { return };
+        if self.result().is_some() {
+            self.announce_result();
+            return;
+        }
+        let pos = self.pos().clone();
+        self.driver.go(&pos, self.clock.limits(&pos, side));
+    }
+
+    fn restart_analysis(&mut self) {
+        if self.analyzing && self.result().is_none() {
+            self.cancel();
+            let pos = self.pos().clone();
+            self.driver.go(&pos, SearchLimits { infinite: true, ..SearchLimits::default() });
+        }
+    }
+
+    /// The game's result and reason, once it is over
+    fn result(&self) -> Option<String> {
+        let pos = self.pos();
+        let score = |winner: Color| if winner == Color::White { "1-0" } else { "0-1" };
+        let name = |color: Color| if color == Color::White { "White" } else { "Black" };
+        if let Some((winner, _)) = pos.variant().outcome(pos) {
+            return Some(format!("{} {{{} wins by three checks}}", score(winner), name(winner)));
+        }
+        if pos.is_checkmate() {
+            let winner = pos.side_to_move().flip();
+            return Some(format!("{} {{{} mates}}", score(winner), name(winner)));
+        }
+        if pos.is_stalemate() {
+            return Some("1/2-1/2 {Stalemate}".to_string());
+        }
+        if pos.is_dead_position() {
+            return Some("1/2-1/2 {Insufficient material}".to_string());
+        }
+        if pos.is_fifty_move_draw() {
+            return Some("1/2-1/2 {Fifty move rule}".to_string());
+        }
+        if self.game.iter().filter(|p| p.hash() == pos.hash()).count() >= 3 {
+            return Some("1/2-1/2 {Draw by repetition}".to_string());
+        }
+        None
+    }
+
+    /// Tell the GUI the game is over, when it is and the engine is playing
+    fn announce_result(&mut self) {
+        if self.side.is_none() {
+            return;
+        }
+        if let Some(result) = self.result() {
+            self.send(result);
+            self.side = None;
+        }
+    }
+}
+
+fn main() {
+    let (events, inputs) = mpsc::channel();
+    let mut engine = Engine::new(Arc::new(|line| println!("{}", line)), events.clone());
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if events.send(Input::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = events.send(Input::Line("quit".to_string()));
+    });
+    for input in inputs {
+        if !engine.handle(input) {
+            return;
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Receiver;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct Harness {
+        engine: Engine,
+        inputs: Receiver<Input>,
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let lines = Arc::new(Mutex::new(Vec::new()));
+            let sink = Arc::clone(&lines);
+            let (events, inputs) = mpsc::channel();
+            let engine = Engine::new(Arc::new(move |line| sink.lock().unwrap().push(line)), events);
+            Harness { engine, inputs, lines }
+        }
+
+        fn send(&mut self, commands: &[&str]) {
+            for command in commands {
+                assert!(self.engine.handle(Input::Line(command.to_string())));
+            }
+        }
+
+        /// Feed search events to the engine until a line starts with `prefix`
+        fn until(&mut self, prefix: &str) -> Vec<String> {
+            while !self.lines.lock().unwrap().iter().any(|l| l.starts_with(prefix)) {
+                let input = self.inputs.recv_timeout(Duration::from_secs(10)).expect("no search event");
+                self.engine.handle(input);
+            }
+            self.lines.lock().unwrap().clone()
+        }
+
+        fn last(&self) -> String {
+            self.lines.lock().unwrap().last().cloned().unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn test_parse_level_and_clock() {
+        assert_eq!(parse_level(&["40", "5", "0"]), Some((40, 300_000.0, 0.0)));
+        assert_eq!(parse_level(&["0", "2:30", "1.5"]), Some((0, 150_000.0, 1500.0)));
+        assert_eq!(parse_level(&["40", "5"]), None);
+
+        let pos = Position::from_fen("8/8/8/4k3/8/8/8/4K3 w - - 0 45").unwrap();
+        let clock = Clock { own_ms: Some(20_000.0), opponent_ms: Some(9000.0), ..Clock::default() };
+        let limits = clock.limits(&pos, Color::Black);
+        assert_eq!((limits.wtime, limits.btime, limits.movestogo), (Some(9000.0), Some(20_000.0), Some(36)));
+        let limits = Clock { move_time_ms: Some(2000.0), depth: Some(4), ..clock }.limits(&pos, Color::White);
+        assert_eq!((limits.movetime, limits.wtime, limits.depth), (Some(2000.0), None, Some(4)));
+    }
+
+    #[test]
+    fn test_protocol_output() {
+        let features = feature_lines();
+        assert_eq!(features.last().unwrap(), "feature done=1");
+        assert!(features[1].contains("usermove=1") && features[1].contains("setboard=1"));
+        assert!(features[1].ends_with("variants=\"normal,early-promotion,capture-limited,3check\""));
+        assert_eq!(parse_variant("3check"), Some(Variant::ThreeCheck));
+        assert_eq!(parse_variant("normal"), Some(Variant::Standard));
+        assert_eq!(parse_variant("crazyhouse"), None);
+
+        assert_eq!(xboard_score(-35), -35);
+        assert_eq!(xboard_score(MATE_SCORE - 3), 100_002);
+        assert_eq!(xboard_score(-MATE_SCORE + 2), -100_001);
+    }
+
+    #[test]
+    fn test_plays_and_announces_mate() {
+        let mut h = Harness::new();
+        h.send(&["xboard", "protover 2", "new", "sd 3", "post", "usermove e2e4"]);
+        let out = h.until("move ");
+        assert!(out.iter().any(|l| l.starts_with("3 ")), "{:?}", out);
+        assert_eq!(h.engine.game.len(), 3);
+
+        // Fool's mate, found and announced by the engine playing black
+        h.send(&["new", "force", "usermove f2f3", "usermove e7e5", "usermove g2g4", "sd 2", "go"]);
+        let out = h.until("0-1");
+        assert_eq!(out[out.len() - 2], "move d8h4");
+        assert_eq!(h.last(), "0-1 {Black mates}");
+        assert_eq!(h.engine.side, None);
+    }
+
+    #[test]
+    fn test_board_commands() {
+        let mut h = Harness::new();
+        h.send(&["new", "force", "usermove e2e5"]);
+        assert_eq!(h.last(), "Illegal move: e2e5");
+        h.send(&["usermove e2e4", "usermove e7e5", "usermove g1f3", "undo"]);
+        assert_eq!(h.engine.game.len(), 3);
+        h.send(&["remove"]);
+        assert_eq!(h.engine.game.len(), 1);
+        h.send(&["ping 7"]);
+        assert_eq!(h.last(), "pong 7");
+
+        h.send(&["setboard 8/8/8/8/8/8/8/8 w - - 0 1"]);
+        assert_eq!(h.last(), "tellusererror Illegal position");
+        h.send(&["setboard 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "usermove e2e4"]);
+        assert_eq!(h.engine.pos().to_fen(), "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+
+        // The third occurrence of a position is a draw
+        h.send(&["setboard 4k3/8/8/8/8/8/8/R3K3 w - - 0 1"]);
+        for _ in 0..2 {
+            h.send(&["usermove a1a2", "usermove e8d8", "usermove a2a1", "usermove d8e8"]);
+        }
+        assert_eq!(h.engine.result().as_deref(), Some("1/2-1/2 {Draw by repetition}"));
+    }
+
+    #[test]
+    fn test_options_and_variant() {
+        let mut h = Harness::new();
+        h.send(&["memory 64", "cores 2", "option MultiPV=2", "variant 3check"]);
+        let options = h.engine.driver.options();
+        assert_eq!((options.hash_mb, options.threads, options.multipv), (64, 2, 2));
+        assert_eq!(h.engine.pos().variant(), Variant::ThreeCheck);
+        h.send(&["option Skill Level=99"]);
+        assert_eq!(h.last(), "Error (bad option): Invalid value for Skill Level: 99");
+        h.send(&["variant shogi"]);
+        assert_eq!(h.last(), "Error (unsupported variant): shogi");
+        h.send(&["new"]);
+        assert_eq!(h.engine.pos().variant(), Variant::Standard);
+    }
+
+    #[test]
+    fn test_analyze_and_move_now() {
+        let mut h = Harness::new();
+        h.send(&["new", "force", "analyze"]);
+        h.until("1 ");
+        h.send(&["usermove e2e4"]);
+        h.lines.lock().unwrap().clear();
+        h.until("1 ");
+        h.send(&["exit"]);
+        while let Ok(input) = h.inputs.try_recv() {
+            h.engine.handle(input);
+        }
+        assert!(!h.lines.lock().unwrap().iter().any(|l| l.starts_with("move")));
+
+        // "?" plays the best move found so far
+        h.send(&["st 30", "go"]);
+        std::thread::sleep(Duration::from_millis(20));
+        h.send(&["?"]);
+        h.until("move ");
+        assert!(!h.engine.driver.is_thinking());
+    }
+}
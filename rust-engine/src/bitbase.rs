@@ -0,0 +1,248 @@
+// KPK Bitbase
+// Win/draw result for every king + pawn vs king position, built once by
+// retrograde analysis. One bit per position: 2 sides to move × 24 pawn
+// squares (files a-d, ranks 2-7; e-h are mirrored) × 64 × 64 king squares
+// = 196,608 bits (24 KB).
+
+use crate::attacks::{king_attacks, pawn_attacks};
+use crate::types::{Color, Square};
+use std::sync::OnceLock;
+
+const MAX_INDEX: usize = 2 * 24 * 64 * 64;
+
+// Classification results, combinable as bit flags during analysis
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// Index of a normalized position: strong side is white, pawn on files a-d.
+/// `stm` is 0 when white (the strong side) is to move.
+#[inline]
+fn index(stm: usize, black_king: u8, white_king: u8, pawn: u8) -> usize {
+    let file = (pawn % 8) as usize;
+    let rank = (pawn / 8) as usize;
+    white_king as usize | (black_king as usize) << 6 | stm << 12 | file << 13 | (6 - rank) << 15
+}
+
+#[inline]
+fn distance(a: u8, b: u8) -> u8 {
+    let (fa, ra, fb, rb) = (a % 8, a / 8, b % 8, b / 8);
+    fa.abs_diff(fb).max(ra.abs_diff(rb))
+}
+
+#[inline]
+fn attacks_of_king(sq: u8) -> u64 {
+    king_attacks(Square::new(sq)).0
+}
+
+/// Decode an index into (stm, black king, white king, pawn).
+fn decode(idx: usize) -> (usize, u8, u8, u8) {
+    let white_king = (idx & 63) as u8;
+    let black_king = ((idx >> 6) & 63) as u8;
+    let stm = (idx >> 12) & 1;
+    let file = ((idx >> 13) & 3) as u8;
+    let rank = 6 - ((idx >> 15) as u8);
+    (stm, black_king, white_king, rank * 8 + file)
+}
+
+/// Result of a position from the rules alone, before any search.
+fn initial_result(idx: usize) -> u8 {
+    let (stm, bk, wk, psq) = decode(idx);
+    let pawn_attacks_bb = pawn_attacks(Square::new(psq), true).0;
+
+    if distance(wk, bk) <= 1
+        || wk == psq
+        || bk == psq
+        || (stm == 0 && pawn_attacks_bb & (1u64 << bk) != 0)
+    {
+        return INVALID;
+    }
+
+    // White to move can promote safely
+    if stm == 0
+        && psq / 8 == 6
+        && wk != psq + 8
+        && (distance(bk, psq + 8) > 1 || attacks_of_king(wk) & (1u64 << (psq + 8)) != 0)
+    {
+        return WIN;
+    }
+
+    // Black to move is stalemated, or can take an undefended pawn
+    if stm == 1 {
+        let black_moves = attacks_of_king(bk) & !(attacks_of_king(wk) | pawn_attacks_bb);
+        if black_moves == 0 || attacks_of_king(bk) & !attacks_of_king(wk) & (1u64 << psq) != 0 {
+            return DRAW;
+        }
+    }
+
+    UNKNOWN
+}
+
+/// Combine the results of all moves from `idx`: the side to move picks the best one.
+fn classify(db: &[u8], idx: usize) -> u8 {
+    let (stm, bk, wk, psq) = decode(idx);
+    let (good, bad) = if stm == 0 { (WIN, DRAW) } else { (DRAW, WIN) };
+    let mut r = INVALID;
+
+    let mut moves = attacks_of_king(if stm == 0 { wk } else { bk });
+    while moves != 0 {
+        let to = moves.trailing_zeros() as u8;
+        moves &= moves - 1;
+        r |= if stm == 0 { db[index(1, bk, to, psq)] } else { db[index(0, to, wk, psq)] };
+    }
+
+    if stm == 0 {
+        // Single push (promotion from the 7th is handled by initial_result)
+        if psq / 8 < 6 {
+            r |= db[index(1, bk, wk, psq + 8)];
+        }
+        // Double push from the 2nd rank
+        if psq / 8 == 1 && psq + 8 != wk && psq + 8 != bk {
+            r |= db[index(1, bk, wk, psq + 16)];
+        }
+    }
+
+    if r & good != 0 {
+        good
+    } else if r & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+/// Packed win bits for every normalized position.
+pub struct Bitbase {
+    bits: Vec<u64>,
+}
+
+impl Bitbase {
+    /// Run the retrograde analysis.
+    fn generate() -> Self {
+        let mut db: Vec<u8> = (0..MAX_INDEX).map(initial_result).collect();
+
+        // Iterate until no unknown position can be resolved
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in 0..MAX_INDEX {
+                if db[idx] == UNKNOWN {
+                    let result = classify(&db, idx);
+                    if result != UNKNOWN {
+                        db[idx] = result;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut bits = vec![0u64; MAX_INDEX / 64];
+        for (idx, &result) in db.iter().enumerate() {
+            if result == WIN {
+                bits[idx / 64] |= 1u64 << (idx % 64);
+            }
+        }
+        Bitbase { bits }
+    }
+
+    #[inline]
+    fn is_win_index(&self, idx: usize) -> bool {
+        self.bits[idx / 64] & (1u64 << (idx % 64)) != 0
+    }
+}
+
+static BITBASE: OnceLock<Bitbase> = OnceLock::new();
+
+/// Get the global bitbase (generated on first call)
+fn bitbase() -> &'static Bitbase {
+    BITBASE.get_or_init(Bitbase::generate)
+}
+
+/// Is this KPK position a win for the side with the pawn?
+/// `strong` is the color of the pawn side; squares are real board squares.
+pub fn probe_kpk(strong: Color, strong_king: Square, pawn: Square, weak_king: Square, side_to_move: Color) -> bool {
+    // Normalize: strong side plays "up" as white, pawn on files a-d
+    let flip_rank = if strong == Color::White { 0 } else { 56 };
+    let flip_file = if pawn.file() >= 4 { 7 } else { 0 };
+    let norm = |sq: Square| sq.0 ^ flip_rank ^ flip_file;
+
+    let stm = if side_to_move == strong { 0 } else { 1 };
+    bitbase().is_win_index(index(stm, norm(weak_king), norm(strong_king), norm(pawn)))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+    use crate::types::PieceType;
+
+    /// Probe a K+P vs K position given by FEN.
+    fn probe_fen(fen: &str) -> bool {
+        let pos = Position::from_fen(fen).unwrap();
+        let strong = if pos.pieces(Color::White, PieceType::Pawn).is_not_empty() { Color::White } else { Color::Black };
+        let weak = strong.flip();
+        probe_kpk(
+            strong,
+            pos.pieces(strong, PieceType::King).lsb().unwrap(),
+            pos.pieces(strong, PieceType::Pawn).lsb().unwrap(),
+            pos.pieces(weak, PieceType::King).lsb().unwrap(),
+            pos.side_to_move(),
+        )
+    }
+
+    #[test]
+    fn test_win_count() {
+        // Sanity check on the whole table: most KPK positions are wins, but far from all
+        let wins: u32 = bitbase().bits.iter().map(|w| w.count_ones()).sum();
+        assert!(wins > 60_000 && wins < 140_000, "Unexpected number of wins: {}", wins);
+    }
+
+    #[test]
+    fn test_index_roundtrip() {
+        for idx in [0, 1, 4095, 4096, 8191, 8192, MAX_INDEX - 1] {
+            let (stm, bk, wk, psq) = decode(idx);
+            assert_eq!(index(stm, bk, wk, psq), idx);
+        }
+    }
+
+    #[test]
+    fn test_king_in_front_on_sixth_wins() {
+        assert!(probe_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"));
+        assert!(probe_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"));
+    }
+
+    #[test]
+    fn test_opposition_draws() {
+        // Defending king in front of the pawn, attacker behind it
+        assert!(!probe_fen("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1"));
+        // Pawn on the 6th with the defender in front: every try ends in stalemate
+        assert!(!probe_fen("4k3/8/4P3/4K3/8/8/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_rook_pawn_corner_draws() {
+        assert!(!probe_fen("k7/8/1K6/P7/8/8/8/8 w - - 0 1"));
+        assert!(!probe_fen("7k/8/6K1/7P/8/8/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_rule_of_the_square() {
+        // Black king can't catch the a-pawn
+        assert!(probe_fen("7k/8/8/8/P7/8/8/7K w - - 0 1"));
+        // Black to move steps into the square and catches it
+        assert!(!probe_fen("8/8/8/4k3/P7/8/8/7K b - - 0 1"));
+    }
+
+    #[test]
+    fn test_black_pawn_is_mirrored() {
+        // Same as the white "king in front on the 6th" win, colors flipped
+        assert!(probe_fen("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"));
+        // White king blocks the black pawn's path
+        assert!(!probe_fen("4k3/4p3/8/8/8/4K3/8/8 w - - 0 1"));
+    }
+}
@@ -0,0 +1,281 @@
+// Opening Book
+// Books in this engine's own format: a 16-byte signature, then 16-byte
+// big-endian entries (key, move, weight, learn) sorted by key, several entries
+// per position. Entries are laid out as in Polyglot books, but keyed by this
+// engine's Zobrist hashes rather than Polyglot's Random64 keys, so the two
+// can't read each other's books; the signature lets a Polyglot book be turned
+// away instead of silently matching no position.
+// Moves use Polyglot's encoding: to-square in bits 0-5, from-square in bits
+// 6-11, promotion piece in bits 12-14 (1 = knight ... 4 = queen, 5 = king for
+// variants that allow it), castling written as the king taking its rook.
+
+use std::collections::HashMap;
+
+use crate::movegen::generate_legal_moves;
+use crate::position::Position;
+use crate::types::{Move, PieceType, Square};
+
+/// Bytes per book entry
+pub const ENTRY_SIZE: usize = 16;
+
+/// First bytes of every book file
+pub const SIGNATURE: [u8; ENTRY_SIZE] = *b"PVC opening book";
+
+/// One move for one position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    /// Zobrist hash of the position
+    pub key: u64,
+    /// Move in Polyglot encoding
+    pub mv: u16,
+    /// Relative frequency of the move; 0 means never play it
+    pub weight: u16,
+    /// Unused here, kept for the format
+    pub learn: u32,
+}
+
+/// Polyglot encoding of a move
+pub fn encode_move(m: Move) -> u16 {
+    let from = m.from();
+    let mut to = m.to();
+    if m.is_castling() {
+        // King takes rook: e1g1 -> e1h1, e1c1 -> e1a1
+        to = Square::from_file_rank(if to.file() > from.file() { 7 } else { 0 }, to.rank());
+    }
+    let promo = match m.promotion_piece() {
+        Some(PieceType::Knight) => 1,
+        Some(PieceType::Bishop) => 2,
+        Some(PieceType::Rook) => 3,
+        Some(PieceType::Queen) => 4,
+        Some(PieceType::King) => 5,
+        _ => 0,
+    };
+    to.0 as u16 | (from.0 as u16) << 6 | promo << 12
+}
+
+/// The legal move in `pos` with this Polyglot encoding
+pub fn decode_move(pos: &Position, raw: u16) -> Option<Move> {
+    generate_legal_moves(pos).iter().copied().find(|&m| encode_move(m) == raw)
+}
+
+/// Book file bytes: the signature, then the entries sorted by key and then by
+/// weight, heaviest first
+pub fn write_book(entries: &[BookEntry]) -> Vec<u8> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|e| (e.key, std::cmp::Reverse(e.weight), e.mv));
+    let mut out = Vec::with_capacity((sorted.len() + 1) * ENTRY_SIZE);
+    out.extend_from_slice(&SIGNATURE);
+    for e in sorted {
+        out.extend_from_slice(&e.key.to_be_bytes());
+        out.extend_from_slice(&e.mv.to_be_bytes());
+        out.extend_from_slice(&e.weight.to_be_bytes());
+        out.extend_from_slice(&e.learn.to_be_bytes());
+    }
+    out
+}
+
+/// Entries of a book file, in file order
+pub fn read_book(bytes: &[u8]) -> Result<Vec<BookEntry>, &'static str> {
+    let Some(entries) = bytes.strip_prefix(&SIGNATURE) else {
+        return Err("Not an opening book from this engine (Polyglot books use other position keys)");
+    };
+    if !entries.len().is_multiple_of(ENTRY_SIZE) {
+        return Err("Book size is not a multiple of 16 bytes");
+    }
+    Ok(entries
+        .chunks_exact(ENTRY_SIZE)
+        .map(|e| BookEntry {
+            key: u64::from_be_bytes(e[0..8].try_into().unwrap()),
+            mv: u16::from_be_bytes([e[8], e[9]]),
+            weight: u16::from_be_bytes([e[10], e[11]]),
+            learn: u32::from_be_bytes(e[12..16].try_into().unwrap()),
+        })
+        .collect())
+}
+
+/// Book moves for `pos` with their weights, heaviest first. `book` must be
+/// sorted by key, as `write_book` leaves it.
+pub fn probe(book: &[BookEntry], pos: &Position) -> Vec<(Move, u16)> {
+    let key = pos.hash();
+    let start = book.partition_point(|e| e.key < key);
+    let mut moves: Vec<(Move, u16)> = book[start..]
+        .iter()
+        .take_while(|e| e.key == key)
+        .filter(|e| e.weight > 0)
+        .filter_map(|e| decode_move(pos, e.mv).map(|m| (m, e.weight)))
+        .collect();
+    moves.sort_by_key(|&(_, w)| std::cmp::Reverse(w));
+    moves
+}
+
+// =============================================================================
+// BUILDING
+// =============================================================================
+
+/// How a book move's weight comes from the games it was played in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weighting {
+    /// Number of games
+    Count,
+    /// Points scored by the side playing it: 2 per win, 1 per draw, so
+    /// moves that win more often get played more
+    Score,
+}
+
+#[derive(Clone, Copy, Default)]
+struct MoveStats {
+    games: u32,
+    /// Half points for the side that played the move
+    points: u32,
+}
+
+/// Collects the opening moves of many games into a book
+pub struct BookBuilder {
+    max_ply: usize,
+    stats: HashMap<(u64, u16), MoveStats>,
+    games: usize,
+}
+
+impl BookBuilder {
+    /// A builder keeping the first `max_ply` moves of each game
+    pub fn new(max_ply: usize) -> Self {
+        BookBuilder { max_ply, stats: HashMap::new(), games: 0 }
+    }
+
+    /// Games added so far
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Add a game's moves from `start`, with its result ("1-0", "0-1" or
+    /// "1/2-1/2"). Unfinished games ("*") are skipped; returns whether the
+    /// game was used. Moves must be legal.
+    pub fn add_game(&mut self, start: &Position, moves: &[Move], result: &str) -> bool {
+        let white_points = match result {
+            "1-0" => 2,
+            "0-1" => 0,
+            "1/2-1/2" => 1,
+            _ => return false,
+        };
+        let mut pos = start.clone();
+        for &m in moves.iter().take(self.max_ply) {
+            let points = if pos.side_to_move() == crate::types::Color::White { white_points } else { 2 - white_points };
+            let stats = self.stats.entry((pos.hash(), encode_move(m))).or_default();
+            stats.games += 1;
+            stats.points += points;
+            if pos.make_move(m).is_none() {
+                break;
+            }
+        }
+        self.games += 1;
+        true
+    }
+
+    /// Book entries for moves played in at least `min_games` games. Moves
+    /// that scored nothing are left out with Score weighting; weights are
+    /// scaled down to fit 16 bits if needed.
+    pub fn build(&self, min_games: u32, weighting: Weighting) -> Vec<BookEntry> {
+        let weight = |s: &MoveStats| match weighting {
+            Weighting::Count => s.games,
+            Weighting::Score => s.points,
+        };
+        let kept: Vec<(u64, u16, u32)> = self
+            .stats
+            .iter()
+            .filter(|(_, s)| s.games >= min_games.max(1) && weight(s) > 0)
+            .map(|(&(key, mv), s)| (key, mv, weight(s)))
+            .collect();
+        let max = kept.iter().map(|&(_, _, w)| w).max().unwrap_or(0);
+        let scale = if max > u16::MAX as u32 { u16::MAX as f64 / max as f64 } else { 1.0 };
+        let mut entries: Vec<BookEntry> = kept
+            .into_iter()
+            .map(|(key, mv, w)| BookEntry { key, mv, weight: ((w as f64 * scale) as u16).max(1), learn: 0 })
+            .collect();
+        entries.sort_by_key(|e| (e.key, std::cmp::Reverse(e.weight), e.mv));
+        entries
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn moves(pos: &Position, ucis: &[&str]) -> Vec<Move> {
+        let mut pos = pos.clone();
+        ucis.iter()
+            .map(|uci| {
+                let m = generate_legal_moves(&pos).iter().copied().find(|m| m.to_uci() == *uci).unwrap();
+                pos.make_move(m);
+                m
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_move_encoding() {
+        let e2e4 = Move::new(Square::E2, sq("e4"));
+        assert_eq!(encode_move(e2e4), 28 | 12 << 6);
+        assert_eq!(encode_move(Move::new_castling(Square::E1, Square::G1)), 7 | 4 << 6);
+        assert_eq!(encode_move(Move::new_castling(Square::E8, Square::C8)), 56 | 60 << 6);
+        let promo = Move::new_promotion(Square::from_file_rank(0, 6), Square::from_file_rank(0, 7), PieceType::Queen);
+        assert_eq!(encode_move(promo) >> 12, 4);
+
+        let pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(decode_move(&pos, 7 | 4 << 6), Some(Move::new_castling(Square::E1, Square::G1)));
+        assert_eq!(decode_move(&pos, encode_move(e2e4)), None);
+    }
+
+    #[test]
+    fn test_build_write_and_probe() {
+        let start = Position::starting_position();
+        let mut builder = BookBuilder::new(2);
+        for (line, result) in [
+            (&["e2e4", "e7e5", "g1f3"][..], "1-0"),
+            (&["e2e4", "c7c5"], "0-1"),
+            (&["e2e4", "e7e5"], "1/2-1/2"),
+            (&["d2d4", "d7d5"], "1-0"),
+        ] {
+            assert!(builder.add_game(&start, &moves(&start, line), result));
+        }
+        assert!(!builder.add_game(&start, &moves(&start, &["e2e4"]), "*"));
+        assert_eq!(builder.games(), 4);
+
+        // e4: 3 games, 1 win + 1 draw for white = 3 points; d4: 1 win = 2 points
+        let book = builder.build(1, Weighting::Score);
+        let bytes = write_book(&book);
+        assert_eq!(bytes.len(), (book.len() + 1) * ENTRY_SIZE);
+        let book = read_book(&bytes).unwrap();
+        let e4 = Move::new(Square::E2, sq("e4"));
+        let d4 = Move::new(Square::D2, sq("d4"));
+        assert_eq!(probe(&book, &start), [(e4, 3), (d4, 2)]);
+
+        // Only moves from at least two games; Nf3 is past the ply limit
+        let book = builder.build(2, Weighting::Count);
+        assert_eq!(probe(&book, &start), [(e4, 3)]);
+        let mut pos = start.clone();
+        pos.make_move(e4);
+        assert_eq!(probe(&book, &pos), [(Move::new(Square::E7, sq("e5")), 2)]);
+        pos.make_move(Move::new(Square::E7, sq("e5")));
+        assert!(probe(&book, &pos).is_empty());
+
+        assert!(read_book(&bytes[..ENTRY_SIZE + 15]).is_err());
+    }
+
+    #[test]
+    fn test_foreign_books_are_rejected() {
+        // Polyglot layout without the signature: the first entry is a position
+        let entry = BookEntry { key: 0x463b_9618_1691_fc9c, mv: 28 | 12 << 6, weight: 1, learn: 0 };
+        let polyglot = write_book(&[entry])[ENTRY_SIZE..].to_vec();
+        assert!(read_book(&polyglot).unwrap_err().contains("Polyglot"));
+        assert!(read_book(&[]).is_err());
+        assert_eq!(read_book(&SIGNATURE), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,336 @@
+// Training Data
+// Self-play games scored by the search, kept as (position, score, result)
+// records for tuning the evaluation. Games start with a few random moves for
+// variety, then both sides search each move to a node or depth limit; a game
+// ends by the rules, by threefold repetition, by resignation once the score
+// stays lopsided, or as a draw at the ply cap. Only quiet positions are kept:
+// not in check, best move not a capture or promotion, score not a mate.
+//
+// Records are text, "<fen> | <score> | <result>", or 32-byte binary records
+// (see `TrainingRecord::to_bytes`). Scores are centipawns and results are
+// 1.0 / 0.5 / 0.0, both from White's point of view.
+
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+
+use crate::eval::{Score, MATE_SCORE};
+use crate::movegen::generate_legal_moves;
+use crate::position::Position;
+use crate::search::{search_with_control, SearchControl, SearchOptions};
+use crate::tt::TranspositionTable;
+use crate::types::{CastlingRights, Color, Square};
+
+/// Size of a binary record
+pub const RECORD_BYTES: usize = 32;
+
+/// Scores this close to a mate are mate scores
+const MATE_WINDOW: Score = 256;
+
+// =============================================================================
+// RECORDS
+// =============================================================================
+
+/// A position of a self-play game with its search score and the game's result
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrainingRecord {
+    pub fen: String,
+    /// Centipawns for White
+    pub score: Score,
+    /// White's points in half-points: 0, 1 or 2
+    pub result: u8,
+}
+
+impl TrainingRecord {
+    /// "<fen> | <score> | <result>"
+    pub fn to_text(&self) -> String {
+        format!("{} | {} | {:.1}", self.fen, self.score, self.result as f32 / 2.0)
+    }
+
+    /// Parse a `to_text` line
+    pub fn parse_text(line: &str) -> Result<TrainingRecord, String> {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let [fen, score, result] = fields[..] else {
+            return Err(format!("Expected \"fen | score | result\": {}", line));
+        };
+        Position::from_fen_strict(fen).map_err(|e| e.to_string())?;
+        let score = score.parse().map_err(|_| format!("Invalid score: {}", score))?;
+        let result = match result {
+            "1.0" | "1" | "1-0" => 2,
+            "0.5" | "1/2-1/2" => 1,
+            "0.0" | "0" | "0-1" => 0,
+            _ => return Err(format!("Invalid result: {}", result)),
+        };
+        Ok(TrainingRecord { fen: fen.to_string(), score, result })
+    }
+
+    /// Binary record, little-endian:
+    /// bytes 0-7 occupancy, 8-23 a nibble (color << 3 | piece type) per
+    /// occupied square from a1 up, 24 side to move (bit 0) and castling
+    /// rights (bits 1-4), 25 en passant square (0xFF for none), 26 halfmove
+    /// clock, 27-28 fullmove number, 29-30 score, 31 result.
+    /// Positions of more than 32 pieces don't fit.
+    pub fn to_bytes(&self) -> Result<[u8; RECORD_BYTES], String> {
+        let pos = Position::from_fen(&self.fen).map_err(|e| e.to_string())?;
+        let occupied = pos.occupied().0;
+        if occupied.count_ones() > 32 {
+            return Err("More than 32 pieces".to_string());
+        }
+        let mut bytes = [0u8; RECORD_BYTES];
+        bytes[0..8].copy_from_slice(&occupied.to_le_bytes());
+        let pieces = (0..64u8).filter_map(|sq| pos.piece_on(Square(sq)));
+        for (i, (color, piece)) in pieces.enumerate() {
+            bytes[8 + i / 2] |= ((color as u8) << 3 | piece as u8) << (4 * (i % 2));
+        }
+        bytes[24] = (pos.side_to_move() == Color::Black) as u8 | pos.castling_rights().0 << 1;
+        bytes[25] = pos.en_passant_square().map_or(0xFF, |sq| sq.0);
+        bytes[26] = pos.halfmove_clock();
+        bytes[27..29].copy_from_slice(&pos.fullmove_number().to_le_bytes());
+        let score = self.score.clamp(i16::MIN as Score, i16::MAX as Score) as i16;
+        bytes[29..31].copy_from_slice(&score.to_le_bytes());
+        bytes[31] = self.result;
+        Ok(bytes)
+    }
+
+    /// Read a `to_bytes` record
+    pub fn from_bytes(bytes: &[u8; RECORD_BYTES]) -> Result<TrainingRecord, String> {
+        const PIECES: [char; 6] = ['p', 'n', 'b', 'r', 'q', 'k'];
+        let occupied = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        if occupied.count_ones() > 32 {
+            return Err("More than 32 pieces".to_string());
+        }
+        let mut board = [None; 64];
+        for (i, sq) in (0..64).filter(|sq| occupied >> sq & 1 != 0).enumerate() {
+            let nibble = bytes[8 + i / 2] >> (4 * (i % 2)) & 0xF;
+            let piece = *PIECES.get((nibble & 7) as usize).ok_or("Invalid piece")?;
+            board[sq] = Some(if nibble & 8 == 0 { piece.to_ascii_uppercase() } else { piece });
+        }
+
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match board[rank * 8 + file] {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        let rights = CastlingRights(bytes[24] >> 1 & 0xF);
+        let castling: String = [
+            (CastlingRights::WHITE_KINGSIDE, 'K'),
+            (CastlingRights::WHITE_QUEENSIDE, 'Q'),
+            (CastlingRights::BLACK_KINGSIDE, 'k'),
+            (CastlingRights::BLACK_QUEENSIDE, 'q'),
+        ]
+        .into_iter()
+        .filter(|&(right, _)| rights.has(right))
+        .map(|(_, c)| c)
+        .collect();
+        let en_passant = match bytes[25] {
+            0xFF => "-".to_string(),
+            sq if sq < 64 => Square(sq).to_algebraic(),
+            _ => return Err("Invalid en passant square".to_string()),
+        };
+        let fen = format!(
+            "{} {} {} {} {} {}",
+            fen,
+            if bytes[24] & 1 == 0 { 'w' } else { 'b' },
+            if castling.is_empty() { "-" } else { &castling },
+            en_passant,
+            bytes[26],
+            u16::from_le_bytes([bytes[27], bytes[28]])
+        );
+        Position::from_fen_strict(&fen).map_err(|e| e.to_string())?;
+        if bytes[31] > 2 {
+            return Err("Invalid result".to_string());
+        }
+        let score = i16::from_le_bytes([bytes[29], bytes[30]]) as Score;
+        Ok(TrainingRecord { fen, score, result: bytes[31] })
+    }
+}
+
+// =============================================================================
+// SELF-PLAY
+// =============================================================================
+
+/// How self-play games are played and adjudicated
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatagenOptions {
+    /// Random moves from the start position before the search takes over
+    pub random_plies: u32,
+    /// Nodes per move
+    pub nodes: u64,
+    /// Depth per move (0 = only the node limit)
+    pub depth: u8,
+    /// Game length, in plies, at which the game is scored a draw
+    pub max_plies: usize,
+    /// A side resigns once the score is this far against it...
+    pub resign_cp: Score,
+    /// ...for this many plies in a row
+    pub resign_plies: u32,
+    /// Transposition table size, as a power of two entries
+    pub hash_power: u8,
+}
+
+impl Default for DatagenOptions {
+    fn default() -> Self {
+        DatagenOptions {
+            random_plies: 8,
+            nodes: 5000,
+            depth: 0,
+            max_plies: 400,
+            resign_cp: 1000,
+            resign_plies: 8,
+            hash_power: 16,
+        }
+    }
+}
+
+/// White's half-points if the game is over at `pos`, after the positions
+/// hashed in `history`
+fn game_result(pos: &Position, history: &[u64]) -> Option<u8> {
+    let white_wins = |winner: Color| if winner == Color::White { 2 } else { 0 };
+    if let Some((winner, _)) = pos.variant().outcome(pos) {
+        return Some(white_wins(winner));
+    }
+    if pos.is_checkmate() {
+        return Some(white_wins(pos.side_to_move().flip()));
+    }
+    if pos.is_draw() || pos.is_insufficient_material() || history.iter().filter(|&&h| h == pos.hash()).count() >= 3 {
+        return Some(1);
+    }
+    None
+}
+
+/// Play one self-play game from the random opening of `seed` and return its
+/// quiet positions, scored and labelled with the result
+pub fn selfplay_game(seed: u64, options: &DatagenOptions) -> Vec<TrainingRecord> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pos = Position::starting_position();
+    for _ in 0..options.random_plies {
+        let Some(m) = generate_legal_moves(&pos).iter().copied().choose(&mut rng) else { return Vec::new() };
+        pos.make_move(m);
+    }
+
+    let mut tt = TranspositionTable::new(options.hash_power);
+    let search_options = SearchOptions::default();
+    let mut history = vec![pos.hash()];
+    let mut positions = Vec::new();
+    // Plies in a row the score has favored one side by resign_cp: positive
+    // for White, negative for Black
+    let mut lopsided: i32 = 0;
+    let result = loop {
+        if let Some(result) = game_result(&pos, &history) {
+            break result;
+        }
+        if history.len() > options.max_plies {
+            break 1;
+        }
+        let control = SearchControl::new(f64::INFINITY);
+        control.set_node_limit(options.nodes);
+        let (best, score, _) = search_with_control(&mut pos.clone(), options.depth, &mut tt, &control, &search_options);
+        let Some(best) = best else { break 1 };
+        let us = pos.side_to_move();
+        let white_score = if us == Color::White { score } else { -score };
+
+        let tactical = best.is_promotion() || best.is_en_passant() || pos.piece_on(best.to()).is_some();
+        if !tactical && !pos.is_in_check(us) && MATE_SCORE - score.abs() >= MATE_WINDOW {
+            positions.push((pos.to_fen(), white_score));
+        }
+
+        lopsided = match white_score {
+            s if s >= options.resign_cp => lopsided.max(0) + 1,
+            s if s <= -options.resign_cp => lopsided.min(0) - 1,
+            _ => 0,
+        };
+        if lopsided.unsigned_abs() >= options.resign_plies {
+            break if lopsided > 0 { 2 } else { 0 };
+        }
+        pos.make_move(best);
+        history.push(pos.hash());
+    };
+    positions.into_iter().map(|(fen, score)| TrainingRecord { fen, score, result }).collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_records() {
+        let record = TrainingRecord {
+            fen: "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string(),
+            score: -35,
+            result: 1,
+        };
+        let line = record.to_text();
+        assert_eq!(line, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 | -35 | 0.5");
+        assert_eq!(TrainingRecord::parse_text(&line).unwrap(), record);
+        assert_eq!(TrainingRecord::parse_text("8/8/8/8/8/8/8/K6k w - - 0 1 | 0 | 1-0").unwrap().result, 2);
+        assert!(TrainingRecord::parse_text("8/8/8/8/8/8/8/K6k w - - 0 1 | 0").is_err());
+        assert_eq!(
+            TrainingRecord::parse_text("8/8/8/8/8/8/8/K6k w - - 0 1 | 0 | 2").unwrap_err(),
+            "Invalid result: 2"
+        );
+    }
+
+    #[test]
+    fn test_binary_records() {
+        for (fen, score, result) in [
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 20, 2),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w Kq - 3 17", -1234, 0),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 99 300", 40000, 1),
+        ] {
+            let record = TrainingRecord { fen: fen.to_string(), score, result };
+            let decoded = TrainingRecord::from_bytes(&record.to_bytes().unwrap()).unwrap();
+            assert_eq!(decoded.fen, fen);
+            assert_eq!((decoded.score, decoded.result), (score.min(i16::MAX as Score), result));
+        }
+        let mut bytes = TrainingRecord { fen: Position::starting_position().to_fen(), score: 0, result: 1 }
+            .to_bytes()
+            .unwrap();
+        bytes[31] = 3;
+        assert_eq!(TrainingRecord::from_bytes(&bytes).unwrap_err(), "Invalid result");
+        bytes[8] = 0x66;
+        assert_eq!(TrainingRecord::from_bytes(&bytes).unwrap_err(), "Invalid piece");
+    }
+
+    #[test]
+    fn test_selfplay_game() {
+        let options = DatagenOptions { nodes: 300, max_plies: 40, ..DatagenOptions::default() };
+        let records = selfplay_game(3, &options);
+        assert!(!records.is_empty());
+        assert_eq!(records, selfplay_game(3, &options));
+        let result = records[0].result;
+        for record in &records {
+            assert_eq!(record.result, result);
+            let pos = Position::from_fen(&record.fen).unwrap();
+            assert!(!pos.is_in_check(pos.side_to_move()), "{}", record.fen);
+            assert!(record.score.abs() < MATE_SCORE - MATE_WINDOW);
+        }
+
+        // Lopsided from the start: the losing side resigns after resign_plies
+        let options = DatagenOptions { random_plies: 0, ..options };
+        assert_eq!(game_result(&Position::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap(), &[]), None);
+        let mated = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(game_result(&mated, &[]), Some(2));
+        assert_eq!(selfplay_game(1, &DatagenOptions { resign_plies: 1, resign_cp: -30000, ..options }).len(), 1);
+    }
+}
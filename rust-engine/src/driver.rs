@@ -0,0 +1,485 @@
+// Search Driver
+// The engine core behind the native protocol front ends (the uci and xboard
+// binaries): options, a shared transposition table, and searches on their own
+// thread that report progress and their move as events. The front ends keep
+// the game, parse their protocol and format the events.
+//
+// Threads > 1 is Lazy SMP: helper threads search the same position into the
+// shared table while the main thread reports. Skill Level below MAX_SKILL
+// caps the depth and plays any of the best few moves within a margin.
+
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use rand::Rng;
+
+use crate::position::Position;
+use crate::search::{
+    search_multipv_with_progress, search_with_control, search_with_progress, PvLine, SearchControl, SearchOptions,
+    SearchStats,
+};
+use crate::timeman::TimeLimits;
+use crate::tt::{SharedTable, TranspositionTable};
+use crate::types::{Color, Move};
+use crate::variant::Variant;
+
+/// Strongest skill level: the full engine
+pub const MAX_SKILL: u8 = 20;
+/// Root moves a weakened engine chooses among
+const SKILL_LINES: usize = 4;
+/// How far a weakened engine's move may trail the best, per level below
+/// MAX_SKILL, in centipawns
+const SKILL_MARGIN_CP: i32 = 15;
+
+// =============================================================================
+// OPTIONS
+// =============================================================================
+
+/// Engine settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineOptions {
+    pub hash_mb: usize,
+    pub threads: usize,
+    pub multipv: usize,
+    /// Report a move to ponder on with the best move
+    pub ponder: bool,
+    pub skill: u8,
+    pub variant: Variant,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        EngineOptions {
+            hash_mb: 16,
+            threads: 1,
+            multipv: 1,
+            ponder: false,
+            skill: MAX_SKILL,
+            variant: Variant::Standard,
+        }
+    }
+}
+
+impl EngineOptions {
+    /// The numeric options: name, current value, minimum and maximum
+    pub fn spins(&self) -> [(&'static str, i64, i64, i64); 4] {
+        [
+            ("Hash", self.hash_mb as i64, 1, 4096),
+            ("Threads", self.threads as i64, 1, 256),
+            ("MultiPV", self.multipv as i64, 1, 64),
+            ("Skill Level", self.skill as i64, 0, MAX_SKILL as i64),
+        ]
+    }
+
+    /// Set an option by name (case-insensitive): one of `spins`, "Ponder"
+    /// (true/false) or "Variant" (also "UCI_Variant")
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {}: {}", name, value);
+        let key = name.to_ascii_lowercase();
+        if let Some(&(spin, _, min, max)) = self.spins().iter().find(|s| s.0.eq_ignore_ascii_case(name)) {
+            let v = value.trim().parse::<i64>().ok().filter(|v| (min..=max).contains(v)).ok_or_else(invalid)?;
+            match spin {
+                "Hash" => self.hash_mb = v as usize,
+                "Threads" => self.threads = v as usize,
+                "MultiPV" => self.multipv = v as usize,
+                _ => self.skill = v as u8,
+            }
+            return Ok(());
+        }
+        match key.as_str() {
+            "ponder" => self.ponder = value.trim().parse().map_err(|_| invalid())?,
+            "variant" | "uci_variant" => self.variant = Variant::from_name(value).ok_or_else(invalid)?,
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+        Ok(())
+    }
+
+    /// Size of a `hash_mb` table, as a power of two entries
+    pub fn hash_size_power(&self) -> u8 {
+        ((self.hash_mb << 20) / SharedTable::ENTRY_BYTES).max(1).ilog2() as u8
+    }
+}
+
+/// Limits of one search
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    /// Clock times and increments in ms
+    pub wtime: Option<f64>,
+    pub btime: Option<f64>,
+    pub winc: f64,
+    pub binc: f64,
+    /// Moves to the next time control (None = sudden death)
+    pub movestogo: Option<u32>,
+    /// Fixed time for the move, in ms
+    pub movetime: Option<f64>,
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    /// Search until stopped, reporting the move only then
+    pub infinite: bool,
+    /// Ponder search: unlimited until `ponderhit` or `stop`
+    pub ponder: bool,
+}
+
+impl SearchLimits {
+    /// Time limits for `side`: the fixed move time as a plain budget, the
+    /// time manager's soft and hard limits for a clock, unlimited without either
+    pub fn time_limits(&self, side: Color) -> TimeLimits {
+        let budget = |ms: f64| TimeLimits { soft_ms: f64::INFINITY, hard_ms: ms };
+        if let Some(ms) = self.movetime {
+            return budget(ms.max(1.0));
+        }
+        let (time, inc) = match side {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        match time {
+            Some(time) if !self.infinite => TimeLimits::for_clock(time, inc, self.movestogo),
+            _ => budget(f64::INFINITY),
+        }
+    }
+}
+
+// =============================================================================
+// EVENTS
+// =============================================================================
+
+/// Progress of a search: one per completed depth, one per line with MultiPV
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u8,
+    pub seldepth: u8,
+    /// Number of the line (from 1) when several are searched
+    pub multipv: Option<usize>,
+    /// Centipawns (or mate scores) for the side to move
+    pub score: i32,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time_ms: f64,
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    fn new(stats: &SearchStats, multipv: Option<usize>, score: i32, pv: &[Move]) -> Self {
+        SearchInfo {
+            depth: stats.depth,
+            seldepth: stats.seldepth.max(stats.depth),
+            multipv,
+            score,
+            nodes: stats.nodes,
+            nps: stats.nps,
+            time_ms: stats.time_ms,
+            pv: pv.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchEvent {
+    Info(SearchInfo),
+    /// The search is over: the move to play (None without a legal move), and
+    /// the expected reply when pondering is on
+    BestMove { best: Option<Move>, ponder: Option<Move> },
+}
+
+/// Receives the events of the driver's searches, on the search thread
+pub type EventSink = Arc<dyn Fn(SearchEvent) + Send + Sync>;
+
+// =============================================================================
+// SEARCH
+// =============================================================================
+
+/// Deepest search at a skill level below MAX_SKILL
+pub fn skill_depth(skill: u8) -> u8 {
+    1 + skill / 2
+}
+
+/// Index of the line a weakened engine plays: any of `lines` (best first)
+/// within the skill's margin of the best
+pub fn skill_pick(lines: &[PvLine], skill: u8, rng: &mut impl Rng) -> usize {
+    let margin = (MAX_SKILL.saturating_sub(skill)) as i32 * SKILL_MARGIN_CP;
+    let best = lines[0].score;
+    let candidates = lines.iter().take_while(|line| best - line.score <= margin).count();
+    rng.gen_range(0..candidates.max(1))
+}
+
+/// The main thread's search: an info event (one per PV with MultiPV) for
+/// each completed depth, then the move to play and its expected continuation
+fn think(
+    pos: &mut Position,
+    tt: &mut TranspositionTable,
+    limits: &SearchLimits,
+    settings: &EngineOptions,
+    control: &SearchControl,
+    sink: &EventSink,
+) -> (Option<Move>, Vec<Move>) {
+    let options = SearchOptions::default();
+    let weakened = settings.skill < MAX_SKILL;
+    let mut depth = limits.depth.unwrap_or(0);
+    if weakened {
+        let cap = skill_depth(settings.skill);
+        depth = if depth == 0 { cap } else { depth.min(cap) };
+    }
+    let searched = if weakened { settings.multipv.max(SKILL_LINES) } else { settings.multipv };
+
+    if searched == 1 {
+        let mut pv = Vec::new();
+        let mut report = |stats: &SearchStats, line: &[Move]| {
+            pv = line.to_vec();
+            sink(SearchEvent::Info(SearchInfo::new(stats, None, stats.score, line)));
+        };
+        let (best, _, _) = search_with_progress(pos, depth, tt, control, &options, Some(&mut report));
+        return (best, pv);
+    }
+
+    let shown = settings.multipv;
+    let mut report = |stats: &SearchStats, lines: &[PvLine]| {
+        for (i, line) in lines.iter().take(shown).enumerate() {
+            let k = (shown > 1).then_some(i + 1);
+            sink(SearchEvent::Info(SearchInfo::new(stats, k, line.score, &line.pv)));
+        }
+    };
+    let (lines, _) = search_multipv_with_progress(pos, searched, depth, tt, control, &options, Some(&mut report));
+    if lines.is_empty() {
+        return (None, Vec::new());
+    }
+    let pick = if weakened { skill_pick(&lines, settings.skill, &mut rand::thread_rng()) } else { 0 };
+    (Some(lines[pick].mv), lines[pick].pv.clone())
+}
+
+// =============================================================================
+// DRIVER
+// =============================================================================
+
+/// A search running on its own thread
+struct Running {
+    control: Arc<SearchControl>,
+    /// Time to search for once a ponder search gets its ponderhit
+    time: TimeLimits,
+    handle: JoinHandle<()>,
+}
+
+/// Options, table and the running search of a native engine
+pub struct SearchDriver {
+    settings: EngineOptions,
+    /// Searched by the main thread and the Threads - 1 helpers alike
+    table: Arc<SharedTable>,
+    search: Option<Running>,
+    sink: EventSink,
+}
+
+impl SearchDriver {
+    pub fn new(sink: EventSink) -> Self {
+        let settings = EngineOptions::default();
+        SearchDriver { table: SharedTable::new(settings.hash_size_power()), settings, search: None, sink }
+    }
+
+    pub fn options(&self) -> &EngineOptions {
+        &self.settings
+    }
+
+    /// Set an option (see `EngineOptions::set`), stopping any search first.
+    /// A new Hash size reallocates the table; a new variant clears it, its
+    /// scores having been found under the old rules.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.stop();
+        let before = self.settings.clone();
+        self.settings.set(name, value)?;
+        if self.settings.hash_mb != before.hash_mb {
+            self.table = SharedTable::new(self.settings.hash_size_power());
+        }
+        if self.settings.variant != before.variant {
+            self.table.clear();
+        }
+        Ok(())
+    }
+
+    /// Forget the previous game: stop searching and clear the table
+    pub fn new_game(&mut self) {
+        self.stop();
+        self.table.clear();
+    }
+
+    /// Start searching `pos`, stopping any search first. Events go to the sink,
+    /// ending with `BestMove`.
+    pub fn go(&mut self, pos: &Position, limits: SearchLimits) {
+        self.stop();
+        let time = limits.time_limits(pos.side_to_move());
+        let control = Arc::new(if limits.ponder { SearchControl::ponder() } else { SearchControl::with_limits(time) });
+        if let Some(nodes) = limits.nodes {
+            control.set_node_limit(nodes);
+        }
+        self.table.new_search();
+        let pos = pos.clone();
+        let table = Arc::clone(&self.table);
+        let settings = self.settings.clone();
+        let sink = Arc::clone(&self.sink);
+        let thread_control = Arc::clone(&control);
+        let handle = std::thread::spawn(move || {
+            let control = thread_control;
+            let helpers = SearchControl::new(f64::INFINITY);
+            let options = SearchOptions::default();
+            let (best, pv) = std::thread::scope(|scope| {
+                for _ in 1..settings.threads {
+                    let (mut pos, mut tt) = (pos.clone(), TranspositionTable::attach(Arc::clone(&table)));
+                    let (helpers, options) = (&helpers, &options);
+                    scope.spawn(move || search_with_control(&mut pos, 0, &mut tt, helpers, options));
+                }
+                let mut tt = TranspositionTable::attach(Arc::clone(&table));
+                let result = think(&mut pos.clone(), &mut tt, &limits, &settings, &control, &sink);
+                helpers.stop();
+                result
+            });
+
+            // The move only after `stop` (or `ponderhit`) in infinite and ponder mode
+            while !control.is_stopped() && (limits.infinite || control.is_pondering()) {
+                std::thread::sleep(std::time::Duration::from_millis(2));
+            }
+            let ponder = match pv.get(1) {
+                Some(&reply) if settings.ponder && pv.first() == best.as_ref() => Some(reply),
+                _ => None,
+            };
+            sink(SearchEvent::BestMove { best, ponder });
+        });
+        self.search = Some(Running { control, time, handle });
+    }
+
+    /// The opponent played the predicted move: the ponder search becomes a
+    /// normal one with the limits it was started with
+    pub fn ponderhit(&self) {
+        if let Some(running) = &self.search {
+            running.control.set_soft_limit(running.time.soft_ms);
+            running.control.ponderhit(running.time.hard_ms);
+        }
+    }
+
+    /// Whether a search is still running (its move not sent yet)
+    pub fn is_thinking(&self) -> bool {
+        self.search.as_ref().is_some_and(|running| !running.handle.is_finished())
+    }
+
+    /// Stop the running search, if any, waiting for its `BestMove`
+    pub fn stop(&mut self) {
+        if let Some(running) = self.search.take() {
+            running.control.stop();
+            running.handle.join().expect("search thread panicked");
+        }
+    }
+}
+
+impl Drop for SearchDriver {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn driver() -> (SearchDriver, Arc<Mutex<Vec<SearchEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        (SearchDriver::new(Arc::new(move |e| sink.lock().unwrap().push(e))), events)
+    }
+
+    fn wait(driver: &mut SearchDriver) {
+        let start = std::time::Instant::now();
+        while driver.is_thinking() {
+            assert!(start.elapsed().as_secs() < 10, "Search didn't end");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        driver.stop();
+    }
+
+    #[test]
+    fn test_options() {
+        let mut options = EngineOptions::default();
+        assert_eq!(options.hash_size_power(), 20);
+        options.set("hash", "64").unwrap();
+        assert_eq!(options.hash_size_power(), 22);
+        options.set("UCI_Variant", "three-check").unwrap();
+        assert_eq!(options.variant, Variant::ThreeCheck);
+        options.set("skill level", "3").unwrap();
+        assert_eq!(options.skill, 3);
+        options.set("Ponder", "true").unwrap();
+        assert!(options.ponder);
+        assert_eq!(options.set("Threads", "0").unwrap_err(), "Invalid value for Threads: 0");
+        assert_eq!(options.set("MultiPV", "x").unwrap_err(), "Invalid value for MultiPV: x");
+        assert_eq!(options.set("Contempt", "20").unwrap_err(), "Unknown option: Contempt");
+        assert_eq!(options.set("Style", "1").unwrap_err(), "Unknown option: Style");
+    }
+
+    #[test]
+    fn test_time_limits() {
+        let limits = SearchLimits { wtime: Some(60000.0), btime: Some(30000.0), winc: 1000.0, ..SearchLimits::default() };
+        assert_eq!(limits.time_limits(Color::White), TimeLimits::for_clock(60000.0, 1000.0, None));
+        assert_eq!(limits.time_limits(Color::Black), TimeLimits::for_clock(30000.0, 0.0, None));
+        let fixed = SearchLimits { movetime: Some(250.0), ..limits.clone() }.time_limits(Color::Black);
+        assert_eq!((fixed.soft_ms, fixed.hard_ms), (f64::INFINITY, 250.0));
+        let infinite = SearchLimits { infinite: true, ..limits }.time_limits(Color::White);
+        assert_eq!(infinite.hard_ms, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_skill_pick() {
+        use rand::SeedableRng;
+        let line = |score| PvLine { mv: Move::NULL, score, pv: Vec::new() };
+        let lines = [line(50), line(40), line(-100), line(-300)];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        assert!((0..50).all(|_| skill_pick(&lines, MAX_SKILL, &mut rng) == 0));
+        // 15 cp per level: level 19 allows the second line, level 10 the third
+        assert!((0..50).all(|_| skill_pick(&lines, 19, &mut rng) < 2));
+        let picks: Vec<usize> = (0..200).map(|_| skill_pick(&lines, 10, &mut rng)).collect();
+        assert!(picks.contains(&2) && !picks.contains(&3));
+        assert_eq!(skill_depth(0), 1);
+    }
+
+    #[test]
+    fn test_threads_multipv_and_ponder() {
+        let (mut driver, events) = driver();
+        driver.set_option("MultiPV", "3").unwrap();
+        driver.set_option("Threads", "3").unwrap();
+        driver.set_option("Ponder", "true").unwrap();
+        driver.go(&Position::starting_position(), SearchLimits { depth: Some(3), ..SearchLimits::default() });
+        wait(&mut driver);
+
+        let events = events.lock().unwrap().clone();
+        let lines: Vec<Option<usize>> = events
+            .iter()
+            .filter_map(|e| match e {
+                SearchEvent::Info(info) if info.depth == 3 => Some(info.multipv),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines, [Some(1), Some(2), Some(3)]);
+        match events.last().unwrap() {
+            SearchEvent::BestMove { best: Some(_), ponder } => assert!(ponder.is_some()),
+            other => panic!("expected a move, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_infinite_and_ponderhit() {
+        let (mut driver, events) = driver();
+        let pos = Position::starting_position();
+        driver.go(&pos, SearchLimits { infinite: true, ..SearchLimits::default() });
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert!(driver.is_thinking());
+        driver.stop();
+        assert!(matches!(events.lock().unwrap().last(), Some(SearchEvent::BestMove { best: Some(_), .. })));
+
+        // Pondering waits for ponderhit, then plays within its movetime
+        events.lock().unwrap().clear();
+        driver.go(&pos, SearchLimits { ponder: true, movetime: Some(30.0), ..SearchLimits::default() });
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        assert!(driver.is_thinking());
+        driver.ponderhit();
+        wait(&mut driver);
+        assert!(matches!(events.lock().unwrap().last(), Some(SearchEvent::BestMove { best: Some(_), .. })));
+    }
+}
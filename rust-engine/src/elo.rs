@@ -0,0 +1,320 @@
+// Match Statistics
+// Elo estimates and the sequential probability ratio test for engine-vs-engine
+// matches. Games are played in pairs, one with each color from the same
+// opening, and counted by pair score (the pentanomial: 0, 1/2, 1, 3/2 or 2
+// points), which cancels most of the opening's bias out of the variance.
+// Games not played in pairs are counted one by one (win, draw or loss).
+
+/// z for a two-sided 95% confidence interval
+pub const Z_95: f64 = 1.959964;
+
+/// Elo difference giving an expected score of `score` (0..1), logistic model
+pub fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// Expected score (0..1) of a player `elo` points stronger
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Standard normal cumulative distribution, by the Abramowitz and Stegun
+/// approximation of erf (error below 1e-7)
+pub fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// Elo of a mean score per game (0..1) and the half-width of its 95%
+/// confidence interval, from the variance of one of `samples` results
+fn elo_interval(mean: f64, variance: f64, samples: u64) -> (f64, f64) {
+    let error = Z_95 * (variance / samples.max(1) as f64).sqrt();
+    let margin = (score_to_elo(mean + error) - score_to_elo(mean - error)) / 2.0;
+    (score_to_elo(mean), margin)
+}
+
+/// Normalized Elo: how far `mean` is from an even score in standard
+/// deviations of a game's score, on the Elo scale, which unlike Elo doesn't
+/// shrink as draws get more common. With the half-width of its 95% interval.
+fn normalized_elo(mean: f64, game_variance: f64, games: u64) -> (f64, f64) {
+    if game_variance <= 0.0 || games == 0 {
+        return (0.0, 0.0);
+    }
+    let scale = 800.0 / std::f64::consts::LN_10;
+    ((mean - 0.5) / game_variance.sqrt() * scale, Z_95 * scale / (games as f64).sqrt())
+}
+
+// =============================================================================
+// PAIR RESULTS
+// =============================================================================
+
+/// Pairs of games by the points a player scored in them: `counts[i]` pairs
+/// scored i half-points (0 = two losses, 4 = two wins)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pentanomial {
+    pub counts: [u64; 5],
+}
+
+impl Pentanomial {
+    /// Count a pair scoring `half_points` (0..=4)
+    pub fn add(&mut self, half_points: usize) {
+        self.counts[half_points.min(4)] += 1;
+    }
+
+    pub fn pairs(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Mean score per game (0..1) and the variance of a pair's score, both
+    /// as fractions of the pair's two points (an even score before any pair)
+    fn mean_and_variance(&self) -> (f64, f64) {
+        if self.pairs() == 0 {
+            return (0.5, 0.0);
+        }
+        let n = self.pairs() as f64;
+        let fraction = |i: usize| i as f64 / 4.0;
+        let mean = self.counts.iter().enumerate().map(|(i, &c)| c as f64 * fraction(i)).sum::<f64>() / n;
+        let variance =
+            self.counts.iter().enumerate().map(|(i, &c)| c as f64 * (fraction(i) - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance)
+    }
+
+    /// Mean score per game, 0..1
+    pub fn score(&self) -> f64 {
+        self.mean_and_variance().0
+    }
+
+    /// Elo estimate and the half-width of its 95% confidence interval
+    pub fn elo(&self) -> (f64, f64) {
+        let (mean, variance) = self.mean_and_variance();
+        elo_interval(mean, variance, self.pairs())
+    }
+}
+
+// =============================================================================
+// SINGLE GAMES
+// =============================================================================
+
+/// Games by result from one player's side, for games not played in pairs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GameCounts {
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl GameCounts {
+    /// Count a game the player scored `score` in (1, 1/2 or 0)
+    pub fn add(&mut self, score: f64) {
+        if score > 0.75 {
+            self.wins += 1;
+        } else if score > 0.25 {
+            self.draws += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+
+    pub fn games(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Mean score per game (0..1) and the variance of a game's score (an
+    /// even score before any game)
+    fn mean_and_variance(&self) -> (f64, f64) {
+        if self.games() == 0 {
+            return (0.5, 0.0);
+        }
+        let n = self.games() as f64;
+        let (w, d, l) = (self.wins as f64 / n, self.draws as f64 / n, self.losses as f64 / n);
+        let mean = w + d / 2.0;
+        let variance = w * (1.0 - mean).powi(2) + d * (0.5 - mean).powi(2) + l * mean.powi(2);
+        (mean, variance)
+    }
+
+    /// Mean score per game, 0..1
+    pub fn score(&self) -> f64 {
+        self.mean_and_variance().0
+    }
+
+    /// Elo estimate and the half-width of its 95% confidence interval
+    pub fn elo(&self) -> (f64, f64) {
+        let (mean, variance) = self.mean_and_variance();
+        elo_interval(mean, variance, self.games())
+    }
+
+    /// Normalized Elo and the half-width of its 95% confidence interval
+    pub fn normalized_elo(&self) -> (f64, f64) {
+        let (mean, variance) = self.mean_and_variance();
+        normalized_elo(mean, variance, self.games())
+    }
+
+    /// Likelihood of superiority: the probability that the player is the
+    /// stronger, from its wins and losses (draws say nothing either way)
+    pub fn los(&self) -> f64 {
+        let decisive = (self.wins + self.losses) as f64;
+        if decisive == 0.0 {
+            return 0.5;
+        }
+        normal_cdf((self.wins as f64 - self.losses as f64) / decisive.sqrt())
+    }
+}
+
+// =============================================================================
+// SPRT
+// =============================================================================
+
+/// Outcome of a sequential test so far
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtVerdict {
+    /// The player is no stronger than `elo0`: reject the change
+    AcceptH0,
+    /// The player is at least `elo1` stronger: accept the change
+    AcceptH1,
+    Continue,
+}
+
+/// Sequential test of H0: elo = elo0 against H1: elo = elo1 (logistic Elo),
+/// with false positive rate `alpha` and false negative rate `beta`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+impl Sprt {
+    /// Log-likelihood ratio bounds: H0 is accepted below the first, H1 above
+    /// the second
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /// Log-likelihood ratio of H1 over H0 for the pairs played, by the normal
+    /// approximation of the generalized SPRT
+    pub fn llr(&self, pairs: &Pentanomial) -> f64 {
+        let (mean, variance) = pairs.mean_and_variance();
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (elo_to_score(self.elo0), elo_to_score(self.elo1));
+        pairs.pairs() as f64 * (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, pairs: &Pentanomial) -> SprtVerdict {
+        let (lower, upper) = self.bounds();
+        let llr = self.llr(pairs);
+        if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elo_score_conversion() {
+        assert!(score_to_elo(0.5).abs() < 1e-9);
+        assert!((score_to_elo(0.75) - 190.85).abs() < 0.01);
+        assert!((elo_to_score(score_to_elo(0.3)) - 0.3).abs() < 1e-9);
+        assert!(score_to_elo(1.0).is_finite() && score_to_elo(0.0) < -2000.0);
+    }
+
+    #[test]
+    fn test_pentanomial_elo() {
+        let mut pairs = Pentanomial::default();
+        assert_eq!(pairs.elo(), (0.0, 0.0));
+        for half_points in [0, 1, 2, 2, 3, 4, 4, 3, 2, 9] {
+            pairs.add(half_points);
+        }
+        assert_eq!(pairs.counts, [1, 1, 3, 2, 3]);
+        assert_eq!(pairs.pairs(), 10);
+        assert!((pairs.score() - 0.625).abs() < 1e-9);
+        let (elo, margin) = pairs.elo();
+        assert!((elo - 88.74).abs() < 0.01);
+        assert!(margin > 100.0, "Ten pairs prove little: {margin}");
+
+        // The same score over many more pairs narrows the interval
+        let many = Pentanomial { counts: pairs.counts.map(|c| c * 100) };
+        assert!((many.elo().0 - elo).abs() < 1e-9);
+        assert!(many.elo().1 < margin / 5.0);
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(Z_95) - 0.975).abs() < 1e-6);
+        assert!((normal_cdf(-1.0) - 0.158655).abs() < 1e-6);
+        assert!(normal_cdf(8.0) <= 1.0 && normal_cdf(-8.0) >= 0.0);
+    }
+
+    #[test]
+    fn test_game_counts() {
+        let mut games = GameCounts::default();
+        assert_eq!((games.elo(), games.normalized_elo(), games.los()), ((0.0, 0.0), (0.0, 0.0), 0.5));
+        for score in [1.0, 1.0, 1.0, 0.5, 0.5, 0.0] {
+            games.add(score);
+        }
+        assert_eq!(games, GameCounts { wins: 3, draws: 2, losses: 1 });
+        assert!((games.score() - 2.0 / 3.0).abs() < 1e-9);
+        let (elo, margin) = games.elo();
+        assert!((elo - 120.41).abs() < 0.01);
+        assert!(margin > elo, "Six games prove little: {margin}");
+        // wins - losses = 2 over 4 decisive games: one standard deviation
+        assert!((games.los() - 0.841345).abs() < 1e-6);
+
+        // Same score, more draws: the same Elo, but more significant
+        let drawish = GameCounts { wins: 200, draws: 200, losses: 0 };
+        let decisive = GameCounts { wins: 300, draws: 0, losses: 100 };
+        assert!((drawish.elo().0 - decisive.elo().0).abs() < 1e-9);
+        assert!(drawish.elo().1 < decisive.elo().1);
+        assert!(drawish.normalized_elo().0 > decisive.normalized_elo().0);
+        assert_eq!(drawish.normalized_elo().1, decisive.normalized_elo().1);
+        assert!(decisive.los() > 0.999);
+        let mut reversed = decisive;
+        std::mem::swap(&mut reversed.wins, &mut reversed.losses);
+        assert!((reversed.los() + decisive.los() - 1.0).abs() < 1e-9);
+        assert!((reversed.normalized_elo().0 + decisive.normalized_elo().0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sprt() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001 && (upper - 2.944).abs() < 0.001);
+
+        // Undecided early, then decided either way by a clear edge
+        let even = Pentanomial { counts: [5, 20, 50, 20, 5] };
+        assert_eq!(sprt.verdict(&even), SprtVerdict::Continue);
+        let stronger = Pentanomial { counts: [50, 400, 1000, 600, 150] };
+        assert!(sprt.llr(&stronger) > upper);
+        assert_eq!(sprt.verdict(&stronger), SprtVerdict::AcceptH1);
+        let mut weaker = stronger;
+        weaker.counts.reverse();
+        assert_eq!(sprt.verdict(&weaker), SprtVerdict::AcceptH0);
+
+        // All draws say nothing
+        assert_eq!(sprt.llr(&Pentanomial { counts: [0, 0, 40, 0, 0] }), 0.0);
+    }
+}
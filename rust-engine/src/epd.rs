@@ -0,0 +1,241 @@
+// EPD (Extended Position Description)
+// A position (the first four FEN fields) followed by operations such as
+// `bm Nf3; id "WAC.001";`. Test suites and tuning data are stored this way.
+
+/// One EPD line
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// Piece placement, side to move, castling and en passant, as in FEN
+    pub position: String,
+    /// Operations in line order: opcode and operands, e.g. ("bm", ["Nf3", "e4"])
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    /// Operands of an operation, if present
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(op, _)| op == opcode).map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Add an operation, replacing one with the same opcode
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(op, _)| op == opcode) {
+            Some((_, existing)) => *existing = operands,
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+
+    /// Position name ("id")
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id").and_then(|ops| ops.first()).map(|s| s.as_str())
+    }
+
+    /// Best moves in SAN ("bm"); empty if not given
+    pub fn best_moves(&self) -> &[String] {
+        self.operation("bm").unwrap_or(&[])
+    }
+
+    /// Moves to avoid in SAN ("am"); empty if not given
+    pub fn avoid_moves(&self) -> &[String] {
+        self.operation("am").unwrap_or(&[])
+    }
+
+    /// Evaluation in centipawns for the side to move ("ce")
+    pub fn centipawn_eval(&self) -> Option<i32> {
+        self.operation("ce").and_then(|ops| ops.first()).and_then(|s| s.parse().ok())
+    }
+
+    /// Predicted variation in SAN ("pv"); empty if not given
+    pub fn pv(&self) -> &[String] {
+        self.operation("pv").unwrap_or(&[])
+    }
+
+    /// Full FEN, with the move counters from the "hmvc" and "fmvn" operations
+    /// (0 and 1 when absent)
+    pub fn fen(&self) -> String {
+        let counter = |opcode: &str, default: u16| {
+            self.operation(opcode).and_then(|ops| ops.first()).and_then(|s| s.parse().ok()).unwrap_or(default)
+        };
+        format!("{} {} {}", self.position, counter("hmvc", 0), counter("fmvn", 1))
+    }
+
+    /// The record as one EPD line
+    pub fn to_epd(&self) -> String {
+        let mut line = self.position.clone();
+        for (opcode, operands) in &self.operations {
+            line.push(' ');
+            line.push_str(opcode);
+            for operand in operands {
+                line.push(' ');
+                if needs_quotes(opcode, operand) {
+                    line.push('"');
+                    line.push_str(&operand.replace('\\', "\\\\").replace('"', "\\\""));
+                    line.push('"');
+                } else {
+                    line.push_str(operand);
+                }
+            }
+            line.push(';');
+        }
+        line
+    }
+}
+
+/// Names and comments are quoted by convention; anything that wouldn't read
+/// back as one token has to be.
+fn needs_quotes(opcode: &str, operand: &str) -> bool {
+    let is_comment = opcode.len() == 2 && opcode.starts_with('c') && opcode.as_bytes()[1].is_ascii_digit();
+    opcode == "id" || is_comment || operand.is_empty() || operand.contains(|c: char| c.is_whitespace() || c == ';' || c == '"')
+}
+
+/// Parse one EPD line. The position is checked to be a readable FEN.
+pub fn parse_epd(line: &str) -> Result<EpdRecord, String> {
+    let mut rest = line.trim();
+    let mut position = Vec::with_capacity(4);
+    while position.len() < 4 && !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        position.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    if position.len() < 4 {
+        return Err("EPD needs piece placement, side to move, castling and en passant fields".into());
+    }
+    let mut record = EpdRecord { position: position.join(" "), operations: Vec::new() };
+    crate::position::Position::from_fen(&record.fen()).map_err(|e| format!("Invalid position: {}", e))?;
+
+    let mut chars = rest.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut opcode = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == ';' {
+                break;
+            }
+            opcode.push(c);
+            chars.next();
+        }
+        if opcode.is_empty() {
+            return Err("Empty opcode".into());
+        }
+
+        let mut operands = Vec::new();
+        loop {
+            match chars.next() {
+                Some(';') => break,
+                Some(c) if c.is_whitespace() => {}
+                Some('"') => {
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\\') => operand.extend(chars.next()),
+                            Some('"') => break,
+                            Some(c) => operand.push(c),
+                            None => return Err(format!("Unterminated string in '{}' operation", opcode)),
+                        }
+                    }
+                    operands.push(operand);
+                }
+                Some(c) => {
+                    let mut operand = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == ';' {
+                            break;
+                        }
+                        operand.push(c);
+                        chars.next();
+                    }
+                    operands.push(operand);
+                }
+                None => return Err(format!("Missing ';' after '{}' operation", opcode)),
+            }
+        }
+        record.operations.push((opcode, operands));
+    }
+
+    Ok(record)
+}
+
+/// Parse a file of EPD lines, skipping blank lines and `#` comments. Errors
+/// name the (1-based) line.
+pub fn parse_epd_file(text: &str) -> Result<Vec<EpdRecord>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| parse_epd(line).map_err(|e| format!("Line {}: {}", i + 1, e)))
+        .collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAC1: &str = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#;
+
+    #[test]
+    fn test_parse_operations() {
+        let record = parse_epd(WAC1).unwrap();
+        assert_eq!(record.position, "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -");
+        assert_eq!(record.best_moves(), ["Qg6"]);
+        assert_eq!(record.id(), Some("WAC.001"));
+        assert!(record.avoid_moves().is_empty());
+        assert_eq!(record.centipawn_eval(), None);
+        assert_eq!(record.fen(), "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+    }
+
+    #[test]
+    fn test_multiple_operands_and_counters() {
+        let record = parse_epd("4k3/8/8/8/8/8/4P3/4K3 w - - am Kd1 Kf1; ce +150; pv e4 Kd7 e5; hmvc 3; fmvn 40; c0 \"pawn; endgame\";").unwrap();
+        assert_eq!(record.avoid_moves(), ["Kd1", "Kf1"]);
+        assert_eq!(record.centipawn_eval(), Some(150));
+        assert_eq!(record.pv(), ["e4", "Kd7", "e5"]);
+        assert_eq!(record.operation("c0").unwrap(), ["pawn; endgame"]);
+        assert_eq!(record.fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 3 40");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut record = parse_epd(WAC1).unwrap();
+        assert_eq!(record.to_epd(), WAC1);
+        record.set_operation("ce", vec!["320".to_string()]);
+        record.set_operation("bm", vec!["Qg6".to_string(), "Nxf7+".to_string()]);
+        let line = record.to_epd();
+        assert_eq!(line, r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6 Nxf7+; id "WAC.001"; ce 320;"#);
+        assert_eq!(parse_epd(&line).unwrap(), record);
+    }
+
+    #[test]
+    fn test_position_only() {
+        let record = parse_epd("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").unwrap();
+        assert!(record.operations.is_empty());
+        assert_eq!(record.to_epd(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse_epd("8/8/8 w -").is_err());
+        assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 x - - bm Kd2;").is_err());
+        assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2").is_err());
+        assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - id \"open;").is_err());
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let text = format!("# Win at Chess\n{}\n\n4k3/8/8/8/8/8/4P3/4K3 w - - bm e4;\n", WAC1);
+        let records = parse_epd_file(&text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].best_moves(), ["e4"]);
+
+        let err = parse_epd_file("4k3/8/8/8/8/8/4P3/4K3 w - - bm e4;\nbroken\n").unwrap_err();
+        assert!(err.starts_with("Line 2:"), "{}", err);
+    }
+}
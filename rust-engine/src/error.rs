@@ -0,0 +1,122 @@
+// Engine Errors
+// Failures reported across the WASM boundary as objects rather than bare
+// strings, so frontends can react to the kind of error and point at the
+// input that caused it:
+//
+//   {kind:"IllegalMove", message:"Illegal move: e2e5", field:"e2e5"}
+
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+use crate::position::FenError;
+
+/// What went wrong
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ErrorKind {
+    /// A FEN that doesn't parse, or describes an impossible position
+    BadFen,
+    /// A square name that isn't a1..h8
+    BadSquare,
+    /// Move text that can't be read (bad UCI or SAN, ambiguous SAN)
+    BadNotation,
+    /// A well-formed move that isn't legal in the position
+    IllegalMove,
+    /// PGN text that doesn't parse
+    BadPgn,
+    /// An EPD line that doesn't parse
+    BadEpd,
+    /// Bytes or text that aren't a saved game
+    BadSavedGame,
+    /// An unknown variant, personality or other setting
+    BadOption,
+    /// Bytes that aren't an opening book
+    BadBook,
+    /// An id that names no game in an `EngineSession`
+    UnknownGame,
+}
+
+/// An error with its kind, a readable message, and the offending input
+/// (a FEN field name, a move, a PGN tag...) when there is one
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EngineError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub field: Option<String>,
+}
+
+impl EngineError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        EngineError { kind, message: message.into(), field: None }
+    }
+
+    /// The same error, naming the input at fault
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// An illegal or unreadable move, as reported by SAN parsing
+    pub fn from_san(error: &str, san: &str) -> Self {
+        let kind = match error {
+            "Illegal move" | "Castling is not legal" => ErrorKind::IllegalMove,
+            _ => ErrorKind::BadNotation,
+        };
+        EngineError::new(kind, format!("{}: {}", error, san)).with_field(san)
+    }
+}
+
+impl From<FenError> for EngineError {
+    fn from(e: FenError) -> Self {
+        let error = EngineError::new(ErrorKind::BadFen, e.to_string());
+        match e.field() {
+            Some(field) => error.with_field(field),
+            None => error,
+        }
+    }
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<EngineError> for JsValue {
+    fn from(e: EngineError) -> Self {
+        crate::to_js(&e)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+
+    #[test]
+    fn test_serialized_shape() {
+        let e = EngineError::new(ErrorKind::IllegalMove, "Illegal move: e2e5").with_field("e2e5");
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({"kind":"IllegalMove","message":"Illegal move: e2e5","field":"e2e5"})
+        );
+        assert_eq!(e.to_string(), "Illegal move: e2e5");
+    }
+
+    #[test]
+    fn test_from_fen_and_san_errors() {
+        let e = EngineError::from(Position::from_fen_strict("4k3/8/8/8/8/8/8/4K3 x - - 0 1").err().unwrap());
+        assert_eq!((e.kind, e.field.as_deref()), (ErrorKind::BadFen, Some("sideToMove")));
+        let e = EngineError::from(Position::from_fen_strict("8/8/8 w - - 0 1").err().unwrap());
+        assert_eq!(e.field.as_deref(), Some("placement"));
+
+        assert_eq!(EngineError::from_san("Illegal move", "e5").kind, ErrorKind::IllegalMove);
+        let e = EngineError::from_san("Ambiguous move", "Nd2");
+        assert_eq!((e.kind, e.message.as_str()), (ErrorKind::BadNotation, "Ambiguous move: Nd2"));
+    }
+}
@@ -2,9 +2,15 @@
 // Assigns a numeric score to any chess position
 // Positive = White advantage, Negative = Black advantage
 
+use crate::bitbase::probe_kpk;
+use crate::attacks::{adjacent_files, king_attacks, knight_attacks, passed_pawn_mask, pawn_attack_span, pawn_attacks};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
 use crate::bitboard::Bitboard;
+use crate::pawns::{self, PawnEntry, PawnTable};
 use crate::position::Position;
 use crate::types::{Color, PieceType, Square};
+use crate::variant::{PromotionRules, Variant};
+use std::cell::RefCell;
 
 /// Score type (centipawns - 100 = 1 pawn)
 pub type Score = i32;
@@ -13,6 +19,9 @@ pub type Score = i32;
 pub const MATE_SCORE: Score = 30000;
 pub const DRAW_SCORE: Score = 0;
 
+/// Bonus for the side to move (having the move is worth something)
+pub const TEMPO: Score = 10;
+
 // =============================================================================
 // MATERIAL VALUES (in centipawns)
 // =============================================================================
@@ -141,122 +150,835 @@ const KING_PST_EG: [Score; 64] = [
     -50, -40, -30, -20, -20, -30, -40, -50,
 ];
 
+// =============================================================================
+// PAWN STRUCTURE TERMS (middlegame / endgame)
+// =============================================================================
+
+/// Passed pawn bonus by relative rank (rank 2 .. rank 7)
+const PASSED_PAWN_MG: [Score; 8] = [0, 5, 10, 15, 25, 40, 60, 0];
+const PASSED_PAWN_EG: [Score; 8] = [0, 10, 20, 35, 60, 100, 150, 0];
+/// Passed pawn defended by a friendly pawn
+const PROTECTED_PASSER_MG: Score = 10;
+const PROTECTED_PASSER_EG: Score = 20;
+/// Passed pawn with another passer beside it on an adjacent file
+const CONNECTED_PASSER_MG: Score = 8;
+const CONNECTED_PASSER_EG: Score = 15;
+/// Penalty per extra pawn on a file
+const DOUBLED_PAWN_MG: Score = 10;
+const DOUBLED_PAWN_EG: Score = 20;
+/// Pawn with no friendly pawns on adjacent files
+const ISOLATED_PAWN_MG: Score = 10;
+const ISOLATED_PAWN_EG: Score = 15;
+/// Pawn that can't be supported by friendly pawns and whose advance is stopped by an enemy pawn
+const BACKWARD_PAWN_MG: Score = 8;
+const BACKWARD_PAWN_EG: Score = 10;
+
+// =============================================================================
+// PIECE TERMS (middlegame / endgame)
+// =============================================================================
+
+/// Knight outpost bonus by relative rank (4th to 6th rank; elsewhere 0)
+const KNIGHT_OUTPOST_MG: [Score; 8] = [0, 0, 0, 15, 25, 20, 0, 0];
+const KNIGHT_OUTPOST_EG: [Score; 8] = [0, 0, 0, 8, 12, 10, 0, 0];
+
+/// Enemy piece attacked by one of our lower-valued pieces, by victim type (P, N, B, R, Q)
+const THREAT_BY_LOWER_MG: [Score; 5] = [0, 30, 30, 45, 60];
+const THREAT_BY_LOWER_EG: [Score; 5] = [0, 25, 25, 40, 50];
+/// Enemy piece (not pawn or king) attacked and not defended at all
+const HANGING_PIECE_MG: Score = 25;
+const HANGING_PIECE_EG: Score = 15;
+
+/// Bonus for having both bishops
+const BISHOP_PAIR: Score = 30;
+/// Non-king material below which the king uses the endgame PST
+const KING_ENDGAME_MATERIAL: Score = 2000;
+
+// =============================================================================
+// EVALUATION PARAMETERS
+// =============================================================================
+
+/// Weights of the pawn-structure terms. Kept apart from the other weights because
+/// the pawn hash table caches their result and must be flushed when they change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PawnParams {
+    /// Passed pawn bonus by relative rank
+    pub passed_mg: [Score; 8],
+    pub passed_eg: [Score; 8],
+    pub protected_passer_mg: Score,
+    pub protected_passer_eg: Score,
+    pub connected_passer_mg: Score,
+    pub connected_passer_eg: Score,
+    /// Penalty per extra pawn on a file
+    pub doubled_mg: Score,
+    pub doubled_eg: Score,
+    pub isolated_mg: Score,
+    pub isolated_eg: Score,
+    pub backward_mg: Score,
+    pub backward_eg: Score,
+}
+
+/// Every weight used by the evaluation. `EvalParams::DEFAULT` is the standard
+/// evaluation; tuning, personalities and A/B experiments work on modified copies.
+/// PSTs are from white's point of view (index 0 = a1).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    /// Bonus for the side to move
+    pub tempo: Score,
+    pub pawn_value: Score,
+    pub knight_value: Score,
+    pub bishop_value: Score,
+    pub rook_value: Score,
+    pub queen_value: Score,
+    pub pawn_pst: [Score; 64],
+    pub knight_pst: [Score; 64],
+    pub bishop_pst: [Score; 64],
+    pub rook_pst: [Score; 64],
+    pub queen_pst: [Score; 64],
+    pub king_pst_mg: [Score; 64],
+    pub king_pst_eg: [Score; 64],
+    /// Non-king material below which the king switches to its endgame table
+    pub king_endgame_material: Score,
+    pub bishop_pair: Score,
+    pub pawns: PawnParams,
+    /// Knight outpost bonus by relative rank
+    pub knight_outpost_mg: [Score; 8],
+    pub knight_outpost_eg: [Score; 8],
+    /// Threat by a lower-valued piece, by victim type (P, N, B, R, Q)
+    pub threat_by_lower_mg: [Score; 5],
+    pub threat_by_lower_eg: [Score; 5],
+    pub hanging_piece_mg: Score,
+    pub hanging_piece_eg: Score,
+    pub mop_up_edge: Score,
+    pub mop_up_king_proximity: Score,
+    pub kpk_win_bonus: Score,
+    /// Endgame scale factors, out of SCALE_NORMAL
+    pub scale_opposite_bishops: i32,
+    pub scale_rook_pawn_vs_rook: i32,
+    pub scale_rook_edge_pawn_vs_rook: i32,
+}
+
+impl EvalParams {
+    pub const DEFAULT: EvalParams = EvalParams {
+        tempo: TEMPO,
+        pawn_value: PAWN_VALUE,
+        knight_value: KNIGHT_VALUE,
+        bishop_value: BISHOP_VALUE,
+        rook_value: ROOK_VALUE,
+        queen_value: QUEEN_VALUE,
+        pawn_pst: PAWN_PST,
+        knight_pst: KNIGHT_PST,
+        bishop_pst: BISHOP_PST,
+        rook_pst: ROOK_PST,
+        queen_pst: QUEEN_PST,
+        king_pst_mg: KING_PST_MG,
+        king_pst_eg: KING_PST_EG,
+        king_endgame_material: KING_ENDGAME_MATERIAL,
+        bishop_pair: BISHOP_PAIR,
+        pawns: PawnParams {
+            passed_mg: PASSED_PAWN_MG,
+            passed_eg: PASSED_PAWN_EG,
+            protected_passer_mg: PROTECTED_PASSER_MG,
+            protected_passer_eg: PROTECTED_PASSER_EG,
+            connected_passer_mg: CONNECTED_PASSER_MG,
+            connected_passer_eg: CONNECTED_PASSER_EG,
+            doubled_mg: DOUBLED_PAWN_MG,
+            doubled_eg: DOUBLED_PAWN_EG,
+            isolated_mg: ISOLATED_PAWN_MG,
+            isolated_eg: ISOLATED_PAWN_EG,
+            backward_mg: BACKWARD_PAWN_MG,
+            backward_eg: BACKWARD_PAWN_EG,
+        },
+        knight_outpost_mg: KNIGHT_OUTPOST_MG,
+        knight_outpost_eg: KNIGHT_OUTPOST_EG,
+        threat_by_lower_mg: THREAT_BY_LOWER_MG,
+        threat_by_lower_eg: THREAT_BY_LOWER_EG,
+        hanging_piece_mg: HANGING_PIECE_MG,
+        hanging_piece_eg: HANGING_PIECE_EG,
+        mop_up_edge: MOP_UP_EDGE,
+        mop_up_king_proximity: MOP_UP_KING_PROXIMITY,
+        kpk_win_bonus: KPK_WIN_BONUS,
+        scale_opposite_bishops: SCALE_OPPOSITE_BISHOPS,
+        scale_rook_pawn_vs_rook: SCALE_ROOK_PAWN_VS_ROOK,
+        scale_rook_edge_pawn_vs_rook: SCALE_ROOK_EDGE_PAWN_VS_ROOK,
+    };
+
+    /// Material value of a piece under these parameters (king excluded: 0)
+    #[inline]
+    pub fn piece_value(&self, piece: PieceType) -> Score {
+        match piece {
+            PieceType::Pawn => self.pawn_value,
+            PieceType::Knight => self.knight_value,
+            PieceType::Bishop => self.bishop_value,
+            PieceType::Rook => self.rook_value,
+            PieceType::Queen => self.queen_value,
+            PieceType::King => 0,
+        }
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams::DEFAULT
+    }
+}
+
+/// The one shared instance of the default weights. Evaluating with this reference
+/// uses the material + PST sums `Position` keeps incrementally; any other
+/// `EvalParams`, even an identical copy, recomputes them from the board.
+static DEFAULT_PARAMS: EvalParams = EvalParams::DEFAULT;
+
+/// The default weights, with the incremental material + PST fast path.
+pub fn default_params() -> &'static EvalParams {
+    &DEFAULT_PARAMS
+}
+
+// =============================================================================
+// INCREMENTAL MATERIAL + PST TABLES
+// =============================================================================
+
+/// Material of each piece type under the default weights (king 0)
+pub(crate) const MATERIAL: [Score; 6] = [PAWN_VALUE, KNIGHT_VALUE, BISHOP_VALUE, ROOK_VALUE, QUEEN_VALUE, 0];
+
+/// Material + PST of a piece on a square under the default weights, indexed
+/// [color][piece][square]. Kings are 0: their table depends on the material left.
+/// `Position` keeps running sums of these as pieces are added and removed.
+pub(crate) const PSQ: [[[Score; 64]; 6]; 2] = build_psq();
+
+const fn build_psq() -> [[[Score; 64]; 6]; 2] {
+    let tables = [&PAWN_PST, &KNIGHT_PST, &BISHOP_PST, &ROOK_PST, &QUEEN_PST];
+    let mut psq = [[[0; 64]; 6]; 2];
+    let mut piece = 0;
+    while piece < tables.len() {
+        let mut sq = 0;
+        while sq < 64 {
+            psq[Color::White as usize][piece][sq] = MATERIAL[piece] + tables[piece][sq];
+            psq[Color::Black as usize][piece][sq] = MATERIAL[piece] + tables[piece][sq ^ 56];
+            sq += 1;
+        }
+        piece += 1;
+    }
+    psq
+}
+
+// =============================================================================
+// ATTACK MAPS
+// =============================================================================
+
+/// Squares attacked by one side, per attacking piece type.
+struct AttackMap {
+    /// Indexed by `PieceType as usize`
+    by_piece: [Bitboard; 6],
+    all: Bitboard,
+}
+
+impl AttackMap {
+    fn new(pos: &Position, color: Color) -> Self {
+        let occupied = pos.occupied();
+        let is_white = color == Color::White;
+        let mut by_piece = [Bitboard::EMPTY; 6];
+
+        for sq in pos.pieces(color, PieceType::Pawn) {
+            by_piece[PieceType::Pawn as usize] |= pawn_attacks(sq, is_white);
+        }
+        for sq in pos.pieces(color, PieceType::Knight) {
+            by_piece[PieceType::Knight as usize] |= knight_attacks(sq);
+        }
+        for sq in pos.pieces(color, PieceType::Bishop) {
+            by_piece[PieceType::Bishop as usize] |= bishop_attacks(sq, occupied);
+        }
+        for sq in pos.pieces(color, PieceType::Rook) {
+            by_piece[PieceType::Rook as usize] |= rook_attacks(sq, occupied);
+        }
+        for sq in pos.pieces(color, PieceType::Queen) {
+            by_piece[PieceType::Queen as usize] |= queen_attacks(sq, occupied);
+        }
+        for sq in pos.pieces(color, PieceType::King) {
+            by_piece[PieceType::King as usize] |= king_attacks(sq);
+        }
+
+        let all = by_piece.iter().fold(Bitboard::EMPTY, |acc, &bb| acc | bb);
+        AttackMap { by_piece, all }
+    }
+}
+
+// =============================================================================
+// GAME PHASE
+// =============================================================================
+
+/// Phase of a position with all minor and major pieces on the board.
+const MAX_PHASE: i32 = 24;
+
+/// Game phase from non-pawn material: MAX_PHASE = opening, 0 = pawn endgame.
+fn game_phase(pos: &Position) -> i32 {
+    let mut phase = 0;
+    for color in [Color::White, Color::Black] {
+        phase += pos.pieces(color, PieceType::Knight).count() as i32;
+        phase += pos.pieces(color, PieceType::Bishop).count() as i32;
+        phase += pos.pieces(color, PieceType::Rook).count() as i32 * 2;
+        phase += pos.pieces(color, PieceType::Queen).count() as i32 * 4;
+    }
+    phase.min(MAX_PHASE)
+}
+
+/// Blend middlegame and endgame scores by game phase.
+#[inline]
+fn taper(mg: Score, eg: Score, phase: i32) -> Score {
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
 // =============================================================================
 // EVALUATION FUNCTIONS
 // =============================================================================
 
 /// Main evaluation function
-/// Returns score from the perspective of the side to move
+/// Returns score from the perspective of the side to move, including the tempo bonus.
 pub fn evaluate(pos: &Position) -> Score {
-    let white_score = evaluate_side(pos, Color::White);
-    let black_score = evaluate_side(pos, Color::Black);
-    
-    let score = white_score - black_score;
-    
+    evaluate_with(pos, &DEFAULT_PARAMS)
+}
+
+/// Evaluate with the given weights, from the perspective of the side to move.
+/// Pass `default_params()` rather than a copy of the defaults to keep the
+/// incremental material + PST fast path.
+///
+/// With the `eval-symmetry-check` feature, debug builds also verify that the
+/// color-mirrored position evaluates to the exact opposite, so new terms can't
+/// silently favor one color.
+pub fn evaluate_with(pos: &Position, params: &EvalParams) -> Score {
+    let score = evaluate_white(pos, params);
+
+    #[cfg(feature = "eval-symmetry-check")]
+    debug_assert_eq!(
+        score,
+        -evaluate_white(&pos.mirrored(), params),
+        "Eval is not color-symmetric for {}",
+        pos.to_fen()
+    );
+
     // Return from perspective of side to move
     if pos.side_to_move() == Color::White {
-        score
+        score + params.tempo
     } else {
-        -score
+        -score + params.tempo
     }
 }
 
-/// Evaluate one side's position
-fn evaluate_side(pos: &Position, color: Color) -> Score {
-    let mut score: Score = 0;
-    
-    // Material and piece-square tables
-    score += evaluate_pawns(pos, color);
-    score += evaluate_knights(pos, color);
-    score += evaluate_bishops(pos, color);
-    score += evaluate_rooks(pos, color);
-    score += evaluate_queens(pos, color);
-    score += evaluate_king(pos, color);
-    
-    score
+/// Static evaluation from white's point of view, without tempo.
+fn evaluate_white(pos: &Position, params: &EvalParams) -> Score {
+    // K+P vs K is solved exactly by the bitbase
+    let kpk = kpk_result(pos);
+    if let Some((_, false)) = kpk {
+        return DRAW_SCORE;
+    }
+
+    let pawns = pawn_structure(pos, &params.pawns);
+    let (w, b) = (Color::White as usize, Color::Black as usize);
+
+    // Phase-dependent terms, white minus black
+    let mut mg = pawns.mg[w] - pawns.mg[b];
+    let mut eg = pawns.eg[w] - pawns.eg[b];
+    let (white_mg, white_eg) = evaluate_outposts(pos, Color::White, params);
+    let (black_mg, black_eg) = evaluate_outposts(pos, Color::Black, params);
+    mg += white_mg - black_mg;
+    eg += white_eg - black_eg;
+
+    let white_attacks = AttackMap::new(pos, Color::White);
+    let black_attacks = AttackMap::new(pos, Color::Black);
+    let (white_mg, white_eg) = evaluate_threats(pos, Color::White, &white_attacks, &black_attacks, params);
+    let (black_mg, black_eg) = evaluate_threats(pos, Color::Black, &black_attacks, &white_attacks, params);
+    mg += white_mg - black_mg;
+    eg += white_eg - black_eg;
+
+    // Material and PST: kept up to date by Position for the default weights
+    let (white_psq, black_psq, material) = if std::ptr::eq(params, &DEFAULT_PARAMS) {
+        (pos.psq(Color::White), pos.psq(Color::Black), pos.material(Color::White) + pos.material(Color::Black))
+    } else {
+        (material_pst(pos, Color::White, params), material_pst(pos, Color::Black, params), count_material(pos, params))
+    };
+    let white_score = white_psq + evaluate_side(pos, Color::White, material, params);
+    let black_score = black_psq + evaluate_side(pos, Color::Black, material, params);
+
+    let mut score = white_score - black_score + taper(mg, eg, game_phase(pos));
+    score += mop_up(pos, Color::White, params) - mop_up(pos, Color::Black, params);
+    score += pos.variant().eval_bonus(pos);
+
+    if let Some((strong, true)) = kpk {
+        let bonus = params.kpk_win_bonus;
+        return if strong == Color::White { score + bonus } else { score - bonus };
+    }
+
+    // Drawish endgames: pull the score toward zero
+    let strong = if score > 0 { Color::White } else { Color::Black };
+    score * scale_factor(pos, strong, params) / SCALE_NORMAL
 }
 
-fn evaluate_pawns(pos: &Position, color: Color) -> Score {
-    let pawns = pos.pieces(color, PieceType::Pawn);
-    let mut score: Score = 0;
-    
-    let mut bb = pawns;
-    while let Some(sq) = bb.pop_lsb() {
-        score += PAWN_VALUE;
-        score += pst_value(&PAWN_PST, sq, color);
+// =============================================================================
+// EVALUATION BREAKDOWN
+// =============================================================================
+
+/// One side's evaluation split by term, in centipawns in that side's favor.
+/// Middlegame and endgame scores are blended per term, so the terms can add up
+/// to a few centipawns more or less than the combined evaluation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvalTerms {
+    /// Piece values, king excluded
+    pub material: Score,
+    /// Piece-square tables, king included
+    pub pst: Score,
+    /// Passed, doubled, isolated and backward pawns
+    pub pawn_structure: Score,
+    /// Knights on outposts
+    pub outposts: Score,
+    /// Enemy pieces attacked by lower-valued pieces or left undefended
+    pub threats: Score,
+    pub bishop_pair: Score,
+    /// Mating bonus against a bare king
+    pub mop_up: Score,
+}
+
+impl EvalTerms {
+    /// Sum of all terms
+    pub fn total(&self) -> Score {
+        self.material + self.pst + self.pawn_structure + self.outposts + self.threats + self.bishop_pair + self.mop_up
     }
-    
-    score
 }
 
-fn evaluate_knights(pos: &Position, color: Color) -> Score {
-    let knights = pos.pieces(color, PieceType::Knight);
-    let mut score: Score = 0;
-    
-    let mut bb = knights;
-    while let Some(sq) = bb.pop_lsb() {
-        score += KNIGHT_VALUE;
-        score += pst_value(&KNIGHT_PST, sq, color);
+/// Per-term view of the static evaluation, for explaining a score in the UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub white: EvalTerms,
+    pub black: EvalTerms,
+    /// Game phase: 24 with all pieces on the board, 0 in a pawn endgame
+    pub phase: i32,
+    /// Share of the score kept by endgame scaling, out of 64 (0 for a drawn K+P vs K)
+    pub scale: i32,
+    /// Bonus for a K+P vs K win, from white's point of view
+    pub kpk_bonus: Score,
+    /// Bonus for the side to move
+    pub tempo: Score,
+    /// Static evaluation from white's point of view, without tempo
+    pub total: Score,
+}
+
+/// Split the evaluation of `pos` into its terms for each side.
+/// Slower than `evaluate_with`; not meant for use during search.
+pub fn evaluate_breakdown(pos: &Position, params: &EvalParams) -> EvalBreakdown {
+    let phase = game_phase(pos);
+    let pawns = pawn_structure(pos, &params.pawns);
+    let material = count_material(pos, params);
+    let attacks = [AttackMap::new(pos, Color::White), AttackMap::new(pos, Color::Black)];
+
+    let side = |color: Color| {
+        let (us, them) = (color as usize, color.flip() as usize);
+        let piece_material: Score = [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen]
+            .into_iter()
+            .map(|piece| pos.pieces(color, piece).count() as Score * params.piece_value(piece))
+            .sum();
+        let (outpost_mg, outpost_eg) = evaluate_outposts(pos, color, params);
+        let (threat_mg, threat_eg) = evaluate_threats(pos, color, &attacks[us], &attacks[them], params);
+        EvalTerms {
+            material: piece_material,
+            pst: material_pst(pos, color, params) - piece_material + evaluate_king(pos, color, material, params),
+            pawn_structure: taper(pawns.mg[us], pawns.eg[us], phase),
+            outposts: taper(outpost_mg, outpost_eg, phase),
+            threats: taper(threat_mg, threat_eg, phase),
+            bishop_pair: if pos.pieces(color, PieceType::Bishop).count() >= 2 { params.bishop_pair } else { 0 },
+            mop_up: mop_up(pos, color, params),
+        }
+    };
+    let white = side(Color::White);
+    let black = side(Color::Black);
+
+    let (scale, kpk_bonus) = match kpk_result(pos) {
+        Some((_, false)) => (0, 0),
+        Some((Color::White, true)) => (SCALE_NORMAL, params.kpk_win_bonus),
+        Some((Color::Black, true)) => (SCALE_NORMAL, -params.kpk_win_bonus),
+        None => {
+            let strong = if white.total() > black.total() { Color::White } else { Color::Black };
+            (scale_factor(pos, strong, params), 0)
+        }
+    };
+
+    EvalBreakdown {
+        white,
+        black,
+        phase,
+        scale,
+        kpk_bonus,
+        tempo: params.tempo,
+        total: evaluate_white(pos, params),
     }
-    
-    score
 }
 
-fn evaluate_bishops(pos: &Position, color: Color) -> Score {
-    let bishops = pos.pieces(color, PieceType::Bishop);
-    let mut score: Score = 0;
-    
-    let mut bb = bishops;
-    while let Some(sq) = bb.pop_lsb() {
-        score += BISHOP_VALUE;
-        score += pst_value(&BISHOP_PST, sq, color);
+// =============================================================================
+// MOP-UP (basic mates against a bare king)
+// =============================================================================
+
+/// Bonus per step the losing king is away from the center
+const MOP_UP_EDGE: Score = 10;
+/// Bonus per step the kings are closer than the maximum distance (14)
+const MOP_UP_KING_PROXIMITY: Score = 4;
+
+/// Manhattan distance from the four center squares (0 = center, 6 = corner)
+#[inline]
+fn center_distance(sq: Square) -> Score {
+    let file = sq.file() as Score;
+    let rank = sq.rank() as Score;
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
+
+/// Drive a bare enemy king to the edge and bring our king close, when `color`
+/// has a queen or rook to mate with. The PSTs alone don't find these mates at low depth.
+fn mop_up(pos: &Position, color: Color, params: &EvalParams) -> Score {
+    let them = color.flip();
+    let bare_king = pos.occupied_by(them) == pos.pieces(them, PieceType::King);
+    let can_mate = (pos.pieces(color, PieceType::Queen) | pos.pieces(color, PieceType::Rook)).is_not_empty();
+    if !bare_king || !can_mate {
+        return 0;
     }
-    
-    // Bishop pair bonus
-    if bishops.count() >= 2 {
-        score += 30;
+
+    let (Some(our_king), Some(their_king)) = (pos.pieces(color, PieceType::King).lsb(), pos.pieces(them, PieceType::King).lsb()) else {
+        return 0;
+    };
+    let king_distance = (our_king.file().abs_diff(their_king.file()) + our_king.rank().abs_diff(their_king.rank())) as Score;
+
+    params.mop_up_edge * center_distance(their_king) + params.mop_up_king_proximity * (14 - king_distance)
+}
+
+// =============================================================================
+// KPK BITBASE
+// =============================================================================
+
+/// Bonus for a K+P vs K position the bitbase reports as won, so the engine
+/// prefers it over any drawn line and keeps pushing toward promotion
+const KPK_WIN_BONUS: Score = 600;
+
+/// For a K+P vs K position, the side with the pawn and whether it wins.
+/// The bitbase is built for standard rules and promotions; other variants
+/// or promotion choices win differently, so they get the normal eval. It only covers pawns on ranks
+/// 2-7, so a pawn a lenient FEN put on its first or last rank is left out too.
+fn kpk_result(pos: &Position) -> Option<(Color, bool)> {
+    if pos.variant() != Variant::Standard
+        || pos.promotion_rules() != PromotionRules::STANDARD
+        || pos.occupied().count() != 3
+    {
+        return None;
     }
-    
-    score
+    let strong = if pos.pieces(Color::White, PieceType::Pawn).count() == 1 {
+        Color::White
+    } else if pos.pieces(Color::Black, PieceType::Pawn).count() == 1 {
+        Color::Black
+    } else {
+        return None;
+    };
+    let weak = strong.flip();
+
+    let strong_king = pos.pieces(strong, PieceType::King).lsb()?;
+    let weak_king = pos.pieces(weak, PieceType::King).lsb()?;
+    let pawn = pos.pieces(strong, PieceType::Pawn).lsb()?;
+    let rank = if strong == Color::White { pawn.rank() } else { 7 - pawn.rank() };
+    if !(1..=6).contains(&rank) {
+        return None;
+    }
+    Some((strong, probe_kpk(strong, strong_king, pawn, weak_king, pos.side_to_move())))
 }
 
-fn evaluate_rooks(pos: &Position, color: Color) -> Score {
-    let rooks = pos.pieces(color, PieceType::Rook);
+// =============================================================================
+// ENDGAME SCALING
+// =============================================================================
+
+/// Scale factor for a normal position (score unchanged)
+const SCALE_NORMAL: i32 = 64;
+/// Opposite-colored bishops, nothing but pawns besides
+const SCALE_OPPOSITE_BISHOPS: i32 = 32;
+/// Rook + pawn vs rook
+const SCALE_ROOK_PAWN_VS_ROOK: i32 = 32;
+/// Rook + pawn vs rook with the pawn on a rook file
+const SCALE_ROOK_EDGE_PAWN_VS_ROOK: i32 = 16;
+
+/// How much of the eval the `strong` side keeps, out of SCALE_NORMAL, in
+/// material configurations that are known to be hard or impossible to win.
+fn scale_factor(pos: &Position, strong: Color, params: &EvalParams) -> i32 {
+    let weak = strong.flip();
+    let count = |color: Color, piece: PieceType| pos.pieces(color, piece).count();
+    let minors_majors = |color: Color| {
+        count(color, PieceType::Knight) + count(color, PieceType::Bishop)
+            + count(color, PieceType::Rook) + count(color, PieceType::Queen)
+    };
+    let strong_pawns = pos.pieces(strong, PieceType::Pawn);
+
+    // Wrong rook pawn: K + B + pawns all on one rook file vs bare K in front of the
+    // promotion corner, with the bishop unable to control that corner.
+    if minors_majors(strong) == 1
+        && count(strong, PieceType::Bishop) == 1
+        && pos.occupied_by(weak) == pos.pieces(weak, PieceType::King)
+        && strong_pawns.is_not_empty()
+    {
+        for (file, file_bb) in [(0u8, Bitboard::FILE_A), (7u8, Bitboard::FILE_H)] {
+            if (strong_pawns & !file_bb).is_empty() {
+                let promo_rank = if strong == Color::White { 7 } else { 0 };
+                let corner = Square::from_file_rank(file, promo_rank);
+                let corner_color = if Bitboard::LIGHT_SQUARES.has(corner) {
+                    Bitboard::LIGHT_SQUARES
+                } else {
+                    Bitboard::DARK_SQUARES
+                };
+                let wrong_bishop = (pos.pieces(strong, PieceType::Bishop) & corner_color).is_empty();
+                let defender_near = pos.pieces(weak, PieceType::King).lsb()
+                    .is_some_and(|k| (k.file().abs_diff(file)).max(k.rank().abs_diff(promo_rank)) <= 1);
+                if wrong_bishop && defender_near {
+                    return 0;
+                }
+            }
+        }
+    }
+
+    // Opposite-colored bishops with only pawns besides
+    if minors_majors(strong) == 1
+        && minors_majors(weak) == 1
+        && count(strong, PieceType::Bishop) == 1
+        && count(weak, PieceType::Bishop) == 1
+    {
+        let bishops = pos.pieces(strong, PieceType::Bishop) | pos.pieces(weak, PieceType::Bishop);
+        if (bishops & Bitboard::LIGHT_SQUARES).count() == 1 {
+            return params.scale_opposite_bishops;
+        }
+    }
+
+    // Rook + pawn vs rook
+    if minors_majors(strong) == 1
+        && minors_majors(weak) == 1
+        && count(strong, PieceType::Rook) == 1
+        && count(weak, PieceType::Rook) == 1
+        && strong_pawns.count() == 1
+        && pos.pieces(weak, PieceType::Pawn).is_empty()
+    {
+        let edge = (strong_pawns & (Bitboard::FILE_A | Bitboard::FILE_H)).is_not_empty();
+        return if edge { params.scale_rook_edge_pawn_vs_rook } else { params.scale_rook_pawn_vs_rook };
+    }
+
+    SCALE_NORMAL
+}
+
+/// Material and piece-square score of one side's pieces, king excluded, computed
+/// from the board. Equals `Position::psq` under the default weights.
+fn material_pst(pos: &Position, color: Color, params: &EvalParams) -> Score {
     let mut score: Score = 0;
-    
-    let mut bb = rooks;
-    while let Some(sq) = bb.pop_lsb() {
-        score += ROOK_VALUE;
-        score += pst_value(&ROOK_PST, sq, color);
+    for (piece, pst) in [
+        (PieceType::Pawn, &params.pawn_pst),
+        (PieceType::Knight, &params.knight_pst),
+        (PieceType::Bishop, &params.bishop_pst),
+        (PieceType::Rook, &params.rook_pst),
+        (PieceType::Queen, &params.queen_pst),
+    ] {
+        let value = params.piece_value(piece);
+        for sq in pos.pieces(color, piece) {
+            score += value + pst_value(pst, sq, color);
+        }
     }
-    
     score
 }
 
-fn evaluate_queens(pos: &Position, color: Color) -> Score {
-    let queens = pos.pieces(color, PieceType::Queen);
+/// Piece terms on top of material and PST. `material` is the total non-king
+/// material on the board (both sides).
+fn evaluate_side(pos: &Position, color: Color, material: Score, params: &EvalParams) -> Score {
     let mut score: Score = 0;
-    
-    let mut bb = queens;
-    while let Some(sq) = bb.pop_lsb() {
-        score += QUEEN_VALUE;
-        score += pst_value(&QUEEN_PST, sq, color);
+
+    // Bishop pair bonus
+    if pos.pieces(color, PieceType::Bishop).count() >= 2 {
+        score += params.bishop_pair;
     }
-    
+    score += evaluate_king(pos, color, material, params);
+
     score
 }
 
-fn evaluate_king(pos: &Position, color: Color) -> Score {
+thread_local! {
+    /// Pawn hash table and the weights its entries were computed with. One per
+    /// thread so `evaluate` can stay a plain `&Position` function.
+    static PAWN_TABLE: RefCell<(PawnParams, PawnTable)> =
+        RefCell::new((EvalParams::DEFAULT.pawns, PawnTable::new(pawns::DEFAULT_SIZE_POWER)));
+}
+
+/// Pawn scores for both colors, served from the pawn hash table when this
+/// pawn configuration has been seen before.
+/// Everything in here must depend on pawn placement only.
+fn pawn_structure(pos: &Position, params: &PawnParams) -> PawnEntry {
+    let key = pos.pawn_hash();
+    PAWN_TABLE.with(|cell| {
+        let (table_params, table) = &mut *cell.borrow_mut();
+        if table_params != params {
+            *table_params = *params;
+            table.clear();
+        }
+        if let Some(entry) = table.probe(key) {
+            return entry;
+        }
+        let (white_mg, white_eg) = evaluate_pawns(pos, Color::White, params);
+        let (black_mg, black_eg) = evaluate_pawns(pos, Color::Black, params);
+        let entry = PawnEntry { key, mg: [white_mg, black_mg], eg: [white_eg, black_eg] };
+        table.store(entry);
+        entry
+    })
+}
+
+/// Pawn structure for one side as (middlegame, endgame) scores.
+/// Pawn material and PST are part of `material_pst`.
+fn evaluate_pawns(pos: &Position, color: Color, params: &PawnParams) -> (Score, Score) {
+    let is_white = color == Color::White;
+    let pawns = pos.pieces(color, PieceType::Pawn);
+    let their_pawns = pos.pieces(color.flip(), PieceType::Pawn);
+    let mut mg: Score = 0;
+    let mut eg: Score = 0;
+
+    // Doubled pawns: every pawn beyond the first on a file
+    for file in 0..8 {
+        let on_file = (pawns & Bitboard(Bitboard::FILE_A.0 << file)).count() as Score;
+        if on_file > 1 {
+            mg -= params.doubled_mg * (on_file - 1);
+            eg -= params.doubled_eg * (on_file - 1);
+        }
+    }
+
+    // Passed pawns: no enemy pawn in front on the same or adjacent files
+    let mut passers = Bitboard::EMPTY;
+    for sq in pawns {
+        if (passed_pawn_mask(sq, is_white) & their_pawns).is_empty() {
+            passers.set(sq);
+        }
+    }
+
+    for sq in pawns {
+        let neighbours = adjacent_files(sq) & pawns;
+        if neighbours.is_empty() {
+            mg -= params.isolated_mg;
+            eg -= params.isolated_eg;
+        } else if is_backward(sq, is_white, neighbours, their_pawns) {
+            mg -= params.backward_mg;
+            eg -= params.backward_eg;
+        }
+
+        if passers.has(sq) {
+            let rank = relative_rank(sq, color);
+            mg += params.passed_mg[rank];
+            eg += params.passed_eg[rank];
+
+            // Defended by a friendly pawn (one of the squares an enemy pawn here would attack)
+            if (pawn_attacks(sq, !is_white) & pawns).is_not_empty() {
+                mg += params.protected_passer_mg;
+                eg += params.protected_passer_eg;
+            }
+            // Another passer on an adjacent file, at most one rank away
+            if (king_attacks(sq) & adjacent_files(sq) & passers).is_not_empty() {
+                mg += params.connected_passer_mg;
+                eg += params.connected_passer_eg;
+            }
+        }
+    }
+
+    (mg, eg)
+}
+
+/// A pawn is backward when every friendly pawn on the adjacent files (`neighbours`)
+/// is already ahead of it, and an enemy pawn controls the square in front of it.
+fn is_backward(sq: Square, is_white: bool, neighbours: Bitboard, their_pawns: Bitboard) -> bool {
+    let rank = sq.rank();
+    let (stop, same_rank) = match (is_white, rank) {
+        (true, 0..=6) => (Square::new(sq.0 + 8), Bitboard(0xFFu64 << (rank * 8))),
+        (false, 1..=7) => (Square::new(sq.0 - 8), Bitboard(0xFFu64 << (rank * 8))),
+        _ => return false,
+    };
+    // Squares on the adjacent files beside or behind the pawn
+    let support = (passed_pawn_mask(sq, !is_white) | same_rank) & adjacent_files(sq);
+    (neighbours & support).is_empty() && (pawn_attacks(stop, is_white) & their_pawns).is_not_empty()
+}
+
+/// Rank from the side's own perspective (0 = back rank, 7 = promotion rank)
+#[inline]
+fn relative_rank(sq: Square, color: Color) -> usize {
+    if color == Color::White {
+        sq.rank() as usize
+    } else {
+        7 - sq.rank() as usize
+    }
+}
+
+/// Knights on outposts: defended by a friendly pawn and out of reach of enemy
+/// pawns (none on the adjacent files ahead). Worth more on central files.
+fn evaluate_outposts(pos: &Position, color: Color, params: &EvalParams) -> (Score, Score) {
+    let is_white = color == Color::White;
+    let our_pawns = pos.pieces(color, PieceType::Pawn);
+    let their_pawns = pos.pieces(color.flip(), PieceType::Pawn);
+    let mut mg: Score = 0;
+    let mut eg: Score = 0;
+
+    for sq in pos.pieces(color, PieceType::Knight) {
+        let rank = relative_rank(sq, color);
+        if params.knight_outpost_mg[rank] == 0 && params.knight_outpost_eg[rank] == 0 {
+            continue;
+        }
+        let defended = (pawn_attacks(sq, !is_white) & our_pawns).is_not_empty();
+        let safe = (pawn_attack_span(sq, is_white) & their_pawns).is_empty();
+        if defended && safe {
+            // Edge files count half
+            let edge = sq.file() == 0 || sq.file() == 7;
+            let divisor = if edge { 2 } else { 1 };
+            mg += params.knight_outpost_mg[rank] / divisor;
+            eg += params.knight_outpost_eg[rank] / divisor;
+        }
+    }
+
+    (mg, eg)
+}
+
+/// Threats made by `color`: enemy pieces attacked by a lower-valued piece
+/// (minors count as equal), and enemy pieces attacked but not defended.
+fn evaluate_threats(
+    pos: &Position,
+    color: Color,
+    ours: &AttackMap,
+    theirs: &AttackMap,
+    params: &EvalParams,
+) -> (Score, Score) {
+    let them = color.flip();
+    let mut mg: Score = 0;
+    let mut eg: Score = 0;
+
+    let by_pawns = ours.by_piece[PieceType::Pawn as usize];
+    let by_minors = by_pawns
+        | ours.by_piece[PieceType::Knight as usize]
+        | ours.by_piece[PieceType::Bishop as usize];
+    let by_rooks = by_minors | ours.by_piece[PieceType::Rook as usize];
+
+    for (victim, lower_attackers) in [
+        (PieceType::Knight, by_pawns),
+        (PieceType::Bishop, by_pawns),
+        (PieceType::Rook, by_minors),
+        (PieceType::Queen, by_rooks),
+    ] {
+        let count = (pos.pieces(them, victim) & lower_attackers).count() as Score;
+        mg += count * params.threat_by_lower_mg[victim as usize];
+        eg += count * params.threat_by_lower_eg[victim as usize];
+    }
+
+    let pieces = pos.occupied_by(them) & !pos.pieces(them, PieceType::Pawn) & !pos.pieces(them, PieceType::King);
+    let hanging = (pieces & ours.all & !theirs.all).count() as Score;
+    mg += hanging * params.hanging_piece_mg;
+    eg += hanging * params.hanging_piece_eg;
+
+    (mg, eg)
+}
+
+/// King PST: the endgame table once `material` (non-king, both sides) runs low
+fn evaluate_king(pos: &Position, color: Color, material: Score, params: &EvalParams) -> Score {
     let king = pos.pieces(color, PieceType::King);
     let mut score: Score = 0;
     
     if let Some(sq) = king.lsb() {
         // Use endgame table if few pieces remain
-        let total_material = count_material(pos);
-        let pst = if total_material < 2000 {
-            &KING_PST_EG
+        let pst = if material < params.king_endgame_material {
+            &params.king_pst_eg
         } else {
-            &KING_PST_MG
+            &params.king_pst_mg
         };
         
         score += pst_value(pst, sq, color);
@@ -278,20 +1000,49 @@ fn pst_value(table: &[Score; 64], sq: Square, color: Color) -> Score {
 }
 
 /// Count total non-king material
-fn count_material(pos: &Position) -> Score {
+fn count_material(pos: &Position, params: &EvalParams) -> Score {
     let mut total: Score = 0;
     
     for color in [Color::White, Color::Black] {
-        total += pos.pieces(color, PieceType::Pawn).count() as Score * PAWN_VALUE;
-        total += pos.pieces(color, PieceType::Knight).count() as Score * KNIGHT_VALUE;
-        total += pos.pieces(color, PieceType::Bishop).count() as Score * BISHOP_VALUE;
-        total += pos.pieces(color, PieceType::Rook).count() as Score * ROOK_VALUE;
-        total += pos.pieces(color, PieceType::Queen).count() as Score * QUEEN_VALUE;
+        for piece in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+            total += pos.pieces(color, piece).count() as Score * params.piece_value(piece);
+        }
     }
     
     total
 }
 
+// =============================================================================
+// WIN PROBABILITY
+// =============================================================================
+
+/// Advantage (centipawns) at which the side ahead wins as often as not
+const WDL_MIDPOINT: f64 = 200.0;
+/// Width of the logistic curve: larger means results depend less on the score
+const WDL_SPREAD: f64 = 100.0;
+
+/// Win/draw/loss chances in per mille for the side the score favors when
+/// positive, as `[win, draw, loss]` summing to 1000.
+///
+/// Two logistic curves, one for winning and one for losing: with equal
+/// material a draw is the likely result (about 76%), +1 pawn wins about 27% of
+/// games, +3 pawns about 73%. Mate scores map to a certain result.
+pub fn score_to_wdl(score: Score) -> [u32; 3] {
+    let cp = score as f64;
+    let curve = |x: f64| 1.0 / (1.0 + ((WDL_MIDPOINT - x) / WDL_SPREAD).exp());
+    let win = (curve(cp) * 1000.0).round() as u32;
+    let loss = (curve(-cp) * 1000.0).round() as u32;
+    [win, 1000 - win - loss, loss]
+}
+
+/// Expected result (win = 1, draw = 0.5) for the side the score favors when
+/// positive: 0.5 at 0, approaching 1.0 as the score grows. Suited for an eval
+/// bar in percent.
+pub fn score_to_win_probability(score: Score) -> f64 {
+    let [win, draw, _] = score_to_wdl(score);
+    (win as f64 + draw as f64 / 2.0) / 1000.0
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -381,17 +1132,468 @@ mod tests {
         // From black's view, same position, so it should be negative.
         assert!(score_w > 0, "White should see positive eval: {}", score_w);
         assert!(score_b < 0, "Black should see negative eval: {}", score_b);
-        // They should be exact negations (same position, just side flipped)
-        assert_eq!(score_w, -score_b, "Eval should negate when side flips");
+        // They should be exact negations apart from the tempo bonus
+        assert_eq!(score_w - TEMPO, -(score_b - TEMPO), "Eval should negate when side flips");
+    }
+
+    #[test]
+    fn test_tempo_bonus() {
+        // Symmetric position: whoever is to move is ahead by exactly the tempo
+        let white = Position::starting_position();
+        let black = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(evaluate(&white), TEMPO);
+        assert_eq!(evaluate(&black), TEMPO);
+    }
+
+    #[test]
+    fn test_eval_color_symmetry() {
+        // Asymmetric positions exercising every term: the mirror must score the exact opposite
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r1bqkb1r/pp3ppp/2np1n2/4p3/2B1P3/2N2N2/PPP2PPP/R1BQK2R b KQkq - 0 6",
+            "4k3/8/8/4p3/2P1P3/3P4/8/4K3 w - - 0 1",
+            "4k3/pp3ppp/8/3N4/4P3/8/PP3PPP/4K3 b - - 0 1",
+            "8/8/4k3/8/2P5/8/8/4K2R w K - 0 1",
+            "r1b1k2r/ppp2ppp/2n5/3qp3/1b1P4/2N1BN2/PPP2PPP/R2QKB1R w KQkq - 0 1",
+            "8/5k2/8/2b1p3/4P3/3B4/5K2/8 w - - 0 1",
+            "7k/8/7P/8/8/3B4/8/K7 w - - 0 1",
+            "8/8/8/3k4/8/8/8/R3K3 b - - 0 1",
+            "8/8/8/8/4k3/8/2P5/5K2 w - - 0 1",
+            "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1",
+        ];
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+            let mirror = pos.mirrored();
+            assert_eq!(evaluate_white(&pos, &EvalParams::DEFAULT), -evaluate_white(&mirror, &EvalParams::DEFAULT), "White-relative eval not symmetric: {}", fen);
+            assert_eq!(evaluate(&pos), evaluate(&mirror), "Side-to-move eval not symmetric: {}", fen);
+        }
     }
 
     #[test]
     fn test_eval_pawn_structure_matters() {
-        // Doubled pawns should be worth less than spread pawns
-        // But our eval doesn't penalize doubled pawns yet.
-        // At minimum, verify eval doesn't crash on pathological pawn structures
+        // Equal, symmetric pawns should be near equal
         let pos = Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap();
         let score = evaluate(&pos);
         assert!(score.abs() < 100, "Equal pawns should be near equal: {}", score);
+
+        // Doubled pawns should be worth less than spread pawns
+        let doubled = Position::from_fen("4k3/pppp4/8/8/8/2P5/P1PP4/4K3 w - - 0 1").unwrap();
+        assert!(evaluate(&doubled) < evaluate(&pos),
+            "Doubled pawns should cost: {} vs {}", evaluate(&doubled), evaluate(&pos));
+    }
+
+    #[test]
+    fn test_doubled_pawn_penalty() {
+        // Tripled c-pawns: two extra pawns on the file (all isolated either way)
+        let tripled = Position::from_fen("4k3/8/8/2P5/2P5/2P5/8/4K3 w - - 0 1").unwrap();
+        let (_, eg) = evaluate_pawns(&tripled, Color::White, &EvalParams::DEFAULT.pawns);
+        // Only the front pawn is passed (the others have a friendly pawn ahead, not an enemy)
+        let passed: Score = [2, 3, 4].iter().map(|&r| PASSED_PAWN_EG[r as usize]).sum();
+        assert_eq!(eg, passed - 2 * DOUBLED_PAWN_EG - 3 * ISOLATED_PAWN_EG);
+    }
+
+    #[test]
+    fn test_isolated_pawn_penalty() {
+        // a2 + c2 are isolated; b2 + c2 support each other. Black pawns block passers.
+        let isolated = Position::from_fen("4k3/ppp5/8/8/8/8/P1P5/4K3 w - - 0 1").unwrap();
+        let connected = Position::from_fen("4k3/ppp5/8/8/8/8/1PP5/4K3 w - - 0 1").unwrap();
+        let (iso_mg, iso_eg) = evaluate_pawns(&isolated, Color::White, &EvalParams::DEFAULT.pawns);
+        let (con_mg, con_eg) = evaluate_pawns(&connected, Color::White, &EvalParams::DEFAULT.pawns);
+        assert_eq!(con_mg - iso_mg, 2 * ISOLATED_PAWN_MG);
+        assert_eq!(con_eg - iso_eg, 2 * ISOLATED_PAWN_EG);
+    }
+
+    #[test]
+    fn test_backward_pawn() {
+        // d3 is behind c4/e4 and its stop square d4 is hit by the black e5 pawn
+        let white_pawns = Position::from_fen("4k3/8/8/4p3/2P1P3/3P4/8/4K3 w - - 0 1").unwrap();
+        let d3 = Square::from_file_rank(3, 2);
+        let pawns = white_pawns.pieces(Color::White, PieceType::Pawn);
+        let theirs = white_pawns.pieces(Color::Black, PieceType::Pawn);
+        assert!(is_backward(d3, true, adjacent_files(d3) & pawns, theirs));
+
+        // Without the black e5 pawn the advance is free: not backward
+        assert!(!is_backward(d3, true, adjacent_files(d3) & pawns, Bitboard::EMPTY));
+
+        // A pawn level with a neighbour can be supported: not backward
+        let e4 = Square::from_file_rank(4, 3);
+        assert!(!is_backward(e4, true, adjacent_files(e4) & pawns, theirs));
+
+        // Mirrored for black: d6 behind c5/e5, stop square d5 hit by white e4
+        let black_pawns = Position::from_fen("4k3/8/3p4/2p1p3/4P3/8/8/4K3 b - - 0 1").unwrap();
+        let d6 = Square::from_file_rank(3, 5);
+        let pawns = black_pawns.pieces(Color::Black, PieceType::Pawn);
+        let theirs = black_pawns.pieces(Color::White, PieceType::Pawn);
+        assert!(is_backward(d6, false, adjacent_files(d6) & pawns, theirs));
+    }
+
+    #[test]
+    fn test_pawn_table_matches_direct_eval() {
+        let pos = Position::from_fen("r1bqkb1r/pp3ppp/2np1n2/4p3/2B1P3/2N2N2/PPP2PPP/R1BQK2R w KQkq - 0 6").unwrap();
+        let params = &EvalParams::DEFAULT.pawns;
+        let (white, black) = (evaluate_pawns(&pos, Color::White, params), evaluate_pawns(&pos, Color::Black, params));
+        // First call fills the table, second is served from it
+        for _ in 0..2 {
+            let entry = pawn_structure(&pos, &EvalParams::DEFAULT.pawns);
+            assert_eq!((entry.mg[0], entry.eg[0]), white);
+            assert_eq!((entry.mg[1], entry.eg[1]), black);
+        }
+        assert_eq!(evaluate(&pos), evaluate(&pos));
+    }
+
+    #[test]
+    fn test_passed_pawn_bonus() {
+        // d5 is passed; adding a black pawn on c7 (adjacent file, in front) makes it not passed
+        let passed = Position::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        let blocked = Position::from_fen("4k3/2p5/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        let (mg, eg) = evaluate_pawns(&passed, Color::White, &EvalParams::DEFAULT.pawns);
+        let (blocked_mg, blocked_eg) = evaluate_pawns(&blocked, Color::White, &EvalParams::DEFAULT.pawns);
+        assert!(mg > blocked_mg && eg > blocked_eg, "Passed pawn should score higher");
+    }
+
+    #[test]
+    fn test_passed_pawn_scales_with_rank() {
+        // Extra h-pawns keep these out of the KPK bitbase
+        let on_4th = Position::from_fen("4k3/7p/8/8/3P4/8/7P/4K3 w - - 0 1").unwrap();
+        let on_7th = Position::from_fen("4k3/3P3p/8/8/8/8/7P/4K3 w - - 0 1").unwrap();
+        assert!(evaluate(&on_7th) > evaluate(&on_4th) + 100,
+            "7th-rank passer should be worth much more: {} vs {}", evaluate(&on_7th), evaluate(&on_4th));
+
+        // Same for black (mirror)
+        let black_on_2nd = Position::from_fen("4k3/7p/8/8/8/8/3p3P/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluate(&black_on_2nd), evaluate(&on_7th), "Mirrored passers must score the same");
+    }
+
+    #[test]
+    fn test_protected_and_connected_passers() {
+        // e5 protected by d4 (d4 itself is passed too)
+        let protected = Position::from_fen("4k3/8/8/4P3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        // Same pawns far apart: a4 and e5, neither protected nor connected (and both isolated)
+        let split = Position::from_fen("4k3/8/8/4P3/P7/8/8/4K3 w - - 0 1").unwrap();
+        let (p_mg, p_eg) = evaluate_pawns(&protected, Color::White, &EvalParams::DEFAULT.pawns);
+        let (s_mg, s_eg) = evaluate_pawns(&split, Color::White, &EvalParams::DEFAULT.pawns);
+        let expected = PROTECTED_PASSER_EG + 2 * CONNECTED_PASSER_EG + 2 * ISOLATED_PAWN_EG;
+        assert_eq!(p_eg - s_eg, expected);
+        assert!(p_mg > s_mg);
+    }
+
+    #[test]
+    fn test_taper() {
+        assert_eq!(taper(100, 200, MAX_PHASE), 100);
+        assert_eq!(taper(100, 200, 0), 200);
+        assert_eq!(taper(100, 200, MAX_PHASE / 2), 150);
+        assert_eq!(game_phase(&Position::starting_position()), MAX_PHASE);
+        assert_eq!(game_phase(&Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap()), 0);
+    }
+
+    #[test]
+    fn test_knight_outpost() {
+        // Nd5 defended by e4, no black c/e pawns left to chase it
+        let outpost = Position::from_fen("4k3/pp3ppp/8/3N4/4P3/8/PP3PPP/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_outposts(&outpost, Color::White, &EvalParams::DEFAULT), (KNIGHT_OUTPOST_MG[4], KNIGHT_OUTPOST_EG[4]));
+
+        // Black c-pawn on c7 can still kick the knight with c6: not an outpost
+        let kickable = Position::from_fen("4k3/ppp2ppp/8/3N4/4P3/8/PP3PPP/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_outposts(&kickable, Color::White, &EvalParams::DEFAULT), (0, 0));
+
+        // Undefended knight: not an outpost
+        let undefended = Position::from_fen("4k3/pp3ppp/8/3N4/8/8/PP3PPP/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_outposts(&undefended, Color::White, &EvalParams::DEFAULT), (0, 0));
+
+        // Mirrored for black: Nd4 defended by e5
+        let black = Position::from_fen("4k3/pp3ppp/8/4p3/3n4/8/PP3PPP/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluate_outposts(&black, Color::Black, &EvalParams::DEFAULT), (KNIGHT_OUTPOST_MG[4], KNIGHT_OUTPOST_EG[4]));
+    }
+
+    #[test]
+    fn test_knight_outpost_edge_file_counts_half() {
+        let edge = Position::from_fen("4k3/6pp/8/N7/1P6/8/6PP/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_outposts(&edge, Color::White, &EvalParams::DEFAULT), (KNIGHT_OUTPOST_MG[4] / 2, KNIGHT_OUTPOST_EG[4] / 2));
+    }
+
+    #[test]
+    fn test_threat_by_lower_piece() {
+        // White pawn on d4 attacks the black knight on e5 (defended by the f6 pawn, so not hanging)
+        let pos = Position::from_fen("4k3/8/5p2/4n3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        let white = AttackMap::new(&pos, Color::White);
+        let black = AttackMap::new(&pos, Color::Black);
+        assert_eq!(evaluate_threats(&pos, Color::White, &white, &black, &EvalParams::DEFAULT),
+            (THREAT_BY_LOWER_MG[PieceType::Knight as usize], THREAT_BY_LOWER_EG[PieceType::Knight as usize]));
+
+        // Knight attacking a bishop is not a threat by a lower piece (and the bishop is defended)
+        let pos = Position::from_fen("4k3/8/5p2/4b3/8/3N4/8/4K3 w - - 0 1").unwrap();
+        let white = AttackMap::new(&pos, Color::White);
+        let black = AttackMap::new(&pos, Color::Black);
+        assert_eq!(evaluate_threats(&pos, Color::White, &white, &black, &EvalParams::DEFAULT), (0, 0));
+    }
+
+    #[test]
+    fn test_hanging_piece() {
+        // Black rook on a8 attacked by the white rook on a1, nothing defends it
+        let hanging = Position::from_fen("r5k1/8/8/8/8/8/8/R5K1 b - - 0 1").unwrap();
+        let white = AttackMap::new(&hanging, Color::White);
+        let black = AttackMap::new(&hanging, Color::Black);
+        // White's a1 rook is equally hanging to the black rook
+        assert_eq!(evaluate_threats(&hanging, Color::White, &white, &black, &EvalParams::DEFAULT), (HANGING_PIECE_MG, HANGING_PIECE_EG));
+        assert_eq!(evaluate_threats(&hanging, Color::Black, &black, &white, &EvalParams::DEFAULT), (HANGING_PIECE_MG, HANGING_PIECE_EG));
+
+        // Defend the a8 rook with the king: only white's rook remains hanging
+        let defended = Position::from_fen("r7/1k6/8/8/8/8/8/R5K1 b - - 0 1").unwrap();
+        let white = AttackMap::new(&defended, Color::White);
+        let black = AttackMap::new(&defended, Color::Black);
+        assert_eq!(evaluate_threats(&defended, Color::White, &white, &black, &EvalParams::DEFAULT), (0, 0));
+        assert_eq!(evaluate_threats(&defended, Color::Black, &black, &white, &EvalParams::DEFAULT), (HANGING_PIECE_MG, HANGING_PIECE_EG));
+    }
+
+    #[test]
+    fn test_attacked_queen_lowers_eval() {
+        // Black to move with the queen attacked by a knight: worse than with the knight elsewhere
+        let threatened = Position::from_fen("4k3/8/8/3q4/8/4N3/8/4K3 b - - 0 1").unwrap();
+        let quiet = Position::from_fen("4k3/8/8/3q4/8/8/8/4K1N1 b - - 0 1").unwrap();
+        assert!(evaluate(&threatened) < evaluate(&quiet));
+    }
+
+    #[test]
+    fn test_opposite_colored_bishops_scale() {
+        // d3 (light) vs c5 (dark) are opposite colors; with a light-squared e6 bishop the eval is not scaled
+        let opposite = Position::from_fen("8/5k2/8/2b5/4P3/P2B4/5K2/8 w - - 0 1").unwrap();
+        let same = Position::from_fen("8/5k2/4b3/8/4P3/P2B4/5K2/8 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&opposite, Color::White, &EvalParams::DEFAULT), SCALE_OPPOSITE_BISHOPS);
+        assert_eq!(scale_factor(&same, Color::White, &EvalParams::DEFAULT), SCALE_NORMAL);
+        assert!(evaluate(&opposite) < evaluate(&same));
+    }
+
+    #[test]
+    fn test_rook_pawn_vs_rook_scale() {
+        let center = Position::from_fen("8/8/4k3/8/3P4/8/r7/3RK3 w - - 0 1").unwrap();
+        let edge = Position::from_fen("8/8/4k3/8/P7/8/r7/3RK3 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&center, Color::White, &EvalParams::DEFAULT), SCALE_ROOK_PAWN_VS_ROOK);
+        assert_eq!(scale_factor(&edge, Color::White, &EvalParams::DEFAULT), SCALE_ROOK_EDGE_PAWN_VS_ROOK);
+
+        // Black as the stronger side
+        let black = Position::from_fen("3rk3/R7/8/p7/8/4K3/8/8 b - - 0 1").unwrap();
+        assert_eq!(scale_factor(&black, Color::Black, &EvalParams::DEFAULT), SCALE_ROOK_EDGE_PAWN_VS_ROOK);
+    }
+
+    #[test]
+    fn test_wrong_rook_pawn_bishop_is_draw() {
+        // h-pawn with a light-squared bishop (d3) can't drive the king out of the dark h8 corner
+        let wrong = Position::from_fen("7k/8/7P/8/8/3B4/8/K7 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&wrong, Color::White, &EvalParams::DEFAULT), 0);
+        assert_eq!(evaluate(&wrong), TEMPO);
+
+        // Dark-squared bishop (e5) controls h8: winnable
+        let right = Position::from_fen("7k/8/7P/4B3/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&right, Color::White, &EvalParams::DEFAULT), SCALE_NORMAL);
+
+        // Defending king too far from the corner: winnable
+        let far = Position::from_fen("8/8/7P/2k5/8/3B4/8/K7 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&far, Color::White, &EvalParams::DEFAULT), SCALE_NORMAL);
+    }
+
+    #[test]
+    fn test_center_distance() {
+        assert_eq!(center_distance(Square::from_file_rank(3, 3)), 0); // d4
+        assert_eq!(center_distance(Square::from_file_rank(4, 4)), 0); // e5
+        assert_eq!(center_distance(Square::A1), 6);
+        assert_eq!(center_distance(Square::H8), 6);
+        assert_eq!(center_distance(Square::from_file_rank(0, 3)), 3); // a4
+    }
+
+    #[test]
+    fn test_mop_up_drives_king_to_edge() {
+        // KQ vs K: defender in the corner with our king close is better than defender in the center
+        let cornered = Position::from_fen("k7/8/1K6/8/8/8/8/7Q w - - 0 1").unwrap();
+        let central = Position::from_fen("8/8/1K6/8/4k3/8/8/7Q w - - 0 1").unwrap();
+        assert!(mop_up(&cornered, Color::White, &EvalParams::DEFAULT) > mop_up(&central, Color::White, &EvalParams::DEFAULT));
+        assert!(evaluate(&cornered) > evaluate(&central));
+
+        // Our king closer is better, same defender square
+        let far = Position::from_fen("k7/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let near = Position::from_fen("k7/8/2K5/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert!(mop_up(&near, Color::White, &EvalParams::DEFAULT) > mop_up(&far, Color::White, &EvalParams::DEFAULT));
+    }
+
+    #[test]
+    fn test_mop_up_needs_bare_king_and_mating_piece() {
+        // Defender still has a pawn: no mop-up
+        let pos = Position::from_fen("k7/p7/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(mop_up(&pos, Color::White, &EvalParams::DEFAULT), 0);
+        // Only minor pieces: no mop-up
+        let pos = Position::from_fen("k7/8/8/8/8/8/8/2B1K1N1 w - - 0 1").unwrap();
+        assert_eq!(mop_up(&pos, Color::White, &EvalParams::DEFAULT), 0);
+        // The bare king's side never gets a bonus
+        let pos = Position::from_fen("k7/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(mop_up(&pos, Color::Black, &EvalParams::DEFAULT), 0);
+    }
+
+    #[test]
+    fn test_kpk_draw_scores_zero() {
+        // Defender holds the opposition in front of the pawn
+        let pos = Position::from_fen("4k3/8/4P3/4K3/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(evaluate_white(&pos, &EvalParams::DEFAULT), 0);
+        // Rook pawn with the defender in the corner
+        let pos = Position::from_fen("7k/8/6K1/7P/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(evaluate_white(&pos, &EvalParams::DEFAULT), 0);
+    }
+
+    #[test]
+    fn test_kpk_win_gets_bonus() {
+        let won = Position::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").unwrap();
+        assert!(evaluate_white(&won, &EvalParams::DEFAULT) > KPK_WIN_BONUS);
+        // Same for black, seen from black's side
+        let won = Position::from_fen("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1").unwrap();
+        assert!(evaluate(&won) > KPK_WIN_BONUS);
+        // Other material is left to the normal eval
+        let pos = Position::from_fen("4k3/8/4K3/4P3/8/8/8/7N w - - 0 1").unwrap();
+        assert_eq!(kpk_result(&pos), None);
+    }
+
+    #[test]
+    fn test_kpk_pawn_outside_bitbase_ranks() {
+        // Pawns on their last or first rank, which from_fen accepts
+        for fen in [
+            "P7/8/8/8/8/8/8/k6K w - - 0 1",
+            "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+            "K6k/8/8/8/8/8/8/p7 b - - 0 1",
+            "p3K3/8/8/8/8/8/8/4k3 b - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            assert_eq!(kpk_result(&pos), None, "{}", fen);
+            evaluate(&pos);
+        }
+    }
+
+    #[test]
+    fn test_kpk_bitbase_standard_only() {
+        // Drawn under standard rules, but the bitbase knows nothing of other variants
+        let mut pos = Position::from_fen("4k3/8/4P3/4K3/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(kpk_result(&pos), Some((Color::White, false)));
+        for variant in Variant::ALL.iter().copied().filter(|&v| v != Variant::Standard) {
+            pos.set_variant(variant);
+            assert_eq!(kpk_result(&pos), None, "{}", variant.name());
+            assert!(evaluate_white(&pos, &EvalParams::DEFAULT) > 0, "{}", variant.name());
+            assert_eq!(evaluate_breakdown(&pos, default_params()).kpk_bonus, 0, "{}", variant.name());
+        }
+    }
+
+    #[test]
+    fn test_kpk_bitbase_standard_promotions_only() {
+        // Won for white, unless the pawn can only become a knight
+        let mut pos = Position::from_fen("8/8/8/8/8/k7/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(kpk_result(&pos), Some((Color::White, true)));
+        let won = evaluate_white(&pos, &EvalParams::DEFAULT);
+        pos.set_promotion_rules(PromotionRules::parse("n").unwrap());
+        assert_eq!(kpk_result(&pos), None);
+        assert_eq!(evaluate_breakdown(&pos, default_params()).kpk_bonus, 0);
+        assert!(evaluate_white(&pos, &EvalParams::DEFAULT) < won - KPK_WIN_BONUS / 2);
+    }
+
+    #[test]
+    fn test_default_params() {
+        let pos = Position::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        assert_eq!(evaluate_with(&pos, &EvalParams::default()), evaluate(&pos));
+        assert_eq!(EvalParams::DEFAULT.piece_value(PieceType::Rook), piece_value(PieceType::Rook));
+    }
+
+    #[test]
+    fn test_incremental_psq_matches_full_eval() {
+        // default_params() uses Position's running sums; a copy of the defaults recomputes them
+        let copy = EvalParams::default();
+        let mut pos = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(pos.psq(Color::White), material_pst(&pos, Color::White, &copy));
+        for m in crate::movegen::generate_legal_moves(&pos).iter() {
+            let undo = pos.make_move(*m).unwrap();
+            assert_eq!(evaluate_with(&pos, default_params()), evaluate_with(&pos, &copy), "After {}", m.to_uci());
+            pos.unmake_move(*m, &undo);
+        }
+    }
+
+    #[test]
+    fn test_params_change_eval() {
+        // White has an extra knight; raising its value raises the eval by the same amount
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let mut params = EvalParams::default();
+        params.knight_value += 50;
+        assert_eq!(evaluate_with(&pos, &params), evaluate(&pos) + 50);
+
+        params = EvalParams::default();
+        params.tempo = 0;
+        assert_eq!(evaluate_with(&Position::starting_position(), &params), 0);
+    }
+
+    #[test]
+    fn test_pawn_params_flush_pawn_table() {
+        // White has an isolated pawn; the cached pawn score must follow the weights
+        let pos = Position::from_fen("4k3/pp6/8/8/8/8/P7/4K3 w - - 0 1").unwrap();
+        let before = evaluate(&pos);
+        let mut params = EvalParams::default();
+        params.pawns.isolated_mg += 40;
+        params.pawns.isolated_eg += 40;
+        assert_eq!(evaluate_with(&pos, &params), before - 40);
+        assert_eq!(evaluate(&pos), before);
+    }
+
+    #[test]
+    fn test_breakdown_matches_eval() {
+        let start = evaluate_breakdown(&Position::starting_position(), default_params());
+        assert_eq!(start.white, start.black);
+        assert_eq!(start.white.material, 8 * PAWN_VALUE + 2 * (KNIGHT_VALUE + BISHOP_VALUE + ROOK_VALUE) + QUEEN_VALUE);
+        assert_eq!(start.phase, MAX_PHASE);
+        assert_eq!(start.total, 0);
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "8/5k2/8/3N4/8/8/3K4/6r1 b - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            let b = evaluate_breakdown(&pos, default_params());
+            let stm_score = if pos.side_to_move() == Color::White { b.total } else { -b.total };
+            assert_eq!(stm_score + b.tempo, evaluate(&pos));
+            assert_eq!(b.scale, SCALE_NORMAL);
+            // Only per-term tapering rounds differently
+            let sum = b.white.total() - b.black.total();
+            assert!((sum - b.total).abs() <= 6, "{}: terms sum to {}, eval is {}", fen, sum, b.total);
+        }
+    }
+
+    #[test]
+    fn test_breakdown_endgame_scaling() {
+        let kpk_draw = Position::from_fen("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let b = evaluate_breakdown(&kpk_draw, default_params());
+        assert_eq!((b.scale, b.kpk_bonus, b.total), (0, 0, 0));
+        assert_eq!(b.white.material, PAWN_VALUE);
+
+        let kpk_win = Position::from_fen("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluate_breakdown(&kpk_win, default_params()).kpk_bonus, -KPK_WIN_BONUS);
+
+        let opposite_bishops = Position::from_fen("4k3/5p2/8/8/2b5/8/1PP2B2/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_breakdown(&opposite_bishops, default_params()).scale, SCALE_OPPOSITE_BISHOPS);
+    }
+
+    #[test]
+    fn test_wdl_model() {
+        assert_eq!(score_to_wdl(0), [119, 762, 119]);
+        assert_eq!(score_to_win_probability(0), 0.5);
+
+        // Symmetric: the loss chance at +x is the win chance at -x
+        for cp in [50, 150, 400, 900] {
+            let [win, draw, loss] = score_to_wdl(cp);
+            assert_eq!(score_to_wdl(-cp), [loss, draw, win]);
+            assert!((score_to_win_probability(cp) + score_to_win_probability(-cp) - 1.0).abs() < 1e-9);
+        }
+
+        // Monotonic in the score
+        let mut last = 0.0;
+        for cp in (-1000..=1000).step_by(25) {
+            let p = score_to_win_probability(cp);
+            assert!(p >= last, "Not monotonic at {}", cp);
+            last = p;
+        }
+
+        assert_eq!(score_to_wdl(MATE_SCORE - 5), [1000, 0, 0]);
+        assert_eq!(score_to_win_probability(-MATE_SCORE + 5), 0.0);
     }
 }
@@ -0,0 +1,103 @@
+// Evaluation Cache Module
+// Small hash table of static evaluation scores, separate from the TT.
+// Quiescence search reaches the same positions through different capture orders;
+// caching the score by Zobrist hash avoids re-running the evaluator for them.
+
+use crate::eval::{evaluate_with, EvalParams, Score};
+use crate::position::Position;
+
+/// Default number of entries: 2^16 (1 MB).
+pub const DEFAULT_SIZE_POWER: u32 = 16;
+
+#[derive(Clone, Copy, Default)]
+struct EvalEntry {
+    hash: u64,
+    score: Score,
+}
+
+/// Always-replace cache of `evaluate_with()` results.
+/// Scores are side-to-move relative, which is safe because the hash includes the side to move.
+/// The cache doesn't know the weights: use one cache per set of `EvalParams`.
+pub struct EvalCache {
+    entries: Vec<EvalEntry>,
+    mask: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl EvalCache {
+    /// Create a cache with 2^size_power entries.
+    pub fn new(size_power: u32) -> Self {
+        let capacity = 1usize << size_power;
+        EvalCache {
+            entries: vec![EvalEntry::default(); capacity],
+            mask: capacity - 1,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up a cached score.
+    #[inline]
+    pub fn probe(&mut self, hash: u64) -> Option<Score> {
+        let entry = &self.entries[(hash as usize) & self.mask];
+        if entry.hash == hash && hash != 0 {
+            self.hits += 1;
+            Some(entry.score)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    /// Store a score, replacing whatever was in the slot.
+    #[inline]
+    pub fn store(&mut self, hash: u64, score: Score) {
+        self.entries[(hash as usize) & self.mask] = EvalEntry { hash, score };
+    }
+
+    /// Static evaluation of `pos`, served from the cache when possible.
+    pub fn evaluate(&mut self, pos: &Position, params: &EvalParams) -> Score {
+        let hash = pos.hash();
+        if let Some(score) = self.probe(hash) {
+            return score;
+        }
+        let score = evaluate_with(pos, params);
+        self.store(hash, score);
+        score
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{default_params, evaluate};
+
+    #[test]
+    fn cached_score_matches_evaluate() {
+        let mut cache = EvalCache::new(10);
+        let pos = Position::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+
+        let first = cache.evaluate(&pos, default_params());
+        let second = cache.evaluate(&pos, default_params());
+        assert_eq!(first, evaluate(&pos));
+        assert_eq!(second, first);
+        assert_eq!(cache.misses, 1);
+        assert_eq!(cache.hits, 1);
+    }
+
+    #[test]
+    fn always_replace_on_index_collision() {
+        let mut cache = EvalCache::new(4);
+        let a = 0x0000_0000_0000_0013u64;
+        let b = 0x1000_0000_0000_0013u64; // same slot, different hash
+        cache.store(a, 10);
+        cache.store(b, 20);
+        assert_eq!(cache.probe(a), None);
+        assert_eq!(cache.probe(b), Some(20));
+    }
+}
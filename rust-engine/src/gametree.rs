@@ -0,0 +1,214 @@
+// Game Tree
+// Every move played in a game, with variations: each position keeps the moves
+// tried from it, the main continuation first. GameState walks the tree with a
+// cursor; the moves on the way to the cursor are its history.
+
+use crate::types::Move;
+
+/// Index of a node in the tree
+pub type NodeId = usize;
+
+/// The node before any move
+pub const ROOT: NodeId = 0;
+
+#[derive(Clone)]
+struct Node {
+    /// Move leading here (unused for the root)
+    mv: Move,
+    parent: NodeId,
+    /// Continuations, the main line first
+    children: Vec<NodeId>,
+    /// Child entered most recently, which redo returns to
+    selected: Option<NodeId>,
+    /// Annotator's comment after the move (before the game, for the root)
+    comment: String,
+    /// Numeric annotation glyphs: 1 = "!", 2 = "?", ...
+    nags: Vec<u8>,
+}
+
+impl Node {
+    fn new(mv: Move, parent: NodeId) -> Self {
+        Node { mv, parent, children: Vec::new(), selected: None, comment: String::new(), nags: Vec::new() }
+    }
+}
+
+/// Moves and variations of one game. Deleted lines are unlinked, not freed,
+/// until the tree is replaced.
+#[derive(Clone)]
+pub struct GameTree {
+    nodes: Vec<Node>,
+}
+
+impl GameTree {
+    pub fn new() -> Self {
+        GameTree { nodes: vec![Node::new(Move::NULL, ROOT)] }
+    }
+
+    /// Move leading to `id`
+    pub fn mv(&self, id: NodeId) -> Move {
+        self.nodes[id].mv
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        (id != ROOT).then(|| self.nodes[id].parent)
+    }
+
+    pub fn comment(&self, id: NodeId) -> &str {
+        &self.nodes[id].comment
+    }
+
+    pub fn set_comment(&mut self, id: NodeId, comment: String) {
+        self.nodes[id].comment = comment;
+    }
+
+    pub fn nags(&self, id: NodeId) -> &[u8] {
+        &self.nodes[id].nags
+    }
+
+    pub fn set_nags(&mut self, id: NodeId, nags: Vec<u8>) {
+        self.nodes[id].nags = nags;
+    }
+
+    /// Continuations from `id`, the main line first
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id].children
+    }
+
+    /// The node reached by playing `mv` at `id`, added as a new variation (or
+    /// the main line, if it is the first) when not there yet. It becomes the
+    /// selected child.
+    pub fn enter(&mut self, id: NodeId, mv: Move) -> NodeId {
+        let child = match self.nodes[id].children.iter().find(|&&c| self.nodes[c].mv == mv) {
+            Some(&c) => c,
+            None => {
+                let c = self.nodes.len();
+                self.nodes.push(Node::new(mv, id));
+                self.nodes[id].children.push(c);
+                c
+            }
+        };
+        self.nodes[id].selected = Some(child);
+        child
+    }
+
+    /// Where redo goes from `id`: the child entered last, or the main line
+    pub fn selected_child(&self, id: NodeId) -> Option<NodeId> {
+        let node = &self.nodes[id];
+        node.selected.filter(|c| node.children.contains(c)).or_else(|| node.children.first().copied())
+    }
+
+    /// Nodes redo would visit from `id`, in order
+    pub fn line_after(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.selected_child(id), move |&c| self.selected_child(c))
+    }
+
+    /// Whether every move leading to `id` is the main continuation
+    pub fn is_mainline(&self, id: NodeId) -> bool {
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            if self.nodes[parent].children.first() != Some(&node) {
+                return false;
+            }
+            node = parent;
+        }
+        true
+    }
+
+    /// Nodes of the main line from the start, the root excluded
+    pub fn mainline_nodes(&self) -> Vec<NodeId> {
+        std::iter::successors(self.nodes[ROOT].children.first().copied(), |&c| self.nodes[c].children.first().copied())
+            .collect()
+    }
+
+    /// Moves of the main line from the start
+    pub fn mainline(&self) -> Vec<Move> {
+        self.mainline_nodes().into_iter().map(|c| self.nodes[c].mv).collect()
+    }
+
+    /// Make the line leading to `id` the main line, putting each of its moves
+    /// first among its alternatives. Returns false if it already was.
+    pub fn promote(&mut self, id: NodeId) -> bool {
+        let mut changed = false;
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            let children = &mut self.nodes[parent].children;
+            if let Some(i) = children.iter().position(|&c| c == node).filter(|&i| i > 0) {
+                let c = children.remove(i);
+                children.insert(0, c);
+                changed = true;
+            }
+            node = parent;
+        }
+        changed
+    }
+
+    /// Delete `id` and everything after it. Returns its parent, or None for the root.
+    pub fn remove(&mut self, id: NodeId) -> Option<NodeId> {
+        let parent = self.parent(id)?;
+        let node = &mut self.nodes[parent];
+        node.children.retain(|&c| c != id);
+        if node.selected == Some(id) {
+            node.selected = None;
+        }
+        Some(parent)
+    }
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Square;
+
+    fn mv(uci: &str) -> Move {
+        Move::new(Square::from_algebraic(&uci[0..2]).unwrap(), Square::from_algebraic(&uci[2..4]).unwrap())
+    }
+
+    #[test]
+    fn test_enter_and_variations() {
+        let mut tree = GameTree::new();
+        let e4 = tree.enter(ROOT, mv("e2e4"));
+        let e5 = tree.enter(e4, mv("e7e5"));
+        // Same move again: same node
+        assert_eq!(tree.enter(ROOT, mv("e2e4")), e4);
+        let c5 = tree.enter(e4, mv("c7c5"));
+        assert_eq!(tree.children(e4), [e5, c5]);
+        assert_eq!(tree.parent(c5), Some(e4));
+        assert_eq!(tree.parent(ROOT), None);
+
+        // Redo follows the child entered last
+        assert_eq!(tree.selected_child(e4), Some(c5));
+        assert_eq!(tree.line_after(ROOT).collect::<Vec<_>>(), [e4, c5]);
+        assert!(tree.is_mainline(e5) && !tree.is_mainline(c5));
+        assert_eq!(tree.mainline(), [mv("e2e4"), mv("e7e5")]);
+    }
+
+    #[test]
+    fn test_promote_and_remove() {
+        let mut tree = GameTree::new();
+        let e4 = tree.enter(ROOT, mv("e2e4"));
+        let e5 = tree.enter(e4, mv("e7e5"));
+        let c5 = tree.enter(e4, mv("c7c5"));
+        let nf3 = tree.enter(c5, mv("g1f3"));
+
+        assert!(tree.promote(nf3));
+        assert!(!tree.promote(nf3));
+        assert_eq!(tree.children(e4), [c5, e5]);
+        assert_eq!(tree.mainline(), [mv("e2e4"), mv("c7c5"), mv("g1f3")]);
+
+        assert_eq!(tree.remove(c5), Some(e4));
+        assert_eq!(tree.children(e4), [e5]);
+        assert_eq!(tree.selected_child(e4), Some(e5));
+        assert_eq!(tree.mainline(), [mv("e2e4"), mv("e7e5")]);
+        assert_eq!(tree.remove(ROOT), None);
+    }
+}
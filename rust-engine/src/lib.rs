@@ -2,21 +2,55 @@
 // Compiled to WebAssembly for browser use
 
 mod attacks;
+#[cfg(feature = "engine")]
+pub mod bench;
+mod bitbase;
 mod bitboard;
+pub mod book;
+#[cfg(all(feature = "engine", not(target_arch = "wasm32")))]
+pub mod datagen;
+#[cfg(all(feature = "engine", not(target_arch = "wasm32")))]
+pub mod driver;
+pub mod elo;
 pub mod eval;
+#[cfg(feature = "engine")]
+mod evalcache;
+mod gametree;
+pub mod epd;
+pub mod error;
 mod magic;
 pub mod movegen;
+mod pawns;
+#[cfg(feature = "engine")]
+pub mod personality;
+pub mod pgn;
 pub mod position;
+pub mod san;
+mod savegame;
+#[cfg(feature = "engine")]
 pub mod search;
-mod tt;
+#[cfg(feature = "engine")]
+pub mod session;
+#[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+pub mod syzygy;
+#[cfg(feature = "engine")]
+pub mod timeman;
+#[cfg(feature = "engine")]
+pub mod tt;
 pub mod types;
+pub mod variant;
+#[cfg(feature = "engine")]
+pub mod worker;
 mod zobrist;
 
 use wasm_bindgen::prelude::*;
 use position::Position;
 use movegen::{generate_legal_moves, generate_pseudo_legal_moves, perft};
+#[cfg(feature = "engine")]
 use search::{search, search_iterative};
+#[cfg(feature = "engine")]
 use eval::evaluate;
+use error::{EngineError, ErrorKind};
 
 // Initialize panic hook for better error messages in browser console
 #[wasm_bindgen(start)]
@@ -37,8 +71,16 @@ pub fn new_game() -> Position {
 
 /// Create position from FEN string
 #[wasm_bindgen]
-pub fn from_fen(fen: &str) -> Result<Position, String> {
-    Position::from_fen(fen).map_err(|e| e.to_string())
+pub fn from_fen(fen: &str) -> Result<Position, EngineError> {
+    Position::from_fen(fen).map_err(|e| EngineError::new(ErrorKind::BadFen, e))
+}
+
+/// Create position from FEN string, rejecting positions that can't occur in a
+/// game (missing kings, pawns on the back rank, side not to move in check, ...).
+/// The error says what is wrong and in which FEN field, for board editors.
+#[wasm_bindgen]
+pub fn from_fen_strict(fen: &str) -> Result<Position, EngineError> {
+    Position::from_fen_strict(fen).map_err(EngineError::from)
 }
 
 /// Get FEN string from position
@@ -56,9 +98,8 @@ pub fn ping() -> String {
 /// Get engine info
 #[wasm_bindgen]
 pub fn engine_info() -> String {
-    format!(
-        "Chess Engine v0.2.0\nBitboard + Magic Bitboards\nAlpha-Beta Search with Quiescence\nPiece-Square Tables\nCompiled to WebAssembly"
-    )
+    "Chess Engine v0.2.0\nBitboard + Magic Bitboards\nAlpha-Beta Search with Quiescence\nPiece-Square Tables\nCompiled to WebAssembly"
+        .to_string()
 }
 
 // =============================================================================
@@ -67,6 +108,7 @@ pub fn engine_info() -> String {
 
 /// Get best move for the current position
 /// Returns move in UCI format (e.g., "e2e4")
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 pub fn get_best_move(pos: &Position, depth: u8) -> Option<String> {
     let mut pos = pos.clone();
@@ -75,6 +117,7 @@ pub fn get_best_move(pos: &Position, depth: u8) -> Option<String> {
 }
 
 /// Get best move with iterative deepening (better for time management)
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 pub fn get_best_move_iterative(pos: &Position, max_depth: u8) -> Option<String> {
     let mut pos = pos.clone();
@@ -83,12 +126,46 @@ pub fn get_best_move_iterative(pos: &Position, max_depth: u8) -> Option<String>
 }
 
 /// Evaluate the current position (centipawns, from side-to-move perspective)
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 pub fn eval_position(pos: &Position) -> i32 {
     evaluate(pos)
 }
 
+/// Expected result for the side a score favors, 0.0 to 1.0 (0.5 = equal).
+/// For drawing an eval bar in percent instead of centipawns.
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+pub fn score_to_win_probability(cp: i32) -> f64 {
+    eval::score_to_win_probability(cp)
+}
+
+/// Names of the available engine personalities (array), for `GameState::set_personality`
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+pub fn list_personalities() -> JsValue {
+    to_js(&personality_names())
+}
+
+/// `list_personalities` without the conversion to a JS array
+#[cfg(feature = "engine")]
+pub fn personality_names() -> Vec<&'static str> {
+    personality::Personality::ALL.iter().map(|p| p.name()).collect()
+}
+
+/// Names of the available rule variants (array), for `GameState::set_variant`
+#[wasm_bindgen]
+pub fn list_variants() -> JsValue {
+    to_js(&variant_names())
+}
+
+/// `list_variants` without the conversion to a JS array
+pub fn variant_names() -> Vec<&'static str> {
+    variant::Variant::ALL.iter().map(|v| v.name()).collect()
+}
+
 /// Search result with full info
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 pub struct SearchResult {
     best_move: String,
@@ -97,6 +174,7 @@ pub struct SearchResult {
     depth: u8,
 }
 
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 impl SearchResult {
     #[wasm_bindgen(getter)]
@@ -121,6 +199,7 @@ impl SearchResult {
 }
 
 /// Search with full stats
+#[cfg(feature = "engine")]
 #[wasm_bindgen]
 pub fn search_position(pos: &Position, depth: u8) -> SearchResult {
     let mut pos = pos.clone();
@@ -141,8 +220,7 @@ pub fn search_position(pos: &Position, depth: u8) -> SearchResult {
 /// Get all legal moves for a position as a JSON array of move strings (UCI format)
 #[wasm_bindgen]
 pub fn get_legal_moves(pos: &Position) -> Vec<JsValue> {
-    let mut pos = pos.clone();
-    let moves = generate_legal_moves(&mut pos);
+    let moves = generate_legal_moves(pos);
     moves.iter()
         .map(|m| JsValue::from_str(&m.to_uci()))
         .collect()
@@ -151,8 +229,7 @@ pub fn get_legal_moves(pos: &Position) -> Vec<JsValue> {
 /// Get number of legal moves in position
 #[wasm_bindgen]
 pub fn count_legal_moves(pos: &Position) -> usize {
-    let mut pos = pos.clone();
-    generate_legal_moves(&mut pos).len()
+    generate_legal_moves(pos).len()
 }
 
 /// Get all pseudo-legal moves (may leave king in check)
@@ -179,6 +256,7 @@ pub fn make_move(pos: &mut Position, from_file: u8, from_rank: u8, to_file: u8,
             "r" | "R" => PieceType::Rook,
             "b" | "B" => PieceType::Bishop,
             "n" | "N" => PieceType::Knight,
+            "k" | "K" => PieceType::King,
             _ => PieceType::Queen,
         };
         Move::new_promotion(from, to, promo_piece)
@@ -189,58 +267,30 @@ pub fn make_move(pos: &mut Position, from_file: u8, from_rank: u8, to_file: u8,
     pos.make_move(m).is_some()
 }
 
-/// Make a move using UCI notation (e.g., "e2e4", "e7e8q")
+/// Make a move using UCI notation (e.g., "e2e4", "e7e8q"). Returns false,
+/// leaving the position alone, unless it names a legal move.
 #[wasm_bindgen]
 pub fn make_move_uci(pos: &mut Position, uci: &str) -> bool {
-    use types::{Move, Square, PieceType};
-    
-    if uci.len() < 4 {
+    use types::{PieceType, Square};
+
+    if !uci.is_ascii() || uci.len() < 4 {
         return false;
     }
-    
-    let from = match Square::from_algebraic(&uci[0..2]) {
-        Some(sq) => sq,
-        None => return false,
-    };
-    let to = match Square::from_algebraic(&uci[2..4]) {
-        Some(sq) => sq,
-        None => return false,
-    };
-    
-    // Check for en passant
-    if let Some((_, piece_type)) = pos.piece_on(from) {
-        if piece_type == PieceType::Pawn {
-            if let Some(ep_sq) = pos.en_passant_square() {
-                if to == ep_sq {
-                    return pos.make_move(Move::new_en_passant(from, to)).is_some();
-                }
-            }
-        }
-        
-        // Check for castling
-        if piece_type == PieceType::King {
-            let diff = (to.file() as i8 - from.file() as i8).abs();
-            if diff == 2 {
-                return pos.make_move(Move::new_castling(from, to)).is_some();
-            }
-        }
-    }
-    
-    let m = if uci.len() >= 5 {
-        let promo_char = uci.chars().nth(4).unwrap();
-        let promo_piece = match promo_char {
-            'q' | 'Q' => PieceType::Queen,
-            'r' | 'R' => PieceType::Rook,
-            'b' | 'B' => PieceType::Bishop,
-            'n' | 'N' => PieceType::Knight,
-            _ => PieceType::Queen,
-        };
-        Move::new_promotion(from, to, promo_piece)
-    } else {
-        Move::new(from, to)
+    let (Some(from), Some(to)) = (Square::from_algebraic(&uci[0..2]), Square::from_algebraic(&uci[2..4])) else {
+        return false;
     };
-    
-    pos.make_move(m).is_some()
+    let promotion = uci[4..].chars().next().map(|c| match c.to_ascii_lowercase() {
+        'r' => PieceType::Rook,
+        'b' => PieceType::Bishop,
+        'n' => PieceType::Knight,
+        'k' => PieceType::King,
+        _ => PieceType::Queen,
+    });
+
+    // The legal move it names picks the move type (en passant, castling, promotion)
+    let legal = generate_legal_moves(pos);
+    let found = legal.iter().find(|m| m.from() == from && m.to() == to && m.promotion_piece() == promotion).copied();
+    found.is_some_and(|m| pos.make_move(m).is_some())
 }
 
 /// Check if the current side is in check
@@ -267,6 +317,16 @@ pub fn get_hash(pos: &Position) -> u64 {
     pos.hash()
 }
 
+/// Zobrist hash as 16 lowercase hex digits, usable as a JS map key
+#[wasm_bindgen]
+pub fn get_hash_hex(pos: &Position) -> String {
+    hash_hex(pos.hash())
+}
+
+fn hash_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
 // =============================================================================
 // GAME-STATE DETECTION API (Task 2.1)
 // =============================================================================
@@ -289,6 +349,13 @@ pub fn is_insufficient_material(pos: &Position) -> bool {
     pos.is_insufficient_material()
 }
 
+/// Check if neither side can checkmate by any sequence of legal moves
+/// (insufficient material, same-colored bishops, locked pawn walls)
+#[wasm_bindgen]
+pub fn is_dead_position(pos: &Position) -> bool {
+    pos.is_dead_position()
+}
+
 /// Check if the 50-move rule draw has been reached
 #[wasm_bindgen]
 pub fn is_fifty_move_draw(pos: &Position) -> bool {
@@ -309,6 +376,87 @@ pub fn game_status(pos: &Position) -> String {
     pos.game_status()
 }
 
+// =============================================================================
+// SHARED TRANSPOSITION TABLE (WASM threads)
+// =============================================================================
+// On a cross-origin isolated page the module can run in several workers over
+// one shared memory (a build with the `atomics` target feature). A SharedTT
+// and the registry `SharedTT.open` looks ids up in live in that memory, so
+// each worker must instantiate the module over the page's memory rather than
+// a fresh one: the page posts `worker_init()` to the worker, which passes it
+// to `init` before `start_worker()` (see worker.rs). The page then creates the
+// table, sends its id to the workers, and each worker's game opens it with
+// `SharedTT.open(id)` and `use_shared_tt`. Workers searching the same position
+// then share what they find, which is parallel search. An instance with a
+// memory of its own finds none of the page's tables.
+
+/// `{module, memory}` of this instance: posted to a worker and passed to its
+/// `init`, it runs the engine over this instance's memory and tables
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+pub fn worker_init() -> JsValue {
+    let init = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&init, &JsValue::from_str("module"), &wasm_bindgen::module());
+    let _ = js_sys::Reflect::set(&init, &JsValue::from_str("memory"), &wasm_bindgen::memory());
+    init.into()
+}
+
+/// Whether the page may share memory between workers (`crossOriginIsolated`)
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+pub fn cross_origin_isolated() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("crossOriginIsolated"))
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// A transposition table several games or workers search with at once
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+pub struct SharedTT {
+    table: std::sync::Arc<tt::SharedTable>,
+}
+
+#[cfg(feature = "engine")]
+#[wasm_bindgen]
+impl SharedTT {
+    /// A table of 2^`size_power` entries (16 bytes each)
+    #[wasm_bindgen(constructor)]
+    pub fn new(size_power: u8) -> SharedTT {
+        SharedTT { table: tt::SharedTable::new(size_power.min(26)) }
+    }
+
+    /// The table with this id, created in this memory and still in use
+    pub fn open(id: u32) -> Option<SharedTT> {
+        tt::SharedTable::open(id).map(|table| SharedTT { table })
+    }
+
+    /// Id to pass to other workers
+    pub fn id(&self) -> u32 {
+        self.table.id()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    /// Fraction of entries in use
+    pub fn utilization(&self) -> f64 {
+        self.table.filled() as f64 / self.table.capacity() as f64
+    }
+
+    /// Age the entries of earlier searches, once per move for all workers
+    pub fn new_search(&self) {
+        self.table.new_search();
+    }
+
+    /// Empty the table for everyone using it
+    pub fn clear(&self) {
+        self.table.clear();
+    }
+}
+
 // =============================================================================
 // GAME STATE WITH HISTORY (Task 2.1 — Threefold Repetition)
 // Wraps Position + hash history for full game-state detection
@@ -321,6 +469,49 @@ pub struct GameState {
     hash_history: Vec<u64>,
     move_history: Vec<(types::Move, position::UndoInfo)>,
     uci_history: Vec<String>,
+    /// Every move played, with variations; `move_history` is the path to `node`
+    tree: gametree::GameTree,
+    node: gametree::NodeId,
+    #[cfg(feature = "engine")]
+    tt: tt::TranspositionTable,
+    #[cfg(feature = "engine")]
+    ponder: Option<PonderState>,
+    /// Playing style used by searches
+    #[cfg(feature = "engine")]
+    personality: personality::Personality,
+    /// Time control, if the game is played on a clock
+    clock: Option<Clock>,
+    /// Pending draw offer: who made it, and at which ply
+    draw_offer: Option<(types::Color, usize)>,
+    /// Result and status of a game ended off the board (resignation, agreement)
+    ending: Option<(&'static str, &'static str)>,
+}
+
+/// Chess clock: base time plus a per-move increment, in milliseconds.
+struct Clock {
+    base_ms: f64,
+    increment_ms: f64,
+    /// Time left, indexed by color
+    remaining: [f64; 2],
+    /// Timestamp the side to move's time is charged from, for `record_time_at`
+    last_timestamp: Option<f64>,
+}
+
+impl Clock {
+    fn new(base_ms: f64, increment_ms: f64) -> Self {
+        Clock { base_ms, increment_ms, remaining: [base_ms; 2], last_timestamp: None }
+    }
+}
+
+/// Pondering on a predicted opponent reply.
+#[cfg(feature = "engine")]
+struct PonderState {
+    /// Hash of the game position the prediction was made from
+    base_hash: u64,
+    /// Predicted opponent move (UCI)
+    uci: String,
+    /// Position after the predicted move
+    position: Position,
 }
 
 #[wasm_bindgen]
@@ -335,42 +526,129 @@ impl GameState {
             hash_history: vec![hash],
             move_history: Vec::new(),
             uci_history: Vec::new(),
+            tree: gametree::GameTree::new(),
+            node: gametree::ROOT,
+            #[cfg(feature = "engine")]
+            tt: tt::TranspositionTable::default_wasm(),
+            #[cfg(feature = "engine")]
+            ponder: None,
+            #[cfg(feature = "engine")]
+            personality: personality::Personality::default(),
+            clock: None,
+            draw_offer: None,
+            ending: None,
         }
     }
 
     /// Create from FEN string
-    pub fn from_fen(fen: &str) -> Result<GameState, String> {
-        let pos = Position::from_fen(fen).map_err(|e| e.to_string())?;
+    pub fn from_fen(fen: &str) -> Result<GameState, EngineError> {
+        let pos = Position::from_fen(fen).map_err(|e| EngineError::new(ErrorKind::BadFen, e))?;
         let hash = pos.hash();
         Ok(Self {
             position: pos,
             hash_history: vec![hash],
             move_history: Vec::new(),
             uci_history: Vec::new(),
+            tree: gametree::GameTree::new(),
+            node: gametree::ROOT,
+            #[cfg(feature = "engine")]
+            tt: tt::TranspositionTable::default_wasm(),
+            #[cfg(feature = "engine")]
+            ponder: None,
+            #[cfg(feature = "engine")]
+            personality: personality::Personality::default(),
+            clock: None,
+            draw_offer: None,
+            ending: None,
         })
     }
 
     /// Make a move in UCI notation. Returns true if legal.
     pub fn make_move_uci(&mut self, uci: &str) -> bool {
+        self.try_move_uci(uci).is_ok()
+    }
+
+    /// Make a move in standard algebraic notation ("e4", "Nxf3+", "e8=Q", "O-O").
+    /// Returns true if the move was parsed and legal.
+    pub fn make_move_san(&mut self, san: &str) -> bool {
+        self.try_move_san(san).is_ok()
+    }
+
+    /// Make a move in UCI notation, throwing an error object saying why it
+    /// wasn't played: BadNotation, BadSquare or IllegalMove.
+    pub fn try_move_uci(&mut self, uci: &str) -> Result<(), EngineError> {
+        let m = Self::resolve_uci_checked(&self.position, uci)?;
+        if !self.position.is_legal(m) || !self.play(m) {
+            return Err(EngineError::new(ErrorKind::IllegalMove, format!("Illegal move: {}", uci)).with_field(uci));
+        }
+        self.press_clock();
+        Ok(())
+    }
+
+    /// Make a move in standard algebraic notation, throwing an error object
+    /// saying why it wasn't played: BadNotation (including ambiguous moves)
+    /// or IllegalMove.
+    pub fn try_move_san(&mut self, san: &str) -> Result<(), EngineError> {
+        let m = san::parse_san(&self.position, san).map_err(|e| EngineError::from_san(e, san))?;
+        if !self.play(m) {
+            return Err(EngineError::from_san("Illegal move", san));
+        }
+        self.press_clock();
+        Ok(())
+    }
+
+    /// Whether a UCI move is legal in the current position. Doesn't change the game.
+    pub fn is_legal_uci(&self, uci: &str) -> bool {
+        Self::resolve_uci(&self.position, uci).is_some_and(|m| self.position.is_legal(m))
+    }
+
+    /// Play a move and record it in the history, entering its node in the
+    /// tree (a new variation if it wasn't there). Returns false if illegal.
+    fn play(&mut self, m: types::Move) -> bool {
+        if let Some(undo) = self.position.make_move(m) {
+            self.hash_history.push(self.position.hash());
+            self.move_history.push((m, undo));
+            self.uci_history.push(m.to_uci());
+            self.node = self.tree.enter(self.node, m);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `resolve_uci`, saying what is wrong with the text when it fails
+    fn resolve_uci_checked(pos: &Position, uci: &str) -> Result<types::Move, EngineError> {
+        let bad = |kind, what: &str, field: &str| EngineError::new(kind, format!("{}: {}", what, uci)).with_field(field);
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(bad(ErrorKind::BadNotation, "Invalid UCI move", uci));
+        }
+        for square in [&uci[0..2], &uci[2..4]] {
+            if types::Square::from_algebraic(square).is_none() {
+                return Err(bad(ErrorKind::BadSquare, "Invalid square", square));
+            }
+        }
+        if uci.len() == 5 && !"qrbnkQRBNK".contains(&uci[4..]) {
+            return Err(bad(ErrorKind::BadNotation, "Invalid promotion piece", &uci[4..]));
+        }
+        Self::resolve_uci(pos, uci).ok_or_else(|| bad(ErrorKind::IllegalMove, "No piece to move", uci))
+    }
+
+    /// Turn a UCI string into a move for `pos`, picking the right move type
+    /// (en passant, castling, promotion). Legality is not checked.
+    fn resolve_uci(pos: &Position, uci: &str) -> Option<types::Move> {
         use types::{Move, Square, PieceType};
 
         if uci.len() < 4 {
-            return false;
+            return None;
         }
 
-        let from = match Square::from_algebraic(&uci[0..2]) {
-            Some(sq) => sq,
-            None => return false,
-        };
-        let to = match Square::from_algebraic(&uci[2..4]) {
-            Some(sq) => sq,
-            None => return false,
-        };
+        let from = Square::from_algebraic(&uci[0..2])?;
+        let to = Square::from_algebraic(&uci[2..4])?;
 
         // Determine the correct move type
-        let m = if let Some((_, piece_type)) = self.position.piece_on(from) {
+        let m = if let Some((_, piece_type)) = pos.piece_on(from) {
             if piece_type == PieceType::Pawn {
-                if let Some(ep_sq) = self.position.en_passant_square() {
+                if let Some(ep_sq) = pos.en_passant_square() {
                     if to == ep_sq {
                         Move::new_en_passant(from, to)
                     } else if uci.len() >= 5 {
@@ -396,17 +674,10 @@ impl GameState {
                 Move::new(from, to)
             }
         } else {
-            return false; // No piece on from square
+            return None; // No piece on from square
         };
 
-        if let Some(undo) = self.position.make_move(m) {
-            self.hash_history.push(self.position.hash());
-            self.move_history.push((m, undo));
-            self.uci_history.push(uci.to_string());
-            true
-        } else {
-            false
-        }
+        Some(m)
     }
 
     fn parse_promo(ch: char) -> types::PieceType {
@@ -415,250 +686,1836 @@ impl GameState {
             'r' | 'R' => types::PieceType::Rook,
             'b' | 'B' => types::PieceType::Bishop,
             'n' | 'N' => types::PieceType::Knight,
+            'k' | 'K' => types::PieceType::King,
             _ => types::PieceType::Queen,
         }
     }
 
     /// Undo the last move. Returns the UCI string of the undone move, or empty string if nothing to undo.
+    /// The move stays in the game tree, and `redo` replays it.
     pub fn undo(&mut self) -> String {
         if let Some((m, undo)) = self.move_history.pop() {
-            self.position.unmake_move(m, &undo);
+            Self::unplay(&mut self.position, m, &undo);
             self.hash_history.pop();
+            self.node = self.tree.parent(self.node).unwrap_or(gametree::ROOT);
             self.uci_history.pop().unwrap_or_default()
         } else {
             String::new()
         }
     }
 
-    /// Get the FEN of the current position
-    pub fn fen(&self) -> String {
-        self.position.to_fen()
+    /// Go forward one move: the one last taken back from here, or else the
+    /// main continuation. Returns its UCI string, or empty string if the
+    /// current position has no continuation.
+    pub fn redo(&mut self) -> String {
+        match self.tree.selected_child(self.node) {
+            Some(child) if self.replay(child) => self.uci_history.last().cloned().unwrap_or_default(),
+            _ => String::new(),
+        }
     }
 
-    /// Get the Zobrist hash
-    pub fn hash(&self) -> u64 {
-        self.position.hash()
+    /// Whether `redo` has a move to replay
+    pub fn can_redo(&self) -> bool {
+        self.tree.selected_child(self.node).is_some()
     }
 
-    /// Get current turn: "w" or "b"
-    pub fn turn(&self) -> String {
-        match self.position.side_to_move() {
-            types::Color::White => "w".to_string(),
-            types::Color::Black => "b".to_string(),
-        }
+    /// Plies played to reach the current position (0 = game start)
+    pub fn current_ply(&self) -> usize {
+        self.move_history.len()
     }
 
-    /// Reset to starting position
-    pub fn reset(&mut self) {
-        self.position = Position::starting_position();
-        let hash = self.position.hash();
-        self.hash_history = vec![hash];
-        self.move_history.clear();
-        self.uci_history.clear();
+    /// Plies in the whole game, including those taken back that `redo` can replay
+    pub fn total_plies(&self) -> usize {
+        self.move_history.len() + self.tree.line_after(self.node).count()
     }
 
-    /// Load a position from FEN, clearing history
-    pub fn load_fen(&mut self, fen: &str) -> bool {
-        match Position::from_fen(fen) {
-            Ok(pos) => {
-                let hash = pos.hash();
-                self.position = pos;
-                self.hash_history = vec![hash];
-                self.move_history.clear();
-                self.uci_history.clear();
-                true
+    /// Jump to the position after `ply` plies, undoing or redoing as needed;
+    /// the moves beyond it stay available. Returns false if the game is shorter.
+    pub fn goto_ply(&mut self, ply: usize) -> bool {
+        if ply > self.total_plies() {
+            return false;
+        }
+        while self.current_ply() > ply {
+            self.undo();
+        }
+        while self.current_ply() < ply {
+            if self.redo().is_empty() {
+                return false;
             }
-            Err(_) => false,
         }
+        true
     }
 
-    /// Get move history as UCI strings (JSON array)
-    pub fn history(&self) -> String {
-        use std::fmt::Write;
-        let mut result = String::from("[");
-        for (i, uci) in self.uci_history.iter().enumerate() {
-            if i > 0 {
-                result.push(',');
+    /// FEN of the position after `ply` plies, without moving there.
+    /// Undefined if the game is shorter.
+    pub fn fen_at(&self, ply: usize) -> Option<String> {
+        let current = self.current_ply();
+        if ply > self.total_plies() {
+            return None;
+        }
+        let mut pos = self.position.clone();
+        for (m, undo) in self.move_history.iter().skip(ply).rev() {
+            Self::unplay(&mut pos, *m, undo);
+        }
+        for m in self.tree.line_after(self.node).take(ply.saturating_sub(current)).map(|c| self.tree.mv(c)) {
+            if m == types::Move::NULL {
+                pos.make_null_move();
+            } else {
+                pos.make_move(m)?;
             }
-            write!(result, "\"{}\"", uci).unwrap();
         }
-        result.push(']');
-        result
+        Some(pos.to_fen())
     }
 
-    /// Get the board as a JSON string representing 8x8 array
-    /// Each cell is null or {\"type\":\"P\",\"color\":\"w\"} etc.
-    pub fn get_board_json(&self) -> String {
-        let mut result = String::from("[");
-        for rank in (0..8u8).rev() {  // rank 7 (row 0) down to rank 0 (row 7)
-            if rank < 7 {
-                result.push(',');
-            }
-            result.push('[');
-            for file in 0..8u8 {
-                if file > 0 {
-                    result.push(',');
-                }
-                let sq = types::Square::from_file_rank(file, rank);
-                match self.position.piece_on(sq) {
-                    Some((color, piece_type)) => {
-                        let c = match color {
-                            types::Color::White => "w",
-                            types::Color::Black => "b",
-                        };
-                        let p = match piece_type {
-                            types::PieceType::Pawn => "P",
-                            types::PieceType::Knight => "N",
-                            types::PieceType::Bishop => "B",
-                            types::PieceType::Rook => "R",
-                            types::PieceType::Queen => "Q",
-                            types::PieceType::King => "K",
-                        };
-                        result.push_str(&format!("{{\"type\":\"{}\",\"color\":\"{}\"}}", p, c));
-                    }
-                    None => result.push_str("null"),
-                }
-            }
-            result.push(']');
-        }
-        result.push(']');
-        result
+    /// UCI moves stored after the current position, the main continuation first
+    pub fn continuations(&self) -> JsValue {
+        to_js(&self.continuations_list())
     }
 
-    /// Get piece at a specific square (file 0-7, rank 0-7 where rank 0 = row 7 in display)
-    /// Returns empty string if no piece, or "wP", "bN", etc.
-    pub fn piece_at(&self, file: u8, rank: u8) -> String {
-        let sq = types::Square::from_file_rank(file, rank);
-        match self.position.piece_on(sq) {
-            Some((color, piece_type)) => {
-                let c = match color {
-                    types::Color::White => 'w',
-                    types::Color::Black => 'b',
-                };
-                let p = match piece_type {
-                    types::PieceType::Pawn => 'P',
-                    types::PieceType::Knight => 'N',
-                    types::PieceType::Bishop => 'B',
-                    types::PieceType::Rook => 'R',
-                    types::PieceType::Queen => 'Q',
-                    types::PieceType::King => 'K',
-                };
-                format!("{}{}", c, p)
-            }
-            None => String::new(),
-        }
+    /// UCI moves stored as alternatives to the last move (itself included),
+    /// the main line first. Empty at the start of the game.
+    pub fn siblings(&self) -> JsValue {
+        to_js(&self.siblings_list())
     }
 
-    /// Check if current side is in check
-    pub fn is_in_check(&self) -> bool {
-        self.position.is_in_check(self.position.side_to_move())
+    /// Index of the last move among `siblings` (0 = main line, or at the start)
+    pub fn variation_index(&self) -> usize {
+        self.tree
+            .parent(self.node)
+            .and_then(|parent| self.tree.children(parent).iter().position(|&c| c == self.node))
+            .unwrap_or(0)
     }
 
-    /// Check if current side is in checkmate
-    pub fn is_checkmate(&self) -> bool {
-        self.position.is_checkmate()
+    /// Play the `index`-th of `continuations`. Returns false if there is none.
+    pub fn goto_continuation(&mut self, index: usize) -> bool {
+        match self.tree.children(self.node).get(index) {
+            Some(&child) => self.replay(child),
+            None => false,
+        }
     }
 
-    /// Check if current side is in stalemate
-    pub fn is_stalemate(&self) -> bool {
-        self.position.is_stalemate()
+    /// Replace the last move with the `index`-th of `siblings`, switching to
+    /// that variation. Returns false if there is none.
+    pub fn goto_sibling(&mut self, index: usize) -> bool {
+        let Some(parent) = self.tree.parent(self.node) else { return false };
+        let Some(&sibling) = self.tree.children(parent).get(index) else { return false };
+        self.undo();
+        self.replay(sibling)
     }
 
-    /// Check for insufficient material
-    pub fn is_insufficient_material(&self) -> bool {
-        self.position.is_insufficient_material()
+    /// Whether the moves played so far follow the main line
+    pub fn is_mainline(&self) -> bool {
+        self.tree.is_mainline(self.node)
     }
 
-    /// Check 50-move rule
-    pub fn is_fifty_move_draw(&self) -> bool {
-        self.position.is_fifty_move_draw()
+    /// Make the line leading to the current position the main line. Returns
+    /// false if it already was.
+    pub fn promote_variation(&mut self) -> bool {
+        self.tree.promote(self.node)
     }
 
-    /// Check threefold repetition using hash history
-    pub fn is_threefold_repetition(&self) -> bool {
-        let current_hash = self.position.hash();
-        let count = self.hash_history.iter().filter(|&&h| h == current_hash).count();
-        count >= 3
+    /// Delete the last move and every move after it, in all variations, and
+    /// go back one ply. Returns false at the start of the game.
+    pub fn delete_variation(&mut self) -> bool {
+        let node = self.node;
+        if self.undo().is_empty() {
+            return false;
+        }
+        self.tree.remove(node);
+        true
     }
 
-    /// Check if the game is drawn (any draw condition including repetition)
-    pub fn is_draw(&self) -> bool {
-        self.position.is_stalemate()
-            || self.position.is_insufficient_material()
-            || self.position.is_fifty_move_draw()
-            || self.is_threefold_repetition()
+    /// UCI moves of the main line from the start, wherever the game is now
+    pub fn mainline(&self) -> JsValue {
+        to_js(&self.mainline_list())
     }
 
-    /// Check if the game is over (checkmate or any draw)
-    pub fn is_game_over(&self) -> bool {
-        self.is_checkmate() || self.is_draw()
+    /// Set the comment on the move at `ply` of the current line (1 = first
+    /// move, 0 = before the game); empty text removes it. Returns false past
+    /// the end of the line.
+    pub fn set_comment(&mut self, ply: usize, text: &str) -> bool {
+        let Some(node) = self.line_node(ply) else { return false };
+        self.tree.set_comment(node, text.trim().to_string());
+        true
     }
 
-    /// Get full game status including repetition detection
-    /// Returns: "checkmate", "stalemate", "insufficient_material", "fifty_move",
-    ///          "threefold_repetition", or "playing"
-    pub fn status(&self) -> String {
-        if self.position.is_checkmate() {
-            return "checkmate".to_string();
+    /// Comment on the move at `ply`; undefined if it has none
+    pub fn comment(&self, ply: usize) -> Option<String> {
+        self.line_node(ply).map(|node| self.tree.comment(node)).filter(|c| !c.is_empty()).map(str::to_string)
+    }
+
+    /// Set the NAGs of the move at `ply`, as glyphs or "$n" codes separated by
+    /// spaces ("!?", "?? $18"); empty clears them. Returns false (changing
+    /// nothing) for an unknown glyph or a ply past the end of the line.
+    pub fn set_nags(&mut self, ply: usize, nags: &str) -> bool {
+        let Some(node) = self.line_node(ply).filter(|&n| n != gametree::ROOT) else { return false };
+        let Some(codes) = nags.split_whitespace().map(pgn::nag_from_str).collect::<Option<Vec<u8>>>() else {
+            return false;
+        };
+        self.tree.set_nags(node, codes);
+        true
+    }
+
+    /// Comments and NAGs of each move in the current line, including moves
+    /// `redo` can replay: [{ply:1,san:"e4",comment:null,nags:[1],glyphs:["!"]},...]
+    pub fn annotations(&self) -> JsValue {
+        to_js(&self.annotations_list())
+    }
+
+    /// The main line of the game as PGN, with its comments and NAGs. The
+    /// result is the game's `result` when the current position ends the
+    /// main line; otherwise "*" unless the line ends in mate or a rules draw.
+    pub fn pgn(&self) -> String {
+        let nodes = self.tree.mainline_nodes();
+        let mut pos = self.start_position();
+        let start_fen = pos.to_fen();
+        let mut game = pgn::PgnGame::default();
+        for &node in &nodes {
+            let m = self.tree.mv(node);
+            if m == types::Move::NULL {
+                game.moves.push("--".to_string());
+                pos.make_null_move();
+            } else {
+                game.moves.push(m.to_san(&pos));
+                pos.make_move(m);
+            }
         }
-        if self.position.is_stalemate() {
-            return "stalemate".to_string();
+        for (ply, &node) in std::iter::once(&gametree::ROOT).chain(&nodes).enumerate() {
+            if !self.tree.comment(node).is_empty() {
+                game.comments.push((ply, self.tree.comment(node).to_string()));
+            }
+            game.nags.extend(self.tree.nags(node).iter().map(|&n| (ply, n)));
         }
-        if self.position.is_insufficient_material() {
-            return "insufficient_material".to_string();
+
+        // At the end of the main line, the game's own result; elsewhere, the board's
+        game.result = if self.tree.is_mainline(self.node) && self.tree.children(self.node).is_empty() {
+            self.result()
+        } else {
+            decided_result(&pos).to_string()
+        };
+        for (name, value) in [("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"), ("White", "?"), ("Black", "?")] {
+            game.headers.push((name.to_string(), value.to_string()));
         }
-        if self.position.is_fifty_move_draw() {
-            return "fifty_move".to_string();
+        game.headers.push(("Result".to_string(), game.result.clone()));
+        if pos.variant() != variant::Variant::Standard {
+            game.headers.push(("Variant".to_string(), pos.variant().name().to_string()));
         }
-        if self.is_threefold_repetition() {
-            return "threefold_repetition".to_string();
+        if start_fen != initial_fen(pos.variant()) {
+            game.headers.push(("SetUp".to_string(), "1".to_string()));
+            game.headers.push(("FEN".to_string(), start_fen));
         }
-        "playing".to_string()
+        game.to_pgn()
     }
 
-    /// Get legal moves as UCI strings
-    pub fn legal_moves(&self) -> Vec<JsValue> {
-        let mut pos = self.position.clone();
-        let moves = generate_legal_moves(&mut pos);
-        moves.iter()
-            .map(|m| JsValue::from_str(&m.to_uci()))
-            .collect()
+    /// Pass the turn without moving, to see what the opponent threatens.
+    /// Recorded in the history as "0000" (SAN "--"). Returns false if the
+    /// side to move is in check.
+    pub fn make_null_move(&mut self) -> bool {
+        if self.position.is_in_check(self.position.side_to_move()) {
+            return false;
+        }
+        let undo = position::UndoInfo {
+            captured: None,
+            castling: self.position.castling_rights(),
+            en_passant: self.position.en_passant_square(),
+            halfmove_clock: self.position.halfmove_clock(),
+            hash: self.position.hash(),
+            pawn_hash: self.position.pawn_hash(),
+            checks_given: [self.position.checks_given(types::Color::White), self.position.checks_given(types::Color::Black)],
+            lost: self.position.lost_counts(),
+        };
+        self.position.make_null_move();
+        self.hash_history.push(self.position.hash());
+        self.move_history.push((types::Move::NULL, undo));
+        self.uci_history.push("0000".to_string());
+        self.node = self.tree.enter(self.node, types::Move::NULL);
+        true
+    }
+
+    /// Take back a null move. Returns false (and does nothing) if the last
+    /// entry in the history is a real move.
+    pub fn undo_null(&mut self) -> bool {
+        match self.move_history.last() {
+            Some((m, _)) if *m == types::Move::NULL => {
+                self.undo();
+                true
+            }
+            _ => false,
+        }
     }
 
-    /// Get best move via search
-    pub fn best_move(&self, depth: u8) -> Option<String> {
-        let mut pos = self.position.clone();
-        let (best_move, _, _) = search(&mut pos, depth);
-        best_move.map(|m| m.to_uci())
+    /// The draw offer, if made at this ply or the one before (the offerer
+    /// may still make a move; the opponent's move declines it)
+    fn pending_draw_offer(&self) -> Option<types::Color> {
+        let (color, ply) = self.draw_offer?;
+        (ply..=ply + 1).contains(&self.current_ply()).then_some(color)
     }
 
-    /// Get the number of moves played (hash history length - 1)
-    pub fn move_count(&self) -> usize {
-        self.hash_history.len() - 1
+    /// Credit the increment to the side that just moved, unless its flag
+    /// has already fallen.
+    fn press_clock(&mut self) {
+        let mover = self.position.side_to_move().flip() as usize;
+        if let Some(clock) = self.clock.as_mut().filter(|c| c.remaining[mover] > 0.0) {
+            clock.remaining[mover] += clock.increment_ms;
+        }
     }
 
-    /// Evaluate current position
-    pub fn eval(&self) -> i32 {
-        evaluate(&self.position)
+    /// Give both sides the base time again, keeping the time control
+    fn restart_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            *clock = Clock::new(clock.base_ms, clock.increment_ms);
+        }
     }
 
-    /// Time-limited search. Searches deeper until time budget is exhausted.
-    /// Returns JSON: {"bestMove":"e2e4","score":15,"depth":6,"nodes":123456,"timeMs":987.5,"nps":125000,"ttHits":1234}
-    pub fn search_timed(&self, max_ms: f64) -> String {
-        let mut pos = self.position.clone();
-        let (best_move, score, stats) = search::search_timed(&mut pos, max_ms, 0);
-        let mv_str = best_move.map_or("null".to_string(), |m| format!("\"{}\"", m.to_uci()));
-        format!(
-            "{{\"bestMove\":{},\"score\":{},\"depth\":{},\"nodes\":{},\"timeMs\":{:.1},\"nps\":{},\"ttHits\":{}}}",
-            mv_str, score, stats.depth, stats.nodes, stats.time_ms, stats.nps, stats.tt_hits
-        )
+    /// "timeout" if a side has run out of time, or
+    /// "timeout_vs_insufficient_material" if it has but the opponent has too
+    /// little material to win
+    fn timeout(&self) -> Option<&'static str> {
+        let flagged = self.flagged()?;
+        Some(if self.position.has_mating_material(flagged.flip()) { "timeout" } else { "timeout_vs_insufficient_material" })
     }
 
-    /// Fixed-depth search returning full stats as JSON.
-    pub fn search_depth(&self, depth: u8) -> String {
-        let mut pos = self.position.clone();
-        let start = {
-            #[cfg(target_arch = "wasm32")]
+    /// Side whose time has run out (the side to move, or the side that moved
+    /// after running out)
+    fn flagged(&self) -> Option<types::Color> {
+        let clock = self.clock.as_ref()?;
+        let side = self.position.side_to_move();
+        [side, side.flip()].into_iter().find(|&c| clock.remaining[c as usize] <= 0.0)
+    }
+
+    /// Node of the move at `ply` of the current line (the root for 0), looking
+    /// ahead along the line `redo` follows
+    fn line_node(&self, ply: usize) -> Option<gametree::NodeId> {
+        let current = self.current_ply();
+        if ply > current {
+            return self.tree.line_after(self.node).nth(ply - current - 1);
+        }
+        let mut node = self.node;
+        for _ in ply..current {
+            node = self.tree.parent(node)?;
+        }
+        Some(node)
+    }
+
+    /// Position before the first move of the game
+    fn start_position(&self) -> Position {
+        let mut pos = self.position.clone();
+        for (m, undo) in self.move_history.iter().rev() {
+            Self::unplay(&mut pos, *m, undo);
+        }
+        pos
+    }
+
+    /// Play the move leading to `child`, a child of the current node
+    fn replay(&mut self, child: gametree::NodeId) -> bool {
+        let m = self.tree.mv(child);
+        if m == types::Move::NULL { self.make_null_move() } else { self.play(m) }
+    }
+
+    /// Reverse one history entry, which may be a null move.
+    fn unplay(pos: &mut Position, m: types::Move, undo: &position::UndoInfo) {
+        if m == types::Move::NULL {
+            pos.unmake_null_move(undo.en_passant, undo.hash);
+        } else {
+            pos.unmake_move(m, undo);
+        }
+    }
+
+    /// Get the FEN of the current position
+    pub fn fen(&self) -> String {
+        self.position.to_fen()
+    }
+
+    /// Get the Zobrist hash (a BigInt in JavaScript; see `hash_hex` for a string key)
+    pub fn hash(&self) -> u64 {
+        self.position.hash()
+    }
+
+    /// Zobrist hash as 16 lowercase hex digits, e.g. for keying caches by position
+    pub fn hash_hex(&self) -> String {
+        hash_hex(self.position.hash())
+    }
+
+    /// High 32 bits of the Zobrist hash, a plain JS number
+    pub fn hash_hi(&self) -> u32 {
+        (self.position.hash() >> 32) as u32
+    }
+
+    /// Low 32 bits of the Zobrist hash, a plain JS number
+    pub fn hash_lo(&self) -> u32 {
+        self.position.hash() as u32
+    }
+
+    /// Whether the current position is the one in `fen`: same pieces, side
+    /// to move, castling rights and en passant square. Move counters don't
+    /// matter. False for an invalid FEN.
+    pub fn same_position(&self, fen: &str) -> bool {
+        Position::from_fen(fen).is_ok_and(|mut pos| {
+            pos.set_variant(self.position.variant());
+            pos.hash() == self.position.hash()
+        })
+    }
+
+    /// Whether two games are at the same position (as `same_position`)
+    pub fn same_position_as(&self, other: &GameState) -> bool {
+        self.position.hash() == other.position.hash()
+    }
+
+    /// Get current turn: "w" or "b"
+    pub fn turn(&self) -> String {
+        match self.position.side_to_move() {
+            types::Color::White => "w".to_string(),
+            types::Color::Black => "b".to_string(),
+        }
+    }
+
+    /// Reset to starting position (a new game also starts with an empty TT)
+    pub fn reset(&mut self) {
+        self.position = Position::starting_position();
+        let hash = self.position.hash();
+        self.hash_history = vec![hash];
+        self.move_history.clear();
+        self.tree = gametree::GameTree::new();
+        self.node = gametree::ROOT;
+        self.uci_history.clear();
+        #[cfg(feature = "engine")]
+        self.tt.clear();
+        self.draw_offer = None;
+        self.ending = None;
+        self.restart_clock();
+    }
+
+    /// Load a position from FEN, clearing history
+    pub fn load_fen(&mut self, fen: &str) -> bool {
+        self.try_load_fen(fen).is_ok()
+    }
+
+    /// `load_fen`, throwing a BadFen error object naming the field at fault
+    pub fn try_load_fen(&mut self, fen: &str) -> Result<(), EngineError> {
+        let pos = Position::from_fen(fen).map_err(|e| EngineError::new(ErrorKind::BadFen, e))?;
+        self.set_start(pos);
+        Ok(())
+    }
+
+    /// Load the first game of a PGN text (tags, movetext with comments, NAGs
+    /// and variations) and play its main line with its comments and NAGs,
+    /// starting from the FEN tag if there is one. A Variant tag switches to that variant's rules; without
+    /// one the current variant is kept. On error the game is left unchanged
+    /// and the error says which move failed.
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), EngineError> {
+        let game = pgn::parse_pgn(pgn).map_err(|e| EngineError::new(ErrorKind::BadPgn, e))?;
+        let start = match game.start_fen() {
+            Some(fen) => Position::from_fen(fen)
+                .map_err(|e| EngineError::new(ErrorKind::BadFen, format!("Invalid FEN tag: {}", e)).with_field("FEN"))?,
+            None => Position::starting_position(),
+        };
+        let rules = match game.variant() {
+            Some(name) => variant::Variant::from_name(name).ok_or_else(|| {
+                EngineError::new(ErrorKind::BadOption, format!("Unsupported variant: {}", name)).with_field("Variant")
+            })?,
+            None => self.position.variant(),
+        };
+
+        let saved = (
+            self.position.clone(),
+            self.hash_history.clone(),
+            self.move_history.clone(),
+            self.uci_history.clone(),
+            self.tree.clone(),
+            self.node,
+        );
+        self.set_start(start);
+        if rules != self.position.variant() {
+            self.position.set_variant(rules);
+            #[cfg(feature = "engine")]
+            self.tt.clear();
+        }
+        for (ply, san) in game.moves.iter().enumerate() {
+            let fullmove = self.position.fullmove_number();
+            let dots = if self.position.side_to_move() == types::Color::White { "." } else { "..." };
+            let result = san::parse_san(&self.position, san).and_then(|m| if self.play(m) { Ok(()) } else { Err("Illegal move") });
+            if let Err(e) = result {
+                (self.position, self.hash_history, self.move_history, self.uci_history, self.tree, self.node) = saved;
+                let mut error = EngineError::from_san(e, san);
+                error.message = format!("{} at {}{} {} (ply {})", e, fullmove, dots, san, ply + 1);
+                return Err(error);
+            }
+        }
+        for (ply, text) in &game.comments {
+            self.set_comment(*ply, text);
+        }
+        for ply in 1..=game.moves.len() {
+            if let Some(node) = self.line_node(ply) {
+                self.tree.set_nags(node, game.nags_at(ply));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the position of an EPD line ("<fen fields> bm Nf3; id \"x\";"),
+    /// clearing history. Move counters come from its hmvc/fmvn operations.
+    pub fn load_epd(&mut self, epd: &str) -> Result<(), EngineError> {
+        let record = epd::parse_epd(epd).map_err(|e| EngineError::new(ErrorKind::BadEpd, e))?;
+        let pos = Position::from_fen(&record.fen()).map_err(|e| EngineError::new(ErrorKind::BadFen, e))?;
+        self.set_start(pos);
+        Ok(())
+    }
+
+    /// Start over from `pos` with an empty history, keeping the variant and
+    /// promotion rules.
+    fn set_start(&mut self, mut pos: Position) {
+        // A FEN with check counts starts a three-check game
+        if pos.variant() == variant::Variant::ThreeCheck && self.position.variant() != pos.variant() {
+            #[cfg(feature = "engine")]
+            self.tt.clear();
+        } else {
+            pos.set_variant(self.position.variant());
+        }
+        pos.set_promotion_rules(self.position.promotion_rules());
+        self.hash_history = vec![pos.hash()];
+        self.position = pos;
+        self.move_history.clear();
+        self.tree = gametree::GameTree::new();
+        self.node = gametree::ROOT;
+        self.uci_history.clear();
+        #[cfg(feature = "engine")]
+        {
+            self.ponder = None;
+        }
+        self.draw_offer = None;
+        self.ending = None;
+        self.restart_clock();
+    }
+
+    /// Get move history as an array of UCI strings
+    pub fn history(&self) -> JsValue {
+        to_js(&self.history_list())
+    }
+
+    /// Get move history in standard algebraic notation, as an array
+    /// like ["e4","e5","Nf3","Nc6","Bb5"]
+    pub fn history_san(&self) -> JsValue {
+        to_js(&self.history_san_list())
+    }
+
+    /// Get the board as an 8x8 array, row 0 = rank 8.
+    /// Each cell is null or {type:"P",color:"w"} etc.
+    pub fn get_board_json(&self) -> JsValue {
+        to_js(&self.board())
+    }
+
+    /// Get piece at a specific square (file 0-7, rank 0-7 where rank 0 = row 7 in display)
+    /// Returns empty string if no piece, or "wP", "bN", etc.
+    pub fn piece_at(&self, file: u8, rank: u8) -> String {
+        let sq = types::Square::from_file_rank(file, rank);
+        match self.position.piece_on(sq) {
+            Some((color, piece_type)) => {
+                let c = match color {
+                    types::Color::White => 'w',
+                    types::Color::Black => 'b',
+                };
+                let p = match piece_type {
+                    types::PieceType::Pawn => 'P',
+                    types::PieceType::Knight => 'N',
+                    types::PieceType::Bishop => 'B',
+                    types::PieceType::Rook => 'R',
+                    types::PieceType::Queen => 'Q',
+                    types::PieceType::King => 'K',
+                };
+                format!("{}{}", c, p)
+            }
+            None => String::new(),
+        }
+    }
+
+    /// The board as text, one rank per line with rank numbers and file
+    /// letters, in FEN letters ("PNBRQK" for White, lowercase for Black)
+    /// and "." for empty squares. With `flip`, drawn from Black's side.
+    pub fn ascii(&self, flip: bool) -> String {
+        self.render(flip, '.', |color, piece| {
+            let letter = piece_letter(piece).chars().next().unwrap();
+            if color == types::Color::White { letter } else { letter.to_ascii_lowercase() }
+        })
+    }
+
+    /// `ascii` with chess symbols (♔♕♖♗♘♙ for White, ♚♛♜♝♞♟ for Black)
+    /// and "·" for empty squares
+    pub fn unicode(&self, flip: bool) -> String {
+        self.render(flip, '·', piece_symbol)
+    }
+
+    /// Squares of the pieces attacking a square (file 0-7, rank 0-7), as an
+    /// array like ["d4","f3"]. `color` is "w" or "b" for one side's
+    /// attackers, anything else for both.
+    pub fn attackers(&self, file: u8, rank: u8, color: &str) -> JsValue {
+        to_js(&self.attackers_list(file, rank, color))
+    }
+
+    /// How many pieces of one side attack each square, for threat heatmaps:
+    /// {counts:[[0,1,...],...],attacked:"0x0000000000ff0000"}. `counts` is 8x8
+    /// like `get_board_json` (row 0 = rank 8); `attacked` is the bitboard of
+    /// squares attacked at least once, bit 0 = a1. `color` is "w" or "b" for
+    /// one side's attacks, anything else for both.
+    pub fn attack_map(&self, color: &str) -> JsValue {
+        to_js(&self.attack_map_report(color))
+    }
+
+    /// Squares of the pieces giving check to the side to move (array)
+    pub fn checkers(&self) -> JsValue {
+        to_js(&self.checkers_list())
+    }
+
+    /// Squares of pinned pieces (array). `color` is "w" or "b" for one
+    /// side's pieces, anything else for both.
+    pub fn pinned(&self, color: &str) -> JsValue {
+        to_js(&self.pinned_list(color))
+    }
+
+    /// Check if current side is in check
+    pub fn is_in_check(&self) -> bool {
+        self.position.is_in_check(self.position.side_to_move())
+    }
+
+    /// Check if current side is in checkmate
+    pub fn is_checkmate(&self) -> bool {
+        self.position.is_checkmate()
+    }
+
+    /// Check if current side is in stalemate
+    pub fn is_stalemate(&self) -> bool {
+        self.position.is_stalemate()
+    }
+
+    /// Check for insufficient material
+    pub fn is_insufficient_material(&self) -> bool {
+        self.position.is_insufficient_material()
+    }
+
+    /// Check for a dead position: no sequence of legal moves leads to mate
+    pub fn is_dead_position(&self) -> bool {
+        self.position.is_dead_position()
+    }
+
+    /// Check 50-move rule
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.position.is_fifty_move_draw()
+    }
+
+    /// Check threefold repetition using hash history
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// How many times the current position has occurred in the game, this
+    /// time included (1 = first time; 2 = one repetition away from a draw)
+    pub fn repetition_count(&self) -> usize {
+        let current_hash = self.position.hash();
+        self.hash_history.iter().filter(|&&h| h == current_hash).count()
+    }
+
+    /// FEN of every position in the game so far, from the start to the
+    /// current one, as an array
+    pub fn fen_history(&self) -> JsValue {
+        to_js(&self.fen_history_list())
+    }
+
+    /// Check if the game is drawn (any draw condition including repetition)
+    pub fn is_draw(&self) -> bool {
+        self.position.is_stalemate()
+            || self.position.is_dead_position()
+            || self.position.is_fifty_move_draw()
+            || self.is_threefold_repetition()
+            || self.timeout() == Some("timeout_vs_insufficient_material")
+            || self.ending.is_some_and(|(result, _)| result == "1/2-1/2")
+    }
+
+    /// Check if the game is over (checkmate, any draw, a variant win, a loss
+    /// on time, or resignation)
+    pub fn is_game_over(&self) -> bool {
+        self.ending.is_some()
+            || self.position.variant().outcome(&self.position).is_some()
+            || self.is_checkmate()
+            || self.is_draw()
+            || self.timeout().is_some()
+    }
+
+    /// Get full game status including repetition detection
+    /// Returns: "checkmate", "stalemate", "insufficient_material", "dead_position",
+    ///          "fifty_move", "threefold_repetition", "timeout" (the side to
+    ///          move lost on time), "timeout_vs_insufficient_material" (its flag
+    ///          fell, but the opponent can't win), or "playing", or the name of
+    ///          a variant's own win condition; "resignation" or "draw_agreement"
+    ///          for games ended off the board
+    pub fn status(&self) -> String {
+        if let Some((_, status)) = self.ending {
+            return status.to_string();
+        }
+        if let Some((_, status)) = self.position.variant().outcome(&self.position) {
+            return status.to_string();
+        }
+        if self.position.is_checkmate() {
+            return "checkmate".to_string();
+        }
+        if self.position.is_stalemate() {
+            return "stalemate".to_string();
+        }
+        if self.position.is_insufficient_material() {
+            return "insufficient_material".to_string();
+        }
+        if self.position.is_dead_position() {
+            return "dead_position".to_string();
+        }
+        if self.position.is_fifty_move_draw() {
+            return "fifty_move".to_string();
+        }
+        if self.is_threefold_repetition() {
+            return "threefold_repetition".to_string();
+        }
+        if let Some(status) = self.timeout() {
+            return status.to_string();
+        }
+        "playing".to_string()
+    }
+
+    /// Result of the game: "1-0", "0-1", "1/2-1/2", or "*" while it goes on.
+    /// `status` says how it ended.
+    pub fn result(&self) -> String {
+        if let Some((result, _)) = self.ending {
+            return result.to_string();
+        }
+        let winner = match self.position.variant().outcome(&self.position) {
+            Some((color, _)) => Some(color),
+            None if self.position.is_checkmate() => Some(self.position.side_to_move().flip()),
+            None if self.timeout() == Some("timeout") => self.flagged().map(types::Color::flip),
+            None => None,
+        };
+        match winner {
+            Some(types::Color::White) => "1-0",
+            Some(types::Color::Black) => "0-1",
+            None if self.is_draw() => "1/2-1/2",
+            None => "*",
+        }
+        .to_string()
+    }
+
+    /// The side to move offers a draw, open until the opponent's next move.
+    /// Returns false if the game is over or an offer is already pending.
+    pub fn offer_draw(&mut self) -> bool {
+        if self.is_game_over() || self.pending_draw_offer().is_some() {
+            return false;
+        }
+        self.draw_offer = Some((self.position.side_to_move(), self.current_ply()));
+        true
+    }
+
+    /// Who offered the pending draw: "w" or "b"; undefined if none
+    pub fn draw_offered(&self) -> Option<String> {
+        self.pending_draw_offer().map(|c| color_letter(c).to_string())
+    }
+
+    /// Accept the pending draw offer, ending the game. Returns false if there is none.
+    pub fn accept_draw(&mut self) -> bool {
+        if self.pending_draw_offer().is_none() || self.is_game_over() {
+            return false;
+        }
+        self.draw_offer = None;
+        self.ending = Some(("1/2-1/2", "draw_agreement"));
+        true
+    }
+
+    /// Turn down the pending draw offer. Returns false if there is none.
+    pub fn decline_draw(&mut self) -> bool {
+        let pending = self.pending_draw_offer().is_some();
+        self.draw_offer = None;
+        pending
+    }
+
+    /// "w" or "b" resigns. Returns false if the game is already over or the
+    /// color isn't valid.
+    pub fn resign(&mut self, color: &str) -> bool {
+        let result = match color {
+            "w" => "0-1",
+            "b" => "1-0",
+            _ => return false,
+        };
+        if self.is_game_over() {
+            return false;
+        }
+        self.draw_offer = None;
+        self.ending = Some((result, "resignation"));
+        true
+    }
+
+    /// Play on a clock: each side gets `base_ms`, plus `increment_ms` after
+    /// each move made with `make_move_uci`/`make_move_san`. Undo and the other
+    /// navigation methods leave the clock alone; a new game restarts it.
+    pub fn set_clock(&mut self, base_ms: f64, increment_ms: f64) {
+        self.clock = Some(Clock::new(base_ms.max(0.0), increment_ms.max(0.0)));
+    }
+
+    /// Stop playing on a clock
+    pub fn clear_clock(&mut self) {
+        self.clock = None;
+    }
+
+    pub fn has_clock(&self) -> bool {
+        self.clock.is_some()
+    }
+
+    /// Charge the side to move for `ms` of thinking time. Returns false if
+    /// there is no clock.
+    pub fn record_time_used(&mut self, ms: f64) -> bool {
+        let side = self.position.side_to_move() as usize;
+        let Some(clock) = &mut self.clock else { return false };
+        clock.remaining[side] = (clock.remaining[side] - ms.max(0.0)).max(0.0);
+        true
+    }
+
+    /// Charge the side to move for the time since the previous call (e.g.
+    /// with `Date.now()`); the first call only starts counting. Returns false
+    /// if there is no clock.
+    pub fn record_time_at(&mut self, timestamp_ms: f64) -> bool {
+        let Some(clock) = &mut self.clock else { return false };
+        let used = clock.last_timestamp.map_or(0.0, |t| timestamp_ms - t);
+        clock.last_timestamp = Some(timestamp_ms);
+        self.record_time_used(used)
+    }
+
+    /// Milliseconds left for "w" or "b"; undefined without a clock
+    pub fn time_left(&self, color: &str) -> Option<f64> {
+        let side = match color {
+            "w" => types::Color::White,
+            "b" => types::Color::Black,
+            _ => return None,
+        };
+        self.clock.as_ref().map(|c| c.remaining[side as usize])
+    }
+
+    /// Get legal moves as UCI strings
+    pub fn legal_moves(&self) -> Vec<JsValue> {
+        let moves = generate_legal_moves(&self.position);
+        moves.iter()
+            .map(|m| JsValue::from_str(&m.to_uci()))
+            .collect()
+    }
+
+    /// Legal moves with what a UI needs to show them, one object per move:
+    /// {uci:"e1g1",from:"e1",to:"g1",piece:"K",color:"w",captured:null,promotion:null,
+    /// isCapture:false,isCastle:true,isEnPassant:false,isPromotion:false,san:"O-O",givesCheck:false}
+    pub fn legal_moves_detailed(&self) -> JsValue {
+        to_js(&self.legal_moves_report())
+    }
+
+    /// Where the piece on a square (file 0-7, rank 0-7) can legally go, for
+    /// click-to-move: [{to:"e8",file:4,rank:7,isCapture:false,promotions:["q","r","b","n"],mustPromote:true},...]
+    /// `promotions` is empty for ordinary moves. Empty if the square has no piece
+    /// of the side to move.
+    pub fn legal_moves_from(&self, file: u8, rank: u8) -> JsValue {
+        to_js(&self.legal_moves_from_report(file, rank))
+    }
+
+    /// The last move played, in the same form as `legal_moves_detailed`.
+    /// Null before the first move and after a null move.
+    pub fn last_move_info(&self) -> JsValue {
+        self.last_move_report().map_or(JsValue::NULL, |info| to_js(&info))
+    }
+
+    /// Get best move via search (reuses the game's TT)
+    #[cfg(feature = "engine")]
+    pub fn best_move(&mut self, depth: u8) -> Option<String> {
+        let mut pos = self.position.clone();
+        self.tt.new_search();
+        let (best_move, _, _) = search::search_with_tt(&mut pos, depth, &mut self.tt, &self.personality.search_options());
+        best_move.map(|m| m.to_uci())
+    }
+
+    /// Get the number of moves played (hash history length - 1)
+    pub fn move_count(&self) -> usize {
+        self.hash_history.len() - 1
+    }
+
+    /// Evaluate current position
+    #[cfg(feature = "engine")]
+    pub fn eval(&self) -> i32 {
+        evaluate(&self.position)
+    }
+
+    /// Evaluation split into terms per side, for explaining the score.
+    /// Returns an object: {total:130,tempo:10,phase:18,scale:1.0,kpkBonus:0,
+    /// white:{material:3900,pst:45,pawnStructure:-10,outposts:20,threats:0,bishopPair:30,mopUp:0,total:3985},
+    /// black:{...}}
+    /// Term values are centipawns in that side's favor; `total` is white's point of
+    /// view without tempo, after `scale` (the share kept in drawish endgames).
+    #[cfg(feature = "engine")]
+    pub fn eval_breakdown(&self) -> JsValue {
+        to_js(&self.eval_breakdown_report())
+    }
+
+    /// Time-limited search. Searches deeper until time budget is exhausted.
+    /// Returns an object: {bestMove:"e2e4",score:15,depth:6,seldepth:14,nodes:123456,qnodes:80000,
+    /// timeMs:987.5,nps:125000,ttProbes:43000,ttHits:1234,ttCutoffs:800,ttStores:42000,
+    /// betaCutoffs:9000,firstMoveCutoffRate:0.912,nullMoveTries:500,nullMoveCutoffs:300,
+    /// lmrReductions:7000,lmrResearches:400,wdl:[269,684,47],winProbability:0.611}
+    /// `wdl` is win/draw/loss per mille and `winProbability` the expected result
+    /// (draw = half), both for the side to move.
+    /// The game's TT is kept between calls, so later moves benefit from earlier searches.
+    #[cfg(feature = "engine")]
+    pub fn search_timed(&mut self, max_ms: f64) -> JsValue {
+        to_js(&self.search_timed_report(max_ms))
+    }
+
+    /// Search for a move in clock play: the side to move's time (`wtime` or
+    /// `btime`, ms) and increment give a soft and a hard limit (see `timeman`),
+    /// and the search ends early once the best move is stable. Same fields as
+    /// `search_timed`.
+    #[cfg(feature = "engine")]
+    pub fn search_clock(&mut self, wtime: f64, btime: f64, winc: f64, binc: f64) -> JsValue {
+        to_js(&self.search_clock_report(wtime, btime, winc, binc))
+    }
+
+    /// Fixed-depth search returning full stats (reuses the game's TT).
+    /// Same fields as `search_timed`.
+    #[cfg(feature = "engine")]
+    pub fn search_depth(&mut self, depth: u8) -> JsValue {
+        to_js(&self.search_depth_report(depth))
+    }
+
+    /// Search without freezing the page: the search runs in slices of
+    /// `ASYNC_STEP_NODES` nodes and yields to the event loop between them.
+    /// Resolves to the same object as `search_timed`. `max_depth` 0 = no depth
+    /// limit, `max_ms` 0 = no time limit; at least one must be set. Searches a
+    /// snapshot of the current position with its own TT (or the shared one,
+    /// see `use_shared_tt`), so the game can be played on meanwhile.
+    #[cfg(feature = "engine")]
+    pub fn search_async(&self, max_depth: u8, max_ms: f64) -> js_sys::Promise {
+        let options = self.personality.search_options();
+        let mut task = search::SteppedSearch::with_tt(&self.position, max_depth, max_ms, options, self.search_tt());
+        wasm_bindgen_futures::future_to_promise(async move {
+            if max_depth == 0 && max_ms <= 0.0 {
+                return Err(JsValue::from_str("search_async needs a depth or time limit"));
+            }
+            while !task.step(ASYNC_STEP_NODES) {
+                yield_to_event_loop().await?;
+            }
+            let (best_move, score, stats) = task.result();
+            Ok(to_js(&SearchReport::new(best_move, score, stats.depth, stats.time_ms, stats.nps, &stats)))
+        })
+    }
+
+    /// The `n` best moves at a fixed depth, for "show hint" buttons. Best first:
+    /// [{move:"e2e4",san:"e4",score:30,pv:["e2e4","e7e5","g1f3"],pvSan:["e4","e5","Nf3"]},...]
+    /// Scores are centipawns for the side to move. Reuses the game's TT.
+    #[cfg(feature = "engine")]
+    pub fn hints(&mut self, n: usize, depth: u8) -> JsValue {
+        to_js(&self.hints_report(n, depth))
+    }
+
+    /// `hints` searching deeper until `max_ms` is spent
+    #[cfg(feature = "engine")]
+    pub fn hints_timed(&mut self, n: usize, max_ms: f64) -> JsValue {
+        to_js(&self.hints_timed_report(n, max_ms))
+    }
+
+    /// Set the engine's playing style by name: "balanced" (default), "aggressive",
+    /// "positional", "materialist" or "gambiteer". Affects searches only; `eval`
+    /// stays the standard evaluation. Returns false for an unknown name.
+    #[cfg(feature = "engine")]
+    pub fn set_personality(&mut self, name: &str) -> bool {
+        match personality::Personality::from_name(name) {
+            Some(p) => {
+                if p != self.personality {
+                    // TT scores were computed with the old weights
+                    self.personality = p;
+                    self.tt.clear();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Name of the current playing style
+    #[cfg(feature = "engine")]
+    pub fn personality(&self) -> String {
+        self.personality.name().to_string()
+    }
+
+    /// Play the rest of the game under a variant's rules, by name ("standard").
+    /// The board and history are kept. Returns false for an unknown name.
+    pub fn set_variant(&mut self, name: &str) -> bool {
+        match variant::Variant::from_name(name) {
+            Some(v) => {
+                if v != self.position.variant() {
+                    // TT moves and scores were found under the old rules
+                    self.position.set_variant(v);
+                    #[cfg(feature = "engine")]
+                    self.tt.clear();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Name of the variant being played
+    pub fn variant(&self) -> String {
+        self.position.variant().name().to_string()
+    }
+
+    /// Set the pieces pawns may promote to, as letters: "qrbn" (standard),
+    /// "n" for knights only, "rbn" for no queens, "qrbnk" to allow kings.
+    /// A side with several kings must keep all of them out of check.
+    /// Returns false for an empty or invalid set.
+    pub fn set_promotion_rules(&mut self, pieces: &str) -> bool {
+        match variant::PromotionRules::parse(pieces) {
+            Some(rules) => {
+                if rules != self.position.promotion_rules() {
+                    self.position.set_promotion_rules(rules);
+                    #[cfg(feature = "engine")]
+                    self.tt.clear();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pieces pawns may promote to, as letters ("qrbn")
+    pub fn promotion_rules(&self) -> String {
+        self.position.promotion_rules().letters()
+    }
+
+    /// Pieces each side has had captured and not brought back by promoting,
+    /// which is the promotion pool in the "capture-limited" variant. Counted
+    /// from the position the game was set up in, as an object:
+    /// {w:{p:0,n:1,b:0,r:0,q:0},b:{...}}
+    pub fn lost_pieces(&self) -> JsValue {
+        to_js(&self.lost_pieces_report())
+    }
+
+    /// Checks each side has given, counted in the "three-check" variant, as
+    /// an object: {w:1,b:0}
+    pub fn checks_given(&self) -> JsValue {
+        to_js(&self.checks_given_report())
+    }
+
+    /// Clear the transposition table kept between searches.
+    #[cfg(feature = "engine")]
+    pub fn clear_tt(&mut self) {
+        self.tt.clear();
+    }
+
+    /// Search with a shared table from now on, instead of the game's own.
+    /// Changing the variant, promotion rules or personality clears it for
+    /// everyone using it.
+    #[cfg(feature = "engine")]
+    pub fn use_shared_tt(&mut self, shared: &SharedTT) {
+        self.tt = tt::TranspositionTable::attach(std::sync::Arc::clone(&shared.table));
+    }
+
+    /// Go back to a table of the game's own
+    #[cfg(feature = "engine")]
+    pub fn use_own_tt(&mut self) {
+        self.tt = tt::TranspositionTable::default_wasm();
+    }
+
+    /// Id of the shared table searched with, if any
+    #[cfg(feature = "engine")]
+    pub fn shared_tt_id(&self) -> Option<u32> {
+        self.tt.shared_table().map(|t| t.id())
+    }
+
+    /// Transposition table statistics as an object:
+    /// {capacity:262144,utilization:0.42,generation:3,hits:1234,misses:567,stores:890,collisions:12,hitRate:68.5}
+    #[cfg(feature = "engine")]
+    pub fn tt_stats(&self) -> JsValue {
+        to_js(&self.tt_stats_report())
+    }
+
+    // ── Pondering ──
+    // Without threads in the browser, pondering is done in slices: call
+    // `start_ponder` with the expected reply, then `ponder(ms)` repeatedly while the
+    // opponent thinks. The work lands in the game's TT. If the opponent plays the
+    // predicted move, `ponderhit(ms)` plays it and runs the real search, which
+    // picks up where pondering left off; otherwise call `stop_ponder`.
+
+    /// Start pondering on the predicted opponent move. Returns false if the move is illegal.
+    #[cfg(feature = "engine")]
+    pub fn start_ponder(&mut self, uci: &str) -> bool {
+        let m = match Self::resolve_uci(&self.position, uci) {
+            Some(m) => m,
+            None => return false,
+        };
+        if !self.position.is_legal(m) {
+            return false;
+        }
+        let mut position = self.position.clone();
+        if position.make_move(m).is_none() {
+            return false;
+        }
+        self.ponder = Some(PonderState {
+            base_hash: self.position.hash(),
+            uci: uci.to_string(),
+            position,
+        });
+        true
+    }
+
+    /// Whether a ponder search is active for the current position.
+    #[cfg(feature = "engine")]
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.as_ref().is_some_and(|p| p.base_hash == self.position.hash())
+    }
+
+    /// The predicted move being pondered, if any.
+    #[cfg(feature = "engine")]
+    pub fn ponder_move(&self) -> Option<String> {
+        if self.is_pondering() {
+            self.ponder.as_ref().map(|p| p.uci.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Ponder for up to `slice_ms` on the predicted position.
+    /// Returns the search result (bestMove is our reply to the predicted move),
+    /// or null if not pondering.
+    #[cfg(feature = "engine")]
+    pub fn ponder(&mut self, slice_ms: f64) -> JsValue {
+        self.ponder_report(slice_ms).map_or(JsValue::NULL, |r| to_js(&r))
+    }
+
+    /// The opponent played the predicted move: play it and run a normal timed search.
+    /// Returns the search result, or null if not pondering.
+    #[cfg(feature = "engine")]
+    pub fn ponderhit(&mut self, max_ms: f64) -> JsValue {
+        self.ponderhit_report(max_ms).map_or(JsValue::NULL, |r| to_js(&r))
+    }
+
+    /// Stop pondering (the opponent played something else). Returns true if pondering was active.
+    #[cfg(feature = "engine")]
+    pub fn stop_ponder(&mut self) -> bool {
+        let was_pondering = self.is_pondering();
+        self.ponder = None;
+        was_pondering
+    }
+
+    /// Run perft from the current position at the given depth.
+    /// Returns the total leaf node count — the standard correctness benchmark.
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut pos = self.position.clone();
+        perft(&mut pos, depth)
+    }
+
+    /// Run perft divide — returns [["e2e4", 8102], ["d2d4", 8338], ...]
+    /// Shows node count per root move (useful for debugging move generation).
+    pub fn perft_divide(&self, depth: u32) -> JsValue {
+        to_js(&self.perft_divide_list(depth))
+    }
+
+    /// Run perft counting captures, en passants, castles, promotions, checks,
+    /// discovered/double checks and checkmates — returns one object per depth:
+    /// [{depth: 1, nodes: 20, captures: 0, enPassants: 0, ...}, ...]
+    /// Compare with published tables to debug variant rules.
+    pub fn perft_detailed(&self, depth: u32) -> JsValue {
+        to_js(&self.perft_detailed_list(depth))
+    }
+}
+
+// =============================================================================
+// STRUCTURED RESULTS
+// =============================================================================
+//
+// The values behind the GameState methods that return JS objects. They are
+// plain Rust here so native code and tests can use them; `to_js` converts
+// them with serde-wasm-bindgen at the boundary.
+
+/// A piece on the board, as in `get_board_json`: {"type":"P","color":"w"}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct BoardPiece {
+    #[serde(rename = "type")]
+    pub piece: &'static str,
+    pub color: &'static str,
+}
+
+/// A move and its effects, for `legal_moves_detailed` and `last_move_info`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveInfo {
+    pub uci: String,
+    pub from: String,
+    pub to: String,
+    /// Piece letter of the mover ("P", "N", ..., "K")
+    pub piece: &'static str,
+    pub color: &'static str,
+    /// Piece letter of the captured piece
+    pub captured: Option<&'static str>,
+    /// Piece letter of the promotion piece
+    pub promotion: Option<&'static str>,
+    pub is_capture: bool,
+    pub is_castle: bool,
+    pub is_en_passant: bool,
+    pub is_promotion: bool,
+    pub san: String,
+    pub gives_check: bool,
+}
+
+impl MoveInfo {
+    /// Describe legal move `m` in `pos`, the position before it is played
+    pub fn new(pos: &Position, m: types::Move) -> Self {
+        let (color, piece) = pos.piece_on(m.from()).unwrap_or((pos.side_to_move(), types::PieceType::Pawn));
+        let captured = if m.is_en_passant() {
+            Some(types::PieceType::Pawn)
+        } else {
+            pos.piece_on(m.to()).filter(|&(c, _)| c != color).map(|(_, p)| p)
+        };
+        MoveInfo {
+            uci: m.to_uci(),
+            from: m.from().to_algebraic(),
+            to: m.to().to_algebraic(),
+            piece: piece_letter(piece),
+            color: color_letter(color),
+            captured: captured.map(piece_letter),
+            promotion: m.promotion_piece().map(piece_letter),
+            is_capture: captured.is_some(),
+            is_castle: m.is_castling(),
+            is_en_passant: m.is_en_passant(),
+            is_promotion: m.is_promotion(),
+            san: m.to_san(pos),
+            gives_check: pos.gives_check(m),
+        }
+    }
+}
+
+/// A square a piece can move to, for `legal_moves_from`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Destination {
+    pub to: String,
+    pub file: u8,
+    pub rank: u8,
+    pub is_capture: bool,
+    /// Lowercase UCI letters of the pieces a pawn may promote to here
+    pub promotions: Vec<&'static str>,
+    /// No move to this square leaves the pawn a pawn
+    pub must_promote: bool,
+}
+
+/// Attacker counts for `attack_map`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct AttackMap {
+    /// 8 rows from rank 8 down to rank 1, files a to h
+    pub counts: Vec<Vec<u8>>,
+    /// Squares with at least one attacker, as a hex bitboard
+    pub attacked: String,
+}
+
+/// Evaluation terms of one side, for `eval_breakdown`
+#[cfg(feature = "engine")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalTermsReport {
+    pub material: eval::Score,
+    pub pst: eval::Score,
+    pub pawn_structure: eval::Score,
+    pub outposts: eval::Score,
+    pub threats: eval::Score,
+    pub bishop_pair: eval::Score,
+    pub mop_up: eval::Score,
+    pub total: eval::Score,
+}
+
+#[cfg(feature = "engine")]
+impl EvalTermsReport {
+    fn new(t: &eval::EvalTerms) -> Self {
+        EvalTermsReport {
+            material: t.material,
+            pst: t.pst,
+            pawn_structure: t.pawn_structure,
+            outposts: t.outposts,
+            threats: t.threats,
+            bishop_pair: t.bishop_pair,
+            mop_up: t.mop_up,
+            total: t.total(),
+        }
+    }
+}
+
+/// The evaluation split into terms, for `eval_breakdown`
+#[cfg(feature = "engine")]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalReport {
+    pub total: eval::Score,
+    pub tempo: eval::Score,
+    pub phase: i32,
+    /// Share of the score kept by endgame scaling, 0.0 to 1.0
+    pub scale: f64,
+    pub kpk_bonus: eval::Score,
+    pub white: EvalTermsReport,
+    pub black: EvalTermsReport,
+}
+
+#[cfg(feature = "engine")]
+impl EvalReport {
+    fn new(b: &eval::EvalBreakdown) -> Self {
+        EvalReport {
+            total: b.total,
+            tempo: b.tempo,
+            phase: b.phase,
+            scale: b.scale as f64 / 64.0,
+            kpk_bonus: b.kpk_bonus,
+            white: EvalTermsReport::new(&b.white),
+            black: EvalTermsReport::new(&b.black),
+        }
+    }
+}
+
+/// One value for each side, for `lost_pieces` and `checks_given`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct BySide<T> {
+    pub w: T,
+    pub b: T,
+}
+
+/// Count of each piece type but the king, for `lost_pieces`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct PieceCounts {
+    pub p: u32,
+    pub n: u32,
+    pub b: u32,
+    pub r: u32,
+    pub q: u32,
+}
+
+/// Transposition table statistics, for `tt_stats`
+#[cfg(feature = "engine")]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtStats {
+    pub capacity: usize,
+    /// Share of the entries in use, 0.0 to 1.0
+    pub utilization: f64,
+    pub generation: u8,
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    pub collisions: u64,
+    /// Hits per probe, in percent
+    pub hit_rate: f64,
+}
+
+/// A candidate move for `hints`
+#[cfg(feature = "engine")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hint {
+    #[serde(rename = "move")]
+    pub mv: String,
+    pub san: String,
+    /// Centipawns for the side to move
+    pub score: eval::Score,
+    pub pv: Vec<String>,
+    pub pv_san: Vec<String>,
+}
+
+#[cfg(feature = "engine")]
+impl Hint {
+    fn new(pos: &Position, line: &search::PvLine) -> Self {
+        let mut pos = pos.clone();
+        let mut pv_san = Vec::with_capacity(line.pv.len());
+        for &m in &line.pv {
+            pv_san.push(m.to_san(&pos));
+            pos.make_move(m);
+        }
+        Hint {
+            mv: line.mv.to_uci(),
+            san: pv_san[0].clone(),
+            score: line.score,
+            pv: line.pv.iter().map(|m| m.to_uci()).collect(),
+            pv_san,
+        }
+    }
+}
+
+/// Comment and NAGs of one move, for `annotations`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Annotation {
+    pub ply: usize,
+    pub san: String,
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+    /// The NAGs as shown: "!", "?!", or "$n" for those without a glyph
+    pub glyphs: Vec<String>,
+}
+
+/// FEN of the standard starting position under `variant`'s rules
+fn initial_fen(variant: variant::Variant) -> String {
+    let mut pos = Position::starting_position();
+    pos.set_variant(variant);
+    pos.to_fen()
+}
+
+/// PGN result for a finished position: mate, a variant win, or a draw by
+/// the rules of the position alone (repetitions aren't seen); "*" otherwise
+fn decided_result(pos: &Position) -> &'static str {
+    let winner = match pos.variant().outcome(pos) {
+        Some((color, _)) => Some(color),
+        None if pos.is_checkmate() => Some(pos.side_to_move().flip()),
+        None => None,
+    };
+    match winner {
+        Some(types::Color::White) => "1-0",
+        Some(types::Color::Black) => "0-1",
+        None if pos.is_stalemate() || pos.is_insufficient_material() || pos.is_dead_position() || pos.is_fifty_move_draw() => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// Uppercase letter of a piece type, as used in board and move objects
+fn piece_letter(piece: types::PieceType) -> &'static str {
+    match piece {
+        types::PieceType::Pawn => "P",
+        types::PieceType::Knight => "N",
+        types::PieceType::Bishop => "B",
+        types::PieceType::Rook => "R",
+        types::PieceType::Queen => "Q",
+        types::PieceType::King => "K",
+    }
+}
+
+/// Lowercase letter of a promotion piece, as in UCI
+fn promotion_letter(piece: types::PieceType) -> &'static str {
+    match piece {
+        types::PieceType::Knight => "n",
+        types::PieceType::Bishop => "b",
+        types::PieceType::Rook => "r",
+        types::PieceType::King => "k",
+        _ => "q",
+    }
+}
+
+/// Unicode chess symbol for a piece
+fn piece_symbol(color: types::Color, piece: types::PieceType) -> char {
+    use types::PieceType::*;
+    match (color, piece) {
+        (types::Color::White, King) => '♔',
+        (types::Color::White, Queen) => '♕',
+        (types::Color::White, Rook) => '♖',
+        (types::Color::White, Bishop) => '♗',
+        (types::Color::White, Knight) => '♘',
+        (types::Color::White, Pawn) => '♙',
+        (types::Color::Black, King) => '♚',
+        (types::Color::Black, Queen) => '♛',
+        (types::Color::Black, Rook) => '♜',
+        (types::Color::Black, Bishop) => '♝',
+        (types::Color::Black, Knight) => '♞',
+        (types::Color::Black, Pawn) => '♟',
+    }
+}
+
+fn color_letter(color: types::Color) -> &'static str {
+    match color {
+        types::Color::White => "w",
+        types::Color::Black => "b",
+    }
+}
+
+/// Search result and statistics for `search_timed`, `search_depth` and pondering
+#[cfg(feature = "engine")]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReport {
+    pub best_move: Option<String>,
+    pub score: eval::Score,
+    pub depth: u8,
+    pub seldepth: u8,
+    pub nodes: u64,
+    pub qnodes: u64,
+    pub time_ms: f64,
+    pub nps: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub tt_cutoffs: u64,
+    pub tt_stores: u64,
+    pub beta_cutoffs: u64,
+    pub first_move_cutoff_rate: f64,
+    pub null_move_tries: u64,
+    pub null_move_cutoffs: u64,
+    pub lmr_reductions: u64,
+    pub lmr_researches: u64,
+    /// Win/draw/loss per mille for the side to move
+    pub wdl: [u32; 3],
+    pub win_probability: f64,
+}
+
+#[cfg(feature = "engine")]
+impl SearchReport {
+    fn new(best_move: Option<types::Move>, score: eval::Score, depth: u8, time_ms: f64, nps: u64, stats: &search::SearchStats) -> Self {
+        SearchReport {
+            best_move: best_move.map(|m| m.to_uci()),
+            score,
+            depth,
+            seldepth: stats.seldepth,
+            nodes: stats.nodes,
+            qnodes: stats.qnodes,
+            time_ms,
+            nps,
+            tt_probes: stats.tt_probes,
+            tt_hits: stats.tt_hits,
+            tt_cutoffs: stats.tt_cutoffs,
+            tt_stores: stats.tt_stores,
+            beta_cutoffs: stats.beta_cutoffs,
+            first_move_cutoff_rate: stats.first_move_cutoff_rate(),
+            null_move_tries: stats.null_move_tries,
+            null_move_cutoffs: stats.null_move_cutoffs,
+            lmr_reductions: stats.lmr_reductions,
+            lmr_researches: stats.lmr_researches,
+            wdl: eval::score_to_wdl(score),
+            win_probability: eval::score_to_win_probability(score),
+        }
+    }
+}
+
+/// Nodes `search_async` searches between yields: a few milliseconds of work
+#[cfg(feature = "engine")]
+const ASYNC_STEP_NODES: u64 = 20_000;
+
+/// Let the browser render and handle input before continuing (a zero-delay
+/// `setTimeout`; a resolved promise where there is none)
+#[cfg(feature = "engine")]
+async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        let scheduled = set_timeout.is_some_and(|f| f.call2(&JsValue::NULL, &resolve, &JsValue::from(0)).is_ok());
+        if !scheduled {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ())
+}
+
+/// Convert a result for JavaScript: objects, arrays, strings and numbers
+/// instead of JSON text. Null if it can't be represented.
+fn to_js<T: serde::Serialize>(value: &T) -> JsValue {
+    serde_wasm_bindgen::to_value(value).unwrap_or(JsValue::NULL)
+}
+
+impl GameState {
+    /// Moves played so far, in UCI notation ("0000" for a null move)
+    pub fn history_list(&self) -> Vec<String> {
+        self.uci_history.clone()
+    }
+
+    /// Moves played so far, in SAN ("--" for a null move)
+    pub fn history_san_list(&self) -> Vec<String> {
+        // Rewind to the start of the game, then replay writing each move
+        let mut pos = self.start_position();
+        let mut sans = Vec::with_capacity(self.move_history.len());
+        for (m, _) in &self.move_history {
+            if *m == types::Move::NULL {
+                sans.push("--".to_string());
+                pos.make_null_move();
+                continue;
+            }
+            sans.push(m.to_san(&pos));
+            pos.make_move(*m);
+        }
+        sans
+    }
+
+    /// FEN of every position from the start of the game to the current one
+    pub fn fen_history_list(&self) -> Vec<String> {
+        let mut pos = self.start_position();
+        let mut fens = Vec::with_capacity(self.move_history.len() + 1);
+        fens.push(pos.to_fen());
+        for (m, _) in &self.move_history {
+            if *m == types::Move::NULL {
+                pos.make_null_move();
+            } else {
+                pos.make_move(*m);
+            }
+            fens.push(pos.to_fen());
+        }
+        fens
+    }
+
+    /// Moves stored after the current position, in UCI, the main continuation first
+    pub fn continuations_list(&self) -> Vec<String> {
+        self.tree.children(self.node).iter().map(|&c| self.tree.mv(c).to_uci()).collect()
+    }
+
+    /// Alternatives to the last move (itself included), in UCI, the main line first
+    pub fn siblings_list(&self) -> Vec<String> {
+        match self.tree.parent(self.node) {
+            Some(parent) => self.tree.children(parent).iter().map(|&c| self.tree.mv(c).to_uci()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Main line of the game from the start, in UCI
+    pub fn mainline_list(&self) -> Vec<String> {
+        self.tree.mainline().iter().map(|m| m.to_uci()).collect()
+    }
+
+    /// Comments and NAGs of each move in the current line, the moves ahead included
+    pub fn annotations_list(&self) -> Vec<Annotation> {
+        let mut pos = self.start_position();
+        let plies = self.current_ply() + self.tree.line_after(self.node).count();
+        let mut list = Vec::with_capacity(plies);
+        for ply in 1..=plies {
+            let Some(node) = self.line_node(ply) else { break };
+            let m = self.tree.mv(node);
+            let san = if m == types::Move::NULL { "--".to_string() } else { m.to_san(&pos) };
+            if m == types::Move::NULL {
+                pos.make_null_move();
+            } else {
+                pos.make_move(m);
+            }
+            let nags = self.tree.nags(node).to_vec();
+            list.push(Annotation {
+                ply,
+                san,
+                comment: Some(self.tree.comment(node)).filter(|c| !c.is_empty()).map(str::to_string),
+                glyphs: nags.iter().map(|&n| pgn::nag_glyph(n)).collect(),
+                nags,
+            });
+        }
+        list
+    }
+
+    /// The board as 8 rows from rank 8 down to rank 1, files a to h
+    pub fn board(&self) -> Vec<Vec<Option<BoardPiece>>> {
+        (0..8u8)
+            .rev()
+            .map(|rank| {
+                (0..8u8)
+                    .map(|file| {
+                        self.position
+                            .piece_on(types::Square::from_file_rank(file, rank))
+                            .map(|(color, piece)| BoardPiece { piece: piece_letter(piece), color: color_letter(color) })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Draw the board with `glyph` for pieces and `empty` for empty squares,
+    /// rank 8 at the top unless flipped
+    fn render(&self, flip: bool, empty: char, glyph: impl Fn(types::Color, types::PieceType) -> char) -> String {
+        let ranks: Vec<u8> = if flip { (0..8).collect() } else { (0..8).rev().collect() };
+        let files: Vec<u8> = if flip { (0..8).rev().collect() } else { (0..8).collect() };
+        let mut out = String::new();
+        for &rank in &ranks {
+            out.push((b'1' + rank) as char);
+            for &file in &files {
+                out.push(' ');
+                out.push(match self.position.piece_on(types::Square::from_file_rank(file, rank)) {
+                    Some((color, piece)) => glyph(color, piece),
+                    None => empty,
+                });
+            }
+            out.push('\n');
+        }
+        out.push(' ');
+        for &file in &files {
+            out.push(' ');
+            out.push((b'a' + file) as char);
+        }
+        out.push('\n');
+        out
+    }
+
+    /// `legal_moves_detailed` without the conversion to JS objects
+    pub fn legal_moves_report(&self) -> Vec<MoveInfo> {
+        generate_legal_moves(&self.position).iter().map(|&m| MoveInfo::new(&self.position, m)).collect()
+    }
+
+    /// `legal_moves_from` without the conversion to JS objects
+    pub fn legal_moves_from_report(&self, file: u8, rank: u8) -> Vec<Destination> {
+        if file > 7 || rank > 7 {
+            return Vec::new();
+        }
+        let from = types::Square::from_file_rank(file, rank);
+        let mut destinations: Vec<Destination> = Vec::new();
+        for m in generate_legal_moves(&self.position).iter().filter(|m| m.from() == from) {
+            let to = m.to();
+            let index = match destinations.iter().position(|d| (d.file, d.rank) == (to.file(), to.rank())) {
+                Some(i) => i,
+                None => {
+                    let is_capture = m.is_en_passant()
+                        || self.position.piece_on(to).is_some_and(|(c, _)| c != self.position.side_to_move());
+                    destinations.push(Destination {
+                        to: to.to_algebraic(),
+                        file: to.file(),
+                        rank: to.rank(),
+                        is_capture,
+                        promotions: Vec::new(),
+                        must_promote: true,
+                    });
+                    destinations.len() - 1
+                }
+            };
+            let dest = &mut destinations[index];
+            match m.promotion_piece() {
+                Some(piece) => dest.promotions.push(promotion_letter(piece)),
+                None => dest.must_promote = false,
+            }
+        }
+        for dest in &mut destinations {
+            dest.must_promote &= !dest.promotions.is_empty();
+        }
+        destinations
+    }
+
+    /// `attack_map` without the conversion to a JS object
+    pub fn attack_map_report(&self, color: &str) -> AttackMap {
+        let attackers = |sq| match color {
+            "w" => self.position.attackers_by(sq, types::Color::White),
+            "b" => self.position.attackers_by(sq, types::Color::Black),
+            _ => self.position.attackers_to(sq),
+        };
+        let mut attacked = bitboard::Bitboard::EMPTY;
+        let counts = (0..8u8)
+            .rev()
+            .map(|rank| {
+                (0..8u8)
+                    .map(|file| {
+                        let sq = types::Square::from_file_rank(file, rank);
+                        let count = attackers(sq).count() as u8;
+                        if count > 0 {
+                            attacked.set(sq);
+                        }
+                        count
+                    })
+                    .collect()
+            })
+            .collect();
+        AttackMap { counts, attacked: format!("0x{:016x}", attacked.0) }
+    }
+
+    /// `attackers` without the conversion to a JS array
+    pub fn attackers_list(&self, file: u8, rank: u8, color: &str) -> Vec<String> {
+        if file >= 8 || rank >= 8 {
+            return Vec::new();
+        }
+        let sq = types::Square::from_file_rank(file, rank);
+        let attackers = match color {
+            "w" => self.position.attackers_by(sq, types::Color::White),
+            "b" => self.position.attackers_by(sq, types::Color::Black),
+            _ => self.position.attackers_to(sq),
+        };
+        attackers.map(|sq| sq.to_algebraic()).collect()
+    }
+
+    /// `checkers` without the conversion to a JS array
+    pub fn checkers_list(&self) -> Vec<String> {
+        self.position.checkers().map(|sq| sq.to_algebraic()).collect()
+    }
+
+    /// `pinned` without the conversion to a JS array
+    pub fn pinned_list(&self, color: &str) -> Vec<String> {
+        let pinned = match color {
+            "w" => self.position.pinned(types::Color::White),
+            "b" => self.position.pinned(types::Color::Black),
+            _ => self.position.pinned(types::Color::White) | self.position.pinned(types::Color::Black),
+        };
+        pinned.map(|sq| sq.to_algebraic()).collect()
+    }
+
+    /// `eval_breakdown` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn eval_breakdown_report(&self) -> EvalReport {
+        EvalReport::new(&eval::evaluate_breakdown(&self.position, eval::default_params()))
+    }
+
+    /// `lost_pieces` without the conversion to a JS object
+    pub fn lost_pieces_report(&self) -> BySide<PieceCounts> {
+        use types::{Color, PieceType};
+        let side = |color: Color| PieceCounts {
+            p: self.position.lost_pieces(color, PieceType::Pawn),
+            n: self.position.lost_pieces(color, PieceType::Knight),
+            b: self.position.lost_pieces(color, PieceType::Bishop),
+            r: self.position.lost_pieces(color, PieceType::Rook),
+            q: self.position.lost_pieces(color, PieceType::Queen),
+        };
+        BySide { w: side(Color::White), b: side(Color::Black) }
+    }
+
+    /// `checks_given` without the conversion to a JS object
+    pub fn checks_given_report(&self) -> BySide<u8> {
+        BySide {
+            w: self.position.checks_given(types::Color::White),
+            b: self.position.checks_given(types::Color::Black),
+        }
+    }
+
+    /// `tt_stats` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn tt_stats_report(&self) -> TtStats {
+        TtStats {
+            capacity: self.tt.capacity(),
+            utilization: self.tt.utilization(),
+            generation: self.tt.generation(),
+            hits: self.tt.hits,
+            misses: self.tt.misses,
+            stores: self.tt.stores,
+            collisions: self.tt.collisions,
+            hit_rate: self.tt.hit_rate(),
+        }
+    }
+
+    /// `last_move_info` without the conversion to a JS object
+    pub fn last_move_report(&self) -> Option<MoveInfo> {
+        let &(m, ref undo) = self.move_history.last()?;
+        if m == types::Move::NULL {
+            return None;
+        }
+        let mut pos = self.position.clone();
+        pos.unmake_move(m, undo);
+        Some(MoveInfo::new(&pos, m))
+    }
+
+    /// A table for a search running apart from the game (`search_async`, the
+    /// worker): a handle on the game's shared table, or a fresh one
+    #[cfg(feature = "engine")]
+    fn search_tt(&self) -> tt::TranspositionTable {
+        match self.tt.shared_table() {
+            Some(table) => tt::TranspositionTable::attach(std::sync::Arc::clone(table)),
+            None => tt::TranspositionTable::default_wasm(),
+        }
+    }
+
+    /// `search_timed` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn search_timed_report(&mut self, max_ms: f64) -> SearchReport {
+        let mut pos = self.position.clone();
+        let (best_move, score, stats) = search::search_timed_with_tt(&mut pos, max_ms, 0, &mut self.tt, &self.personality.search_options());
+        SearchReport::new(best_move, score, stats.depth, stats.time_ms, stats.nps, &stats)
+    }
+
+    /// `search_clock` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn search_clock_report(&mut self, wtime: f64, btime: f64, winc: f64, binc: f64) -> SearchReport {
+        let (time, inc) = match self.position.side_to_move() {
+            types::Color::White => (wtime, winc),
+            types::Color::Black => (btime, binc),
+        };
+        let control = search::SearchControl::with_limits(timeman::TimeLimits::for_clock(time, inc, None));
+        let mut pos = self.position.clone();
+        let (best_move, score, stats) =
+            search::search_with_control(&mut pos, 0, &mut self.tt, &control, &self.personality.search_options());
+        SearchReport::new(best_move, score, stats.depth, stats.time_ms, stats.nps, &stats)
+    }
+
+    /// `search_depth` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn search_depth_report(&mut self, depth: u8) -> SearchReport {
+        let mut pos = self.position.clone();
+        let start = {
+            #[cfg(target_arch = "wasm32")]
             { js_sys::Date::now() }
             #[cfg(not(target_arch = "wasm32"))]
             {
@@ -666,7 +2523,8 @@ impl GameState {
                 SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64() * 1000.0
             }
         };
-        let (best_move, score, stats) = search::search(&mut pos, depth);
+        self.tt.new_search();
+        let (best_move, score, stats) = search::search_with_tt(&mut pos, depth, &mut self.tt, &self.personality.search_options());
         let elapsed = {
             #[cfg(target_arch = "wasm32")]
             { js_sys::Date::now() - start }
@@ -677,60 +2535,389 @@ impl GameState {
             }
         };
         let nps = if elapsed > 0.0 { (stats.nodes as f64 / (elapsed / 1000.0)) as u64 } else { 0 };
-        let mv_str = best_move.map_or("null".to_string(), |m| format!("\"{}\"", m.to_uci()));
-        format!(
-            "{{\"bestMove\":{},\"score\":{},\"depth\":{},\"nodes\":{},\"timeMs\":{:.1},\"nps\":{}}}",
-            mv_str, score, depth, stats.nodes, elapsed, nps
-        )
+        SearchReport::new(best_move, score, depth, elapsed, nps, &stats)
+    }
+
+    /// `hints` without the conversion to JS objects
+    #[cfg(feature = "engine")]
+    pub fn hints_report(&mut self, n: usize, depth: u8) -> Vec<Hint> {
+        let mut pos = self.position.clone();
+        let lines = search::search_multipv(&mut pos, n, depth, &mut self.tt, &self.personality.search_options());
+        lines.iter().map(|line| Hint::new(&self.position, line)).collect()
+    }
+
+    /// `hints_timed` without the conversion to JS objects
+    #[cfg(feature = "engine")]
+    pub fn hints_timed_report(&mut self, n: usize, max_ms: f64) -> Vec<Hint> {
+        let mut pos = self.position.clone();
+        let control = search::SearchControl::new(max_ms);
+        let (lines, _) =
+            search::search_multipv_with_control(&mut pos, n, 0, &mut self.tt, &control, &self.personality.search_options());
+        lines.iter().map(|line| Hint::new(&self.position, line)).collect()
+    }
+
+    /// `ponder` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn ponder_report(&mut self, slice_ms: f64) -> Option<SearchReport> {
+        if !self.is_pondering() {
+            return None;
+        }
+        let mut pos = self.ponder.as_ref()?.position.clone();
+        let (best_move, score, stats) = search::search_timed_with_tt(&mut pos, slice_ms, 0, &mut self.tt, &self.personality.search_options());
+        Some(SearchReport::new(best_move, score, stats.depth, stats.time_ms, stats.nps, &stats))
+    }
+
+    /// `ponderhit` without the conversion to a JS object
+    #[cfg(feature = "engine")]
+    pub fn ponderhit_report(&mut self, max_ms: f64) -> Option<SearchReport> {
+        if !self.is_pondering() {
+            return None;
+        }
+        let uci = self.ponder.take()?.uci;
+        if !self.make_move_uci(&uci) {
+            return None;
+        }
+        Some(self.search_timed_report(max_ms))
+    }
+
+    /// `perft_divide` without the conversion to a JS array
+    pub fn perft_divide_list(&self, depth: u32) -> Vec<(String, u64)> {
+        let mut pos = self.position.clone();
+        movegen::perft_divide(&mut pos, depth)
+    }
+
+    /// `perft_detailed` without the conversion to a JS array
+    pub fn perft_detailed_list(&self, depth: u32) -> Vec<movegen::PerftStats> {
+        let mut pos = self.position.clone();
+        movegen::perft_detailed(&mut pos, depth)
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// GAME STATE TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gamestate_new() {
+        let gs = GameState::new();
+        assert_eq!(gs.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(gs.move_count(), 0);
+        assert!(!gs.is_checkmate());
+        assert!(!gs.is_stalemate());
+        assert!(!gs.is_draw());
+        assert!(!gs.is_game_over());
+        assert_eq!(gs.status(), "playing");
+    }
+
+    #[test]
+    fn test_gamestate_make_move() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        assert_eq!(gs.move_count(), 1);
+        assert!(gs.make_move_uci("e7e5"));
+        assert_eq!(gs.move_count(), 2);
+    }
+
+    #[test]
+    fn test_gamestate_is_legal_uci() {
+        let mut gs = GameState::new();
+        let fen = gs.fen();
+        assert!(gs.is_legal_uci("e2e4"));
+        assert!(gs.is_legal_uci("g1f3"));
+        assert!(!gs.is_legal_uci("e2e5"));
+        assert!(!gs.is_legal_uci("e7e5"));
+        assert!(!gs.is_legal_uci("zz"));
+        assert_eq!(gs.fen(), fen);
+        assert_eq!(gs.move_count(), 0);
+
+        // Castling, promotion and en passant need the right move type
+        assert!(gs.load_fen("r3k3/1P6/8/3pP3/8/8/8/4K2R w Kq d6 0 1"));
+        assert!(gs.is_legal_uci("e1g1"));
+        assert!(gs.is_legal_uci("b7a8q"));
+        assert!(gs.is_legal_uci("e5d6"));
+        assert!(!gs.is_legal_uci("e1c1"));
+    }
+
+    #[test]
+    fn test_gamestate_attackers() {
+        let gs = GameState::new();
+        // f3 is covered by the e2/g2 pawns and the g1 knight
+        assert_eq!(gs.attackers_list(5, 2, "w"), ["g1", "e2", "g2"]);
+        assert!(gs.attackers_list(5, 2, "b").is_empty());
+        assert_eq!(gs.attackers_list(5, 2, ""), gs.attackers_list(5, 2, "w"));
+        assert!(gs.attackers_list(8, 0, "").is_empty());
+    }
+
+    #[test]
+    fn test_gamestate_checkers_and_pinned() {
+        let mut gs = GameState::new();
+        assert!(gs.checkers_list().is_empty());
+        assert!(gs.pinned_list("").is_empty());
+        // 1. e4 d6 2. Bb5+ c6: the blocking pawn is pinned
+        for m in ["e2e4", "d7d6", "f1b5"] {
+            assert!(gs.make_move_uci(m));
+        }
+        assert_eq!(gs.checkers_list(), ["b5"]);
+        assert!(gs.make_move_uci("c7c6"));
+        assert!(gs.checkers_list().is_empty());
+        assert_eq!(gs.pinned_list("b"), ["c6"]);
+        assert!(gs.pinned_list("w").is_empty());
     }
 
-    /// Run perft from the current position at the given depth.
-    /// Returns the total leaf node count — the standard correctness benchmark.
-    pub fn perft(&self, depth: u32) -> u64 {
-        let mut pos = self.position.clone();
-        perft(&mut pos, depth)
+    #[test]
+    fn test_gamestate_load_epd() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        gs.load_epd("4k3/8/8/8/8/8/4P3/4K3 b - - bm Kd7; hmvc 4; fmvn 31;").unwrap();
+        assert_eq!(gs.fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 4 31");
+        assert_eq!(gs.move_count(), 0);
+
+        assert!(gs.load_epd("4k3/8/8/8/8/8/4P3/4K3 x - - bm Kd7;").is_err());
+        assert_eq!(gs.fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 4 31");
     }
 
-    /// Run perft divide — returns JSON: [["e2e4", 8102], ["d2d4", 8338], ...]
-    /// Shows node count per root move (useful for debugging move generation).
-    pub fn perft_divide(&self, depth: u32) -> String {
-        let mut pos = self.position.clone();
-        let results = movegen::perft_divide(&mut pos, depth);
-        let entries: Vec<String> = results
-            .iter()
-            .map(|(uci, nodes)| format!("[\"{}\",{}]", uci, nodes))
-            .collect();
-        format!("[{}]", entries.join(","))
+    #[test]
+    fn test_gamestate_make_move_san() {
+        let mut gs = GameState::new();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"] {
+            assert!(gs.make_move_san(san), "Rejected {}", san);
+        }
+        assert_eq!(gs.history_list(), ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5c6", "d7c6", "e1g1"]);
+        assert_eq!(gs.history_san_list(), ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"]);
+        assert!(!gs.make_move_san("Nf3"));
+        assert!(!gs.make_move_san("Qxh7"));
+        assert_eq!(gs.move_count(), 9);
+        assert_eq!(gs.undo(), "e1g1");
     }
-}
 
-// =============================================================================
-// GAME STATE TESTS
-// =============================================================================
+    #[test]
+    fn test_gamestate_load_pgn() {
+        let pgn = "[Event \"Casual\"]\n[Result \"0-1\"]\n\n1. f3 {weakening} e5 2. g4?? (2. Nc3) Qh4# 0-1\n";
+        let mut gs = GameState::new();
+        gs.load_pgn(pgn).unwrap();
+        assert_eq!(gs.history_san_list(), ["f3", "e5", "g4", "Qh4#"]);
+        assert!(gs.is_checkmate());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // From a FEN tag
+        gs.load_pgn("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10\"]\n\n10... Kd7 11. e4 *").unwrap();
+        assert_eq!(gs.history_list(), ["e8d7", "e2e4"]);
+        assert_eq!(gs.fen(), "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 11");
+    }
 
     #[test]
-    fn test_gamestate_new() {
-        let gs = GameState::new();
-        assert_eq!(gs.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-        assert_eq!(gs.move_count(), 0);
-        assert!(!gs.is_checkmate());
-        assert!(!gs.is_stalemate());
-        assert!(!gs.is_draw());
-        assert!(!gs.is_game_over());
+    fn test_gamestate_clock() {
+        let mut gs = GameState::new();
+        assert!(!gs.record_time_used(100.0));
+        assert_eq!(gs.time_left("w"), None);
+
+        gs.set_clock(60_000.0, 2_000.0);
+        assert!(gs.record_time_used(5_000.0));
+        assert!(gs.make_move_uci("e2e4"));
+        assert_eq!(gs.time_left("w"), Some(57_000.0));
+        // Timestamps charge the time between calls
+        assert!(gs.record_time_at(1_000_000.0));
+        assert!(gs.record_time_at(1_003_000.0));
+        assert!(gs.make_move_san("e5"));
+        assert_eq!((gs.time_left("w"), gs.time_left("b")), (Some(57_000.0), Some(59_000.0)));
+        // Navigation doesn't touch the clock
+        gs.undo();
+        assert_eq!(gs.time_left("b"), Some(59_000.0));
+        gs.redo();
+
+        // White runs out: a loss, and moving afterwards earns no increment
+        gs.record_time_used(60_000.0);
+        assert_eq!(gs.time_left("w"), Some(0.0));
+        assert_eq!(gs.status(), "timeout");
+        assert!(gs.is_game_over() && !gs.is_draw());
+        assert!(gs.make_move_uci("g1f3"));
+        assert_eq!(gs.status(), "timeout");
+
+        // Black can't win with a lone king: a draw
+        gs.load_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(gs.time_left("w"), Some(60_000.0));
+        gs.record_time_used(70_000.0);
+        assert_eq!(gs.status(), "timeout_vs_insufficient_material");
+        assert!(gs.is_draw());
+
+        gs.clear_clock();
         assert_eq!(gs.status(), "playing");
     }
 
     #[test]
-    fn test_gamestate_make_move() {
+    fn test_gamestate_result() {
         let mut gs = GameState::new();
+        assert_eq!(gs.result(), "*");
+        assert!(!gs.accept_draw());
+
+        // An offer lasts through the offerer's move; the reply declines it
+        assert!(gs.offer_draw());
+        assert!(!gs.offer_draw());
         assert!(gs.make_move_uci("e2e4"));
-        assert_eq!(gs.move_count(), 1);
+        assert_eq!(gs.draw_offered().as_deref(), Some("w"));
         assert!(gs.make_move_uci("e7e5"));
+        assert_eq!(gs.draw_offered(), None);
+        assert!(!gs.accept_draw());
+
+        assert!(gs.offer_draw());
+        assert!(gs.decline_draw());
+        assert!(!gs.decline_draw());
+        assert!(gs.offer_draw());
+        assert!(gs.accept_draw());
+        assert_eq!((gs.result().as_str(), gs.status().as_str()), ("1/2-1/2", "draw_agreement"));
+        assert!(gs.is_game_over() && gs.is_draw());
+        assert!(!gs.resign("w"));
+        assert!(gs.pgn().ends_with("1. e4 e5 1/2-1/2\n"));
+
+        gs.reset();
+        assert!(!gs.resign("x"));
+        assert!(gs.resign("b"));
+        assert_eq!((gs.result().as_str(), gs.status().as_str()), ("1-0", "resignation"));
+        assert!(gs.pgn().contains("[Result \"1-0\"]"));
+
+        // Board and clock results
+        gs.reset();
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            gs.make_move_uci(uci);
+        }
+        assert_eq!(gs.result(), "0-1");
+        gs.load_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        gs.set_clock(1_000.0, 0.0);
+        gs.record_time_used(1_000.0);
+        assert_eq!(gs.result(), "1/2-1/2");
+        gs.load_fen("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1");
+        gs.record_time_used(1_000.0);
+        assert_eq!(gs.result(), "0-1");
+    }
+
+    #[test]
+    fn test_gamestate_annotations() {
+        let mut gs = GameState::new();
+        gs.load_pgn("{Fool's mate} 1. f3 {weakening} e5 2. g4?? (2. Nc3) Qh4# 0-1").unwrap();
+        assert_eq!(gs.comment(0).as_deref(), Some("Fool's mate"));
+        assert_eq!(gs.comment(1).as_deref(), Some("weakening"));
+        assert_eq!(gs.comment(2), None);
+        let notes = gs.annotations_list();
+        assert_eq!(notes.len(), 4);
+        assert_eq!((notes[2].san.as_str(), &notes[2].nags[..], &notes[2].glyphs[..]), ("g4", &[4][..], &["??".to_string()][..]));
+
+        // Annotations stay on their moves while navigating, ahead of the cursor too
+        assert!(gs.goto_ply(1));
+        assert!(gs.set_nags(4, "! $18"));
+        assert!(!gs.set_nags(4, "!!!"));
+        assert!(!gs.set_nags(5, "!"));
+        assert!(!gs.set_nags(0, "!"));
+        assert!(gs.set_comment(2, "  the only move  "));
+        assert!(gs.set_comment(1, ""));
+        assert_eq!(gs.comment(1), None);
+        let json = serde_json::to_value(&gs.annotations_list()[3]).unwrap();
+        assert_eq!(json, serde_json::json!({"ply":4,"san":"Qh4#","comment":null,"nags":[1,18],"glyphs":["!","$18"]}));
+
+        // Through PGN export and back
+        let pgn = gs.pgn();
+        assert!(pgn.contains("[Result \"0-1\"]"), "{}", pgn);
+        assert!(pgn.ends_with("{Fool's mate} 1. f3 e5 {the only move} 2. g4 $4 Qh4# $1 $18 0-1\n"), "{}", pgn);
+        let mut copy = GameState::new();
+        copy.load_pgn(&pgn).unwrap();
+        assert_eq!(copy.annotations_list(), gs.annotations_list());
+        assert_eq!(copy.comment(0).as_deref(), Some("Fool's mate"));
+
+        // A FEN start and a variant go into the tags
+        let mut gs = GameState::new();
+        gs.set_variant("three-check");
+        gs.load_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 10");
+        gs.make_move_uci("e8d7");
+        let pgn = gs.pgn();
+        assert!(pgn.contains("[Variant \"three-check\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10 +0+0\"]"), "{}", pgn);
+        assert!(pgn.ends_with("\n\n10... Kd7 *\n"), "{}", pgn);
+    }
+
+    #[test]
+    fn test_gamestate_load_pgn_error_keeps_game() {
+        let mut gs = GameState::new();
+        gs.make_move_uci("d2d4");
+        let err = gs.load_pgn("1. e4 e5 2. Ke2 Ke7 3. O-O *").unwrap_err();
+        assert_eq!(err.message, "Castling is not legal at 3. O-O (ply 5)");
+        assert_eq!((err.kind, err.field.as_deref()), (ErrorKind::IllegalMove, Some("O-O")));
+        assert_eq!(gs.history_list(), ["d2d4"]);
+        assert_eq!(gs.load_pgn("1. e4 {oops").unwrap_err().kind, ErrorKind::BadPgn);
+        let err = gs.load_pgn("[Variant \"atomic\"]\n\n1. e4 *").unwrap_err();
+        assert_eq!((err.kind, err.field.as_deref()), (ErrorKind::BadOption, Some("Variant")));
+        assert_eq!(gs.move_count(), 1);
+    }
+
+    #[test]
+    fn test_gamestate_board_rendering() {
+        let mut gs = GameState::new();
+        gs.make_move_uci("e2e4");
+        assert_eq!(
+            gs.ascii(false),
+            "8 r n b q k b n r\n\
+             7 p p p p p p p p\n\
+             6 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             4 . . . . P . . .\n\
+             3 . . . . . . . .\n\
+             2 P P P P . P P P\n\
+             1 R N B Q K B N R\n\
+             \x20 a b c d e f g h\n"
+        );
+        let flipped = gs.ascii(true);
+        let lines: Vec<&str> = flipped.lines().collect();
+        assert_eq!(lines[0], "1 R N B K Q B N R");
+        assert_eq!(lines[4], "5 . . . . . . . .");
+        assert_eq!(lines[3], "4 . . . P . . . .");
+        assert_eq!(lines[8], "  h g f e d c b a");
+
+        let unicode = gs.unicode(false);
+        assert!(unicode.starts_with("8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜\n"), "{}", unicode);
+        assert!(unicode.contains("4 · · · · ♙ · · ·\n"), "{}", unicode);
+        assert_eq!(gs.unicode(true).lines().next(), Some("1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖"));
+    }
+
+    #[test]
+    fn test_gamestate_structured_errors() {
+        let mut gs = GameState::new();
+        let kind_and_field = |e: EngineError| (e.kind, e.field);
+        assert_eq!(kind_and_field(gs.try_move_uci("e2").unwrap_err()), (ErrorKind::BadNotation, Some("e2".into())));
+        assert_eq!(kind_and_field(gs.try_move_uci("e2z4").unwrap_err()), (ErrorKind::BadSquare, Some("z4".into())));
+        assert_eq!(kind_and_field(gs.try_move_uci("e7e8x").unwrap_err()), (ErrorKind::BadNotation, Some("x".into())));
+        assert_eq!(kind_and_field(gs.try_move_uci("e3e4").unwrap_err()), (ErrorKind::IllegalMove, Some("e3e4".into())));
+        // A pawn can't jump three squares, even though make_move would move it
+        let err = gs.try_move_uci("e2e5").unwrap_err();
+        assert_eq!((err.kind, err.message.as_str()), (ErrorKind::IllegalMove, "Illegal move: e2e5"));
+        assert!(!gs.make_move_uci("e2e5"));
+        assert_eq!(gs.move_count(), 0);
+
+        assert_eq!(gs.try_move_san("Nd4").unwrap_err().kind, ErrorKind::IllegalMove);
+        assert_eq!(gs.try_move_san("Zz9").unwrap_err().kind, ErrorKind::BadNotation);
+        assert!(gs.try_move_uci("g1f3").is_ok());
+        assert!(gs.try_move_san("Nf6").is_ok());
         assert_eq!(gs.move_count(), 2);
+
+        let err = gs.try_load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::BadFen);
+        assert_eq!(gs.move_count(), 2);
+        let err = from_fen_strict("4k3/8/8/8/8/8/8/4K3 w K - 0 1").err().unwrap();
+        assert_eq!((err.kind, err.field.as_deref()), (ErrorKind::BadFen, Some("castling")));
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], "BadFen");
+    }
+
+    #[test]
+    fn test_gamestate_history_san_from_fen() {
+        let mut gs = GameState::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+        assert!(gs.history_san_list().is_empty());
+        assert!(gs.make_move_uci("h5f7"));
+        assert_eq!(gs.history_san_list(), ["Qxf7#"]);
     }
 
     #[test]
@@ -756,7 +2943,7 @@ mod tests {
 
     #[test]
     fn test_gamestate_stalemate() {
-        let mut gs = GameState::from_fen("k7/8/1Q1K4/8/8/8/8/8 b - - 0 1").unwrap();
+        let gs = GameState::from_fen("k7/8/1Q1K4/8/8/8/8/8 b - - 0 1").unwrap();
         assert!(gs.is_stalemate());
         assert!(gs.is_game_over());
         assert_eq!(gs.status(), "stalemate");
@@ -764,16 +2951,35 @@ mod tests {
 
     #[test]
     fn test_gamestate_insufficient_material() {
-        let mut gs = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let gs = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
         assert!(gs.is_insufficient_material());
         assert!(gs.is_draw());
         assert!(gs.is_game_over());
         assert_eq!(gs.status(), "insufficient_material");
     }
 
+    #[test]
+    fn test_gamestate_three_check_lone_bishop_plays_on() {
+        // Ba4+ is the third check
+        let mut gs = GameState::from_fen("4k3/8/8/8/8/8/8/3BK3 w - - 0 1 +2+0").unwrap();
+        assert!(!gs.is_draw());
+        assert_eq!(gs.status(), "playing");
+        assert!(gs.make_move_uci("d1a4"));
+        assert_eq!(gs.status(), "three_check");
+    }
+
+    #[test]
+    fn test_gamestate_dead_position() {
+        let gs = GameState::from_fen("8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/8 w - - 0 1").unwrap();
+        assert!(!gs.is_insufficient_material());
+        assert!(gs.is_dead_position());
+        assert!(gs.is_draw());
+        assert_eq!(gs.status(), "dead_position");
+    }
+
     #[test]
     fn test_gamestate_fifty_move_draw() {
-        let mut gs = GameState::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 100 50").unwrap();
+        let gs = GameState::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 100 50").unwrap();
         assert!(gs.is_fifty_move_draw());
         assert!(gs.is_draw());
         assert!(gs.is_game_over());
@@ -816,11 +3022,12 @@ mod tests {
         // We can't test legal_moves() directly because it returns Vec<JsValue>
         // which panics outside WASM. Instead verify via the position.
         let gs = GameState::new();
-        let mut pos = gs.position.clone();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = gs.position.clone();
+        let moves = generate_legal_moves(&pos);
         assert_eq!(moves.len(), 20); // 16 pawn + 4 knight moves
     }
 
+    #[cfg(feature = "engine")]
     #[test]
     fn test_gamestate_eval() {
         let gs = GameState::new();
@@ -829,6 +3036,21 @@ mod tests {
         assert!(score.abs() < 50, "Starting eval should be near 0: {}", score);
     }
 
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_eval_breakdown() {
+        let gs = GameState::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let report = gs.eval_breakdown_report();
+        assert_eq!(report.white.material, 320);
+        assert_eq!(report.black.material, 0);
+        assert_eq!(report.total + report.tempo, gs.eval());
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.starts_with("{\"total\":"), "{}", json);
+        for key in ["pst", "pawnStructure", "outposts", "threats", "bishopPair", "mopUp", "phase", "scale", "kpkBonus"] {
+            assert!(json.contains(&format!("\"{}\":", key)), "Missing {}: {}", key, json);
+        }
+    }
+
     #[test]
     fn test_gamestate_hash_changes() {
         let mut gs = GameState::new();
@@ -842,7 +3064,7 @@ mod tests {
     fn test_gamestate_castling() {
         let mut gs = GameState::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
         assert!(gs.make_move_uci("e1g1")); // Kingside castling
-        assert!(gs.is_in_check() == false);
+        assert!(!gs.is_in_check());
     }
 
     #[test]
@@ -988,13 +3210,13 @@ mod tests {
     #[test]
     fn test_gamestate_history() {
         let mut gs = GameState::new();
-        assert_eq!(gs.history(), "[]");
+        assert!(gs.history_list().is_empty());
         gs.make_move_uci("e2e4");
-        assert_eq!(gs.history(), "[\"e2e4\"]");
+        assert_eq!(gs.history_list(), ["e2e4"]);
         gs.make_move_uci("e7e5");
-        assert_eq!(gs.history(), "[\"e2e4\",\"e7e5\"]");
+        assert_eq!(gs.history_list(), ["e2e4", "e7e5"]);
         gs.undo();
-        assert_eq!(gs.history(), "[\"e2e4\"]");
+        assert_eq!(gs.history_list(), ["e2e4"]);
     }
 
     #[test]
@@ -1015,18 +3237,44 @@ mod tests {
     }
 
     #[test]
-    fn test_gamestate_get_board_json() {
+    fn test_gamestate_board() {
         let gs = GameState::new();
-        let json = gs.get_board_json();
-        // Should be valid JSON array of 8 rows
-        assert!(json.starts_with("[["));
-        assert!(json.ends_with("]]"));
-        // Should contain our piece representations
-        assert!(json.contains(r#"{"type":"R","color":"w"}"#));
-        assert!(json.contains(r#"{"type":"K","color":"b"}"#));
+        let board = gs.board();
+        assert_eq!(board.len(), 8);
+        assert_eq!(board[7][0], Some(BoardPiece { piece: "R", color: "w" }));
+        assert_eq!(board[0][4], Some(BoardPiece { piece: "K", color: "b" }));
+        assert_eq!(board[4][4], None);
+        // Serialized the way JavaScript receives it
+        let json = serde_json::to_string(&board).unwrap();
+        assert!(json.starts_with(r#"[[{"type":"R","color":"b"},"#), "{}", json);
         assert!(json.contains("null"));
     }
 
+    #[test]
+    fn test_gamestate_get_board_json_shape() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        // What `get_board_json` hands to JavaScript: 8 rows of 8 cells, row 0 = rank 8,
+        // each cell null or {type, color}
+        let value = serde_json::to_value(gs.board()).unwrap();
+        let rows = value.as_array().unwrap();
+        assert_eq!(rows.len(), 8);
+        for row in rows {
+            let cells = row.as_array().unwrap();
+            assert_eq!(cells.len(), 8);
+            for cell in cells.iter().filter(|c| !c.is_null()) {
+                let piece = cell.as_object().unwrap();
+                assert_eq!(piece.len(), 2, "{}", cell);
+                assert!("PNBRQK".contains(piece["type"].as_str().unwrap()), "{}", cell);
+                assert!(matches!(piece["color"].as_str(), Some("w" | "b")), "{}", cell);
+            }
+        }
+        assert_eq!(rows[0][0], serde_json::json!({"type": "R", "color": "b"}));
+        assert_eq!(rows[4][4], serde_json::json!({"type": "P", "color": "w"}));
+        assert!(rows[6][4].is_null());
+        assert_eq!(rows.iter().flat_map(|r| r.as_array().unwrap()).filter(|c| c.is_null()).count(), 32);
+    }
+
     #[test]
     fn test_gamestate_undo_restores_hash_history() {
         let mut gs = GameState::new();
@@ -1040,6 +3288,203 @@ mod tests {
         assert_eq!(gs.hash(), h0);
     }
 
+    #[test]
+    fn test_gamestate_fen_history_and_repetitions() {
+        let mut gs = GameState::new();
+        assert_eq!(gs.fen_history_list(), [gs.fen()]);
+        assert_eq!(gs.repetition_count(), 1);
+        for (i, uci) in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"].into_iter().enumerate() {
+            assert!(gs.make_move_uci(uci));
+            assert_eq!(gs.fen_history_list().len(), i + 2);
+        }
+        assert_eq!(gs.repetition_count(), 2);
+        assert!(!gs.is_threefold_repetition());
+        assert!(gs.make_move_uci("f6g8"));
+        assert_eq!(gs.repetition_count(), 3);
+        assert!(gs.is_threefold_repetition());
+
+        let fens = gs.fen_history_list();
+        assert_eq!(fens[0], "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(fens[1], "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1");
+        assert_eq!(fens.last(), Some(&gs.fen()));
+        gs.undo();
+        assert_eq!(gs.fen_history_list(), fens[..8]);
+        assert_eq!(gs.repetition_count(), 2);
+    }
+
+    #[test]
+    fn test_gamestate_hash_hex() {
+        let mut gs = GameState::new();
+        let hash = gs.hash();
+        assert_eq!(gs.hash_hex(), format!("{:016x}", hash));
+        assert_eq!(gs.hash_hex().len(), 16);
+        assert_eq!((gs.hash_hi() as u64) << 32 | gs.hash_lo() as u64, hash);
+        assert_eq!(get_hash_hex(&Position::starting_position()), gs.hash_hex());
+
+        let start = gs.fen();
+        gs.make_move_uci("g1f3");
+        assert!(!gs.same_position(&start));
+        let mut other = GameState::new();
+        assert!(!gs.same_position_as(&other));
+        for uci in ["g8f6", "f3g1", "f6g8"] {
+            gs.make_move_uci(uci);
+        }
+        // Same position, different move counters
+        assert!(gs.same_position(&start));
+        assert!(gs.same_position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4 3"));
+        assert!(!gs.same_position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 4 3"));
+        assert!(!gs.same_position("not a fen"));
+        assert!(gs.same_position_as(&other));
+        other.make_move_uci("e2e4");
+        assert!(!gs.same_position_as(&other));
+    }
+
+    #[test]
+    fn test_gamestate_redo() {
+        let mut gs = GameState::new();
+        assert_eq!(gs.redo(), "");
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            assert!(gs.make_move_uci(uci));
+        }
+        let fen = gs.fen();
+        assert_eq!(gs.undo(), "g1f3");
+        assert_eq!(gs.undo(), "e7e5");
+        assert!(gs.can_redo());
+        assert_eq!(gs.redo(), "e7e5");
+        assert_eq!(gs.redo(), "g1f3");
+        assert!(!gs.can_redo());
+        assert_eq!(gs.fen(), fen);
+        assert_eq!(gs.history_list(), ["e2e4", "e7e5", "g1f3"]);
+
+        // Null moves come back too
+        assert!(gs.make_null_move());
+        gs.undo();
+        assert_eq!(gs.redo(), "0000");
+
+        // A different move starts a variation; redo follows the line last visited
+        gs.undo();
+        gs.undo();
+        assert!(gs.make_move_uci("b1c3"));
+        assert!(!gs.can_redo());
+        assert_eq!(gs.redo(), "");
+        gs.undo();
+        assert_eq!(gs.redo(), "b1c3");
+
+        // Loading a new position drops the tree
+        gs.undo();
+        assert!(gs.can_redo());
+        gs.reset();
+        assert!(!gs.can_redo());
+    }
+
+    #[test]
+    fn test_gamestate_variations() {
+        let mut gs = GameState::new();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            assert!(gs.make_move_uci(uci));
+        }
+        assert!(gs.goto_ply(1));
+        // 1. e4 c5 2. Nf3 as a variation
+        assert!(gs.make_move_uci("c7c5"));
+        assert!(gs.make_move_uci("g1f3"));
+        assert!(!gs.is_mainline());
+        assert_eq!(gs.mainline_list(), ["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(gs.total_plies(), 3);
+
+        assert!(gs.goto_ply(1));
+        assert_eq!(gs.continuations_list(), ["e7e5", "c7c5"]);
+        assert!(gs.goto_continuation(0));
+        assert_eq!(gs.siblings_list(), ["e7e5", "c7c5"]);
+        assert_eq!(gs.variation_index(), 0);
+        assert!(gs.is_mainline());
+        assert!(gs.goto_sibling(1));
+        assert_eq!(gs.history_list(), ["e2e4", "c7c5"]);
+        assert_eq!(gs.variation_index(), 1);
+        assert!(!gs.goto_sibling(2));
+        assert!(!gs.goto_continuation(1));
+
+        // Promote the Sicilian to the main line
+        assert_eq!(gs.redo(), "g1f3");
+        assert!(gs.promote_variation());
+        assert!(!gs.promote_variation());
+        assert!(gs.is_mainline());
+        assert_eq!(gs.mainline_list(), ["e2e4", "c7c5", "g1f3"]);
+
+        // Delete it again: back to the position before c5, 1... e5 is the main line
+        assert!(gs.goto_ply(2));
+        assert!(gs.delete_variation());
+        assert_eq!(gs.history_list(), ["e2e4"]);
+        assert_eq!(gs.continuations_list(), ["e7e5"]);
+        assert_eq!(gs.mainline_list(), ["e2e4", "e7e5", "g1f3"]);
+        assert!(gs.goto_ply(0));
+        assert!(gs.siblings_list().is_empty());
+        assert!(!gs.delete_variation());
+    }
+
+    #[test]
+    fn test_gamestate_goto_ply() {
+        let mut gs = GameState::new();
+        let start = gs.fen();
+        let mut fens = vec![start.clone()];
+        for uci in ["e2e4", "e7e5", "g1f3", "b8c6"] {
+            assert!(gs.make_move_uci(uci));
+            fens.push(gs.fen());
+        }
+        assert_eq!((gs.current_ply(), gs.total_plies()), (4, 4));
+
+        assert!(gs.goto_ply(1));
+        assert_eq!(gs.fen(), fens[1]);
+        assert_eq!((gs.current_ply(), gs.total_plies()), (1, 4));
+        // Look ahead and back without moving
+        for (ply, fen) in fens.iter().enumerate() {
+            assert_eq!(gs.fen_at(ply).as_ref(), Some(fen));
+        }
+        assert_eq!(gs.fen_at(5), None);
+        assert_eq!(gs.current_ply(), 1);
+
+        assert!(gs.goto_ply(4));
+        assert_eq!(gs.fen(), fens[4]);
+        assert!(gs.goto_ply(0));
+        assert_eq!(gs.fen(), start);
+        assert!(!gs.goto_ply(5));
+        assert_eq!(gs.current_ply(), 0);
+        assert!(gs.goto_ply(3));
+        assert_eq!(gs.history_list(), ["e2e4", "e7e5", "g1f3"]);
+    }
+
+    #[test]
+    fn test_gamestate_null_move() {
+        let mut gs = GameState::new();
+        gs.make_move_uci("e2e4");
+        let fen = gs.fen();
+        let h = gs.hash();
+
+        // Black passes: white to move again, ep square gone
+        assert!(!gs.undo_null());
+        assert!(gs.make_null_move());
+        assert_eq!(gs.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(gs.history_list(), ["e2e4", "0000"]);
+        gs.make_move_uci("d2d4");
+        assert_eq!(gs.history_san_list(), ["e4", "--", "d4"]);
+        assert_eq!(gs.move_count(), 3);
+
+        assert!(!gs.undo_null());
+        gs.undo();
+        assert!(gs.undo_null());
+        assert_eq!(gs.fen(), fen);
+        assert_eq!(gs.hash(), h);
+
+        // Generic undo handles the pass too
+        gs.make_null_move();
+        assert_eq!(gs.undo(), "0000");
+        assert_eq!(gs.hash(), h);
+
+        // No passing out of check
+        assert!(gs.load_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1"));
+        assert!(!gs.make_null_move());
+        assert!(gs.history_list().is_empty());
+    }
+
     #[test]
     fn test_gamestate_threefold_with_undo() {
         let mut gs = GameState::new();
@@ -1052,6 +3497,26 @@ mod tests {
         assert!(!gs.is_threefold_repetition());
     }
 
+    #[test]
+    fn test_make_move_uci_only_plays_legal_moves() {
+        let mut pos = Position::starting_position();
+        // King taking its own pawn, castling through pieces, non-ASCII text
+        for uci in ["e1e2", "e1g1", "e2e5", "e2\u{e9}4", "\u{e9}2e4", "e2"] {
+            assert!(!make_move_uci(&mut pos, uci), "{}", uci);
+        }
+        assert_eq!(pos.to_fen(), Position::starting_position().to_fen());
+        assert!(make_move_uci(&mut pos, "e2e4"));
+
+        // The move type comes from the legal move: en passant, promotions
+        let mut pos = Position::from_fen("4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(make_move_uci(&mut pos, "e5d6"));
+        assert_eq!(pos.piece_on(types::Square::from_algebraic("d5").unwrap()), None);
+        let mut pos = Position::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!make_move_uci(&mut pos, "b7b8"));
+        assert!(make_move_uci(&mut pos, "b7b8N"));
+        assert_eq!(pos.piece_on(types::Square::from_algebraic("b8").unwrap()), Some((types::Color::White, types::PieceType::Knight)));
+    }
+
     #[test]
     fn test_gamestate_perft_starting_pos() {
         let gs = GameState::new();
@@ -1070,69 +3535,449 @@ mod tests {
         assert_eq!(gs.perft(4), 4085603);
     }
 
+    #[test]
+    fn test_gamestate_move_info() {
+        let mut gs = GameState::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let moves = gs.legal_moves_report();
+        assert_eq!(moves.len(), count_legal_moves(&gs.position));
+        let find = |uci: &str| moves.iter().find(|m| m.uci == uci).unwrap().clone();
+
+        let castle = find("e1g1");
+        assert!(castle.is_castle && !castle.is_capture);
+        assert_eq!((castle.piece, castle.color, castle.san.as_str()), ("K", "w", "O-O"));
+
+        let ep = find("e5d6");
+        assert!(ep.is_en_passant && ep.is_capture);
+        assert_eq!(ep.captured, Some("P"));
+
+        let promo = find("b7a8q");
+        assert!(promo.is_promotion && promo.is_capture && promo.gives_check);
+        assert_eq!((promo.captured, promo.promotion), (Some("R"), Some("Q")));
+        assert_eq!(promo.san, "bxa8=Q+");
+
+        let json = serde_json::to_string(&find("a1a8")).unwrap();
+        assert!(json.contains(r#""from":"a1","to":"a8","piece":"R""#), "{}", json);
+        assert!(json.contains(r#""isCapture":true"#) && json.contains(r#""givesCheck":true"#), "{}", json);
+
+        assert_eq!(gs.last_move_report(), None);
+        assert!(gs.make_move_uci("e5d6"));
+        assert_eq!(gs.last_move_report(), Some(ep));
+        assert!(gs.make_null_move());
+        assert_eq!(gs.last_move_report(), None);
+    }
+
+    #[test]
+    fn test_gamestate_legal_moves_from() {
+        let gs = GameState::from_fen("1n2k3/P7/8/8/8/8/4P3/4K1N1 w - - 0 1").unwrap();
+        // e2 pawn: single and double step
+        let e2 = gs.legal_moves_from_report(4, 1);
+        let targets: Vec<&str> = e2.iter().map(|d| d.to.as_str()).collect();
+        assert_eq!(targets.len(), 2);
+        assert!(targets.contains(&"e3") && targets.contains(&"e4"));
+        assert!(e2.iter().all(|d| d.promotions.is_empty() && !d.must_promote));
+
+        // a7 pawn: push or capture, both promoting
+        let a7 = gs.legal_moves_from_report(0, 6);
+        assert_eq!(a7.len(), 2);
+        let capture = a7.iter().find(|d| d.to == "b8").unwrap();
+        assert!(capture.is_capture && capture.must_promote);
+        assert_eq!((capture.file, capture.rank), (1, 7));
+        assert_eq!(capture.promotions, ["q", "r", "b", "n"]);
+
+        // Empty square, enemy piece, off the board
+        assert!(gs.legal_moves_from_report(3, 3).is_empty());
+        assert!(gs.legal_moves_from_report(1, 7).is_empty());
+        assert!(gs.legal_moves_from_report(8, 0).is_empty());
+
+        // Early promotion: the pawn may also stay a pawn
+        let mut gs = GameState::from_fen("4k3/8/2P5/8/8/8/8/4K3 w - - 0 1").unwrap();
+        gs.set_variant("early-promotion");
+        let c6 = gs.legal_moves_from_report(2, 5);
+        assert_eq!(c6.len(), 1);
+        assert!(!c6[0].must_promote && c6[0].promotions.len() == 4);
+    }
+
+    #[test]
+    fn test_gamestate_attack_map() {
+        let gs = GameState::new();
+        let white = gs.attack_map_report("w");
+        // f3: pawns e2 and g2 and the g1 knight
+        assert_eq!(white.counts[5][5], 3);
+        // e4 and everything from rank 4 up is out of reach
+        assert!(white.counts[..5].iter().flatten().all(|&n| n == 0));
+        // Rank 3 and every square but a1 and h1 on ranks 1-2
+        assert_eq!(white.attacked, "0x0000000000ffff7e");
+
+        let both = gs.attack_map_report("");
+        assert_eq!(both.counts[5][5], 3);
+        assert_eq!(both.counts[2][2], gs.attack_map_report("b").counts[2][2]);
+        assert!(serde_json::to_string(&white).unwrap().starts_with(r#"{"counts":[[0,0,0,0,0,0,0,0],"#));
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_hints() {
+        // Mate in one: Qxf7#
+        let mut gs = GameState::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+        let hints = gs.hints_report(3, 3);
+        assert_eq!(hints.len(), 3);
+        assert_eq!((hints[0].mv.as_str(), hints[0].san.as_str()), ("f3f7", "Qxf7#"));
+        assert!(hints[0].score > hints[1].score);
+        assert!(hints.iter().all(|h| h.pv[0] == h.mv && h.pv.len() == h.pv_san.len()));
+        let json = serde_json::to_string(&hints[0]).unwrap();
+        assert!(json.starts_with(r#"{"move":"f3f7","san":"Qxf7#","score":"#), "{}", json);
+        assert!(json.contains(r#""pvSan":["Qxf7#"]"#), "{}", json);
+
+        let timed = gs.hints_timed_report(2, 50.0);
+        assert_eq!(timed.len(), 2);
+        assert_eq!(timed[0].mv, "f3f7");
+    }
+
     #[test]
     fn test_gamestate_perft_divide() {
         let gs = GameState::new();
-        let json = gs.perft_divide(1);
-        // Should contain 20 entries (one per legal move)
-        let entries: Vec<&str> = json.matches("[\"").collect();
+        let entries = gs.perft_divide_list(1);
+        // One entry per legal move
         assert_eq!(entries.len(), 20);
-        // Verify it's valid JSON-ish: starts with [[ and ends with ]]
-        assert!(json.starts_with("[["));
-        assert!(json.ends_with("]]"));
+        assert!(entries.iter().all(|(_, nodes)| *nodes == 1));
+        assert!(serde_json::to_string(&entries).unwrap().starts_with("[[\""));
     }
 
     #[test]
     fn test_gamestate_perft_divide_depth2() {
         let gs = GameState::new();
-        let json = gs.perft_divide(2);
+        let entries = gs.perft_divide_list(2);
         // Should still have 20 root moves
-        let entries: Vec<&str> = json.matches("[\"").collect();
         assert_eq!(entries.len(), 20);
         // Total nodes from divide should equal perft(2) = 400
-        // Parse manually: extract numbers after commas
-        let total: u64 = json.split(',')
-            .filter_map(|s| {
-                let s = s.trim().trim_matches(|c| c == '[' || c == ']');
-                s.parse::<u64>().ok()
-            })
-            .sum();
+        let total: u64 = entries.iter().map(|(_, nodes)| nodes).sum();
         assert_eq!(total, 400);
     }
 
     #[test]
-    fn test_gamestate_search_timed() {
+    fn test_gamestate_perft_detailed() {
         let gs = GameState::new();
-        let json = gs.search_timed(500.0); // 500ms budget
-        // Should contain all expected fields
-        assert!(json.contains("\"bestMove\""), "Missing bestMove: {}", json);
-        assert!(json.contains("\"score\""), "Missing score: {}", json);
-        assert!(json.contains("\"depth\""), "Missing depth: {}", json);
-        assert!(json.contains("\"nodes\""), "Missing nodes: {}", json);
-        assert!(json.contains("\"timeMs\""), "Missing timeMs: {}", json);
-        assert!(json.contains("\"nps\""), "Missing nps: {}", json);
+        let stats = gs.perft_detailed_list(3);
+        assert_eq!(stats.iter().map(|s| s.nodes).collect::<Vec<_>>(), [20, 400, 8902]);
+        assert_eq!((stats[2].captures, stats[2].checks, stats[2].checkmates), (34, 12, 0));
+        let json = serde_json::to_value(stats[2]).unwrap();
+        assert_eq!(json["enPassants"], 0);
+        assert_eq!(json["checks"], 12);
+
+        // Variant rules show up in the counts: a pawn reaching the 6th rank
+        // may promote in early-promotion
+        let mut gs = GameState::from_fen("4k3/8/8/2P5/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(gs.perft_detailed_list(1)[0].promotions, 0);
+        assert!(gs.set_variant("early-promotion"));
+        assert!(gs.perft_detailed_list(1)[0].promotions > 0);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_search_timed() {
+        let mut gs = GameState::new();
+        let report = gs.search_timed_report(500.0); // 500ms budget
         // bestMove should not be null for starting position
-        assert!(!json.contains("\"bestMove\":null"), "bestMove should exist: {}", json);
+        assert!(report.best_move.is_some());
+        assert!(report.depth > 0 && report.nodes > 0);
+        // Serialized with the camelCase names JavaScript sees
+        let json = serde_json::to_value(&report).unwrap();
+        for key in ["bestMove", "score", "depth", "nodes", "timeMs", "nps"] {
+            assert!(json.get(key).is_some(), "Missing {}: {}", key, json);
+        }
+        assert!(json["bestMove"].is_string() && json["nodes"].is_u64());
     }
 
+    #[cfg(feature = "engine")]
     #[test]
     fn test_gamestate_search_depth_json() {
-        let gs = GameState::new();
-        let json = gs.search_depth(3);
-        assert!(json.contains("\"bestMove\""), "Missing bestMove: {}", json);
-        assert!(json.contains("\"depth\":3"), "Should report depth 3: {}", json);
-        assert!(json.contains("\"nodes\""), "Missing nodes: {}", json);
+        let mut gs = GameState::new();
+        let report = gs.search_depth_report(3);
+        assert!(report.best_move.is_some());
+        assert_eq!(report.depth, 3);
+        assert!(report.nodes > 0);
     }
 
+    #[cfg(feature = "engine")]
     #[test]
     fn test_search_timed_respects_budget() {
-        let gs = GameState::new();
-        let json = gs.search_timed(100.0); // 100ms budget
-        // Parse timeMs — should be roughly within budget (with some overhead)
-        let time_start = json.find("\"timeMs\":").unwrap() + 9;
-        let time_end = json[time_start..].find(|c: char| c == ',' || c == '}').unwrap() + time_start;
-        let time_ms: f64 = json[time_start..time_end].parse().unwrap();
+        let mut gs = GameState::new();
+        let time_ms = gs.search_timed_report(100.0).time_ms; // 100ms budget
         // Should finish within ~2x the budget (overhead from last depth completing)
         assert!(time_ms < 5000.0, "Took too long: {}ms", time_ms);
     }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_search_json_reports_tuning_stats() {
+        let mut gs = GameState::new();
+        let report = gs.search_depth_report(4);
+        let json = serde_json::to_value(&report).unwrap();
+        for key in [
+            "seldepth", "qnodes", "ttProbes", "ttHits", "ttCutoffs", "ttStores", "betaCutoffs",
+            "firstMoveCutoffRate", "nullMoveTries", "nullMoveCutoffs", "lmrReductions", "lmrResearches",
+        ] {
+            assert!(json.get(key).is_some(), "Missing {}: {}", key, json);
+        }
+        assert!(report.seldepth >= 4, "seldepth below depth: {:?}", report);
+        assert!(report.qnodes <= report.nodes);
+        assert!(report.beta_cutoffs > 0);
+        assert!(report.tt_stores > 0);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_personality() {
+        let mut gs = GameState::new();
+        assert_eq!(gs.personality(), "balanced");
+        assert!(gs.set_personality("Gambiteer"));
+        assert_eq!(gs.personality(), "gambiteer");
+        assert!(!gs.set_personality("reckless"));
+        assert_eq!(gs.personality(), "gambiteer");
+        assert!(gs.best_move(3).is_some());
+        assert!(personality_names().contains(&"materialist"));
+    }
+
+    #[test]
+    fn test_gamestate_variant() {
+        let mut gs = GameState::new();
+        assert_eq!(gs.variant(), "standard");
+        assert!(gs.set_variant("Standard"));
+        assert!(!gs.set_variant("bughouse"));
+        assert_eq!(gs.variant(), "standard");
+        assert!(gs.load_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(gs.variant(), "standard");
+        assert_eq!(variant_names(), ["standard", "early-promotion", "capture-limited", "three-check"]);
+
+        // Early promotion: c6-c7 may stay a pawn or promote
+        assert!(gs.load_fen("4k3/8/2P5/8/8/8/8/4K3 w - - 0 1"));
+        assert!(gs.set_variant("early-promotion"));
+        assert!(gs.is_legal_uci("c6c7") && gs.is_legal_uci("c6c7n"));
+        assert!(gs.make_move_san("c7=Q"));
+        assert_eq!(gs.variant(), "early-promotion");
+
+        // A PGN Variant tag sets the rules
+        gs.set_variant("standard");
+        assert!(gs.load_pgn("[Variant \"Early-Promotion\"]\n[FEN \"4k3/8/8/2P5/8/8/8/4K3 w - - 0 1\"]\n1. c6=N *").is_ok());
+        assert_eq!(gs.variant(), "early-promotion");
+        assert!(gs.load_pgn("[Variant \"Crazyhouse\"]\n1. e4 *").is_err());
+        assert_eq!(gs.history_list(), ["c5c6n"]);
+    }
+
+    #[test]
+    fn test_gamestate_promotion_rules() {
+        let mut gs = GameState::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(gs.promotion_rules(), "qrbn");
+        assert!(gs.set_promotion_rules("N"));
+        assert_eq!(gs.promotion_rules(), "n");
+        assert!(!gs.is_legal_uci("a7a8q"));
+        assert!(!gs.make_move_uci("a7a8q"));
+        assert_eq!(count_legal_moves(&gs.position), 1 + 5);
+        assert!(gs.make_move_san("a8=N"));
+        gs.undo();
+
+        assert!(!gs.set_promotion_rules(""));
+        assert!(!gs.set_promotion_rules("qp"));
+        assert!(gs.set_promotion_rules("rbnk"));
+        assert!(gs.make_move_uci("a7a8k"));
+        assert_eq!(gs.fen(), "K3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(gs.history_san_list(), ["a8=K"]);
+
+        // Rules survive loading a new position
+        assert!(gs.load_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(gs.promotion_rules(), "rbnk");
+    }
+
+    #[test]
+    fn test_gamestate_capture_limited() {
+        let mut gs = GameState::from_fen("r2rk3/1P6/8/8/3Q4/8/PPPPPPP1/RNB1KBNR w KQq - 0 1").unwrap();
+        assert!(gs.set_variant("capture-limited"));
+        let lost = gs.lost_pieces_report();
+        assert_eq!((lost.w, lost.b), (PieceCounts::default(), PieceCounts::default()));
+        // Nothing lost yet: the b7 pawn can't promote
+        assert!(!gs.is_legal_uci("b7b8q") && !gs.is_legal_uci("b7a8q"));
+
+        // After losing the queen, a pawn may bring it back
+        assert!(gs.make_move_uci("d4d5") && gs.make_move_uci("d8d5"));
+        assert_eq!(gs.lost_pieces_report().w, PieceCounts { q: 1, ..Default::default() });
+        assert!(gs.is_legal_uci("b7b8q") && gs.is_legal_uci("b7a8q"));
+        assert!(!gs.is_legal_uci("b7b8r") && !gs.is_legal_uci("b7b8n"));
+        assert!(gs.make_move_uci("b7a8q"));
+        let lost = gs.lost_pieces_report();
+        assert_eq!((lost.w, lost.b), (PieceCounts::default(), PieceCounts { r: 1, ..Default::default() }));
+        gs.undo();
+        assert_eq!(gs.lost_pieces_report().w, PieceCounts { q: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn test_gamestate_lost_pieces_from_odds_fen() {
+        // Knight odds: the missing b1 knight was never captured
+        let mut gs = GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(gs.lost_pieces_report().w, PieceCounts::default());
+        // A promotion with the queen still on the board loses nothing
+        assert!(gs.load_fen("4k3/P7/8/8/8/8/8/3QK3 w - - 0 1"));
+        assert!(gs.make_move_uci("a7a8q"));
+        assert_eq!(gs.lost_pieces_report(), BySide { w: PieceCounts::default(), b: PieceCounts::default() });
+        // Captures count from there
+        assert!(gs.load_fen("4k3/8/8/8/8/8/1n6/3QK3 w - - 0 1"));
+        assert!(gs.make_move_uci("d1b1") && gs.make_move_uci("b2d1"));
+        assert!(gs.make_move_uci("b1d1"));
+        assert_eq!(gs.lost_pieces_report().b, PieceCounts { n: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn test_gamestate_three_check() {
+        let mut gs = GameState::new();
+        assert!(gs.set_variant("three-check"));
+        assert_eq!(gs.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0");
+        for san in ["e4", "e5", "Bb5", "Nc6", "Bxc6", "dxc6"] {
+            assert!(gs.make_move_san(san), "{}", san);
+        }
+        assert!(gs.make_move_san("Qh5"));
+        assert!(gs.make_move_san("Qd7"));
+        assert!(gs.make_move_san("Qxf7+"));
+        assert_eq!(gs.checks_given_report(), BySide { w: 1, b: 0 });
+        assert!(gs.fen().ends_with(" +1+0"));
+        let hash = gs.position.hash();
+        assert_eq!(Position::from_fen(&gs.fen()).unwrap().hash(), hash);
+        assert_ne!(Position::from_fen(&gs.fen().replace("+1+0", "+0+0")).unwrap().hash(), hash);
+        gs.undo();
+        assert_eq!(gs.checks_given_report(), BySide { w: 0, b: 0 });
+
+        // Lichess-style remaining counts; the field starts a three-check game
+        let mut gs = GameState::new();
+        assert!(gs.load_fen("4k3/8/8/8/8/6K1/8/7R w - - 1+3 0 1"));
+        assert_eq!(gs.variant(), "three-check");
+        assert!(gs.make_move_uci("h1h8"));
+        assert_eq!(gs.status(), "three_check");
+        assert!(gs.is_game_over());
+        assert!(!gs.load_fen("4k3/8/8/8/8/6K1/8/7R w - - 0 1 +x+0"));
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_search_json_reports_wdl() {
+        // White is a queen up: the side to move should be close to a certain win
+        let mut gs = GameState::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let report = gs.search_depth_report(3);
+        assert_eq!(report.wdl, [1000, 0, 0]);
+        assert!(report.win_probability > 0.999, "{:?}", report);
+        assert_eq!(score_to_win_probability(0), 0.5);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_search_clock() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        // Black's clock counts: a second and a half for the game is a short think
+        let report = gs.search_clock_report(600_000.0, 1_500.0, 0.0, 0.0);
+        assert!(report.best_move.is_some());
+        assert!(report.time_ms < 600.0, "Used {}ms of 1450", report.time_ms);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_tt_persists_between_searches() {
+        let mut gs = GameState::new();
+        assert_eq!(gs.tt_stats_report().stores, 0);
+        gs.search_depth_report(4);
+        let stores = gs.tt_stats_report().stores;
+        assert!(stores > 0);
+        assert_eq!(gs.tt_stats_report().generation, 1);
+
+        // Same position again: the second search should find the entries of the first
+        assert!(gs.search_depth_report(4).best_move.is_some());
+        assert!(gs.tt_stats_report().hits > 0);
+        assert_eq!(gs.tt_stats_report().generation, 2);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_clear_tt() {
+        let mut gs = GameState::new();
+        gs.search_depth_report(3);
+        assert!(gs.tt_stats_report().stores > 0);
+        gs.clear_tt();
+        let stats = gs.tt_stats_report();
+        assert_eq!(stats.stores, 0);
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.generation, 0);
+        assert_eq!(stats.utilization, 0.0, "TT should be empty: {:?}", stats);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_reset_clears_tt() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        gs.search_depth_report(3);
+        gs.reset();
+        assert_eq!(gs.tt_stats_report().stores, 0);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_shared_tt() {
+        let shared = SharedTT::new(14);
+        assert_eq!(SharedTT::open(shared.id()).map(|t| t.capacity()), Some(1 << 14));
+        let mut a = GameState::new();
+        let mut b = GameState::new();
+        a.use_shared_tt(&shared);
+        b.use_shared_tt(&shared);
+        assert_eq!(b.shared_tt_id(), Some(shared.id()));
+
+        // b's search finds what a's left in the table
+        a.search_depth_report(4);
+        assert!(shared.utilization() > 0.0);
+        shared.new_search();
+        b.search_depth_report(4);
+        assert!(b.tt_stats_report().hits > 0);
+
+        b.use_own_tt();
+        assert_eq!(b.shared_tt_id(), None);
+        assert_eq!(b.tt_stats_report().stores, 0);
+        shared.clear();
+        assert_eq!(shared.utilization(), 0.0);
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_ponder_hit() {
+        let mut gs = GameState::new();
+        assert!(gs.make_move_uci("e2e4"));
+        assert!(!gs.is_pondering());
+        assert_eq!(gs.ponder_report(10.0), None);
+
+        assert!(gs.start_ponder("e7e5"));
+        assert!(gs.is_pondering());
+        assert_eq!(gs.ponder_move(), Some("e7e5".to_string()));
+        let report = gs.ponder_report(50.0).unwrap();
+        assert!(report.best_move.is_some(), "Ponder should find a reply");
+        assert!(gs.tt_stats_report().stores > 0);
+
+        let report = gs.ponderhit_report(50.0).unwrap();
+        assert!(report.best_move.is_some(), "Ponderhit should search");
+        assert_eq!(gs.history_list(), ["e2e4", "e7e5"]);
+        assert!(!gs.is_pondering());
+    }
+
+    #[cfg(feature = "engine")]
+    #[test]
+    fn test_gamestate_ponder_miss() {
+        let mut gs = GameState::new();
+        assert!(!gs.start_ponder("e2e5"), "Illegal predictions are rejected");
+        assert!(gs.start_ponder("e2e4"));
+
+        // A different move was played: the prediction no longer applies
+        assert!(gs.make_move_uci("d2d4"));
+        assert!(!gs.is_pondering());
+        assert_eq!(gs.ponder_move(), None);
+        assert_eq!(gs.ponderhit_report(10.0), None);
+        assert!(!gs.stop_ponder());
+
+        assert!(gs.start_ponder("d7d5"));
+        assert!(gs.stop_ponder());
+        assert!(!gs.is_pondering());
+    }
 }
@@ -4,6 +4,11 @@
 // The key insight: for a rook/bishop on a square, only the pieces BETWEEN
 // the piece and the edge matter for blocking. We can hash all 2^n possible
 // blocker configurations to precomputed attack bitboards.
+//
+// Without the `magic` feature no tables are built: attacks are traced square
+// by square with the same functions the tables are filled from.
+
+#![cfg_attr(not(feature = "magic"), allow(dead_code))]
 
 use crate::bitboard::Bitboard;
 use crate::types::Square;
@@ -331,33 +336,64 @@ fn magic_index(blockers: Bitboard, magic: u64, bits: u8) -> usize {
 // GLOBAL INSTANCE (initialized once)
 // =============================================================================
 
+#[cfg(feature = "magic")]
 use std::sync::OnceLock;
 
+#[cfg(feature = "magic")]
 static MAGIC_TABLES: OnceLock<MagicTables> = OnceLock::new();
 
 /// Get the global magic tables (initializes on first call)
+#[cfg(feature = "magic")]
 pub fn magic_tables() -> &'static MagicTables {
     MAGIC_TABLES.get_or_init(MagicTables::new)
 }
 
 /// Get rook attacks
 #[inline]
+#[cfg(feature = "magic")]
 pub fn rook_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
     magic_tables().rook_attacks(sq, occupied)
 }
 
 /// Get bishop attacks
 #[inline]
+#[cfg(feature = "magic")]
 pub fn bishop_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
     magic_tables().bishop_attacks(sq, occupied)
 }
 
 /// Get queen attacks
 #[inline]
+#[cfg(feature = "magic")]
 pub fn queen_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
     magic_tables().queen_attacks(sq, occupied)
 }
 
+// =============================================================================
+// WITHOUT TABLES (no `magic` feature)
+// =============================================================================
+
+/// Get rook attacks
+#[inline]
+#[cfg(not(feature = "magic"))]
+pub fn rook_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    rook_attacks_slow(sq, occupied)
+}
+
+/// Get bishop attacks
+#[inline]
+#[cfg(not(feature = "magic"))]
+pub fn bishop_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks_slow(sq, occupied)
+}
+
+/// Get queen attacks
+#[inline]
+#[cfg(not(feature = "magic"))]
+pub fn queen_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    rook_attacks_slow(sq, occupied) | bishop_attacks_slow(sq, occupied)
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -1,11 +1,12 @@
 // Move Generation Module
 // Generates all legal moves for a position
 
-use crate::attacks::{knight_attacks, king_attacks, pawn_attacks};
+use crate::attacks::{between, knight_attacks, king_attacks, line, pawn_attacks};
 use crate::magic::{rook_attacks, bishop_attacks, queen_attacks};
 use crate::bitboard::Bitboard;
 use crate::position::Position;
 use crate::types::{CastlingRights, Color, Move, PieceType, Square};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 // =============================================================================
 // MOVE LIST - Stack-allocated for speed
@@ -63,33 +64,134 @@ impl Default for MoveList {
 // MOVE GENERATION
 // =============================================================================
 
+/// Which moves a generator produces
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GenType {
+    All,
+    /// Moves that take a piece: captures, capturing promotions, en passant
+    Captures,
+    /// Everything else: pushes, push promotions, castling
+    Quiets,
+    /// Replies to check: king moves, captures of the checker, blocks
+    Evasions,
+}
+
 /// Generate all pseudo-legal moves (doesn't check if king is left in check)
 pub fn generate_pseudo_legal_moves(pos: &Position) -> MoveList {
+    generate(pos, GenType::All)
+}
+
+/// Generate pseudo-legal captures only (including en passant and capturing
+/// promotions), for quiescence search and staged move ordering
+pub fn generate_captures(pos: &Position) -> MoveList {
+    generate(pos, GenType::Captures)
+}
+
+/// Generate pseudo-legal non-captures only (including castling and push
+/// promotions). Together with `generate_captures` this is every pseudo-legal move.
+pub fn generate_quiets(pos: &Position) -> MoveList {
+    generate(pos, GenType::Quiets)
+}
+
+/// Generate pseudo-legal replies to check: king moves, and with a single
+/// checker also captures of it and interpositions. Every legal move of a
+/// side in check is among them. Use only when in check.
+pub fn generate_evasions(pos: &Position) -> MoveList {
+    generate(pos, GenType::Evasions)
+}
+
+fn generate(pos: &Position, gen: GenType) -> MoveList {
     let mut moves = MoveList::new();
     let us = pos.side_to_move();
-    
-    generate_pawn_moves(pos, us, &mut moves);
-    generate_knight_moves(pos, us, &mut moves);
-    generate_bishop_moves(pos, us, &mut moves);
-    generate_rook_moves(pos, us, &mut moves);
-    generate_queen_moves(pos, us, &mut moves);
-    generate_king_moves(pos, us, &mut moves);
-    
+    let friendly = pos.occupied_by(us);
+    let enemies = pos.occupied_by(us.flip());
+    let empty = !pos.occupied();
+
+    let (targets, push_targets, capture_targets) = match gen {
+        GenType::All => (!friendly, empty, enemies),
+        GenType::Captures => (enemies, Bitboard::EMPTY, enemies),
+        GenType::Quiets => (empty, empty, Bitboard::EMPTY),
+        GenType::Evasions => {
+            let checkers = pos.checkers();
+            let king_sq = pos.pieces(us, PieceType::King).lsb();
+            if let (Some(king_sq), Some(checker)) = (king_sq, checkers.lsb()) {
+                // The king has to move out of a double check
+                if checkers.count() > 1 {
+                    generate_king_moves(pos, us, !friendly, false, &mut moves);
+                    return moves;
+                }
+                let block = between(king_sq, checker);
+                (checkers | block, block, checkers)
+            } else {
+                (!friendly, empty, enemies)
+            }
+        }
+    };
+
+    generate_pawn_moves(pos, us, push_targets, capture_targets, &mut moves);
+    generate_knight_moves(pos, us, targets, &mut moves);
+    generate_bishop_moves(pos, us, targets, &mut moves);
+    generate_rook_moves(pos, us, targets, &mut moves);
+    generate_queen_moves(pos, us, targets, &mut moves);
+    if gen == GenType::Evasions {
+        // The king steps anywhere (legality is checked later), never castles
+        generate_king_moves(pos, us, !friendly, false, &mut moves);
+    } else {
+        generate_king_moves(pos, us, targets, gen != GenType::Captures, &mut moves);
+    }
+
     moves
 }
 
-/// Generate all legal moves (filters out moves that leave king in check)
-pub fn generate_legal_moves(pos: &mut Position) -> MoveList {
-    let pseudo_legal = generate_pseudo_legal_moves(pos);
+/// Generate all legal moves. Pseudo-legal moves are filtered using the pinned
+/// pieces and the squares the king may step to, without making any of them.
+pub fn generate_legal_moves(pos: &Position) -> MoveList {
     let mut legal = MoveList::new();
-    
-    for m in pseudo_legal.iter() {
-        if let Some(undo) = pos.make_move(*m) {
-            legal.push(*m);
-            pos.unmake_move(*m, &undo);
+    let us = pos.side_to_move();
+    let them = us.flip();
+    let king_sq = match pos.pieces(us, PieceType::King).lsb() {
+        Some(sq) => sq,
+        None => return legal,
+    };
+
+    // Pins and evasions assume one king; with more, try each move on a copy
+    if pos.has_extra_kings(us) {
+        for &m in generate_pseudo_legal_moves(pos).iter() {
+            if pos.clone().make_move(m).is_some() {
+                legal.push(m);
+            }
         }
+        return legal;
     }
-    
+
+    let pseudo_legal = if pos.checkers().is_not_empty() {
+        generate_evasions(pos)
+    } else {
+        generate_pseudo_legal_moves(pos)
+    };
+    let pinned = pos.pinned(us);
+    // Sliders see through the king when it steps away from them
+    let without_king = pos.occupied() & !Bitboard::from_square(king_sq);
+
+    for &m in pseudo_legal.iter() {
+        let (from, to) = (m.from(), m.to());
+        let is_legal = if from == king_sq {
+            // Castling squares are checked by the generator
+            m.is_castling() || !pos.is_attacked_with(to, them, without_king, Bitboard::from_square(to))
+        } else if m.is_en_passant() {
+            // Two pawns leave the rank at once, so test the resulting board
+            let captured = Bitboard::from_square(Square::new(if us == Color::White { to.0 - 8 } else { to.0 + 8 }));
+            let occupied = (pos.occupied() & !Bitboard::from_square(from) & !captured) | Bitboard::from_square(to);
+            !pos.is_attacked_with(king_sq, them, occupied, captured)
+        } else {
+            // A pinned piece may only move along the pin
+            !pinned.has(from) || line(king_sq, from).has(to)
+        };
+        if is_legal {
+            legal.push(m);
+        }
+    }
+
     legal
 }
 
@@ -137,22 +239,173 @@ pub fn perft_divide(pos: &mut Position, depth: u32) -> Vec<(String, u64)> {
     results
 }
 
+/// Counts of one perft depth, as in the chessprogramming.org Perft Results
+/// tables: the moves made at that depth and what they were
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerftStats {
+    pub depth: u32,
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    /// Checks by a piece other than the one that moved (double checks included)
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+/// Perft counting move kinds, checks and mates: one `PerftStats` for each
+/// depth from 1 to `depth`, from a single walk of the tree
+pub fn perft_detailed(pos: &mut Position, depth: u32) -> Vec<PerftStats> {
+    let mut stats: Vec<PerftStats> = (1..=depth).map(|depth| PerftStats { depth, ..Default::default() }).collect();
+    perft_detailed_walk(pos, &mut stats);
+    stats
+}
+
+fn perft_detailed_walk(pos: &mut Position, stats: &mut [PerftStats]) {
+    let Some((here, deeper)) = stats.split_first_mut() else { return };
+    let us = pos.side_to_move();
+    for m in generate_legal_moves(pos).iter() {
+        let before = pos.occupied_by(us);
+        let Some(undo) = pos.make_move(*m) else { continue };
+        here.nodes += 1;
+        here.captures += undo.captured.is_some() as u64;
+        here.en_passants += m.is_en_passant() as u64;
+        here.castles += m.is_castling() as u64;
+        here.promotions += m.is_promotion() as u64;
+
+        let checkers = pos.checkers();
+        if checkers.is_not_empty() {
+            // Squares our pieces moved to: the mover, and the rook when castling
+            let moved = pos.occupied_by(us) & !before;
+            here.checks += 1;
+            here.discovered_checks += (checkers & !moved).is_not_empty() as u64;
+            here.double_checks += (checkers.count() > 1) as u64;
+            here.checkmates += generate_legal_moves(pos).is_empty() as u64;
+        }
+
+        perft_detailed_walk(pos, deeper);
+        pos.unmake_move(*m, &undo);
+    }
+}
+
+// =============================================================================
+// PERFT HASHING - Subtree counts cached by (position, depth)
+// =============================================================================
+
+/// Leaf counts of subtrees already walked, keyed by Zobrist hash and depth.
+/// Entries are two atomic words (key ^ nodes, nodes) as in `tt::SharedTable`,
+/// so one table can be shared by parallel perft threads: a torn write fails
+/// the key check and reads as a miss.
+pub struct PerftTable {
+    words: Box<[AtomicU64]>,
+    mask: usize,
+}
+
+impl PerftTable {
+    /// A table of 2^`size_power` entries, 16 bytes each
+    pub fn new(size_power: u8) -> Self {
+        let capacity = 1usize << size_power;
+        PerftTable { words: (0..capacity * 2).map(|_| AtomicU64::new(0)).collect(), mask: capacity - 1 }
+    }
+
+    /// The hash and depth folded into one key
+    fn key(hash: u64, depth: u32) -> u64 {
+        hash ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn probe(&self, hash: u64, depth: u32) -> Option<u64> {
+        let key = Self::key(hash, depth);
+        let index = (key as usize & self.mask) * 2;
+        let check = self.words[index].load(Ordering::Relaxed);
+        let nodes = self.words[index + 1].load(Ordering::Relaxed);
+        (nodes != 0 && check ^ nodes == key).then_some(nodes)
+    }
+
+    fn store(&self, hash: u64, depth: u32, nodes: u64) {
+        let key = Self::key(hash, depth);
+        let index = (key as usize & self.mask) * 2;
+        self.words[index].store(key ^ nodes, Ordering::Relaxed);
+        self.words[index + 1].store(nodes, Ordering::Relaxed);
+    }
+}
+
+/// `perft` that looks up and stores subtree counts in `table`. Much faster
+/// for deep runs, as transpositions are counted once; use plain `perft` to
+/// check move generation without trusting the hashing.
+pub fn perft_hashed(pos: &mut Position, depth: u32, table: &PerftTable) -> u64 {
+    if depth <= 1 {
+        return perft(pos, depth);
+    }
+    if let Some(nodes) = table.probe(pos.hash(), depth) {
+        return nodes;
+    }
+
+    let mut nodes: u64 = 0;
+    for m in generate_legal_moves(pos).iter() {
+        if let Some(undo) = pos.make_move(*m) {
+            nodes += perft_hashed(pos, depth - 1, table);
+            pos.unmake_move(*m, &undo);
+        }
+    }
+    table.store(pos.hash(), depth, nodes);
+    nodes
+}
+
+/// Perft divide with the root moves split across rayon threads, each on its
+/// own copy of the position, sharing `table` when given (native builds only)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn perft_divide_parallel(pos: &Position, depth: u32, table: Option<&PerftTable>) -> Vec<(String, u64)> {
+    use rayon::prelude::*;
+
+    let moves: Vec<Move> = generate_legal_moves(pos).iter().copied().collect();
+    let mut results: Vec<(String, u64)> = moves
+        .par_iter()
+        .filter_map(|&m| {
+            let mut child = pos.clone();
+            child.make_move(m)?;
+            let nodes = match (depth, table) {
+                (0 | 1, _) => 1,
+                (_, Some(table)) => perft_hashed(&mut child, depth - 1, table),
+                (_, None) => perft(&mut child, depth - 1),
+            };
+            Some((m.to_uci(), nodes))
+        })
+        .collect();
+
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    results
+}
+
+/// `perft` with the root moves split across rayon threads (native builds only)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn perft_parallel(pos: &Position, depth: u32, table: Option<&PerftTable>) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    perft_divide_parallel(pos, depth, table).iter().map(|(_, nodes)| nodes).sum()
+}
+
 // =============================================================================
 // PAWN MOVE GENERATION
 // =============================================================================
 
-fn generate_pawn_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+/// Pawn moves pushing to `push_targets` (empty squares) or capturing on
+/// `capture_targets` (enemy pieces; en passant counts as taking the passed pawn)
+fn generate_pawn_moves(pos: &Position, us: Color, push_targets: Bitboard, capture_targets: Bitboard, moves: &mut MoveList) {
     let pawns = pos.pieces(us, PieceType::Pawn);
-    let occupied = pos.occupied();
-    let empty = !occupied;
-    let enemies = pos.occupied_by(us.flip());
+    let empty = !pos.occupied();
     
     let is_white = us == Color::White;
     
     // Direction pawns move
     let push_dir: i8 = if is_white { 8 } else { -8 };
-    let start_rank = if is_white { Bitboard::RANK_2 } else { Bitboard::RANK_7 };
-    let promo_rank = if is_white { Bitboard::RANK_8 } else { Bitboard::RANK_1 };
+    let promo_rank = pos.variant().promotion_squares(us);
+    let forced_promo = pos.variant().forced_promotion_squares(us);
+    let promo_pieces = pos.variant().promotion_pieces(pos, us);
     
     // Single pawn pushes
     let single_pushes = if is_white {
@@ -169,23 +422,25 @@ fn generate_pawn_moves(pos: &Position, us: Color, moves: &mut MoveList) {
     };
     
     // Process single pushes
-    let mut pushes = single_pushes;
+    let mut pushes = single_pushes & push_targets;
     while let Some(to) = pushes.pop_lsb() {
         let from = Square::new((to.0 as i8 - push_dir) as u8);
         
         if (Bitboard::from_square(to) & promo_rank).is_not_empty() {
             // Promotion!
-            moves.push(Move::new_promotion(from, to, PieceType::Queen));
-            moves.push(Move::new_promotion(from, to, PieceType::Rook));
-            moves.push(Move::new_promotion(from, to, PieceType::Bishop));
-            moves.push(Move::new_promotion(from, to, PieceType::Knight));
+            for piece in promo_pieces.iter() {
+                moves.push(Move::new_promotion(from, to, piece));
+            }
+            if !forced_promo.has(to) {
+                moves.push(Move::new(from, to));
+            }
         } else {
             moves.push(Move::new(from, to));
         }
     }
     
     // Process double pushes
-    let mut doubles = double_pushes;
+    let mut doubles = double_pushes & push_targets;
     while let Some(to) = doubles.pop_lsb() {
         let from = Square::new((to.0 as i8 - push_dir * 2) as u8);
         moves.push(Move::new(from, to));
@@ -194,16 +449,18 @@ fn generate_pawn_moves(pos: &Position, us: Color, moves: &mut MoveList) {
     // Pawn captures
     let mut pawn_bb = pawns;
     while let Some(from) = pawn_bb.pop_lsb() {
-        let attacks = pawn_attacks(from, is_white) & enemies;
+        let attacks = pawn_attacks(from, is_white) & capture_targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
             if (Bitboard::from_square(to) & promo_rank).is_not_empty() {
                 // Capture with promotion
-                moves.push(Move::new_promotion(from, to, PieceType::Queen));
-                moves.push(Move::new_promotion(from, to, PieceType::Rook));
-                moves.push(Move::new_promotion(from, to, PieceType::Bishop));
-                moves.push(Move::new_promotion(from, to, PieceType::Knight));
+                for piece in promo_pieces.iter() {
+                    moves.push(Move::new_promotion(from, to, piece));
+                }
+                if !forced_promo.has(to) {
+                    moves.push(Move::new(from, to));
+                }
             } else {
                 moves.push(Move::new(from, to));
             }
@@ -211,7 +468,10 @@ fn generate_pawn_moves(pos: &Position, us: Color, moves: &mut MoveList) {
     }
     
     // En passant
-    if let Some(ep_sq) = pos.en_passant_square() {
+    if let Some(ep_sq) = pos.en_passant_square().filter(|ep| {
+        let passed_pawn = Square::new((ep.0 as i8 - push_dir) as u8);
+        capture_targets.has(passed_pawn)
+    }) {
         let ep_bb = Bitboard::from_square(ep_sq);
         
         // Find pawns that can capture en passant
@@ -238,14 +498,13 @@ fn generate_pawn_moves(pos: &Position, us: Color, moves: &mut MoveList) {
 // KNIGHT MOVE GENERATION
 // =============================================================================
 
-fn generate_knight_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+fn generate_knight_moves(pos: &Position, us: Color, targets: Bitboard, moves: &mut MoveList) {
     let knights = pos.pieces(us, PieceType::Knight);
-    let friendly = pos.occupied_by(us);
     
     let mut knight_bb = knights;
     while let Some(from) = knight_bb.pop_lsb() {
-        // Get all squares this knight attacks, excluding friendly pieces
-        let attacks = knight_attacks(from) & !friendly;
+        // Get all squares this knight attacks, limited to the wanted targets
+        let attacks = knight_attacks(from) & targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
@@ -258,13 +517,12 @@ fn generate_knight_moves(pos: &Position, us: Color, moves: &mut MoveList) {
 // KING MOVE GENERATION
 // =============================================================================
 
-fn generate_king_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+fn generate_king_moves(pos: &Position, us: Color, targets: Bitboard, castling: bool, moves: &mut MoveList) {
     let king = pos.pieces(us, PieceType::King);
-    let friendly = pos.occupied_by(us);
     
     if let Some(from) = king.lsb() {
         // Normal king moves
-        let attacks = king_attacks(from) & !friendly;
+        let attacks = king_attacks(from) & targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
@@ -272,7 +530,9 @@ fn generate_king_moves(pos: &Position, us: Color, moves: &mut MoveList) {
         }
         
         // Castling
-        generate_castling_moves(pos, us, from, moves);
+        if castling {
+            generate_castling_moves(pos, us, from, moves);
+        }
     }
 }
 
@@ -342,14 +602,13 @@ fn generate_castling_moves(pos: &Position, us: Color, king_sq: Square, moves: &m
 // SLIDING PIECE MOVE GENERATION (Part 3)
 // =============================================================================
 
-fn generate_bishop_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+fn generate_bishop_moves(pos: &Position, us: Color, targets: Bitboard, moves: &mut MoveList) {
     let bishops = pos.pieces(us, PieceType::Bishop);
-    let friendly = pos.occupied_by(us);
     let occupied = pos.occupied();
     
     let mut bishop_bb = bishops;
     while let Some(from) = bishop_bb.pop_lsb() {
-        let attacks = bishop_attacks(from, occupied) & !friendly;
+        let attacks = bishop_attacks(from, occupied) & targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
@@ -358,14 +617,13 @@ fn generate_bishop_moves(pos: &Position, us: Color, moves: &mut MoveList) {
     }
 }
 
-fn generate_rook_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+fn generate_rook_moves(pos: &Position, us: Color, targets: Bitboard, moves: &mut MoveList) {
     let rooks = pos.pieces(us, PieceType::Rook);
-    let friendly = pos.occupied_by(us);
     let occupied = pos.occupied();
     
     let mut rook_bb = rooks;
     while let Some(from) = rook_bb.pop_lsb() {
-        let attacks = rook_attacks(from, occupied) & !friendly;
+        let attacks = rook_attacks(from, occupied) & targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
@@ -374,14 +632,13 @@ fn generate_rook_moves(pos: &Position, us: Color, moves: &mut MoveList) {
     }
 }
 
-fn generate_queen_moves(pos: &Position, us: Color, moves: &mut MoveList) {
+fn generate_queen_moves(pos: &Position, us: Color, targets: Bitboard, moves: &mut MoveList) {
     let queens = pos.pieces(us, PieceType::Queen);
-    let friendly = pos.occupied_by(us);
     let occupied = pos.occupied();
     
     let mut queen_bb = queens;
     while let Some(from) = queen_bb.pop_lsb() {
-        let attacks = queen_attacks(from, occupied) & !friendly;
+        let attacks = queen_attacks(from, occupied) & targets;
         
         let mut att = attacks;
         while let Some(to) = att.pop_lsb() {
@@ -419,6 +676,41 @@ mod tests {
         assert_eq!(promo_moves.len(), 4);
     }
 
+    #[test]
+    fn test_promotion_rules_limit_choices() {
+        use crate::variant::PromotionRules;
+
+        let mut pos = Position::from_fen("8/4P3/8/8/8/8/8/4K2k w - - 0 1").unwrap();
+        pos.set_promotion_rules(PromotionRules::parse("n").unwrap());
+        let promos: Vec<_> = generate_legal_moves(&pos).iter().filter_map(|m| m.promotion_piece()).collect();
+        assert_eq!(promos, [PieceType::Knight]);
+
+        pos.set_promotion_rules(PromotionRules::parse("qrbnk").unwrap());
+        let king_promo = Move::new_promotion(Square::from_algebraic("e7").unwrap(), Square::from_algebraic("e8").unwrap(), PieceType::King);
+        assert!(generate_legal_moves(&pos).iter().any(|&m| m == king_promo));
+        assert!(king_promo.is_promotion() && !king_promo.is_castling() && !king_promo.is_en_passant());
+        assert_eq!(king_promo.to_uci(), "e7e8k");
+
+        let fen = pos.to_fen();
+        let undo = pos.make_move(king_promo).unwrap();
+        assert_eq!(pos.pieces(Color::White, PieceType::King).count(), 2);
+        pos.unmake_move(king_promo, &undo);
+        assert_eq!(pos.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_extra_kings_all_stay_out_of_check() {
+        // The e1 king is in check; moving the a8 king doesn't answer it
+        let pos = Position::from_fen("K3k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert!(pos.is_in_check(Color::White));
+        let moves = generate_legal_moves(&pos);
+        assert_eq!(moves.len(), 3);
+        assert!(moves.iter().all(|m| m.from() == Square::E1));
+        for &m in generate_pseudo_legal_moves(&pos).iter() {
+            assert_eq!(pos.is_legal(m), moves.iter().any(|&x| x == m), "{}", m.to_uci());
+        }
+    }
+
     #[test]
     fn test_knight_moves_center() {
         // Knight on e4
@@ -496,8 +788,8 @@ mod tests {
     #[test]
     fn test_legal_moves_filters_check() {
         // King on e1 with enemy rook on e8 - many moves are illegal
-        let mut pos = Position::from_fen("4r3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
-        let legal = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("4r3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let legal = generate_legal_moves(&pos);
         
         // King can only move to d1, d2, f1, f2 (e-file blocked by rook)
         assert_eq!(legal.len(), 4);
@@ -505,8 +797,8 @@ mod tests {
     
     #[test]
     fn test_starting_position_legal_moves() {
-        let mut pos = Position::starting_position();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::starting_position();
+        let moves = generate_legal_moves(&pos);
         
         // Starting position has 20 legal moves
         assert_eq!(moves.len(), 20);
@@ -530,8 +822,8 @@ mod tests {
     fn test_no_castling_while_in_check() {
         // White king on e1 in check from black rook on e8
         // No castling should be allowed
-        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/R3K2r w KQ - 0 1").unwrap();
-        let legal = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/R3K2r w KQ - 0 1").unwrap();
+        let legal = generate_legal_moves(&pos);
         let castle_moves: Vec<_> = legal.iter().filter(|m| m.is_castling()).collect();
         assert_eq!(castle_moves.len(), 0);
     }
@@ -665,8 +957,8 @@ mod tests {
     fn test_stalemate_king_cornered() {
         // Black king on a8, White queen on b6, White king on c8
         // Black to move — no legal moves, not in check = stalemate
-        let mut pos = Position::from_fen("k7/8/1Q6/8/8/8/8/2K5 b - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("k7/8/1Q6/8/8/8/8/2K5 b - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Stalemate position should have 0 legal moves");
         assert!(!pos.is_in_check(pos.side_to_move()), "Stalemate: king should NOT be in check");
     }
@@ -675,8 +967,8 @@ mod tests {
     fn test_stalemate_king_h8_corner() {
         // Black king stuck in h8 corner
         // Kh8, White Qg6 Kf7 — stalemate
-        let mut pos = Position::from_fen("7k/5K2/6Q1/8/8/8/8/8 b - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("7k/5K2/6Q1/8/8/8/8/8 b - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Should be stalemate, got {} moves", moves.len());
         assert!(!pos.is_in_check(pos.side_to_move()));
     }
@@ -686,13 +978,13 @@ mod tests {
         // Back rank mate: Black king on g8, White rook delivers mate on e8
         let mut pos = Position::from_fen("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
         // Play Re8# — first verify Re8 is a legal move
-        let moves = generate_legal_moves(&mut pos);
+        let moves = generate_legal_moves(&pos);
         let re8 = moves.iter().find(|m| m.to_uci() == "e1e8");
         assert!(re8.is_some(), "Re8 should be a legal move");
 
         // Make the move, then check black has no legal moves AND is in check
         pos.make_move(*re8.unwrap()).unwrap();
-        let black_moves = generate_legal_moves(&mut pos);
+        let black_moves = generate_legal_moves(&pos);
         assert!(black_moves.is_empty(), "Checkmate: black should have 0 legal moves");
         assert!(pos.is_in_check(pos.side_to_move()), "Checkmate: black king should be in check");
     }
@@ -700,8 +992,8 @@ mod tests {
     #[test]
     fn test_checkmate_scholars_mate() {
         // After 1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6?? 4. Qxf7#
-        let mut pos = Position::from_fen("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Scholar's mate: should be checkmate");
         assert!(pos.is_in_check(pos.side_to_move()), "King should be in check");
     }
@@ -709,8 +1001,8 @@ mod tests {
     #[test]
     fn test_fools_mate() {
         // After 1. f3 e5 2. g4 Qh4#
-        let mut pos = Position::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Fool's mate: should be checkmate");
         assert!(pos.is_in_check(pos.side_to_move()));
     }
@@ -725,8 +1017,8 @@ mod tests {
         // White king on a5, White pawn on b5, Black pawn just played c7-c5
         // Black rook on h5 — the b5 pawn is pinned to king along rank 5
         // Taking en passant (bxc6 e.p.) would expose king to Rh5 check
-        let mut pos = Position::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         let ep_moves: Vec<_> = moves.iter().filter(|m| m.is_en_passant()).collect();
         assert!(ep_moves.is_empty(),
             "Pawn pinned along rank cannot take en passant, found {} EP moves", ep_moves.len());
@@ -736,8 +1028,8 @@ mod tests {
     fn test_ep_pin_horizontal_other_side() {
         // Mirror: King on h5, pawn on g5, black pawn on f5 (just played f7-f5)
         // Black rook on a5
-        let mut pos = Position::from_fen("8/8/8/r4pPK/8/8/8/4k3 w - f6 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("8/8/8/r4pPK/8/8/8/4k3 w - f6 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         let ep_moves: Vec<_> = moves.iter().filter(|m| m.is_en_passant()).collect();
         assert!(ep_moves.is_empty(),
             "Pawn pinned along rank cannot take en passant (mirror)");
@@ -746,8 +1038,8 @@ mod tests {
     #[test]
     fn test_ep_allowed_when_not_pinned() {
         // Same setup but no rook — EP should be allowed
-        let mut pos = Position::from_fen("8/8/8/KPp5/8/8/8/4k3 w - c6 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("8/8/8/KPp5/8/8/8/4k3 w - c6 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         let ep_moves: Vec<_> = moves.iter().filter(|m| m.is_en_passant()).collect();
         assert_eq!(ep_moves.len(), 1, "EP should be allowed when not pinned");
     }
@@ -759,8 +1051,8 @@ mod tests {
     #[test]
     fn test_king_vs_king() {
         // K vs K — both sides have only king moves, no possible checkmate
-        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         // King on e1 should have 5 moves (d1,d2,e2,f1,f2)
         assert_eq!(moves.len(), 5, "K vs K king moves: {}", moves.len());
     }
@@ -768,9 +1060,9 @@ mod tests {
     #[test]
     fn test_king_bishop_vs_king() {
         // K+B vs K — insufficient material
-        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/4K1B1 w - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
-        assert!(moves.len() > 0, "K+B vs K should have legal moves");
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K1B1 w - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
+        assert!(!moves.is_empty(), "K+B vs K should have legal moves");
         // Verify no checkmate is possible (just verify position works)
         let piece_count = pos.piece_count();
         assert_eq!(piece_count, 3);
@@ -779,9 +1071,9 @@ mod tests {
     #[test]
     fn test_king_knight_vs_king() {
         // K+N vs K — insufficient material
-        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
-        assert!(moves.len() > 0);
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
+        assert!(!moves.is_empty());
         assert_eq!(pos.piece_count(), 3);
     }
 
@@ -809,7 +1101,7 @@ mod tests {
         pos.make_move(Move::new(Square::from_file_rank(3, 7), Square::from_file_rank(7, 3))).unwrap();
 
         // Now white is in checkmate
-        let moves = generate_legal_moves(&mut pos);
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "White should be checkmated");
         assert!(pos.is_in_check(pos.side_to_move()), "White king should be in check");
     }
@@ -835,7 +1127,7 @@ mod tests {
         pos.make_move(Move::new(Square::from_file_rank(7, 4), Square::from_file_rank(5, 6))).unwrap();
 
         // Black is checkmated
-        let moves = generate_legal_moves(&mut pos);
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Black should be checkmated after Scholar's Mate");
         assert!(pos.is_in_check(pos.side_to_move()));
     }
@@ -845,8 +1137,8 @@ mod tests {
         // Known stalemate position:
         // Black king on a8, White Qb6 Kd6
         // Ka8 moves: a7 (Qb6), b8 (Qb6), b7 (Qb6+Kd6) → all attacked
-        let mut pos = Position::from_fen("k7/8/1Q1K4/8/8/8/8/8 b - - 0 1").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("k7/8/1Q1K4/8/8/8/8/8 b - - 0 1").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.is_empty(), "Should be stalemate, got {} moves", moves.len());
         assert!(!pos.is_in_check(pos.side_to_move()), "Should not be in check (stalemate not checkmate)");
     }
@@ -855,19 +1147,199 @@ mod tests {
     // ADDITIONAL PERFT EDGE CASES (Task 1.7)
     // =========================================================================
 
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_perft_parallel_matches_serial() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+        for fen in fens {
+            let mut pos = Position::from_fen(fen).unwrap();
+            for depth in 0..=3 {
+                assert_eq!(perft_parallel(&pos, depth, None), perft(&mut pos, depth), "{} depth {}", fen, depth);
+            }
+            assert_eq!(perft_divide_parallel(&pos, 2, None), perft_divide(&mut pos, 2));
+        }
+        let kiwipete = Position::from_fen(fens[1]).unwrap();
+        assert_eq!(perft_parallel(&kiwipete, 3, None), 97862);
+        // No legal moves: nothing to split
+        let mated = Position::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(perft_parallel(&mated, 2, None), 0);
+    }
+
+    #[test]
+    fn test_perft_hashed_matches_perft() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        ];
+        // A tiny table so entries of different depths keep replacing each other
+        let table = PerftTable::new(4);
+        for fen in fens {
+            let mut pos = Position::from_fen(fen).unwrap();
+            for depth in 0..=3 {
+                assert_eq!(perft_hashed(&mut pos, depth, &table), perft(&mut pos, depth), "{} depth {}", fen, depth);
+            }
+        }
+        let table = PerftTable::new(16);
+        let mut pos = Position::starting_position();
+        assert_eq!(perft_hashed(&mut pos, 4, &table), 197_281);
+        // Second run is answered from the table
+        assert_eq!(table.probe(pos.hash(), 4), Some(197_281));
+        assert_eq!(table.probe(pos.hash(), 3), None);
+        #[cfg(not(target_arch = "wasm32"))]
+        assert_eq!(perft_parallel(&pos, 4, Some(&table)), 197_281);
+    }
+
+    #[test]
+    fn test_perft_detailed() {
+        // Kiwipete, depths 1-3, from chessprogramming.org/Perft_Results
+        let mut pos = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let stats = perft_detailed(&mut pos, 3);
+        let row = |s: &PerftStats| {
+            (s.nodes, s.captures, s.en_passants, s.castles, s.promotions, s.checks, s.discovered_checks, s.double_checks, s.checkmates)
+        };
+        assert_eq!(row(&stats[0]), (48, 8, 0, 2, 0, 0, 0, 0, 0));
+        assert_eq!(row(&stats[1]), (2039, 351, 1, 91, 0, 3, 0, 0, 0));
+        assert_eq!(row(&stats[2]), (97862, 17102, 45, 3162, 0, 993, 0, 0, 1));
+        assert_eq!(stats[2].depth, 3);
+
+        // Position 3: en passants and discovered checks
+        let mut pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        let stats = perft_detailed(&mut pos, 3);
+        assert_eq!(row(&stats[2]), (2812, 209, 2, 0, 0, 267, 3, 0, 0));
+        assert_eq!(stats.iter().map(|s| s.nodes).collect::<Vec<_>>(), (1..=3).map(|d| perft(&mut pos, d)).collect::<Vec<_>>());
+
+        // Position 4: promotions and mates
+        let mut pos = Position::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+        let stats = perft_detailed(&mut pos, 3);
+        assert_eq!(row(&stats[2]), (9467, 1021, 4, 0, 120, 38, 2, 0, 22));
+        assert!(perft_detailed(&mut pos, 0).is_empty());
+    }
+
     #[test]
     fn test_perft_double_check() {
         // Position with discovered double check possibilities
-        let mut pos = Position::from_fen("r1bqk2r/pppp1ppp/2n5/2b1p3/2BnP3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
-        let moves = generate_legal_moves(&mut pos);
-        assert!(moves.len() > 0, "Should have legal moves in complex middlegame");
+        let pos = Position::from_fen("r1bqk2r/pppp1ppp/2n5/2b1p3/2BnP3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let moves = generate_legal_moves(&pos);
+        assert!(!moves.is_empty(), "Should have legal moves in complex middlegame");
     }
 
     #[test]
     fn test_perft_many_captures() {
         // Position rich in captures
-        let mut pos = Position::from_fen("r2q1rk1/ppp2ppp/2npbn2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 7").unwrap();
-        let moves = generate_legal_moves(&mut pos);
+        let pos = Position::from_fen("r2q1rk1/ppp2ppp/2npbn2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 7").unwrap();
+        let moves = generate_legal_moves(&pos);
         assert!(moves.len() > 20, "Complex middlegame should have many moves");
     }
+
+    #[test]
+    fn test_captures_and_quiets_split_pseudo_legal() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+            let captures = generate_captures(&pos);
+            let quiets = generate_quiets(&pos);
+            let mut split: Vec<String> = captures.iter().chain(quiets.iter()).map(|m| m.to_uci()).collect();
+            let mut all: Vec<String> = generate_pseudo_legal_moves(&pos).iter().map(|m| m.to_uci()).collect();
+            split.sort();
+            all.sort();
+            assert_eq!(split, all, "{}", fen);
+
+            for m in captures.iter() {
+                assert!(m.is_en_passant() || pos.piece_on(m.to()).is_some(), "{} is not a capture", m.to_uci());
+            }
+            for m in quiets.iter() {
+                assert!(!m.is_en_passant() && pos.piece_on(m.to()).is_none(), "{} is a capture", m.to_uci());
+            }
+        }
+    }
+
+    #[test]
+    fn test_captures_in_starting_position() {
+        let pos = Position::starting_position();
+        assert!(generate_captures(&pos).is_empty());
+        assert_eq!(generate_quiets(&pos).len(), 20);
+        // Push promotions are quiet, capturing promotions and en passant are not
+        let pos = Position::from_fen("1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let caps: Vec<String> = generate_captures(&pos).iter().map(|m| m.to_uci()).collect();
+        assert_eq!(caps, ["a7b8q", "a7b8r", "a7b8b", "a7b8n", "e5d6"]);
+        assert_eq!(generate_quiets(&pos).iter().filter(|m| m.is_promotion()).count(), 4);
+    }
+
+    #[test]
+    fn test_evasions_cover_all_legal_moves() {
+        let fens = [
+            // Single checks by slider, knight and pawn
+            "4k3/8/8/8/8/8/3P4/r3K2R w K - 0 1",
+            "4k3/8/8/8/8/3n4/8/R3K3 w Q - 0 1",
+            "4k3/8/8/8/8/8/3p4/4K3 w - - 0 1",
+            // Double check
+            "4k3/8/8/8/1b6/3n4/8/R3K2R w KQ - 0 1",
+            // The checker can be taken en passant
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+            // Interposing by promotion
+            "K6r/1P6/8/8/8/8/8/k7 w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ];
+        for fen in fens {
+            let mut pos = Position::from_fen(fen).unwrap();
+            let mut expected = Vec::new();
+            for m in generate_pseudo_legal_moves(&pos).iter() {
+                if let Some(undo) = pos.make_move(*m) {
+                    expected.push(m.to_uci());
+                    pos.unmake_move(*m, &undo);
+                }
+            }
+            let actual: Vec<String> = generate_legal_moves(&pos).iter().map(|m| m.to_uci()).collect();
+            assert_eq!(actual, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_evasions_are_few() {
+        // Only king moves survive a double check
+        let pos = Position::from_fen("4k3/8/8/8/1b6/3n4/8/R3K2R w KQ - 0 1").unwrap();
+        let evasions = generate_evasions(&pos);
+        assert!(evasions.iter().all(|m| m.from() == Square::E1));
+        // d1, f1, d2, e2, f2; no castling out of check
+        assert_eq!(evasions.len(), 5);
+    }
+
+    #[test]
+    fn test_pinned_pieces_move_along_pin() {
+        // Knight g2 pinned by the rook, bishop e3 by the queen
+        let pos = Position::from_fen("6r1/8/1q6/8/8/4B3/6N1/k5K1 w - - 0 1").unwrap();
+        let pinned: Vec<Square> = pos.pinned(Color::White).collect();
+        assert_eq!(pinned, [Square::new(14), Square::new(20)]);
+
+        let legal = generate_legal_moves(&pos);
+        assert!(legal.iter().all(|m| m.from() != Square::new(14)));
+        let mut bishop: Vec<String> = legal.iter().filter(|m| m.from() == Square::new(20)).map(|m| m.to_uci()).collect();
+        bishop.sort();
+        assert_eq!(bishop, ["e3b6", "e3c5", "e3d4", "e3f2"]);
+
+        // A second blocker on the line means no pin
+        let pos = Position::from_fen("6r1/8/1q6/8/3P4/4B3/8/k5K1 w - - 0 1").unwrap();
+        assert!(pos.pinned(Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_king_cannot_step_along_check_ray() {
+        // The rook's ray continues through the king's square
+        let pos = Position::from_fen("k7/8/8/8/r3K3/8/8/8 w - - 0 1").unwrap();
+        let legal: Vec<String> = generate_legal_moves(&pos).iter().map(|m| m.to_uci()).collect();
+        assert!(!legal.contains(&"e4f4".to_string()));
+        assert_eq!(legal.len(), 6);
+    }
 }
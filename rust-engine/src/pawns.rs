@@ -0,0 +1,92 @@
+// Pawn Hash Table Module
+// Caches pawn-structure evaluation keyed by the pawn-only Zobrist hash.
+// Pawn structure changes rarely during search, so most probes hit even with a small table.
+
+use crate::eval::Score;
+
+/// Default number of entries: 2^12 (~64 KB).
+pub const DEFAULT_SIZE_POWER: u32 = 12;
+
+/// Cached pawn-structure terms for one pawn configuration.
+/// Scores are per color, indexed by `Color as usize`, each from that color's view.
+#[derive(Clone, Copy, Default)]
+pub struct PawnEntry {
+    pub key: u64,
+    /// Middlegame pawn score per color
+    pub mg: [Score; 2],
+    /// Endgame pawn score per color
+    pub eg: [Score; 2],
+}
+
+/// Always-replace hash table of pawn-structure evaluations.
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+    mask: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl PawnTable {
+    /// Create a table with 2^size_power entries.
+    pub fn new(size_power: u32) -> Self {
+        let capacity = 1usize << size_power;
+        PawnTable {
+            entries: vec![PawnEntry::default(); capacity],
+            mask: capacity - 1,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up the entry for a pawn key. Key 0 (no pawns) is never cached.
+    #[inline]
+    pub fn probe(&mut self, key: u64) -> Option<PawnEntry> {
+        let entry = self.entries[(key as usize) & self.mask];
+        if entry.key == key && key != 0 {
+            self.hits += 1;
+            Some(entry)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    /// Drop every entry (after the pawn weights change).
+    pub fn clear(&mut self) {
+        self.entries.fill(PawnEntry::default());
+    }
+
+    /// Store an entry, replacing whatever was in the slot.
+    #[inline]
+    pub fn store(&mut self, entry: PawnEntry) {
+        self.entries[(entry.key as usize) & self.mask] = entry;
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_and_probe() {
+        let mut table = PawnTable::new(4);
+        assert!(table.probe(0x1234).is_none());
+        table.store(PawnEntry { key: 0x1234, mg: [850, 790], eg: [900, 820] });
+        let entry = table.probe(0x1234).unwrap();
+        assert_eq!(entry.mg, [850, 790]);
+        assert_eq!(entry.eg, [900, 820]);
+        assert_eq!(table.hits, 1);
+        assert_eq!(table.misses, 1);
+    }
+
+    #[test]
+    fn zero_key_never_hits() {
+        let mut table = PawnTable::new(4);
+        table.store(PawnEntry { key: 0, mg: [1, 2], eg: [3, 4] });
+        assert!(table.probe(0).is_none());
+    }
+}
@@ -0,0 +1,205 @@
+// Engine Personalities
+// Playing styles built from evaluation weights and search settings, so
+// opponents differ in what they value and not only in how deep they look.
+
+use crate::eval::{default_params, EvalParams, PawnParams, Score};
+use crate::search::SearchOptions;
+
+/// Playing style of the engine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Personality {
+    /// The standard evaluation
+    #[default]
+    Balanced,
+    /// Active pieces and threats, avoids draws
+    Aggressive,
+    /// Sound pawn structure, outposts and the bishop pair
+    Positional,
+    /// Counts material and little else
+    Materialist,
+    /// Gives up pawns for development and initiative, avoids draws
+    Gambiteer,
+}
+
+impl Personality {
+    pub const ALL: [Personality; 5] = [
+        Personality::Balanced,
+        Personality::Aggressive,
+        Personality::Positional,
+        Personality::Materialist,
+        Personality::Gambiteer,
+    ];
+
+    /// Lowercase name, as used by the tournament runner and the WASM API
+    pub fn name(&self) -> &'static str {
+        match self {
+            Personality::Balanced => "balanced",
+            Personality::Aggressive => "aggressive",
+            Personality::Positional => "positional",
+            Personality::Materialist => "materialist",
+            Personality::Gambiteer => "gambiteer",
+        }
+    }
+
+    /// Parse a name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Personality> {
+        Self::ALL.into_iter().find(|p| p.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Evaluation weights of this style
+    pub fn params(&self) -> &'static EvalParams {
+        match self {
+            Personality::Balanced => default_params(),
+            Personality::Aggressive => &AGGRESSIVE,
+            Personality::Positional => &POSITIONAL,
+            Personality::Materialist => &MATERIALIST,
+            Personality::Gambiteer => &GAMBITEER,
+        }
+    }
+
+    /// Contempt for draws, in centipawns
+    pub fn contempt(&self) -> Score {
+        match self {
+            Personality::Balanced | Personality::Positional | Personality::Materialist => 0,
+            Personality::Aggressive => 25,
+            Personality::Gambiteer => 40,
+        }
+    }
+
+    /// Search settings playing in this style
+    pub fn search_options(&self) -> SearchOptions<'static> {
+        SearchOptions { params: self.params(), contempt: self.contempt() }
+    }
+}
+
+// =============================================================================
+// PRESETS
+// =============================================================================
+
+/// Every entry of `table` scaled by `percent`
+const fn scaled<const N: usize>(table: [Score; N], percent: Score) -> [Score; N] {
+    let mut out = table;
+    let mut i = 0;
+    while i < N {
+        out[i] = table[i] * percent / 100;
+        i += 1;
+    }
+    out
+}
+
+const D: EvalParams = EvalParams::DEFAULT;
+
+static AGGRESSIVE: EvalParams = EvalParams {
+    tempo: 20,
+    knight_pst: scaled(D.knight_pst, 125),
+    bishop_pst: scaled(D.bishop_pst, 125),
+    queen_pst: scaled(D.queen_pst, 150),
+    threat_by_lower_mg: scaled(D.threat_by_lower_mg, 150),
+    threat_by_lower_eg: scaled(D.threat_by_lower_eg, 150),
+    hanging_piece_mg: D.hanging_piece_mg * 3 / 2,
+    hanging_piece_eg: D.hanging_piece_eg * 3 / 2,
+    ..D
+};
+
+static POSITIONAL: EvalParams = EvalParams {
+    bishop_pair: 50,
+    knight_outpost_mg: scaled(D.knight_outpost_mg, 150),
+    knight_outpost_eg: scaled(D.knight_outpost_eg, 150),
+    pawns: PawnParams {
+        passed_mg: scaled(D.pawns.passed_mg, 125),
+        passed_eg: scaled(D.pawns.passed_eg, 125),
+        doubled_mg: D.pawns.doubled_mg * 3 / 2,
+        doubled_eg: D.pawns.doubled_eg * 3 / 2,
+        isolated_mg: D.pawns.isolated_mg * 3 / 2,
+        isolated_eg: D.pawns.isolated_eg * 3 / 2,
+        backward_mg: D.pawns.backward_mg * 3 / 2,
+        backward_eg: D.pawns.backward_eg * 3 / 2,
+        ..D.pawns
+    },
+    ..D
+};
+
+static MATERIALIST: EvalParams = EvalParams {
+    tempo: 5,
+    pawn_pst: scaled(D.pawn_pst, 50),
+    knight_pst: scaled(D.knight_pst, 50),
+    bishop_pst: scaled(D.bishop_pst, 50),
+    rook_pst: scaled(D.rook_pst, 50),
+    queen_pst: scaled(D.queen_pst, 50),
+    knight_outpost_mg: scaled(D.knight_outpost_mg, 50),
+    knight_outpost_eg: scaled(D.knight_outpost_eg, 50),
+    threat_by_lower_mg: scaled(D.threat_by_lower_mg, 50),
+    threat_by_lower_eg: scaled(D.threat_by_lower_eg, 50),
+    ..D
+};
+
+static GAMBITEER: EvalParams = EvalParams {
+    tempo: 30,
+    pawn_value: 75,
+    knight_pst: scaled(D.knight_pst, 150),
+    bishop_pst: scaled(D.bishop_pst, 150),
+    threat_by_lower_mg: scaled(D.threat_by_lower_mg, 150),
+    threat_by_lower_eg: scaled(D.threat_by_lower_eg, 150),
+    ..D
+};
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::evaluate_with;
+    use crate::position::Position;
+    use crate::search::search_with_options;
+
+    #[test]
+    fn test_names_roundtrip() {
+        for p in Personality::ALL {
+            assert_eq!(Personality::from_name(p.name()), Some(p));
+        }
+        assert_eq!(Personality::from_name(" Gambiteer "), Some(Personality::Gambiteer));
+        assert_eq!(Personality::from_name("reckless"), None);
+    }
+
+    #[test]
+    fn test_presets_differ() {
+        assert!(std::ptr::eq(Personality::Balanced.params(), default_params()));
+        for (i, a) in Personality::ALL.iter().enumerate() {
+            for b in &Personality::ALL[i + 1..] {
+                assert_ne!(a.params(), b.params(), "{} and {} are the same", a.name(), b.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_gambiteer_values_pawn_less() {
+        // An extra pawn is worth less to the gambiteer
+        let pos = Position::from_fen("4k3/pp6/8/8/8/8/PPP5/4K3 w - - 0 1").unwrap();
+        let balanced = evaluate_with(&pos, Personality::Balanced.params());
+        let gambiteer = evaluate_with(&pos, Personality::Gambiteer.params());
+        assert!(gambiteer - Personality::Gambiteer.params().tempo < balanced - Personality::Balanced.params().tempo,
+            "{} vs {}", gambiteer, balanced);
+    }
+
+    #[test]
+    fn test_contempt_shifts_root_score() {
+        // Bare kings: every line is a draw, which contempt makes worse for the root side
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        let opts = |contempt| SearchOptions { contempt, ..SearchOptions::default() };
+        let (_, neutral, _) = search_with_options(&mut Position::from_fen(fen).unwrap(), 2, &opts(0));
+        let (_, biased, _) = search_with_options(&mut Position::from_fen(fen).unwrap(), 2, &opts(30));
+        assert_eq!(neutral, 0);
+        assert_eq!(biased, -30);
+    }
+
+    #[test]
+    fn test_every_personality_finds_a_move() {
+        for p in Personality::ALL {
+            let mut pos = Position::starting_position();
+            let (mv, _, _) = search_with_options(&mut pos, 3, &p.search_options());
+            assert!(mv.is_some(), "{} found no move", p.name());
+        }
+    }
+}
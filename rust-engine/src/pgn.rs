@@ -0,0 +1,482 @@
+// PGN Import/Export
+// Reads a game in Portable Game Notation: tag pairs, then movetext with move
+// numbers, comments, NAGs and variations. Only the main line is kept, with its
+// comments and NAGs; moves stay SAN strings here and are checked when the game
+// is replayed. Writes the same structure back as PGN text.
+
+/// A game read from PGN
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    /// Tag pairs in file order, e.g. ("White", "Carlsen, Magnus")
+    pub headers: Vec<(String, String)>,
+    /// Main-line moves in SAN
+    pub moves: Vec<String>,
+    /// Game termination marker: "1-0", "0-1", "1/2-1/2" or "*"
+    pub result: String,
+    /// Main-line comments as (ply, text): ply 0 is before the first move,
+    /// ply n after the n-th
+    pub comments: Vec<(usize, String)>,
+    /// Main-line NAGs as (ply, code), e.g. (3, 1) for "!" on the third move
+    pub nags: Vec<(usize, u8)>,
+}
+
+impl PgnGame {
+    /// Value of a tag (exact name match)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Starting position from the FEN tag, if the game doesn't start from the
+    /// standard position
+    pub fn start_fen(&self) -> Option<&str> {
+        self.header("FEN")
+    }
+
+    /// Rule variant from the Variant tag (e.g. "Three-check"); absent for
+    /// standard chess
+    pub fn variant(&self) -> Option<&str> {
+        self.header("Variant")
+    }
+
+    /// Comment at `ply`, if any
+    pub fn comment(&self, ply: usize) -> Option<&str> {
+        self.comments.iter().find(|(p, _)| *p == ply).map(|(_, c)| c.as_str())
+    }
+
+    /// NAGs at `ply`, in file order
+    pub fn nags_at(&self, ply: usize) -> Vec<u8> {
+        self.nags.iter().filter(|(p, _)| *p == ply).map(|&(_, n)| n).collect()
+    }
+
+    fn add_comment(&mut self, text: &str) {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return;
+        }
+        let ply = self.moves.len();
+        match self.comments.iter_mut().find(|(p, _)| *p == ply) {
+            Some((_, c)) => {
+                c.push(' ');
+                c.push_str(&text);
+            }
+            None => self.comments.push((ply, text)),
+        }
+    }
+
+    /// Write the game as PGN: tag pairs, a blank line, then movetext wrapped
+    /// at 80 columns. Move numbers follow the FEN tag when there is one.
+    pub fn to_pgn(&self) -> String {
+        let mut out = String::new();
+        for (name, value) in &self.headers {
+            out.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        out.push('\n');
+
+        // Side to move and move number at the start
+        let fen_fields: Vec<&str> = self.start_fen().map(|f| f.split_whitespace().collect()).unwrap_or_default();
+        let mut black = fen_fields.get(1) == Some(&"b");
+        let mut number: u32 = fen_fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+
+        let mut tokens = Vec::new();
+        if let Some(c) = self.comment(0) {
+            tokens.push(comment_token(c));
+        }
+        // Black's move needs its number at the start and after a comment
+        let mut need_number = true;
+        for (i, san) in self.moves.iter().enumerate() {
+            if !black {
+                tokens.push(format!("{}.", number));
+            } else if need_number {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(san.clone());
+            tokens.extend(self.nags_at(i + 1).iter().map(|n| format!("${}", n)));
+            need_number = false;
+            if let Some(c) = self.comment(i + 1) {
+                tokens.push(comment_token(c));
+                need_number = true;
+            }
+            if black {
+                number += 1;
+            }
+            black = !black;
+        }
+        tokens.push(if self.result.is_empty() { "*".to_string() } else { self.result.clone() });
+
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > 80 {
+                out.push('\n');
+                line_len = 0;
+            } else if line_len > 0 {
+                out.push(' ');
+                line_len += 1;
+            }
+            line_len += token.len();
+            out.push_str(&token);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// A brace comment; "}" can't appear inside one
+fn comment_token(text: &str) -> String {
+    format!("{{{}}}", text.replace('}', ")"))
+}
+
+/// Glyphs for NAGs 1-6, the move assessments written after a move
+const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+/// NAG code for a glyph ("!?") or a "$n" token
+pub fn nag_from_str(s: &str) -> Option<u8> {
+    match s.strip_prefix('$') {
+        Some(n) => n.parse().ok(),
+        None => GLYPHS.iter().position(|&g| g == s).map(|i| i as u8 + 1),
+    }
+}
+
+/// Glyph for a NAG: "!" for 1 ... "?!" for 6, "$n" for the rest
+pub fn nag_glyph(nag: u8) -> String {
+    match nag {
+        1..=6 => GLYPHS[nag as usize - 1].to_string(),
+        _ => format!("${}", nag),
+    }
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Parse the first game in `text`.
+///
+/// Main-line comments (`{...}` and `;` to end of line) and NAGs (`$1`, or
+/// `!`/`?` glyphs) are kept by ply; move numbers are dropped and variations in
+/// parentheses are skipped, including nested ones. A missing result marker
+/// reads as "*".
+pub fn parse_pgn(text: &str) -> Result<PgnGame, String> {
+    let mut game = PgnGame::default();
+    let mut chars = text.chars().peekable();
+    let mut depth = 0usize; // variation nesting
+    let mut in_movetext = false;
+    let mut at_line_start = true;
+
+    while let Some(&c) = chars.peek() {
+        // "%" at the start of a line escapes the whole line
+        if at_line_start && c == '%' {
+            for c in chars.by_ref() {
+                if c == '\n' {
+                    break;
+                }
+            }
+            continue;
+        }
+        at_line_start = c == '\n';
+
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' if depth == 0 => {
+                // A tag after the movetext starts the next game
+                if in_movetext {
+                    break;
+                }
+                chars.next();
+                game.headers.push(parse_tag(&mut chars)?);
+            }
+            '{' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => text.push(c),
+                        None => return Err("Unterminated comment".into()),
+                    }
+                }
+                if depth == 0 {
+                    game.add_comment(&text);
+                }
+            }
+            ';' => {
+                chars.next();
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        at_line_start = true;
+                        break;
+                    }
+                    text.push(c);
+                }
+                if depth == 0 {
+                    game.add_comment(&text);
+                }
+            }
+            '(' => {
+                chars.next();
+                depth += 1;
+            }
+            ')' => {
+                chars.next();
+                depth = depth.checked_sub(1).ok_or("Unbalanced ')' in movetext")?;
+            }
+            _ => {
+                in_movetext = true;
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{}();[".contains(c) {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if token.is_empty() {
+                    // A stray character such as ']' or '}'
+                    return Err(format!("Unexpected '{}' in movetext", c));
+                }
+                if depth > 0 {
+                    continue;
+                }
+                if RESULTS.contains(&token.as_str()) {
+                    game.result = token;
+                    break;
+                }
+                if let Some(san) = movetext_move(&token) {
+                    game.moves.push(san.to_string());
+                }
+                // "$n", a glyph token, or a glyph glued to the move
+                let glyph = token.trim_start_matches(|c: char| c != '!' && c != '?' && c != '$');
+                if let Some(nag) = nag_from_str(glyph) {
+                    game.nags.push((game.moves.len(), nag));
+                }
+            }
+        }
+    }
+
+    if depth > 0 {
+        return Err("Unterminated variation".into());
+    }
+    if game.result.is_empty() {
+        game.result = game.header("Result").filter(|r| RESULTS.contains(r)).unwrap_or("*").to_string();
+    }
+    Ok(game)
+}
+
+/// Parse every game in a PGN collection. Games are split where a tag line
+/// follows movetext, outside brace comments; each is parsed on its own, so one bad game doesn't stop
+/// the rest.
+pub fn parse_pgn_games(text: &str) -> Vec<Result<PgnGame, String>> {
+    let mut games = Vec::new();
+    let mut start = 0;
+    let mut seen_movetext = false;
+    let mut offset = 0;
+    let mut in_comment = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if in_comment {
+            seen_movetext = true;
+        } else if trimmed.starts_with('[') {
+            if seen_movetext {
+                games.push(&text[start..offset]);
+                start = offset;
+                seen_movetext = false;
+            }
+        } else if !trimmed.is_empty() && !trimmed.starts_with('%') {
+            seen_movetext = true;
+        }
+        for c in line.chars() {
+            match c {
+                '{' => in_comment = true,
+                '}' => in_comment = false,
+                ';' if !in_comment => break,
+                _ => {}
+            }
+        }
+        offset += line.len();
+    }
+    if !text[start..].trim().is_empty() {
+        games.push(&text[start..]);
+    }
+    games.into_iter().map(parse_pgn).collect()
+}
+
+/// Read `Name "Value"]` after the opening bracket of a tag pair.
+fn parse_tag(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<(String, String), String> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == '"' || c == ']' {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(format!("Malformed tag [{}", name));
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => match chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(format!("Unterminated tag [{}", name)),
+            },
+            Some('"') => break,
+            Some('\n') | None => return Err(format!("Unterminated tag [{}", name)),
+            Some(c) => value.push(c),
+        }
+    }
+
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.next() != Some(']') {
+        return Err(format!("Malformed tag [{}", name));
+    }
+    Ok((name, value))
+}
+
+/// The SAN move in a movetext token, without move number or annotations.
+/// None for tokens that aren't moves (bare move numbers, NAGs).
+fn movetext_move(token: &str) -> Option<&str> {
+    if token.starts_with('$') || token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // "12." / "12..." prefix, possibly glued to the move ("12.e4"), but not "0-0"
+    let san = match token.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) if i > 0 && token[i..].starts_with('.') => token[i..].trim_start_matches('.'),
+        _ => token,
+    };
+    let san = san.trim_end_matches(['!', '?']);
+    if san.is_empty() {
+        None
+    } else {
+        Some(san)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 {This is a weak move already.} 4. dxe5 Bxf3
+5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+
+Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8
+17. Rd8# 1-0
+"#;
+
+    #[test]
+    fn test_parse_headers_and_moves() {
+        let game = parse_pgn(OPERA_GAME).unwrap();
+        assert_eq!(game.header("White"), Some("Paul Morphy"));
+        assert_eq!(game.header("Black"), Some("Duke Karl / Count Isouard"));
+        assert_eq!(game.header("Round"), None);
+        assert_eq!(game.moves.len(), 33);
+        assert_eq!(&game.moves[..4], ["e4", "e5", "Nf3", "d6"]);
+        assert_eq!(game.moves.last().unwrap(), "Rd8#");
+        assert_eq!(game.result, "1-0");
+        assert_eq!(game.start_fen(), None);
+    }
+
+    #[test]
+    fn test_skips_comments_nags_and_variations() {
+        let pgn = "1. e4 $1 e5!? ; line comment\n2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) {brace\ncomment} 2... Nc6 3.Bb5 a6 4. 0-0 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "0-0"]);
+        assert_eq!(game.result, "*");
+    }
+
+    #[test]
+    fn test_comments_and_nags() {
+        let pgn = "{Start} 1. e4 $1 e5!? ; line comment\n2. Nf3 (2. f4 {not kept}) {brace\ncomment} {again} 2... Nc6 ?! 3. Bb5 $14 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(game.comment(0), Some("Start"));
+        assert_eq!(game.comment(2), Some("line comment"));
+        assert_eq!(game.comment(3), Some("brace comment again"));
+        assert_eq!(game.comment(1), None);
+        assert_eq!(game.nags, [(1, 1), (2, 5), (4, 6), (5, 14)]);
+        assert_eq!(nag_glyph(5), "!?");
+        assert_eq!(nag_glyph(14), "$14");
+        assert_eq!(nag_from_str("??"), Some(4));
+        assert_eq!(nag_from_str("!!!"), None);
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let game = parse_pgn(OPERA_GAME).unwrap();
+        let text = game.to_pgn();
+        assert!(text.starts_with("[Event \"Paris\"]\n[Site \"Paris FRA\"]\n"));
+        assert!(text.contains("\n\n1. e4 e5 2. Nf3 d6 3. d4 Bg4 {This is a weak move already.} 4. dxe5 Bxf3 "));
+        assert!(text.lines().all(|l| l.len() <= 80));
+        assert_eq!(parse_pgn(&text).unwrap(), game);
+
+        // Black to move from a FEN; a comment interrupts the line
+        let pgn = "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10\"]\n\n10... Kd7 11. e4 $2 Ke6 12. Kd2 {done} 12... Kd6 1-0\n";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.to_pgn(), pgn);
+    }
+
+    #[test]
+    fn test_escaped_tag_value() {
+        let game = parse_pgn(r#"[Annotator "A \"quoted\" name \\ here"] 1. d4 *"#).unwrap();
+        assert_eq!(game.header("Annotator"), Some(r#"A "quoted" name \ here"#));
+    }
+
+    #[test]
+    fn test_only_first_game() {
+        let pgn = "[Event \"1\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"2\"]\n\n1. d4 *\n";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.moves, ["e4", "e5"]);
+        assert_eq!(game.result, "1/2-1/2");
+    }
+
+    #[test]
+    fn test_parse_all_games() {
+        let pgn = "[Event \"1\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"2\"]\n\n1. d4 {\n[not a tag]} *\n[Event \"3\"]\n1. e4 ) *\n\n[Event \"4\"]\n1. c4 1-0";
+        let games = parse_pgn_games(pgn);
+        assert_eq!(games.len(), 4);
+        assert_eq!(games[0].as_ref().unwrap().moves, ["e4", "e5"]);
+        assert_eq!(games[1].as_ref().unwrap().header("Event"), Some("2"));
+        assert!(games[2].is_err());
+        assert_eq!(games[3].as_ref().unwrap().result, "1-0");
+        assert!(parse_pgn_games("  \n").is_empty());
+    }
+
+    #[test]
+    fn test_missing_result_uses_tag() {
+        let game = parse_pgn("[Result \"0-1\"]\n1. f3 e5 2. g4 Qh4#").unwrap();
+        assert_eq!(game.result, "0-1");
+        assert_eq!(parse_pgn("1. e4").unwrap().result, "*");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse_pgn("1. e4 {never closed").is_err());
+        assert!(parse_pgn("1. e4 (1. d4").is_err());
+        assert!(parse_pgn("1. e4 ) e5").is_err());
+        assert!(parse_pgn("[White Morphy]\n1. e4").is_err());
+        assert!(parse_pgn("[White \"Morphy\n1. e4").is_err());
+    }
+
+    #[test]
+    fn test_escape_lines_and_fen_tag() {
+        let pgn = "% exported by some tool\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.start_fen(), Some("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        assert_eq!(game.moves, ["e4", "Kd7"]);
+    }
+}
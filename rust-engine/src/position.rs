@@ -1,7 +1,9 @@
 // Chess Position - Full board state using bitboards
 
 use crate::bitboard::Bitboard;
+use crate::eval::{Score, MATERIAL, PSQ};
 use crate::types::{CastlingRights, Color, Move, PieceType, Square};
+use crate::variant::{PromotionRules, Variant};
 use crate::zobrist;
 use wasm_bindgen::prelude::*;
 
@@ -17,6 +19,9 @@ pub struct Position {
     occupied_by_color: [Bitboard; 2],
     occupied_all: Bitboard,
 
+    // Piece on each square, kept in step with the bitboards for O(1) piece_on
+    board: [Option<(Color, PieceType)>; 64],
+
     // Game state
     side_to_move: Color,
     castling: CastlingRights,
@@ -26,6 +31,23 @@ pub struct Position {
 
     // Zobrist hash for transposition tables and repetition detection
     hash: u64,
+    // Zobrist hash of pawns only (keys the pawn structure cache)
+    pawn_hash: u64,
+
+    // Running material + piece-square sums per color (default eval weights, kings excluded)
+    psq: [Score; 2],
+    // Running non-king material per color
+    material: [Score; 2],
+
+    // Rule set, consulted by movegen, make_move, game status and eval
+    variant: Variant,
+    // Pieces pawns may promote to in this game
+    promotion_rules: PromotionRules,
+    // Checks given by each color (counted in three-check only)
+    checks_given: [u8; 2],
+    // Pieces of each color captured since the position was set up and not
+    // brought back by a promotion, by piece type
+    lost: [[u8; 6]; 2],
 }
 
 impl Position {
@@ -39,12 +61,20 @@ impl Position {
             pieces: [[Bitboard::EMPTY; 6]; 2],
             occupied_by_color: [Bitboard::EMPTY; 2],
             occupied_all: Bitboard::EMPTY,
+            board: [None; 64],
             side_to_move: Color::White,
             castling: CastlingRights::NONE,
             en_passant: None,
             halfmove_clock: 0,
             fullmove_number: 1,
             hash: 0,
+            pawn_hash: 0,
+            psq: [0; 2],
+            material: [0; 2],
+            variant: Variant::Standard,
+            promotion_rules: PromotionRules::STANDARD,
+            checks_given: [0; 2],
+            lost: [[0; 6]; 2],
         }
     }
 
@@ -106,12 +136,65 @@ impl Position {
         self.halfmove_clock
     }
 
+    /// Get fullmove number (starts at 1, incremented after black moves)
+    #[inline]
+    pub fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    /// Get the variant whose rules apply
+    #[inline]
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Play under a variant's rules from here on. The board is unchanged.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+
+    /// Get the pieces pawns may promote to
+    #[inline]
+    pub fn promotion_rules(&self) -> PromotionRules {
+        self.promotion_rules
+    }
+
+    /// Restrict (or widen) the pieces pawns may promote to from here on
+    pub fn set_promotion_rules(&mut self, rules: PromotionRules) {
+        self.promotion_rules = rules;
+    }
+
+    /// Checks `color` has given so far (three-check)
+    #[inline]
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks_given[color as usize]
+    }
+
     /// Get Zobrist hash
     #[inline]
     pub fn hash(&self) -> u64 {
         self.hash
     }
 
+    /// Get pawn-only Zobrist hash (same for any positions with identical pawns)
+    #[inline]
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+
+    /// Material + piece-square score of `color`'s non-king pieces under the
+    /// default evaluation weights, kept up to date by every move
+    #[inline]
+    pub fn psq(&self, color: Color) -> Score {
+        self.psq[color as usize]
+    }
+
+    /// Non-king material of `color` under the default piece values
+    #[inline]
+    pub fn material(&self, color: Color) -> Score {
+        self.material[color as usize]
+    }
+
     /// Iterate over all pieces on the board
     pub fn pieces_iter(&self) -> impl Iterator<Item = (Color, PieceType, Square)> + '_ {
         let colors = [Color::White, Color::Black];
@@ -132,6 +215,20 @@ impl Position {
         })
     }
 
+    /// Compute the pawn-only Zobrist hash from scratch (for initialization / verification)
+    pub fn compute_pawn_hash(&self) -> u64 {
+        zobrist::compute_pawn_hash(self.pieces_iter())
+    }
+
+    /// Compute the material + PST sums from scratch (for verification)
+    pub fn compute_psq(&self) -> [Score; 2] {
+        let mut psq = [0; 2];
+        for (color, piece, sq) in self.pieces_iter() {
+            psq[color as usize] += PSQ[color as usize][piece as usize][sq.index()];
+        }
+        psq
+    }
+
     /// Compute Zobrist hash from scratch (for initialization / verification)
     pub fn compute_hash(&self) -> u64 {
         zobrist::compute_hash(
@@ -139,30 +236,102 @@ impl Position {
             self.side_to_move,
             self.castling,
             self.en_passant,
-        )
+        ) ^ zobrist::checks_key(Color::White, self.checks_given[0])
+            ^ zobrist::checks_key(Color::Black, self.checks_given[1])
     }
 
     /// Find what piece is on a square
+    #[inline]
     pub fn piece_on(&self, sq: Square) -> Option<(Color, PieceType)> {
-        let bb = Bitboard::from_square(sq);
+        self.board[sq.index()]
+    }
+
+    // =========================================================================
+    // VALIDATION
+    // =========================================================================
+
+    /// Cross-check the redundant parts of the position (see
+    /// `check_consistency`) and that each side has a king; extra kings are
+    /// playable. Describes the first problem found.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        self.check_consistency()?;
+        if self.pieces(Color::White, PieceType::King).is_empty() || self.pieces(Color::Black, PieceType::King).is_empty() {
+            return Err("Each side needs a king");
+        }
+        Ok(())
+    }
+
+    /// Piece bitboards against the occupancy boards and square array, the
+    /// incremental hashes and material sums against a recount, and the en
+    /// passant square against the pawn that must just have passed it. Checked
+    /// after every make/unmake in debug builds; kingless boards from
+    /// `from_fen` are playable, so the king rule of `validate` is left out.
+    fn check_consistency(&self) -> Result<(), &'static str> {
+        let mut all = Bitboard::EMPTY;
+        for color in [Color::White, Color::Black] {
+            let mut by_color = Bitboard::EMPTY;
+            for piece in self.pieces[color as usize] {
+                if (by_color & piece).is_not_empty() {
+                    return Err("Two piece bitboards share a square");
+                }
+                by_color |= piece;
+            }
+            if by_color != self.occupied_by(color) {
+                return Err("Color occupancy doesn't match the piece bitboards");
+            }
+            if (all & by_color).is_not_empty() {
+                return Err("White and black pieces share a square");
+            }
+            all |= by_color;
+        }
+        if all != self.occupied_all {
+            return Err("Occupancy doesn't match the color boards");
+        }
 
+        // One pass recounting everything kept up to date piece by piece
+        let (mut hash, mut pawn_hash, mut psq, mut material) = (0, 0, [0; 2], [0; 2]);
+        let pieces = [
+            PieceType::Pawn, PieceType::Knight, PieceType::Bishop,
+            PieceType::Rook, PieceType::Queen, PieceType::King,
+        ];
         for color in [Color::White, Color::Black] {
-            if (self.occupied_by_color[color as usize] & bb).is_not_empty() {
-                for piece_type in [
-                    PieceType::Pawn,
-                    PieceType::Knight,
-                    PieceType::Bishop,
-                    PieceType::Rook,
-                    PieceType::Queen,
-                    PieceType::King,
-                ] {
-                    if (self.pieces[color as usize][piece_type as usize] & bb).is_not_empty() {
-                        return Some((color, piece_type));
+            for piece in pieces {
+                for sq in self.pieces(color, piece) {
+                    if self.board[sq.index()] != Some((color, piece)) {
+                        return Err("Square array doesn't match the bitboards");
+                    }
+                    let key = zobrist::piece_key(color, piece, sq);
+                    hash ^= key;
+                    if piece == PieceType::Pawn {
+                        pawn_hash ^= key;
                     }
+                    psq[color as usize] += PSQ[color as usize][piece as usize][sq.index()];
+                    material[color as usize] += MATERIAL[piece as usize];
                 }
             }
         }
-        None
+        if self.board.iter().filter(|p| p.is_some()).count() != all.count() as usize {
+            return Err("Square array doesn't match the bitboards");
+        }
+        // compute_hash() from the piece keys already summed
+        let hash = zobrist::compute_hash(std::iter::empty(), self.side_to_move, self.castling, self.en_passant)
+            ^ zobrist::checks_key(Color::White, self.checks_given[0])
+            ^ zobrist::checks_key(Color::Black, self.checks_given[1])
+            ^ hash;
+        if self.hash != hash {
+            return Err("Hash doesn't match compute_hash()");
+        }
+        if self.pawn_hash != pawn_hash {
+            return Err("Pawn hash doesn't match compute_pawn_hash()");
+        }
+        if self.psq != psq || self.material != material {
+            return Err("Material sums don't match the pieces");
+        }
+
+        if self.en_passant.is_some_and(|ep| !self.en_passant_plausible(ep)) {
+            return Err("En passant square without a pawn that just passed it");
+        }
+        Ok(())
     }
 
     // =========================================================================
@@ -174,7 +343,10 @@ impl Position {
         let bb = Bitboard::from_square(sq);
         self.pieces[color as usize][piece as usize] |= bb;
         self.occupied_by_color[color as usize] |= bb;
+        self.board[sq.index()] = Some((color, piece));
         self.occupied_all |= bb;
+        self.psq[color as usize] += PSQ[color as usize][piece as usize][sq.index()];
+        self.material[color as usize] += MATERIAL[piece as usize];
     }
 
     /// Remove a piece from the board
@@ -185,6 +357,9 @@ impl Position {
         self.occupied_by_color[color as usize] =
             Bitboard(self.occupied_by_color[color as usize].0 & !bb.0);
         self.occupied_all = Bitboard(self.occupied_all.0 & !bb.0);
+        self.board[sq.index()] = None;
+        self.psq[color as usize] -= PSQ[color as usize][piece as usize][sq.index()];
+        self.material[color as usize] -= MATERIAL[piece as usize];
     }
 
     /// Move a piece (doesn't handle captures, just movement)
@@ -212,6 +387,12 @@ pub struct UndoInfo {
     pub halfmove_clock: u8,
     /// Zobrist hash BEFORE the move
     pub hash: u64,
+    /// Pawn hash BEFORE the move
+    pub pawn_hash: u64,
+    /// Checks given by each color BEFORE the move
+    pub checks_given: [u8; 2],
+    /// Lost pieces of each color BEFORE the move
+    pub lost: [[u8; 6]; 2],
 }
 
 impl Position {
@@ -234,6 +415,16 @@ impl Position {
             Some((color, piece)) if color == us => piece,
             _ => return None, // Invalid move
         };
+
+        // Promotions must be by a pawn, where and to what the variant allows
+        if let Some(promo) = m.promotion_piece() {
+            if moving_piece != PieceType::Pawn
+                || !self.variant.promotion_squares(us).has(to)
+                || !self.variant.promotion_pieces(self, us).allows(promo)
+            {
+                return None;
+            }
+        }
         
         // Save undo info BEFORE modifying anything
         let mut undo = UndoInfo {
@@ -242,6 +433,9 @@ impl Position {
             en_passant: self.en_passant,
             halfmove_clock: self.halfmove_clock,
             hash: self.hash,
+            pawn_hash: self.pawn_hash,
+            checks_given: self.checks_given,
+            lost: self.lost,
         };
 
         // === Hash: XOR out old castling rights (will XOR in new ones after update) ===
@@ -257,8 +451,12 @@ impl Position {
             if cap_color == them {
                 undo.captured = Some(cap_piece);
                 self.remove_piece(them, cap_piece, to);
+                self.lost[them as usize][cap_piece as usize] += 1;
                 // Hash: XOR out captured piece
                 self.hash ^= zobrist::piece_key(them, cap_piece, to);
+                if cap_piece == PieceType::Pawn {
+                    self.pawn_hash ^= zobrist::piece_key(them, cap_piece, to);
+                }
             } else {
                 return None; // Can't capture own piece
             }
@@ -274,8 +472,10 @@ impl Position {
             };
             undo.captured = Some(PieceType::Pawn);
             self.remove_piece(them, PieceType::Pawn, captured_sq);
+            self.lost[them as usize][PieceType::Pawn as usize] += 1;
             // Hash: XOR out en-passant captured pawn
             self.hash ^= zobrist::piece_key(them, PieceType::Pawn, captured_sq);
+            self.pawn_hash ^= zobrist::piece_key(them, PieceType::Pawn, captured_sq);
         }
         
         if m.is_castling() {
@@ -296,9 +496,14 @@ impl Position {
         // Move the piece — hash: XOR out piece from old square
         self.remove_piece(us, moving_piece, from);
         self.hash ^= zobrist::piece_key(us, moving_piece, from);
+        if moving_piece == PieceType::Pawn {
+            self.pawn_hash ^= zobrist::piece_key(us, moving_piece, from);
+        }
         
-        // Handle promotion
+        // Handle promotion; a piece promoted to comes back from the lost ones if it can
         let placed_piece = if let Some(promo) = m.promotion_piece() {
+            let lost = &mut self.lost[us as usize][promo as usize];
+            *lost = lost.saturating_sub(1);
             promo
         } else {
             moving_piece
@@ -306,6 +511,9 @@ impl Position {
         self.add_piece(us, placed_piece, to);
         // Hash: XOR in piece at new square (could be promoted piece type)
         self.hash ^= zobrist::piece_key(us, placed_piece, to);
+        if placed_piece == PieceType::Pawn {
+            self.pawn_hash ^= zobrist::piece_key(us, placed_piece, to);
+        }
         
         // Update castling rights
         self.update_castling_rights(from, to);
@@ -352,7 +560,17 @@ impl Position {
             self.unmake_move(m, &undo);
             return None;
         }
-        
+
+        if self.variant.counts_checks() && self.is_in_check(them) {
+            let given = &mut self.checks_given[us as usize];
+            self.hash ^= zobrist::checks_key(us, *given) ^ zobrist::checks_key(us, given.saturating_add(1));
+            *given = given.saturating_add(1);
+        }
+
+        #[cfg(debug_assertions)]
+        if let Err(e) = self.check_consistency() {
+            panic!("{} after {} in {}", e, m.to_uci(), self.to_fen());
+        }
         Some(undo)
     }
 
@@ -378,6 +596,9 @@ impl Position {
         self.en_passant = undo.en_passant;
         self.halfmove_clock = undo.halfmove_clock;
         self.hash = undo.hash;
+        self.pawn_hash = undo.pawn_hash;
+        self.checks_given = undo.checks_given;
+        self.lost = undo.lost;
 
         let from = m.from();
         let to = m.to();
@@ -431,6 +652,11 @@ impl Position {
             // Rook was moved from rook_from to rook_to in make_move, so reverse it
             self.move_piece(us, PieceType::Rook, rook_to, rook_from);
         }
+
+        #[cfg(debug_assertions)]
+        if let Err(e) = self.check_consistency() {
+            panic!("{} after undoing {} in {}", e, m.to_uci(), self.to_fen());
+        }
     }
     
     fn update_castling_rights(&mut self, from: Square, to: Square) {
@@ -486,6 +712,18 @@ impl Position {
         self.hash = saved_hash;
     }
 
+    /// How many pieces of a type `color` has had captured and not brought
+    /// back by promoting to it. Counted from the position the game was set
+    /// up in: pieces missing from a FEN (odds games) were never lost.
+    pub fn lost_pieces(&self, color: Color, piece: PieceType) -> u32 {
+        self.lost[color as usize][piece as usize] as u32
+    }
+
+    /// `lost_pieces` of both colors by piece type, as kept in `UndoInfo`
+    pub fn lost_counts(&self) -> [[u8; 6]; 2] {
+        self.lost
+    }
+
     /// Check if side has non-pawn material (needed for null move pruning safety).
     pub fn has_non_pawn_material(&self, color: Color) -> bool {
         let knights = self.pieces(color, PieceType::Knight);
@@ -499,68 +737,256 @@ impl Position {
     // CHECK DETECTION
     // =========================================================================
 
-    /// Check if the given side's king is in check
+    /// Check if the given side's king is in check. With several kings (after
+    /// a promotion to king) every one of them must stay out of check.
     pub fn is_in_check(&self, color: Color) -> bool {
-        let king_bb = self.pieces(color, PieceType::King);
-        if let Some(king_sq) = king_bb.lsb() {
-            self.is_square_attacked(king_sq, color.flip())
-        } else {
+        let mut kings = self.pieces(color, PieceType::King);
+        match kings.pop_lsb() {
+            Some(king_sq) => {
+                self.is_square_attacked(king_sq, color.flip())
+                    || kings.any(|sq| self.is_square_attacked(sq, color.flip()))
+            }
             // No king found - shouldn't happen in valid position
-            true
+            None => true,
         }
     }
+
+    /// Whether `color` has more than one king (promotion to king)
+    #[inline]
+    pub fn has_extra_kings(&self, color: Color) -> bool {
+        self.pieces(color, PieceType::King).count() > 1
+    }
     
+    /// Enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> Bitboard {
+        let us = self.side_to_move;
+        let mut checkers = Bitboard::EMPTY;
+        for king_sq in self.pieces(us, PieceType::King) {
+            checkers |= self.attackers_by(king_sq, us.flip());
+        }
+        checkers
+    }
+
+    /// Pieces of `color` that are the only thing between their own king and
+    /// an enemy rook, bishop or queen, so they may only move along that line
+    pub fn pinned(&self, color: Color) -> Bitboard {
+        use crate::attacks::between;
+        use crate::magic::{bishop_attacks, rook_attacks};
+
+        let king_sq = match self.pieces(color, PieceType::King).lsb() {
+            Some(sq) => sq,
+            None => return Bitboard::EMPTY,
+        };
+        let them = color.flip();
+        let queens = self.pieces(them, PieceType::Queen);
+        let snipers = (rook_attacks(king_sq, Bitboard::EMPTY) & (self.pieces(them, PieceType::Rook) | queens))
+            | (bishop_attacks(king_sq, Bitboard::EMPTY) & (self.pieces(them, PieceType::Bishop) | queens));
+
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = between(king_sq, sniper) & self.occupied();
+            if blockers.count() == 1 {
+                pinned |= blockers & self.occupied_by(color);
+            }
+        }
+        pinned
+    }
+
+    /// Pieces of both colors attacking a square
+    pub fn attackers_to(&self, sq: Square) -> Bitboard {
+        self.attackers_to_with(sq, self.occupied())
+    }
+
+    /// Pieces of one color attacking a square
+    pub fn attackers_by(&self, sq: Square, color: Color) -> Bitboard {
+        self.attackers_to(sq) & self.occupied_by(color)
+    }
+
+    /// Pieces on `occupied` attacking a square, with sliders seeing through
+    /// everything else. Lets exchange evaluation take pieces off one by one
+    /// and uncover the x-ray attackers behind them.
+    pub fn attackers_to_with(&self, sq: Square, occupied: Bitboard) -> Bitboard {
+        use crate::attacks::{knight_attacks, king_attacks, pawn_attacks};
+        use crate::magic::{bishop_attacks, rook_attacks};
+
+        let both = |piece| self.pieces(Color::White, piece) | self.pieces(Color::Black, piece);
+        let queens = both(PieceType::Queen);
+
+        let attackers = (knight_attacks(sq) & both(PieceType::Knight))
+            | (king_attacks(sq) & both(PieceType::King))
+            // A white pawn attacks `sq` from where a black pawn on `sq` would attack
+            | (pawn_attacks(sq, false) & self.pieces(Color::White, PieceType::Pawn))
+            | (pawn_attacks(sq, true) & self.pieces(Color::Black, PieceType::Pawn))
+            | (bishop_attacks(sq, occupied) & (both(PieceType::Bishop) | queens))
+            | (rook_attacks(sq, occupied) & (both(PieceType::Rook) | queens));
+        attackers & occupied
+    }
+
     /// Check if a square is attacked by the given side
     pub fn is_square_attacked(&self, sq: Square, attacker: Color) -> bool {
+        self.is_attacked_with(sq, attacker, self.occupied(), Bitboard::EMPTY)
+    }
+
+    /// Attack test against a hypothetical board: sliders see through
+    /// `occupied`, and attacker pieces on `removed` (just captured) don't count.
+    #[inline]
+    pub(crate) fn is_attacked_with(&self, sq: Square, attacker: Color, occupied: Bitboard, removed: Bitboard) -> bool {
         use crate::attacks::{knight_attacks, king_attacks, pawn_attacks};
-        
+        use crate::magic::{bishop_attacks, rook_attacks};
+        let theirs = |piece| self.pieces(attacker, piece) & !removed;
+
         // Knight attacks
-        let knights = self.pieces(attacker, PieceType::Knight);
-        if (knight_attacks(sq) & knights).is_not_empty() {
+        if (knight_attacks(sq) & theirs(PieceType::Knight)).is_not_empty() {
             return true;
         }
-        
+
         // King attacks
-        let king = self.pieces(attacker, PieceType::King);
-        if (king_attacks(sq) & king).is_not_empty() {
+        if (king_attacks(sq) & theirs(PieceType::King)).is_not_empty() {
             return true;
         }
-        
+
         // Pawn attacks (note: we check from defender's perspective)
-        let pawns = self.pieces(attacker, PieceType::Pawn);
         let pawn_att = pawn_attacks(sq, attacker == Color::Black); // Flip because we're checking who attacks this square
-        if (pawn_att & pawns).is_not_empty() {
+        if (pawn_att & theirs(PieceType::Pawn)).is_not_empty() {
             return true;
         }
-        
+
         // Sliding piece attacks using magic bitboards
-        use crate::magic::{bishop_attacks, rook_attacks};
-        let occupied = self.occupied();
-        
+        let queens = theirs(PieceType::Queen);
+
         // Bishop + Queen diagonal attacks
-        let bishops = self.pieces(attacker, PieceType::Bishop);
-        let queens = self.pieces(attacker, PieceType::Queen);
-        let bishop_att = bishop_attacks(sq, occupied);
-        if (bishop_att & (bishops | queens)).is_not_empty() {
+        if (bishop_attacks(sq, occupied) & (theirs(PieceType::Bishop) | queens)).is_not_empty() {
             return true;
         }
-        
+
         // Rook + Queen straight attacks
-        let rooks = self.pieces(attacker, PieceType::Rook);
-        let rook_att = rook_attacks(sq, occupied);
-        if (rook_att & (rooks | queens)).is_not_empty() {
+        if (rook_attacks(sq, occupied) & (theirs(PieceType::Rook) | queens)).is_not_empty() {
             return true;
         }
-        
+
         false
     }
 
+    /// Whether `m` is a legal move here, without making it. Checks the move
+    /// is one the side to move could play, then that its own king isn't
+    /// attacked on the board after the move.
+    pub fn is_legal(&self, m: Move) -> bool {
+        if !crate::movegen::generate_pseudo_legal_moves(self).iter().any(|&x| x == m) {
+            return false;
+        }
+        if self.has_extra_kings(self.side_to_move) {
+            return self.clone().make_move(m).is_some();
+        }
+        // The generator already checks the squares the king crosses
+        if m.is_castling() {
+            return true;
+        }
+
+        let us = self.side_to_move;
+        let (from, to) = (m.from(), m.to());
+        let mut captured = Bitboard::from_square(to);
+        if m.is_en_passant() {
+            let behind = if us == Color::White { to.0 - 8 } else { to.0 + 8 };
+            captured = Bitboard::from_square(Square::new(behind));
+        }
+        let occupied = (self.occupied() & !Bitboard::from_square(from) & !captured) | Bitboard::from_square(to);
+
+        let king_sq = if self.pieces(us, PieceType::King).has(from) {
+            to
+        } else {
+            match self.pieces(us, PieceType::King).lsb() {
+                Some(sq) => sq,
+                None => return false,
+            }
+        };
+        !self.is_attacked_with(king_sq, us.flip(), occupied, captured)
+    }
+
+    /// Whether the move, legal or pseudo-legal for the side to move, gives
+    /// check: directly from the moved (or promoted) piece, or by uncovering a
+    /// rook, bishop or queen. Cheap enough for move ordering; doesn't make the move.
+    pub fn gives_check(&self, m: Move) -> bool {
+        use crate::attacks::{knight_attacks, pawn_attacks};
+        use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+
+        let us = self.side_to_move;
+        if self.has_extra_kings(us.flip()) {
+            let mut after = self.clone();
+            return after.make_move(m).is_some() && after.is_in_check(us.flip());
+        }
+        let king_sq = match self.pieces(us.flip(), PieceType::King).lsb() {
+            Some(sq) => sq,
+            None => return false,
+        };
+        let (from, to) = (m.from(), m.to());
+        let piece = match self.piece_on(from) {
+            Some((_, piece)) => m.promotion_piece().unwrap_or(piece),
+            None => return false,
+        };
+
+        // Board after the move, as far as lines to the king are concerned
+        let mut occupied = (self.occupied() & !Bitboard::from_square(from)) | Bitboard::from_square(to);
+        let mut moved = Bitboard::from_square(from);
+        if m.is_en_passant() {
+            let captured = if us == Color::White { to.0 - 8 } else { to.0 + 8 };
+            occupied &= !Bitboard::from_square(Square::new(captured));
+        }
+        if m.is_castling() {
+            // The rook lands next to the king's destination and may give check
+            let rank = to.rank();
+            let (rook_from, rook_to) = if to.file() == 6 { (7, 5) } else { (0, 3) };
+            let (rook_from, rook_to) = (Square::from_file_rank(rook_from, rank), Square::from_file_rank(rook_to, rank));
+            occupied = (occupied & !Bitboard::from_square(rook_from)) | Bitboard::from_square(rook_to);
+            moved |= Bitboard::from_square(rook_from);
+            if rook_attacks(rook_to, occupied).has(king_sq) {
+                return true;
+            }
+        }
+
+        let direct = match piece {
+            PieceType::Pawn => pawn_attacks(to, us == Color::White).has(king_sq),
+            PieceType::Knight => knight_attacks(to).has(king_sq),
+            PieceType::Bishop => bishop_attacks(to, occupied).has(king_sq),
+            PieceType::Rook => rook_attacks(to, occupied).has(king_sq),
+            PieceType::Queen => queen_attacks(to, occupied).has(king_sq),
+            PieceType::King => false,
+        };
+        if direct {
+            return true;
+        }
+
+        // Discovered: one of our other sliders now sees the king
+        let queens = self.pieces(us, PieceType::Queen);
+        let sliders = ((bishop_attacks(king_sq, occupied) & (self.pieces(us, PieceType::Bishop) | queens))
+            | (rook_attacks(king_sq, occupied) & (self.pieces(us, PieceType::Rook) | queens)))
+            & !moved;
+        sliders.is_not_empty()
+    }
+
     // =========================================================================
     // FEN PARSING
     // =========================================================================
 
-    /// Parse position from FEN string
+    /// Parse position from FEN string. Three-check counts are read from a
+    /// "+1+0" field after the move counters (checks given) or a "2+3" field
+    /// before them (checks remaining).
+    /// Fields the placement can't support are dropped rather than refused:
+    /// castling rights without their king and rook, and an en passant square
+    /// no pawn can just have passed.
     pub fn from_fen(fen: &str) -> Result<Self, &'static str> {
+        let mut pos = Self::parse_fen(fen)?;
+        let missing = pos.castling_without_pieces().fold(0, |rights, (right, _)| rights | right);
+        pos.castling.remove(missing);
+        if pos.en_passant.is_some_and(|ep| !pos.en_passant_plausible(ep)) {
+            pos.en_passant = None;
+        }
+        pos.hash = pos.compute_hash();
+        Ok(pos)
+    }
+
+    /// Read every FEN field as written, without checking the fields against
+    /// the board
+    fn parse_fen(fen: &str) -> Result<Self, &'static str> {
         let parts: Vec<&str> = fen.split_whitespace().collect();
         if parts.len() < 4 {
             return Err("FEN must have at least 4 parts");
@@ -583,6 +1009,9 @@ impl Position {
                 }
                 '1'..='8' => {
                     file += ch.to_digit(10).unwrap() as u8;
+                    if file > 8 {
+                        return Err("Too many files in FEN rank");
+                    }
                 }
                 _ => {
                     if file >= 8 {
@@ -639,14 +1068,22 @@ impl Position {
             Square::from_algebraic(parts[3])
         };
 
+        // Three-check counts (optional), the one field with a '+'
+        let mut counters: Vec<&str> = parts[4..].to_vec();
+        if let Some(i) = counters.iter().position(|f| f.contains('+')) {
+            pos.checks_given = parse_check_counts(counters.remove(i)).ok_or("Invalid check counts")?;
+            pos.variant = Variant::ThreeCheck;
+        }
+
         // Parse halfmove clock (optional)
-        pos.halfmove_clock = parts.get(4).and_then(|s| s.parse().ok()).unwrap_or(0);
+        pos.halfmove_clock = counters.first().and_then(|s| s.parse().ok()).unwrap_or(0);
 
         // Parse fullmove number (optional)
-        pos.fullmove_number = parts.get(5).and_then(|s| s.parse().ok()).unwrap_or(1);
+        pos.fullmove_number = counters.get(1).and_then(|s| s.parse().ok()).unwrap_or(1);
 
         // Compute Zobrist hash from the fully parsed position
         pos.hash = pos.compute_hash();
+        pos.pawn_hash = pos.compute_pawn_hash();
 
         Ok(pos)
     }
@@ -732,8 +1169,406 @@ impl Position {
         // Halfmove clock and fullmove number
         fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
 
+        // Checks given, in variants that count them
+        if self.variant.counts_checks() {
+            fen.push_str(&format!(" +{}+{}", self.checks_given[0], self.checks_given[1]));
+        }
+
         fen
     }
+
+    // =========================================================================
+    // COLOR MIRROR (for eval symmetry checks)
+    // =========================================================================
+
+    /// The same position with colors swapped and the board flipped vertically:
+    /// white pieces become black pieces on the mirrored rank, the other side is
+    /// to move, and castling/en passant are mirrored to match.
+    pub fn mirrored(&self) -> Position {
+        let mut pos = Position::empty();
+        for (color, piece, sq) in self.pieces_iter() {
+            pos.add_piece(color.flip(), piece, Square::new(sq.0 ^ 56));
+        }
+
+        pos.side_to_move = self.side_to_move.flip();
+        let mut castling = CastlingRights::NONE;
+        for (from, to) in [
+            (CastlingRights::WHITE_KINGSIDE, CastlingRights::BLACK_KINGSIDE),
+            (CastlingRights::WHITE_QUEENSIDE, CastlingRights::BLACK_QUEENSIDE),
+            (CastlingRights::BLACK_KINGSIDE, CastlingRights::WHITE_KINGSIDE),
+            (CastlingRights::BLACK_QUEENSIDE, CastlingRights::WHITE_QUEENSIDE),
+        ] {
+            if self.castling.has(from) {
+                castling.add(to);
+            }
+        }
+        pos.castling = castling;
+        pos.en_passant = self.en_passant.map(|sq| Square::new(sq.0 ^ 56));
+        pos.halfmove_clock = self.halfmove_clock;
+        pos.fullmove_number = self.fullmove_number;
+        pos.variant = self.variant;
+        pos.promotion_rules = self.promotion_rules;
+        pos.checks_given = [self.checks_given[1], self.checks_given[0]];
+        pos.lost = [self.lost[1], self.lost[0]];
+        pos.hash = pos.compute_hash();
+        pos.pawn_hash = pos.compute_pawn_hash();
+        pos
+    }
+}
+
+// =============================================================================
+// STRICT FEN VALIDATION
+// =============================================================================
+
+/// What `Position::from_fen_strict` found wrong with a FEN.
+/// Ranks are 1-8 as printed on a board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN has 4 to 6 space-separated fields
+    WrongFieldCount(usize),
+    /// Piece placement must have 8 ranks separated by '/'
+    WrongRankCount(usize),
+    /// A rank doesn't add up to 8 squares
+    BadRankLength(u8),
+    InvalidPiece(char),
+    InvalidSideToMove,
+    InvalidCastling,
+    InvalidEnPassant,
+    InvalidHalfmoveClock,
+    InvalidFullmoveNumber,
+    /// Each side needs exactly one king
+    KingCount(Color, u32),
+    /// More than 8 pawns or 16 pieces for a side
+    TooManyPieces(Color),
+    PawnOnBackRank(Square),
+    /// The side that just moved is still in check
+    OpponentInCheck,
+    /// A castling right whose king or rook is not on its starting square
+    CastlingWithoutPieces(char),
+    /// No pawn can just have made a double step to pass this square
+    ImpossibleEnPassant(Square),
+    /// Three-check field that is neither "+W+B" nor "W+B" (0 to 3)
+    InvalidCheckCounts,
+}
+
+fn color_name(color: Color) -> &'static str {
+    if color == Color::White { "White" } else { "Black" }
+}
+
+impl FenError {
+    /// FEN field at fault, named as in error objects ("placement",
+    /// "sideToMove", "castling", "enPassant", "halfmoveClock",
+    /// "fullmoveNumber", "checks"). None when the field count is wrong.
+    pub fn field(&self) -> Option<&'static str> {
+        Some(match self {
+            FenError::WrongFieldCount(_) => return None,
+            FenError::WrongRankCount(_)
+            | FenError::BadRankLength(_)
+            | FenError::InvalidPiece(_)
+            | FenError::KingCount(..)
+            | FenError::TooManyPieces(_)
+            | FenError::PawnOnBackRank(_)
+            | FenError::OpponentInCheck => "placement",
+            FenError::InvalidSideToMove => "sideToMove",
+            FenError::InvalidCastling | FenError::CastlingWithoutPieces(_) => "castling",
+            FenError::InvalidEnPassant | FenError::ImpossibleEnPassant(_) => "enPassant",
+            FenError::InvalidHalfmoveClock => "halfmoveClock",
+            FenError::InvalidFullmoveNumber => "fullmoveNumber",
+            FenError::InvalidCheckCounts => "checks",
+        })
+    }
+}
+
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            FenError::WrongFieldCount(n) => write!(f, "FEN must have 4 to 6 fields, found {}", n),
+            FenError::WrongRankCount(n) => write!(f, "Piece placement must have 8 ranks, found {}", n),
+            FenError::BadRankLength(rank) => write!(f, "Rank {} does not have 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "Invalid piece character '{}'", c),
+            FenError::InvalidSideToMove => write!(f, "Side to move must be 'w' or 'b'"),
+            FenError::InvalidCastling => write!(f, "Castling rights must be '-' or a combination of KQkq"),
+            FenError::InvalidEnPassant => write!(f, "En passant square must be '-' or a square on rank 3 or 6"),
+            FenError::InvalidHalfmoveClock => write!(f, "Invalid halfmove clock"),
+            FenError::InvalidFullmoveNumber => write!(f, "Fullmove number must be at least 1"),
+            FenError::KingCount(color, n) => write!(f, "{} has {} kings, needs exactly 1", color_name(color), n),
+            FenError::TooManyPieces(color) => write!(f, "{} has more than 8 pawns or 16 pieces", color_name(color)),
+            FenError::PawnOnBackRank(sq) => write!(f, "Pawn on {} (first or last rank)", sq.to_algebraic()),
+            FenError::OpponentInCheck => write!(f, "The side not to move is in check"),
+            FenError::CastlingWithoutPieces(c) => write!(f, "Castling right '{}' without king and rook on their squares", c),
+            FenError::ImpossibleEnPassant(sq) => write!(f, "En passant square {} is impossible here", sq.to_algebraic()),
+            FenError::InvalidCheckCounts => write!(f, "Check counts must look like +1+0 (given) or 2+3 (remaining)"),
+        }
+    }
+}
+
+impl Position {
+    /// Parse a FEN and reject anything that can't occur in a game: unlike
+    /// `from_fen`, every field must be well-formed, each side needs one king,
+    /// pawns can't stand on the back ranks, the side not to move can't be in
+    /// check, and castling rights and the en passant square must match the board.
+    pub fn from_fen_strict(fen: &str) -> Result<Self, FenError> {
+        let mut parts: Vec<&str> = fen.split_whitespace().collect();
+        // An optional three-check field, anywhere after the en passant square
+        if let Some(i) = parts.iter().skip(4).position(|f| f.contains('+')) {
+            if parse_check_counts(parts.remove(i + 4)).is_none() {
+                return Err(FenError::InvalidCheckCounts);
+            }
+        }
+        if !(4..=6).contains(&parts.len()) {
+            return Err(FenError::WrongFieldCount(parts.len()));
+        }
+
+        let ranks: Vec<&str> = parts[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+        for (i, rank) in ranks.iter().enumerate() {
+            let mut squares = 0;
+            for ch in rank.chars() {
+                match ch {
+                    '1'..='8' => squares += ch.to_digit(10).unwrap(),
+                    'P' | 'N' | 'B' | 'R' | 'Q' | 'K' | 'p' | 'n' | 'b' | 'r' | 'q' | 'k' => squares += 1,
+                    _ => return Err(FenError::InvalidPiece(ch)),
+                }
+            }
+            if squares != 8 {
+                return Err(FenError::BadRankLength(8 - i as u8));
+            }
+        }
+
+        if !matches!(parts[1], "w" | "b") {
+            return Err(FenError::InvalidSideToMove);
+        }
+        let castling = parts[2];
+        let well_formed = castling == "-"
+            || (!castling.is_empty()
+                && castling.chars().all(|c| "KQkq".contains(c))
+                && castling.chars().enumerate().all(|(i, c)| !castling[i + 1..].contains(c)));
+        if !well_formed {
+            return Err(FenError::InvalidCastling);
+        }
+        if parts[3] != "-" && !Square::from_algebraic(parts[3]).is_some_and(|sq| sq.rank() == 2 || sq.rank() == 5) {
+            return Err(FenError::InvalidEnPassant);
+        }
+        if parts.get(4).is_some_and(|s| s.parse::<u8>().is_err()) {
+            return Err(FenError::InvalidHalfmoveClock);
+        }
+        if parts.get(5).is_some_and(|s| !s.parse::<u16>().is_ok_and(|n| n >= 1)) {
+            return Err(FenError::InvalidFullmoveNumber);
+        }
+
+        // Well-formed, so the raw parser reads it completely
+        let pos = Position::parse_fen(fen).expect("FEN syntax already checked");
+        pos.check_legal()?;
+        Ok(pos)
+    }
+
+    /// Board-level checks of `from_fen_strict`.
+    fn check_legal(&self) -> Result<(), FenError> {
+        for color in [Color::White, Color::Black] {
+            let kings = self.pieces(color, PieceType::King).count();
+            if kings != 1 {
+                return Err(FenError::KingCount(color, kings));
+            }
+            if self.pieces(color, PieceType::Pawn).count() > 8 || self.occupied_by(color).count() > 16 {
+                return Err(FenError::TooManyPieces(color));
+            }
+        }
+
+        let pawns = self.pieces(Color::White, PieceType::Pawn) | self.pieces(Color::Black, PieceType::Pawn);
+        if let Some(sq) = (pawns & (Bitboard::RANK_1 | Bitboard::RANK_8)).lsb() {
+            return Err(FenError::PawnOnBackRank(sq));
+        }
+
+        if self.is_in_check(self.side_to_move.flip()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        if let Some((_, letter)) = self.castling_without_pieces().next() {
+            return Err(FenError::CastlingWithoutPieces(letter));
+        }
+        if let Some(ep) = self.en_passant.filter(|&ep| !self.en_passant_plausible(ep)) {
+            return Err(FenError::ImpossibleEnPassant(ep));
+        }
+
+        Ok(())
+    }
+}
+
+impl Position {
+    /// Castling rights held (with their FEN letters) whose king or rook is
+    /// not on its starting square
+    fn castling_without_pieces(&self) -> impl Iterator<Item = (u8, char)> + '_ {
+        [
+            (CastlingRights::WHITE_KINGSIDE, 'K', Color::White, 4, 7),
+            (CastlingRights::WHITE_QUEENSIDE, 'Q', Color::White, 4, 0),
+            (CastlingRights::BLACK_KINGSIDE, 'k', Color::Black, 60, 63),
+            (CastlingRights::BLACK_QUEENSIDE, 'q', Color::Black, 60, 56),
+        ]
+        .into_iter()
+        .filter(move |&(right, _, color, king, rook)| {
+            self.castling.has(right)
+                && (self.piece_on(Square::new(king)) != Some((color, PieceType::King))
+                    || self.piece_on(Square::new(rook)) != Some((color, PieceType::Rook)))
+        })
+        .map(|(right, letter, ..)| (right, letter))
+    }
+
+    /// Whether a pawn can just have made a double step to pass `ep`: it
+    /// stands in front of `ep` (from the mover's view), and both squares it
+    /// crossed are empty
+    fn en_passant_plausible(&self, ep: Square) -> bool {
+        let mover = self.side_to_move.flip();
+        let (expected_rank, pawn_sq, origin) = match mover {
+            Color::Black => (5, ep.0.wrapping_sub(8), ep.0 + 8),
+            Color::White => (2, ep.0 + 8, ep.0.wrapping_sub(8)),
+        };
+        ep.rank() == expected_rank
+            && self.piece_on(Square::new(pawn_sq)) == Some((mover, PieceType::Pawn))
+            && self.piece_on(ep).is_none()
+            && self.piece_on(Square::new(origin)).is_none()
+    }
+}
+
+impl From<FenError> for JsValue {
+    fn from(e: FenError) -> JsValue {
+        JsValue::from_str(&e.to_string())
+    }
+}
+
+// =============================================================================
+// POSITION BUILDER
+// =============================================================================
+
+/// Sets up a position square by square, for board editors. Nothing is checked
+/// until `build()`, which applies the same rules as `from_fen_strict`.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct PositionBuilder {
+    pos: Position,
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl PositionBuilder {
+    /// Empty board, white to move, no castling rights
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PositionBuilder {
+        PositionBuilder { pos: Position::empty() }
+    }
+
+    /// Start from an existing position, e.g. to edit the current game
+    pub fn from_position(pos: &Position) -> PositionBuilder {
+        PositionBuilder { pos: pos.clone() }
+    }
+
+    /// Put a piece ("PNBRQK" white, "pnbrqk" black) on a square, replacing
+    /// whatever was there. Returns false for a bad square or piece letter.
+    pub fn put_piece(&mut self, file: u8, rank: u8, piece: char) -> bool {
+        let kind = match piece.to_ascii_lowercase() {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
+            _ => return false,
+        };
+        if file >= 8 || rank >= 8 {
+            return false;
+        }
+        let color = if piece.is_ascii_uppercase() { Color::White } else { Color::Black };
+        self.remove_piece(file, rank);
+        self.pos.add_piece(color, kind, Square::from_file_rank(file, rank));
+        true
+    }
+
+    /// Empty a square. Returns false if there was nothing on it.
+    pub fn remove_piece(&mut self, file: u8, rank: u8) -> bool {
+        if file >= 8 || rank >= 8 {
+            return false;
+        }
+        let sq = Square::from_file_rank(file, rank);
+        match self.pos.piece_on(sq) {
+            Some((color, piece)) => {
+                self.pos.remove_piece(color, piece, sq);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every piece, keeping side to move and move counters
+    pub fn clear(&mut self) {
+        self.pos.pieces = [[Bitboard::EMPTY; 6]; 2];
+        self.pos.occupied_by_color = [Bitboard::EMPTY; 2];
+        self.pos.occupied_all = Bitboard::EMPTY;
+        self.pos.board = [None; 64];
+        self.pos.psq = [0; 2];
+        self.pos.material = [0; 2];
+    }
+
+    pub fn set_turn(&mut self, white: bool) {
+        self.pos.side_to_move = if white { Color::White } else { Color::Black };
+    }
+
+    /// Castling rights as in FEN ("KQkq", "Kq", "-")
+    pub fn set_castling(&mut self, rights: &str) -> bool {
+        let mut castling = CastlingRights::NONE;
+        if rights != "-" {
+            for ch in rights.chars() {
+                match ch {
+                    'K' => castling.add(CastlingRights::WHITE_KINGSIDE),
+                    'Q' => castling.add(CastlingRights::WHITE_QUEENSIDE),
+                    'k' => castling.add(CastlingRights::BLACK_KINGSIDE),
+                    'q' => castling.add(CastlingRights::BLACK_QUEENSIDE),
+                    _ => return false,
+                }
+            }
+        }
+        self.pos.castling = castling;
+        true
+    }
+
+    /// En passant target square ("e3") or "-" for none
+    pub fn set_ep(&mut self, square: &str) -> bool {
+        if square == "-" {
+            self.pos.en_passant = None;
+            return true;
+        }
+        match Square::from_algebraic(square) {
+            Some(sq) => {
+                self.pos.en_passant = Some(sq);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_halfmove_clock(&mut self, halfmoves: u8) {
+        self.pos.halfmove_clock = halfmoves;
+    }
+
+    /// Fullmove number, at least 1
+    pub fn set_fullmove_number(&mut self, fullmove: u16) {
+        self.pos.fullmove_number = fullmove.max(1);
+    }
+
+    /// Check the position and compute its hashes. The error describes the
+    /// first problem found (see `FenError`).
+    pub fn build(&self) -> Result<Position, FenError> {
+        let mut pos = self.pos.clone();
+        pos.check_legal()?;
+        pos.hash = pos.compute_hash();
+        pos.pawn_hash = pos.compute_pawn_hash();
+        Ok(pos)
+    }
 }
 
 // =============================================================================
@@ -792,8 +1627,7 @@ impl Position {
         if !self.is_in_check(self.side_to_move) {
             return false;
         }
-        let mut clone = self.clone();
-        crate::movegen::generate_legal_moves(&mut clone).is_empty()
+        crate::movegen::generate_legal_moves(self).is_empty()
     }
 
     /// Check if current side is in stalemate (NOT in check AND no legal moves)
@@ -801,15 +1635,21 @@ impl Position {
         if self.is_in_check(self.side_to_move) {
             return false;
         }
-        let mut clone = self.clone();
-        crate::movegen::generate_legal_moves(&mut clone).is_empty()
+        crate::movegen::generate_legal_moves(self).is_empty()
     }
 
     /// Check if the position has insufficient material for either side to checkmate
-    /// Returns true for: K vs K, K+N vs K, K+B vs K, K+B vs K+B (same color bishops)
+    /// Returns true for: K vs K, K+N vs K, K+B vs K, K+B vs K+B (same color bishops).
+    /// Under three-check any piece can still give the checks that win, so only
+    /// bare kings are insufficient.
     pub fn is_insufficient_material(&self) -> bool {
         use crate::bitboard::Bitboard;
 
+        if self.variant.counts_checks() {
+            let kings = self.pieces(Color::White, PieceType::King) | self.pieces(Color::Black, PieceType::King);
+            return (self.occupied() & !kings).is_empty();
+        }
+
         let white_pawns = self.pieces[Color::White as usize][PieceType::Pawn as usize];
         let black_pawns = self.pieces[Color::Black as usize][PieceType::Pawn as usize];
         let white_rooks = self.pieces[Color::White as usize][PieceType::Rook as usize];
@@ -861,6 +1701,130 @@ impl Position {
         false
     }
 
+    /// Whether `color` has enough material to win on time: a pawn, rook or
+    /// queen, or two minor pieces. A lone king, or king and one minor piece,
+    /// only draws when the opponent's flag falls (the USCF convention).
+    /// Under three-check any piece besides the king will do.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        if self.variant.counts_checks() {
+            return (self.occupied_by(color) & !self.pieces(color, PieceType::King)).is_not_empty();
+        }
+        let majors = self.pieces(color, PieceType::Pawn) | self.pieces(color, PieceType::Rook) | self.pieces(color, PieceType::Queen);
+        let minors = self.pieces(color, PieceType::Knight) | self.pieces(color, PieceType::Bishop);
+        majors.is_not_empty() || minors.count() >= 2
+    }
+
+    /// Check if no sequence of legal moves can end in checkmate (FIDE 5.2.2).
+    /// Besides insufficient material this covers kings with bishops all on
+    /// one square color, and locked pawn walls the kings can't get through.
+    /// Errs on the side of "not dead". Under three-check, blocked bishops and
+    /// pawns can still give check, so only bare kings are dead.
+    pub fn is_dead_position(&self) -> bool {
+        use crate::bitboard::Bitboard;
+
+        if self.is_insufficient_material() {
+            return true;
+        }
+        if self.variant.counts_checks() {
+            return false;
+        }
+        let [white, black] = [Color::White, Color::Black].map(|c| {
+            self.pieces(c, PieceType::Knight) | self.pieces(c, PieceType::Rook) | self.pieces(c, PieceType::Queen)
+        });
+        if (white | black).is_not_empty() {
+            return false;
+        }
+
+        let pawns = self.pieces(Color::White, PieceType::Pawn) | self.pieces(Color::Black, PieceType::Pawn);
+        if pawns.is_empty() {
+            // Bishops on one color only ever attack that color; a king can
+            // always step orthogonally off it
+            let bishops = self.pieces(Color::White, PieceType::Bishop) | self.pieces(Color::Black, PieceType::Bishop);
+            return (bishops & Bitboard::LIGHT_SQUARES).is_empty() || (bishops & Bitboard::DARK_SQUARES).is_empty();
+        }
+        self.is_locked_pawn_wall()
+    }
+
+    /// Kings, bishops and pawns where no pawn can ever move again and neither
+    /// king can be mated. Pawns are frozen when every one is blocked by a pawn
+    /// in front, none can capture a pawn, no bishop stands on a color the
+    /// enemy pawns attack, and no king can reach an enemy pawn without
+    /// walking into a pawn attack.
+    fn is_locked_pawn_wall(&self) -> bool {
+        use crate::bitboard::Bitboard;
+
+        let white_pawns = self.pieces(Color::White, PieceType::Pawn);
+        let black_pawns = self.pieces(Color::Black, PieceType::Pawn);
+        let pawns = white_pawns | black_pawns;
+        if (white_pawns.north() & !pawns).is_not_empty() || (black_pawns.south() & !pawns).is_not_empty() {
+            return false;
+        }
+        let white_attacks = white_pawns.north_east() | white_pawns.north_west();
+        let black_attacks = black_pawns.south_east() | black_pawns.south_west();
+        if (white_attacks & black_pawns).is_not_empty() || (black_attacks & white_pawns).is_not_empty() {
+            return false;
+        }
+
+        // Squares each king can ever walk to
+        let mut regions = [Bitboard::EMPTY; 2];
+        for color in [Color::White, Color::Black] {
+            let (own_pawns, enemy_pawns, enemy_attacks) = match color {
+                Color::White => (white_pawns, black_pawns, black_attacks),
+                Color::Black => (black_pawns, white_pawns, white_attacks),
+            };
+            let king = self.pieces(color, PieceType::King);
+            if (king & enemy_attacks).is_not_empty() {
+                return false;
+            }
+            let passable = !own_pawns & !enemy_attacks;
+            let mut region = king;
+            loop {
+                let grown = (region | king_zone(region)) & passable;
+                if grown == region {
+                    break;
+                }
+                region = grown;
+            }
+            if (region & enemy_pawns).is_not_empty() {
+                return false;
+            }
+            regions[color as usize] = region;
+        }
+
+        for color in [Color::White, Color::Black] {
+            let them = color.flip();
+            let enemy_attacks = if color == Color::White { black_attacks } else { white_attacks };
+            let enemy_bishops = self.pieces(them, PieceType::Bishop);
+            let region = regions[color as usize];
+            for (shade, other) in [
+                (Bitboard::LIGHT_SQUARES, Bitboard::DARK_SQUARES),
+                (Bitboard::DARK_SQUARES, Bitboard::LIGHT_SQUARES),
+            ] {
+                // Our bishops would be taken by a pawn, unlocking the wall
+                if (self.pieces(color, PieceType::Bishop) & shade).is_not_empty() && (enemy_attacks & shade).is_not_empty() {
+                    return false;
+                }
+                if (enemy_bishops & shade).is_empty() {
+                    continue;
+                }
+                // Their bishops can check us on `shade` squares. The king
+                // always has an orthogonal escape (on the other shade) unless
+                // the regions touch or our own bishop can block it.
+                let orthogonal = region.north() | region.south() | region.east() | region.west();
+                let escapable = (region & shade & !orthogonal).is_empty();
+                let kings_apart = (king_zone(region) & regions[them as usize]).is_empty();
+                if (enemy_bishops & other).is_not_empty()
+                    || (self.pieces(color, PieceType::Bishop) & other).is_not_empty()
+                    || !escapable
+                    || !kings_apart
+                {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
     /// Check if the 50-move rule has been reached (halfmove clock >= 100)
     pub fn is_fifty_move_draw(&self) -> bool {
         self.halfmove_clock >= 100
@@ -870,26 +1834,54 @@ impl Position {
     /// Note: Threefold repetition is NOT checked here — it requires move history,
     /// which is tracked by GameState in lib.rs.
     pub fn is_draw(&self) -> bool {
-        self.is_stalemate() || self.is_insufficient_material() || self.is_fifty_move_draw()
+        self.is_stalemate() || self.is_dead_position() || self.is_fifty_move_draw()
     }
 
     /// Get game status string
-    /// Returns "checkmate", "stalemate", "draw", or "playing"
+    /// Returns "checkmate", "stalemate", "draw", or "playing", or the name of
+    /// a variant's own win condition.
     /// Note: Does not detect threefold repetition (needs history).
     pub fn game_status(&self) -> String {
+        if let Some((_, status)) = self.variant.outcome(self) {
+            return status.to_string();
+        }
         if self.is_checkmate() {
             return "checkmate".to_string();
         }
         if self.is_stalemate() {
             return "stalemate".to_string();
         }
-        if self.is_insufficient_material() || self.is_fifty_move_draw() {
+        if self.is_dead_position() || self.is_fifty_move_draw() {
             return "draw".to_string();
         }
         "playing".to_string()
     }
 }
 
+/// Three-check counts from a FEN field: "+W+B" checks given, or "W+B" checks
+/// remaining out of three
+fn parse_check_counts(field: &str) -> Option<[u8; 2]> {
+    let (given, counts) = match field.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, field),
+    };
+    let (white, black) = counts.split_once('+')?;
+    let (white, black): (u8, u8) = (white.parse().ok()?, black.parse().ok()?);
+    if given {
+        Some([white, black])
+    } else if white <= 3 && black <= 3 {
+        Some([3 - white, 3 - black])
+    } else {
+        None
+    }
+}
+
+/// Every square a king on any square of `bb` could step to
+fn king_zone(bb: crate::bitboard::Bitboard) -> crate::bitboard::Bitboard {
+    let row = bb | bb.east() | bb.west();
+    row | row.north() | row.south()
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -977,6 +1969,18 @@ mod tests {
         assert_eq!(pos.halfmove_clock(), 0);
     }
 
+    #[test]
+    fn test_mirrored() {
+        let pos = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w Kq - 0 1").unwrap();
+        let mirror = pos.mirrored();
+        assert_eq!(mirror.to_fen(), "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b Qk - 0 1");
+        assert_eq!(mirror.hash(), mirror.compute_hash());
+        assert_eq!(mirror.mirrored().to_fen(), pos.to_fen(), "Mirroring twice is the identity");
+
+        let ep = Position::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
+        assert_eq!(ep.mirrored().to_fen(), "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+    }
+
     // =========================================================================
     // ZOBRIST HASH TESTS
     // =========================================================================
@@ -1032,6 +2036,31 @@ mod tests {
         assert_eq!(pos.hash(), pos.compute_hash(), "Hash mismatch after 3. Bc4");
     }
 
+    #[test]
+    fn test_psq_incremental_matches_full() {
+        // Every legal move from positions with captures, promotions, castling and en passant
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ];
+        for fen in fens {
+            let mut pos = Position::from_fen(fen).unwrap();
+            assert_eq!([pos.psq(Color::White), pos.psq(Color::Black)], pos.compute_psq());
+            for m in crate::movegen::generate_legal_moves(&pos).iter() {
+                let undo = pos.make_move(*m).unwrap();
+                assert_eq!([pos.psq(Color::White), pos.psq(Color::Black)], pos.compute_psq(),
+                    "PSQ mismatch after {} in {}", m.to_uci(), fen);
+                let material: Score = pos.pieces_iter()
+                    .filter(|&(color, _, _)| color == Color::White)
+                    .map(|(_, piece, _)| MATERIAL[piece as usize])
+                    .sum();
+                assert_eq!(pos.material(Color::White), material, "Material mismatch after {}", m.to_uci());
+                pos.unmake_move(*m, &undo);
+            }
+        }
+    }
+
     #[test]
     fn test_hash_same_position_different_move_order() {
         // Reach the same position via different move orders → same hash
@@ -1101,6 +2130,7 @@ mod tests {
         let mut pos = Position::from_fen("4k3/8/8/8/8/5p2/8/4K1N1 w - - 5 1").unwrap();
         pos.make_move(Move::new(Square::G1, Square::from_file_rank(5, 2))).unwrap(); // Nxf3
         assert_eq!(pos.hash(), pos.compute_hash(), "Hash mismatch after capture");
+        assert_eq!(pos.pawn_hash(), pos.compute_pawn_hash(), "Pawn hash mismatch after capture");
     }
 
     #[test]
@@ -1117,6 +2147,7 @@ mod tests {
         let mut pos = Position::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
         pos.make_move(Move::new_en_passant(Square::from_file_rank(3, 4), Square::from_file_rank(4, 5))).unwrap(); // d5xe6 ep
         assert_eq!(pos.hash(), pos.compute_hash(), "Hash mismatch after en passant");
+        assert_eq!(pos.pawn_hash(), pos.compute_pawn_hash(), "Pawn hash mismatch after en passant");
     }
 
     #[test]
@@ -1125,6 +2156,29 @@ mod tests {
         let mut pos = Position::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
         pos.make_move(Move::new_promotion(Square::A7, Square::A8, PieceType::Queen)).unwrap();
         assert_eq!(pos.hash(), pos.compute_hash(), "Hash mismatch after promotion");
+        assert_eq!(pos.pawn_hash(), pos.compute_pawn_hash(), "Pawn hash mismatch after promotion");
+        assert_eq!(pos.pawn_hash(), 0, "No pawns left after promotion");
+    }
+
+    #[test]
+    fn test_pawn_hash_ignores_pieces() {
+        // Pawn hash changes only when pawns move, are captured or promote
+        let mut pos = Position::starting_position();
+        let start_pawns = pos.pawn_hash();
+        assert_ne!(start_pawns, 0);
+
+        pos.make_move(Move::new(Square::G1, Square::from_file_rank(5, 2))).unwrap(); // Nf3
+        assert_eq!(pos.pawn_hash(), start_pawns, "Knight move must not change pawn hash");
+
+        pos.make_move(Move::new(Square::from_file_rank(4, 6), Square::from_file_rank(4, 4))).unwrap(); // e5
+        assert_ne!(pos.pawn_hash(), start_pawns);
+        assert_eq!(pos.pawn_hash(), pos.compute_pawn_hash());
+
+        // Same pawns, different pieces → same pawn hash
+        let a = Position::from_fen("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1").unwrap();
+        let b = Position::from_fen("r3k3/pp6/8/8/8/8/PP6/R3K3 b - - 0 1").unwrap();
+        assert_eq!(a.pawn_hash(), b.pawn_hash());
+        assert_ne!(a.hash(), b.hash());
     }
 
     // =========================================================================
@@ -1137,7 +2191,7 @@ mod tests {
         let original_fen = pos.to_fen();
         let original_hash = pos.hash();
 
-        let undo = pos.make_move(m).expect(&format!(
+        let undo = pos.make_move(m).unwrap_or_else(|| panic!(
             "Move {} should be legal in position {}", m.to_uci(), fen
         ));
         // Position should have changed
@@ -1218,6 +2272,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_make_move_rejects_bad_promotions() {
+        let mut pos = Position::from_fen("4k3/P7/8/8/8/8/4P3/R3K3 w - - 0 1").unwrap();
+        let fen = pos.to_fen();
+        // Not on the last rank, not by a pawn
+        assert!(pos.make_move(Move::new_promotion(Square::E2, Square::from_algebraic("e3").unwrap(), PieceType::Queen)).is_none());
+        assert!(pos.make_move(Move::new_promotion(Square::A1, Square::from_algebraic("a2").unwrap(), PieceType::Queen)).is_none());
+        assert_eq!(pos.to_fen(), fen);
+        assert!(pos.make_move(Move::new_promotion(Square::A7, Square::A8, PieceType::Knight)).is_some());
+    }
+
+    #[test]
+    fn test_lost_pieces() {
+        // Queen odds: a piece missing from the FEN was never captured
+        let pos = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1").unwrap();
+        assert_eq!(pos.lost_pieces(Color::White, PieceType::Queen), 0);
+
+        // A capture-promotion loses black a knight; white's pawn wasn't captured
+        let mut pos = Position::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let m = Move::new_promotion(Square::A7, Square::from_file_rank(1, 7), PieceType::Queen);
+        let undo = pos.make_move(m).unwrap();
+        assert_eq!(pos.lost_pieces(Color::Black, PieceType::Knight), 1);
+        assert_eq!(pos.lost_pieces(Color::White, PieceType::Pawn), 0);
+        assert_eq!(pos.lost_pieces(Color::White, PieceType::Queen), 0);
+        assert_eq!(pos.mirrored().lost_pieces(Color::White, PieceType::Knight), 1);
+        pos.unmake_move(m, &undo);
+        assert_eq!(pos.lost_pieces(Color::Black, PieceType::Knight), 0);
+
+        // En passant
+        let mut pos = Position::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
+        pos.make_move(Move::new_en_passant(Square::from_file_rank(3, 4), Square::from_file_rank(4, 5))).unwrap();
+        assert_eq!(pos.lost_pieces(Color::Black, PieceType::Pawn), 1);
+    }
+
     #[test]
     fn test_unmake_black_castling() {
         assert_make_unmake_roundtrip(
@@ -1275,6 +2363,10 @@ mod tests {
                         "FEN mismatch after make/unmake {} in {}", m.to_uci(), fen);
                     assert_eq!(pos.hash(), original_hash,
                         "Hash mismatch after make/unmake {} in {}", m.to_uci(), fen);
+                    assert_eq!(pos.pawn_hash(), pos.compute_pawn_hash(),
+                        "Pawn hash mismatch after make/unmake {} in {}", m.to_uci(), fen);
+                    assert_eq!([pos.psq(Color::White), pos.psq(Color::Black)], pos.compute_psq(),
+                        "PSQ mismatch after make/unmake {} in {}", m.to_uci(), fen);
                 }
                 // If make_move returned None, position was already restored internally
             }
@@ -1371,6 +2463,316 @@ mod tests {
         assert!(Position::from_fen("4k3/8/8/8/8/8/8/4K2X w - - 0 1").is_err());
     }
 
+    #[test]
+    fn test_fen_strict_accepts_legal() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "4k3/8/8/8/8/8/8/4K3 w - -",
+        ];
+        for fen in fens {
+            assert!(Position::from_fen_strict(fen).is_ok(), "{} rejected", fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_strict_syntax_errors() {
+        let cases = [
+            ("4k3/8/8/8/8/8/8/4K3 w", FenError::WrongFieldCount(2)),
+            ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::WrongRankCount(7)),
+            ("4k3/8/8/8/8/8/9/4K3 w - - 0 1", FenError::InvalidPiece('9')),
+            ("4k3/8/8/8/8/8/8/4K4 w - - 0 1", FenError::BadRankLength(1)),
+            ("4k3/7/8/8/8/8/8/4K3 w - - 0 1", FenError::BadRankLength(7)),
+            ("4k3/8/8/8/8/8/8/4K2X w - - 0 1", FenError::InvalidPiece('X')),
+            ("4k3/8/8/8/8/8/8/4K3 W - - 0 1", FenError::InvalidSideToMove),
+            ("4k3/8/8/8/8/8/8/4K3 w KK - 0 1", FenError::InvalidCastling),
+            ("4k3/8/8/8/8/8/8/4K3 w x - 0 1", FenError::InvalidCastling),
+            ("4k3/8/8/8/8/8/8/4K3 w - e4 0 1", FenError::InvalidEnPassant),
+            ("4k3/8/8/8/8/8/8/4K3 w - - x 1", FenError::InvalidHalfmoveClock),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 0", FenError::InvalidFullmoveNumber),
+        ];
+        for (fen, err) in cases {
+            assert_eq!(Position::from_fen_strict(fen).err(), Some(err), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_strict_illegal_positions() {
+        let cases = [
+            ("8/8/8/8/8/8/8/4K3 w - - 0 1", FenError::KingCount(Color::Black, 0)),
+            ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", FenError::KingCount(Color::White, 2)),
+            ("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1", FenError::TooManyPieces(Color::White)),
+            ("4k3/8/8/8/8/8/8/P3K3 w - - 0 1", FenError::PawnOnBackRank(Square::new(0))),
+            ("4k2p/8/8/8/8/8/8/4K3 w - - 0 1", FenError::PawnOnBackRank(Square::new(63))),
+            ("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1", FenError::OpponentInCheck),
+            ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", FenError::CastlingWithoutPieces('K')),
+            ("4k2r/8/8/8/8/8/8/4K3 w q - 0 1", FenError::CastlingWithoutPieces('q')),
+            ("4k3/8/8/8/8/8/8/4K3 w - e6 0 1", FenError::ImpossibleEnPassant(Square::new(44))),
+            ("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1", FenError::ImpossibleEnPassant(Square::new(20))),
+        ];
+        for (fen, err) in cases {
+            assert_eq!(Position::from_fen_strict(fen).err(), Some(err), "{}", fen);
+            // The lenient parser takes all of them
+            assert!(Position::from_fen(fen).is_ok(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_drops_impossible_fields() {
+        // Castling rights without their rook, en passant without the pawn
+        let pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2N w KQkq e6 0 1").unwrap();
+        assert_eq!(pos.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2N w Qkq - 0 1");
+        assert_eq!(pos.hash(), pos.compute_hash());
+        assert!(!crate::movegen::generate_legal_moves(&pos).iter().any(|m| m.to_uci() == "e1g1"));
+        let pos = Position::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap();
+        assert_eq!(pos.en_passant_square(), Square::from_algebraic("d6"));
+
+        // Overlong ranks are refused rather than wrapped around
+        assert!(Position::from_fen("4k3/8/88888888888888888888888888888888/8/8/8/8/4K3 w - - 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/44p/8/8/8/8/4K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_builder_matches_fen() {
+        let mut b = PositionBuilder::new();
+        assert!(b.put_piece(4, 0, 'K'));
+        assert!(b.put_piece(7, 0, 'R'));
+        assert!(b.put_piece(4, 3, 'P'));
+        assert!(b.put_piece(4, 7, 'k'));
+        assert!(b.put_piece(3, 6, 'q'));
+        assert!(b.set_castling("K"));
+        assert!(b.set_ep("e3"));
+        b.set_turn(false);
+        b.set_fullmove_number(12);
+
+        let pos = b.build().unwrap();
+        let fen = "4k3/3q4/8/8/4P3/8/8/4K2R b K e3 0 12";
+        assert_eq!(pos.to_fen(), fen);
+        assert_eq!(pos.hash(), Position::from_fen(fen).unwrap().hash());
+        assert_eq!(pos.compute_pawn_hash(), Position::from_fen(fen).unwrap().compute_pawn_hash());
+    }
+
+    #[test]
+    fn test_builder_edit_and_errors() {
+        let mut b = PositionBuilder::from_position(&Position::starting_position());
+        // Replacing a piece keeps one piece per square
+        assert!(b.put_piece(3, 0, 'N'));
+        assert!(b.remove_piece(4, 1));
+        assert!(!b.remove_piece(4, 3));
+        assert_eq!(b.build().unwrap().to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPP1PPP/RNBNKBNR w KQkq - 0 1");
+
+        assert!(!b.put_piece(8, 0, 'Q'));
+        assert!(!b.put_piece(0, 0, 'x'));
+        assert!(!b.set_castling("KX"));
+        assert!(!b.set_ep("e9"));
+
+        assert!(b.remove_piece(7, 0));
+        assert_eq!(b.build().err(), Some(FenError::CastlingWithoutPieces('K')));
+        b.clear();
+        assert_eq!(b.build().err(), Some(FenError::KingCount(Color::White, 0)));
+    }
+
+    #[test]
+    fn test_is_legal_matches_movegen() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            // En passant capture that would expose the king along the rank
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+            // Pinned pieces and a king in check
+            "4k3/4r3/8/8/8/4B3/3N4/4K2r w - - 0 1",
+        ];
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+            let legal = crate::movegen::generate_legal_moves(&pos);
+            for m in crate::movegen::generate_pseudo_legal_moves(&pos).iter() {
+                assert_eq!(pos.is_legal(*m), legal.iter().any(|l| l == m), "{} in {}", m.to_uci(), fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_legal_rejects_foreign_moves() {
+        let pos = Position::starting_position();
+        // Wrong side, empty square, blocked piece, malformed promotion
+        assert!(!pos.is_legal(Move::new(Square::new(52), Square::new(36))));
+        assert!(!pos.is_legal(Move::new(Square::new(20), Square::new(28))));
+        assert!(!pos.is_legal(Move::new(Square::new(0), Square::new(16))));
+        assert!(!pos.is_legal(Move::new_promotion(Square::new(12), Square::new(28), PieceType::Queen)));
+        assert!(pos.is_legal(Move::new(Square::new(12), Square::new(28))));
+        assert!(pos.is_legal(Move::new(Square::new(6), Square::new(21))));
+    }
+
+    #[test]
+    fn test_checkers() {
+        assert!(Position::starting_position().checkers().is_empty());
+        // Rook and knight both give check
+        let pos = Position::from_fen("4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        let checkers: Vec<Square> = pos.checkers().collect();
+        assert_eq!(checkers, [Square::new(4), Square::new(43)]);
+        // Pawn check, and a blocked rook doesn't count
+        let pos = Position::from_fen("4k3/3P4/8/8/8/8/4N3/4RK2 b - - 0 1").unwrap();
+        assert_eq!(pos.checkers().collect::<Vec<_>>(), [Square::new(51)]);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        // e4 hit by knight f2, pawn d3 and bishop b7 (white side),
+        // pawn f5, knight c5 and rook e8 (black); the queen e1 is behind the pawn e2
+        let pos = Position::from_fen("k3r3/1B6/8/2n2p2/4p3/3P4/4PN2/K3Q3 w - - 0 1").unwrap();
+        let e4 = Square::new(28);
+        let squares = |bb: Bitboard| bb.map(|sq| sq.to_algebraic()).collect::<Vec<_>>();
+
+        assert_eq!(squares(pos.attackers_by(e4, Color::White)), ["f2", "d3", "b7"]);
+        assert_eq!(squares(pos.attackers_by(e4, Color::Black)), ["c5", "f5", "e8"]);
+        assert_eq!(pos.attackers_to(e4), pos.attackers_by(e4, Color::White) | pos.attackers_by(e4, Color::Black));
+
+        // Remove the e2 pawn: the queen behind it joins in
+        let occupied = pos.occupied() & !Bitboard::from_square(Square::new(12));
+        assert_eq!(squares(pos.attackers_to_with(e4, occupied) & pos.occupied_by(Color::White)), ["e1", "f2", "d3", "b7"]);
+    }
+
+    #[test]
+    fn test_pinned() {
+        assert!(Position::starting_position().pinned(Color::White).is_empty());
+        // Black knight pinned by the bishop
+        let pos = Position::from_fen("4k3/3n4/8/1B6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(pos.pinned(Color::Black).collect::<Vec<_>>(), [Square::new(51)]);
+        // Own pieces of the pinning side don't count as pinned
+        let pos = Position::from_fen("4k3/3N4/8/1B6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(pos.pinned(Color::Black).is_empty());
+        assert!(pos.pinned(Color::White).is_empty());
+        // Two pieces on the line: neither is pinned
+        let pos = Position::from_fen("4k3/8/8/4r3/8/4P3/4R3/4K3 w - - 0 1").unwrap();
+        assert!(pos.pinned(Color::White).is_empty());
+    }
+
+    /// Piece on a square according to the bitboards alone
+    fn piece_from_bitboards(pos: &Position, sq: Square) -> Option<(Color, PieceType)> {
+        pos.pieces_iter().find(|&(_, _, s)| s == sq).map(|(c, p, _)| (c, p))
+    }
+
+    #[test]
+    fn test_mailbox_follows_make_unmake() {
+        let mut pos = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let check = |pos: &Position| {
+            for i in 0..64 {
+                let sq = Square::new(i);
+                assert_eq!(pos.piece_on(sq), piece_from_bitboards(pos, sq), "{} in {}", sq.to_algebraic(), pos.to_fen());
+            }
+        };
+        check(&pos);
+        // Every move (castling, en passant, promotions, captures) and back
+        for m in crate::movegen::generate_legal_moves(&pos).iter() {
+            let undo = pos.make_move(*m).unwrap();
+            check(&pos);
+            for reply in crate::movegen::generate_legal_moves(&pos).iter() {
+                let reply_undo = pos.make_move(*reply).unwrap();
+                check(&pos);
+                pos.unmake_move(*reply, &reply_undo);
+            }
+            pos.unmake_move(*m, &undo);
+        }
+        check(&pos);
+    }
+
+    #[test]
+    fn test_validate() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut pos = Position::from_fen(kiwipete).unwrap();
+        assert_eq!(pos.validate(), Ok(()));
+        let e4 = Move::new(Square::from_algebraic("e2").unwrap(), Square::from_algebraic("e4").unwrap());
+        let mut after_e4 = Position::starting_position();
+        after_e4.make_move(e4).unwrap();
+        assert_eq!(after_e4.validate(), Ok(()));
+
+        // Each kind of corruption, made behind the incremental updates' back
+        let e4_sq = Square::from_algebraic("e4").unwrap();
+        let mut broken = pos.clone();
+        broken.hash ^= 1;
+        assert_eq!(broken.validate(), Err("Hash doesn't match compute_hash()"));
+        broken = pos.clone();
+        broken.pieces[0][PieceType::Knight as usize].set(Square::from_algebraic("e2").unwrap());
+        assert_eq!(broken.validate(), Err("Two piece bitboards share a square"));
+        broken = pos.clone();
+        broken.board[e4_sq.index()] = Some((Color::White, PieceType::Queen));
+        assert_eq!(broken.validate(), Err("Square array doesn't match the bitboards"));
+        broken = pos.clone();
+        broken.remove_piece(Color::Black, PieceType::King, Square::from_algebraic("e8").unwrap());
+        broken.hash = broken.compute_hash();
+        assert_eq!(broken.check_consistency(), Ok(()));
+        assert_eq!(broken.validate(), Err("Each side needs a king"));
+        broken = pos.clone();
+        broken.material[0] += 1;
+        assert_eq!(broken.validate(), Err("Material sums don't match the pieces"));
+        broken = after_e4.clone();
+        broken.en_passant = Square::from_algebraic("d3");
+        broken.hash = broken.compute_hash();
+        assert_eq!(broken.validate(), Err("En passant square without a pawn that just passed it"));
+
+        // Extra kings are playable, so valid
+        pos = Position::from_fen("K3k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert_eq!(pos.validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Hash doesn't match compute_hash() after e2e4")]
+    fn test_make_move_validates_in_debug() {
+        let mut pos = Position::starting_position();
+        pos.hash ^= 1;
+        pos.make_move(Move::new(Square::from_algebraic("e2").unwrap(), Square::from_algebraic("e4").unwrap()));
+    }
+
+    #[test]
+    fn test_gives_check_matches_make_move() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            // Castling with check from the rook, en passant discovering a check
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            "8/8/8/1k1pP2Q/8/8/8/4K3 w - d6 0 1",
+            // Promotions to a checking knight
+            "8/1P1k4/8/8/8/8/8/4K3 w - - 0 1",
+        ];
+        for fen in fens {
+            let mut pos = Position::from_fen(fen).unwrap();
+            for m in crate::movegen::generate_legal_moves(&pos).iter() {
+                let predicted = pos.gives_check(*m);
+                let undo = pos.make_move(*m).unwrap();
+                let actual = pos.is_in_check(pos.side_to_move());
+                pos.unmake_move(*m, &undo);
+                assert_eq!(predicted, actual, "{} in {}", m.to_uci(), fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gives_check_kinds() {
+        let pos = Position::from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert!(pos.gives_check(Move::new_castling(Square::E1, Square::G1)));
+        let pos = Position::from_fen("8/8/8/1k1pP2Q/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(pos.gives_check(Move::new_en_passant(Square::new(36), Square::new(43))));
+        let pos = Position::from_fen("8/1P1k4/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(pos.gives_check(Move::new_promotion(Square::new(49), Square::new(57), PieceType::Knight)));
+        assert!(!pos.gives_check(Move::new_promotion(Square::new(49), Square::new(57), PieceType::Rook)));
+    }
+
+    #[test]
+    fn test_fen_error_messages() {
+        let err = Position::from_fen_strict("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").err().unwrap();
+        assert_eq!(err.to_string(), "Pawn on a1 (first or last rank)");
+        let err = Position::from_fen_strict("8/8/8/8/8/8/8/4K3 w - - 0 1").err().unwrap();
+        assert_eq!(err.to_string(), "Black has 0 kings, needs exactly 1");
+    }
+
     // =========================================================================
     // 50-MOVE RULE CLOCK TESTS (Task 1.7)
     // =========================================================================
@@ -1439,7 +2841,7 @@ mod tests {
     #[test]
     fn test_fullmove_increments_after_black() {
         let mut pos = Position::starting_position();
-        assert_eq!(pos.to_fen().split(' ').last().unwrap(), "1");
+        assert_eq!(pos.to_fen().split(' ').next_back().unwrap(), "1");
 
         // 1. e4 — still fullmove 1
         pos.make_move(Move::new(Square::E2, Square::from_file_rank(4, 3))).unwrap();
@@ -1513,6 +2915,16 @@ mod tests {
 
     // --- Insufficient Material ---
 
+    #[test]
+    fn test_has_mating_material() {
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+        assert!(!pos.has_mating_material(Color::White));
+        assert!(!pos.has_mating_material(Color::Black));
+        let pos = Position::from_fen("4k3/7p/8/8/8/8/8/3BKN2 w - - 0 1").unwrap();
+        assert!(pos.has_mating_material(Color::White));
+        assert!(pos.has_mating_material(Color::Black));
+    }
+
     #[test]
     fn test_insufficient_material_k_vs_k() {
         let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
@@ -1591,6 +3003,67 @@ mod tests {
         assert!(!pos.is_insufficient_material());
     }
 
+    // --- Dead positions ---
+
+    #[test]
+    fn test_dead_position_same_color_bishops() {
+        // Three bishops, all on dark squares
+        let pos = Position::from_fen("4k3/8/8/8/8/2b5/8/B1B1K3 w - - 0 1").unwrap();
+        assert!(!pos.is_insufficient_material());
+        assert!(pos.is_dead_position());
+        assert!(pos.is_draw());
+
+        let pos = Position::from_fen("4k3/8/8/8/8/3b4/8/B1B1K3 w - - 0 1").unwrap();
+        assert!(!pos.is_dead_position());
+    }
+
+    #[test]
+    fn test_dead_position_pawn_wall() {
+        let pos = Position::from_fen("8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/8 w - - 0 1").unwrap();
+        assert!(pos.is_dead_position());
+        assert!(pos.is_draw());
+        assert_eq!(pos.game_status(), "draw");
+
+        // A bishop that can't reach any pawn or check the black king
+        let pos = Position::from_fen("8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/2B5 w - - 0 1").unwrap();
+        assert!(pos.is_dead_position());
+    }
+
+    #[test]
+    fn test_three_check_draws_need_bare_kings() {
+        // Bb5+ wins with two checks already given
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/3BK3 w - - 0 1 +2+0").unwrap();
+        assert!(!pos.is_insufficient_material());
+        assert!(!pos.is_dead_position());
+        assert!(!pos.is_draw());
+        assert_eq!(pos.game_status(), "playing");
+        assert!(pos.has_mating_material(Color::White));
+        assert!(!pos.has_mating_material(Color::Black));
+        // Same-color bishops and a locked pawn wall still give checks
+        for fen in [
+            "4k3/8/8/8/8/2b5/8/B1B1K3 w - - 0 1 +0+0",
+            "8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/2B5 w - - 0 1 +0+0",
+        ] {
+            assert!(!Position::from_fen(fen).unwrap().is_dead_position(), "{}", fen);
+        }
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+2").unwrap();
+        assert!(pos.is_insufficient_material());
+        assert_eq!(pos.game_status(), "draw");
+    }
+
+    #[test]
+    fn test_not_dead_position() {
+        // Bishop can be given up to a pawn, opening the wall
+        assert!(!Position::from_fen("8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/3B4 w - - 0 1").unwrap().is_dead_position());
+        // The white king walks round the h-file to f5
+        assert!(!Position::from_fen("8/8/4k3/1p1p1p2/1P1P1P2/4K3/8/8 w - - 0 1").unwrap().is_dead_position());
+        // d4xe5 is possible
+        assert!(!Position::from_fen("4k3/8/8/3pp3/3PP3/8/8/4K3 w - - 0 1").unwrap().is_dead_position());
+        // A pawn that can still advance
+        assert!(!Position::from_fen("4k3/8/8/3p4/3P4/8/6P1/4K3 w - - 0 1").unwrap().is_dead_position());
+        assert!(!Position::starting_position().is_dead_position());
+    }
+
     // --- 50-move rule ---
 
     #[test]
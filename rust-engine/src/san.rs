@@ -0,0 +1,336 @@
+// Standard Algebraic Notation
+// Reading and writing moves the way humans and PGN files do ("Nxf3+",
+// "exd8=Q", "O-O"). Both directions work from the legal move list: parsed
+// moves are always legal, and written moves carry just enough disambiguation.
+
+use crate::movegen::generate_legal_moves;
+use crate::position::Position;
+use crate::types::{Move, PieceType, Square};
+
+/// Uppercase SAN letter of a piece (pawns have none)
+fn piece_letter(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "",
+        PieceType::Knight => "N",
+        PieceType::Bishop => "B",
+        PieceType::Rook => "R",
+        PieceType::Queen => "Q",
+        PieceType::King => "K",
+    }
+}
+
+/// Piece type for an uppercase SAN piece letter
+fn piece_from_letter(ch: char) -> Option<PieceType> {
+    match ch {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+/// Parse a SAN move for `pos`.
+///
+/// Accepts disambiguation by file, rank or square ("Nbd2", "R1e2", "Qh4e1"),
+/// captures with or without `x`, promotions as `e8=Q` or `e8Q`, castling with
+/// letter O or digit 0, and ignores check/mate marks and annotations (`+ # ! ?`).
+pub fn parse_san(pos: &Position, san: &str) -> Result<Move, &'static str> {
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+    if text.is_empty() {
+        return Err("Empty move");
+    }
+
+    let legal = generate_legal_moves(pos);
+
+    // Castling
+    let castle_to_file = match text {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None,
+    };
+    if let Some(file) = castle_to_file {
+        return legal
+            .iter()
+            .find(|m| m.is_castling() && m.to().file() == file)
+            .copied()
+            .ok_or("Castling is not legal");
+    }
+
+    let mut chars: Vec<char> = text.chars().filter(|&c| c != 'x' && c != ':' && c != '-').collect();
+
+    // Moving piece
+    let piece = match chars.first().copied().and_then(piece_from_letter) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        }
+        None => PieceType::Pawn,
+    };
+
+    // Promotion suffix: "=Q" or a bare trailing piece letter
+    let promotion = match chars.last().copied().and_then(piece_from_letter) {
+        Some(promo) if piece == PieceType::Pawn => {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            Some(promo)
+        }
+        _ => None,
+    };
+    if promotion == Some(PieceType::King) && !pos.promotion_rules().allows(PieceType::King) {
+        return Err("Cannot promote to a king");
+    }
+
+    // Destination is the last two characters, disambiguation whatever is left
+    if chars.len() < 2 {
+        return Err("Missing destination square");
+    }
+    let dest: String = chars[chars.len() - 2..].iter().collect();
+    let to = Square::from_algebraic(&dest).ok_or("Invalid destination square")?;
+    let mut from_file = None;
+    let mut from_rank = None;
+    for &c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+            '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+            _ => return Err("Invalid move text"),
+        }
+    }
+
+    let mut found = None;
+    for &m in legal.iter() {
+        let moved = pos.piece_on(m.from()).map(|(_, p)| p);
+        if moved != Some(piece)
+            || m.to() != to
+            || m.is_castling()
+            || from_file.is_some_and(|f| m.from().file() != f)
+            || from_rank.is_some_and(|r| m.from().rank() != r)
+            || m.promotion_piece() != promotion
+        {
+            continue;
+        }
+        if found.is_some() {
+            return Err("Ambiguous move");
+        }
+        found = Some(m);
+    }
+
+    found.ok_or(if piece == PieceType::Pawn && promotion.is_none() && (to.rank() == 0 || to.rank() == 7) {
+        "Missing promotion piece"
+    } else {
+        "Illegal move"
+    })
+}
+
+impl Move {
+    /// This move in SAN for `pos` (the position before the move), with `+` or
+    /// `#` when it gives check or mate. The move must be legal in `pos`.
+    pub fn to_san(self, pos: &Position) -> String {
+        let legal = generate_legal_moves(pos);
+        let mut san = String::new();
+
+        if self.is_castling() {
+            san.push_str(if self.to().file() == 6 { "O-O" } else { "O-O-O" });
+        } else {
+            let piece = pos.piece_on(self.from()).map_or(PieceType::Pawn, |(_, p)| p);
+            let is_capture = self.is_en_passant() || pos.piece_on(self.to()).is_some();
+            san.push_str(piece_letter(piece));
+
+            if piece == PieceType::Pawn {
+                if is_capture {
+                    san.push((b'a' + self.from().file()) as char);
+                }
+            } else {
+                // Other pieces of the same type that can reach the same square
+                let rivals: Vec<Square> = legal
+                    .iter()
+                    .filter(|m| m.to() == self.to() && m.from() != self.from())
+                    .filter(|m| pos.piece_on(m.from()).map(|(_, p)| p) == Some(piece))
+                    .map(|m| m.from())
+                    .collect();
+                if !rivals.is_empty() {
+                    let file = (b'a' + self.from().file()) as char;
+                    let rank = (b'1' + self.from().rank()) as char;
+                    if rivals.iter().all(|sq| sq.file() != self.from().file()) {
+                        san.push(file);
+                    } else if rivals.iter().all(|sq| sq.rank() != self.from().rank()) {
+                        san.push(rank);
+                    } else {
+                        san.push(file);
+                        san.push(rank);
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&self.to().to_algebraic());
+            if let Some(promo) = self.promotion_piece() {
+                san.push('=');
+                san.push_str(piece_letter(promo));
+            }
+        }
+
+        let mut pos = pos.clone();
+        if pos.make_move(self).is_some() && pos.is_in_check(pos.side_to_move()) {
+            let mated = generate_legal_moves(&pos).is_empty();
+            san.push(if mated { '#' } else { '+' });
+        }
+        san
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(fen: &str, san: &str) -> Result<String, &'static str> {
+        let pos = Position::from_fen(fen).unwrap();
+        parse_san(&pos, san).map(|m| m.to_uci())
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_pawn_and_piece_moves() {
+        assert_eq!(parse(START, "e4"), Ok("e2e4".into()));
+        assert_eq!(parse(START, "e3"), Ok("e2e3".into()));
+        assert_eq!(parse(START, "Nf3"), Ok("g1f3".into()));
+        assert_eq!(parse(START, "Nc3!?"), Ok("b1c3".into()));
+        assert_eq!(parse(START, "Ng1-f3"), Ok("g1f3".into()));
+        assert_eq!(parse(START, "e5"), Err("Illegal move"));
+        assert_eq!(parse(START, "Nd4"), Err("Illegal move"));
+        assert_eq!(parse(START, ""), Err("Empty move"));
+        assert_eq!(parse(START, "Nz9"), Err("Invalid destination square"));
+    }
+
+    #[test]
+    fn test_captures_and_checks() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+        assert_eq!(parse(fen, "exd5"), Ok("e4d5".into()));
+        assert_eq!(parse(fen, "Nxe5"), Ok("f3e5".into()));
+        assert_eq!(parse(fen, "Bb5+"), Ok("f1b5".into()));
+        // Scholar's mate
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        assert_eq!(parse(fen, "Qxf7#"), Ok("h5f7".into()));
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        // Knights on b1 and f3 can both reach d2
+        let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        assert_eq!(parse(fen, "Nd2"), Err("Ambiguous move"));
+        assert_eq!(parse(fen, "Nbd2"), Ok("b1d2".into()));
+        assert_eq!(parse(fen, "Nfd2"), Ok("f3d2".into()));
+        // Rooks on a1 and a5 share a file: disambiguate by rank
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(parse(fen, "R1a3"), Ok("a1a3".into()));
+        assert_eq!(parse(fen, "R5a3"), Ok("a5a3".into()));
+        // Three queens: file and rank both needed
+        let fen = "4k3/8/8/8/Q6Q/8/8/K6Q w - - 0 1";
+        assert_eq!(parse(fen, "Qh4e1"), Ok("h4e1".into()));
+        assert_eq!(parse(fen, "Qhe1"), Err("Ambiguous move"));
+    }
+
+    #[test]
+    fn test_promotions() {
+        let fen = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(parse(fen, "e8=Q+"), Ok("e7e8q".into()));
+        assert_eq!(parse(fen, "e8N"), Ok("e7e8n".into()));
+        assert_eq!(parse(fen, "exd8=R"), Ok("e7d8r".into()));
+        assert_eq!(parse(fen, "e8"), Err("Missing promotion piece"));
+        assert_eq!(parse(fen, "e8=K"), Err("Cannot promote to a king"));
+    }
+
+    #[test]
+    fn test_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(parse(fen, "O-O"), Ok("e1g1".into()));
+        assert_eq!(parse(fen, "0-0-0"), Ok("e1c1".into()));
+        let black = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(parse(black, "O-O-O"), Ok("e8c8".into()));
+        let no_rights = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1";
+        assert_eq!(parse(no_rights, "O-O"), Err("Castling is not legal"));
+    }
+
+    #[test]
+    fn test_en_passant() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2";
+        let pos = Position::from_fen(fen).unwrap();
+        let m = parse_san(&pos, "exd6 e.p.").unwrap();
+        assert!(m.is_en_passant());
+        assert_eq!(m.to_uci(), "e5d6");
+    }
+
+    fn san(fen: &str, uci: &str) -> String {
+        let pos = Position::from_fen(fen).unwrap();
+        let m = *generate_legal_moves(&pos).iter().find(|m| m.to_uci() == uci).unwrap();
+        m.to_san(&pos)
+    }
+
+    #[test]
+    fn test_to_san_basic() {
+        assert_eq!(san(START, "e2e4"), "e4");
+        assert_eq!(san(START, "g1f3"), "Nf3");
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+        assert_eq!(san(fen, "e4d5"), "exd5");
+        assert_eq!(san(fen, "f3e5"), "Nxe5");
+        assert_eq!(san(fen, "f1b5"), "Bb5+");
+        let mate = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        assert_eq!(san(mate, "h5f7"), "Qxf7#");
+    }
+
+    #[test]
+    fn test_to_san_disambiguation() {
+        let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        assert_eq!(san(fen, "b1d2"), "Nbd2");
+        assert_eq!(san(fen, "f3d4"), "Nd4");
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(fen, "a1a3"), "R1a3");
+        let fen = "4k3/8/8/8/Q6Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(fen, "h4e1"), "Q4e1+");
+        assert_eq!(san(fen, "a4e4"), "Qae4+");
+        // Rivals on the same file and the same rank: full square
+        let fen = "2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(fen, "h4e1"), "Qh4e1");
+        // A pinned knight is no rival: plain "Ne5"
+        let fen = "4k3/8/8/8/1b6/2N3N1/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "g3e4"), "Ne4");
+    }
+
+    #[test]
+    fn test_to_san_special_moves() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1g1"), "O-O");
+        assert_eq!(san(fen, "e1c1"), "O-O-O");
+        let fen = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "e7e8q"), "e8=Q+");
+        assert_eq!(san(fen, "e7d8n"), "exd8=N");
+        assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn test_san_roundtrip() {
+        // Every legal move written and read back gives the same move
+        for fen in [
+            START,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            for &m in generate_legal_moves(&pos).iter() {
+                let text = m.to_san(&pos);
+                assert_eq!(parse_san(&pos, &text), Ok(m), "{} in {}", text, fen);
+            }
+        }
+    }
+}
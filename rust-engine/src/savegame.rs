@@ -0,0 +1,380 @@
+// Compact Game Encoding
+// A game in a few bytes per move, for localStorage or a database: variant,
+// promotion rules and starting FEN, then one byte per move (its index among
+// the legal moves), the current ply, the clock, annotations and an off-board
+// result. The line saved is the one `history` and `redo` walk; other
+// variations are dropped.
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{EngineError, ErrorKind};
+use crate::movegen::generate_legal_moves;
+use crate::position::Position;
+use crate::types::Move;
+use crate::variant::{PromotionRules, Variant};
+use crate::{initial_fen, Clock, GameState};
+
+/// Format version, the first byte
+const VERSION: u8 = 1;
+
+/// Move byte for a null move
+const NULL_MOVE: u8 = 0xFF;
+
+/// Off-board endings, by code (0 = none)
+const ENDINGS: [(&str, &str); 3] = [("0-1", "resignation"), ("1-0", "resignation"), ("1/2-1/2", "draw_agreement")];
+
+#[wasm_bindgen]
+impl GameState {
+    /// The game as compact bytes (a Uint8Array), restored by `deserialize`
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![VERSION, Variant::ALL.iter().position(|&v| v == self.position.variant()).unwrap_or(0) as u8];
+        write_str(&mut out, &self.position.promotion_rules().letters());
+
+        let mut pos = self.start_position();
+        let fen = pos.to_fen();
+        write_str(&mut out, if fen == initial_fen(pos.variant()) { "" } else { &fen });
+
+        let plies = self.current_ply() + self.tree.line_after(self.node).count();
+        write_varint(&mut out, plies as u64);
+        for ply in 1..=plies {
+            let m = self.line_node(ply).map_or(Move::NULL, |n| self.tree.mv(n));
+            if m == Move::NULL {
+                out.push(NULL_MOVE);
+                pos.make_null_move();
+            } else {
+                let moves = generate_legal_moves(&pos);
+                out.push(moves.iter().position(|&l| l == m).unwrap_or(0) as u8);
+                pos.make_move(m);
+            }
+        }
+        write_varint(&mut out, self.current_ply() as u64);
+
+        match &self.clock {
+            Some(clock) => {
+                out.push(1);
+                for ms in [clock.base_ms, clock.increment_ms, clock.remaining[0], clock.remaining[1]] {
+                    out.extend_from_slice(&ms.to_le_bytes());
+                }
+            }
+            None => out.push(0),
+        }
+
+        let notes: Vec<(usize, &str, &[u8])> = (0..=plies)
+            .filter_map(|ply| self.line_node(ply).map(|n| (ply, self.tree.comment(n), self.tree.nags(n))))
+            .filter(|(_, comment, nags)| !comment.is_empty() || !nags.is_empty())
+            .collect();
+        write_varint(&mut out, notes.len() as u64);
+        for (ply, comment, nags) in notes {
+            write_varint(&mut out, ply as u64);
+            write_str(&mut out, comment);
+            out.push(nags.len() as u8);
+            out.extend_from_slice(nags);
+        }
+
+        out.push(self.ending.and_then(|e| ENDINGS.iter().position(|&x| x == e)).map_or(0, |i| i as u8 + 1));
+        out
+    }
+
+    /// Restore a game written by `serialize`. On error the game is left unchanged.
+    pub fn deserialize(&mut self, bytes: &[u8]) -> Result<(), EngineError> {
+        let saved = decode(bytes).map_err(|e| EngineError::new(ErrorKind::BadSavedGame, e))?;
+
+        self.set_start(saved.start);
+        if self.position.variant() != saved.variant {
+            self.position.set_variant(saved.variant);
+            #[cfg(feature = "engine")]
+            self.tt.clear();
+        }
+        self.position.set_promotion_rules(saved.rules);
+        for m in saved.moves {
+            if m == Move::NULL {
+                self.make_null_move();
+            } else {
+                self.play(m);
+            }
+        }
+        self.goto_ply(saved.current_ply);
+        for (ply, comment, nags) in saved.notes {
+            if let Some(node) = self.line_node(ply) {
+                self.tree.set_comment(node, comment);
+                self.tree.set_nags(node, nags);
+            }
+        }
+        self.clock = saved.clock;
+        self.ending = saved.ending;
+        Ok(())
+    }
+
+    /// `serialize` as base64 text
+    pub fn serialize_base64(&self) -> String {
+        base64_encode(&self.serialize())
+    }
+
+    /// `deserialize` from base64 text
+    pub fn deserialize_base64(&mut self, text: &str) -> Result<(), EngineError> {
+        let bytes = base64_decode(text).ok_or(EngineError::new(ErrorKind::BadSavedGame, "Invalid base64"))?;
+        self.deserialize(&bytes)
+    }
+}
+
+/// A decoded game, checked move by move
+struct SavedGame {
+    variant: Variant,
+    rules: PromotionRules,
+    start: Position,
+    moves: Vec<Move>,
+    current_ply: usize,
+    clock: Option<Clock>,
+    notes: Vec<(usize, String, Vec<u8>)>,
+    ending: Option<(&'static str, &'static str)>,
+}
+
+fn decode(bytes: &[u8]) -> Result<SavedGame, &'static str> {
+    let mut r = Reader { bytes, at: 0 };
+    if r.u8()? != VERSION {
+        return Err("Unsupported saved game version");
+    }
+    let variant = *Variant::ALL.get(r.u8()? as usize).ok_or("Unknown variant")?;
+    let rules = PromotionRules::parse(&r.str()?).ok_or("Invalid promotion rules")?;
+    let fen = r.str()?;
+    let mut start = if fen.is_empty() { Position::starting_position() } else { Position::from_fen(&fen).map_err(|_| "Invalid FEN")? };
+    start.set_variant(variant);
+    start.set_promotion_rules(rules);
+
+    let plies = r.varint()? as usize;
+    let mut pos = start.clone();
+    let mut moves = Vec::with_capacity(plies.min(bytes.len()));
+    for _ in 0..plies {
+        let index = r.u8()?;
+        if index == NULL_MOVE {
+            if pos.is_in_check(pos.side_to_move()) {
+                return Err("Null move in check");
+            }
+            pos.make_null_move();
+            moves.push(Move::NULL);
+        } else {
+            let m = *generate_legal_moves(&pos).iter().nth(index as usize).ok_or("Invalid move")?;
+            pos.make_move(m);
+            moves.push(m);
+        }
+    }
+    let current_ply = r.varint()? as usize;
+    if current_ply > plies {
+        return Err("Current ply past the end of the game");
+    }
+
+    let clock = match r.u8()? {
+        0 => None,
+        1 => {
+            let [base_ms, increment_ms, white, black] = [r.f64()?, r.f64()?, r.f64()?, r.f64()?];
+            Some(Clock { base_ms, increment_ms, remaining: [white, black], last_timestamp: None })
+        }
+        _ => return Err("Invalid clock"),
+    };
+
+    let count = r.varint()? as usize;
+    let mut notes = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let ply = r.varint()? as usize;
+        if ply > plies {
+            return Err("Annotation past the end of the game");
+        }
+        let comment = r.str()?;
+        let len = r.u8()? as usize;
+        notes.push((ply, comment, r.take(len)?.to_vec()));
+    }
+
+    let ending = match r.u8()? {
+        0 => None,
+        code => Some(*ENDINGS.get(code as usize - 1).ok_or("Invalid result")?),
+    };
+    if r.at != bytes.len() {
+        return Err("Trailing bytes after saved game");
+    }
+    Ok(SavedGame { variant, rules, start, moves, current_ply, clock, notes, ending })
+}
+
+// =============================================================================
+// BYTES
+// =============================================================================
+
+/// LEB128: 7 bits per byte, low bits first
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], &'static str> {
+        let end = self.at.checked_add(n).filter(|&e| e <= self.bytes.len()).ok_or("Saved game is truncated")?;
+        let slice = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, &'static str> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.u8()?;
+            n |= ((b & 0x7F) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err("Invalid number")
+    }
+
+    fn f64(&mut self) -> Result<f64, &'static str> {
+        let bytes = self.take(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+    }
+
+    fn str(&mut self) -> Result<String, &'static str> {
+        let len = self.varint()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Invalid text")
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64, padded or not. None for any other character.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .trim()
+        .trim_end_matches('=')
+        .bytes()
+        .map(|c| BASE64.iter().position(|&d| d == c).map(|i| i as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &d)| n | (d as u32) << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for (bytes, text) in [(&b""[..], ""), (b"f", "Zg=="), (b"fo", "Zm8="), (b"foo", "Zm9v"), (b"foob", "Zm9vYg==")] {
+            assert_eq!(base64_encode(bytes), text);
+            assert_eq!(base64_decode(text).unwrap(), bytes);
+        }
+        assert_eq!(base64_decode("Zm9vYg").unwrap(), b"foob");
+        assert_eq!(base64_decode("Zm9v!"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut gs = GameState::new();
+        gs.set_variant("three-check");
+        gs.set_clock(180_000.0, 2_000.0);
+        gs.load_pgn("{Opening} 1. e4 e5 2. Nf3!? Nc6 3. Bb5 {Ruy Lopez} a6 *").unwrap();
+        gs.record_time_used(1_500.0);
+        gs.goto_ply(4);
+        gs.offer_draw();
+        gs.accept_draw();
+
+        let bytes = gs.serialize();
+        // Header, six one-byte moves, clock: far smaller than the PGN
+        assert!(bytes.len() < 80, "{} bytes", bytes.len());
+
+        let mut copy = GameState::new();
+        copy.deserialize(&bytes).unwrap();
+        assert_eq!(copy.fen(), gs.fen());
+        assert_eq!(copy.variant(), "three-check");
+        assert_eq!(copy.history_list(), gs.history_list());
+        assert_eq!(copy.total_plies(), 6);
+        assert_eq!(copy.annotations_list(), gs.annotations_list());
+        assert_eq!(copy.comment(0).as_deref(), Some("Opening"));
+        assert_eq!(copy.time_left("w"), gs.time_left("w"));
+        assert_eq!(copy.status(), "draw_agreement");
+        assert_eq!(copy.serialize(), bytes);
+
+        let mut copy = GameState::new();
+        copy.deserialize_base64(&gs.serialize_base64()).unwrap();
+        assert_eq!(copy.fen(), gs.fen());
+    }
+
+    #[test]
+    fn test_fen_start_and_null_moves() {
+        let mut gs = GameState::new();
+        gs.set_promotion_rules("n");
+        gs.load_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(gs.make_null_move());
+        assert!(gs.make_move_uci("e8d7"));
+        assert!(gs.make_move_uci("b7b8n"));
+
+        let mut copy = GameState::new();
+        copy.deserialize(&gs.serialize()).unwrap();
+        assert_eq!(copy.fen(), gs.fen());
+        assert_eq!(copy.history_list(), ["0000", "e8d7", "b7b8n"]);
+        assert_eq!(copy.promotion_rules(), "n");
+    }
+
+    #[test]
+    fn test_bad_input_keeps_game() {
+        let mut gs = GameState::new();
+        gs.make_move_uci("d2d4");
+        let fen = gs.fen();
+        let bytes = GameState::new().serialize();
+
+        assert!(gs.deserialize(&[]).is_err());
+        assert!(gs.deserialize(&[9]).is_err());
+        assert!(gs.deserialize(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(gs.deserialize(&trailing).is_err());
+        // One move with an out-of-range index
+        let mut bad = bytes[..8].to_vec();
+        bad.extend_from_slice(&[1, 200, 0, 0, 0, 0]);
+        assert_eq!(gs.deserialize(&bad), Err(EngineError::new(ErrorKind::BadSavedGame, "Invalid move")));
+        assert!(gs.deserialize_base64("not base64!").is_err());
+        assert_eq!(gs.fen(), fen);
+    }
+}
@@ -1,12 +1,17 @@
 // Search Module
 // Implements minimax with alpha-beta pruning to find the best move
-// With: transposition table, killer move heuristic, MVV-LVA ordering
+// With: transposition table, killer move heuristic, MVV-LVA ordering, eval cache
 
-use crate::eval::{evaluate, Score, MATE_SCORE, DRAW_SCORE};
-use crate::movegen::{generate_legal_moves, MoveList};
+use crate::eval::{default_params, EvalParams, Score, MATE_SCORE, DRAW_SCORE};
+use crate::evalcache::{self, EvalCache};
+use crate::movegen::{generate_captures, generate_legal_moves, generate_quiets, MoveList};
 use crate::position::Position;
 use crate::tt::{TranspositionTable, TTFlag, score_to_tt, score_from_tt};
-use crate::types::Move;
+use crate::timeman::{stability_scale, TimeLimits};
+use crate::types::{Color, Move};
+#[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+use crate::syzygy;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 // =============================================================================
 // TIME MEASUREMENT (works on both native and WASM)
@@ -38,15 +43,63 @@ const NUM_KILLERS: usize = 2;
 /// Search statistics
 #[derive(Default, Clone)]
 pub struct SearchStats {
+    /// Total nodes visited (main search + quiescence)
     pub nodes: u64,
+    /// Nodes visited in quiescence search
+    pub qnodes: u64,
     pub depth: u8,
+    /// Deepest ply reached, including quiescence
+    pub seldepth: u8,
     pub best_move: Option<Move>,
     pub score: Score,
     pub time_ms: f64,
     pub nps: u64,
     pub time_stopped: bool,
+    pub tt_probes: u64,
     pub tt_hits: u64,
     pub tt_cutoffs: u64,
+    pub tt_stores: u64,
+    /// Fail-high nodes in the main search
+    pub beta_cutoffs: u64,
+    /// Fail-high nodes where the first move searched caused the cutoff
+    pub first_move_cutoffs: u64,
+    pub null_move_tries: u64,
+    pub null_move_cutoffs: u64,
+    /// Moves searched at reduced depth by LMR
+    pub lmr_reductions: u64,
+    /// Reduced searches that had to be repeated at full depth
+    pub lmr_researches: u64,
+    /// Positions resolved by an endgame tablebase probe
+    pub tb_hits: u64,
+}
+
+impl SearchStats {
+    /// Fraction of beta cutoffs produced by the first move (move ordering quality).
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        if self.beta_cutoffs == 0 {
+            0.0
+        } else {
+            self.first_move_cutoffs as f64 / self.beta_cutoffs as f64
+        }
+    }
+
+    /// Add the counters of one iteration to the running totals.
+    fn accumulate(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.qnodes += other.qnodes;
+        self.seldepth = self.seldepth.max(other.seldepth);
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.tt_cutoffs += other.tt_cutoffs;
+        self.tt_stores += other.tt_stores;
+        self.beta_cutoffs += other.beta_cutoffs;
+        self.first_move_cutoffs += other.first_move_cutoffs;
+        self.null_move_tries += other.null_move_tries;
+        self.null_move_cutoffs += other.null_move_cutoffs;
+        self.lmr_reductions += other.lmr_reductions;
+        self.lmr_researches += other.lmr_researches;
+        self.tb_hits += other.tb_hits;
+    }
 }
 
 /// Killer moves table: 2 killer moves per ply.
@@ -80,6 +133,244 @@ impl Killers {
     }
 }
 
+// =============================================================================
+// SEARCH CONTROL (stop / deadline / ponder)
+// =============================================================================
+
+/// How many nodes to search between checks of the stop flag and the clock.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Shared control for a running search: stop flag, time budget and ponder state.
+/// All fields are atomics so a control can be shared (e.g. via `Arc`) with the
+/// thread that reads user/GUI input while the search runs.
+pub struct SearchControl {
+    stop: AtomicBool,
+    pondering: AtomicBool,
+    /// Start of the time budget (f64 ms bits)
+    start_ms: AtomicU64,
+    /// Time budget in ms (f64 bits); infinite while pondering
+    budget_ms: AtomicU64,
+    /// Nodes the whole search may visit
+    node_limit: AtomicU64,
+    /// Clock play: no new iteration past this many ms (f64 bits, scaled by
+    /// best-move stability); infinite for a plain budget
+    soft_ms: AtomicU64,
+}
+
+impl SearchControl {
+    /// Control for a normal search with a time budget (`f64::INFINITY` for none).
+    pub fn new(max_ms: f64) -> Self {
+        SearchControl {
+            stop: AtomicBool::new(false),
+            pondering: AtomicBool::new(false),
+            start_ms: AtomicU64::new(now_ms().to_bits()),
+            budget_ms: AtomicU64::new(max_ms.to_bits()),
+            node_limit: AtomicU64::new(u64::MAX),
+            soft_ms: AtomicU64::new(f64::INFINITY.to_bits()),
+        }
+    }
+
+    /// Control for clock play: iterations stop past the soft limit, the
+    /// search aborts at the hard one (see `timeman`)
+    pub fn with_limits(limits: TimeLimits) -> Self {
+        let control = Self::new(limits.hard_ms);
+        control.set_soft_limit(limits.soft_ms);
+        control
+    }
+
+    /// Start no iteration after `ms` (scaled by best-move stability), for a
+    /// ponder search turned into a clock search by `ponderhit`
+    pub fn set_soft_limit(&self, ms: f64) {
+        self.soft_ms.store(ms.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Stop after about `nodes` nodes (the first iteration still completes)
+    pub fn set_node_limit(&self, nodes: u64) {
+        self.node_limit.store(nodes, Ordering::Relaxed);
+    }
+
+    fn node_limit(&self) -> u64 {
+        self.node_limit.load(Ordering::Relaxed)
+    }
+
+    /// Control for a ponder search: runs without a time limit until
+    /// `ponderhit` or `stop` is called.
+    pub fn ponder() -> Self {
+        let control = Self::new(f64::INFINITY);
+        control.pondering.store(true, Ordering::Relaxed);
+        control
+    }
+
+    /// Ask the search to stop as soon as possible.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::Relaxed)
+    }
+
+    /// The predicted move was played: turn the ponder search into a normal
+    /// search with `max_ms` counted from now.
+    pub fn ponderhit(&self, max_ms: f64) {
+        self.start_ms.store(now_ms().to_bits(), Ordering::Relaxed);
+        self.budget_ms.store(max_ms.to_bits(), Ordering::Relaxed);
+        self.pondering.store(false, Ordering::Relaxed);
+    }
+
+    /// Milliseconds since the budget started.
+    fn elapsed_ms(&self) -> f64 {
+        now_ms() - f64::from_bits(self.start_ms.load(Ordering::Relaxed))
+    }
+
+    fn budget(&self) -> f64 {
+        f64::from_bits(self.budget_ms.load(Ordering::Relaxed))
+    }
+
+    /// Whether the running iteration must be abandoned.
+    fn time_up(&self) -> bool {
+        self.is_stopped() || (!self.is_pondering() && self.elapsed_ms() >= self.budget())
+    }
+
+    /// Whether another iteration is worth starting, the best move having
+    /// been the same for `stable` iterations. With a soft limit: stop past it,
+    /// as scaled by `stability_scale`. Otherwise stop if the budget is spent,
+    /// or if the next iteration (~3x the time so far) clearly won't fit.
+    fn should_stop_iterating(&self, stable: u32) -> bool {
+        if self.is_stopped() {
+            return true;
+        }
+        if self.is_pondering() {
+            return false;
+        }
+        let elapsed = self.elapsed_ms();
+        let budget = self.budget();
+        let soft = f64::from_bits(self.soft_ms.load(Ordering::Relaxed));
+        if soft.is_finite() {
+            return elapsed >= budget || elapsed >= soft * stability_scale(stable);
+        }
+        elapsed >= budget || budget - elapsed < elapsed * 3.0
+    }
+}
+
+/// Evaluation and playing-style settings for a search. The default is the
+/// standard engine; personalities supply their own.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions<'a> {
+    /// Evaluation weights. Use `default_params()` for the standard evaluation,
+    /// which is faster than any copy of it.
+    pub params: &'a EvalParams,
+    /// Taken off every draw (stalemate, repetition, fifty moves, insufficient
+    /// material) for the side that started the search, and added for the
+    /// opponent, so the engine avoids draws when positive and seeks them when
+    /// negative. Scores include it.
+    pub contempt: Score,
+}
+
+impl Default for SearchOptions<'_> {
+    fn default() -> Self {
+        SearchOptions { params: default_params(), contempt: 0 }
+    }
+}
+
+/// Per-search mutable state threaded through the recursion.
+struct SearchContext<'a> {
+    killers: Killers,
+    /// Static eval cache, kept across iterations of the same search
+    eval_cache: EvalCache,
+    options: SearchOptions<'a>,
+    /// Side to move at the root, which contempt favors
+    root_color: Color,
+    /// Hashes of the positions from the root to the current node's parent
+    path: Vec<u64>,
+    /// Start of the part of `path` reached without a null move, where a
+    /// repeated hash is a real repetition
+    path_start: usize,
+    control: Option<&'a SearchControl>,
+    /// Node count at which the control is checked next
+    next_check: u64,
+    /// Set once the control asks to stop; the current iteration is discarded
+    aborted: bool,
+    /// Root moves left out of the search, for multi-PV
+    excluded: Vec<Move>,
+    /// Abort the iteration once it has visited this many nodes and stored a
+    /// result, so an iteration repeated from the TT always gets further
+    node_limit: u64,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(control: Option<&'a SearchControl>, options: &SearchOptions<'a>, root_color: Color) -> Self {
+        SearchContext {
+            killers: Killers::new(),
+            eval_cache: EvalCache::new(evalcache::DEFAULT_SIZE_POWER),
+            options: *options,
+            root_color,
+            path: Vec::new(),
+            path_start: 0,
+            control,
+            next_check: CHECK_INTERVAL,
+            aborted: false,
+            excluded: Vec::new(),
+            node_limit: u64::MAX,
+        }
+    }
+
+    /// Prepare for the next iterative-deepening iteration.
+    fn start_iteration(&mut self, control: Option<&'a SearchControl>) {
+        self.killers = Killers::new();
+        self.path.clear();
+        self.path_start = 0;
+        self.control = control;
+        self.next_check = CHECK_INTERVAL;
+    }
+
+    /// Static eval for the side to move.
+    #[inline]
+    fn static_eval(&mut self, pos: &Position) -> Score {
+        self.eval_cache.evaluate(pos, self.options.params)
+    }
+
+    /// Draw score for the side to move, with contempt applied.
+    #[inline]
+    fn draw_score(&self, pos: &Position) -> Score {
+        if pos.side_to_move() == self.root_color {
+            DRAW_SCORE - self.options.contempt
+        } else {
+            DRAW_SCORE + self.options.contempt
+        }
+    }
+
+    /// Whether the position is drawn by the fifty-move rule, insufficient
+    /// material or a repetition since the root. One repetition is enough:
+    /// whatever avoids the draw could have been played the first time.
+    fn is_draw(&self, pos: &Position) -> bool {
+        if pos.halfmove_clock() >= 100 || pos.is_insufficient_material() {
+            return true;
+        }
+        let hash = pos.hash();
+        let reversible = pos.halfmove_clock() as usize;
+        let since_null = self.path.get(self.path_start..).unwrap_or_default();
+        since_null.iter().rev().take(reversible).any(|&h| h == hash)
+    }
+
+    /// Poll the control every `CHECK_INTERVAL` nodes.
+    #[inline]
+    fn check_abort(&mut self, stats: &SearchStats) -> bool {
+        if !self.aborted && stats.nodes >= self.next_check {
+            self.next_check = stats.nodes + CHECK_INTERVAL;
+            if let Some(control) = self.control {
+                self.aborted = control.time_up();
+            }
+            self.aborted |= stats.nodes >= self.node_limit && stats.tt_stores > 0;
+        }
+        self.aborted
+    }
+}
+
 // =============================================================================
 // MAIN SEARCH FUNCTIONS
 // =============================================================================
@@ -87,22 +378,59 @@ impl Killers {
 /// Find the best move (no TT — backwards compatible).
 pub fn search(pos: &mut Position, depth: u8) -> (Option<Move>, Score, SearchStats) {
     let mut tt = TranspositionTable::new(18);
-    search_with_tt(pos, depth, &mut tt)
+    search_with_tt(pos, depth, &mut tt, &SearchOptions::default())
+}
+
+/// Find the best move with the given evaluation and style settings (no TT reuse).
+pub fn search_with_options(pos: &mut Position, depth: u8, options: &SearchOptions) -> (Option<Move>, Score, SearchStats) {
+    let mut tt = TranspositionTable::new(18);
+    search_with_tt(pos, depth, &mut tt, options)
 }
 
 /// Find the best move using the given TT.
-pub fn search_with_tt(pos: &mut Position, depth: u8, tt: &mut TranspositionTable) -> (Option<Move>, Score, SearchStats) {
+/// When reusing a TT across moves, call `tt.new_search()` first so entries
+/// from earlier searches age out. Clear the TT when switching options: its
+/// scores were computed with the old ones.
+pub fn search_with_tt(
+    pos: &mut Position,
+    depth: u8,
+    tt: &mut TranspositionTable,
+    options: &SearchOptions,
+) -> (Option<Move>, Score, SearchStats) {
+    #[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+    if let Some(result) = tablebase_root(pos) {
+        return result;
+    }
+    let mut ctx = SearchContext::new(None, options, pos.side_to_move());
+    search_iteration(pos, depth, tt, &mut ctx)
+}
+
+/// Root position covered by the tablebases: play the DTZ move without searching.
+#[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+fn tablebase_root(pos: &mut Position) -> Option<(Option<Move>, Score, SearchStats)> {
+    let (mv, score) = syzygy::probe_root(pos)?;
+    let stats = SearchStats { best_move: Some(mv), score, tb_hits: 1, ..SearchStats::default() };
+    Some((Some(mv), score, stats))
+}
+
+/// One fixed-depth search from the root.
+fn search_iteration(
+    pos: &mut Position,
+    depth: u8,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+) -> (Option<Move>, Score, SearchStats) {
     let mut stats = SearchStats::default();
-    let mut killers = Killers::new();
+    let hits_before = tt.hits;
     stats.depth = depth;
 
     let (score, best_move) = alpha_beta(
-        pos, depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1, &mut stats, tt, &mut killers, true,
+        pos, depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1, &mut stats, tt, ctx, true,
     );
 
     stats.score = score;
     stats.best_move = best_move;
-    stats.tt_hits = tt.hits;
+    stats.tt_hits = tt.hits - hits_before;
 
     (best_move, score, stats)
 }
@@ -110,21 +438,21 @@ pub fn search_with_tt(pos: &mut Position, depth: u8, tt: &mut TranspositionTable
 /// Iterative deepening search (creates its own TT, shared across depths).
 pub fn search_iterative(pos: &mut Position, max_depth: u8) -> (Option<Move>, Score, SearchStats) {
     let mut tt = TranspositionTable::new(18);
+    tt.new_search();
     let mut best_move = None;
     let mut best_score = -MATE_SCORE;
     let mut total_stats = SearchStats::default();
 
     for depth in 1..=max_depth {
-        let (mv, score, stats) = search_with_tt(pos, depth, &mut tt);
+        let (mv, score, stats) = search_with_tt(pos, depth, &mut tt, &SearchOptions::default());
 
         if let Some(m) = mv {
             best_move = Some(m);
             best_score = score;
         }
 
-        total_stats.nodes += stats.nodes;
+        total_stats.accumulate(&stats);
         total_stats.depth = depth;
-        total_stats.tt_hits = tt.hits;
     }
 
     total_stats.best_move = best_move;
@@ -133,36 +461,100 @@ pub fn search_iterative(pos: &mut Position, max_depth: u8) -> (Option<Move>, Sco
     (best_move, best_score, total_stats)
 }
 
-/// Time-limited iterative deepening with TT.
+/// Time-limited iterative deepening with TT (creates its own TT).
 pub fn search_timed(pos: &mut Position, max_ms: f64, max_depth: u8) -> (Option<Move>, Score, SearchStats) {
+    let mut tt = TranspositionTable::new(18);
+    search_timed_with_tt(pos, max_ms, max_depth, &mut tt, &SearchOptions::default())
+}
+
+/// Time-limited iterative deepening using the given TT.
+/// Starts a new TT generation, so the table can be kept across moves.
+pub fn search_timed_with_tt(
+    pos: &mut Position,
+    max_ms: f64,
+    max_depth: u8,
+    tt: &mut TranspositionTable,
+    options: &SearchOptions,
+) -> (Option<Move>, Score, SearchStats) {
+    let control = SearchControl::new(max_ms);
+    search_with_control(pos, max_depth, tt, &control, options)
+}
+
+/// Iterative deepening governed by a `SearchControl`: runs until the control's
+/// time budget or node limit is spent, `stop()` is called, or `max_depth`
+/// (0 = unlimited) is reached. While the control is pondering there is no time
+/// limit. An iteration cut short is discarded; depth 1 always completes so a
+/// move is always returned.
+pub fn search_with_control(
+    pos: &mut Position,
+    max_depth: u8,
+    tt: &mut TranspositionTable,
+    control: &SearchControl,
+    options: &SearchOptions,
+) -> (Option<Move>, Score, SearchStats) {
+    search_with_progress(pos, max_depth, tt, control, options, None)
+}
+
+/// Called after each completed depth with the statistics so far and the PV
+pub type IterationReport<'a> = dyn FnMut(&SearchStats, &[Move]) + 'a;
+
+/// `search_with_control`, calling `on_iteration` after each completed depth
+/// with the statistics so far (depth, best move, score, nodes, time) and the
+/// principal variation, as UCI "info" lines report them
+pub fn search_with_progress(
+    pos: &mut Position,
+    max_depth: u8,
+    tt: &mut TranspositionTable,
+    control: &SearchControl,
+    options: &SearchOptions,
+    mut on_iteration: Option<&mut IterationReport>,
+) -> (Option<Move>, Score, SearchStats) {
     let start = now_ms();
-    let deadline = start + max_ms;
     let depth_limit = if max_depth == 0 { MAX_DEPTH } else { max_depth };
 
-    let mut tt = TranspositionTable::new(18);
+    #[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+    if let Some((mv, score, mut stats)) = tablebase_root(pos) {
+        stats.time_ms = now_ms() - start;
+        return (mv, score, stats);
+    }
+
+    tt.new_search();
     let mut best_move = None;
     let mut best_score = -MATE_SCORE;
     let mut total_stats = SearchStats::default();
+    let mut ctx = SearchContext::new(None, options, pos.side_to_move());
+    // Iterations in a row that ended with the same best move
+    let mut stable = 0;
 
     for depth in 1..=depth_limit {
-        let (mv, score, stats) = search_with_tt(pos, depth, &mut tt);
+        ctx.start_iteration(if depth > 1 { Some(control) } else { None });
+        if depth > 1 {
+            ctx.node_limit = control.node_limit().saturating_sub(total_stats.nodes);
+        }
+        let (mv, score, stats) = search_iteration(pos, depth, tt, &mut ctx);
 
-        total_stats.nodes += stats.nodes;
+        total_stats.accumulate(&stats);
+        if ctx.aborted {
+            total_stats.time_stopped = true;
+            break;
+        }
         total_stats.depth = depth;
-        total_stats.tt_hits = tt.hits;
 
         if let Some(m) = mv {
+            stable = if best_move == Some(m) { stable + 1 } else { 0 };
             best_move = Some(m);
             best_score = score;
+            if let Some(report) = on_iteration.as_deref_mut() {
+                let elapsed = now_ms() - start;
+                total_stats.best_move = best_move;
+                total_stats.score = best_score;
+                total_stats.time_ms = elapsed;
+                total_stats.nps = if elapsed > 0.0 { (total_stats.nodes as f64 / (elapsed / 1000.0)) as u64 } else { 0 };
+                report(&total_stats, &tt_pv(pos, tt, m, depth as usize));
+            }
         }
 
-        let elapsed = now_ms() - start;
-        if elapsed >= max_ms {
-            total_stats.time_stopped = true;
-            break;
-        }
-        let remaining = deadline - now_ms();
-        if remaining < elapsed * 3.0 {
+        if control.should_stop_iterating(stable) {
             total_stats.time_stopped = true;
             break;
         }
@@ -179,6 +571,268 @@ pub fn search_timed(pos: &mut Position, max_ms: f64, max_depth: u8) -> (Option<M
     (best_move, best_score, total_stats)
 }
 
+// =============================================================================
+// STEPPED SEARCH
+// =============================================================================
+
+/// Most a slice's node budget grows while an iteration is being repeated
+const MAX_SLICE_GROWTH: u64 = 4;
+
+/// Iterative deepening run a slice at a time, for callers that have to hand
+/// control back between slices (the browser's main thread). A slice that runs
+/// out of nodes abandons its iteration; the next slices repeat it with up to
+/// `MAX_SLICE_GROWTH` times the budget, finding the finished subtrees in the
+/// TT. The time limit is checked inside a slice, so none runs past it.
+pub struct SteppedSearch {
+    pos: Position,
+    tt: TranspositionTable,
+    /// Deadline and stop request, polled during a slice
+    control: SearchControl,
+    options: SearchOptions<'static>,
+    depth_limit: u8,
+    /// Time limit in ms, 0 for none
+    max_ms: f64,
+    start: f64,
+    /// Next depth to search
+    depth: u8,
+    /// Slices the current depth has run out of nodes in
+    retries: u32,
+    best_move: Option<Move>,
+    best_score: Score,
+    stats: SearchStats,
+    done: bool,
+}
+
+impl SteppedSearch {
+    /// Search a copy of `pos` with its own TT, to `max_depth` (0 = no limit)
+    /// or for `max_ms` (0 = no limit), whichever comes first. The first
+    /// iteration always completes, so a move is found.
+    pub fn new(pos: &Position, max_depth: u8, max_ms: f64, options: SearchOptions<'static>) -> Self {
+        Self::with_tt(pos, max_depth, max_ms, options, TranspositionTable::default_wasm())
+    }
+
+    /// `new`, searching with `tt` (a handle on a shared table, for instance)
+    pub fn with_tt(pos: &Position, max_depth: u8, max_ms: f64, options: SearchOptions<'static>, mut tt: TranspositionTable) -> Self {
+        tt.new_search();
+        SteppedSearch {
+            pos: pos.clone(),
+            tt,
+            options,
+            control: SearchControl::new(if max_ms > 0.0 { max_ms } else { f64::INFINITY }),
+            depth_limit: if max_depth == 0 { MAX_DEPTH } else { max_depth.min(MAX_DEPTH) },
+            max_ms,
+            start: now_ms(),
+            depth: 1,
+            retries: 0,
+            best_move: None,
+            best_score: -MATE_SCORE,
+            stats: SearchStats::default(),
+            done: false,
+        }
+    }
+
+    /// Search for about `node_budget` nodes. Returns true once the search is finished.
+    pub fn step(&mut self, node_budget: u64) -> bool {
+        if self.done {
+            return true;
+        }
+        if self.control.is_stopped() && self.best_move.is_some() {
+            self.finish(true);
+            return true;
+        }
+        // The first iteration always completes, so there is a move to return
+        let control = self.best_move.is_some().then_some(&self.control);
+        let mut ctx = SearchContext::new(control, &self.options, self.pos.side_to_move());
+        if self.depth > 1 {
+            let growth = (1u64 << self.retries.min(8)).min(MAX_SLICE_GROWTH);
+            ctx.node_limit = node_budget.max(CHECK_INTERVAL).saturating_mul(growth);
+        }
+        let (mv, score, stats) = search_iteration(&mut self.pos, self.depth, &mut self.tt, &mut ctx);
+        self.stats.accumulate(&stats);
+
+        if ctx.aborted {
+            self.retries += 1;
+        } else {
+            self.stats.depth = self.depth;
+            if let Some(m) = mv {
+                self.best_move = Some(m);
+                self.best_score = score;
+            }
+            self.retries = 0;
+            self.depth += 1;
+        }
+
+        let out_of_time = self.max_ms > 0.0 && now_ms() - self.start >= self.max_ms;
+        let stop = (out_of_time || self.control.is_stopped()) && self.best_move.is_some();
+        if self.depth > self.depth_limit || stop {
+            self.finish(stop && self.depth <= self.depth_limit);
+        }
+        self.done
+    }
+
+    /// Finish at the next `step`, with the deepest iteration completed so far
+    pub fn stop(&mut self) {
+        self.control.stop();
+    }
+
+    fn finish(&mut self, time_stopped: bool) {
+        let elapsed = now_ms() - self.start;
+        self.done = true;
+        self.stats.time_stopped = time_stopped;
+        self.stats.time_ms = elapsed;
+        self.stats.nps = if elapsed > 0.0 { (self.stats.nodes as f64 / (elapsed / 1000.0)) as u64 } else { 0 };
+        self.stats.best_move = self.best_move;
+        self.stats.score = self.best_score;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Best move, score and statistics of the deepest finished iteration
+    pub fn result(&self) -> (Option<Move>, Score, SearchStats) {
+        (self.best_move, self.best_score, self.stats.clone())
+    }
+}
+
+// =============================================================================
+// MULTI-PV
+// =============================================================================
+
+/// One of the best root moves found by a multi-PV search
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvLine {
+    pub mv: Move,
+    /// Score for the side to move at the root
+    pub score: Score,
+    /// Expected continuation, starting with `mv`
+    pub pv: Vec<Move>,
+}
+
+/// Iterative deepening for the `n` best root moves: each depth searches the
+/// root once per line, leaving out the moves already found. Governed by
+/// `control` like `search_with_control`; an interrupted depth is discarded.
+/// Lines are best first, fewer than `n` if there aren't that many legal moves.
+pub fn search_multipv_with_control(
+    pos: &mut Position,
+    n: usize,
+    max_depth: u8,
+    tt: &mut TranspositionTable,
+    control: &SearchControl,
+    options: &SearchOptions,
+) -> (Vec<PvLine>, SearchStats) {
+    search_multipv_with_progress(pos, n, max_depth, tt, control, options, None)
+}
+
+/// Called after each completed multi-PV depth with the statistics so far and the lines
+pub type MultiPvReport<'a> = dyn FnMut(&SearchStats, &[PvLine]) + 'a;
+
+/// `search_multipv_with_control`, calling `on_depth` after each completed depth
+pub fn search_multipv_with_progress(
+    pos: &mut Position,
+    n: usize,
+    max_depth: u8,
+    tt: &mut TranspositionTable,
+    control: &SearchControl,
+    options: &SearchOptions,
+    mut on_depth: Option<&mut MultiPvReport>,
+) -> (Vec<PvLine>, SearchStats) {
+    let start = now_ms();
+    let depth_limit = if max_depth == 0 { MAX_DEPTH } else { max_depth };
+    let n = n.min(generate_legal_moves(pos).len());
+
+    tt.new_search();
+    let mut lines: Vec<PvLine> = Vec::new();
+    let mut total_stats = SearchStats::default();
+    let mut ctx = SearchContext::new(None, options, pos.side_to_move());
+    let mut stable = 0;
+
+    'deepening: for depth in 1..=depth_limit {
+        let mut depth_lines: Vec<PvLine> = Vec::with_capacity(n);
+        ctx.excluded.clear();
+        for _ in 0..n {
+            ctx.start_iteration(if depth > 1 { Some(control) } else { None });
+            if depth > 1 {
+                ctx.node_limit = control.node_limit().saturating_sub(total_stats.nodes);
+            }
+            let (mv, score, stats) = search_iteration(pos, depth, tt, &mut ctx);
+            total_stats.accumulate(&stats);
+            if ctx.aborted {
+                total_stats.time_stopped = true;
+                break 'deepening;
+            }
+            let Some(mv) = mv else { break };
+            depth_lines.push(PvLine { mv, score, pv: tt_pv(pos, tt, mv, depth as usize) });
+            ctx.excluded.push(mv);
+        }
+        depth_lines.sort_by_key(|line| std::cmp::Reverse(line.score));
+        let same_best = matches!((lines.first(), depth_lines.first()), (Some(a), Some(b)) if a.mv == b.mv);
+        stable = if same_best { stable + 1 } else { 0 };
+        lines = depth_lines;
+        total_stats.depth = depth;
+        if let Some(report) = on_depth.as_deref_mut() {
+            let elapsed = now_ms() - start;
+            total_stats.time_ms = elapsed;
+            total_stats.nps = if elapsed > 0.0 { (total_stats.nodes as f64 / (elapsed / 1000.0)) as u64 } else { 0 };
+            report(&total_stats, &lines);
+        }
+
+        if control.should_stop_iterating(stable) {
+            total_stats.time_stopped = true;
+            break;
+        }
+    }
+
+    let total_time = now_ms() - start;
+    total_stats.time_ms = total_time;
+    total_stats.nps = if total_time > 0.0 {
+        (total_stats.nodes as f64 / (total_time / 1000.0)) as u64
+    } else { 0 };
+    if let Some(best) = lines.first() {
+        total_stats.best_move = Some(best.mv);
+        total_stats.score = best.score;
+    }
+
+    (lines, total_stats)
+}
+
+/// Fixed-depth multi-PV search using the given TT.
+pub fn search_multipv(
+    pos: &mut Position,
+    n: usize,
+    depth: u8,
+    tt: &mut TranspositionTable,
+    options: &SearchOptions,
+) -> Vec<PvLine> {
+    let control = SearchControl::new(f64::INFINITY);
+    search_multipv_with_control(pos, n, depth.max(1), tt, &control, options).0
+}
+
+/// Continuation after root move `first`, following TT best moves for at most
+/// `max_len` moves in all
+fn tt_pv(pos: &mut Position, tt: &mut TranspositionTable, first: Move, max_len: usize) -> Vec<Move> {
+    let mut pv = vec![first];
+    let mut played = Vec::new();
+    let mut mv = first;
+    while let Some(undo) = pos.make_move(mv) {
+        played.push((mv, undo));
+        if pv.len() >= max_len {
+            break;
+        }
+        match tt.probe(pos.hash()).and_then(|e| e.best_move) {
+            Some(next) if generate_legal_moves(pos).iter().any(|&m| m == next) => {
+                pv.push(next);
+                mv = next;
+            }
+            _ => break,
+        }
+    }
+    for (mv, undo) in played.iter().rev() {
+        pos.unmake_move(*mv, undo);
+    }
+    pv
+}
+
 // =============================================================================
 // ALPHA-BETA SEARCH WITH TT + KILLERS + NMP + LMR
 // =============================================================================
@@ -190,6 +844,7 @@ const LMR_FULL_DEPTH_MOVES: usize = 4;
 /// Late Move Reduction: minimum depth to apply LMR
 const LMR_MIN_DEPTH: u8 = 3;
 
+#[allow(clippy::too_many_arguments)]
 fn alpha_beta(
     pos: &mut Position,
     depth: u8,
@@ -198,26 +853,41 @@ fn alpha_beta(
     beta: Score,
     stats: &mut SearchStats,
     tt: &mut TranspositionTable,
-    killers: &mut Killers,
+    ctx: &mut SearchContext,
     do_null: bool,
 ) -> (Score, Option<Move>) {
+    if ctx.check_abort(stats) {
+        return (0, None);
+    }
     stats.nodes += 1;
+    stats.seldepth = stats.seldepth.max(ply);
+
+    // Game already won under the variant's rules (e.g. a third check)
+    if let Some(score) = variant_score(pos, ply) {
+        return (score, None);
+    }
+    if ply > 0 && ctx.is_draw(pos) {
+        return (ctx.draw_score(pos), None);
+    }
 
     // Base case: leaf node
     if depth == 0 {
-        return (quiescence(pos, alpha, beta, stats), None);
+        return (quiescence(pos, ply, alpha, beta, stats, ctx), None);
     }
 
     let in_check = pos.is_in_check(pos.side_to_move());
+    // The root entry doesn't know which moves are excluded: neither trust nor overwrite it
+    let excluding = ply == 0 && !ctx.excluded.is_empty();
 
     // ── TT Probe ──
     let hash = pos.hash();
     let mut tt_move: Option<Move> = None;
 
+    stats.tt_probes += 1;
     if let Some(entry) = tt.probe(hash) {
         tt_move = entry.best_move;
 
-        if entry.depth >= depth {
+        if entry.depth >= depth && !excluding {
             let tt_score = score_from_tt(entry.score, ply);
             match entry.flag {
                 TTFlag::Exact => {
@@ -243,21 +913,49 @@ fn alpha_beta(
         }
     }
 
+    // ── Tablebase Probe ──
+    // WDL ignores the halfmove clock, so only probe right after a capture or pawn move.
+    // Positions with the maximum piece count are the most expensive to look up and
+    // are only probed with at least `probe_depth` remaining.
+    #[cfg(all(feature = "syzygy", not(target_arch = "wasm32")))]
+    if ply > 0 && pos.halfmove_clock() == 0 {
+        let pieces = pos.occupied().count();
+        let max_pieces = syzygy::max_pieces();
+        if pieces < max_pieces || (pieces == max_pieces && depth >= syzygy::probe_depth()) {
+            if let Some(wdl) = syzygy::probe_wdl(pos) {
+                stats.tb_hits += 1;
+                let score = wdl.to_score(ply);
+                tt.store(hash, depth, score_to_tt(score, ply), TTFlag::Exact, None);
+                stats.tt_stores += 1;
+                return (score, None);
+            }
+        }
+    }
+
     // ── Null Move Pruning ──
     // Skip if: in check, at root (ply 0), already did null move, no non-pawn material,
     // or depth is too shallow.
     if do_null && !in_check && ply > 0 && depth > NMP_REDUCTION + 1
         && pos.has_non_pawn_material(pos.side_to_move())
     {
+        stats.null_move_tries += 1;
         let (saved_ep, saved_hash) = pos.make_null_move();
         let reduced_depth = depth - 1 - NMP_REDUCTION;
+        // Neither the positions before the null move nor the one it leads to
+        // can be repeated by real moves after it
+        let path_start = std::mem::replace(&mut ctx.path_start, ctx.path.len() + 1);
         let (null_score, _) = alpha_beta(
-            pos, reduced_depth, ply + 1, -beta, -beta + 1, stats, tt, killers, false,
+            pos, reduced_depth, ply + 1, -beta, -beta + 1, stats, tt, ctx, false,
         );
         let null_score = -null_score;
+        ctx.path_start = path_start;
         pos.unmake_null_move(saved_ep, saved_hash);
+        if ctx.aborted {
+            return (0, None);
+        }
 
         if null_score >= beta {
+            stats.null_move_cutoffs += 1;
             return (beta, None); // Null move cutoff
         }
     }
@@ -270,22 +968,26 @@ fn alpha_beta(
         let score = if in_check {
             -MATE_SCORE + ply as Score
         } else {
-            DRAW_SCORE
+            ctx.draw_score(pos)
         };
         return (score, None);
     }
 
     // Order moves: TT move first, then captures (MVV-LVA), then killers, then quiet
-    let ordered_moves = order_moves_full(&moves, pos, tt_move, killers, ply);
+    let ordered_moves = order_moves_full(&moves, pos, tt_move, &ctx.killers, ply);
 
     let mut best_move = None;
     let original_alpha = alpha;
     let mut moves_searched: usize = 0;
+    ctx.path.push(hash);
 
     for mv in ordered_moves.iter() {
+        if excluding && ctx.excluded.contains(mv) {
+            continue;
+        }
         let is_cap = is_capture(pos, *mv);
         let is_promo = mv.is_promotion();
-        let is_killer = killers.is_killer(ply, *mv);
+        let is_killer = ctx.killers.is_killer(ply, *mv);
 
         let undo = match pos.make_move(*mv) {
             Some(u) => u,
@@ -307,15 +1009,17 @@ fn alpha_beta(
             && !gives_check
         {
             // Search at reduced depth (reduction of 1)
+            stats.lmr_reductions += 1;
             let (reduced_score, _) = alpha_beta(
-                pos, depth - 2, ply + 1, -beta, -alpha, stats, tt, killers, true,
+                pos, depth - 2, ply + 1, -beta, -alpha, stats, tt, ctx, true,
             );
             let reduced_score = -reduced_score;
 
             if reduced_score > alpha {
                 // LMR failed — re-search at full depth
+                stats.lmr_researches += 1;
                 let (full_score, _) = alpha_beta(
-                    pos, depth - 1, ply + 1, -beta, -alpha, stats, tt, killers, true,
+                    pos, depth - 1, ply + 1, -beta, -alpha, stats, tt, ctx, true,
                 );
                 score = -full_score;
             } else {
@@ -323,11 +1027,15 @@ fn alpha_beta(
             }
         } else {
             // Full-depth search
-            let (s, _) = alpha_beta(pos, depth - 1, ply + 1, -beta, -alpha, stats, tt, killers, true);
+            let (s, _) = alpha_beta(pos, depth - 1, ply + 1, -beta, -alpha, stats, tt, ctx, true);
             score = -s;
         }
 
         pos.unmake_move(*mv, &undo);
+        if ctx.aborted {
+            ctx.path.pop();
+            return (0, None);
+        }
         moves_searched += 1;
 
         if score > alpha {
@@ -335,14 +1043,19 @@ fn alpha_beta(
             best_move = Some(*mv);
 
             if alpha >= beta {
+                stats.beta_cutoffs += 1;
+                if moves_searched == 1 {
+                    stats.first_move_cutoffs += 1;
+                }
                 // Beta cutoff — store killer if quiet move
                 if !is_cap {
-                    killers.store(ply, *mv);
+                    ctx.killers.store(ply, *mv);
                 }
                 break;
             }
         }
     }
+    ctx.path.pop();
 
     // ── TT Store ──
     let flag = if alpha >= beta {
@@ -352,7 +1065,10 @@ fn alpha_beta(
     } else {
         TTFlag::UpperBound
     };
-    tt.store(hash, depth, score_to_tt(alpha, ply), flag, best_move);
+    if !excluding {
+        tt.store(hash, depth, score_to_tt(alpha, ply), flag, best_move);
+        stats.tt_stores += 1;
+    }
 
     (alpha, best_move)
 }
@@ -363,13 +1079,27 @@ fn alpha_beta(
 
 fn quiescence(
     pos: &mut Position,
+    ply: u8,
     mut alpha: Score,
     beta: Score,
     stats: &mut SearchStats,
+    ctx: &mut SearchContext,
 ) -> Score {
+    if ctx.check_abort(stats) {
+        return 0;
+    }
     stats.nodes += 1;
+    stats.qnodes += 1;
+    stats.seldepth = stats.seldepth.max(ply);
+
+    if let Some(score) = variant_score(pos, ply) {
+        return score;
+    }
+    if ply > 0 && ctx.is_draw(pos) {
+        return ctx.draw_score(pos);
+    }
 
-    let stand_pat = evaluate(pos);
+    let stand_pat = ctx.static_eval(pos);
 
     if stand_pat >= beta {
         return beta;
@@ -378,27 +1108,15 @@ fn quiescence(
         alpha = stand_pat;
     }
 
-    let moves = generate_legal_moves(pos);
-
-    if moves.is_empty() {
-        if pos.is_in_check(pos.side_to_move()) {
-            return -MATE_SCORE;
-        } else {
-            return DRAW_SCORE;
-        }
-    }
-
-    for mv in moves.iter() {
-        if !is_capture(pos, *mv) {
-            continue;
-        }
-
+    let mut any_legal = false;
+    for mv in generate_captures(pos).iter() {
         let undo = match pos.make_move(*mv) {
             Some(u) => u,
             None => continue,
         };
+        any_legal = true;
 
-        let score = -quiescence(pos, -beta, -alpha, stats);
+        let score = -quiescence(pos, ply.saturating_add(1), -beta, -alpha, stats, ctx);
         pos.unmake_move(*mv, &undo);
 
         if score >= beta {
@@ -409,9 +1127,37 @@ fn quiescence(
         }
     }
 
+    // No capture could be played: still spot checkmate and stalemate
+    if !any_legal && !has_legal_quiet(pos) {
+        if pos.is_in_check(pos.side_to_move()) {
+            return -MATE_SCORE;
+        } else {
+            return ctx.draw_score(pos);
+        }
+    }
+
     alpha
 }
 
+/// Mate-style score for the side to move if the variant has already decided
+/// the game
+fn variant_score(pos: &Position, ply: u8) -> Option<Score> {
+    let (winner, _) = pos.variant().outcome(pos)?;
+    let score = MATE_SCORE - ply as Score;
+    Some(if winner == pos.side_to_move() { score } else { -score })
+}
+
+/// Whether any non-capture is legal (stops at the first one found)
+fn has_legal_quiet(pos: &mut Position) -> bool {
+    generate_quiets(pos).iter().any(|&mv| match pos.make_move(mv) {
+        Some(undo) => {
+            pos.unmake_move(mv, &undo);
+            true
+        }
+        None => false,
+    })
+}
+
 // =============================================================================
 // MOVE ORDERING
 // =============================================================================
@@ -454,7 +1200,7 @@ fn order_moves_full(moves: &MoveList, pos: &Position, tt_move: Option<Move>, kil
         })
         .collect();
 
-    scored.sort_by(|a, b| b.1.cmp(&a.1));
+    scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
 
     let mut ordered = MoveList::new();
     for (mv, _) in scored {
@@ -531,8 +1277,8 @@ mod tests {
         let mut pos = Position::starting_position();
         let mut tt = TranspositionTable::new(18);
 
-        let (_, _, stats1) = search_with_tt(&mut pos, 4, &mut tt);
-        let (_, _, stats2) = search_with_tt(&mut pos, 4, &mut tt);
+        let (_, _, stats1) = search_with_tt(&mut pos, 4, &mut tt, &SearchOptions::default());
+        let (_, _, stats2) = search_with_tt(&mut pos, 4, &mut tt, &SearchOptions::default());
 
         // Second search should use fewer nodes thanks to TT
         assert!(stats2.nodes <= stats1.nodes,
@@ -572,4 +1318,264 @@ mod tests {
         let (mv, _, _) = search(&mut pos, 6);
         assert!(mv.is_some());
     }
+
+    #[test]
+    fn test_search_wins_three_check() {
+        // The third check beats winning the queen
+        let mut pos = Position::from_fen("4k3/8/8/8/8/6K1/8/1q5R w - - 0 1 +2+0").unwrap();
+        let (mv, score, _) = search(&mut pos, 3);
+        assert!(score > MATE_SCORE - 10);
+        pos.make_move(mv.unwrap()).unwrap();
+        assert_eq!(pos.checks_given(crate::types::Color::White), 3);
+    }
+
+    #[test]
+    fn test_contempt_avoids_drawing_capture() {
+        // Bxe3 leaves a bare bishop: a draw, which only beats keeping the
+        // piece against the pawn while draws are worth nothing
+        let fen = "8/8/3k4/8/8/4p3/8/2B3K1 w - - 0 1";
+        let opts = |contempt| SearchOptions { contempt, ..SearchOptions::default() };
+        let (neutral, score, _) = search_with_options(&mut Position::from_fen(fen).unwrap(), 4, &opts(0));
+        assert_eq!(neutral.unwrap().to_uci(), "c1e3");
+        assert_eq!(score, DRAW_SCORE);
+        let (biased, _, _) = search_with_options(&mut Position::from_fen(fen).unwrap(), 4, &opts(100));
+        assert_ne!(biased.unwrap().to_uci(), "c1e3");
+    }
+
+    #[test]
+    fn test_fifty_move_draw_is_scored() {
+        // A queen up, but any move without a capture or pawn push ends the game
+        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/Q3K3 w - - 99 80").unwrap();
+        let (_, score, _) = search(&mut pos, 3);
+        assert_eq!(score, DRAW_SCORE);
+    }
+
+    #[test]
+    fn test_repetition_since_root_is_a_draw() {
+        let mut pos = Position::starting_position();
+        let options = SearchOptions::default();
+        let mut ctx = SearchContext::new(None, &options, Color::White);
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            assert!(!ctx.is_draw(&pos));
+            ctx.path.push(pos.hash());
+            let mv = generate_legal_moves(&pos).iter().copied().find(|m| m.to_uci() == uci).unwrap();
+            pos.make_move(mv);
+        }
+        assert!(ctx.is_draw(&pos));
+        // Not when a null move was played since the first occurrence
+        ctx.path_start = 1;
+        assert!(!ctx.is_draw(&pos));
+    }
+
+    #[test]
+    fn test_multipv_lines() {
+        let mut pos = Position::from_fen("r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let fen = pos.to_fen();
+        let mut tt = TranspositionTable::new(16);
+        let lines = search_multipv(&mut pos, 3, 4, &mut tt, &SearchOptions::default());
+        assert_eq!(pos.to_fen(), fen);
+        assert_eq!(lines.len(), 3);
+        assert!(lines.windows(2).all(|w| w[0].score >= w[1].score));
+        // Distinct root moves, each line starting with its move
+        assert!(lines[0].mv != lines[1].mv && lines[1].mv != lines[2].mv && lines[0].mv != lines[2].mv);
+        assert!(lines.iter().all(|l| l.pv[0] == l.mv && l.pv.len() <= 4));
+        // The first line scores like a plain search
+        let (_, score, _) = search(&mut pos, 4);
+        assert_eq!(lines[0].score, score);
+
+        // Only as many lines as legal moves
+        let mut pos = Position::from_fen("7k/8/8/8/8/5q2/8/7K w - - 0 1").unwrap();
+        let lines = search_multipv(&mut pos, 5, 3, &mut tt, &SearchOptions::default());
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_multipv_timed_stops() {
+        let mut pos = Position::starting_position();
+        let mut tt = TranspositionTable::new(16);
+        let control = SearchControl::new(100.0);
+        let (lines, stats) = search_multipv_with_control(&mut pos, 2, 0, &mut tt, &control, &SearchOptions::default());
+        assert_eq!(lines.len(), 2);
+        assert!(stats.time_stopped && stats.depth >= 1);
+        assert!(stats.time_ms < 5000.0);
+    }
+
+    #[test]
+    fn test_multipv_progress() {
+        let mut pos = Position::starting_position();
+        let mut tt = TranspositionTable::new(16);
+        let control = SearchControl::new(f64::INFINITY);
+        let mut depths = Vec::new();
+        let mut report = |stats: &SearchStats, lines: &[PvLine]| {
+            assert_eq!(lines.len(), 3);
+            depths.push(stats.depth);
+        };
+        let (lines, _) =
+            search_multipv_with_progress(&mut pos, 3, 3, &mut tt, &control, &SearchOptions::default(), Some(&mut report));
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_stepped_search_matches_full_search() {
+        let fen = "r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let pos = Position::from_fen(fen).unwrap();
+        let mut task = SteppedSearch::new(&pos, 5, 0.0, SearchOptions::default());
+        let mut steps = 0;
+        while !task.step(2000) {
+            steps += 1;
+            assert!(steps < 10_000, "stepped search doesn't finish");
+        }
+        // Small slices: some iterations had to be repeated
+        assert!(steps > 5, "{} steps", steps);
+        assert!(task.is_done() && task.step(2000));
+        let (mv, score, stats) = task.result();
+        assert_eq!(stats.depth, 5);
+        assert!(!stats.time_stopped);
+
+        let mut full = Position::from_fen(fen).unwrap();
+        let (_, full_score, _) = search_iterative(&mut full, 5);
+        assert!(mv.is_some());
+        assert_eq!(score, full_score);
+    }
+
+    #[test]
+    fn test_stepped_search_time_limit() {
+        let pos = Position::starting_position();
+        let mut task = SteppedSearch::new(&pos, 0, 50.0, SearchOptions::default());
+        while !task.step(5000) {}
+        let (mv, _, stats) = task.result();
+        assert!(mv.is_some());
+        assert!(stats.time_stopped && stats.depth >= 1);
+        assert!(stats.time_ms < 5000.0);
+    }
+
+    #[test]
+    fn test_stepped_search_slices_stay_bounded() {
+        let fen = "r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let pos = Position::from_fen(fen).unwrap();
+        let budget = 2000;
+        let mut task = SteppedSearch::new(&pos, 5, 0.0, SearchOptions::default());
+        let (mut steps, mut nodes) = (0, 0);
+        while !task.step(budget) {
+            let slice = task.stats.nodes - nodes;
+            assert!(slice <= budget * MAX_SLICE_GROWTH + CHECK_INTERVAL, "{} nodes in one slice", slice);
+            nodes = task.stats.nodes;
+            steps += 1;
+        }
+        // Iterations were repeated, without their slices growing past the cap
+        assert!(steps > 5, "{} steps", steps);
+        assert_eq!(task.result().2.depth, 5);
+
+        // The time limit holds inside a slice, however large its budget: the
+        // iteration it cuts short would otherwise run to the end
+        let mut task = SteppedSearch::new(&Position::starting_position(), 0, 100.0, SearchOptions::default());
+        while !task.step(u64::MAX / 8) {}
+        let (mv, _, stats) = task.result();
+        assert!(mv.is_some() && stats.time_stopped);
+        assert!(stats.time_ms < 150.0, "{} ms", stats.time_ms);
+    }
+
+    #[test]
+    fn test_search_stats_counters() {
+        let mut pos = Position::from_fen("r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let (_, _, stats) = search_iterative(&mut pos, 5);
+
+        assert!(stats.qnodes > 0 && stats.qnodes < stats.nodes);
+        assert!(stats.seldepth >= 5, "seldepth {} below nominal depth", stats.seldepth);
+        assert!(stats.tt_probes >= stats.tt_hits);
+        assert!(stats.tt_stores > 0);
+        assert!(stats.beta_cutoffs > 0);
+        assert!(stats.first_move_cutoffs <= stats.beta_cutoffs);
+        let rate = stats.first_move_cutoff_rate();
+        assert!(rate > 0.0 && rate <= 1.0);
+        assert!(stats.null_move_cutoffs <= stats.null_move_tries);
+        assert!(stats.lmr_researches <= stats.lmr_reductions);
+        assert!(stats.lmr_reductions > 0);
+    }
+
+    #[test]
+    fn test_search_control_stop_still_returns_move() {
+        // Stopped before starting: depth 1 always completes, deeper iterations are skipped
+        let mut pos = Position::starting_position();
+        let mut tt = TranspositionTable::new(16);
+        let control = SearchControl::new(f64::INFINITY);
+        control.stop();
+        let (mv, _, stats) = search_with_control(&mut pos, 10, &mut tt, &control, &SearchOptions::default());
+        assert!(mv.is_some());
+        assert_eq!(stats.depth, 1);
+        assert!(stats.time_stopped);
+    }
+
+    #[test]
+    fn test_search_control_aborts_mid_iteration() {
+        // Without a depth limit the deadline must cut the search short
+        let mut pos = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut tt = TranspositionTable::new(16);
+        let start = now_ms();
+        let (mv, _, stats) = search_timed_with_tt(&mut pos, 50.0, 0, &mut tt, &SearchOptions::default());
+        assert!(mv.is_some());
+        assert!(stats.time_stopped);
+        assert!(now_ms() - start < 2000.0, "Search ignored its deadline");
+    }
+
+    #[test]
+    fn test_clock_limits_stop_at_soft_limit() {
+        let mut pos = Position::starting_position();
+        let mut tt = TranspositionTable::new(16);
+        let control = SearchControl::with_limits(TimeLimits { soft_ms: 20.0, hard_ms: 3000.0 });
+        let start = now_ms();
+        let (mv, _, stats) = search_with_control(&mut pos, 0, &mut tt, &control, &SearchOptions::default());
+        assert!(mv.is_some());
+        assert!(stats.time_stopped);
+        // The last iteration started before 1.4 x soft and ran to its end
+        assert!(now_ms() - start < 2500.0, "Soft limit ignored");
+    }
+
+    #[test]
+    fn test_search_progress_and_node_limit() {
+        let mut pos = Position::starting_position();
+        let mut tt = TranspositionTable::new(16);
+        let control = SearchControl::new(f64::INFINITY);
+        control.set_node_limit(20_000);
+        let mut reports = Vec::new();
+        let mut report = |stats: &SearchStats, pv: &[Move]| reports.push((stats.depth, stats.nodes, pv.to_vec()));
+        let (mv, _, stats) = search_with_progress(&mut pos, 0, &mut tt, &control, &SearchOptions::default(), Some(&mut report));
+        assert!(stats.time_stopped);
+        assert!(stats.nodes < 40_000, "Node limit ignored: {}", stats.nodes);
+
+        let depths: Vec<u8> = reports.iter().map(|r| r.0).collect();
+        assert_eq!(depths, (1..=stats.depth).collect::<Vec<_>>());
+        let (_, nodes, pv) = reports.last().unwrap();
+        assert!(*nodes <= stats.nodes);
+        assert_eq!(pv.first().copied(), mv);
+        assert!(pv.len() > 1);
+    }
+
+    #[test]
+    fn test_ponder_then_ponderhit() {
+        use std::sync::Arc;
+
+        let control = Arc::new(SearchControl::ponder());
+        let worker = {
+            let control = Arc::clone(&control);
+            std::thread::spawn(move || {
+                let mut pos = Position::starting_position();
+                let mut tt = TranspositionTable::new(16);
+                search_with_control(&mut pos, 0, &mut tt, &control, &SearchOptions::default())
+            })
+        };
+
+        // Pondering has no time limit: still running after a while
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(control.is_pondering());
+        assert!(!worker.is_finished());
+
+        // Ponderhit converts it into a normal 50ms search
+        control.ponderhit(50.0);
+        assert!(!control.is_pondering());
+        let (mv, _, stats) = worker.join().unwrap();
+        assert!(mv.is_some());
+        assert!(stats.time_stopped);
+    }
 }
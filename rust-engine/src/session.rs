@@ -0,0 +1,334 @@
+// Multi-Game Session
+// Many independent games in one WASM instance, for sites running several
+// boards at once (simuls, puzzle batches). Games are addressed by id and
+// share one transposition table and one opening book instead of each board
+// carrying its own ~6 MB table.
+//
+// The shared table is lent to a game for the length of a search. Games with
+// different rules or playing styles can't reuse each other's entries, so the
+// table is cleared when a search switches between them.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::book::{probe, read_book, BookEntry};
+use crate::error::{EngineError, ErrorKind};
+use crate::personality::Personality;
+use crate::tt::TranspositionTable;
+use crate::variant::{PromotionRules, Variant};
+use crate::{to_js, GameState, SearchReport};
+
+/// Table size of games inside a session, which search with the shared table
+const GAME_TT_POWER: u8 = 0;
+
+/// A book move for a session game
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BookMove {
+    pub uci: String,
+    pub san: String,
+    pub weight: u16,
+}
+
+/// Several games sharing a transposition table and an opening book
+#[wasm_bindgen]
+pub struct EngineSession {
+    games: BTreeMap<u32, GameState>,
+    next_id: u32,
+    tt: TranspositionTable,
+    /// Rules and style the shared table's entries were searched under
+    tt_owner: Option<(Variant, PromotionRules, Personality)>,
+    book: Vec<BookEntry>,
+}
+
+#[wasm_bindgen]
+impl EngineSession {
+    /// An empty session with a default-size table and no book
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        EngineSession {
+            games: BTreeMap::new(),
+            next_id: 1,
+            tt: TranspositionTable::default_wasm(),
+            tt_owner: None,
+            book: Vec::new(),
+        }
+    }
+
+    // =========================================================================
+    // GAMES
+    // =========================================================================
+
+    /// Start a game from the initial position. Returns its id.
+    pub fn new_game(&mut self) -> u32 {
+        self.add_game(GameState::new())
+    }
+
+    /// Start a game from a FEN. Returns its id.
+    pub fn new_game_from_fen(&mut self, fen: &str) -> Result<u32, EngineError> {
+        Ok(self.add_game(GameState::from_fen(fen)?))
+    }
+
+    /// Move a game into the session, dropping its own table. Returns its id.
+    pub fn add_game(&mut self, mut game: GameState) -> u32 {
+        game.tt = TranspositionTable::new(GAME_TT_POWER);
+        let id = self.next_id;
+        self.next_id += 1;
+        self.games.insert(id, game);
+        id
+    }
+
+    /// Take a game out of the session, giving it a table of its own again
+    pub fn take_game(&mut self, id: u32) -> Result<GameState, EngineError> {
+        let mut game = self.games.remove(&id).ok_or_else(|| unknown(id))?;
+        game.tt = TranspositionTable::default_wasm();
+        Ok(game)
+    }
+
+    /// Drop a game. Returns false if there was no game with this id.
+    pub fn remove_game(&mut self, id: u32) -> bool {
+        self.games.remove(&id).is_some()
+    }
+
+    /// Ids of the games in the session, oldest first
+    pub fn ids(&self) -> Vec<u32> {
+        self.games.keys().copied().collect()
+    }
+
+    /// Number of games in the session
+    pub fn game_count(&self) -> usize {
+        self.games.len()
+    }
+
+    /// FEN of a game's current position
+    pub fn fen(&self, id: u32) -> Result<String, EngineError> {
+        Ok(self.game(id)?.fen())
+    }
+
+    /// A game's status, as `GameState::status`
+    pub fn status(&self, id: u32) -> Result<String, EngineError> {
+        Ok(self.game(id)?.status())
+    }
+
+    /// Legal moves of a game in UCI notation
+    pub fn legal_moves(&self, id: u32) -> Result<Vec<JsValue>, EngineError> {
+        Ok(self.game(id)?.legal_moves())
+    }
+
+    /// Play a UCI move in a game
+    pub fn make_move_uci(&mut self, id: u32, uci: &str) -> Result<(), EngineError> {
+        self.game_mut(id)?.try_move_uci(uci)
+    }
+
+    /// Play a SAN move in a game
+    pub fn make_move_san(&mut self, id: u32, san: &str) -> Result<(), EngineError> {
+        self.game_mut(id)?.try_move_san(san)
+    }
+
+    /// Take back a game's last move. Returns its UCI, or "" at the start.
+    pub fn undo(&mut self, id: u32) -> Result<String, EngineError> {
+        Ok(self.game_mut(id)?.undo())
+    }
+
+    /// Set up a game from a FEN, clearing its history
+    pub fn load_fen(&mut self, id: u32, fen: &str) -> Result<(), EngineError> {
+        self.game_mut(id)?.try_load_fen(fen)
+    }
+
+    // =========================================================================
+    // SEARCH
+    // =========================================================================
+
+    /// Best move for a game: the heaviest book move if the position is in
+    /// the book, otherwise a search to `depth` with the shared table
+    pub fn best_move(&mut self, id: u32, depth: u8) -> Result<Option<String>, EngineError> {
+        if let Some(m) = self.book_moves_list(id)?.into_iter().next() {
+            return Ok(Some(m.uci));
+        }
+        self.with_tt(id, |game| game.best_move(depth))
+    }
+
+    /// Search a game for `max_ms` with the shared table, ignoring the book.
+    /// Returns {bestMove, score, depth, timeMs, nps, ...} as `GameState::search_timed`.
+    pub fn search_timed(&mut self, id: u32, max_ms: f64) -> Result<JsValue, EngineError> {
+        Ok(to_js(&self.search_timed_report(id, max_ms)?))
+    }
+
+    /// Replace the shared table with one of 2^`size_power` entries
+    pub fn set_tt_size(&mut self, size_power: u8) {
+        self.tt = TranspositionTable::new(size_power.min(24));
+        self.tt_owner = None;
+    }
+
+    /// Empty the shared table
+    pub fn clear_tt(&mut self) {
+        self.tt.clear();
+        self.tt_owner = None;
+    }
+
+    // =========================================================================
+    // BOOK
+    // =========================================================================
+
+    /// Use an opening book file (see `book::write_book`) for all games,
+    /// replacing any earlier one. Returns its number of entries.
+    pub fn load_book(&mut self, bytes: &[u8]) -> Result<usize, EngineError> {
+        let mut book = read_book(bytes).map_err(|e| EngineError::new(ErrorKind::BadBook, e))?;
+        // Probing needs the entries sorted by key
+        book.sort_by_key(|e| e.key);
+        self.book = book;
+        Ok(self.book.len())
+    }
+
+    /// Stop using the opening book
+    pub fn clear_book(&mut self) {
+        self.book.clear();
+    }
+
+    /// Entries in the opening book
+    pub fn book_size(&self) -> usize {
+        self.book.len()
+    }
+
+    /// Book moves for a game's position as [{uci, san, weight}], heaviest first
+    pub fn book_moves(&self, id: u32) -> Result<JsValue, EngineError> {
+        Ok(to_js(&self.book_moves_list(id)?))
+    }
+}
+
+impl EngineSession {
+    /// A game of the session
+    pub fn game(&self, id: u32) -> Result<&GameState, EngineError> {
+        self.games.get(&id).ok_or_else(|| unknown(id))
+    }
+
+    /// A game of the session, to change
+    pub fn game_mut(&mut self, id: u32) -> Result<&mut GameState, EngineError> {
+        self.games.get_mut(&id).ok_or_else(|| unknown(id))
+    }
+
+    /// `book_moves` without the conversion to JS objects
+    pub fn book_moves_list(&self, id: u32) -> Result<Vec<BookMove>, EngineError> {
+        let pos = &self.game(id)?.position;
+        Ok(probe(&self.book, pos)
+            .into_iter()
+            .map(|(m, weight)| BookMove { uci: m.to_uci(), san: m.to_san(pos), weight })
+            .collect())
+    }
+
+    /// `search_timed` without the conversion to a JS object
+    pub fn search_timed_report(&mut self, id: u32, max_ms: f64) -> Result<SearchReport, EngineError> {
+        self.with_tt(id, |game| game.search_timed_report(max_ms))
+    }
+
+    /// Run `f` on a game with the shared table lent to it, clearing the
+    /// table first if it was filled under other rules or another style
+    fn with_tt<R>(&mut self, id: u32, f: impl FnOnce(&mut GameState) -> R) -> Result<R, EngineError> {
+        let game = self.games.get_mut(&id).ok_or_else(|| unknown(id))?;
+        let owner = (game.position.variant(), game.position.promotion_rules(), game.personality);
+        if self.tt_owner != Some(owner) {
+            self.tt.clear();
+            self.tt_owner = Some(owner);
+        }
+        std::mem::swap(&mut game.tt, &mut self.tt);
+        let result = f(game);
+        std::mem::swap(&mut game.tt, &mut self.tt);
+        Ok(result)
+    }
+}
+
+impl Default for EngineSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unknown(id: u32) -> EngineError {
+    EngineError::new(ErrorKind::UnknownGame, format!("No game with id {}", id)).with_field(id.to_string())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::{write_book, BookBuilder, Weighting};
+    use crate::position::Position;
+
+    #[test]
+    fn test_games_are_independent() {
+        let mut session = EngineSession::new();
+        let a = session.new_game();
+        let b = session.new_game_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(session.ids(), [a, b]);
+
+        session.make_move_uci(a, "e2e4").unwrap();
+        session.make_move_san(b, "e4").unwrap();
+        assert_eq!(session.fen(a).unwrap(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(session.fen(b).unwrap(), "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+        assert_eq!(session.make_move_uci(a, "e2e4").unwrap_err().kind, ErrorKind::IllegalMove);
+        assert_eq!(session.undo(b).unwrap(), "e2e4");
+        assert_eq!(session.game(a).unwrap().move_count(), 1);
+
+        let err = session.fen(99).unwrap_err();
+        assert_eq!((err.kind, err.field.as_deref()), (ErrorKind::UnknownGame, Some("99")));
+
+        let game = session.take_game(a).unwrap();
+        assert_eq!(game.move_count(), 1);
+        assert!(!session.remove_game(a));
+        assert!(session.remove_game(b));
+        assert_eq!(session.game_count(), 0);
+        // Ids aren't reused
+        assert_eq!(session.add_game(game), b + 1);
+    }
+
+    #[test]
+    fn test_shared_table_search() {
+        let mut session = EngineSession::new();
+        let mate = session.new_game_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let other = session.new_game();
+        assert_eq!(session.best_move(mate, 3).unwrap().as_deref(), Some("a1a8"));
+        assert!(session.tt.stores > 0);
+        assert!(session.best_move(other, 2).unwrap().is_some());
+        // The game's own table is the small one again
+        assert_eq!(session.game(mate).unwrap().tt.capacity(), TranspositionTable::new(GAME_TT_POWER).capacity());
+
+        // Another variant empties the table before searching
+        session.game_mut(other).unwrap().set_variant("early-promotion");
+        let report = session.search_timed_report(other, 50.0).unwrap();
+        assert!(report.best_move.is_some());
+        assert_eq!(session.tt_owner.map(|(v, _, _)| v), Some(Variant::EarlyPromotion));
+    }
+
+    #[test]
+    fn test_book_moves() {
+        let start = Position::starting_position();
+        let mut pos = start.clone();
+        let e4 = crate::san::parse_san(&pos, "e4").unwrap();
+        pos.make_move(e4);
+        let e5 = crate::san::parse_san(&pos, "e5").unwrap();
+        let mut builder = BookBuilder::new(4);
+        builder.add_game(&start, &[e4, e5], "1-0");
+        let bytes = write_book(&builder.build(1, Weighting::Count));
+
+        let mut session = EngineSession::new();
+        assert_eq!(session.load_book(&bytes).unwrap(), 2);
+        assert_eq!(session.load_book(&bytes[..5]).unwrap_err().kind, ErrorKind::BadBook);
+        assert_eq!(session.book_size(), 2);
+
+        let a = session.new_game();
+        let b = session.new_game();
+        assert_eq!(session.book_moves_list(a).unwrap(), [BookMove { uci: "e2e4".into(), san: "e4".into(), weight: 1 }]);
+        assert_eq!(session.best_move(a, 1).unwrap().as_deref(), Some("e2e4"));
+        session.make_move_uci(b, "e2e4").unwrap();
+        assert_eq!(session.best_move(b, 1).unwrap().as_deref(), Some("e7e5"));
+        session.make_move_uci(b, "e7e5").unwrap();
+        assert!(session.book_moves_list(b).unwrap().is_empty());
+
+        session.clear_book();
+        assert!(session.book_moves_list(a).unwrap().is_empty());
+    }
+}
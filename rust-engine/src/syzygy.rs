@@ -0,0 +1,399 @@
+// Syzygy Tablebase Module
+// WDL/DTZ probing of Syzygy endgame tablebases for the native binaries.
+// Table decoding is done by pyrrhic-rs; this module adapts it to our bitboards,
+// owns the process-wide handle and caches WDL results per thread.
+
+use crate::attacks::{king_attacks, knight_attacks, pawn_attacks};
+use crate::eval::{Score, DRAW_SCORE};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::movegen::generate_legal_moves;
+use crate::position::Position;
+use crate::types::{CastlingRights, Color, Move, PieceType, Square};
+use crate::variant::{PromotionRules, Variant};
+use crate::bitboard::Bitboard;
+use pyrrhic_rs::{DtzProbeValue, EngineAdapter, TableBases, WdlProbeResult};
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::RwLock;
+
+/// Score of a tablebase win at the root. Below the mate range (> 29000), above any eval.
+pub const TB_WIN_SCORE: Score = 20000;
+
+/// Default minimum remaining depth for probing positions with the maximum piece count.
+pub const DEFAULT_PROBE_DEPTH: u8 = 1;
+
+/// Probe cache size: 2^16 entries (~1 MB) per thread.
+const CACHE_SIZE_POWER: u32 = 16;
+
+/// Separator between directories in a tablebase path (as in the UCI SyzygyPath option)
+#[cfg(windows)]
+pub const PATH_SEPARATOR: char = ';';
+#[cfg(not(windows))]
+pub const PATH_SEPARATOR: char = ':';
+
+// =============================================================================
+// ENGINE ADAPTER (pyrrhic-rs uses our attack tables for its move generation)
+// =============================================================================
+
+#[derive(Clone, Copy)]
+struct Adapter;
+
+impl EngineAdapter for Adapter {
+    fn pawn_attacks(color: pyrrhic_rs::Color, square: u64) -> u64 {
+        pawn_attacks(Square::new(square as u8), color == pyrrhic_rs::Color::White).0
+    }
+    fn knight_attacks(square: u64) -> u64 {
+        knight_attacks(Square::new(square as u8)).0
+    }
+    fn bishop_attacks(square: u64, occupied: u64) -> u64 {
+        bishop_attacks(Square::new(square as u8), Bitboard(occupied)).0
+    }
+    fn rook_attacks(square: u64, occupied: u64) -> u64 {
+        rook_attacks(Square::new(square as u8), Bitboard(occupied)).0
+    }
+    fn queen_attacks(square: u64, occupied: u64) -> u64 {
+        queen_attacks(Square::new(square as u8), Bitboard(occupied)).0
+    }
+    fn king_attacks(square: u64) -> u64 {
+        king_attacks(Square::new(square as u8)).0
+    }
+}
+
+// =============================================================================
+// WDL RESULT
+// =============================================================================
+
+/// Win/draw/loss from the side to move's point of view.
+/// Cursed wins and blessed losses are decided by the 50-move rule and count as draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_probe(result: WdlProbeResult) -> Wdl {
+        match result {
+            WdlProbeResult::Loss => Wdl::Loss,
+            WdlProbeResult::BlessedLoss => Wdl::BlessedLoss,
+            WdlProbeResult::Draw => Wdl::Draw,
+            WdlProbeResult::CursedWin => Wdl::CursedWin,
+            WdlProbeResult::Win => Wdl::Win,
+        }
+    }
+
+    /// Search score for this result at `ply` from the root. Wins found closer
+    /// to the root score higher; 50-move-rule results sit just off the draw score.
+    pub fn to_score(self, ply: u8) -> Score {
+        match self {
+            Wdl::Win => TB_WIN_SCORE - ply as Score,
+            Wdl::CursedWin => DRAW_SCORE + 1,
+            Wdl::Draw => DRAW_SCORE,
+            Wdl::BlessedLoss => DRAW_SCORE - 1,
+            Wdl::Loss => -TB_WIN_SCORE + ply as Score,
+        }
+    }
+}
+
+// =============================================================================
+// GLOBAL HANDLE
+// =============================================================================
+
+/// Loaded tables. pyrrhic-rs allows a single instance per process; WDL probes are
+/// thread-safe (read lock), root DTZ probes are not (write lock).
+static TABLEBASES: RwLock<Option<TableBases<Adapter>>> = RwLock::new(None);
+
+/// Largest piece count covered by the loaded tables (0 = none loaded)
+static MAX_PIECES: AtomicU32 = AtomicU32::new(0);
+
+/// Bumped on every (re)load so per-thread caches drop stale results
+static GENERATION: AtomicU32 = AtomicU32::new(0);
+
+static PROBE_DEPTH: AtomicU8 = AtomicU8::new(DEFAULT_PROBE_DEPTH);
+
+/// Does any directory in `path` contain a WDL table?
+fn has_tables(path: &str) -> bool {
+    path.split(PATH_SEPARATOR).filter(|dir| !dir.is_empty()).any(|dir| {
+        std::fs::read_dir(Path::new(dir)).is_ok_and(|entries| {
+            entries.flatten().any(|e| e.path().extension().is_some_and(|ext| ext == "rtbw"))
+        })
+    })
+}
+
+/// Load the tables found in `path` (directories separated by `PATH_SEPARATOR`),
+/// replacing any loaded before. Returns the largest piece count available.
+/// An empty path unloads the tables.
+pub fn init(path: &str) -> Result<u32, String> {
+    let mut tables = TABLEBASES.write().unwrap_or_else(|e| e.into_inner());
+    // Free the old tables before loading: only one instance may exist
+    *tables = None;
+    MAX_PIECES.store(0, Ordering::Relaxed);
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+
+    if path.is_empty() {
+        return Ok(0);
+    }
+    if !has_tables(path) {
+        return Err(format!("No Syzygy tables (.rtbw) found in '{}'", path));
+    }
+
+    let tb = TableBases::<Adapter>::new(path)
+        .map_err(|e| format!("Failed to load Syzygy tables from '{}': {:?}", path, e))?;
+    let max_pieces = tb.max_pieces();
+    *tables = Some(tb);
+    MAX_PIECES.store(max_pieces, Ordering::Relaxed);
+    Ok(max_pieces)
+}
+
+/// Largest piece count that can be probed (0 when no tables are loaded).
+#[inline]
+pub fn max_pieces() -> u32 {
+    MAX_PIECES.load(Ordering::Relaxed)
+}
+
+/// Minimum remaining depth at which interior nodes with the maximum piece count are probed.
+/// Positions with fewer pieces are probed at any depth.
+pub fn probe_depth() -> u8 {
+    PROBE_DEPTH.load(Ordering::Relaxed)
+}
+
+pub fn set_probe_depth(depth: u8) {
+    PROBE_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+// =============================================================================
+// PROBE CACHE
+// =============================================================================
+
+/// Cached value for a position that is not in the tables (or failed to probe)
+const NO_RESULT: u8 = u8::MAX;
+
+#[derive(Clone, Copy, Default)]
+struct CacheEntry {
+    hash: u64,
+    result: u8,
+}
+
+/// Always-replace cache of WDL probes keyed by Zobrist hash. Table lookups
+/// decompress blocks from disk-backed memory, so repeated probes are worth caching.
+struct ProbeCache {
+    entries: Vec<CacheEntry>,
+    mask: usize,
+    generation: u32,
+}
+
+impl ProbeCache {
+    fn new(size_power: u32) -> Self {
+        let capacity = 1usize << size_power;
+        ProbeCache {
+            entries: vec![CacheEntry::default(); capacity],
+            mask: capacity - 1,
+            generation: 0,
+        }
+    }
+
+    /// Cached result: `Some(None)` for a known miss, `None` if not cached.
+    fn probe(&self, hash: u64) -> Option<Option<Wdl>> {
+        let entry = self.entries[(hash as usize) & self.mask];
+        if entry.hash != hash || hash == 0 {
+            return None;
+        }
+        Some(match entry.result {
+            0 => Some(Wdl::Loss),
+            1 => Some(Wdl::BlessedLoss),
+            2 => Some(Wdl::Draw),
+            3 => Some(Wdl::CursedWin),
+            4 => Some(Wdl::Win),
+            _ => None,
+        })
+    }
+
+    fn store(&mut self, hash: u64, wdl: Option<Wdl>) {
+        let result = wdl.map_or(NO_RESULT, |w| w as u8);
+        self.entries[(hash as usize) & self.mask] = CacheEntry { hash, result };
+    }
+
+    /// Drop everything if the tables were reloaded since the last use.
+    fn sync_generation(&mut self) {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if self.generation != generation {
+            self.entries.fill(CacheEntry::default());
+            self.generation = generation;
+        }
+    }
+}
+
+thread_local! {
+    static PROBE_CACHE: RefCell<ProbeCache> = RefCell::new(ProbeCache::new(CACHE_SIZE_POWER));
+}
+
+// =============================================================================
+// PROBING
+// =============================================================================
+
+/// Can `pos` be looked up at all? Tables assume no castling rights, one
+/// king a side and standard promotions.
+#[inline]
+fn probeable(pos: &Position) -> bool {
+    let pieces = pos.occupied().count();
+    pieces <= max_pieces()
+        && pos.castling_rights() == CastlingRights::NONE
+        && pos.variant() == Variant::Standard
+        && pos.promotion_rules() == PromotionRules::STANDARD
+        && !pos.has_extra_kings(Color::White)
+        && !pos.has_extra_kings(Color::Black)
+}
+
+/// Piece-type bitboard for both colors
+#[inline]
+fn both(pos: &Position, piece: PieceType) -> u64 {
+    (pos.pieces(Color::White, piece) | pos.pieces(Color::Black, piece)).0
+}
+
+fn probe_wdl_uncached(pos: &Position) -> Option<Wdl> {
+    let tables = TABLEBASES.read().unwrap_or_else(|e| e.into_inner());
+    let tb = tables.as_ref()?;
+    let result = tb.probe_wdl(
+        pos.occupied_by(Color::White).0,
+        pos.occupied_by(Color::Black).0,
+        both(pos, PieceType::King),
+        both(pos, PieceType::Queen),
+        both(pos, PieceType::Rook),
+        both(pos, PieceType::Bishop),
+        both(pos, PieceType::Knight),
+        both(pos, PieceType::Pawn),
+        pos.en_passant_square().map_or(0, |sq| sq.0 as u32),
+        pos.side_to_move() == Color::White,
+    );
+    result.ok().map(Wdl::from_probe)
+}
+
+/// WDL value of `pos` for the side to move, if it is covered by the loaded tables.
+/// The result ignores the halfmove clock, so it is only exact right after a
+/// capture or pawn move (halfmove clock 0).
+pub fn probe_wdl(pos: &Position) -> Option<Wdl> {
+    if !probeable(pos) {
+        return None;
+    }
+    let hash = pos.hash();
+    PROBE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.sync_generation();
+        if let Some(cached) = cache.probe(hash) {
+            return cached;
+        }
+        let wdl = probe_wdl_uncached(pos);
+        cache.store(hash, wdl);
+        wdl
+    })
+}
+
+/// Best move at the root by DTZ (fastest zeroing move that keeps the WDL result,
+/// respecting the 50-move rule) and its score. `None` if `pos` is not in the
+/// tables or has no legal moves.
+pub fn probe_root(pos: &mut Position) -> Option<(Move, Score)> {
+    if !probeable(pos) {
+        return None;
+    }
+    let result = {
+        let tables = TABLEBASES.write().unwrap_or_else(|e| e.into_inner());
+        let tb = tables.as_ref()?;
+        tb.probe_root(
+            pos.occupied_by(Color::White).0,
+            pos.occupied_by(Color::Black).0,
+            both(pos, PieceType::King),
+            both(pos, PieceType::Queen),
+            both(pos, PieceType::Rook),
+            both(pos, PieceType::Bishop),
+            both(pos, PieceType::Knight),
+            both(pos, PieceType::Pawn),
+            pos.halfmove_clock() as u32,
+            pos.en_passant_square().map_or(0, |sq| sq.0 as u32),
+            pos.side_to_move() == Color::White,
+        )
+        .ok()?
+    };
+
+    let DtzProbeValue::DtzResult(root) = result.root else {
+        return None;
+    };
+    let promotion = match root.promotion {
+        pyrrhic_rs::Piece::Queen => Some(PieceType::Queen),
+        pyrrhic_rs::Piece::Rook => Some(PieceType::Rook),
+        pyrrhic_rs::Piece::Bishop => Some(PieceType::Bishop),
+        pyrrhic_rs::Piece::Knight => Some(PieceType::Knight),
+        _ => None,
+    };
+    let moves = generate_legal_moves(pos);
+    let mv = moves.iter().copied().find(|m| {
+        m.from().0 == root.from_square && m.to().0 == root.to_square && m.promotion_piece() == promotion
+    })?;
+    Some((mv, Wdl::from_probe(root.wdl).to_score(0)))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wdl_scores_are_ordered() {
+        let scores: Vec<Score> = [Wdl::Loss, Wdl::BlessedLoss, Wdl::Draw, Wdl::CursedWin, Wdl::Win]
+            .iter()
+            .map(|w| w.to_score(3))
+            .collect();
+        assert!(scores.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(Wdl::Win.to_score(2), -Wdl::Loss.to_score(2));
+        // Prefer the win closer to the root
+        assert!(Wdl::Win.to_score(1) > Wdl::Win.to_score(5));
+    }
+
+    #[test]
+    fn test_probe_cache() {
+        let mut cache = ProbeCache::new(4);
+        assert_eq!(cache.probe(0x1234), None);
+        cache.store(0x1234, Some(Wdl::CursedWin));
+        assert_eq!(cache.probe(0x1234), Some(Some(Wdl::CursedWin)));
+        // Misses are cached too
+        cache.store(0x5678, None);
+        assert_eq!(cache.probe(0x5678), Some(None));
+        // Hash 0 never hits
+        cache.store(0, Some(Wdl::Win));
+        assert_eq!(cache.probe(0), None);
+    }
+
+    #[test]
+    fn test_adapter_matches_engine_attacks() {
+        let occupied = 0x0000_0010_0800_0000u64;
+        assert_eq!(Adapter::knight_attacks(1), knight_attacks(Square::new(1)).0);
+        assert_eq!(Adapter::king_attacks(36), king_attacks(Square::new(36)).0);
+        assert_eq!(Adapter::rook_attacks(27, occupied), rook_attacks(Square::new(27), Bitboard(occupied)).0);
+        assert_eq!(Adapter::bishop_attacks(18, occupied), bishop_attacks(Square::new(18), Bitboard(occupied)).0);
+        assert_eq!(
+            Adapter::queen_attacks(18, occupied),
+            Adapter::rook_attacks(18, occupied) | Adapter::bishop_attacks(18, occupied)
+        );
+        assert_eq!(Adapter::pawn_attacks(pyrrhic_rs::Color::White, 12), pawn_attacks(Square::new(12), true).0);
+        assert_eq!(Adapter::pawn_attacks(pyrrhic_rs::Color::Black, 52), pawn_attacks(Square::new(52), false).0);
+    }
+
+    #[test]
+    fn test_missing_tables() {
+        let dir = std::env::temp_dir().join("chess-engine-no-syzygy");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(init(dir.to_str().unwrap()).is_err());
+        assert_eq!(max_pieces(), 0);
+
+        // Nothing loaded: every probe misses
+        let mut pos = Position::from_fen("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(probe_wdl(&pos), None);
+        assert_eq!(probe_root(&mut pos), None);
+    }
+}
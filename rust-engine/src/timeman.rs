@@ -0,0 +1,85 @@
+// Time Management
+// Turns a chess clock (time left, increment, moves to the next time control)
+// into two limits for one move. The search finishes iterations until the soft
+// limit, stretched while the best move keeps changing and shrunk once it has
+// settled; it abandons an iteration at the hard limit, whatever happens.
+
+/// Time kept in hand for GUI and transport overhead, in ms
+pub const MOVE_OVERHEAD_MS: f64 = 50.0;
+/// Moves the remaining time is spread over in sudden death
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Hard limit as a multiple of the soft one
+const HARD_FACTOR: f64 = 5.0;
+/// Most of the usable time one move may take
+const MAX_SHARE: f64 = 0.8;
+
+/// Limits for one move, in ms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeLimits {
+    /// Start no iteration past this (scaled by `stability_scale`)
+    pub soft_ms: f64,
+    /// Abort the running iteration here
+    pub hard_ms: f64,
+}
+
+impl TimeLimits {
+    /// Limits for a side with `time_ms` on its clock, `inc_ms` added per move
+    /// and `moves_to_go` moves to the next time control (None = sudden death)
+    pub fn for_clock(time_ms: f64, inc_ms: f64, moves_to_go: Option<u32>) -> Self {
+        let usable = (time_ms - MOVE_OVERHEAD_MS).max(time_ms * 0.5).max(1.0);
+        let moves = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, DEFAULT_MOVES_TO_GO) as f64;
+        let hard_cap = if moves_to_go == Some(1) { usable } else { usable * MAX_SHARE };
+        let soft = (usable / moves + inc_ms.max(0.0) * 0.75).min(hard_cap);
+        TimeLimits { soft_ms: soft.max(1.0), hard_ms: (soft * HARD_FACTOR).min(hard_cap).max(1.0) }
+    }
+}
+
+/// Factor on the soft limit after `stable` iterations in a row ended with
+/// the same best move: more time while it keeps changing, less once settled
+pub fn stability_scale(stable: u32) -> f64 {
+    match stable {
+        0 => 1.4,
+        1 => 1.1,
+        2 => 0.9,
+        3 | 4 => 0.7,
+        _ => 0.5,
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_limits() {
+        // Sudden death: a thirtieth of the clock, plus most of the increment
+        let limits = TimeLimits::for_clock(60_050.0, 1000.0, None);
+        assert_eq!(limits.soft_ms, 2000.0 + 750.0);
+        assert_eq!(limits.hard_ms, 2750.0 * HARD_FACTOR);
+
+        // Never more than the clock allows, even with a large increment
+        let limits = TimeLimits::for_clock(1000.0, 10_000.0, None);
+        assert!(limits.hard_ms <= 950.0 * MAX_SHARE);
+        assert!(limits.soft_ms <= limits.hard_ms);
+
+        // Last move before the time control may use everything but the overhead
+        let limits = TimeLimits::for_clock(5050.0, 0.0, Some(1));
+        assert_eq!((limits.soft_ms, limits.hard_ms), (5000.0, 5000.0));
+        let limits = TimeLimits::for_clock(10_050.0, 0.0, Some(10));
+        assert_eq!(limits.soft_ms, 1000.0);
+
+        // Almost flagged: still some time to find a move
+        let limits = TimeLimits::for_clock(20.0, 0.0, None);
+        assert!(limits.hard_ms >= 1.0 && limits.hard_ms < 20.0);
+    }
+
+    #[test]
+    fn test_stability_scale() {
+        assert!(stability_scale(0) > 1.0);
+        assert!((1..8).all(|n| stability_scale(n) <= stability_scale(n - 1)));
+    }
+}
@@ -1,6 +1,15 @@
 // Transposition Table Module
 // Array-based hash table for caching search results.
-// Uses Zobrist hash as key, always-replace policy.
+// Uses Zobrist hash as key, depth-preferred replacement with generation aging.
+//
+// A table is either private to one search or a handle on a `SharedTable` that
+// several threads search with at once (WASM threads over a SharedArrayBuffer,
+// or native threads). Shared entries are packed into two atomic words, the
+// data and the hash XOR the data, so a torn write fails the hash check and
+// reads as a miss instead of a wrong entry.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 use crate::eval::Score;
 use crate::types::Move;
@@ -33,6 +42,8 @@ pub struct TTEntry {
     pub flag: TTFlag,
     /// Best move found (for move ordering / PV recovery).
     pub best_move: Option<Move>,
+    /// Search generation this entry was written (or last hit) in.
+    pub generation: u8,
 }
 
 impl Default for TTEntry {
@@ -43,21 +54,52 @@ impl Default for TTEntry {
             score: 0,
             flag: TTFlag::Exact,
             best_move: None,
+            generation: 0,
         }
     }
 }
 
+// =============================================================================
+// TT BUCKET
+// =============================================================================
+
+/// Number of entries sharing one hash index.
+pub const BUCKET_SIZE: usize = 4;
+
+/// Weight of one generation of age in the replacement score (in plies of depth).
+const AGE_WEIGHT: i32 = 8;
+
+/// A group of entries addressed by the same index.
+/// Colliding positions can coexist instead of evicting each other.
+#[derive(Clone, Copy, Default)]
+struct TTBucket {
+    entries: [TTEntry; BUCKET_SIZE],
+}
+
 // =============================================================================
 // TRANSPOSITION TABLE
 // =============================================================================
 
+/// Where a table's entries live
+enum Storage {
+    /// Buckets of this table only
+    Local(Vec<TTBucket>),
+    /// Buckets other threads search with too
+    Shared(Arc<SharedTable>),
+}
+
 /// Fixed-size transposition table.
-/// Uses `hash % capacity` as index (always-replace).
-/// For WASM, default to 2^18 entries (~5 MB) to stay within limits.
+/// Uses `hash % buckets` as index into 4-entry buckets. When a bucket is full,
+/// the entry with the lowest `depth - 8 * age` is replaced.
+/// For WASM, default to 2^18 entries (~6 MB) to stay within limits.
 pub struct TranspositionTable {
-    entries: Vec<TTEntry>,
+    storage: Storage,
+    /// Total number of entries (buckets * BUCKET_SIZE).
     capacity: usize,
-    /// Stats
+    /// Current search generation. Bumped by `new_search()`; entries written in
+    /// an older generation are considered stale and may be replaced freely.
+    generation: u8,
+    /// Stats (per handle for a shared table)
     pub hits: u64,
     pub misses: u64,
     pub stores: u64,
@@ -68,10 +110,21 @@ impl TranspositionTable {
     /// Create a new TT with the given number of entries.
     /// `size_power` is the power of 2 (e.g., 18 = 2^18 = 262144 entries).
     pub fn new(size_power: u8) -> Self {
-        let capacity = 1usize << size_power;
+        let capacity = (1usize << size_power).max(BUCKET_SIZE);
+        Self::with_storage(Storage::Local(vec![TTBucket::default(); capacity / BUCKET_SIZE]), capacity, 0)
+    }
+
+    /// A handle on a shared table, in the table's current generation
+    pub fn attach(table: Arc<SharedTable>) -> Self {
+        let (capacity, generation) = (table.capacity(), table.generation());
+        Self::with_storage(Storage::Shared(table), capacity, generation)
+    }
+
+    fn with_storage(storage: Storage, capacity: usize, generation: u8) -> Self {
         TranspositionTable {
-            entries: vec![TTEntry::default(); capacity],
+            storage,
             capacity,
+            generation,
             hits: 0,
             misses: 0,
             stores: 0,
@@ -79,57 +132,97 @@ impl TranspositionTable {
         }
     }
 
-    /// Default table size for WASM (2^18 = 262144 entries, ~5 MB).
+    /// The shared table this is a handle on, if any
+    pub fn shared_table(&self) -> Option<&Arc<SharedTable>> {
+        match &self.storage {
+            Storage::Local(_) => None,
+            Storage::Shared(table) => Some(table),
+        }
+    }
+
+    /// Default table size for WASM (2^18 = 262144 entries, ~6 MB).
     pub fn default_wasm() -> Self {
         Self::new(18)
     }
 
+    /// Start a new search: bump the generation so entries from previous
+    /// searches become preferred replacement victims.
+    /// Call once per root search (not per iterative-deepening iteration).
+    /// A shared table's generation is bumped by `SharedTable::new_search`,
+    /// once for all its threads; here a handle only catches up with it.
+    pub fn new_search(&mut self) {
+        self.generation = match &self.storage {
+            Storage::Local(_) => self.generation.wrapping_add(1),
+            Storage::Shared(table) => table.generation(),
+        };
+    }
+
+    /// Current search generation.
+    pub fn generation(&self) -> u8 {
+        self.generation
+    }
+
+    /// Total number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    fn bucket_index(&self, hash: u64) -> usize {
+        (hash as usize) & (self.capacity / BUCKET_SIZE - 1)
+    }
+
     /// Probe the TT for a matching entry.
     /// Returns Some(entry) if the hash matches exactly.
-    pub fn probe(&mut self, hash: u64) -> Option<&TTEntry> {
-        let idx = (hash as usize) & (self.capacity - 1);
-        let entry = &self.entries[idx];
-        if entry.hash == hash && entry.hash != 0 {
+    /// A hit refreshes the entry's generation so still-useful knowledge survives.
+    pub fn probe(&mut self, hash: u64) -> Option<TTEntry> {
+        let idx = self.bucket_index(hash);
+        let generation = self.generation;
+        let found = match &mut self.storage {
+            Storage::Local(buckets) => buckets[idx].entries.iter_mut().find(|e| e.hash == hash && e.hash != 0).map(|entry| {
+                entry.generation = generation;
+                *entry
+            }),
+            Storage::Shared(table) => table.probe(idx, hash, generation),
+        };
+        if found.is_some() {
             self.hits += 1;
-            Some(entry)
         } else {
             self.misses += 1;
-            None
         }
+        found
     }
 
     /// Store an entry in the TT.
-    /// Always-replace: overwrites whatever was in the slot.
-    /// Depth-preferred variant: only overwrite if new depth >= existing depth.
+    /// Slot choice within the bucket: the same position if present, else an
+    /// empty slot, else the entry with the lowest `depth - 8 * age` (so stale
+    /// and shallow entries go first).
     pub fn store(&mut self, hash: u64, depth: u8, score: Score, flag: TTFlag, best_move: Option<Move>) {
-        let idx = (hash as usize) & (self.capacity - 1);
-        let existing = &self.entries[idx];
-
-        // Track collisions (different position in same slot)
-        if existing.hash != 0 && existing.hash != hash {
-            self.collisions += 1;
+        let idx = self.bucket_index(hash);
+        let entry = TTEntry { hash, depth, score, flag, best_move, generation: self.generation };
+        let (slot, collision) = match &self.storage {
+            Storage::Local(buckets) => choose_slot(&buckets[idx].entries, hash, self.generation),
+            Storage::Shared(table) => choose_slot(&table.load_bucket(idx), hash, self.generation),
+        };
+        match &mut self.storage {
+            Storage::Local(buckets) => buckets[idx].entries[slot] = entry,
+            Storage::Shared(table) => table.write(idx * BUCKET_SIZE + slot, &entry),
         }
-
-        // Depth-preferred: only replace if new search is at least as deep
-        if existing.hash != 0 && existing.hash != hash && existing.depth > depth {
-            return; // Keep higher-depth entry
+        // Track collisions (different position evicted from a full bucket)
+        if collision {
+            self.collisions += 1;
         }
-
-        self.entries[idx] = TTEntry {
-            hash,
-            depth,
-            score,
-            flag,
-            best_move,
-        };
         self.stores += 1;
     }
 
-    /// Clear the entire table (e.g., for a new game).
+    /// Clear the entire table (e.g., for a new game). Clearing a shared table
+    /// clears it for every thread.
     pub fn clear(&mut self) {
-        for entry in self.entries.iter_mut() {
-            *entry = TTEntry::default();
+        match &mut self.storage {
+            Storage::Local(buckets) => buckets.iter_mut().for_each(|bucket| *bucket = TTBucket::default()),
+            Storage::Shared(table) => table.clear(),
         }
+        self.generation = 0;
         self.hits = 0;
         self.misses = 0;
         self.stores = 0;
@@ -138,7 +231,10 @@ impl TranspositionTable {
 
     /// Table utilization (fraction of slots filled).
     pub fn utilization(&self) -> f64 {
-        let filled = self.entries.iter().filter(|e| e.hash != 0).count();
+        let filled = match &self.storage {
+            Storage::Local(buckets) => buckets.iter().flat_map(|b| b.entries.iter()).filter(|e| e.hash != 0).count(),
+            Storage::Shared(table) => table.filled(),
+        };
         filled as f64 / self.capacity as f64
     }
 
@@ -149,6 +245,171 @@ impl TranspositionTable {
     }
 }
 
+/// Slot of a bucket to store `hash` in, and whether that evicts another
+/// position: the same position if present, else an empty slot, else the
+/// entry with the lowest `depth - 8 * age`
+fn choose_slot(entries: &[TTEntry; BUCKET_SIZE], hash: u64, generation: u8) -> (usize, bool) {
+    if let Some(i) = entries.iter().position(|e| e.hash == hash) {
+        return (i, false);
+    }
+    if let Some(i) = entries.iter().position(|e| e.hash == 0) {
+        return (i, false);
+    }
+    let mut victim = 0;
+    let mut victim_score = i32::MAX;
+    for (i, e) in entries.iter().enumerate() {
+        let age = generation.wrapping_sub(e.generation);
+        let worth = e.depth as i32 - AGE_WEIGHT * age as i32;
+        if worth < victim_score {
+            victim = i;
+            victim_score = worth;
+        }
+    }
+    (victim, true)
+}
+
+// =============================================================================
+// SHARED TABLE
+// =============================================================================
+
+/// Buckets searched by several threads at once. Threads each hold their own
+/// `TranspositionTable` handle (`attach`), with their own statistics.
+pub struct SharedTable {
+    /// Two words per entry: the hash XOR the data, then the data
+    words: Box<[AtomicU64]>,
+    capacity: usize,
+    generation: AtomicU8,
+    id: u32,
+}
+
+/// Shared tables by id, so threads that can only pass numbers (WASM workers)
+/// can open the same table
+static SHARED_TABLES: Mutex<Vec<(u32, Weak<SharedTable>)>> = Mutex::new(Vec::new());
+static NEXT_SHARED_ID: AtomicU32 = AtomicU32::new(1);
+
+impl SharedTable {
+    /// Bytes per entry: two atomic words
+    pub const ENTRY_BYTES: usize = 16;
+
+    /// A table of 2^`size_power` entries, `ENTRY_BYTES` each
+    pub fn new(size_power: u8) -> Arc<Self> {
+        let capacity = (1usize << size_power).max(BUCKET_SIZE);
+        let table = Arc::new(SharedTable {
+            words: (0..capacity * 2).map(|_| AtomicU64::new(0)).collect(),
+            capacity,
+            generation: AtomicU8::new(0),
+            id: NEXT_SHARED_ID.fetch_add(1, Ordering::Relaxed),
+        });
+        let mut tables = SHARED_TABLES.lock().unwrap_or_else(|e| e.into_inner());
+        tables.retain(|(_, t)| t.strong_count() > 0);
+        tables.push((table.id, Arc::downgrade(&table)));
+        table
+    }
+
+    /// The live table with this id
+    pub fn open(id: u32) -> Option<Arc<Self>> {
+        let tables = SHARED_TABLES.lock().unwrap_or_else(|e| e.into_inner());
+        tables.iter().find(|(i, _)| *i == id).and_then(|(_, t)| t.upgrade())
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Start a new search for all threads (see `TranspositionTable::new_search`)
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Empty the table and restart its generations
+    pub fn clear(&self) {
+        self.words.iter().for_each(|w| w.store(0, Ordering::Relaxed));
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Entries in use
+    pub fn filled(&self) -> usize {
+        self.words.chunks_exact(2).filter(|e| e[1].load(Ordering::Relaxed) != 0).count()
+    }
+
+    /// Entry `index`, empty if it was torn by a concurrent write
+    fn load(&self, index: usize) -> TTEntry {
+        let key = self.words[index * 2].load(Ordering::Relaxed);
+        let data = self.words[index * 2 + 1].load(Ordering::Relaxed);
+        if data == 0 {
+            return TTEntry::default();
+        }
+        unpack(key ^ data, data)
+    }
+
+    fn load_bucket(&self, bucket: usize) -> [TTEntry; BUCKET_SIZE] {
+        std::array::from_fn(|slot| self.load(bucket * BUCKET_SIZE + slot))
+    }
+
+    fn write(&self, index: usize, entry: &TTEntry) {
+        let data = pack(entry);
+        self.words[index * 2].store(entry.hash ^ data, Ordering::Relaxed);
+        self.words[index * 2 + 1].store(data, Ordering::Relaxed);
+    }
+
+    fn probe(&self, bucket: usize, hash: u64, generation: u8) -> Option<TTEntry> {
+        let slot = (0..BUCKET_SIZE).find(|&slot| {
+            let entry = self.load(bucket * BUCKET_SIZE + slot);
+            entry.hash == hash && entry.hash != 0
+        })?;
+        let index = bucket * BUCKET_SIZE + slot;
+        let mut entry = self.load(index);
+        if entry.hash != hash {
+            return None; // Replaced in between
+        }
+        if entry.generation != generation {
+            entry.generation = generation;
+            self.write(index, &entry);
+        }
+        Some(entry)
+    }
+}
+
+/// Data word of an entry: move (16 bits, 0 = none), score (16), depth (8),
+/// generation (8), flag (2) and a set bit so no stored entry packs to zero
+fn pack(entry: &TTEntry) -> u64 {
+    let flag = match entry.flag {
+        TTFlag::Exact => 0u64,
+        TTFlag::LowerBound => 1,
+        TTFlag::UpperBound => 2,
+    };
+    entry.best_move.map_or(0, |m| m.0 as u64)
+        | (entry.score as i16 as u16 as u64) << 16
+        | (entry.depth as u64) << 32
+        | (entry.generation as u64) << 40
+        | flag << 48
+        | 1 << 50
+}
+
+fn unpack(hash: u64, data: u64) -> TTEntry {
+    let mv = data as u16;
+    TTEntry {
+        hash,
+        best_move: (mv != 0).then_some(Move(mv)),
+        score: (data >> 16) as u16 as i16 as Score,
+        depth: (data >> 32) as u8,
+        generation: (data >> 40) as u8,
+        flag: match (data >> 48) & 3 {
+            0 => TTFlag::Exact,
+            1 => TTFlag::LowerBound,
+            _ => TTFlag::UpperBound,
+        },
+    }
+}
+
 // =============================================================================
 // SCORE ADJUSTMENT FOR MATE SCORES
 // =============================================================================
@@ -230,18 +491,107 @@ mod tests {
     #[test]
     fn test_tt_depth_preferred_keeps_deeper() {
         let mut tt = TranspositionTable::new(10);
-        // Two different hashes that map to the same slot
-        let capacity = 1024usize;
+        // Two different hashes that map to the same bucket
+        let buckets = 1024 / BUCKET_SIZE;
         let hash1 = 42u64;
-        let hash2 = hash1 + capacity as u64; // Same slot, different hash
+        let hash2 = hash1 + buckets as u64; // Same bucket, different hash
 
         tt.store(hash1, 8, 200, TTFlag::Exact, None);
-        tt.store(hash2, 3, 50, TTFlag::Exact, None); // Lower depth — should NOT replace
+        tt.store(hash2, 3, 50, TTFlag::Exact, None); // Lower depth — should NOT evict
 
         let entry = tt.probe(hash1).unwrap();
         assert_eq!(entry.depth, 8); // Original kept
     }
 
+    /// Hashes that all land in the same bucket of a 2^10-entry table.
+    fn colliding_hashes(n: usize) -> Vec<u64> {
+        let buckets = (1024 / BUCKET_SIZE) as u64;
+        (0..n as u64).map(|i| 42 + i * buckets).collect()
+    }
+
+    #[test]
+    fn test_tt_bucket_holds_colliding_entries() {
+        let mut tt = TranspositionTable::new(10);
+        let hashes = colliding_hashes(BUCKET_SIZE);
+        for (i, &h) in hashes.iter().enumerate() {
+            tt.store(h, i as u8 + 1, 10 * i as Score, TTFlag::Exact, None);
+        }
+        for (i, &h) in hashes.iter().enumerate() {
+            let entry = tt.probe(h).expect("all colliding entries should fit in one bucket");
+            assert_eq!(entry.depth, i as u8 + 1);
+        }
+        assert_eq!(tt.collisions, 0);
+    }
+
+    #[test]
+    fn test_tt_full_bucket_evicts_shallowest() {
+        let mut tt = TranspositionTable::new(10);
+        let hashes = colliding_hashes(BUCKET_SIZE + 1);
+        let depths = [6, 2, 9, 7];
+        for (&h, &d) in hashes.iter().zip(depths.iter()) {
+            tt.store(h, d, 0, TTFlag::Exact, None);
+        }
+        tt.store(hashes[BUCKET_SIZE], 5, 0, TTFlag::Exact, None);
+
+        assert!(tt.probe(hashes[1]).is_none(), "depth-2 entry should be evicted");
+        assert!(tt.probe(hashes[BUCKET_SIZE]).is_some());
+        assert_eq!(tt.collisions, 1);
+    }
+
+    #[test]
+    fn test_tt_stale_entry_evicted_before_deeper_current() {
+        let mut tt = TranspositionTable::new(10);
+        let hashes = colliding_hashes(BUCKET_SIZE + 1);
+
+        tt.store(hashes[0], 10, 0, TTFlag::Exact, None);
+        tt.new_search();
+        for &h in &hashes[1..BUCKET_SIZE] {
+            tt.store(h, 5, 0, TTFlag::Exact, None);
+        }
+        tt.store(hashes[BUCKET_SIZE], 3, 0, TTFlag::Exact, None);
+
+        // 10 - 8 * 1 = 2 < 5: the stale deep entry is the cheapest to lose
+        assert!(tt.probe(hashes[0]).is_none());
+        let current = tt.generation();
+        let entry = tt.probe(hashes[BUCKET_SIZE]).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.generation, current);
+    }
+
+    #[test]
+    fn test_tt_probe_refreshes_generation() {
+        let mut tt = TranspositionTable::new(10);
+        let hashes = colliding_hashes(BUCKET_SIZE + 1);
+
+        tt.store(hashes[0], 10, 0, TTFlag::Exact, None);
+        tt.new_search();
+        assert!(tt.probe(hashes[0]).is_some()); // Hit marks the entry as current again
+        for &h in &hashes[1..=BUCKET_SIZE] {
+            tt.store(h, 5, 0, TTFlag::Exact, None);
+        }
+
+        assert_eq!(tt.probe(hashes[0]).unwrap().depth, 10); // Deeper current entry kept
+    }
+
+    #[test]
+    fn test_tt_generation_wraps() {
+        let mut tt = TranspositionTable::new(10);
+        for _ in 0..256 {
+            tt.new_search();
+        }
+        assert_eq!(tt.generation(), 0);
+    }
+
+    #[test]
+    fn test_tt_utilization() {
+        let mut tt = TranspositionTable::new(10);
+        assert_eq!(tt.utilization(), 0.0);
+        for &h in &colliding_hashes(BUCKET_SIZE) {
+            tt.store(h, 1, 0, TTFlag::Exact, None);
+        }
+        assert!((tt.utilization() - BUCKET_SIZE as f64 / 1024.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_tt_clear() {
         let mut tt = TranspositionTable::new(10);
@@ -296,6 +646,70 @@ mod tests {
         assert!((tt.hit_rate() - 50.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_shared_table_handles() {
+        let table = SharedTable::new(10);
+        let mut a = TranspositionTable::attach(Arc::clone(&table));
+        let mut b = TranspositionTable::attach(SharedTable::open(table.id()).unwrap());
+        let mv = Move::new(Square::from_file_rank(4, 1), Square::from_file_rank(4, 3));
+
+        a.store(12345, 7, -30005, TTFlag::UpperBound, Some(mv));
+        a.store(777, 3, 250, TTFlag::LowerBound, None);
+        let entry = b.probe(12345).unwrap();
+        assert_eq!((entry.depth, entry.score, entry.flag, entry.best_move), (7, -30005, TTFlag::UpperBound, Some(mv)));
+        assert_eq!(b.probe(777).unwrap().score, 250);
+        assert!(b.probe(778).is_none());
+        assert_eq!((a.stores, b.hits, b.misses), (2, 2, 1));
+        assert!((b.utilization() - 2.0 / 1024.0).abs() < 1e-9);
+
+        // Generations are advanced for the table, handles follow on new_search
+        table.new_search();
+        a.new_search();
+        assert_eq!(a.generation(), 1);
+        assert_eq!(b.generation(), 0);
+
+        b.clear();
+        assert!(a.probe(12345).is_none());
+        let id = table.id();
+        drop((a, b, table));
+        assert!(SharedTable::open(id).is_none());
+    }
+
+    #[test]
+    fn test_shared_torn_entry_is_a_miss() {
+        let table = SharedTable::new(10);
+        let mut tt = TranspositionTable::attach(Arc::clone(&table));
+        tt.store(42, 5, 100, TTFlag::Exact, None);
+        // Bucket 42 of 256: 42 % 256
+        let index = 42 * BUCKET_SIZE;
+        // Another thread's data word landed, its key word didn't
+        let other = pack(&TTEntry { hash: 42, depth: 9, ..TTEntry::default() });
+        table.words[index * 2 + 1].store(other, Ordering::Relaxed);
+        assert!(tt.probe(42).is_none());
+    }
+
+    #[test]
+    fn test_shared_table_across_threads() {
+        let table = SharedTable::new(12);
+        std::thread::scope(|scope| {
+            for t in 0..4u64 {
+                let table = Arc::clone(&table);
+                scope.spawn(move || {
+                    let mut tt = TranspositionTable::attach(table);
+                    for i in 0..200u64 {
+                        tt.store(1 + i * 4 + t, 4, (i as Score) - 100, TTFlag::Exact, None);
+                    }
+                });
+            }
+        });
+        let mut tt = TranspositionTable::attach(table);
+        for t in 0..4u64 {
+            for i in 0..200u64 {
+                assert_eq!(tt.probe(1 + i * 4 + t).unwrap().score, i as Score - 100);
+            }
+        }
+    }
+
     #[test]
     fn test_score_to_from_tt() {
         // Normal scores pass through unchanged
@@ -179,6 +179,8 @@ impl CastlingRights {
 // bits 6-11: to square (0-63)
 // bits 12-13: promotion piece (0=N, 1=B, 2=R, 3=Q)
 // bits 14-15: move flags (0=normal, 1=promotion, 2=en passant, 3=castling)
+// Promotion to a king (variant rule) is the castling flag with promotion bits
+// 3; real castling moves leave those bits clear.
 
 #[wasm_bindgen]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -201,6 +203,9 @@ impl Move {
     pub const FLAG_EN_PASSANT: u16 = 2;
     pub const FLAG_CASTLING: u16 = 3;
 
+    /// Flag and promotion bits of a promotion to king
+    const KING_PROMOTION: u16 = (Self::FLAG_CASTLING << Self::FLAG_SHIFT) | (3 << Self::PROMO_SHIFT);
+
     #[inline]
     pub fn new(from: Square, to: Square) -> Self {
         Move((from.0 as u16) | ((to.0 as u16) << Self::TO_SHIFT))
@@ -208,6 +213,9 @@ impl Move {
 
     #[inline]
     pub fn new_promotion(from: Square, to: Square, promo: PieceType) -> Self {
+        if promo == PieceType::King {
+            return Move((from.0 as u16) | ((to.0 as u16) << Self::TO_SHIFT) | Self::KING_PROMOTION);
+        }
         let promo_bits = match promo {
             PieceType::Knight => 0,
             PieceType::Bishop => 1,
@@ -253,13 +261,15 @@ impl Move {
 
     #[inline]
     pub fn promotion_piece(self) -> Option<PieceType> {
-        if self.is_promotion() {
+        if self.flags() == Self::FLAG_PROMOTION {
             Some(match (self.0 & Self::PROMO_MASK) >> Self::PROMO_SHIFT {
                 0 => PieceType::Knight,
                 1 => PieceType::Bishop,
                 2 => PieceType::Rook,
                 _ => PieceType::Queen,
             })
+        } else if self.is_king_promotion() {
+            Some(PieceType::King)
         } else {
             None
         }
@@ -272,7 +282,12 @@ impl Move {
 
     #[inline]
     pub fn is_promotion(self) -> bool {
-        self.flags() == Self::FLAG_PROMOTION
+        self.flags() == Self::FLAG_PROMOTION || self.is_king_promotion()
+    }
+
+    #[inline]
+    fn is_king_promotion(self) -> bool {
+        self.0 & (Self::FLAG_MASK | Self::PROMO_MASK) == Self::KING_PROMOTION
     }
 
     #[inline]
@@ -282,7 +297,7 @@ impl Move {
 
     #[inline]
     pub fn is_castling(self) -> bool {
-        self.flags() == Self::FLAG_CASTLING
+        self.flags() == Self::FLAG_CASTLING && !self.is_king_promotion()
     }
 
     /// Convert to UCI notation (e.g., "e2e4", "e7e8q")
@@ -295,6 +310,7 @@ impl Move {
                 PieceType::Bishop => 'b',
                 PieceType::Rook => 'r',
                 PieceType::Queen => 'q',
+                PieceType::King => 'k',
                 _ => 'q',
             };
             format!("{}{}{}", from, to, promo_char)
@@ -0,0 +1,303 @@
+// Chess Variants
+// Rule differences from standard chess live behind one enum that move
+// generation, make_move, game status and evaluation consult, so a new rule
+// set doesn't need its own Position.
+
+use crate::attacks::passed_pawn_mask;
+use crate::bitboard::Bitboard;
+use crate::eval::{default_params, Score};
+use crate::position::Position;
+use crate::types::{Color, PieceType};
+
+/// Rule set a game is played under. Position carries it, so search and move
+/// generation follow the rules without extra parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Variant {
+    /// FIDE rules
+    #[default]
+    Standard,
+    /// Pawns may promote on reaching the 6th or 7th rank, and must on the 8th
+    EarlyPromotion,
+    /// Pawns may only promote to a piece type their side has had captured
+    /// (and not brought back yet), and stay put on the 7th rank while there is none
+    CaptureLimited,
+    /// Giving a third check wins
+    ThreeCheck,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] =
+        [Variant::Standard, Variant::EarlyPromotion, Variant::CaptureLimited, Variant::ThreeCheck];
+
+    /// Lowercase name, as used by the WASM API
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::EarlyPromotion => "early-promotion",
+            Variant::CaptureLimited => "capture-limited",
+            Variant::ThreeCheck => "three-check",
+        }
+    }
+
+    /// Parse a name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Variant> {
+        Self::ALL.into_iter().find(|v| v.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Whether Position keeps count of the checks each side has given
+    pub fn counts_checks(&self) -> bool {
+        *self == Variant::ThreeCheck
+    }
+
+    /// Pieces a pawn of `us` may promote to
+    pub fn promotion_pieces(&self, pos: &Position, us: Color) -> PromotionRules {
+        match self {
+            Variant::Standard | Variant::EarlyPromotion | Variant::ThreeCheck => pos.promotion_rules(),
+            Variant::CaptureLimited => {
+                let mut rules = pos.promotion_rules();
+                for piece in PROMOTION_ORDER {
+                    if pos.lost_pieces(us, piece) == 0 {
+                        rules = rules.without(piece);
+                    }
+                }
+                rules
+            }
+        }
+    }
+
+    /// Squares on which a pawn of `color` may promote
+    pub fn promotion_squares(&self, color: Color) -> Bitboard {
+        match (self, color) {
+            (Variant::Standard | Variant::CaptureLimited | Variant::ThreeCheck, _) => self.forced_promotion_squares(color),
+            (Variant::EarlyPromotion, Color::White) => Bitboard::RANK_6 | Bitboard::RANK_7 | Bitboard::RANK_8,
+            (Variant::EarlyPromotion, Color::Black) => Bitboard::RANK_3 | Bitboard::RANK_2 | Bitboard::RANK_1,
+        }
+    }
+
+    /// Squares on which a pawn of `color` must promote; elsewhere in
+    /// `promotion_squares` it may also stay a pawn
+    pub fn forced_promotion_squares(&self, color: Color) -> Bitboard {
+        if color == Color::White { Bitboard::RANK_8 } else { Bitboard::RANK_1 }
+    }
+
+    /// Game won under this variant's own rules, checked before checkmate and
+    /// draws: the winner and a status name for it
+    pub fn outcome(&self, pos: &Position) -> Option<(Color, &'static str)> {
+        match self {
+            Variant::Standard | Variant::EarlyPromotion | Variant::CaptureLimited => None,
+            Variant::ThreeCheck => [Color::White, Color::Black]
+                .into_iter()
+                .find(|&c| pos.checks_given(c) >= 3)
+                .map(|c| (c, "three_check")),
+        }
+    }
+
+    /// Evaluation term for rules that standard weights don't see, from
+    /// white's point of view
+    pub fn eval_bonus(&self, pos: &Position) -> Score {
+        match self {
+            Variant::Standard | Variant::CaptureLimited => 0,
+            Variant::EarlyPromotion => early_passer_bonus(pos, Color::White) - early_passer_bonus(pos, Color::Black),
+            Variant::ThreeCheck => {
+                CHECKS_GIVEN_BONUS[pos.checks_given(Color::White).min(2) as usize]
+                    - CHECKS_GIVEN_BONUS[pos.checks_given(Color::Black).min(2) as usize]
+            }
+        }
+    }
+}
+
+/// Three-check: worth of having given 0, 1 or 2 checks. The third ends the
+/// game, so each check is worth more than the last.
+const CHECKS_GIVEN_BONUS: [Score; 3] = [0, 90, 300];
+
+/// Passed pawns are two ranks closer to promoting than the standard passed
+/// pawn table assumes: score each as if it stood two ranks further up
+fn early_passer_bonus(pos: &Position, color: Color) -> Score {
+    let table = &default_params().pawns.passed_eg;
+    let their_pawns = pos.pieces(color.flip(), PieceType::Pawn);
+    pos.pieces(color, PieceType::Pawn)
+        .filter(|&sq| (passed_pawn_mask(sq, color == Color::White) & their_pawns).is_empty())
+        .map(|sq| {
+            let rank = if color == Color::White { sq.rank() } else { 7 - sq.rank() } as usize;
+            table[(rank + 2).min(6)] - table[rank]
+        })
+        .sum()
+}
+
+// =============================================================================
+// PROMOTION RULES
+// =============================================================================
+
+/// Piece types pawns may promote to in a game, set per game on top of the
+/// variant (e.g. knights only, no queens, or kings allowed)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PromotionRules(u8);
+
+/// Promotion choices in move-generation order, most valuable first
+const PROMOTION_ORDER: [PieceType; 5] =
+    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::King];
+
+impl PromotionRules {
+    /// Queen, rook, bishop or knight
+    pub const STANDARD: PromotionRules = PromotionRules(
+        1 << PieceType::Queen as u8 | 1 << PieceType::Rook as u8 | 1 << PieceType::Bishop as u8 | 1 << PieceType::Knight as u8,
+    );
+
+    /// Rules allowing exactly `pieces`. None if the list is empty or has a pawn.
+    pub fn from_pieces(pieces: &[PieceType]) -> Option<PromotionRules> {
+        if pieces.is_empty() || pieces.contains(&PieceType::Pawn) {
+            return None;
+        }
+        Some(PromotionRules(pieces.iter().fold(0, |mask, &p| mask | 1 << p as u8)))
+    }
+
+    /// Parse piece letters such as "qrbn", "N" or "rbnk" (case-insensitive)
+    pub fn parse(letters: &str) -> Option<PromotionRules> {
+        let pieces: Option<Vec<PieceType>> = letters
+            .trim()
+            .chars()
+            .map(|c| match c.to_ascii_lowercase() {
+                'q' => Some(PieceType::Queen),
+                'r' => Some(PieceType::Rook),
+                'b' => Some(PieceType::Bishop),
+                'n' => Some(PieceType::Knight),
+                'k' => Some(PieceType::King),
+                _ => None,
+            })
+            .collect();
+        Self::from_pieces(&pieces?)
+    }
+
+    /// Lowercase letters of the allowed pieces, most valuable first ("qrbn")
+    pub fn letters(&self) -> String {
+        self.iter()
+            .map(|p| match p {
+                PieceType::Queen => 'q',
+                PieceType::Rook => 'r',
+                PieceType::Bishop => 'b',
+                PieceType::Knight => 'n',
+                _ => 'k',
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn allows(&self, piece: PieceType) -> bool {
+        self.0 & (1 << piece as u8) != 0
+    }
+
+    /// The same rules with `piece` no longer allowed (may leave none)
+    pub fn without(&self, piece: PieceType) -> PromotionRules {
+        PromotionRules(self.0 & !(1 << piece as u8))
+    }
+
+    /// Allowed pieces, most valuable first
+    pub fn iter(&self) -> impl Iterator<Item = PieceType> {
+        let rules = *self;
+        PROMOTION_ORDER.into_iter().filter(move |&p| rules.allows(p))
+    }
+}
+
+impl Default for PromotionRules {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Square;
+
+    #[test]
+    fn test_names_roundtrip() {
+        for v in Variant::ALL {
+            assert_eq!(Variant::from_name(v.name()), Some(v));
+        }
+        assert_eq!(Variant::from_name(" Standard "), Some(Variant::Standard));
+        assert_eq!(Variant::from_name("crazyhouse"), None);
+    }
+
+    #[test]
+    fn test_standard_rules() {
+        let pos = Position::starting_position();
+        assert_eq!(pos.variant(), Variant::Standard);
+        assert_eq!(Variant::Standard.promotion_pieces(&pos, Color::White), PromotionRules::STANDARD);
+        assert_eq!(Variant::Standard.promotion_squares(Color::White), Bitboard::RANK_8);
+        assert_eq!(Variant::Standard.promotion_squares(Color::Black), Bitboard::RANK_1);
+        assert_eq!(Variant::Standard.outcome(&pos), None);
+        assert_eq!(Variant::Standard.eval_bonus(&pos), 0);
+    }
+
+    #[test]
+    fn test_promotion_rules() {
+        assert_eq!(PromotionRules::STANDARD.letters(), "qrbn");
+        assert_eq!(PromotionRules::parse("NQ"), PromotionRules::from_pieces(&[PieceType::Queen, PieceType::Knight]));
+        let knights = PromotionRules::parse("n").unwrap();
+        assert!(knights.allows(PieceType::Knight) && !knights.allows(PieceType::Queen));
+        assert_eq!(PromotionRules::parse("rbnk").unwrap().iter().last(), Some(PieceType::King));
+        assert_eq!(PromotionRules::parse(""), None);
+        assert_eq!(PromotionRules::parse("qp"), None);
+        assert_eq!(PromotionRules::parse("x"), None);
+    }
+
+    #[test]
+    fn test_early_promotion_rules() {
+        let v = Variant::EarlyPromotion;
+        assert_eq!(Variant::from_name("Early-Promotion"), Some(v));
+        assert!(v.promotion_squares(Color::White).has(Square::from_algebraic("c6").unwrap()));
+        assert!(!v.promotion_squares(Color::White).has(Square::from_algebraic("c5").unwrap()));
+        assert!(v.promotion_squares(Color::Black).has(Square::from_algebraic("c3").unwrap()));
+        assert_eq!(v.forced_promotion_squares(Color::Black), Bitboard::RANK_1);
+    }
+
+    #[test]
+    fn test_early_promotion_values_passers() {
+        // White's d5 passer is two steps from a promotion square
+        let mut pos = Position::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        pos.set_variant(Variant::EarlyPromotion);
+        assert!(Variant::EarlyPromotion.eval_bonus(&pos) > 0);
+        assert_eq!(Variant::EarlyPromotion.eval_bonus(&pos.mirrored()), -Variant::EarlyPromotion.eval_bonus(&pos));
+        // Blocked by an enemy pawn: nothing extra
+        let pos = Position::from_fen("4k3/8/3p4/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(Variant::EarlyPromotion.eval_bonus(&pos), 0);
+    }
+
+    #[test]
+    fn test_capture_limited_promotions() {
+        // Nothing captured yet: a piece missing from the setup is no pool
+        let mut pos = Position::from_fen("1r2k3/P7/8/8/8/8/rPPPPPPP/RN1QKB1R b K - 0 1").unwrap();
+        pos.set_variant(Variant::CaptureLimited);
+        assert_eq!(Variant::CaptureLimited.promotion_pieces(&pos, Color::White).letters(), "");
+        assert_eq!(Variant::CaptureLimited.promotion_pieces(&pos, Color::Black).letters(), "");
+        // White loses a rook and a knight
+        for uci in ["a2a1", "h1g1", "a1b1"] {
+            let m = crate::movegen::generate_legal_moves(&pos).iter().find(|m| m.to_uci() == uci).copied().unwrap();
+            pos.make_move(m).unwrap();
+        }
+        assert_eq!(Variant::CaptureLimited.promotion_pieces(&pos, Color::White).letters(), "rn");
+        // Still filtered by the game's promotion rules
+        pos.set_promotion_rules(PromotionRules::parse("qn").unwrap());
+        assert_eq!(Variant::CaptureLimited.promotion_pieces(&pos, Color::White).letters(), "n");
+    }
+
+    #[test]
+    fn test_three_check() {
+        let v = Variant::ThreeCheck;
+        assert!(v.counts_checks() && !Variant::Standard.counts_checks());
+        let mut pos = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0").unwrap();
+        assert_eq!(pos.variant(), v);
+        assert_eq!(v.outcome(&pos), None);
+        assert!(v.eval_bonus(&pos) > 0);
+        assert_eq!(v.eval_bonus(&pos.mirrored()), -v.eval_bonus(&pos));
+        // Third check
+        let m = crate::movegen::generate_legal_moves(&pos).iter().find(|m| m.to_uci() == "a1a8").copied().unwrap();
+        pos.make_move(m).unwrap();
+        assert_eq!(pos.checks_given(Color::White), 3);
+        assert_eq!(v.outcome(&pos), Some((Color::White, "three_check")));
+    }
+}
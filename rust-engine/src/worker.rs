@@ -0,0 +1,368 @@
+// Web Worker Protocol
+// Message-based engine for a Web Worker, in the spirit of stockfish.js. The
+// page posts command objects and receives event objects. With the
+// `wasm-pack build --target web` output, the worker is a module worker
+// (`new Worker('engine-worker.js', {type: "module"})`) that only loads it:
+//
+//   import init, { start_worker } from './chess_engine.js';
+//   init().then(() => start_worker());
+//
+// To search with the page's SharedTTs (threads build, see lib.rs), the worker
+// must run over the page's memory instead: the page posts `worker_init()`
+// first, and the worker waits for it before loading.
+//
+//   self.onmessage = ({ data }) => {
+//     init({ module_or_path: data.module, memory: data.memory }).then(() => start_worker());
+//   };
+//
+// Commands: {type:"position",fen?,moves?}, {type:"go",depth?,movetime?},
+// {type:"stop"}, {type:"newgame"}, {type:"isready"}, {type:"setoption",name,value},
+// {type:"sharedtt",id?}
+// Events: {type:"info",...}, {type:"bestmove",...}, {type:"readyok"}, {type:"error",message}
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::position::Position;
+use crate::search::SteppedSearch;
+use crate::GameState;
+
+/// Nodes searched between checks for new messages
+const STEP_NODES: u64 = 20_000;
+
+/// A message from the page
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Command {
+    /// Set up a position: a FEN (the starting position if absent) and UCI moves played from it
+    Position {
+        fen: Option<String>,
+        #[serde(default)]
+        moves: Vec<String>,
+    },
+    /// Search to `depth` or for `movetime` ms; with neither, until "stop"
+    Go { depth: Option<u8>, movetime: Option<f64> },
+    /// End the search, reporting the best move found so far
+    Stop,
+    /// Forget the previous game (TT included) and go back to the starting position
+    NewGame,
+    /// Answered with "readyok"
+    IsReady,
+    /// "variant", "personality" or "promotionRules", as the GameState setters take them
+    SetOption { name: String, value: String },
+    /// Search with the `SharedTT` of this id, or with the worker's own table when absent
+    SharedTT { id: Option<u32> },
+}
+
+/// A message to the page
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event {
+    /// A search depth was completed
+    #[serde(rename_all = "camelCase")]
+    Info { depth: u8, score: i32, nodes: u64, time_ms: f64, best_move: Option<String> },
+    /// The search is over; `move` is null when there is no legal move
+    #[serde(rename_all = "camelCase")]
+    BestMove {
+        #[serde(rename = "move")]
+        mv: Option<String>,
+        score: i32,
+        depth: u8,
+        nodes: u64,
+        time_ms: f64,
+    },
+    ReadyOk,
+    Error { message: String },
+}
+
+/// The engine behind the protocol, independent of the worker's message plumbing
+pub struct WorkerEngine {
+    game: GameState,
+    search: Option<SteppedSearch>,
+    /// Depth of the last "info" sent for the running search
+    reported_depth: u8,
+}
+
+impl WorkerEngine {
+    pub fn new() -> Self {
+        WorkerEngine { game: GameState::new(), search: None, reported_depth: 0 }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Handle one command, returning the events to send. Any command but
+    /// "isready" first ends a running search, with its "bestmove".
+    pub fn handle(&mut self, command: Command) -> Vec<Event> {
+        if command == Command::IsReady {
+            return vec![Event::ReadyOk];
+        }
+        let mut events = self.finish_search();
+        match command {
+            Command::Position { fen, moves } => {
+                let start = match fen {
+                    Some(fen) => Position::from_fen_strict(&fen).map_err(|e| e.to_string()),
+                    None => Ok(Position::starting_position()),
+                };
+                match start {
+                    Ok(pos) => {
+                        self.game.set_start(pos);
+                        if let Some(bad) = moves.iter().find(|uci| !(self.game.is_legal_uci(uci) && self.game.make_move_uci(uci))) {
+                            events.push(error(&format!("Illegal move: {}", bad)));
+                        }
+                    }
+                    Err(e) => events.push(error(&format!("Invalid FEN: {}", e))),
+                }
+            }
+            Command::Go { depth, movetime } => {
+                let options = self.game.personality.search_options();
+                let max_depth = depth.unwrap_or(0);
+                let max_ms = movetime.unwrap_or(0.0);
+                let tt = self.game.search_tt();
+                self.search = Some(SteppedSearch::with_tt(&self.game.position, max_depth, max_ms, options, tt));
+                self.reported_depth = 0;
+            }
+            Command::Stop | Command::IsReady => {}
+            Command::NewGame => self.game.reset(),
+            Command::SharedTT { id: None } => self.game.use_own_tt(),
+            Command::SharedTT { id: Some(id) } => match crate::SharedTT::open(id) {
+                Some(shared) => self.game.use_shared_tt(&shared),
+                None => events.push(error(&format!(
+                    "Unknown shared table: {} (was the worker started over the page's memory?)",
+                    id
+                ))),
+            },
+            Command::SetOption { name, value } => {
+                let ok = match name.as_str() {
+                    "variant" => self.game.set_variant(&value),
+                    "personality" => self.game.set_personality(&value),
+                    "promotionRules" => self.game.set_promotion_rules(&value),
+                    _ => {
+                        events.push(error(&format!("Unknown option: {}", name)));
+                        return events;
+                    }
+                };
+                if !ok {
+                    events.push(error(&format!("Invalid value for {}: {}", name, value)));
+                }
+            }
+        }
+        events
+    }
+
+    /// Search one slice: an "info" for each depth completed, and "bestmove"
+    /// once the search is over
+    pub fn step(&mut self) -> Vec<Event> {
+        let Some(search) = &mut self.search else { return Vec::new() };
+        let done = search.step(STEP_NODES);
+        let mut events = Vec::new();
+        let (best_move, score, stats) = search.result();
+        if stats.depth > self.reported_depth {
+            self.reported_depth = stats.depth;
+            events.push(Event::Info {
+                depth: stats.depth,
+                score,
+                nodes: stats.nodes,
+                time_ms: stats.time_ms,
+                best_move: best_move.map(|m| m.to_uci()),
+            });
+        }
+        if done {
+            events.extend(self.finish_search());
+        }
+        events
+    }
+
+    /// Stop the running search, if any, and report its result
+    fn finish_search(&mut self) -> Vec<Event> {
+        let Some(mut search) = self.search.take() else { return Vec::new() };
+        search.stop();
+        while !search.step(STEP_NODES) {}
+        let (best_move, score, stats) = search.result();
+        vec![Event::BestMove {
+            mv: best_move.map(|m| m.to_uci()),
+            score,
+            depth: stats.depth,
+            nodes: stats.nodes,
+            time_ms: stats.time_ms,
+        }]
+    }
+}
+
+impl Default for WorkerEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn error(message: &str) -> Event {
+    Event::Error { message: message.to_string() }
+}
+
+// =============================================================================
+// WORKER GLUE
+// =============================================================================
+
+/// Run the engine in this Web Worker: handle messages posted to it and post
+/// events back. Searches yield between slices so "stop" is heard.
+#[wasm_bindgen]
+pub fn start_worker() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let engine = Rc::new(RefCell::new(WorkerEngine::new()));
+    let running = Rc::new(std::cell::Cell::new(false));
+
+    let handler = Closure::<dyn FnMut(JsValue)>::new(move |message: JsValue| {
+        let data = js_sys::Reflect::get(&message, &JsValue::from_str("data")).unwrap_or(JsValue::UNDEFINED);
+        let events = match serde_wasm_bindgen::from_value::<Command>(data) {
+            Ok(command) => engine.borrow_mut().handle(command),
+            Err(e) => vec![error(&format!("Bad command: {}", e))],
+        };
+        post_events(&events);
+
+        if engine.borrow().is_searching() && !running.get() {
+            running.set(true);
+            let engine = Rc::clone(&engine);
+            let running = Rc::clone(&running);
+            wasm_bindgen_futures::spawn_local(async move {
+                while engine.borrow().is_searching() {
+                    let events = engine.borrow_mut().step();
+                    post_events(&events);
+                    if crate::yield_to_event_loop().await.is_err() {
+                        break;
+                    }
+                }
+                running.set(false);
+            });
+        }
+    });
+    let _ = js_sys::Reflect::set(&js_sys::global(), &JsValue::from_str("onmessage"), handler.as_ref());
+    handler.forget();
+}
+
+fn post_events(events: &[Event]) {
+    let Some(post) = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("postMessage"))
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+    for event in events {
+        let _ = post.call1(&JsValue::NULL, &crate::to_js(event));
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(json: &str) -> Command {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            command(r#"{"type":"position","moves":["e2e4"]}"#),
+            Command::Position { fen: None, moves: vec!["e2e4".to_string()] }
+        );
+        assert_eq!(command(r#"{"type":"go","depth":3}"#), Command::Go { depth: Some(3), movetime: None });
+        assert_eq!(command(r#"{"type":"isready"}"#), Command::IsReady);
+        assert!(serde_json::from_str::<Command>(r#"{"type":"quit"}"#).is_err());
+    }
+
+    #[test]
+    fn test_search_to_depth() {
+        let mut engine = WorkerEngine::new();
+        assert!(engine.handle(command(r#"{"type":"position","moves":["e2e4","e7e5"]}"#)).is_empty());
+        assert_eq!(engine.game.position.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        assert!(engine.handle(command(r#"{"type":"go","depth":3}"#)).is_empty());
+        assert!(engine.is_searching());
+
+        let mut events = Vec::new();
+        while engine.is_searching() {
+            events.extend(engine.step());
+        }
+        let depths: Vec<u8> =
+            events.iter().filter_map(|e| if let Event::Info { depth, .. } = e { Some(*depth) } else { None }).collect();
+        assert_eq!(depths, [1, 2, 3]);
+        match events.last().unwrap() {
+            Event::BestMove { mv, depth, .. } => {
+                assert!(mv.is_some());
+                assert_eq!(*depth, 3);
+            }
+            other => panic!("expected bestmove, got {:?}", other),
+        }
+        let json = serde_json::to_string(events.last().unwrap()).unwrap();
+        assert!(json.starts_with(r#"{"type":"bestmove","move":""#), "{}", json);
+    }
+
+    #[test]
+    fn test_stop_and_errors() {
+        let mut engine = WorkerEngine::new();
+        engine.handle(command(r#"{"type":"go"}"#));
+        engine.step();
+        assert_eq!(engine.handle(command(r#"{"type":"isready"}"#)), [Event::ReadyOk]);
+        assert!(engine.is_searching());
+        let events = engine.handle(command(r#"{"type":"stop"}"#));
+        assert!(matches!(events[..], [Event::BestMove { mv: Some(_), .. }]));
+        assert!(!engine.is_searching());
+
+        let events = engine.handle(command(r#"{"type":"position","fen":"8/8/8/8/8/8/8/8 w - - 0 1"}"#));
+        assert!(matches!(&events[..], [Event::Error { message }] if message.starts_with("Invalid FEN: ")));
+        let events = engine.handle(command(r#"{"type":"position","moves":["e2e5"]}"#));
+        assert_eq!(events, [error("Illegal move: e2e5")]);
+        let events = engine.handle(command(r#"{"type":"setoption","name":"variant","value":"three-check"}"#));
+        assert!(events.is_empty());
+        assert_eq!(engine.game.variant(), "three-check");
+        let events = engine.handle(command(r#"{"type":"setoption","name":"hash","value":"64"}"#));
+        assert_eq!(events, [error("Unknown option: hash")]);
+    }
+
+    #[test]
+    fn test_shared_tt() {
+        let shared = crate::SharedTT::new(12);
+        let mut engine = WorkerEngine::new();
+        let json = format!(r#"{{"type":"sharedtt","id":{}}}"#, shared.id());
+        assert!(engine.handle(command(&json)).is_empty());
+        assert_eq!(engine.game.shared_tt_id(), Some(shared.id()));
+
+        engine.handle(command(r#"{"type":"go","depth":3}"#));
+        while engine.is_searching() {
+            engine.step();
+        }
+        assert!(shared.utilization() > 0.0);
+
+        assert!(engine.handle(command(r#"{"type":"sharedtt"}"#)).is_empty());
+        assert_eq!(engine.game.shared_tt_id(), None);
+        let events = engine.handle(command(r#"{"type":"sharedtt","id":4000000}"#));
+        assert_eq!(events, [error("Unknown shared table: 4000000 (was the worker started over the page's memory?)")]);
+    }
+
+    #[test]
+    fn test_shared_tt_from_another_instance() {
+        // Instances over one memory are threads of one process: a worker
+        // elsewhere gets only the id, and finds the page's table by it
+        let shared = crate::SharedTT::new(12);
+        let id = shared.id();
+        let events = std::thread::spawn(move || {
+            let mut engine = WorkerEngine::new();
+            let mut events = engine.handle(command(&format!(r#"{{"type":"sharedtt","id":{}}}"#, id)));
+            events.extend(engine.handle(command(r#"{"type":"go","depth":3}"#)));
+            while engine.is_searching() {
+                engine.step();
+            }
+            events
+        })
+        .join()
+        .unwrap();
+        assert!(events.is_empty(), "{:?}", events);
+        assert!(shared.utilization() > 0.0);
+    }
+}
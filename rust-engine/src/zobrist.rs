@@ -59,6 +59,9 @@ const CASTLING_KEYS: [u64; 4] = generate_keys(xorshift64(SEED ^ 0x1111_2222_3333
 // 8 keys for en passant file (only the file matters, not the rank)
 const EP_KEYS: [u64; 8] = generate_keys(xorshift64(SEED ^ 0x5555_6666_7777_8888));
 
+// 3 keys per color for checks given (three-check); no checks hashes as 0
+const CHECK_KEYS: [u64; 6] = generate_keys(xorshift64(SEED ^ 0x9999_AAAA_BBBB_CCCC));
+
 // =============================================================================
 // PUBLIC API
 // =============================================================================
@@ -102,6 +105,16 @@ pub fn en_passant_key(file: u8) -> u64 {
     EP_KEYS[file as usize]
 }
 
+/// Get the Zobrist key for `color` having given `count` checks (three-check).
+/// Zero for no checks, so other variants hash as before.
+#[inline]
+pub fn checks_key(color: Color, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        n => CHECK_KEYS[color as usize * 3 + (n.min(3) as usize - 1)],
+    }
+}
+
 /// Compute the full Zobrist hash for a position from scratch.
 /// Used for initialization and verification.
 pub fn compute_hash(
@@ -133,6 +146,14 @@ pub fn compute_hash(
     hash
 }
 
+/// Compute the pawn-only Zobrist hash from scratch.
+/// Uses the same piece keys as the full hash, restricted to pawns.
+pub fn compute_pawn_hash(pieces_iter: impl Iterator<Item = (Color, PieceType, Square)>) -> u64 {
+    pieces_iter
+        .filter(|&(_, piece, _)| piece == PieceType::Pawn)
+        .fold(0u64, |hash, (color, piece, sq)| hash ^ piece_key(color, piece, sq))
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -19,13 +19,15 @@ interface WasmGameState {
   free(): void;
   fen(): string;
   hash(): bigint;
+  hash_hex(): string;          // 16 hex digits, safe as a map key
   turn(): string;              // "w" | "b"
   make_move_uci(uci: string): boolean;
+  try_move_uci(uci: string): void;  // throws { kind, message, field }
   undo(): string;              // Returns UCI of undone move, or ""
   reset(): void;
   load_fen(fen: string): boolean;
-  history(): string;           // JSON array of UCI strings
-  get_board_json(): string;    // JSON 8x8 array
+  history(): string[];         // UCI strings
+  get_board_json(): (null | { type: string; color: string })[][];  // 8x8, row 0 = rank 8
   piece_at(file: number, rank: number): string;  // "wP", "bN", "" etc.
   is_in_check(): boolean;
   is_checkmate(): boolean;
@@ -156,7 +158,7 @@ export class RustGameState {
   // Instance-owned WASM GameState — each RustGameState gets its own
   private gs: WasmGameState | null = null;
 
-  // Board cache — avoids re-parsing JSON every frame (instance-scoped)
+  // Board cache — avoids rebuilding the board every frame (instance-scoped)
   private boardCache: (Piece | null)[][] | null = null;
   private boardDirty = true;
 
@@ -281,8 +283,7 @@ export class RustGameState {
     if (!this.gs) return emptyBoard();
 
     try {
-      const json = this.gs.get_board_json();
-      const raw: (null | { type: string; color: string })[][] = JSON.parse(json);
+      const raw = this.gs.get_board_json();
 
       const board: (Piece | null)[][] = [];
       for (let row = 0; row < 8; row++) {
@@ -304,7 +305,7 @@ export class RustGameState {
       this.boardDirty = false;
       return board;
     } catch (e) {
-      console.error('[RustGameState] getBoard parse error:', e);
+      console.error('[RustGameState] getBoard error:', e);
       return emptyBoard();
     }
   }
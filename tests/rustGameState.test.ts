@@ -28,7 +28,7 @@ class MockGameState {
   private _draw = false;
   private _gameOver = false;
 
-  // Board state: a simple 8x8 array matching our JSON format
+  // Board state: a simple 8x8 array matching get_board_json
   private _board: (null | { type: string; color: string })[][] = MockGameState.startingBoard();
 
   static startingBoard(): (null | { type: string; color: string })[][] {
@@ -47,6 +47,7 @@ class MockGameState {
 
   fen(): string { return this._fen; }
   hash(): bigint { return BigInt(12345); }
+  hash_hex(): string { return '0000000000003039'; }
   turn(): string { return this._history.length % 2 === 0 ? 'w' : 'b'; }
 
   make_move_uci(uci: string): boolean {
@@ -68,6 +69,12 @@ class MockGameState {
     return true;
   }
 
+  try_move_uci(uci: string): void {
+    if (!this.make_move_uci(uci)) {
+      throw { kind: 'IllegalMove', message: `Illegal move: ${uci}`, field: uci };
+    }
+  }
+
   undo(): string {
     return this._history.pop() || '';
   }
@@ -85,12 +92,12 @@ class MockGameState {
     return true;
   }
 
-  history(): string {
-    return JSON.stringify(this._history);
+  history(): string[] {
+    return [...this._history];
   }
 
-  get_board_json(): string {
-    return JSON.stringify(this._board);
+  get_board_json(): (null | { type: string; color: string })[][] {
+    return this._board.map(row => [...row]);
   }
 
   piece_at(file: number, rank: number): string {
@@ -211,9 +218,8 @@ describe('RustGameState Adapter', () => {
   // BOARD PARSING
   // ========================================================================
   describe('Board Parsing', () => {
-    it('should parse board JSON into Piece[][] format', () => {
-      const json = mock.get_board_json();
-      const raw: (null | { type: string; color: string })[][] = JSON.parse(json);
+    it('should read the board array in Piece[][] format', () => {
+      const raw = mock.get_board_json();
 
       // Row 0 should be black back rank  
       expect(raw[0][0]).toEqual({ type: 'R', color: 'b' });
@@ -226,8 +232,7 @@ describe('RustGameState Adapter', () => {
     });
 
     it('should convert WASM colors to our format', () => {
-      const json = mock.get_board_json();
-      const raw = JSON.parse(json);
+      const raw = mock.get_board_json();
 
       // Test conversion logic (mirrors getBoard in adapter)
       const cell = raw[0][0]; // Black rook
@@ -257,14 +262,14 @@ describe('RustGameState Adapter', () => {
     it('should track move history', () => {
       mock.make_move_uci('e2e4');
       mock.make_move_uci('e7e5');
-      expect(JSON.parse(mock.history())).toEqual(['e2e4', 'e7e5']);
+      expect(mock.history()).toEqual(['e2e4', 'e7e5']);
     });
 
     it('should undo moves', () => {
       mock.make_move_uci('e2e4');
       const undone = mock.undo();
       expect(undone).toBe('e2e4');
-      expect(JSON.parse(mock.history())).toEqual([]);
+      expect(mock.history()).toEqual([]);
     });
 
     it('should return empty string when undoing with no moves', () => {
@@ -294,7 +299,7 @@ describe('RustGameState Adapter', () => {
   // ========================================================================
   describe('Move Building', () => {
     it('should detect regular moves', () => {
-      const board = JSON.parse(mock.get_board_json());
+      const board = mock.get_board_json();
       const uci = 'g1f3';
       const parsed = fromUci(uci)!;
 
@@ -307,7 +312,7 @@ describe('RustGameState Adapter', () => {
       mock.make_move_uci('e2e4');  // move white pawn from row 6 to row 4
       mock.make_move_uci('d7d5');  // move black pawn from row 1 to row 3
 
-      const board = JSON.parse(mock.get_board_json());
+      const board = mock.get_board_json();
       // e4 is at row 4, col 4 — but our mock just moves pieces
       expect(board[4][4]).toEqual({ type: 'P', color: 'w' });
       expect(board[3][3]).toEqual({ type: 'P', color: 'b' });